@@ -0,0 +1,76 @@
+// Benchmark: a filtered 1M-row scan with row storage vs columnar storage.
+// Run with `cargo bench --bench columnar`.
+
+use std::time::Instant;
+
+use sreerag_devadasan::columnar::Aggregate;
+use sreerag_devadasan::engine::{Database, Storage};
+use sreerag_devadasan::eval::Value;
+use sreerag_devadasan::pratt::PrattParser;
+use sreerag_devadasan::tokenizer::tokenize;
+
+const ROWS: usize = 1_000_000;
+const PREDICATE: &str = "age > 30 AND (score * 2 < 150 OR name = 'user7') AND NOT (id = 5)";
+
+fn database(storage: Storage) -> Database {
+    let mut db = Database::with_storage(storage);
+    db.execute_sql("CREATE TABLE people (id INT, age INT, score INT, name VARCHAR(20))").unwrap();
+    // Batches keep the INSERT statements small
+    for batch in (0..ROWS as i64).collect::<Vec<_>>().chunks(10_000) {
+        let rows: Vec<String> =
+            batch.iter().map(|i| format!("({}, {}, {}, 'user{}')", i, i % 90, (i * 7) % 100, i % 1000)).collect();
+        db.execute_sql(&format!("INSERT INTO people VALUES {}", rows.join(", "))).unwrap();
+    }
+    db
+}
+
+fn main() {
+    let mut rows = database(Storage::Rows);
+    let mut columns = database(Storage::Columnar);
+
+    let start = Instant::now();
+    let query = format!("SELECT id FROM people WHERE {}", PREDICATE);
+    let row_hits = rows.execute_sql(&query).unwrap().rows.len();
+    let row_time = start.elapsed();
+
+    let start = Instant::now();
+    let column_hits = columns.execute_sql(&query).unwrap().rows.len();
+    let column_time = start.elapsed();
+
+    assert_eq!(row_hits, column_hits);
+    println!("rows: {}, matching: {}", ROWS, row_hits);
+    println!("row scan:        {:?}", row_time);
+    println!("columnar scan:   {:?}", column_time);
+    println!("speedup:         {:.2}x", row_time.as_secs_f64() / column_time.as_secs_f64());
+
+    // The filter alone, without assembling the result rows
+    let names: Vec<String> = ["id", "age", "score", "name"].iter().map(|c| c.to_string()).collect();
+    let tokens = tokenize(PREDICATE);
+    let expr = PrattParser::new(&tokens).parse_expression(1).expect("benchmark expression parses");
+    let start = Instant::now();
+    let filtered = columns.table("people").unwrap().columnar().unwrap().filter(&expr, &names).unwrap().count();
+    let filter_time = start.elapsed();
+    assert_eq!(filtered, row_hits);
+    println!("columnar filter: {:?}", filter_time);
+
+    let start = Instant::now();
+    let row_sum: i64 = rows
+        .table("people")
+        .unwrap()
+        .rows()
+        .iter()
+        .filter_map(|row| match row[2] {
+            Value::Int(n) => Some(n),
+            _ => None,
+        })
+        .sum();
+    let row_time = start.elapsed();
+
+    let start = Instant::now();
+    let column_sum = columns.table("people").unwrap().columnar().unwrap().aggregate(Aggregate::Sum, Some(2), None);
+    let column_time = start.elapsed();
+
+    assert_eq!(column_sum, Ok(Value::Int(row_sum)));
+    println!("row SUM:         {:?}", row_time);
+    println!("columnar SUM:    {:?}", column_time);
+}
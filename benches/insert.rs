@@ -0,0 +1,51 @@
+// Benchmark: one INSERT of 100,000 VALUES rows, as in a dump file, on the
+// batch path and row by row, into a table with a primary key and one
+// without. Both paths check keys the same way, so the table without one
+// shows what batching itself saves. Run with `cargo bench --bench insert`.
+
+use std::time::{Duration, Instant};
+
+use sreerag_devadasan::ast::Statement;
+use sreerag_devadasan::engine::{Database, Storage};
+use sreerag_devadasan::parser::parse_sql;
+
+const ROWS: usize = 100_000;
+
+fn insert() -> Statement {
+    let row = |i: usize| {
+        let (price, cents, day) = (i % 500, i % 100, i % 28 + 1);
+        format!("({}, 'item{}', {}.{:02}, DATE '2024-01-{:02}', {})", i, i, price, cents, day, i.is_multiple_of(2))
+    };
+    let rows: Vec<String> = (0..ROWS).map(row).collect();
+    parse_sql(&format!("INSERT INTO items VALUES {}", rows.join(", "))).unwrap()
+}
+
+fn run(statement: &Statement, storage: Storage, key: bool, batch: bool) -> Duration {
+    let mut db = Database::with_storage(storage);
+    db.set_batch_inserts(batch);
+    let id = if key { "id INT PRIMARY KEY" } else { "id INT" };
+    let create = format!(
+        "CREATE TABLE items ({}, name VARCHAR(20) NOT NULL, price DECIMAL(10,2), added DATE, shipped BOOL)",
+        id
+    );
+    db.execute_sql(&create).unwrap();
+    let start = Instant::now();
+    db.execute(statement).unwrap();
+    let elapsed = start.elapsed();
+    assert_eq!(db.table("items").unwrap().len(), ROWS);
+    elapsed
+}
+
+fn main() {
+    let start = Instant::now();
+    let statement = insert();
+    println!("rows: {}, parsed in {:?}", ROWS, start.elapsed());
+    for (storage, key) in [(Storage::Rows, true), (Storage::Columnar, true), (Storage::Rows, false)] {
+        let row_by_row = run(&statement, storage, key, false);
+        let batched = run(&statement, storage, key, true);
+        println!("{:?} storage, {}", storage, if key { "primary key" } else { "no key" });
+        println!("  row by row: {:?}", row_by_row);
+        println!("  batched:    {:?}", batched);
+        println!("  speedup:    {:.2}x", row_by_row.as_secs_f64() / batched.as_secs_f64());
+    }
+}
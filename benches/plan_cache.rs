@@ -0,0 +1,57 @@
+// Benchmark: a loop of queries differing only in their literals, with and
+// without the engine's plan cache. Run with `cargo bench --bench plan_cache`.
+//
+// The cache is not a speedup here. Each query is still parsed and
+// fingerprinted, and its literals bound, which costs about as much as the
+// compiling the cache skips. Over a table of 10,000 rows the scan
+// takes nearly all of the time; over an empty one, where it takes none,
+// the two loops still come out even. Runs have measured ratios between
+// 0.92x and 1.17x over the full table and between 0.92x and 1.04x over the
+// empty one, which is noise. The loops are timed against both tables so
+// that a change making the cache pay, or cost, shows in the numbers.
+
+use std::time::{Duration, Instant};
+
+use sreerag_devadasan::engine::Database;
+
+fn database(rows: usize, capacity: usize) -> Database {
+    let mut db = Database::new();
+    db.set_plan_cache_capacity(capacity);
+    db.execute_sql("CREATE TABLE people (id INT, age INT, score INT, name VARCHAR(20))").unwrap();
+    for batch in (0..rows as i64).collect::<Vec<_>>().chunks(1_000) {
+        let rows: Vec<String> =
+            batch.iter().map(|i| format!("({}, {}, {}, 'user{}')", i, i % 90, (i * 7) % 100, i % 1000)).collect();
+        db.execute_sql(&format!("INSERT INTO people VALUES {}", rows.join(", "))).unwrap();
+    }
+    db
+}
+
+/// The time `queries` of the loop take, and the rows they match.
+fn run(db: &mut Database, queries: usize) -> (Duration, usize) {
+    let start = Instant::now();
+    let matched = (0..queries)
+        .map(|i| {
+            let (age, score) = (i % 90, i % 100);
+            let condition = format!("age > {} AND (score < {} OR name = 'user{}')", age, score, i);
+            let sql = format!("SELECT id FROM people WHERE {}", condition);
+            db.execute_sql(&sql).unwrap().rows.len()
+        })
+        .sum();
+    (start.elapsed(), matched)
+}
+
+fn main() {
+    for (rows, queries) in [(10_000, 500), (0, 20_000)] {
+        let mut uncached = database(rows, 0);
+        let mut cached = database(rows, 64);
+        let (uncached_time, uncached_matched) = run(&mut uncached, queries);
+        let (cached_time, cached_matched) = run(&mut cached, queries);
+
+        assert_eq!(uncached_matched, cached_matched);
+        let stats = cached.plan_cache_stats();
+        println!("rows: {}, queries: {}, matching rows: {}", rows, queries, cached_matched);
+        println!("  without the cache: {:?}", uncached_time);
+        println!("  with the cache:    {:?} ({} hits, {} misses)", cached_time, stats.hits, stats.misses);
+        println!("  without / with:    {:.2}x", uncached_time.as_secs_f64() / cached_time.as_secs_f64());
+    }
+}
@@ -0,0 +1,702 @@
+// analyzer.rs — checks function calls and operators against known signatures
+// Author: Sreerag Devadasan
+//
+// The parser accepts any `name(args)` call and any operands on either side
+// of an operator; `analyze` checks them against a `Registry` of
+// signatures. It reports calls to functions the registry doesn't know,
+// calls with the wrong number of arguments or an argument of the wrong
+// type, aggregates nested in aggregates, window functions without OVER,
+// and operators applied to operands they have no signature for.
+// `Registry::new` knows the common functions (COUNT, SUM, LOWER, COALESCE,
+// ...) and the builtin operators; code with functions of its own registers
+// them on top with `scalar`, `aggregate` and `window`, and `apply` adds
+// the functions a CREATE FUNCTION defines. `operator` gives a builtin
+// operator more operand types, such as `+` on text for a dialect that
+// concatenates with it; operators of their own spelling aren't parsed.
+//
+// Types are inferred bottom-up from literals, the return types of calls
+// and, given a schema, the declared types of columns. Anything unknown (a
+// NULL, a placeholder, a column the schema doesn't have) is `SqlType::Any`,
+// which fits every parameter, so only what is certainly wrong is reported.
+//
+// Given a schema, the tables a statement names are checked against it, and
+// so are its columns when every table it reads is a table of the schema:
+// a subquery, CTE or table function brings columns the schema can't tell
+// of. An unknown name comes with the closest known one, the way the
+// tutorial suggests keywords, so `usre_id` is reported with `user_id`.
+// Names that resolve but perhaps not as meant are warnings rather than
+// errors (`Problem::severity`): an unqualified column more than one joined
+// table has, two FROM items under one name, and a CTE hiding a table.
+
+use alloc::collections::BTreeMap;
+use core::fmt;
+
+use crate::prelude::*;
+use crate::ast::{
+    BinaryOperator, DBType, Expression, RoutineType, Statement, StringPrefix, TableColumn, TableFactor, UnaryOperator,
+    WindowSpec,
+};
+use crate::ident::{closest, Ident};
+use crate::lint::Severity;
+use crate::schema_diff::Schema;
+use crate::visit::{walk, Visitor};
+
+/// The type of a value as far as the analyzer can tell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlType {
+    Integer,
+    Text,
+    Boolean,
+    Date,
+    Time,
+    Timestamp,
+    Decimal,
+    /// Not known, or any type at all for a parameter.
+    Any,
+}
+
+impl SqlType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SqlType::Integer => "integer",
+            SqlType::Text => "text",
+            SqlType::Boolean => "boolean",
+            SqlType::Date => "date",
+            SqlType::Time => "time",
+            SqlType::Timestamp => "timestamp",
+            SqlType::Decimal => "decimal",
+            SqlType::Any => "any",
+        }
+    }
+
+    /// True when a value of this type can be given where `expected` is.
+    pub fn fits(self, expected: SqlType) -> bool {
+        self == SqlType::Any || expected == SqlType::Any || self == expected
+    }
+}
+
+impl From<&DBType> for SqlType {
+    fn from(data_type: &DBType) -> Self {
+        match data_type {
+            DBType::Int => SqlType::Integer,
+            DBType::Varchar(_) => SqlType::Text,
+            DBType::Bool => SqlType::Boolean,
+            DBType::Date => SqlType::Date,
+            DBType::Time => SqlType::Time,
+            DBType::Timestamp => SqlType::Timestamp,
+            DBType::Decimal(..) => SqlType::Decimal,
+        }
+    }
+}
+
+/// A type named as written is one the analyzer can't check against.
+impl From<&RoutineType> for SqlType {
+    fn from(data_type: &RoutineType) -> Self {
+        match data_type {
+            RoutineType::Known(known) => known.into(),
+            RoutineType::Named(_) => SqlType::Any,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionKind {
+    Scalar,
+    /// Computed over a group of rows, such as SUM; it may also be used as
+    /// a window function.
+    Aggregate,
+    /// Only usable with OVER, such as ROW_NUMBER.
+    Window,
+}
+
+/// One way of calling a function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionSignature {
+    pub kind: FunctionKind,
+    pub params: Vec<SqlType>,
+    /// Whether the last parameter repeats: the call takes that many
+    /// arguments or more.
+    pub variadic: bool,
+    pub returns: SqlType,
+}
+
+impl FunctionSignature {
+    fn accepts(&self, count: usize) -> bool {
+        if self.variadic {
+            count >= self.params.len()
+        } else {
+            count == self.params.len()
+        }
+    }
+
+    fn param(&self, index: usize) -> SqlType {
+        self.params.get(index).or(self.params.last()).copied().unwrap_or(SqlType::Any)
+    }
+
+    /// The number of arguments, as in "takes 2" or "takes at least 1".
+    fn arity(&self) -> String {
+        if self.variadic {
+            format!("at least {}", self.params.len())
+        } else {
+            self.params.len().to_string()
+        }
+    }
+}
+
+/// The operand types a binary operator accepts, and its result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperatorSignature {
+    pub operator: BinaryOperator,
+    pub left: SqlType,
+    pub right: SqlType,
+    pub returns: SqlType,
+}
+
+/// Function and operator signatures by name, each with its overloads in
+/// the order they were registered.
+#[derive(Debug, Clone)]
+pub struct Registry {
+    functions: BTreeMap<String, Vec<FunctionSignature>>,
+    operators: Vec<OperatorSignature>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Registry {
+    /// The builtin operators and common functions.
+    pub fn new() -> Self {
+        use SqlType::*;
+        let mut registry = Self::empty();
+        for operator in [BinaryOperator::And, BinaryOperator::Or] {
+            registry.operator(operator, Boolean, Boolean, Boolean);
+        }
+        let comparisons = [
+            BinaryOperator::Equals,
+            BinaryOperator::NotEquals,
+            BinaryOperator::GreaterThan,
+            BinaryOperator::GreaterThanOrEqual,
+            BinaryOperator::LessThan,
+            BinaryOperator::LessThanOrEqual,
+            BinaryOperator::IsDistinctFrom,
+            BinaryOperator::IsNotDistinctFrom,
+        ];
+        for operator in comparisons {
+            for operand in [Integer, Text, Boolean, Date, Time, Timestamp, Decimal] {
+                registry.operator(operator.clone(), operand, operand, Boolean);
+            }
+            // Strings are read as dates, times and decimals, dates compare with
+            // timestamps and integers with decimals
+            let mixed = [(Date, Timestamp), (Date, Text), (Time, Text), (Timestamp, Text)];
+            for (left, right) in mixed.into_iter().chain([(Decimal, Integer), (Decimal, Text)]) {
+                registry.operator(operator.clone(), left, right, Boolean);
+                registry.operator(operator.clone(), right, left, Boolean);
+            }
+        }
+        let arithmetic = [
+            BinaryOperator::Add,
+            BinaryOperator::Subtract,
+            BinaryOperator::Multiply,
+            BinaryOperator::Divide,
+        ];
+        for operator in arithmetic {
+            registry
+                .operator(operator.clone(), Integer, Integer, Integer)
+                .operator(operator.clone(), Decimal, Decimal, Decimal)
+                .operator(operator.clone(), Decimal, Integer, Decimal)
+                .operator(operator, Integer, Decimal, Decimal);
+        }
+        registry
+            .operator(BinaryOperator::Add, Date, Integer, Date)
+            .operator(BinaryOperator::Add, Integer, Date, Date)
+            .operator(BinaryOperator::Subtract, Date, Integer, Date)
+            .operator(BinaryOperator::Subtract, Date, Date, Integer);
+        for operator in [BinaryOperator::Like, BinaryOperator::ILike] {
+            registry.operator(operator, Text, Text, Boolean);
+        }
+        registry.operator(BinaryOperator::Concat, Text, Text, Text);
+
+        registry
+            .aggregate("COUNT", &[Any], Integer)
+            .aggregate("SUM", &[Integer], Integer)
+            .aggregate("SUM", &[Decimal], Decimal)
+            .aggregate("AVG", &[Integer], Integer)
+            .aggregate("MIN", &[Any], Any)
+            .aggregate("MAX", &[Any], Any)
+            .scalar("LOWER", &[Text], Text)
+            .scalar("UPPER", &[Text], Text)
+            .scalar("TRIM", &[Text], Text)
+            .scalar("LENGTH", &[Text], Integer)
+            .scalar("SUBSTRING", &[Text, Integer], Text)
+            .scalar("SUBSTRING", &[Text, Integer, Integer], Text)
+            .scalar("ABS", &[Integer], Integer)
+            .scalar("NOW", &[], Any)
+            .scalar("DATE_PART", &[Text, Any], Integer)
+            .scalar("DATE_TRUNC", &[Text, Any], Any)
+            .scalar("NULLIF", &[Any, Any], Any)
+            .variadic("COALESCE", &[Any], Any)
+            .variadic("CONCAT", &[Any], Text)
+            .window("ROW_NUMBER", &[], Integer)
+            .window("RANK", &[], Integer)
+            .window("DENSE_RANK", &[], Integer)
+            .window("LAG", &[Any], Any)
+            .window("LEAD", &[Any], Any);
+        registry
+    }
+
+    /// A registry without any signatures, not even the builtin ones.
+    pub fn empty() -> Self {
+        Registry { functions: BTreeMap::new(), operators: Vec::new() }
+    }
+
+    /// Adds a signature for `name`, matched case-insensitively; a name can
+    /// have several, for calls with different arguments.
+    pub fn function(&mut self, name: &str, signature: FunctionSignature) -> &mut Self {
+        self.functions.entry(name.to_uppercase()).or_default().push(signature);
+        self
+    }
+
+    pub fn scalar(&mut self, name: &str, params: &[SqlType], returns: SqlType) -> &mut Self {
+        self.add(name, FunctionKind::Scalar, params, false, returns)
+    }
+
+    pub fn aggregate(&mut self, name: &str, params: &[SqlType], returns: SqlType) -> &mut Self {
+        self.add(name, FunctionKind::Aggregate, params, false, returns)
+    }
+
+    pub fn window(&mut self, name: &str, params: &[SqlType], returns: SqlType) -> &mut Self {
+        self.add(name, FunctionKind::Window, params, false, returns)
+    }
+
+    /// A scalar function whose last parameter repeats.
+    pub fn variadic(&mut self, name: &str, params: &[SqlType], returns: SqlType) -> &mut Self {
+        self.add(name, FunctionKind::Scalar, params, true, returns)
+    }
+
+    fn add(
+        &mut self,
+        name: &str,
+        kind: FunctionKind,
+        params: &[SqlType],
+        variadic: bool,
+        returns: SqlType,
+    ) -> &mut Self {
+        self.function(name, FunctionSignature { kind, params: params.to_vec(), variadic, returns })
+    }
+
+    /// Lets `operator` take operands of these types.
+    pub fn operator(&mut self, operator: BinaryOperator, left: SqlType, right: SqlType, returns: SqlType) -> &mut Self {
+        self.operators.push(OperatorSignature { operator, left, right, returns });
+        self
+    }
+
+    /// Registers the function a CREATE FUNCTION defines; returns false,
+    /// changing nothing, for any other statement, procedures included.
+    pub fn apply(&mut self, statement: &Statement) -> bool {
+        match statement {
+            Statement::CreateFunction { procedure: false, name, parameters, returns, .. } => {
+                let params: Vec<SqlType> = parameters.iter().map(|parameter| (&parameter.data_type).into()).collect();
+                let returns = returns.as_ref().map_or(SqlType::Any, SqlType::from);
+                // OR REPLACE or not, the new definition is the one calls see
+                self.functions.remove(&name.to_uppercase());
+                self.scalar(name, &params, returns);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The signatures registered for `name`.
+    pub fn signatures(&self, name: &str) -> &[FunctionSignature] {
+        self.functions.get(&name.to_uppercase()).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Something about a call or an operator that can't work.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    UnknownFunction(String),
+    WrongArity {
+        function: String,
+        expected: String,
+        found: usize,
+    },
+    /// `position` counts arguments from 1.
+    WrongArgument {
+        function: String,
+        position: usize,
+        expected: SqlType,
+        found: SqlType,
+    },
+    NestedAggregate {
+        function: String,
+        inside: String,
+    },
+    MissingOver(String),
+    /// An operator without a signature for its operands; `left` is `None`
+    /// for NOT and unary minus.
+    OperandTypes {
+        operator: String,
+        left: Option<SqlType>,
+        right: SqlType,
+    },
+    /// A table the schema doesn't have, with the schema's closest name.
+    UnknownTable {
+        name: String,
+        suggestion: Option<String>,
+    },
+    /// A column none of the statement's tables has, with the closest of
+    /// theirs.
+    UnknownColumn {
+        name: String,
+        suggestion: Option<String>,
+    },
+    /// A warning: two FROM items of one SELECT under the same name.
+    DuplicateAlias(String),
+    /// A warning: an unqualified column that more than one of the joined
+    /// tables has, with those tables.
+    AmbiguousColumn {
+        name: String,
+        tables: Vec<String>,
+    },
+    /// A warning: a CTE named like a table of the schema, which it hides
+    /// from the rest of the statement.
+    ShadowedTable(String),
+}
+
+impl Problem {
+    /// `Warning` for what may well be meant, such as an ambiguous column
+    /// the database resolves or rejects by its own rules; `Error` for the
+    /// rest.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Problem::DuplicateAlias(_) | Problem::AmbiguousColumn { .. } | Problem::ShadowedTable(_) => {
+                Severity::Warning
+            }
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::UnknownFunction(name) => write!(f, "Unknown function: {}", name),
+            Problem::WrongArity { function, expected, found } => {
+                write!(f, "{} takes {} argument(s), but is given {}", function, expected, found)
+            }
+            Problem::WrongArgument { function, position, expected, found } => write!(
+                f,
+                "Argument {} of {} should be {}, but is {}",
+                position,
+                function,
+                expected.name(),
+                found.name()
+            ),
+            Problem::NestedAggregate { function, inside } => {
+                write!(f, "The aggregate {} can't be used inside the aggregate {}", function, inside)
+            }
+            Problem::MissingOver(name) => write!(f, "The window function {} needs an OVER clause", name),
+            Problem::OperandTypes { operator, left: Some(left), right } => {
+                write!(f, "{} can't be applied to {} and {}", operator, left.name(), right.name())
+            }
+            Problem::OperandTypes { operator, left: None, right } => {
+                write!(f, "{} can't be applied to {}", operator, right.name())
+            }
+            Problem::UnknownTable { name, suggestion } => {
+                write!(f, "Unknown table: {}", name)?;
+                did_you_mean(f, suggestion)
+            }
+            Problem::UnknownColumn { name, suggestion } => {
+                write!(f, "Unknown column: {}", name)?;
+                did_you_mean(f, suggestion)
+            }
+            Problem::DuplicateAlias(name) => write!(f, "More than one FROM item is named {}", name),
+            Problem::AmbiguousColumn { name, tables } => {
+                write!(f, "The column {} is ambiguous: it's in {}", name, tables.join(" and "))
+            }
+            Problem::ShadowedTable(name) => write!(f, "The CTE {} hides the table of the same name", name),
+        }
+    }
+}
+
+fn did_you_mean(f: &mut fmt::Formatter<'_>, suggestion: &Option<String>) -> fmt::Result {
+    match suggestion {
+        Some(suggestion) => write!(f, "; did you mean {}?", suggestion),
+        None => Ok(()),
+    }
+}
+
+/// Every problem with the calls, operators and names in `statement`: the
+/// ones with its tables and FROM items first, then those in its columns
+/// and expressions, in source order. Columns are typed from `schema` when
+/// one is given, and from the statement's own columns in a CREATE TABLE.
+pub fn analyze(statement: &Statement, registry: &Registry, schema: Option<&Schema>) -> Vec<Problem> {
+    let mut columns: Vec<(&str, &TableColumn)> = Vec::new();
+    let mut problems = Vec::new();
+    let mut sources = Sources::default();
+    walk(statement, &mut sources);
+    if let Some(schema) = schema {
+        let table = |name: &str| schema.tables.keys().any(|table| table.eq_ignore_ascii_case(name));
+        let shadowed = sources.ctes.iter().filter(|cte| table(cte));
+        problems.extend(shadowed.map(|cte| Problem::ShadowedTable(cte.clone())));
+    }
+    problems.extend(sources.duplicates.iter().cloned().map(Problem::DuplicateAlias));
+    // Whether every column the statement can name comes from a table
+    let mut resolve = sources.statements == 1 && !sources.other;
+    if let Statement::CreateTable { table_name, column_list } = statement {
+        columns.extend(column_list.iter().map(|column| (table_name.as_str(), column)));
+        resolve = false;
+    } else if let Some(schema) = schema {
+        for table in statement.tables() {
+            match schema.tables.iter().find(|(name, _)| name.eq_ignore_ascii_case(table)) {
+                Some((_, table_columns)) => columns.extend(table_columns.iter().map(|column| (table, column))),
+                None => {
+                    let suggestion = closest(table, schema.tables.keys().map(String::as_str)).map(str::to_string);
+                    problems.push(Problem::UnknownTable { name: table.to_string(), suggestion });
+                    resolve = false;
+                }
+            }
+        }
+    }
+    // A statement without tables has no columns to find its names in
+    resolve &= !columns.is_empty();
+    let mut analysis = Analysis { registry, columns, resolve, aggregate: None, problems };
+    // The select list's names are among its expressions
+    let named: Vec<&Ident> = match statement {
+        Statement::Insert { columns: Some(columns), .. } => columns.iter().collect(),
+        Statement::Update { assignments, .. } => assignments.iter().map(|(column, _)| column).collect(),
+        _ => Vec::new(),
+    };
+    for name in named {
+        analysis.unqualified(name);
+    }
+    // A bare name in ORDER BY may be a select-list alias instead
+    let aliased: Vec<&Expression> = match statement {
+        Statement::Select { columns, order_by, .. } => {
+            let aliases = columns.iter().flat_map(|item| &item.alias);
+            let alias = |name: &Ident| aliases.clone().any(|alias| alias.eq_ignore_ascii_case(name.as_str()));
+            let named = |expr: &&Expression| matches!(expr, Expression::Identifier(name) if alias(name));
+            order_by.iter().map(|item| &item.expr).filter(named).collect()
+        }
+        _ => Vec::new(),
+    };
+    for expr in statement.expressions() {
+        if !aliased.iter().any(|&alias| core::ptr::eq(alias, expr)) {
+            analysis.infer(expr);
+        }
+    }
+    analysis.problems
+}
+
+/// What a statement reads from and the names it gives its sources.
+#[derive(Default)]
+struct Sources {
+    /// The statement and those nested in it.
+    statements: usize,
+    /// Whether anything but a table is read: a subquery, a table function,
+    /// a PIVOT.
+    other: bool,
+    /// The names given to more than one FROM item of a SELECT.
+    duplicates: Vec<String>,
+    ctes: Vec<String>,
+}
+
+impl Visitor for Sources {
+    fn statement(&mut self, statement: &Statement) {
+        self.statements += 1;
+        match statement {
+            Statement::Select { from, joins, .. } => {
+                let factors = core::iter::once(from.as_ref()).chain(joins.iter().map(|join| &join.relation));
+                let mut names: Vec<&str> = Vec::new();
+                for name in factors.filter_map(exposed_name) {
+                    let twice = names.iter().any(|seen| seen.eq_ignore_ascii_case(name));
+                    if twice && !self.duplicates.iter().any(|duplicate| duplicate.eq_ignore_ascii_case(name)) {
+                        self.duplicates.push(name.to_string());
+                    }
+                    names.push(name);
+                }
+            }
+            Statement::With { ctes, .. } => self.ctes.extend(ctes.iter().map(|cte| cte.name.to_string())),
+            _ => {}
+        }
+    }
+
+    fn table_factor(&mut self, factor: &TableFactor) {
+        self.other |= !matches!(factor, TableFactor::Table { .. } | TableFactor::AsOf { .. });
+    }
+}
+
+/// The name the rest of a SELECT refers to a FROM item by: its alias, or
+/// the table or function it reads.
+fn exposed_name(factor: &TableFactor) -> Option<&str> {
+    let alias = match factor {
+        TableFactor::Table { alias, .. }
+        | TableFactor::AsOf { alias, .. }
+        | TableFactor::Derived { alias, .. }
+        | TableFactor::Function { alias, .. }
+        | TableFactor::Pivot { alias, .. }
+        | TableFactor::Unpivot { alias, .. } => alias,
+    };
+    match (alias, factor) {
+        (Some(alias), _) => Some(&alias.name),
+        (None, TableFactor::Function { name, .. }) => Some(name),
+        (None, factor) => factor.table_name(),
+    }
+}
+
+struct Analysis<'a> {
+    registry: &'a Registry,
+    /// The columns in scope, with the table each is from.
+    columns: Vec<(&'a str, &'a TableColumn)>,
+    /// Whether `columns` are all the columns in scope, so that a name not
+    /// among them is a problem.
+    resolve: bool,
+    /// The aggregate whose arguments are being analyzed.
+    aggregate: Option<String>,
+    problems: Vec<Problem>,
+}
+
+impl Analysis<'_> {
+    /// The type of `expr`, recording the problems found in it.
+    fn infer(&mut self, expr: &Expression) -> SqlType {
+        match expr {
+            Expression::Number(_) => SqlType::Integer,
+            Expression::Decimal(_) => SqlType::Decimal,
+            Expression::String(_) => SqlType::Text,
+            Expression::PrefixedString { prefix: StringPrefix::Bit, .. } => SqlType::Integer,
+            Expression::PrefixedString { .. } => SqlType::Text,
+            Expression::Boolean(_) => SqlType::Boolean,
+            Expression::Null | Expression::Placeholder(_) => SqlType::Any,
+            Expression::TypedString { data_type, .. } => SqlType::from(data_type),
+            // The `*` of COUNT(*)
+            Expression::Identifier(name) if name == "*" => SqlType::Any,
+            Expression::Identifier(name) => self.unqualified(name),
+            Expression::CompoundIdentifier(parts) => parts.last().map_or(SqlType::Any, |name| self.column(name)),
+            Expression::Grouped(inner) | Expression::Collate { expr: inner, .. } => self.infer(inner),
+            Expression::Cast { expr, data_type } => {
+                self.infer(expr);
+                SqlType::from(data_type)
+            }
+            Expression::UnaryOperation { operator, operand } => {
+                let found = self.infer(operand);
+                let expected = match operator {
+                    // A value of any type is NULL or not
+                    UnaryOperator::IsNull | UnaryOperator::IsNotNull => return SqlType::Boolean,
+                    UnaryOperator::Not => SqlType::Boolean,
+                    UnaryOperator::Negate if found == SqlType::Decimal => SqlType::Decimal,
+                    UnaryOperator::Negate => SqlType::Integer,
+                };
+                if !found.fits(expected) {
+                    let operator = operator.to_string().trim().to_string();
+                    self.problems.push(Problem::OperandTypes { operator, left: None, right: found });
+                }
+                expected
+            }
+            Expression::BinaryOperation { left_operand, operator, right_operand } => {
+                let left = self.infer(left_operand);
+                let right = self.infer(right_operand);
+                let mut signatures = self.registry.operators.iter().filter(|signature| signature.operator == *operator);
+                match signatures.find(|signature| left.fits(signature.left) && right.fits(signature.right)) {
+                    Some(signature) => signature.returns,
+                    None => {
+                        let operator = operator.to_string();
+                        self.problems.push(Problem::OperandTypes { operator, left: Some(left), right });
+                        SqlType::Any
+                    }
+                }
+            }
+            Expression::Function { name, args, over } => self.call(name, args, over.as_deref()),
+        }
+    }
+
+    fn column(&mut self, name: &str) -> SqlType {
+        let column = self.columns.iter().find(|(_, column)| column.column_name.eq_ignore_ascii_case(name));
+        if column.is_none() && self.resolve {
+            let names = self.columns.iter().map(|(_, column)| column.column_name.as_str());
+            let suggestion = closest(name, names).map(str::to_string);
+            self.problems.push(Problem::UnknownColumn { name: name.to_string(), suggestion });
+        }
+        column.map_or(SqlType::Any, |(_, column)| SqlType::from(&column.column_type))
+    }
+
+    /// `column`, for a name without the table it's from.
+    fn unqualified(&mut self, name: &str) -> SqlType {
+        if self.resolve {
+            let tables: Vec<String> = self
+                .columns
+                .iter()
+                .filter(|(_, column)| column.column_name.eq_ignore_ascii_case(name))
+                .map(|(table, _)| table.to_string())
+                .collect();
+            if tables.len() > 1 {
+                self.problems.push(Problem::AmbiguousColumn { name: name.to_string(), tables });
+            }
+        }
+        self.column(name)
+    }
+
+    fn call(&mut self, name: &str, args: &[Expression], window: Option<&WindowSpec>) -> SqlType {
+        let over = window.is_some();
+        let signatures = self.registry.signatures(name);
+        let kind = signatures.first().map(|signature| signature.kind);
+        if signatures.is_empty() {
+            self.problems.push(Problem::UnknownFunction(name.to_string()));
+        } else if kind == Some(FunctionKind::Window) && !over {
+            self.problems.push(Problem::MissingOver(name.to_string()));
+        } else if let (Some(FunctionKind::Aggregate), false, Some(inside)) = (kind, over, &self.aggregate) {
+            let problem = Problem::NestedAggregate { function: name.to_string(), inside: inside.clone() };
+            self.problems.push(problem);
+        }
+
+        // Arguments first, inside this aggregate if it is one
+        let outer = self.aggregate.clone();
+        if kind == Some(FunctionKind::Aggregate) && !over {
+            self.aggregate = Some(name.to_string());
+        }
+        let found: Vec<SqlType> = args.iter().map(|arg| self.infer(arg)).collect();
+        self.aggregate = outer;
+        // The window's expressions are outside any aggregate
+        if let Some(window) = window {
+            for item in window.partition_by.iter().chain(window.order_by.iter().map(|item| &item.expr)) {
+                self.infer(item);
+            }
+        }
+
+        if signatures.is_empty() {
+            return SqlType::Any;
+        }
+        let candidates: Vec<&FunctionSignature> =
+            signatures.iter().filter(|signature| signature.accepts(found.len())).collect();
+        let fits =
+            |signature: &FunctionSignature| found.iter().enumerate().all(|(i, arg)| arg.fits(signature.param(i)));
+        if let Some(signature) = candidates.iter().find(|signature| fits(signature)) {
+            return signature.returns;
+        }
+        match candidates.first() {
+            Some(signature) => {
+                // Some argument doesn't fit, or the signature would have
+                let misfit = found.iter().enumerate().find(|(i, arg)| !arg.fits(signature.param(*i)));
+                let (index, arg) = misfit.expect("an argument that doesn't fit");
+                self.problems.push(Problem::WrongArgument {
+                    function: name.to_string(),
+                    position: index + 1,
+                    expected: signature.param(index),
+                    found: *arg,
+                });
+            }
+            None => {
+                let expected: Vec<String> = signatures.iter().map(FunctionSignature::arity).collect();
+                self.problems.push(Problem::WrongArity {
+                    function: name.to_string(),
+                    expected: expected.join(" or "),
+                    found: found.len(),
+                });
+            }
+        }
+        SqlType::Any
+    }
+}
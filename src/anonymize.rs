@@ -0,0 +1,346 @@
+// anonymize.rs — strips literal values out of queries so logs can be shared
+// Author: Sreerag Devadasan
+
+use crate::prelude::*;
+use crate::ast::{
+    Constraint, Cte, Expression, GroupingElement, Join, MergeAction, MergeClause, MergeSource, OrderByExpr, RoleOption,
+    Statement, SelectItem, StringPrefix, TableColumn, TableFactor, TableSample, TriggerBody, WildcardOptions,
+};
+use crate::hash::fnv1a;
+use crate::parser::parse_sql;
+use crate::tokenizer::ParseError;
+
+/// How literal values are replaced.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnonymizeMode {
+    /// Replace literals with typed placeholders (`:string`, `:number`).
+    Placeholders,
+    /// Replace literals with a stable hash of their value, so equal values
+    /// still compare equal across queries but can't be read back.
+    Hashed,
+}
+
+/// Returns a copy of the statement with every string, number and typed
+/// (`DATE '...'`) literal replaced according to `mode`. Structure, identifiers and keywords are kept.
+pub fn anonymize(statement: &Statement, mode: AnonymizeMode) -> Statement {
+    match statement {
+        Statement::Select {
+            hints,
+            columns,
+            wildcard,
+            from,
+            joins,
+            selection,
+            group_by,
+            qualify,
+            order_by,
+            limit,
+            offset,
+            locking,
+        } => {
+            Statement::Select {
+                hints: hints.clone(),
+                columns: columns
+                    .iter()
+                    .map(|item| SelectItem { expr: anonymize_expression(&item.expr, mode), alias: item.alias.clone() })
+                    .collect(),
+                wildcard: WildcardOptions {
+                    exclude: wildcard.exclude.clone(),
+                    replace: wildcard.replace.iter().map(|(expr, column)| (anonymize_expression(expr, mode), column.clone())).collect(),
+                },
+                from: Box::new(anonymize_table_factor(from, mode)),
+                joins: joins
+                    .iter()
+                    .map(|join| Join {
+                        kind: join.kind,
+                        relation: anonymize_table_factor(&join.relation, mode),
+                        on: join.on.as_ref().map(|expr| anonymize_expression(expr, mode)),
+                    })
+                    .collect(),
+                selection: selection.as_ref().map(|expr| anonymize_expression(expr, mode)),
+                group_by: group_by.iter().map(|element| anonymize_grouping_element(element, mode)).collect(),
+                qualify: qualify.as_ref().map(|expr| anonymize_expression(expr, mode)),
+                order_by: order_by
+                    .iter()
+                    .map(|item| OrderByExpr { expr: anonymize_expression(&item.expr, mode), asc: item.asc })
+                    .collect(),
+                limit: *limit,
+                offset: *offset,
+                locking: locking.clone(),
+            }
+        }
+        Statement::CreateTable { table_name, column_list } => Statement::CreateTable {
+            table_name: table_name.clone(),
+            column_list: column_list
+                .iter()
+                .map(|column| TableColumn {
+                    constraints: column
+                        .constraints
+                        .iter()
+                        .map(|constraint| match constraint {
+                            Constraint::Check(expr) => Constraint::Check(anonymize_expression(expr, mode)),
+                            other => other.clone(),
+                        })
+                        .collect(),
+                    ..column.clone()
+                })
+                .collect(),
+        },
+        Statement::Insert { table_name, columns, rows } => Statement::Insert {
+            table_name: table_name.clone(),
+            columns: columns.clone(),
+            rows: anonymize_rows(rows, mode),
+        },
+        Statement::CreateView { or_replace, name, columns, query } => Statement::CreateView {
+            or_replace: *or_replace,
+            name: name.clone(),
+            columns: columns.clone(),
+            query: Box::new(anonymize(query, mode)),
+        },
+        Statement::InsertSelect { table_name, columns, query } => Statement::InsertSelect {
+            table_name: table_name.clone(),
+            columns: columns.clone(),
+            query: Box::new(anonymize(query, mode)),
+        },
+        Statement::Update { table_name, alias, assignments, selection } => Statement::Update {
+            table_name: table_name.clone(),
+            alias: alias.clone(),
+            assignments: assignments
+                .iter()
+                .map(|(column, value)| (column.clone(), anonymize_expression(value, mode)))
+                .collect(),
+            selection: selection.as_ref().map(|expr| anonymize_expression(expr, mode)),
+        },
+        Statement::Delete { table_name, alias, selection } => Statement::Delete {
+            table_name: table_name.clone(),
+            alias: alias.clone(),
+            selection: selection.as_ref().map(|expr| anonymize_expression(expr, mode)),
+        },
+        Statement::Values { rows } => Statement::Values { rows: anonymize_rows(rows, mode) },
+        Statement::CreateRole { role_name, options } => Statement::CreateRole {
+            role_name: role_name.clone(),
+            options: anonymize_role_options(options, mode),
+        },
+        Statement::AlterRole { role_name, options } => Statement::AlterRole {
+            role_name: role_name.clone(),
+            options: anonymize_role_options(options, mode),
+        },
+        Statement::Merge { target, target_alias, source, source_alias, on, clauses } => Statement::Merge {
+            target: target.clone(),
+            target_alias: target_alias.clone(),
+            source: match source {
+                MergeSource::Query(query) => MergeSource::Query(Box::new(anonymize(query, mode))),
+                table => table.clone(),
+            },
+            source_alias: source_alias.clone(),
+            on: anonymize_expression(on, mode),
+            clauses: clauses
+                .iter()
+                .map(|clause| MergeClause {
+                    matched: clause.matched,
+                    condition: clause.condition.as_ref().map(|expr| anonymize_expression(expr, mode)),
+                    action: match &clause.action {
+                        MergeAction::Update(assignments) => MergeAction::Update(
+                            assignments
+                                .iter()
+                                .map(|(column, value)| (column.clone(), anonymize_expression(value, mode)))
+                                .collect(),
+                        ),
+                        MergeAction::Delete => MergeAction::Delete,
+                        MergeAction::Insert { columns, values } => MergeAction::Insert {
+                            columns: columns.clone(),
+                            values: values.iter().map(|value| anonymize_expression(value, mode)).collect(),
+                        },
+                    },
+                })
+                .collect(),
+        },
+        Statement::CreateTrigger {
+            name,
+            timing,
+            events,
+            update_columns,
+            table_name,
+            for_each_row,
+            condition,
+            body,
+        } => {
+            Statement::CreateTrigger {
+                name: name.clone(),
+                timing: *timing,
+                events: events.clone(),
+                update_columns: update_columns.clone(),
+                table_name: table_name.clone(),
+                for_each_row: *for_each_row,
+                condition: condition.as_ref().map(|expr| anonymize_expression(expr, mode)),
+                body: match body {
+                    TriggerBody::Execute { procedure, function, args } => TriggerBody::Execute {
+                        procedure: *procedure,
+                        function: function.clone(),
+                        args: args.iter().map(|arg| anonymize_expression(arg, mode)).collect(),
+                    },
+                    TriggerBody::Statement(statement) => TriggerBody::Statement(Box::new(anonymize(statement, mode))),
+                    block => block.clone(),
+                },
+            }
+        }
+        Statement::SetVariable { scope, name, value } => Statement::SetVariable {
+            scope: *scope,
+            name: name.clone(),
+            value: value.iter().map(|expr| anonymize_expression(expr, mode)).collect(),
+        },
+        Statement::Prepare { name, parameter_types, statement } => Statement::Prepare {
+            name: name.clone(),
+            parameter_types: parameter_types.clone(),
+            statement: Box::new(anonymize(statement, mode)),
+        },
+        Statement::Explain { analyze, statement } => {
+            Statement::Explain { analyze: *analyze, statement: Box::new(anonymize(statement, mode)) }
+        }
+        Statement::Execute { name, parameters } => Statement::Execute {
+            name: name.clone(),
+            parameters: parameters.iter().map(|expr| anonymize_expression(expr, mode)).collect(),
+        },
+        Statement::SetOperation { operator, all, left, right } => Statement::SetOperation {
+            operator: *operator,
+            all: *all,
+            left: Box::new(anonymize(left, mode)),
+            right: Box::new(anonymize(right, mode)),
+        },
+        Statement::With { recursive, ctes, body } => Statement::With {
+            recursive: *recursive,
+            ctes: ctes
+                .iter()
+                .map(|cte| Cte { query: Box::new(anonymize(&cte.query, mode)), ..cte.clone() })
+                .collect(),
+            body: Box::new(anonymize(body, mode)),
+        },
+        other => other.clone(),
+    }
+}
+
+fn anonymize_table_factor(factor: &TableFactor, mode: AnonymizeMode) -> TableFactor {
+    match factor {
+        TableFactor::Table { name, alias, sample } => TableFactor::Table {
+            name: name.clone(),
+            alias: alias.clone(),
+            sample: sample.as_ref().map(|sample| TableSample {
+                method: sample.method.clone(),
+                percent: anonymize_expression(&sample.percent, mode),
+                repeatable: sample.repeatable.as_ref().map(|expr| anonymize_expression(expr, mode)),
+            }),
+        },
+        TableFactor::AsOf { .. } => factor.clone(),
+        TableFactor::Derived { lateral, subquery, alias } => TableFactor::Derived {
+            lateral: *lateral,
+            subquery: Box::new(anonymize(subquery, mode)),
+            alias: alias.clone(),
+        },
+        TableFactor::Function { lateral, name, args, alias } => TableFactor::Function {
+            lateral: *lateral,
+            name: name.clone(),
+            args: args.iter().map(|arg| anonymize_expression(arg, mode)).collect(),
+            alias: alias.clone(),
+        },
+        TableFactor::Pivot { table, aggregate, column, values, alias } => TableFactor::Pivot {
+            table: Box::new(anonymize_table_factor(table, mode)),
+            aggregate: anonymize_expression(aggregate, mode),
+            column: column.clone(),
+            values: values.iter().map(|value| anonymize_expression(value, mode)).collect(),
+            alias: alias.clone(),
+        },
+        TableFactor::Unpivot { table, value, name, columns, alias } => TableFactor::Unpivot {
+            table: Box::new(anonymize_table_factor(table, mode)),
+            value: value.clone(),
+            name: name.clone(),
+            columns: columns.clone(),
+            alias: alias.clone(),
+        },
+    }
+}
+
+fn anonymize_grouping_element(element: &GroupingElement, mode: AnonymizeMode) -> GroupingElement {
+    let list = |exprs: &[Expression]| exprs.iter().map(|expr| anonymize_expression(expr, mode)).collect();
+    match element {
+        GroupingElement::Expression(expr) => GroupingElement::Expression(anonymize_expression(expr, mode)),
+        GroupingElement::Rollup(exprs) => GroupingElement::Rollup(list(exprs)),
+        GroupingElement::Cube(exprs) => GroupingElement::Cube(list(exprs)),
+        GroupingElement::GroupingSets(sets) => GroupingElement::GroupingSets(anonymize_rows(sets, mode)),
+    }
+}
+
+fn anonymize_rows(rows: &[Vec<Expression>], mode: AnonymizeMode) -> Vec<Vec<Expression>> {
+    rows.iter().map(|row| row.iter().map(|value| anonymize_expression(value, mode)).collect()).collect()
+}
+
+/// Passwords are literals too.
+fn anonymize_role_options(options: &[RoleOption], mode: AnonymizeMode) -> Vec<RoleOption> {
+    options
+        .iter()
+        .map(|option| match option {
+            RoleOption::Password(expr) => RoleOption::Password(anonymize_expression(expr, mode)),
+            other => other.clone(),
+        })
+        .collect()
+}
+
+/// Parses a query and prints it back with its literals anonymized.
+pub fn anonymize_sql(sql: &str, mode: AnonymizeMode) -> Result<String, ParseError> {
+    Ok(anonymize(&parse_sql(sql)?, mode).to_string())
+}
+
+pub fn anonymize_expression(expr: &Expression, mode: AnonymizeMode) -> Expression {
+    match (expr, mode) {
+        (Expression::String(_) | Expression::PrefixedString { .. }, AnonymizeMode::Placeholders) => {
+            Expression::Placeholder(":string".to_string())
+        }
+        (Expression::Number(_) | Expression::Decimal(_), AnonymizeMode::Placeholders) => {
+            Expression::Placeholder(":number".to_string())
+        }
+        (Expression::String(s), AnonymizeMode::Hashed) => Expression::String(format!("h_{:016x}", fnv1a(s.as_bytes()))),
+        (Expression::PrefixedString { prefix, value }, AnonymizeMode::Hashed) => {
+            let hash = fnv1a(value.as_bytes());
+            let value = match prefix {
+                StringPrefix::Bit => format!("{:b}", hash as u32),
+                StringPrefix::Hex => format!("{:016X}", hash),
+                _ => format!("h_{:016x}", hash),
+            };
+            Expression::PrefixedString { prefix: *prefix, value }
+        }
+        // A typed literal is a string read as its type, and keeps the type
+        (Expression::TypedString { data_type, .. }, AnonymizeMode::Placeholders) => Expression::Cast {
+            expr: Box::new(Expression::Placeholder(":string".to_string())),
+            data_type: data_type.clone(),
+        },
+        (Expression::TypedString { data_type, value }, AnonymizeMode::Hashed) => Expression::TypedString {
+            data_type: data_type.clone(),
+            value: format!("h_{:016x}", fnv1a(value.as_bytes())),
+        },
+        (Expression::Number(n), AnonymizeMode::Hashed) => Expression::Number(fnv1a(&n.to_le_bytes()) % 1_000_000_000),
+        (Expression::Decimal(text), AnonymizeMode::Hashed) => {
+            Expression::Decimal(format!("{}.0", fnv1a(text.as_bytes()) % 1_000_000_000))
+        }
+        (Expression::UnaryOperation { operator, operand }, _) => Expression::UnaryOperation {
+            operator: operator.clone(),
+            operand: Box::new(anonymize_expression(operand, mode)),
+        },
+        (Expression::BinaryOperation { left_operand, operator, right_operand }, _) => Expression::BinaryOperation {
+            left_operand: Box::new(anonymize_expression(left_operand, mode)),
+            operator: operator.clone(),
+            right_operand: Box::new(anonymize_expression(right_operand, mode)),
+        },
+        (Expression::Grouped(inner), _) => Expression::Grouped(Box::new(anonymize_expression(inner, mode))),
+        (Expression::Collate { expr, collation }, _) => {
+            Expression::Collate { expr: Box::new(anonymize_expression(expr, mode)), collation: collation.clone() }
+        }
+        (Expression::Cast { expr, data_type }, _) => {
+            Expression::Cast { expr: Box::new(anonymize_expression(expr, mode)), data_type: data_type.clone() }
+        }
+        (Expression::Function { name, args, over }, _) => Expression::Function {
+            name: name.clone(),
+            args: args.iter().map(|arg| anonymize_expression(arg, mode)).collect(),
+            over: over.as_ref().map(|window| Box::new(window.map_expressions(|expr| anonymize_expression(expr, mode)))),
+        },
+        (other, _) => other.clone(),
+    }
+}
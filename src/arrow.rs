@@ -0,0 +1,1011 @@
+// arrow.rs — Apache Arrow record batches and the Arrow IPC file format
+// Author: Sreerag Devadasan
+//
+// Converts engine results to Arrow record batches and reads and writes
+// batches as Arrow IPC files (the format of `.arrow` and Feather v2
+// files), so the engine can exchange data with pyarrow, polars, DataFusion
+// and the rest of the Arrow ecosystem without depending on any of them.
+// The batches of a file can be registered with a `Database` as a
+// read-only table.
+//
+// Batches use the engine's column types: 64-bit integers, booleans, UTF-8
+// strings, 32-bit dates in days, 64-bit times and time-zone-less
+// timestamps in microseconds, and 128-bit decimals. Reading also accepts the narrower integer
+// types and the null type, converted to 64-bit integers, and dates, times
+// and timestamps in other units, converted to these; other types, dictionaries and
+// compressed bodies are rejected as unsupported. Parquet is not
+// supported: it needs a Thrift decoder, page encodings and compression
+// codecs, which don't fit a crate without dependencies.
+//
+// An IPC file is the magic `ARROW1`, a stream of messages (the schema,
+// the record batches, an end-of-stream marker), then a footer that
+// repeats the schema and locates each batch. A message is a FlatBuffers
+// header followed by a body of 8-byte-aligned buffers; each column has a
+// validity bitmap, then its values (strings as 32-bit offsets into their
+// bytes). The bitmaps are least significant bit first, like those of
+// `columnar::Bitmap`. The FlatBuffers builder and reader below only cover
+// what these headers need.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::ast::{Constraint, DBType, Ident, TableColumn};
+use crate::columnar::{Bitmap, Column, ColumnValues, ColumnarTable};
+use crate::datetime::{Date, Time, Timestamp};
+use crate::decimal::{Decimal, MAX_PRECISION};
+use crate::engine::{Database, EngineError, QueryResult};
+use crate::eval::Value;
+
+// === Errors ===
+
+/// Errors raised while converting, reading or writing Arrow data.
+#[derive(Debug)]
+pub enum ArrowError {
+    Io(io::Error),
+    /// The bytes aren't a valid Arrow IPC file.
+    Malformed(String),
+    /// Valid Arrow that uses a feature this module doesn't read.
+    Unsupported(String),
+    /// A result column holds values of different types.
+    MixedTypes(String),
+    /// A batch's schema differs from its file's.
+    SchemaMismatch,
+    Engine(EngineError),
+}
+
+impl fmt::Display for ArrowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArrowError::Io(e) => write!(f, "I/O error: {}", e),
+            ArrowError::Malformed(e) => write!(f, "Malformed Arrow file: {}", e),
+            ArrowError::Unsupported(feature) => write!(f, "Unsupported Arrow feature: {}", feature),
+            ArrowError::MixedTypes(column) => write!(f, "Column {} holds values of different types", column),
+            ArrowError::SchemaMismatch => write!(f, "A record batch's schema differs from the file's"),
+            ArrowError::Engine(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ArrowError {}
+
+impl From<io::Error> for ArrowError {
+    fn from(e: io::Error) -> Self {
+        ArrowError::Io(e)
+    }
+}
+
+impl From<EngineError> for ArrowError {
+    fn from(e: EngineError) -> Self {
+        ArrowError::Engine(e)
+    }
+}
+
+fn malformed(what: &str) -> ArrowError {
+    ArrowError::Malformed(what.to_string())
+}
+
+// === Record batches ===
+
+/// The Arrow types of engine columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataType {
+    Int64,
+    Boolean,
+    Utf8,
+    /// Days since 1970-01-01.
+    Date32,
+    /// Microseconds since midnight.
+    Time64,
+    /// Microseconds since 1970-01-01 00:00:00, without a time zone.
+    Timestamp,
+    /// A precision and a scale, as in DECIMAL(p, s).
+    Decimal128(u8, u8),
+}
+
+impl DataType {
+    /// Whether a column of this type can hold `values`: decimals must be
+    /// exact at its scale.
+    fn describes(self, values: &ColumnValues) -> bool {
+        match (self, values) {
+            (DataType::Decimal128(precision, scale), ColumnValues::Decimal(values)) => {
+                values.iter().all(|decimal| decimal.fit(precision, scale) == Some(*decimal))
+            }
+            (DataType::Int64, ColumnValues::Int(_))
+            | (DataType::Boolean, ColumnValues::Bool(_))
+            | (DataType::Utf8, ColumnValues::Text(_))
+            | (DataType::Date32, ColumnValues::Date(_))
+            | (DataType::Time64, ColumnValues::Time(_))
+            | (DataType::Timestamp, ColumnValues::Timestamp(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Strings have no length limit.
+    fn db_type(self) -> DBType {
+        match self {
+            DataType::Int64 => DBType::Int,
+            DataType::Boolean => DBType::Bool,
+            DataType::Utf8 => DBType::Varchar(usize::MAX),
+            DataType::Date32 => DBType::Date,
+            DataType::Time64 => DBType::Time,
+            DataType::Timestamp => DBType::Timestamp,
+            DataType::Decimal128(precision, scale) => DBType::Decimal(precision, scale),
+        }
+    }
+}
+
+/// One column of a schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub data_type: DataType,
+    pub nullable: bool,
+}
+
+/// Columns of equal length, described by a schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordBatch {
+    schema: Vec<Field>,
+    columns: ColumnarTable,
+}
+
+impl RecordBatch {
+    /// Pairs columns with the fields describing them.
+    pub fn new(schema: Vec<Field>, columns: ColumnarTable) -> Result<Self, ArrowError> {
+        let matching = schema.len() == columns.columns().len()
+            && schema.iter().zip(columns.columns()).all(|(field, column)| {
+                field.data_type.describes(&column.values)
+                    && (field.nullable || column.validity.count() == column.len())
+            });
+        if !matching {
+            return Err(ArrowError::SchemaMismatch);
+        }
+        Ok(Self { schema, columns })
+    }
+
+    /// The rows of a result as a batch. Each column gets the type of its
+    /// first non-NULL value, or Int64 if it has none; decimals must all
+    /// have the same scale.
+    pub fn from_result(result: &QueryResult) -> Result<Self, ArrowError> {
+        let mut schema = Vec::new();
+        for (i, name) in result.columns.iter().enumerate() {
+            let mut data_type = None;
+            for row in &result.rows {
+                let found = match row[i] {
+                    Value::Null => continue,
+                    Value::Int(_) => DataType::Int64,
+                    Value::Bool(_) => DataType::Boolean,
+                    Value::String(_) => DataType::Utf8,
+                    Value::Date(_) => DataType::Date32,
+                    Value::Time(_) => DataType::Time64,
+                    Value::Timestamp(_) => DataType::Timestamp,
+                    Value::Decimal(decimal) => DataType::Decimal128(MAX_PRECISION, decimal.scale()),
+                };
+                if *data_type.get_or_insert(found) != found {
+                    return Err(ArrowError::MixedTypes(name.clone()));
+                }
+            }
+            schema.push(Field { name: name.clone(), data_type: data_type.unwrap_or(DataType::Int64), nullable: true });
+        }
+        let types: Vec<DBType> = schema.iter().map(|field| field.data_type.db_type()).collect();
+        let columns = ColumnarTable::from_rows(&types, &result.rows).map_err(|e| ArrowError::Malformed(e.to_string()))?;
+        Ok(Self { schema, columns })
+    }
+
+    pub fn schema(&self) -> &[Field] {
+        &self.schema
+    }
+
+    pub fn columns(&self) -> &ColumnarTable {
+        &self.columns
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// The batch as engine result rows.
+    pub fn to_result(&self) -> QueryResult {
+        QueryResult {
+            columns: self.schema.iter().map(|field| field.name.clone()).collect(),
+            rows: self.columns.rows(None),
+        }
+    }
+}
+
+// === IPC files ===
+
+const MAGIC: &[u8; 6] = b"ARROW1";
+const CONTINUATION: u32 = 0xFFFF_FFFF;
+const METADATA_V5: i16 = 4;
+
+// Members of the MessageHeader union
+const HEADER_SCHEMA: u8 = 1;
+const HEADER_DICTIONARY_BATCH: u8 = 2;
+const HEADER_RECORD_BATCH: u8 = 3;
+
+// Members of the Type union
+const TYPE_NULL: u8 = 1;
+const TYPE_INT: u8 = 2;
+const TYPE_UTF8: u8 = 5;
+const TYPE_BOOL: u8 = 6;
+const TYPE_DECIMAL: u8 = 7;
+const TYPE_DATE: u8 = 8;
+const TYPE_TIME: u8 = 9;
+const TYPE_TIMESTAMP: u8 = 10;
+
+// Members of the DateUnit and TimeUnit enums
+const DATE_DAY: i16 = 0;
+const DATE_MILLISECOND: i16 = 1;
+const TIME_SECOND: i16 = 0;
+const TIME_MILLISECOND: i16 = 1;
+const TIME_MICROSECOND: i16 = 2;
+
+/// A schema and the record batches stored under it, as in an IPC file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IpcFile {
+    schema: Vec<Field>,
+    batches: Vec<RecordBatch>,
+}
+
+impl IpcFile {
+    /// The batches must all have `schema`.
+    pub fn new(schema: Vec<Field>, batches: Vec<RecordBatch>) -> Result<Self, ArrowError> {
+        if batches.iter().any(|batch| batch.schema != schema) {
+            return Err(ArrowError::SchemaMismatch);
+        }
+        Ok(Self { schema, batches })
+    }
+
+    /// A file holding a result as one batch.
+    pub fn from_result(result: &QueryResult) -> Result<Self, ArrowError> {
+        let batch = RecordBatch::from_result(result)?;
+        Ok(Self { schema: batch.schema.clone(), batches: vec![batch] })
+    }
+
+    pub fn schema(&self) -> &[Field] {
+        &self.schema
+    }
+
+    pub fn batches(&self) -> &[RecordBatch] {
+        &self.batches
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, ArrowError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), ArrowError> {
+        Ok(fs::write(path, self.to_bytes()?)?)
+    }
+
+    /// Registers the rows of every batch with `database` as the read-only
+    /// table `name`.
+    pub fn register(&self, database: &mut Database, name: &str) -> Result<(), ArrowError> {
+        let columns = self
+            .schema
+            .iter()
+            .map(|field| TableColumn {
+                column_name: Ident::new(field.name.as_str()),
+                column_type: field.data_type.db_type(),
+                constraints: if field.nullable { Vec::new() } else { vec![Constraint::NotNull] },
+            })
+            .collect();
+        let types: Vec<DBType> = self.schema.iter().map(|field| field.data_type.db_type()).collect();
+        let mut data = ColumnarTable::new(&types);
+        for batch in &self.batches {
+            for row in batch.columns.rows(None) {
+                data.push_row(&row).map_err(|e| ArrowError::Malformed(e.to_string()))?;
+            }
+        }
+        Ok(database.register(name, columns, data)?)
+    }
+
+    /// Reads a file through its footer.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ArrowError> {
+        if bytes.len() < 18 || &bytes[..6] != MAGIC || &bytes[bytes.len() - 6..] != MAGIC {
+            return Err(malformed("not an Arrow IPC file"));
+        }
+        let footer_end = bytes.len() - 10;
+        let footer_length = usize::try_from(i32_at(bytes, footer_end)?).map_err(|_| malformed("footer length"))?;
+        let footer_start = footer_end.checked_sub(footer_length).ok_or_else(|| malformed("footer length"))?;
+        let footer = Table::root(&bytes[footer_start..footer_end])?;
+
+        let fields = read_schema(&footer.table(1)?.ok_or_else(|| malformed("footer without a schema"))?)?;
+        if footer.vector(2)?.is_some_and(|(_, count)| count > 0) {
+            return Err(ArrowError::Unsupported("dictionary-encoded columns".to_string()));
+        }
+        let mut batches = Vec::new();
+        if let Some((start, count)) = footer.vector(3)? {
+            for i in 0..count {
+                // Block: offset (long), metaDataLength (int), padding, bodyLength (long)
+                let block = footer.bytes;
+                let offset = to_usize(i64_at(block, start + 24 * i)?)?;
+                let metadata_length = to_usize(i64::from(i32_at(block, start + 24 * i + 8)?))?;
+                let body_length = to_usize(i64_at(block, start + 24 * i + 16)?)?;
+                let body_start = offset.checked_add(metadata_length).ok_or_else(|| malformed("block offset"))?;
+
+                let message = read_message(bytes, offset)?;
+                match message.u8(1)? {
+                    Some(HEADER_RECORD_BATCH) => {}
+                    Some(HEADER_DICTIONARY_BATCH) => {
+                        return Err(ArrowError::Unsupported("dictionary-encoded columns".to_string()))
+                    }
+                    _ => return Err(malformed("a block that isn't a record batch")),
+                }
+                let header = message.table(2)?.ok_or_else(|| malformed("message without a header"))?;
+                let body = slice(bytes, body_start, body_length)?;
+                batches.push(read_batch(&header, body, &fields)?);
+            }
+        }
+        Ok(Self { schema: fields.into_iter().map(|(field, _)| field).collect(), batches })
+    }
+
+    /// The file's bytes.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ArrowError> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend([0, 0]);
+
+        let mut builder = Builder::default();
+        let schema = schema_table(&mut builder, &self.schema);
+        let message = message_table(&mut builder, HEADER_SCHEMA, schema, 0);
+        write_message(&mut bytes, &builder.finish(message), &[]);
+
+        let mut blocks = Vec::new();
+        for batch in &self.batches {
+            let mut builder = Builder::default();
+            let (header, body) = batch_table(&mut builder, batch)?;
+            let message = message_table(&mut builder, HEADER_RECORD_BATCH, header, body.len());
+            blocks.push(write_message(&mut bytes, &builder.finish(message), &body));
+        }
+        // End of stream
+        bytes.extend(CONTINUATION.to_le_bytes());
+        bytes.extend(0u32.to_le_bytes());
+
+        let mut builder = Builder::default();
+        let schema = schema_table(&mut builder, &self.schema);
+        let dictionaries = builder.structs(&[], 8);
+        let blocks: Vec<Vec<u8>> = blocks
+            .into_iter()
+            .map(|(offset, metadata_length, body_length)| {
+                let mut block = Vec::with_capacity(24);
+                block.extend((offset as i64).to_le_bytes());
+                block.extend((metadata_length as i32).to_le_bytes());
+                block.extend([0; 4]);
+                block.extend((body_length as i64).to_le_bytes());
+                block
+            })
+            .collect();
+        let blocks = builder.structs(&blocks, 8);
+        let footer = builder.table(&[
+            Some(Slot::I16(METADATA_V5)),
+            Some(Slot::Ref(schema)),
+            Some(Slot::Ref(dictionaries)),
+            Some(Slot::Ref(blocks)),
+        ]);
+        let footer = builder.finish(footer);
+        bytes.extend(&footer);
+        bytes.extend((footer.len() as i32).to_le_bytes());
+        bytes.extend(MAGIC);
+        Ok(bytes)
+    }
+}
+
+/// Appends a message, returning its offset, metadata length and body
+/// length for the footer.
+fn write_message(bytes: &mut Vec<u8>, metadata: &[u8], body: &[u8]) -> (usize, usize, usize) {
+    let offset = bytes.len();
+    bytes.extend(CONTINUATION.to_le_bytes());
+    bytes.extend((metadata.len() as i32).to_le_bytes());
+    bytes.extend(metadata);
+    bytes.extend(body);
+    (offset, 8 + metadata.len(), body.len())
+}
+
+fn message_table(builder: &mut Builder, header_type: u8, header: Ref, body_length: usize) -> Ref {
+    builder.table(&[
+        Some(Slot::I16(METADATA_V5)),
+        Some(Slot::U8(header_type)),
+        Some(Slot::Ref(header)),
+        Some(Slot::I64(body_length as i64)),
+    ])
+}
+
+fn schema_table(builder: &mut Builder, schema: &[Field]) -> Ref {
+    let mut fields = Vec::new();
+    for field in schema {
+        let name = builder.string(&field.name);
+        let (type_type, type_table) = match field.data_type {
+            DataType::Int64 => (TYPE_INT, builder.table(&[Some(Slot::I32(64)), Some(Slot::Bool(true))])),
+            DataType::Boolean => (TYPE_BOOL, builder.table(&[])),
+            DataType::Utf8 => (TYPE_UTF8, builder.table(&[])),
+            DataType::Date32 => (TYPE_DATE, builder.table(&[Some(Slot::I16(DATE_DAY))])),
+            DataType::Time64 => (TYPE_TIME, builder.table(&[Some(Slot::I16(TIME_MICROSECOND)), Some(Slot::I32(64))])),
+            DataType::Timestamp => (TYPE_TIMESTAMP, builder.table(&[Some(Slot::I16(TIME_MICROSECOND))])),
+            DataType::Decimal128(precision, scale) => {
+                let (precision, scale) = (i32::from(precision), i32::from(scale));
+                let fields = [Some(Slot::I32(precision)), Some(Slot::I32(scale)), Some(Slot::I32(128))];
+                (TYPE_DECIMAL, builder.table(&fields))
+            }
+        };
+        let children = builder.refs(&[]);
+        fields.push(builder.table(&[
+            Some(Slot::Ref(name)),
+            Some(Slot::Bool(field.nullable)),
+            Some(Slot::U8(type_type)),
+            Some(Slot::Ref(type_table)),
+            None,
+            Some(Slot::Ref(children)),
+        ]));
+    }
+    let fields = builder.refs(&fields);
+    // Little-endian
+    builder.table(&[Some(Slot::I16(0)), Some(Slot::Ref(fields))])
+}
+
+/// The RecordBatch header of a batch, and its body.
+fn batch_table(builder: &mut Builder, batch: &RecordBatch) -> Result<(Ref, Vec<u8>), ArrowError> {
+    let mut body = Vec::new();
+    let mut buffers: Vec<Vec<u8>> = Vec::new();
+    let mut add = |body: &mut Vec<u8>, bytes: &[u8]| {
+        buffers.push(pair(body.len(), bytes.len()));
+        body.extend(bytes);
+        body.resize(body.len().next_multiple_of(8), 0);
+    };
+    let mut nodes = Vec::new();
+    for (field, column) in batch.schema.iter().zip(batch.columns.columns()) {
+        let nulls = column.len() - column.validity.count();
+        nodes.push(pair(column.len(), nulls));
+        // A column without NULLs can leave out its validity bitmap
+        add(&mut body, &if nulls > 0 { bitmap_bytes(&column.validity) } else { Vec::new() });
+        match &column.values {
+            ColumnValues::Int(values) => add(&mut body, &values.iter().flat_map(|n| n.to_le_bytes()).collect::<Vec<_>>()),
+            ColumnValues::Bool(values) => add(&mut body, &bitmap_bytes(&Bitmap::from_fn(values.len(), |i| values[i]))),
+            ColumnValues::Text(values) => {
+                let mut offsets = vec![0u8; 4];
+                let mut data = Vec::new();
+                for value in values {
+                    data.extend(value.as_bytes());
+                    let offset = i32::try_from(data.len())
+                        .map_err(|_| ArrowError::Unsupported("string columns over 2 GiB".to_string()))?;
+                    offsets.extend(offset.to_le_bytes());
+                }
+                add(&mut body, &offsets);
+                add(&mut body, &data);
+            }
+            ColumnValues::Date(values) => {
+                add(&mut body, &values.iter().flat_map(|date| date.days().to_le_bytes()).collect::<Vec<_>>())
+            }
+            ColumnValues::Time(values) => {
+                add(&mut body, &values.iter().flat_map(|time| time.micros().to_le_bytes()).collect::<Vec<_>>())
+            }
+            ColumnValues::Timestamp(values) => {
+                add(&mut body, &values.iter().flat_map(|at| at.micros().to_le_bytes()).collect::<Vec<_>>())
+            }
+            // The mantissas at the field's scale, which `RecordBatch::new` checked they have
+            ColumnValues::Decimal(values) => {
+                let DataType::Decimal128(_, scale) = field.data_type else {
+                    return Err(ArrowError::SchemaMismatch);
+                };
+                let mantissa = |decimal: &Decimal| decimal.rescale(scale).unwrap_or_default().mantissa();
+                add(&mut body, &values.iter().flat_map(|decimal| mantissa(decimal).to_le_bytes()).collect::<Vec<_>>())
+            }
+        }
+    }
+    let nodes = builder.structs(&nodes, 8);
+    let buffers = builder.structs(&buffers, 8);
+    let header = builder.table(&[
+        Some(Slot::I64(batch.num_rows() as i64)),
+        Some(Slot::Ref(nodes)),
+        Some(Slot::Ref(buffers)),
+    ]);
+    Ok((header, body))
+}
+
+/// A FieldNode or Buffer struct: two longs.
+fn pair(first: usize, second: usize) -> Vec<u8> {
+    let mut bytes = (first as i64).to_le_bytes().to_vec();
+    bytes.extend((second as i64).to_le_bytes());
+    bytes
+}
+
+fn bitmap_bytes(bitmap: &Bitmap) -> Vec<u8> {
+    (0..bitmap.len().div_ceil(8))
+        .map(|byte| (0..8).fold(0u8, |bits, bit| bits | (u8::from(bitmap.get(byte * 8 + bit)) << bit)))
+        .collect()
+}
+
+// === Reading messages ===
+
+/// How a file stores a column, which can be narrower than its `DataType`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Physical {
+    Null,
+    Int { bytes: usize, signed: bool },
+    Bool,
+    Utf8,
+    /// Signed values of `bytes` bytes in `unit`s of the type's unit enum.
+    Temporal { data_type: DataType, bytes: usize, unit: i16 },
+    /// 128-bit mantissas.
+    Decimal { precision: u8, scale: u8 },
+}
+
+/// The Message table of the message at `offset`.
+fn read_message(bytes: &[u8], offset: usize) -> Result<Table<'_>, ArrowError> {
+    let mut position = offset;
+    let mut length = u32_at(bytes, position)?;
+    position += 4;
+    // Files from before the continuation marker start with the length
+    if length == CONTINUATION {
+        length = u32_at(bytes, position)?;
+        position += 4;
+    }
+    Table::root(slice(bytes, position, length as usize)?)
+}
+
+fn read_schema(schema: &Table<'_>) -> Result<Vec<(Field, Physical)>, ArrowError> {
+    if schema.i16(0)?.unwrap_or(0) != 0 {
+        return Err(ArrowError::Unsupported("big-endian data".to_string()));
+    }
+    let mut fields = Vec::new();
+    for field in schema.tables(1)? {
+        let name = field.string(0)?.unwrap_or_default().to_string();
+        if field.target(4)?.is_some() {
+            return Err(ArrowError::Unsupported(format!("dictionary-encoded column {}", name)));
+        }
+        let type_table = field.table(3)?;
+        let physical = match field.u8(2)?.unwrap_or(0) {
+            TYPE_NULL => Physical::Null,
+            TYPE_BOOL => Physical::Bool,
+            TYPE_UTF8 => Physical::Utf8,
+            TYPE_INT => {
+                let int = type_table.ok_or_else(|| malformed("Int type without a table"))?;
+                let width = int.i32(0)?.unwrap_or(0);
+                let signed = int.u8(1)?.unwrap_or(0) != 0;
+                match width {
+                    8 | 16 | 32 => Physical::Int { bytes: width as usize / 8, signed },
+                    64 if signed => Physical::Int { bytes: 8, signed },
+                    _ => return Err(ArrowError::Unsupported(format!("{}-bit integer column {}", width, name))),
+                }
+            }
+            TYPE_DATE => {
+                let unit = type_table.map(|date| date.i16(0)).transpose()?.flatten().unwrap_or(DATE_MILLISECOND);
+                let bytes = if unit == DATE_DAY { 4 } else { 8 };
+                Physical::Temporal { data_type: DataType::Date32, bytes, unit }
+            }
+            TYPE_TIME => {
+                let time = type_table.ok_or_else(|| malformed("Time type without a table"))?;
+                let unit = time.i16(0)?.unwrap_or(TIME_MILLISECOND);
+                let bytes = match time.i32(1)?.unwrap_or(32) {
+                    32 => 4,
+                    64 => 8,
+                    width => return Err(ArrowError::Unsupported(format!("{}-bit time column {}", width, name))),
+                };
+                Physical::Temporal { data_type: DataType::Time64, bytes, unit }
+            }
+            TYPE_TIMESTAMP => {
+                let timestamp = type_table.ok_or_else(|| malformed("Timestamp type without a table"))?;
+                if timestamp.target(1)?.is_some() {
+                    return Err(ArrowError::Unsupported(format!("time zone of timestamp column {}", name)));
+                }
+                let unit = timestamp.i16(0)?.unwrap_or(TIME_SECOND);
+                Physical::Temporal { data_type: DataType::Timestamp, bytes: 8, unit }
+            }
+            TYPE_DECIMAL => {
+                let decimal = type_table.ok_or_else(|| malformed("Decimal type without a table"))?;
+                let width = decimal.i32(2)?.unwrap_or(128);
+                let precision = decimal.i32(0)?.unwrap_or(0);
+                let scale = decimal.i32(1)?.unwrap_or(0);
+                match (u8::try_from(precision), u8::try_from(scale)) {
+                    (Ok(precision), Ok(scale))
+                        if width == 128 && (1..=MAX_PRECISION).contains(&precision) && scale <= precision =>
+                    {
+                        Physical::Decimal { precision, scale }
+                    }
+                    _ => {
+                        let message = format!("{}-bit decimal({}, {}) column {}", width, precision, scale, name);
+                        return Err(ArrowError::Unsupported(message));
+                    }
+                }
+            }
+            other => return Err(ArrowError::Unsupported(format!("type {} of column {}", other, name))),
+        };
+        let data_type = match physical {
+            Physical::Null | Physical::Int { .. } => DataType::Int64,
+            Physical::Bool => DataType::Boolean,
+            Physical::Utf8 => DataType::Utf8,
+            Physical::Temporal { data_type, .. } => data_type,
+            Physical::Decimal { precision, scale } => DataType::Decimal128(precision, scale),
+        };
+        let nullable = field.u8(1)?.unwrap_or(0) != 0 || physical == Physical::Null;
+        fields.push((Field { name, data_type, nullable }, physical));
+    }
+    Ok(fields)
+}
+
+fn read_batch(header: &Table<'_>, body: &[u8], fields: &[(Field, Physical)]) -> Result<RecordBatch, ArrowError> {
+    if header.target(3)?.is_some() {
+        return Err(ArrowError::Unsupported("compressed record batches".to_string()));
+    }
+    let rows = to_usize(header.i64(0)?.unwrap_or(0))?;
+    let pairs = |id: usize| -> Result<Vec<(usize, usize)>, ArrowError> {
+        let Some((start, count)) = header.vector(id)? else { return Ok(Vec::new()) };
+        (0..count)
+            .map(|i| Ok((to_usize(i64_at(header.bytes, start + 16 * i)?)?, to_usize(i64_at(header.bytes, start + 16 * i + 8)?)?)))
+            .collect()
+    };
+    let nodes = pairs(1)?;
+    let mut buffers = pairs(2)?.into_iter();
+    let mut buffer = || -> Result<&[u8], ArrowError> {
+        let (offset, length) = buffers.next().ok_or_else(|| malformed("too few buffers"))?;
+        slice(body, offset, length)
+    };
+
+    let mut columns = Vec::new();
+    for (i, (_, physical)) in fields.iter().enumerate() {
+        let &(length, _) = nodes.get(i).ok_or_else(|| malformed("too few field nodes"))?;
+        if length != rows {
+            return Err(malformed("a column of the wrong length"));
+        }
+        let validity = match physical {
+            Physical::Null => Bitmap::new(rows, false),
+            _ => match buffer()? {
+                [] => Bitmap::new(rows, true),
+                bits => read_bitmap(bits, rows)?,
+            },
+        };
+        let values = match *physical {
+            Physical::Null => ColumnValues::Int(vec![0; rows]),
+            Physical::Int { bytes, signed } => {
+                let data = buffer()?;
+                if data.len() < rows * bytes {
+                    return Err(malformed("a short integer buffer"));
+                }
+                ColumnValues::Int(
+                    (0..rows)
+                        .map(|row| {
+                            let value = &data[row * bytes..(row + 1) * bytes];
+                            // Sign- or zero-extend to 64 bits
+                            let fill = if signed && value[bytes - 1] & 0x80 != 0 { 0xFF } else { 0 };
+                            let mut wide = [fill; 8];
+                            wide[..bytes].copy_from_slice(value);
+                            if validity.get(row) {
+                                i64::from_le_bytes(wide)
+                            } else {
+                                0
+                            }
+                        })
+                        .collect(),
+                )
+            }
+            Physical::Bool => {
+                let bits = read_bitmap(buffer()?, rows)?;
+                ColumnValues::Bool((0..rows).map(|row| bits.get(row) && validity.get(row)).collect())
+            }
+            Physical::Utf8 => {
+                let (offsets, data) = (buffer()?, buffer()?);
+                if offsets.len() < (rows + 1) * 4 {
+                    return Err(malformed("a short offset buffer"));
+                }
+                let offset = |row: usize| to_usize(i64::from(i32_at(offsets, row * 4)?));
+                let mut values = Vec::with_capacity(rows);
+                for row in 0..rows {
+                    let (start, end) = (offset(row)?, offset(row + 1)?);
+                    let bytes = data.get(start..end).ok_or_else(|| malformed("a string outside its buffer"))?;
+                    let value = std::str::from_utf8(bytes).map_err(|_| malformed("a string that isn't UTF-8"))?;
+                    values.push(if validity.get(row) { value.to_string() } else { String::new() });
+                }
+                ColumnValues::Text(values)
+            }
+            Physical::Temporal { data_type, bytes, unit } => {
+                let data = buffer()?;
+                if data.len() < rows * bytes {
+                    return Err(malformed("a short date or time buffer"));
+                }
+                let raw = |row: usize| {
+                    let value = &data[row * bytes..(row + 1) * bytes];
+                    if bytes == 4 {
+                        i64::from(i32::from_le_bytes(value.try_into().unwrap_or_default()))
+                    } else {
+                        i64::from_le_bytes(value.try_into().unwrap_or_default())
+                    }
+                };
+                let out_of_range = || malformed("a date or time out of range");
+                let valid_rows = (0..rows).map(|row| (validity.get(row), raw(row)));
+                match data_type {
+                    DataType::Date32 => ColumnValues::Date(
+                        valid_rows
+                            .map(|(valid, n)| match (valid, unit) {
+                                (false, _) => Ok(Date::default()),
+                                (_, DATE_DAY) => Date::from_days(n).ok_or_else(out_of_range),
+                                _ => Date::from_days(n.div_euclid(86_400_000)).ok_or_else(out_of_range),
+                            })
+                            .collect::<Result<_, _>>()?,
+                    ),
+                    DataType::Time64 => ColumnValues::Time(
+                        valid_rows
+                            .map(|(valid, n)| match valid {
+                                false => Ok(Time::default()),
+                                true => micros(n, unit).and_then(Time::from_micros).ok_or_else(out_of_range),
+                            })
+                            .collect::<Result<_, _>>()?,
+                    ),
+                    _ => ColumnValues::Timestamp(
+                        valid_rows
+                            .map(|(valid, n)| match valid {
+                                false => Ok(Timestamp::default()),
+                                true => micros(n, unit).and_then(Timestamp::from_micros).ok_or_else(out_of_range),
+                            })
+                            .collect::<Result<_, _>>()?,
+                    ),
+                }
+            }
+            Physical::Decimal { precision, scale } => {
+                let data = buffer()?;
+                if data.len() < rows * 16 {
+                    return Err(malformed("a short decimal buffer"));
+                }
+                let decimal = |row: usize| {
+                    let mantissa = i128::from_le_bytes(data[row * 16..(row + 1) * 16].try_into().unwrap_or_default());
+                    Decimal::new(mantissa, scale)
+                        .filter(|decimal| decimal.precision() <= precision)
+                        .ok_or_else(|| malformed("a decimal out of range"))
+                };
+                ColumnValues::Decimal(
+                    (0..rows)
+                        .map(|row| if validity.get(row) { decimal(row) } else { Ok(Decimal::default()) })
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+        };
+        columns.push(Column { values, validity });
+    }
+    let columns = ColumnarTable::from_columns(columns).map_err(|e| ArrowError::Malformed(e.to_string()))?;
+    Ok(RecordBatch { schema: fields.iter().map(|(field, _)| field.clone()).collect(), columns })
+}
+
+/// A count of `unit`s of a TimeUnit in microseconds.
+fn micros(value: i64, unit: i16) -> Option<i64> {
+    match unit {
+        TIME_SECOND => value.checked_mul(1_000_000),
+        TIME_MILLISECOND => value.checked_mul(1_000),
+        TIME_MICROSECOND => Some(value),
+        _ => Some(value.div_euclid(1_000)),
+    }
+}
+
+fn read_bitmap(bytes: &[u8], len: usize) -> Result<Bitmap, ArrowError> {
+    if bytes.len() < len.div_ceil(8) {
+        return Err(malformed("a short bitmap"));
+    }
+    Ok(Bitmap::from_fn(len, |i| (bytes[i / 8] >> (i % 8)) & 1 == 1))
+}
+
+// === FlatBuffers ===
+
+/// A reference to an object written by a `Builder`: its distance from
+/// the end of the buffer.
+#[derive(Debug, Clone, Copy)]
+struct Ref(usize);
+
+/// A field of a table written by a `Builder`.
+enum Slot {
+    U8(u8),
+    Bool(bool),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    Ref(Ref),
+}
+
+/// Builds a FlatBuffer back to front, the way the format is laid out: an
+/// object is written before (that is, after, in the final buffer) the
+/// objects referring to it. Every object is aligned relative to the end,
+/// and `finish` makes the length a multiple of 8, so they end up aligned
+/// from the start too.
+#[derive(Debug, Default)]
+struct Builder {
+    bytes: Vec<u8>,
+}
+
+impl Builder {
+    fn prepend(&mut self, bytes: &[u8]) {
+        self.bytes.splice(0..0, bytes.iter().copied());
+    }
+
+    /// Pads the front so that `size` more bytes end `alignment`-aligned.
+    fn align(&mut self, size: usize, alignment: usize) {
+        let padding = (alignment - (self.bytes.len() + size) % alignment) % alignment;
+        self.prepend(&vec![0; padding]);
+    }
+
+    /// Prepends an offset to `target`, relative to where the offset goes.
+    fn prepend_ref(&mut self, target: Ref) {
+        self.align(4, 4);
+        let offset = (self.bytes.len() + 4 - target.0) as u32;
+        self.prepend(&offset.to_le_bytes());
+    }
+
+    fn string(&mut self, s: &str) -> Ref {
+        self.align(4 + s.len() + 1, 4);
+        self.prepend(&[0]);
+        self.prepend(s.as_bytes());
+        self.prepend(&(s.len() as u32).to_le_bytes());
+        Ref(self.bytes.len())
+    }
+
+    fn refs(&mut self, items: &[Ref]) -> Ref {
+        self.align(4 + 4 * items.len(), 4);
+        for &item in items.iter().rev() {
+            self.prepend_ref(item);
+        }
+        self.prepend(&(items.len() as u32).to_le_bytes());
+        Ref(self.bytes.len())
+    }
+
+    /// A vector of structs, given as their bytes.
+    fn structs(&mut self, items: &[Vec<u8>], alignment: usize) -> Ref {
+        self.align(items.iter().map(Vec::len).sum(), alignment);
+        for item in items.iter().rev() {
+            self.prepend(item);
+        }
+        self.prepend(&(items.len() as u32).to_le_bytes());
+        Ref(self.bytes.len())
+    }
+
+    /// A table with `fields[i]` as field `i`; `None` leaves it out.
+    fn table(&mut self, fields: &[Option<Slot>]) -> Ref {
+        let end = self.bytes.len();
+        // Where each field starts, as a distance from the end
+        let mut positions = vec![0; fields.len()];
+        for (i, field) in fields.iter().enumerate().rev() {
+            match field {
+                None => continue,
+                Some(Slot::U8(value)) => self.prepend(&[*value]),
+                Some(Slot::Bool(value)) => self.prepend(&[u8::from(*value)]),
+                Some(Slot::I16(value)) => {
+                    self.align(2, 2);
+                    self.prepend(&value.to_le_bytes());
+                }
+                Some(Slot::I32(value)) => {
+                    self.align(4, 4);
+                    self.prepend(&value.to_le_bytes());
+                }
+                Some(Slot::I64(value)) => {
+                    self.align(8, 8);
+                    self.prepend(&value.to_le_bytes());
+                }
+                Some(Slot::Ref(target)) => self.prepend_ref(*target),
+            }
+            positions[i] = self.bytes.len();
+        }
+        // The table starts with a signed offset back to its vtable, which
+        // lists the size of the table and the place of each field in it
+        self.align(4, 4);
+        self.prepend(&[0; 4]);
+        let start = self.bytes.len();
+        let mut vtable = Vec::new();
+        vtable.extend(((4 + 2 * fields.len()) as u16).to_le_bytes());
+        vtable.extend(((start - end) as u16).to_le_bytes());
+        for &position in &positions {
+            let offset = if position == 0 { 0 } else { start - position };
+            vtable.extend((offset as u16).to_le_bytes());
+        }
+        self.prepend(&vtable);
+        let at = self.bytes.len() - start;
+        self.bytes[at..at + 4].copy_from_slice(&(vtable.len() as i32).to_le_bytes());
+        Ref(start)
+    }
+
+    fn finish(mut self, root: Ref) -> Vec<u8> {
+        self.align(4, 8);
+        self.prepend_ref(root);
+        self.bytes
+    }
+}
+
+/// A table of a FlatBuffer, read with every access checked against the
+/// buffer's bounds.
+#[derive(Debug, Clone, Copy)]
+struct Table<'b> {
+    bytes: &'b [u8],
+    position: usize,
+    vtable: usize,
+}
+
+impl<'b> Table<'b> {
+    fn root(bytes: &'b [u8]) -> Result<Self, ArrowError> {
+        Self::at(bytes, u32_at(bytes, 0)? as usize)
+    }
+
+    fn at(bytes: &'b [u8], position: usize) -> Result<Self, ArrowError> {
+        let vtable = (position as i64)
+            .checked_sub(i64::from(i32_at(bytes, position)?))
+            .and_then(|vtable| usize::try_from(vtable).ok())
+            .ok_or_else(|| malformed("a vtable outside the buffer"))?;
+        u16_at(bytes, vtable)?;
+        Ok(Self { bytes, position, vtable })
+    }
+
+    /// Where field `id` is stored, if the table has it.
+    fn field(&self, id: usize) -> Result<Option<usize>, ArrowError> {
+        let vtable_length = u16_at(self.bytes, self.vtable)? as usize;
+        if 4 + 2 * id + 2 > vtable_length {
+            return Ok(None);
+        }
+        let offset = u16_at(self.bytes, self.vtable + 4 + 2 * id)? as usize;
+        Ok((offset != 0).then_some(self.position + offset))
+    }
+
+    fn u8(&self, id: usize) -> Result<Option<u8>, ArrowError> {
+        self.field(id)?.map(|at| slice(self.bytes, at, 1).map(|b| b[0])).transpose()
+    }
+
+    fn i16(&self, id: usize) -> Result<Option<i16>, ArrowError> {
+        self.field(id)?.map(|at| u16_at(self.bytes, at).map(|n| n as i16)).transpose()
+    }
+
+    fn i32(&self, id: usize) -> Result<Option<i32>, ArrowError> {
+        self.field(id)?.map(|at| i32_at(self.bytes, at)).transpose()
+    }
+
+    fn i64(&self, id: usize) -> Result<Option<i64>, ArrowError> {
+        self.field(id)?.map(|at| i64_at(self.bytes, at)).transpose()
+    }
+
+    /// Where the object that field `id` refers to starts.
+    fn target(&self, id: usize) -> Result<Option<usize>, ArrowError> {
+        self.field(id)?.map(|at| Ok(at + u32_at(self.bytes, at)? as usize)).transpose()
+    }
+
+    fn table(&self, id: usize) -> Result<Option<Table<'b>>, ArrowError> {
+        self.target(id)?.map(|at| Table::at(self.bytes, at)).transpose()
+    }
+
+    fn string(&self, id: usize) -> Result<Option<&'b str>, ArrowError> {
+        let Some((start, length)) = self.vector(id)? else { return Ok(None) };
+        let bytes = slice(self.bytes, start, length)?;
+        std::str::from_utf8(bytes).map(Some).map_err(|_| malformed("a name that isn't UTF-8"))
+    }
+
+    /// Where the elements of vector field `id` start, and how many there are.
+    fn vector(&self, id: usize) -> Result<Option<(usize, usize)>, ArrowError> {
+        self.target(id)?.map(|at| Ok((at + 4, u32_at(self.bytes, at)? as usize))).transpose()
+    }
+
+    fn tables(&self, id: usize) -> Result<Vec<Table<'b>>, ArrowError> {
+        let Some((start, count)) = self.vector(id)? else { return Ok(Vec::new()) };
+        (0..count)
+            .map(|i| {
+                let at = start + 4 * i;
+                Table::at(self.bytes, at + u32_at(self.bytes, at)? as usize)
+            })
+            .collect()
+    }
+}
+
+fn slice(bytes: &[u8], start: usize, length: usize) -> Result<&[u8], ArrowError> {
+    start.checked_add(length).and_then(|end| bytes.get(start..end)).ok_or_else(|| malformed("truncated"))
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Result<u16, ArrowError> {
+    Ok(u16::from_le_bytes(slice(bytes, at, 2)?.try_into().expect("two bytes")))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Result<u32, ArrowError> {
+    Ok(u32::from_le_bytes(slice(bytes, at, 4)?.try_into().expect("four bytes")))
+}
+
+fn i32_at(bytes: &[u8], at: usize) -> Result<i32, ArrowError> {
+    Ok(i32::from_le_bytes(slice(bytes, at, 4)?.try_into().expect("four bytes")))
+}
+
+fn i64_at(bytes: &[u8], at: usize) -> Result<i64, ArrowError> {
+    Ok(i64::from_le_bytes(slice(bytes, at, 8)?.try_into().expect("eight bytes")))
+}
+
+fn to_usize(n: i64) -> Result<usize, ArrowError> {
+    usize::try_from(n).map_err(|_| malformed("a negative length"))
+}
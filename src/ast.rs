@@ -1,11 +1,29 @@
 // SQL AST components for Rust SQL parser
 // Author: Sreerag Devadasan
+//
+// The enums are `#[non_exhaustive]` so that adding a statement, clause or
+// expression isn't a breaking change: code outside the crate matches them
+// with a wildcard arm, or walks them with the accessors below or a
+// `visit::Visitor`. Removing or renaming a variant or field is breaking,
+// and tests/ast_compat.rs fails to compile when that happens.
+
+use core::fmt;
+
+use crate::prelude::*;
+pub use crate::ident::{Ident, QuoteStyle};
+use crate::extension::Extension;
+use crate::span::Span;
 
 /// Represents an expression in SQL (e.g., identifiers, numbers, logical operations).
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum Expression {
-    Identifier(String),
+    Identifier(Ident),
+    /// A qualified name such as `t.id`, one entry per part.
+    CompoundIdentifier(Vec<Ident>),
     Number(u64),
+    /// A number with a fractional part, such as `9.99`, as written.
+    Decimal(String),
     String(String),
     UnaryOperation {
         operator: UnaryOperator,
@@ -19,10 +37,139 @@ pub enum Expression {
     Boolean(bool),
     Null,
     Grouped(Box<Expression>),
+    /// A bind parameter such as `:name`, kept as written.
+    Placeholder(String),
+    /// A function call such as `lower(name)`, or a window function when
+    /// `over` is set.
+    Function {
+        name: String,
+        args: Vec<Expression>,
+        over: Option<Box<WindowSpec>>,
+    },
+    /// A literal of a type written as a string, such as `DATE '2024-01-31'`.
+    TypedString {
+        data_type: DBType,
+        value: String,
+    },
+    /// `expr COLLATE name`: `expr` compared by the collation `name`.
+    Collate {
+        expr: Box<Expression>,
+        collation: Ident,
+    },
+    /// `CAST(expr AS type)`.
+    Cast {
+        expr: Box<Expression>,
+        data_type: DBType,
+    },
+    /// A string literal with a prefix, such as `N'text'` or `X'DEADBEEF'`.
+    /// The text of an `N` or `E` string is kept with its escapes resolved,
+    /// the digits of a `B` or `X` string as written.
+    PrefixedString {
+        prefix: StringPrefix,
+        value: String,
+    },
+}
+
+/// The letter before the quote of a prefixed string literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StringPrefix {
+    /// `N'...'`, national character text, as SQL Server writes strings.
+    National,
+    /// `E'...'`, text with backslash escapes such as `\n`, from Postgres.
+    Escape,
+    /// `B'1010'`, a string of bits.
+    Bit,
+    /// `X'DEADBEEF'`, a string of bytes as hex digits.
+    Hex,
+}
+
+impl StringPrefix {
+    /// The prefix for the letter `ch`, in either case.
+    pub fn from_letter(ch: char) -> Option<Self> {
+        match ch.to_ascii_uppercase() {
+            'N' => Some(StringPrefix::National),
+            'E' => Some(StringPrefix::Escape),
+            'B' => Some(StringPrefix::Bit),
+            'X' => Some(StringPrefix::Hex),
+            _ => None,
+        }
+    }
+
+    pub fn letter(&self) -> char {
+        match self {
+            StringPrefix::National => 'N',
+            StringPrefix::Escape => 'E',
+            StringPrefix::Bit => 'B',
+            StringPrefix::Hex => 'X',
+        }
+    }
+
+    /// Whether the string is text, rather than digits.
+    pub fn is_text(&self) -> bool {
+        matches!(self, StringPrefix::National | StringPrefix::Escape)
+    }
+}
+
+/// The `OVER (PARTITION BY ... ORDER BY ...)` of a window function.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WindowSpec {
+    pub partition_by: Vec<Expression>,
+    pub order_by: Vec<OrderByExpr>,
+}
+
+impl WindowSpec {
+    /// A copy of the window with `f` applied to each of its expressions.
+    pub fn map_expressions(&self, mut f: impl FnMut(&Expression) -> Expression) -> WindowSpec {
+        WindowSpec {
+            partition_by: self.partition_by.iter().map(&mut f).collect(),
+            order_by: self.order_by.iter().map(|item| OrderByExpr { expr: f(&item.expr), asc: item.asc }).collect(),
+        }
+    }
+}
+
+/// An ORDER BY item of a query or a window; `asc` is `None` when no
+/// direction was written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderByExpr {
+    pub expr: Expression,
+    pub asc: Option<bool>,
+}
+
+impl Expression {
+    /// Calls `visit` on this expression and every sub-expression, parents
+    /// before children and left operands before right ones.
+    pub fn walk<'a>(&'a self, visit: &mut impl FnMut(&'a Expression)) {
+        visit(self);
+        match self {
+            Expression::UnaryOperation { operand, .. } => operand.walk(visit),
+            Expression::BinaryOperation { left_operand, right_operand, .. } => {
+                left_operand.walk(visit);
+                right_operand.walk(visit);
+            }
+            Expression::Grouped(inner)
+            | Expression::Collate { expr: inner, .. }
+            | Expression::Cast { expr: inner, .. } => inner.walk(visit),
+            Expression::Function { args, over, .. } => {
+                for arg in args {
+                    arg.walk(visit);
+                }
+                if let Some(window) = over {
+                    for expr in &window.partition_by {
+                        expr.walk(visit);
+                    }
+                    for item in &window.order_by {
+                        item.expr.walk(visit);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
 /// Binary operators used in expressions (e.g., +, -, =, AND).
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum BinaryOperator {
     Equals,
     NotEquals,
@@ -36,40 +183,1159 @@ pub enum BinaryOperator {
     Subtract,
     Multiply,
     Divide,
+    Like,
+    /// Case-insensitive LIKE, in dialects that have it.
+    ILike,
+    /// `||`, joining two strings; MySQL reads `||` as OR instead.
+    Concat,
+    /// `a IS DISTINCT FROM b`, inequality that counts NULLs as equal to
+    /// each other and different from every other value.
+    IsDistinctFrom,
+    IsNotDistinctFrom,
 }
 
-/// Unary operators used in expressions (e.g., NOT, -).
+/// Unary operators used in expressions (e.g., NOT, -, IS NULL).
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum UnaryOperator {
     Not,
     Negate,
+    /// `expr IS NULL`, written after its operand.
+    IsNull,
+    IsNotNull,
+}
+
+impl UnaryOperator {
+    /// Whether the operator is written after its operand.
+    pub fn is_postfix(&self) -> bool {
+        matches!(self, UnaryOperator::IsNull | UnaryOperator::IsNotNull)
+    }
+}
+
+/// Column types supported in CREATE TABLE.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum DBType {
+    Int,
+    Varchar(usize),
+    Bool,
+    Date,
+    Time,
+    Timestamp,
+    /// DECIMAL(precision, scale): up to `precision` digits in all, `scale`
+    /// of them after the point.
+    Decimal(u8, u8),
+}
+
+/// Column constraints supported in CREATE TABLE.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Constraint {
+    NotNull,
+    PrimaryKey,
+    Check(Expression),
+    /// The collation the column's strings compare by.
+    Collate(Ident),
+}
+
+/// One parameter of a CREATE FUNCTION or CREATE PROCEDURE, optionally named.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionParameter {
+    pub name: Option<String>,
+    pub data_type: RoutineType,
+}
+
+/// The type of a routine's parameter or result. Routines name types that
+/// tables here can't have (`TEXT`, `SETOF INT`, `TRIGGER`, `public.money`),
+/// and those are kept as written.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RoutineType {
+    Known(DBType),
+    Named(String),
+}
+
+/// When a trigger fires relative to the statement that fires it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TriggerTiming {
+    Before,
+    After,
+    InsteadOf,
+}
+
+/// A kind of statement that fires a trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TriggerEvent {
+    Insert,
+    Update,
+    Delete,
+    Truncate,
+}
+
+/// What a trigger runs.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum TriggerBody {
+    /// EXECUTE FUNCTION|PROCEDURE name(arguments), as in Postgres.
+    Execute { procedure: bool, function: String, args: Vec<Expression> },
+    /// A MySQL `BEGIN ... END` block, kept as written and not parsed.
+    Block(String),
+    /// A single MySQL statement.
+    Statement(Box<Statement>),
+}
+
+/// The definition of one column in a CREATE TABLE statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableColumn {
+    pub column_name: Ident,
+    pub column_type: DBType,
+    pub constraints: Vec<Constraint>,
+}
+
+/// Privileges named in GRANT and REVOKE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Privilege {
+    All,
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Truncate,
+    References,
+    Trigger,
+    Usage,
+    Execute,
+}
+
+/// A privilege of GRANT or REVOKE, on only the listed columns of the table
+/// when there are any, as in UPDATE (a, b).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrantedPrivilege {
+    pub privilege: Privilege,
+    pub columns: Vec<Ident>,
+}
+
+/// What GRANT and REVOKE give privileges on: ON [TABLE] name, ON SEQUENCE
+/// name, ON SCHEMA name or ON FUNCTION name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GrantObject {
+    Table(Ident),
+    Sequence(Ident),
+    Schema(Ident),
+    Function(Ident),
+}
+
+/// Attributes set by CREATE ROLE and ALTER ROLE; `false` is the NO- form,
+/// e.g. NOLOGIN.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RoleOption {
+    Login(bool),
+    Superuser(bool),
+    CreateDb(bool),
+    CreateRole(bool),
+    /// A string literal, or NULL to remove the password.
+    Password(Expression),
+}
+
+/// The source of a MERGE: a table or a parenthesized SELECT.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum MergeSource {
+    Table(Ident),
+    Query(Box<Statement>),
+}
+
+/// An optimizer hint: one entry of a `/*+ ... */` comment after SELECT,
+/// e.g. `INDEX(t idx)`, or the MySQL STRAIGHT_JOIN modifier. Names are
+/// upper-cased; arguments are kept as written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl Hint {
+    /// Select modifiers printed as keywords rather than inside a comment.
+    pub const MODIFIERS: &'static [&'static str] = &["STRAIGHT_JOIN"];
+
+    /// Splits the text of a hint comment into hints: names, each with an
+    /// optional parenthesized list of arguments separated by spaces or
+    /// commas. Anything else is skipped.
+    pub fn parse_list(text: &str) -> Vec<Hint> {
+        let mut hints = Vec::new();
+        let mut chars = text.chars().peekable();
+        while let Some(&ch) = chars.peek() {
+            if !(ch.is_alphanumeric() || ch == '_') {
+                chars.next();
+                continue;
+            }
+            let mut name = String::new();
+            while let Some(ch) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                name.push(ch);
+            }
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            let mut args = Vec::new();
+            if chars.next_if_eq(&'(').is_some() {
+                let inside: String = chars.by_ref().take_while(|&c| c != ')').collect();
+                args = inside
+                    .split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|arg| !arg.is_empty())
+                    .map(String::from)
+                    .collect();
+            }
+            hints.push(Hint { name: name.to_uppercase(), args });
+        }
+        hints
+    }
+}
+
+/// `alias [(column, ...)]` after a FROM item; the columns rename the
+/// item's columns in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableAlias {
+    pub name: Ident,
+    pub columns: Vec<Ident>,
+}
+
+/// A FROM item of a SELECT: a table, a parenthesized SELECT or VALUES, or
+/// a table-valued function such as `generate_series(1, 10)`, possibly
+/// reshaped by PIVOT or UNPIVOT. LATERAL items may refer to the columns of
+/// the items before them.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum TableFactor {
+    Table {
+        name: Ident,
+        alias: Option<TableAlias>,
+        sample: Option<TableSample>,
+    },
+    /// `table AS OF VERSION n [alias]`: a table as it was at version `n`
+    /// of the engine's database (see engine.rs).
+    AsOf {
+        name: Ident,
+        version: u64,
+        alias: Option<TableAlias>,
+    },
+    Derived {
+        lateral: bool,
+        subquery: Box<Statement>,
+        alias: Option<TableAlias>,
+    },
+    Function {
+        lateral: bool,
+        name: String,
+        args: Vec<Expression>,
+        alias: Option<TableAlias>,
+    },
+    /// `table PIVOT (aggregate FOR column IN (value, ...))`: one output
+    /// column per listed value of `column`.
+    Pivot {
+        table: Box<TableFactor>,
+        aggregate: Expression,
+        column: Ident,
+        values: Vec<Expression>,
+        alias: Option<TableAlias>,
+    },
+    /// `table UNPIVOT (value FOR name IN (column, ...))`: one output row per
+    /// listed column, its name in `name` and its value in `value`.
+    Unpivot {
+        table: Box<TableFactor>,
+        value: Ident,
+        name: Ident,
+        columns: Vec<Ident>,
+        alias: Option<TableAlias>,
+    },
+}
+
+impl TableFactor {
+    /// The name of a plain table, or of the table under AS OF, PIVOT or
+    /// UNPIVOT; `None` for other items.
+    pub fn table_name(&self) -> Option<&str> {
+        match self {
+            TableFactor::Table { name, .. } | TableFactor::AsOf { name, .. } => Some(name),
+            TableFactor::Pivot { table, .. } | TableFactor::Unpivot { table, .. } => table.table_name(),
+            TableFactor::Derived { .. } | TableFactor::Function { .. } => None,
+        }
+    }
+
+    /// The expressions of a subquery, the function arguments, or those of
+    /// a PIVOT after the ones of the table it reshapes.
+    pub fn expressions(&self) -> Vec<&Expression> {
+        match self {
+            TableFactor::Table { sample, .. } => sample.iter().flat_map(TableSample::expressions).collect(),
+            TableFactor::AsOf { .. } => Vec::new(),
+            TableFactor::Derived { subquery, .. } => subquery.expressions(),
+            TableFactor::Function { args, .. } => args.iter().collect(),
+            TableFactor::Pivot { table, aggregate, values, .. } => {
+                let mut exprs = table.expressions();
+                exprs.push(aggregate);
+                exprs.extend(values);
+                exprs
+            }
+            TableFactor::Unpivot { table, .. } => table.expressions(),
+        }
+    }
+}
+
+/// TABLESAMPLE method (percent) [REPEATABLE (seed)]; the method name, e.g.
+/// SYSTEM or BERNOULLI, is upper-cased.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableSample {
+    pub method: String,
+    pub percent: Expression,
+    pub repeatable: Option<Expression>,
+}
+
+impl TableSample {
+    pub fn expressions(&self) -> Vec<&Expression> {
+        let mut exprs = vec![&self.percent];
+        exprs.extend(&self.repeatable);
+        exprs
+    }
+}
+
+/// The row lock taken by a FOR ... clause of a SELECT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LockStrength {
+    Update,
+    NoKeyUpdate,
+    Share,
+    KeyShare,
+}
+
+/// What a locking SELECT does when a row is already locked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LockWait {
+    NoWait,
+    SkipLocked,
+}
+
+/// FOR UPDATE|SHARE [OF table, ...] [NOWAIT|SKIP LOCKED]; an empty `of`
+/// locks the rows of every table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockingClause {
+    pub strength: LockStrength,
+    pub of: Vec<Ident>,
+    pub wait: Option<LockWait>,
+}
+
+/// UNION, INTERSECT or EXCEPT between two queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SetOperator {
+    Union,
+    Intersect,
+    Except,
+}
+
+/// One `name [(column, ...)] AS (query)` entry of a WITH clause.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cte {
+    pub name: Ident,
+    pub columns: Vec<Ident>,
+    pub query: Box<Statement>,
+}
+
+impl Cte {
+    /// True when the CTE's query reads the CTE itself, which is only
+    /// allowed under WITH RECURSIVE.
+    pub fn is_self_referencing(&self) -> bool {
+        self.query.references_table(&self.name)
+    }
+
+    /// The anchor and recursive members of a self-referencing CTE: the two
+    /// sides of its top-level UNION [ALL], when only the right side reads
+    /// the CTE.
+    pub fn recursive_parts(&self) -> Option<(&Statement, &Statement)> {
+        match &*self.query {
+            Statement::SetOperation { operator: SetOperator::Union, left, right, .. }
+                if !left.references_table(&self.name) && right.references_table(&self.name) =>
+            {
+                Some((left, right))
+            }
+            _ => None,
+        }
+    }
+
+    /// True when the recursive member has no WHERE clause, so nothing
+    /// stops the recursion apart from a LIMIT on the outer query. Always
+    /// false for CTEs that aren't recursive.
+    pub fn has_unbounded_recursion(&self) -> bool {
+        match self.recursive_parts() {
+            Some((_, Statement::Select { selection, .. })) => selection.is_none(),
+            Some(_) => true,
+            None => false,
+        }
+    }
+}
+
+/// How a join combines its rows. A comma in the FROM list is a CROSS join.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Right,
+    Full,
+    Cross,
+}
+
+/// One `JOIN relation [ON condition]` after the first FROM item; `on` is
+/// `None` only for CROSS joins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Join {
+    pub kind: JoinKind,
+    pub relation: TableFactor,
+    pub on: Option<Expression>,
+}
+
+/// One item of a select list: an expression and the name AS gives it, or
+/// `*`, kept as the identifier `*`, for every column of the FROM item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectItem {
+    pub expr: Expression,
+    pub alias: Option<Ident>,
+}
+
+impl SelectItem {
+    /// The item `*`.
+    pub fn wildcard() -> Self {
+        SelectItem { expr: Expression::Identifier(Ident::new("*")), alias: None }
+    }
+
+    /// The plain column `name`.
+    pub fn column(name: Ident) -> Self {
+        SelectItem { expr: Expression::Identifier(name), alias: None }
+    }
+
+    pub fn is_wildcard(&self) -> bool {
+        matches!(&self.expr, Expression::Identifier(name) if name == "*")
+    }
+
+    /// The column the item reads when it is only a column's name.
+    pub fn as_column(&self) -> Option<&Ident> {
+        match &self.expr {
+            Expression::Identifier(name) if !self.is_wildcard() => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The name of the item's column in the result: its alias, the name of
+    /// the column it reads, or else the expression as the generic dialect
+    /// prints it.
+    pub fn output_name(&self) -> String {
+        match (&self.alias, self.as_column()) {
+            (Some(alias), _) => alias.to_string(),
+            (None, Some(column)) => column.to_string(),
+            (None, None) => self.expr.to_sql(crate::dialect::Dialect::Generic),
+        }
+    }
+}
+
+/// Warehouse-dialect modifiers of `*` in a select list:
+/// `* EXCLUDE (a, b)` and `* REPLACE (a + 1 AS a)`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WildcardOptions {
+    pub exclude: Vec<Ident>,
+    /// Replacement expression and the column it replaces.
+    pub replace: Vec<(Expression, Ident)>,
+}
+
+impl WildcardOptions {
+    pub fn is_empty(&self) -> bool {
+        self.exclude.is_empty() && self.replace.is_empty()
+    }
+}
+
+/// One element of a GROUP BY clause.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum GroupingElement {
+    Expression(Expression),
+    /// ROLLUP(a, b): the groupings (a, b), (a) and ().
+    Rollup(Vec<Expression>),
+    /// CUBE(a, b): every subset of the listed expressions.
+    Cube(Vec<Expression>),
+    /// GROUPING SETS ((a), (b), ()): each set as written; a bare expression
+    /// is a set of one.
+    GroupingSets(Vec<Vec<Expression>>),
 }
-/// Represents a SQL statement (currently only SELECT is supported).
+
+impl GroupingElement {
+    /// The expressions of the element in source order.
+    pub fn expressions(&self) -> Vec<&Expression> {
+        match self {
+            GroupingElement::Expression(expr) => vec![expr],
+            GroupingElement::Rollup(exprs) | GroupingElement::Cube(exprs) => exprs.iter().collect(),
+            GroupingElement::GroupingSets(sets) => sets.iter().flatten().collect(),
+        }
+    }
+}
+
+/// What a WHEN clause of a MERGE does to the row.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum MergeAction {
+    /// UPDATE SET column = value, ...
+    Update(Vec<(Ident, Expression)>),
+    Delete,
+    /// INSERT [(columns)] VALUES (...)
+    Insert {
+        columns: Option<Vec<Ident>>,
+        values: Vec<Expression>,
+    },
+}
+
+/// WHEN [NOT] MATCHED [AND condition] THEN action
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeClause {
+    pub matched: bool,
+    pub condition: Option<Expression>,
+    pub action: MergeAction,
+}
+
+impl MergeClause {
+    /// The condition, then the values of the action.
+    pub fn expressions(&self) -> Vec<&Expression> {
+        let mut exprs: Vec<&Expression> = self.condition.iter().collect();
+        match &self.action {
+            MergeAction::Update(assignments) => exprs.extend(assignments.iter().map(|(_, value)| value)),
+            MergeAction::Delete => {}
+            MergeAction::Insert { values, .. } => exprs.extend(values),
+        }
+        exprs
+    }
+}
+
+/// Where COPY reads from or writes to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CopyTarget {
+    File(String),
+    Stdin,
+    Stdout,
+}
+
+/// One `name [value]` entry of COPY's WITH (...) list, e.g. `FORMAT csv`.
+/// Names are upper-cased; values are single literals or words.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopyOption {
+    pub name: String,
+    pub value: Option<Expression>,
+}
+
+/// How LOAD DATA treats rows that duplicate an existing key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DuplicateHandling {
+    Replace,
+    Ignore,
+}
+
+/// The FIELDS and LINES clauses of LOAD DATA.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadDataFormat {
+    pub fields_terminated_by: Option<String>,
+    pub fields_enclosed_by: Option<String>,
+    /// ENCLOSED BY was preceded by OPTIONALLY.
+    pub optionally_enclosed: bool,
+    pub fields_escaped_by: Option<String>,
+    pub lines_starting_by: Option<String>,
+    pub lines_terminated_by: Option<String>,
+}
+
+/// The scope keyword of a SET statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SetScope {
+    Session,
+    Local,
+    Global,
+}
+
+/// What a transaction control statement does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TransactionCommand {
+    Begin,
+    Commit,
+    Rollback,
+}
+
+/// Represents a SQL statement: a query, DML, DDL or a privilege statement.
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum Statement {
+    /// A `*` item in `columns` stands for every column of the FROM item.
     Select {
-        columns: Vec<String>,
-        table: String,
+        hints: Vec<Hint>,
+        columns: Vec<SelectItem>,
+        wildcard: WildcardOptions,
+        from: Box<TableFactor>,
+        joins: Vec<Join>,
         selection: Option<Expression>,
-        order_by: Option<Vec<String>>,
+        group_by: Vec<GroupingElement>,
+        qualify: Option<Expression>,
+        order_by: Vec<OrderByExpr>,
         limit: Option<u64>,
+        offset: Option<u64>,
+        locking: Vec<LockingClause>,
     },
+    CreateTable {
+        table_name: Ident,
+        column_list: Vec<TableColumn>,
+    },
+    DropTable {
+        table_name: Ident,
+        if_exists: bool,
+    },
+    /// CREATE [OR REPLACE] VIEW name [(columns)] AS query
+    CreateView {
+        or_replace: bool,
+        name: Ident,
+        columns: Vec<Ident>,
+        query: Box<Statement>,
+    },
+    /// CREATE [OR REPLACE] FUNCTION|PROCEDURE name (parameters) [RETURNS
+    /// type] AS 'body' [LANGUAGE language]. The body is kept as written and
+    /// not parsed.
+    CreateFunction {
+        or_replace: bool,
+        procedure: bool,
+        name: String,
+        parameters: Vec<FunctionParameter>,
+        returns: Option<RoutineType>,
+        language: Option<String>,
+        body: String,
+    },
+    /// INSERT INTO table [(columns)] VALUES (...), (...)
+    Insert {
+        table_name: Ident,
+        columns: Option<Vec<Ident>>,
+        rows: Vec<Vec<Expression>>,
+    },
+    /// INSERT INTO table [(columns)] query, the rows a SELECT, WITH or set
+    /// operation returns
+    InsertSelect {
+        table_name: Ident,
+        columns: Option<Vec<Ident>>,
+        query: Box<Statement>,
+    },
+    /// UPDATE table [[AS] alias] SET column = value, ... [WHERE condition]
+    Update {
+        table_name: Ident,
+        alias: Option<Ident>,
+        assignments: Vec<(Ident, Expression)>,
+        selection: Option<Expression>,
+    },
+    /// DELETE FROM table [[AS] alias] [WHERE condition]
+    Delete {
+        table_name: Ident,
+        alias: Option<Ident>,
+        selection: Option<Expression>,
+    },
+    /// GRANT privileges ON object TO grantees [WITH GRANT OPTION]
+    Grant {
+        privileges: Vec<GrantedPrivilege>,
+        object: GrantObject,
+        grantees: Vec<String>,
+        with_grant_option: bool,
+    },
+    /// REVOKE privileges ON object FROM grantees
+    Revoke {
+        privileges: Vec<GrantedPrivilege>,
+        object: GrantObject,
+        grantees: Vec<String>,
+    },
+    /// GRANT roles TO grantees [WITH ADMIN OPTION], making the grantees
+    /// members of the roles.
+    GrantRole {
+        roles: Vec<String>,
+        grantees: Vec<String>,
+        with_admin_option: bool,
+    },
+    /// REVOKE roles FROM grantees
+    RevokeRole {
+        roles: Vec<String>,
+        grantees: Vec<String>,
+    },
+    CreateRole {
+        role_name: String,
+        options: Vec<RoleOption>,
+    },
+    /// CREATE TRIGGER name BEFORE|AFTER|INSTEAD OF event [OR event ...] ON
+    /// table [FOR [EACH] ROW|STATEMENT] [WHEN (condition)] body, where an
+    /// UPDATE event may be UPDATE OF column, ...
+    CreateTrigger {
+        name: String,
+        timing: TriggerTiming,
+        events: Vec<TriggerEvent>,
+        /// The columns of UPDATE OF; empty when an update of any column fires it.
+        update_columns: Vec<Ident>,
+        table_name: Ident,
+        for_each_row: bool,
+        condition: Option<Expression>,
+        body: TriggerBody,
+    },
+    AlterRole {
+        role_name: String,
+        options: Vec<RoleOption>,
+    },
+    /// MERGE INTO target [[AS] alias] USING source [[AS] alias] ON condition
+    /// WHEN ... THEN ...
+    Merge {
+        target: Ident,
+        target_alias: Option<Ident>,
+        source: MergeSource,
+        source_alias: Option<Ident>,
+        on: Expression,
+        clauses: Vec<MergeClause>,
+    },
+    /// Postgres COPY table [(columns)] FROM|TO target [WITH (options)]
+    Copy {
+        table_name: Ident,
+        columns: Option<Vec<Ident>>,
+        /// FROM (loading the table) rather than TO.
+        from: bool,
+        target: CopyTarget,
+        options: Vec<CopyOption>,
+    },
+    /// MySQL LOAD DATA [LOCAL] INFILE 'file' ... INTO TABLE table ...
+    LoadData {
+        local: bool,
+        file: String,
+        duplicates: Option<DuplicateHandling>,
+        table_name: Ident,
+        format: LoadDataFormat,
+        ignore_lines: Option<u64>,
+        columns: Option<Vec<Ident>>,
+    },
+    /// SET [SESSION|LOCAL|GLOBAL] name {=|TO} value, ..., plus the special
+    /// forms SET NAMES charset and SET TIME ZONE value. `name` is kept as
+    /// written ("NAMES" and "TIME ZONE" for the special forms); `value` has
+    /// one entry per comma-separated item, with words such as ON or DEFAULT
+    /// as identifiers.
+    SetVariable {
+        scope: Option<SetScope>,
+        name: String,
+        value: Vec<Expression>,
+    },
+    /// PREPARE name [(types)] AS statement
+    Prepare {
+        name: String,
+        parameter_types: Vec<DBType>,
+        statement: Box<Statement>,
+    },
+    /// EXECUTE name [(parameters)]
+    Execute {
+        name: String,
+        parameters: Vec<Expression>,
+    },
+    /// DEALLOCATE [PREPARE] name, or `None` for DEALLOCATE ALL
+    Deallocate {
+        name: Option<String>,
+    },
+    /// EXPLAIN [ANALYZE] statement: the plan of the statement, and with
+    /// ANALYZE what running it actually produced
+    Explain {
+        analyze: bool,
+        statement: Box<Statement>,
+    },
+    /// BEGIN [TRANSACTION|WORK] or START TRANSACTION, COMMIT [TRANSACTION|WORK]
+    /// (or END), ROLLBACK [TRANSACTION|WORK]
+    Transaction {
+        command: TransactionCommand,
+    },
+    /// VALUES (...), (...) as a query of its own
+    Values {
+        rows: Vec<Vec<Expression>>,
+    },
+    /// left UNION|INTERSECT|EXCEPT [ALL] right
+    SetOperation {
+        operator: SetOperator,
+        all: bool,
+        left: Box<Statement>,
+        right: Box<Statement>,
+    },
+    /// WITH [RECURSIVE] name AS (query), ... body
+    With {
+        recursive: bool,
+        ctes: Vec<Cte>,
+        body: Box<Statement>,
+    },
+    /// A statement the parser couldn't read, returned as written by a
+    /// session in loose mode; `span` is its byte range in the parsed text.
+    Unparsed {
+        raw_sql: String,
+        span: Span,
+    },
+    /// A statement parsed by a handler registered in `extension::Extensions`.
+    Custom(Extension),
 }
 impl Statement {
     /// Convenience constructor for Select statement
     pub fn new_select(
-        columns: Vec<String>,
-        table: String,
+        columns: Vec<SelectItem>,
+        table: Ident,
         selection: Option<Expression>,
-        order_by: Option<Vec<String>>,
+        order_by: Vec<OrderByExpr>,
         limit: Option<u64>,
+        offset: Option<u64>,
     ) -> Self {
         Statement::Select {
+            hints: Vec::new(),
             columns,
-            table,
+            wildcard: WildcardOptions::default(),
+            from: Box::new(TableFactor::Table { name: table, alias: None, sample: None }),
+            joins: Vec::new(),
             selection,
+            group_by: Vec::new(),
+            qualify: None,
             order_by,
             limit,
+            offset,
+            locking: Vec::new(),
+        }
+    }
+
+    /// Short name of the statement kind, e.g. "SELECT".
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Statement::Select { .. } => "SELECT",
+            Statement::CreateTable { .. } => "CREATE TABLE",
+            Statement::DropTable { .. } => "DROP TABLE",
+            Statement::CreateView { .. } => "CREATE VIEW",
+            Statement::CreateFunction { procedure: false, .. } => "CREATE FUNCTION",
+            Statement::CreateFunction { procedure: true, .. } => "CREATE PROCEDURE",
+            Statement::Insert { .. } | Statement::InsertSelect { .. } => "INSERT",
+            Statement::Update { .. } => "UPDATE",
+            Statement::Delete { .. } => "DELETE",
+            Statement::Grant { .. } => "GRANT",
+            Statement::Revoke { .. } => "REVOKE",
+            Statement::GrantRole { .. } => "GRANT ROLE",
+            Statement::RevokeRole { .. } => "REVOKE ROLE",
+            Statement::CreateRole { .. } => "CREATE ROLE",
+            Statement::CreateTrigger { .. } => "CREATE TRIGGER",
+            Statement::AlterRole { .. } => "ALTER ROLE",
+            Statement::Merge { .. } => "MERGE",
+            Statement::Copy { .. } => "COPY",
+            Statement::LoadData { .. } => "LOAD DATA",
+            Statement::SetVariable { .. } => "SET",
+            Statement::Prepare { .. } => "PREPARE",
+            Statement::Execute { .. } => "EXECUTE",
+            Statement::Deallocate { .. } => "DEALLOCATE",
+            Statement::Explain { .. } => "EXPLAIN",
+            Statement::Transaction { command: TransactionCommand::Begin } => "BEGIN",
+            Statement::Transaction { command: TransactionCommand::Commit } => "COMMIT",
+            Statement::Transaction { command: TransactionCommand::Rollback } => "ROLLBACK",
+            Statement::Values { .. } => "VALUES",
+            Statement::SetOperation { operator: SetOperator::Union, .. } => "UNION",
+            Statement::SetOperation { operator: SetOperator::Intersect, .. } => "INTERSECT",
+            Statement::SetOperation { operator: SetOperator::Except, .. } => "EXCEPT",
+            Statement::With { .. } => "WITH",
+            Statement::Unparsed { .. } => "UNPARSED",
+            Statement::Custom(statement) => statement.kind(),
+        }
+    }
+
+    /// The top-level expressions of the statement in source order: the
+    /// select list with the `* REPLACE` items in place of `*`, FROM items,
+    /// joins, WHERE, GROUP BY, QUALIFY and ORDER BY clauses of a SELECT, the CHECK constraints of a CREATE TABLE, the
+    /// values of an INSERT or the expressions of its query, the assignments and WHERE of an UPDATE or
+    /// DELETE, or the WHEN condition and body of a trigger.
+    pub fn expressions(&self) -> Vec<&Expression> {
+        match self {
+            Statement::Select { columns, wildcard, from, joins, selection, group_by, qualify, order_by, .. } => {
+                let mut exprs = Vec::new();
+                let mut replaced = false;
+                for item in columns {
+                    if !item.is_wildcard() {
+                        exprs.push(&item.expr);
+                    } else if !replaced {
+                        exprs.extend(wildcard.replace.iter().map(|(expr, _)| expr));
+                        replaced = true;
+                    }
+                }
+                exprs.extend(from.expressions());
+                for join in joins {
+                    exprs.extend(join.relation.expressions());
+                    exprs.extend(&join.on);
+                }
+                exprs.extend(selection);
+                exprs.extend(group_by.iter().flat_map(GroupingElement::expressions));
+                exprs.extend(qualify);
+                exprs.extend(order_by.iter().map(|item| &item.expr));
+                exprs
+            }
+            Statement::CreateTable { column_list, .. } => column_list
+                .iter()
+                .flat_map(|c| &c.constraints)
+                .filter_map(|constraint| match constraint {
+                    Constraint::Check(expr) => Some(expr),
+                    _ => None,
+                })
+                .collect(),
+            Statement::DropTable { .. }
+            | Statement::CreateFunction { .. }
+            | Statement::Grant { .. }
+            | Statement::Revoke { .. }
+            | Statement::GrantRole { .. }
+            | Statement::RevokeRole { .. }
+            | Statement::Copy { .. }
+            | Statement::LoadData { .. } => Vec::new(),
+            Statement::Insert { rows, .. } | Statement::Values { rows } => rows.iter().flatten().collect(),
+            Statement::InsertSelect { query, .. } | Statement::CreateView { query, .. } => query.expressions(),
+            Statement::Update { assignments, selection, .. } => {
+                let mut exprs: Vec<&Expression> = assignments.iter().map(|(_, value)| value).collect();
+                exprs.extend(selection);
+                exprs
+            }
+            Statement::Delete { selection, .. } => selection.iter().collect(),
+            Statement::CreateRole { options, .. } | Statement::AlterRole { options, .. } => options
+                .iter()
+                .filter_map(|option| match option {
+                    RoleOption::Password(expr) => Some(expr),
+                    _ => None,
+                })
+                .collect(),
+            Statement::CreateTrigger { condition, body, .. } => {
+                let mut exprs: Vec<&Expression> = condition.iter().collect();
+                match body {
+                    TriggerBody::Execute { args, .. } => exprs.extend(args),
+                    TriggerBody::Statement(statement) => exprs.extend(statement.expressions()),
+                    TriggerBody::Block(_) => {}
+                }
+                exprs
+            }
+            Statement::SetVariable { value, .. } => value.iter().collect(),
+            Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => statement.expressions(),
+            Statement::Execute { parameters, .. } => parameters.iter().collect(),
+            Statement::Deallocate { .. }
+            | Statement::Transaction { .. }
+            | Statement::Unparsed { .. }
+            | Statement::Custom(_) => Vec::new(),
+            Statement::SetOperation { left, right, .. } => {
+                let mut exprs = left.expressions();
+                exprs.extend(right.expressions());
+                exprs
+            }
+            Statement::With { ctes, body, .. } => {
+                let mut exprs: Vec<&Expression> = ctes.iter().flat_map(|cte| cte.query.expressions()).collect();
+                exprs.extend(body.expressions());
+                exprs
+            }
+            Statement::Merge { source, on, clauses, .. } => {
+                let mut exprs = match source {
+                    MergeSource::Query(query) => query.expressions(),
+                    MergeSource::Table(_) => Vec::new(),
+                };
+                exprs.push(on);
+                exprs.extend(clauses.iter().flat_map(MergeClause::expressions));
+                exprs
+            }
+        }
+    }
+
+    /// The table the statement reads, writes, defines or grants access to;
+    /// `None` for statements about roles and queries without a named table.
+    pub fn table_name(&self) -> Option<&str> {
+        match self {
+            Statement::Select { from, .. } => from.table_name(),
+            Statement::CreateTable { table_name, .. }
+            | Statement::DropTable { table_name, .. }
+            | Statement::Insert { table_name, .. }
+            | Statement::InsertSelect { table_name, .. }
+            | Statement::Update { table_name, .. }
+            | Statement::Delete { table_name, .. }
+            | Statement::Copy { table_name, .. }
+            | Statement::LoadData { table_name, .. }
+            | Statement::CreateTrigger { table_name, .. } => Some(table_name),
+            Statement::Grant { object, .. } | Statement::Revoke { object, .. } => match object {
+                GrantObject::Table(name) => Some(name),
+                _ => None,
+            },
+            Statement::CreateView { name, .. } => Some(name),
+            Statement::Merge { target, .. } => Some(target),
+            Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => statement.table_name(),
+            Statement::CreateRole { .. }
+            | Statement::AlterRole { .. }
+            | Statement::GrantRole { .. }
+            | Statement::RevokeRole { .. }
+            | Statement::CreateFunction { .. }
+            | Statement::SetVariable { .. }
+            | Statement::Execute { .. }
+            | Statement::Deallocate { .. }
+            | Statement::Transaction { .. }
+            | Statement::Values { .. }
+            | Statement::SetOperation { .. }
+            | Statement::With { .. }
+            | Statement::Unparsed { .. }
+            | Statement::Custom(_) => None,
+        }
+    }
+
+    /// True when a query reads table `name` (case-insensitively) in any of
+    /// its FROM items, joins, subqueries or CTEs.
+    pub fn references_table(&self, name: &str) -> bool {
+        fn factor_reads(factor: &TableFactor, name: &str) -> bool {
+            match factor {
+                TableFactor::Table { name: table, .. } | TableFactor::AsOf { name: table, .. } => {
+                    table.eq_ignore_ascii_case(name)
+                }
+                TableFactor::Derived { subquery, .. } => subquery.references_table(name),
+                TableFactor::Pivot { table, .. } | TableFactor::Unpivot { table, .. } => factor_reads(table, name),
+                TableFactor::Function { .. } => false,
+            }
+        }
+        match self {
+            Statement::Select { from, joins, .. } => {
+                factor_reads(from, name) || joins.iter().any(|join| factor_reads(&join.relation, name))
+            }
+            Statement::SetOperation { left, right, .. } => left.references_table(name) || right.references_table(name),
+            Statement::With { ctes, body, .. } => {
+                ctes.iter().any(|cte| cte.query.references_table(name)) || body.references_table(name)
+            }
+            Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => {
+                statement.references_table(name)
+            }
+            Statement::InsertSelect { query, .. } | Statement::CreateView { query, .. } => query.references_table(name),
+            _ => false,
+        }
+    }
+
+    /// Every table the statement reads, writes or defines, each once in
+    /// source order. Names bound by a WITH clause aren't tables and are
+    /// left out.
+    pub fn tables(&self) -> Vec<&str> {
+        fn factor_tables<'a>(factor: &'a TableFactor, out: &mut Vec<&'a str>) {
+            match factor {
+                TableFactor::Table { name, .. } | TableFactor::AsOf { name, .. } => out.push(name),
+                TableFactor::Derived { subquery, .. } => collect(subquery, out),
+                TableFactor::Pivot { table, .. } | TableFactor::Unpivot { table, .. } => factor_tables(table, out),
+                TableFactor::Function { .. } => {}
+            }
+        }
+        fn collect<'a>(statement: &'a Statement, out: &mut Vec<&'a str>) {
+            match statement {
+                Statement::Select { from, joins, .. } => {
+                    factor_tables(from, out);
+                    for join in joins {
+                        factor_tables(&join.relation, out);
+                    }
+                }
+                Statement::SetOperation { left, right, .. } => {
+                    collect(left, out);
+                    collect(right, out);
+                }
+                Statement::With { ctes, body, .. } => {
+                    let mut inner = Vec::new();
+                    for cte in ctes {
+                        collect(&cte.query, &mut inner);
+                    }
+                    collect(body, &mut inner);
+                    let bound = |name: &str| ctes.iter().any(|cte| cte.name.eq_ignore_ascii_case(name));
+                    out.extend(inner.into_iter().filter(|name| !bound(name)));
+                }
+                Statement::Merge { target, source, .. } => {
+                    out.push(target);
+                    match source {
+                        MergeSource::Table(name) => out.push(name),
+                        MergeSource::Query(query) => collect(query, out),
+                    }
+                }
+                Statement::InsertSelect { table_name: name, query, .. } | Statement::CreateView { name, query, .. } => {
+                    out.push(name);
+                    collect(query, out);
+                }
+                Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => collect(statement, out),
+                Statement::CreateTrigger { table_name, body, .. } => {
+                    out.push(table_name);
+                    if let TriggerBody::Statement(inner) = body {
+                        collect(inner, out);
+                    }
+                }
+                other => out.extend(other.table_name()),
+            }
+        }
+        let mut tables = Vec::new();
+        collect(self, &mut tables);
+        let mut seen = Vec::new();
+        tables.retain(|name| {
+            let first = !seen.contains(name);
+            seen.push(*name);
+            first
+        });
+        tables
+    }
+
+    /// True for privilege and role statements (GRANT, REVOKE, CREATE/ALTER ROLE).
+    pub fn is_dcl(&self) -> bool {
+        matches!(
+            self,
+            Statement::Grant { .. }
+                | Statement::Revoke { .. }
+                | Statement::GrantRole { .. }
+                | Statement::RevokeRole { .. }
+                | Statement::CreateRole { .. }
+                | Statement::AlterRole { .. }
+        )
+    }
+
+    /// True for statements that change the schema (CREATE/DROP TABLE,
+    /// CREATE FUNCTION/PROCEDURE, CREATE TRIGGER).
+    pub fn is_ddl(&self) -> bool {
+        matches!(
+            self,
+            Statement::CreateTable { .. }
+                | Statement::DropTable { .. }
+                | Statement::CreateFunction { .. }
+                | Statement::CreateTrigger { .. }
+                | Statement::CreateView { .. }
+        )
+    }
+}
+
+// === SQL printing ===
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            BinaryOperator::Equals => "=",
+            BinaryOperator::NotEquals => "!=",
+            BinaryOperator::GreaterThan => ">",
+            BinaryOperator::GreaterThanOrEqual => ">=",
+            BinaryOperator::LessThan => "<",
+            BinaryOperator::LessThanOrEqual => "<=",
+            BinaryOperator::And => "AND",
+            BinaryOperator::Or => "OR",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Like => "LIKE",
+            BinaryOperator::ILike => "ILIKE",
+            BinaryOperator::Concat => "||",
+            BinaryOperator::IsDistinctFrom => "IS DISTINCT FROM",
+            BinaryOperator::IsNotDistinctFrom => "IS NOT DISTINCT FROM",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnaryOperator::Not => write!(f, "NOT "),
+            UnaryOperator::Negate => write!(f, "-"),
+            UnaryOperator::IsNull => write!(f, " IS NULL"),
+            UnaryOperator::IsNotNull => write!(f, " IS NOT NULL"),
         }
     }
 }
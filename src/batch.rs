@@ -0,0 +1,249 @@
+// batch.rs — parsing many statements at once across worker threads
+// Author: Sreerag Devadasan
+
+#[cfg(feature = "std")]
+use std::{
+    fs, io,
+    path::Path,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::dialect::Dialect;
+use crate::prelude::*;
+use crate::span::Span;
+use crate::tokenizer::{compound_block_len, dollar_quoted_len, quoted_len};
+#[cfg(feature = "std")]
+use crate::{ast::Statement, parser::parse_sql, tokenizer::ParseError};
+
+/// Splits a script, such as a dump file, into its statements without
+/// parsing them, each with its byte range in the script; see
+/// `statement_spans`.
+pub fn split_statements(script: &str) -> Vec<(Span, &str)> {
+    split_statements_with_dialect(script, Dialect::Generic)
+}
+
+/// Like `split_statements`, for a script in `dialect`; see
+/// `statement_spans_with_dialect`.
+pub fn split_statements_with_dialect(script: &str, dialect: Dialect) -> Vec<(Span, &str)> {
+    statement_spans_with_dialect(script, dialect).into_iter().map(|span| (span, span.slice(script))).collect()
+}
+
+/// The byte ranges of the statements of a script. A statement ends at a
+/// semicolon that is not inside a quoted or dollar-quoted string, a
+/// comment, or the `BEGIN ... END` block of a trigger (`FOR EACH ROW
+/// BEGIN`). A BEGIN that starts a statement is a transaction's, so BEGIN,
+/// SAVEPOINT, RELEASE, ROLLBACK TO and COMMIT each stay a statement of
+/// their own for a restore to run in turn.
+///
+/// A range starts at the statement's first token, past any comments before
+/// it, and ends at its last token or comment, so a script's comments
+/// outside statements (a dump's header, say) are no statements. A mysql
+/// client's `DELIMITER //` line, as dumps write around the bodies of
+/// triggers and procedures, changes what ends a statement until the next
+/// such line; the lines themselves are no statements either.
+pub fn statement_spans(script: &str) -> Vec<Span> {
+    statement_spans_with_dialect(script, Dialect::Generic)
+}
+
+/// Like `statement_spans`, for a script in `dialect`: in MySQL, a quote
+/// after a backslash doesn't end a string, as it doesn't in an `E'...'`
+/// string in every dialect.
+pub fn statement_spans_with_dialect(script: &str, dialect: Dialect) -> Vec<Span> {
+    let mut statements = Vec::new();
+    let mut delimiter = ";";
+    // The range of the statement read so far, from its first token
+    let mut statement: Option<Span> = None;
+    let mut previous_word = "";
+    let mut i = 0;
+
+    while i < script.len() {
+        let rest = &script[i..];
+        if rest.starts_with(delimiter) {
+            statements.extend(statement.take());
+            previous_word = "";
+            i += delimiter.len();
+            continue;
+        }
+        let ch = rest.chars().next().unwrap_or_default();
+        let word_len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        let len = match ch {
+            c if c.is_whitespace() => {
+                i += c.len_utf8();
+                continue;
+            }
+            '-' if rest.starts_with("--") => rest.find('\n').unwrap_or(rest.len()),
+            '/' if rest.starts_with("/*") => rest[2..].find("*/").map_or(rest.len(), |end| end + 4),
+            '\'' | '"' => quoted_len(rest, dialect.backslash_escapes()),
+            '`' => quoted_len(rest, false),
+            '$' => dollar_quoted_len(rest).max(1),
+            'E' | 'e' if rest[1..].starts_with('\'') => 1 + quoted_len(&rest[1..], true),
+            _ if word_len == 0 => ch.len_utf8(),
+            _ => {
+                let word = &rest[..word_len];
+                if statement.is_none() && word.eq_ignore_ascii_case("DELIMITER") {
+                    let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+                    if let Some(new) = line[word_len..].split_whitespace().next() {
+                        delimiter = new;
+                    }
+                    i += line.len();
+                    continue;
+                }
+                let block = word.eq_ignore_ascii_case("BEGIN") && previous_word.eq_ignore_ascii_case("ROW");
+                previous_word = word;
+                if block {
+                    compound_block_len(rest).unwrap_or(rest.len())
+                } else {
+                    word_len
+                }
+            }
+        };
+        let comment = rest.starts_with("--") || rest.starts_with("/*");
+        match &mut statement {
+            Some(span) => span.end = i + len,
+            None if !comment => statement = Some(Span::new(i, i + len)),
+            None => {}
+        }
+        i += len;
+    }
+    statements.extend(statement);
+    statements
+}
+
+/// Stops a batch parse early: call `cancel` from any thread, or give it a
+/// deadline. Clones share the same flag.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation {
+    flag: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+#[cfg(feature = "std")]
+impl Cancellation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A token that cancels itself `timeout` from now.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self { flag: Arc::default(), deadline: Some(Instant::now() + timeout) }
+    }
+
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed) || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+}
+
+/// The result of a batch parse that may have been cancelled. `results` has
+/// one slot per input statement; statements that weren't parsed before the
+/// cancellation are `None`.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct BatchOutcome {
+    pub results: Vec<Option<Result<Statement, ParseError>>>,
+    pub cancelled: bool,
+}
+
+#[cfg(feature = "std")]
+impl BatchOutcome {
+    /// Number of statements that were parsed.
+    pub fn completed(&self) -> usize {
+        self.results.iter().filter(|r| r.is_some()).count()
+    }
+
+    /// One result per input statement, as `parse_many` gives them, with
+    /// `ParseError::Cancelled` for those that weren't parsed.
+    pub fn into_results(self) -> Vec<Result<Statement, ParseError>> {
+        self.results.into_iter().map(|result| result.unwrap_or(Err(ParseError::Cancelled))).collect()
+    }
+}
+
+/// Parses every statement on a pool of worker threads. Results are returned
+/// in the same order as the input.
+#[cfg(feature = "std")]
+pub fn parse_many(statements: &[&str]) -> Vec<Result<Statement, ParseError>> {
+    parse_many_cancellable(statements, &Cancellation::new())
+        .results
+        .into_iter()
+        .map(|r| r.expect("batch was never cancelled"))
+        .collect()
+}
+
+/// Like `parse_many`, but stops handing out statements once `cancel` fires.
+/// Workers take statements in input order, so the parsed ones form
+/// (almost) a prefix of the input. A statement that is already being parsed
+/// is finished first; use `ParserOptions` limits to bound a single one.
+#[cfg(feature = "std")]
+pub fn parse_many_cancellable(statements: &[&str], cancel: &Cancellation) -> BatchOutcome {
+    let workers = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let next = AtomicUsize::new(0);
+
+    let parsed: Vec<(usize, Result<Statement, ParseError>)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers.min(statements.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    while !cancel.is_cancelled() {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(sql) = statements.get(i) else { break };
+                        done.push((i, parse_sql(sql)));
+                    }
+                    done
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("parser worker panicked"))
+            .collect()
+    });
+
+    let mut results: Vec<Option<Result<Statement, ParseError>>> = statements.iter().map(|_| None).collect();
+    for (i, result) in parsed {
+        results[i] = Some(result);
+    }
+    let cancelled = results.iter().any(Option::is_none);
+    BatchOutcome { results, cancelled }
+}
+
+/// Splits a multi-statement script on top-level semicolons and parses the
+/// statements in parallel.
+#[cfg(feature = "std")]
+pub fn parse_script(script: &str) -> Vec<Result<Statement, ParseError>> {
+    parse_many(&statements_of(script))
+}
+
+/// Like `parse_script`, but can be cancelled; see `parse_many_cancellable`.
+#[cfg(feature = "std")]
+pub fn parse_script_cancellable(script: &str, cancel: &Cancellation) -> BatchOutcome {
+    parse_many_cancellable(&statements_of(script), cancel)
+}
+
+/// The text of each statement of `script`.
+#[cfg(feature = "std")]
+fn statements_of(script: &str) -> Vec<&str> {
+    split_statements(script).into_iter().map(|(_, sql)| sql).collect()
+}
+
+/// Reads a `.sql` file and parses all of its statements in parallel.
+#[cfg(feature = "std")]
+pub fn parse_file_parallel(path: impl AsRef<Path>) -> io::Result<Vec<Result<Statement, ParseError>>> {
+    let script = fs::read_to_string(path)?;
+    Ok(parse_script(&script))
+}
+
+/// Like `parse_file_parallel`, but can be cancelled; see
+/// `parse_many_cancellable`.
+#[cfg(feature = "std")]
+pub fn parse_file_cancellable(path: impl AsRef<Path>, cancel: &Cancellation) -> io::Result<BatchOutcome> {
+    let script = fs::read_to_string(path)?;
+    Ok(parse_script_cancellable(&script, cancel))
+}
@@ -0,0 +1,251 @@
+// bytecode.rs — compiles expressions into a small stack-machine program
+// Author: Sreerag Devadasan
+//
+// Compiling folds constants: a subexpression that reads no columns, such
+// as `1 + 2` or `NULL AND FALSE`, is computed once by the evaluator and
+// pushed as its value, so its NULLs behave as semantics.rs says whether it
+// is folded or not. Folding handles errors as the program does (see
+// `ErrorHandling` in semantics.rs), so `1 / 0` folds to NULL where it is
+// NULL; where it fails, it is compiled as written and fails when it runs.
+// Templates aren't folded, since their literals are their parameters.
+
+use crate::prelude::*;
+use crate::ast::{BinaryOperator, DBType, Expression, UnaryOperator};
+use crate::collation::Collation;
+use crate::eval::{
+    apply_binary_under, apply_unary_under, builtin, cast_under, compare, evaluate_handling, literal_value, EvalError,
+    Value,
+};
+use crate::semantics::{accepts, ErrorHandling};
+
+/// One stack-machine instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    /// Push a constant value.
+    Push(Value),
+    /// Push the value of the column at this index of the row.
+    Load(usize),
+    /// Pop one operand and push the result.
+    Unary(UnaryOperator),
+    /// Pop the right then the left operand and push the result.
+    Binary(BinaryOperator),
+    /// Pop a value and push it cast to this type.
+    Cast(DBType),
+    /// Compare a column against a constant without copying the column value.
+    /// This is the most common shape of a filter predicate (`age > 30`).
+    CompareColumn {
+        index: usize,
+        operator: BinaryOperator,
+        value: Value,
+    },
+}
+
+/// A compiled expression. Column names are resolved to row indices once, at
+/// compile time, so evaluating a row is a flat loop over the instructions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    instructions: Vec<Instruction>,
+    max_stack: usize,
+    /// The instructions holding the value of each placeholder of a
+    /// template, in the order the placeholders are written.
+    parameters: Vec<usize>,
+    errors: ErrorHandling,
+}
+
+impl Program {
+    /// Compiles `expr` for rows laid out as `columns`.
+    pub fn compile(expr: &Expression, columns: &[String]) -> Result<Self, EvalError> {
+        Self::compile_as(expr, columns, false, &ErrorHandling::STRICT)
+    }
+
+    /// Like `compile`, for a program whose overflows, divisions by zero and
+    /// invalid casts give what `errors` says.
+    pub fn compile_handling(expr: &Expression, columns: &[String], errors: &ErrorHandling) -> Result<Self, EvalError> {
+        Self::compile_as(expr, columns, false, errors)
+    }
+
+    /// Compiles `expr` with its placeholders as parameters, to be given
+    /// values with `bind`; one program then serves every query that
+    /// differs from `expr` only in those values.
+    pub fn compile_template(expr: &Expression, columns: &[String]) -> Result<Self, EvalError> {
+        Self::compile_as(expr, columns, true, &ErrorHandling::STRICT)
+    }
+
+    pub(crate) fn compile_as(
+        expr: &Expression,
+        columns: &[String],
+        template: bool,
+        errors: &ErrorHandling,
+    ) -> Result<Self, EvalError> {
+        let mut program =
+            Program { instructions: Vec::new(), max_stack: 0, parameters: Vec::new(), errors: *errors };
+        program.emit(expr, columns, 0, template)?;
+        Ok(program)
+    }
+
+    /// The number of parameters of a template.
+    pub fn parameters(&self) -> usize {
+        self.parameters.len()
+    }
+
+    /// The template with `values` for its parameters, in order; parameters
+    /// left without a value are NULL.
+    pub fn bind(&self, values: &[Value]) -> Program {
+        let mut program = self.clone();
+        for (&index, value) in self.parameters.iter().zip(values) {
+            match &mut program.instructions[index] {
+                Instruction::Push(slot) | Instruction::CompareColumn { value: slot, .. } => *slot = value.clone(),
+                _ => {}
+            }
+        }
+        program
+    }
+
+    /// Recursively emits instructions in postfix order; `depth` is the number
+    /// of values already on the stack, used to size the stack up front.
+    /// Placeholders are only accepted in a `template`.
+    fn emit(&mut self, expr: &Expression, columns: &[String], depth: usize, template: bool) -> Result<(), EvalError> {
+        if !template && is_foldable(expr) {
+            if let Ok(value) = self.fold(expr) {
+                self.push(Instruction::Push(value), depth + 1);
+                return Ok(());
+            }
+        }
+        match expr {
+            Expression::Identifier(name) => {
+                let index = resolve(name, columns)?;
+                self.push(Instruction::Load(index), depth + 1);
+            }
+            Expression::CompoundIdentifier(parts) => {
+                let name = parts.iter().map(|part| part.as_str()).collect::<Vec<_>>().join(".");
+                let index = resolve(&name, columns)?;
+                self.push(Instruction::Load(index), depth + 1);
+            }
+            Expression::Grouped(inner) => self.emit(inner, columns, depth, template)?,
+            Expression::UnaryOperation { operator, operand } => {
+                self.emit(operand, columns, depth, template)?;
+                self.push(Instruction::Unary(operator.clone()), depth + 1);
+            }
+            Expression::BinaryOperation { left_operand, operator, right_operand } if is_comparison(operator) => {
+                let constant = match &**right_operand {
+                    Expression::Placeholder(_) if template => Ok(Value::Null),
+                    right if !template && is_foldable(right) => self.fold(right),
+                    right => literal_value(right),
+                };
+                if let (Expression::Identifier(name), Ok(value)) = (&**left_operand, constant) {
+                    let index = resolve(name, columns)?;
+                    if matches!(**right_operand, Expression::Placeholder(_)) {
+                        self.parameters.push(self.instructions.len());
+                    }
+                    self.push(Instruction::CompareColumn { index, operator: operator.clone(), value }, depth + 1);
+                } else {
+                    self.emit(left_operand, columns, depth, template)?;
+                    self.emit(right_operand, columns, depth + 1, template)?;
+                    self.push(Instruction::Binary(operator.clone()), depth + 1);
+                }
+            }
+            Expression::BinaryOperation { left_operand, operator, right_operand } => {
+                self.emit(left_operand, columns, depth, template)?;
+                self.emit(right_operand, columns, depth + 1, template)?;
+                self.push(Instruction::Binary(operator.clone()), depth + 1);
+            }
+            Expression::Cast { expr, data_type } => {
+                self.emit(expr, columns, depth, template)?;
+                self.push(Instruction::Cast(data_type.clone()), depth + 1);
+            }
+            Expression::Function { name, .. } => return Err(EvalError::UnknownFunction(name.clone())),
+            Expression::Placeholder(_) if template => {
+                self.parameters.push(self.instructions.len());
+                self.push(Instruction::Push(Value::Null), depth + 1);
+            }
+            literal => self.push(Instruction::Push(literal_value(literal)?), depth + 1),
+        }
+        Ok(())
+    }
+
+    /// The value of a constant expression, computed as the program would.
+    fn fold(&self, expr: &Expression) -> Result<Value, EvalError> {
+        evaluate_handling(expr, &[], &[], &builtin, &self.errors)
+    }
+
+    fn push(&mut self, instruction: Instruction, depth: usize) {
+        self.instructions.push(instruction);
+        self.max_stack = self.max_stack.max(depth);
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Evaluates the program against one row.
+    pub fn evaluate(&self, row: &[Value]) -> Result<Value, EvalError> {
+        self.evaluate_with(row, &mut Vec::with_capacity(self.max_stack))
+    }
+
+    /// Like `evaluate`, but reuses `stack` so scans don't allocate per row.
+    pub fn evaluate_with(&self, row: &[Value], stack: &mut Vec<Value>) -> Result<Value, EvalError> {
+        stack.clear();
+        for instruction in &self.instructions {
+            let value = match instruction {
+                Instruction::Push(value) => value.clone(),
+                Instruction::Load(index) => row[*index].clone(),
+                Instruction::Unary(operator) => apply_unary_under(operator, pop(stack), &self.errors)?,
+                Instruction::Binary(operator) => {
+                    let right = pop(stack);
+                    let left = pop(stack);
+                    apply_binary_under(operator, left, right, &self.errors)?
+                }
+                Instruction::Cast(data_type) => cast_under(&pop(stack), data_type, &self.errors)?,
+                Instruction::CompareColumn { index, operator, value } => compare(operator, &row[*index], value)?,
+            };
+            stack.push(value);
+        }
+        Ok(pop(stack))
+    }
+
+    /// Returns true when the row passes the filter (NULL counts as false).
+    pub fn matches(&self, row: &[Value], stack: &mut Vec<Value>) -> Result<bool, EvalError> {
+        Ok(accepts(&self.evaluate_with(row, stack)?))
+    }
+}
+
+fn resolve(name: &str, columns: &[String]) -> Result<usize, EvalError> {
+    columns
+        .iter()
+        .position(|c| c == name)
+        .ok_or_else(|| EvalError::UnknownColumn(name.to_string()))
+}
+
+/// An operation on literals alone, which compiling computes in advance;
+/// not an operand with a COLLATE, which the VM's values can't carry to the
+/// comparison it's for.
+fn is_foldable(expr: &Expression) -> bool {
+    let mut constant = matches!(
+        expr,
+        Expression::UnaryOperation { .. }
+            | Expression::BinaryOperation { .. }
+            | Expression::Grouped(_)
+            | Expression::Cast { .. }
+    ) && Collation::of(expr) == Ok(None);
+    expr.walk(&mut |expr| {
+        constant &= !matches!(
+            expr,
+            Expression::Identifier(_)
+                | Expression::CompoundIdentifier(_)
+                | Expression::Function { .. }
+                | Expression::Placeholder(_)
+        );
+    });
+    constant
+}
+
+fn is_comparison(operator: &BinaryOperator) -> bool {
+    use BinaryOperator::*;
+    matches!(operator, Equals | NotEquals | GreaterThan | GreaterThanOrEqual | LessThan | LessThanOrEqual)
+}
+
+// The compiler always emits a well-formed postfix program, so the stack can
+// never underflow.
+fn pop(stack: &mut Vec<Value>) -> Value {
+    stack.pop().expect("bytecode stack underflow")
+}
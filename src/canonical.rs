@@ -0,0 +1,540 @@
+// canonical.rs — canonical forms and structural equivalence of statements
+// Author: Sreerag Devadasan
+//
+// Two queries are structurally equivalent when their canonical forms are
+// equal. Canonicalizing rewrites every expression of a query, of the
+// statements that hold one (INSERT ... SELECT, CREATE VIEW, EXPLAIN,
+// PREPARE) and of INSERT, UPDATE and DELETE:
+//   * drops redundant parentheses,
+//   * flattens and sorts the operands of AND, OR, + and *,
+//   * sorts the operands of = and !=,
+//   * orients comparisons so the smaller operand is on the left
+//     (`5 < a` becomes `a > 5`).
+// `equivalent` also looks past what only names or writes things
+// differently: table, join and select-list aliases are renamed by
+// position, and decimals lose the zeros that don't change their value
+// (`1.0` and `1.00`). The canonical form keeps both as written, since they
+// name the result's columns and give its decimals their scale. `minify`
+// prints the canonical form on one line: comments are gone, whitespace is
+// collapsed and keywords are upper case, so equivalent queries minify to
+// the same text.
+
+use crate::prelude::*;
+use crate::ast::{
+    BinaryOperator, Constraint, Cte, Expression, GroupingElement, Join, OrderByExpr, SelectItem, Statement, TableColumn,
+    TableFactor, TableSample, WildcardOptions,
+};
+use crate::dialect::Dialect;
+use crate::ident::Ident;
+use crate::parser::{parse_sql, parse_sql_with_dialect};
+use crate::pratt::PrecedenceTable;
+use crate::tokenizer::ParseError;
+
+/// Returns true if the two statements are structurally equivalent.
+pub fn equivalent(a: &Statement, b: &Statement) -> bool {
+    comparable(a) == comparable(b)
+}
+
+/// The canonical form of `statement` with its aliases renamed `_1`, `_2`,
+/// ... in the order they are named, and its decimals trimmed.
+fn comparable(statement: &Statement) -> Statement {
+    let mut statement = canonicalize(statement);
+    Aliases::default().statement(&mut statement);
+    statement
+}
+
+/// The aliases of a statement by position, lower-cased, as `comparable`
+/// finds them: those of a query's FROM items first, then its select list's.
+#[derive(Default)]
+struct Aliases {
+    names: Vec<String>,
+}
+
+impl Aliases {
+    /// The positional name of `alias`, which it is given here.
+    fn define(&mut self, alias: &mut Ident) {
+        let name = alias.value.to_lowercase();
+        if !self.names.contains(&name) {
+            self.names.push(name);
+        }
+        *alias = self.lookup(alias).expect("the alias was just named");
+    }
+
+    /// The positional name of `name`, if it is one of the aliases.
+    fn lookup(&self, name: &str) -> Option<Ident> {
+        let position = self.names.iter().position(|alias| alias.eq_ignore_ascii_case(name))?;
+        Some(Ident::new(format!("_{}", position + 1)))
+    }
+
+    fn statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::Select { columns, wildcard, from, joins, selection, group_by, qualify, order_by, .. } => {
+                self.factor(from);
+                for join in joins.iter_mut() {
+                    self.factor(&mut join.relation);
+                }
+                for alias in columns.iter_mut().filter_map(|item| item.alias.as_mut()) {
+                    self.define(alias);
+                }
+                let grouped = group_by.iter_mut().flat_map(|element| match element {
+                    GroupingElement::Expression(expr) => vec![expr],
+                    GroupingElement::Rollup(exprs) | GroupingElement::Cube(exprs) => exprs.iter_mut().collect(),
+                    GroupingElement::GroupingSets(sets) => sets.iter_mut().flatten().collect(),
+                });
+                let replaced = wildcard.replace.iter_mut().map(|(expr, _)| expr);
+                let exprs = columns.iter_mut().map(|item| &mut item.expr).chain(replaced);
+                let exprs = exprs.chain(joins.iter_mut().filter_map(|join| join.on.as_mut()));
+                for expr in exprs.chain(selection.iter_mut()).chain(grouped).chain(qualify.iter_mut()) {
+                    self.expression(expr);
+                }
+                // A bare name in ORDER BY may be a select-list alias
+                for item in order_by.iter_mut() {
+                    match &mut item.expr {
+                        Expression::Identifier(name) => {
+                            if let Some(alias) = self.lookup(name) {
+                                *name = alias;
+                            }
+                        }
+                        expr => self.expression(expr),
+                    }
+                }
+            }
+            Statement::Update { alias, assignments, selection, .. } => {
+                alias.iter_mut().for_each(|alias| self.define(alias));
+                let values = assignments.iter_mut().map(|(_, value)| value);
+                values.chain(selection.iter_mut()).for_each(|expr| self.expression(expr));
+            }
+            Statement::Delete { alias, selection, .. } => {
+                alias.iter_mut().for_each(|alias| self.define(alias));
+                selection.iter_mut().for_each(|expr| self.expression(expr));
+            }
+            Statement::Insert { rows, .. } | Statement::Values { rows } => {
+                rows.iter_mut().flatten().for_each(|expr| self.expression(expr));
+            }
+            Statement::SetOperation { left, right, .. } => {
+                self.statement(left);
+                self.statement(right);
+            }
+            Statement::With { ctes, body, .. } => {
+                ctes.iter_mut().for_each(|cte| self.statement(&mut cte.query));
+                self.statement(body);
+            }
+            Statement::InsertSelect { query, .. } | Statement::CreateView { query, .. } => self.statement(query),
+            Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => self.statement(statement),
+            _ => {}
+        }
+    }
+
+    fn factor(&mut self, factor: &mut TableFactor) {
+        let alias = match factor {
+            TableFactor::Table { alias, .. } | TableFactor::AsOf { alias, .. } => alias,
+            TableFactor::Derived { subquery, alias, .. } => {
+                self.statement(subquery);
+                alias
+            }
+            TableFactor::Function { args, alias, .. } => {
+                args.iter_mut().for_each(|arg| self.expression(arg));
+                alias
+            }
+            TableFactor::Pivot { table, aggregate, values, alias, .. } => {
+                self.factor(table);
+                self.expression(aggregate);
+                values.iter_mut().for_each(|value| self.expression(value));
+                alias
+            }
+            TableFactor::Unpivot { table, alias, .. } => {
+                self.factor(table);
+                alias
+            }
+        };
+        if let Some(alias) = alias {
+            self.define(&mut alias.name);
+        }
+    }
+
+    /// Renames the table aliases that qualify columns in `expr`, and the
+    /// columns a subquery's aliases name behind them, and trims its decimals.
+    fn expression(&self, expr: &mut Expression) {
+        match expr {
+            Expression::CompoundIdentifier(parts) if parts.len() > 1 => {
+                let at = parts.len() - 2;
+                if let Some(alias) = self.lookup(&parts[at]) {
+                    parts[at] = alias;
+                    if let Some(column) = self.lookup(&parts[at + 1]) {
+                        parts[at + 1] = column;
+                    }
+                }
+            }
+            Expression::Decimal(text) => *text = trimmed_decimal(text),
+            Expression::UnaryOperation { operand: inner, .. }
+            | Expression::Grouped(inner)
+            | Expression::Collate { expr: inner, .. }
+            | Expression::Cast { expr: inner, .. } => self.expression(inner),
+            Expression::BinaryOperation { left_operand, right_operand, .. } => {
+                self.expression(left_operand);
+                self.expression(right_operand);
+            }
+            Expression::Function { args, over, .. } => {
+                args.iter_mut().for_each(|arg| self.expression(arg));
+                for window in over.iter_mut() {
+                    window.partition_by.iter_mut().for_each(|expr| self.expression(expr));
+                    window.order_by.iter_mut().for_each(|item| self.expression(&mut item.expr));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A decimal written without leading zeros before the point or trailing
+/// ones after it, keeping a digit on each side: `01.50` is `1.5`, `2.00`
+/// is `2.0`.
+fn trimmed_decimal(text: &str) -> String {
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    let whole = whole.trim_start_matches('0');
+    let fraction = fraction.trim_end_matches('0');
+    format!("{}.{}", if whole.is_empty() { "0" } else { whole }, if fraction.is_empty() { "0" } else { fraction })
+}
+
+/// Parses both queries and checks them for structural equivalence.
+pub fn equivalent_sql(a: &str, b: &str) -> Result<bool, ParseError> {
+    Ok(equivalent(&parse_sql(a)?, &parse_sql(b)?))
+}
+
+/// The canonical form of a statement printed in `dialect`, for logs and
+/// cache keys. Only opaque bodies (functions, trigger blocks) keep their
+/// line breaks.
+pub fn minify(statement: &Statement, dialect: Dialect) -> String {
+    canonicalize(statement).to_sql(dialect)
+}
+
+/// Parses a query in `dialect` and minifies it.
+pub fn minify_sql(sql: &str, dialect: Dialect) -> Result<String, ParseError> {
+    Ok(minify(&parse_sql_with_dialect(sql, dialect)?, dialect))
+}
+
+/// Returns the canonical form of a statement.
+pub fn canonicalize(statement: &Statement) -> Statement {
+    match statement {
+        Statement::Select {
+            hints,
+            columns,
+            wildcard,
+            from,
+            joins,
+            selection,
+            group_by,
+            qualify,
+            order_by,
+            limit,
+            offset,
+            locking,
+        } => {
+            Statement::Select {
+                hints: hints.clone(),
+                columns: columns
+                    .iter()
+                    .map(|item| SelectItem { expr: canonicalize_expression(&item.expr), alias: item.alias.clone() })
+                    .collect(),
+                wildcard: WildcardOptions {
+                    exclude: wildcard.exclude.clone(),
+                    replace: wildcard.replace.iter().map(|(expr, column)| (canonicalize_expression(expr), column.clone())).collect(),
+                },
+                from: Box::new(canonicalize_table_factor(from)),
+                joins: joins
+                    .iter()
+                    .map(|join| Join {
+                        kind: join.kind,
+                        relation: canonicalize_table_factor(&join.relation),
+                        on: join.on.as_ref().map(canonicalize_expression),
+                    })
+                    .collect(),
+                selection: selection.as_ref().map(canonicalize_expression),
+                group_by: group_by.iter().map(canonicalize_grouping_element).collect(),
+                qualify: qualify.as_ref().map(canonicalize_expression),
+                order_by: order_by
+                    .iter()
+                    .map(|item| OrderByExpr { expr: canonicalize_expression(&item.expr), asc: item.asc })
+                    .collect(),
+                limit: *limit,
+                offset: *offset,
+                locking: locking.clone(),
+            }
+        }
+        Statement::CreateTable { table_name, column_list } => Statement::CreateTable {
+            table_name: table_name.clone(),
+            column_list: column_list
+                .iter()
+                .map(|column| TableColumn {
+                    constraints: column
+                        .constraints
+                        .iter()
+                        .map(|constraint| match constraint {
+                            Constraint::Check(expr) => Constraint::Check(canonicalize_expression(expr)),
+                            other => other.clone(),
+                        })
+                        .collect(),
+                    ..column.clone()
+                })
+                .collect(),
+        },
+        Statement::SetOperation { operator, all, left, right } => Statement::SetOperation {
+            operator: *operator,
+            all: *all,
+            left: Box::new(canonicalize(left)),
+            right: Box::new(canonicalize(right)),
+        },
+        Statement::With { recursive, ctes, body } => Statement::With {
+            recursive: *recursive,
+            ctes: ctes
+                .iter()
+                .map(|cte| Cte { query: Box::new(canonicalize(&cte.query)), ..cte.clone() })
+                .collect(),
+            body: Box::new(canonicalize(body)),
+        },
+        Statement::Insert { table_name, columns, rows } => Statement::Insert {
+            table_name: table_name.clone(),
+            columns: columns.clone(),
+            rows: rows.iter().map(|row| row.iter().map(canonicalize_expression).collect()).collect(),
+        },
+        Statement::Values { rows } => Statement::Values {
+            rows: rows.iter().map(|row| row.iter().map(canonicalize_expression).collect()).collect(),
+        },
+        Statement::InsertSelect { table_name, columns, query } => Statement::InsertSelect {
+            table_name: table_name.clone(),
+            columns: columns.clone(),
+            query: Box::new(canonicalize(query)),
+        },
+        // Assignments keep their order, as a later one may read what an earlier one set in some dialects
+        Statement::Update { table_name, alias, assignments, selection } => Statement::Update {
+            table_name: table_name.clone(),
+            alias: alias.clone(),
+            assignments: assignments
+                .iter()
+                .map(|(column, value)| (column.clone(), canonicalize_expression(value)))
+                .collect(),
+            selection: selection.as_ref().map(canonicalize_expression),
+        },
+        Statement::Delete { table_name, alias, selection } => Statement::Delete {
+            table_name: table_name.clone(),
+            alias: alias.clone(),
+            selection: selection.as_ref().map(canonicalize_expression),
+        },
+        Statement::CreateView { or_replace, name, columns, query } => Statement::CreateView {
+            or_replace: *or_replace,
+            name: name.clone(),
+            columns: columns.clone(),
+            query: Box::new(canonicalize(query)),
+        },
+        Statement::Explain { analyze, statement } => {
+            Statement::Explain { analyze: *analyze, statement: Box::new(canonicalize(statement)) }
+        }
+        Statement::Prepare { name, parameter_types, statement } => Statement::Prepare {
+            name: name.clone(),
+            parameter_types: parameter_types.clone(),
+            statement: Box::new(canonicalize(statement)),
+        },
+        other => other.clone(),
+    }
+}
+
+fn canonicalize_table_factor(factor: &TableFactor) -> TableFactor {
+    match factor {
+        TableFactor::Table { name, alias, sample } => TableFactor::Table {
+            name: name.clone(),
+            alias: alias.clone(),
+            sample: sample.as_ref().map(|sample| TableSample {
+                method: sample.method.clone(),
+                percent: canonicalize_expression(&sample.percent),
+                repeatable: sample.repeatable.as_ref().map(canonicalize_expression),
+            }),
+        },
+        TableFactor::AsOf { .. } => factor.clone(),
+        TableFactor::Derived { lateral, subquery, alias } => TableFactor::Derived {
+            lateral: *lateral,
+            subquery: Box::new(canonicalize(subquery)),
+            alias: alias.clone(),
+        },
+        TableFactor::Function { lateral, name, args, alias } => TableFactor::Function {
+            lateral: *lateral,
+            name: name.clone(),
+            args: args.iter().map(canonicalize_expression).collect(),
+            alias: alias.clone(),
+        },
+        TableFactor::Pivot { table, aggregate, column, values, alias } => TableFactor::Pivot {
+            table: Box::new(canonicalize_table_factor(table)),
+            aggregate: canonicalize_expression(aggregate),
+            column: column.clone(),
+            values: values.iter().map(canonicalize_expression).collect(),
+            alias: alias.clone(),
+        },
+        TableFactor::Unpivot { table, value, name, columns, alias } => TableFactor::Unpivot {
+            table: Box::new(canonicalize_table_factor(table)),
+            value: value.clone(),
+            name: name.clone(),
+            columns: columns.clone(),
+            alias: alias.clone(),
+        },
+    }
+}
+
+fn canonicalize_grouping_element(element: &GroupingElement) -> GroupingElement {
+    let list = |exprs: &[Expression]| exprs.iter().map(canonicalize_expression).collect();
+    match element {
+        GroupingElement::Expression(expr) => GroupingElement::Expression(canonicalize_expression(expr)),
+        GroupingElement::Rollup(exprs) => GroupingElement::Rollup(list(exprs)),
+        GroupingElement::Cube(exprs) => GroupingElement::Cube(list(exprs)),
+        GroupingElement::GroupingSets(sets) => GroupingElement::GroupingSets(sets.iter().map(|set| list(set)).collect()),
+    }
+}
+
+/// Returns the canonical form of an expression. Parentheses are only kept
+/// where precedence requires them, so the result prints back as valid SQL.
+pub fn canonicalize_expression(expr: &Expression) -> Expression {
+    regroup(normalize(expr))
+}
+
+/// Sorting key for operands; any total order works as long as it is stable.
+fn key(expr: &Expression) -> String {
+    format!("{:?}", expr)
+}
+
+fn normalize(expr: &Expression) -> Expression {
+    use BinaryOperator::*;
+
+    match expr {
+        Expression::Grouped(inner) => normalize(inner),
+        Expression::UnaryOperation { operator, operand } => Expression::UnaryOperation {
+            operator: operator.clone(),
+            operand: Box::new(normalize(operand)),
+        },
+        Expression::BinaryOperation { operator: operator @ (And | Or | Add | Multiply), .. } => {
+            let mut operands = Vec::new();
+            flatten(expr, operator, &mut operands);
+            operands.sort_by_key(key);
+            if matches!(operator, And | Or) {
+                operands.dedup(); // `a AND a` is just `a`
+            }
+            operands
+                .into_iter()
+                .reduce(|left, right| binary(left, operator.clone(), right))
+                .expect("a binary operation has at least two operands")
+        }
+        Expression::BinaryOperation { left_operand, operator, right_operand } => {
+            let left = normalize(left_operand);
+            let right = normalize(right_operand);
+            let swap = key(&left) > key(&right);
+            match (operator, swap) {
+                (Equals | NotEquals, true) => binary(right, operator.clone(), left),
+                (GreaterThan | GreaterThanOrEqual | LessThan | LessThanOrEqual, true) => {
+                    binary(right, flip(operator), left)
+                }
+                _ => binary(left, operator.clone(), right),
+            }
+        }
+        Expression::Collate { expr, collation } => {
+            Expression::Collate { expr: Box::new(normalize(expr)), collation: collation.clone() }
+        }
+        // The operand of a CAST is parenthesized like a function's argument
+        Expression::Cast { expr, data_type } => {
+            Expression::Cast { expr: Box::new(canonicalize_expression(expr)), data_type: data_type.clone() }
+        }
+        // Arguments are whole expressions of their own
+        Expression::Function { name, args, over } => Expression::Function {
+            name: name.clone(),
+            args: args.iter().map(canonicalize_expression).collect(),
+            over: over.as_ref().map(|window| Box::new(window.map_expressions(canonicalize_expression))),
+        },
+        other => other.clone(),
+    }
+}
+
+/// Collects the normalized operands of a chain of the same associative operator.
+fn flatten(expr: &Expression, operator: &BinaryOperator, out: &mut Vec<Expression>) {
+    match expr {
+        Expression::Grouped(inner) => flatten(inner, operator, out),
+        Expression::BinaryOperation { left_operand, operator: op, right_operand } if op == operator => {
+            flatten(left_operand, operator, out);
+            flatten(right_operand, operator, out);
+        }
+        other => out.push(normalize(other)),
+    }
+}
+
+/// The comparison that holds when the operands are swapped.
+fn flip(operator: &BinaryOperator) -> BinaryOperator {
+    match operator {
+        BinaryOperator::GreaterThan => BinaryOperator::LessThan,
+        BinaryOperator::GreaterThanOrEqual => BinaryOperator::LessThanOrEqual,
+        BinaryOperator::LessThan => BinaryOperator::GreaterThan,
+        BinaryOperator::LessThanOrEqual => BinaryOperator::GreaterThanOrEqual,
+        other => other.clone(),
+    }
+}
+
+fn binary(left: Expression, operator: BinaryOperator, right: Expression) -> Expression {
+    Expression::BinaryOperation { left_operand: Box::new(left), operator, right_operand: Box::new(right) }
+}
+
+/// Puts back the parentheses the tree needs to print correctly, in any
+/// dialect: a binary child that binds looser than its parent (or equally,
+/// on the right) is grouped, and so is a prefix operator whose operand
+/// would otherwise take in the parent's operator.
+fn regroup(expr: Expression) -> Expression {
+    match expr {
+        Expression::BinaryOperation { left_operand, operator, right_operand } => {
+            let left = regroup(*left_operand);
+            let right = regroup(*right_operand);
+            let looser = |child: &Expression, or_equal: bool| {
+                PrecedenceTable::ALL.iter().any(|table| {
+                    let parent = table.of(&operator);
+                    match child {
+                        Expression::BinaryOperation { operator, .. } => {
+                            let child = table.of(operator);
+                            child < parent || (or_equal && child == parent)
+                        }
+                        Expression::UnaryOperation { operator, .. } => table.unary(operator) <= parent,
+                        _ => false,
+                    }
+                })
+            };
+            let left = group_if(left, |child| looser(child, false));
+            let right = group_if(right, |child| looser(child, true));
+            binary(left, operator, right)
+        }
+        Expression::UnaryOperation { operator, operand } => {
+            let operand = regroup(*operand);
+            let operand = group_if(operand, |child| match child {
+                Expression::BinaryOperation { operator: child, .. } => {
+                    PrecedenceTable::ALL.iter().any(|table| table.of(child) < table.unary(&operator))
+                }
+                // `(NOT a) IS NULL` and `-(a IS NULL)`: a prefix operator
+                // inside a postfix one, or the other way round
+                Expression::UnaryOperation { operator: child, .. } if child.is_postfix() != operator.is_postfix() => {
+                    PrecedenceTable::ALL.iter().any(|table| {
+                        let (child, parent) = (table.unary(child), table.unary(&operator));
+                        if operator.is_postfix() { child <= parent } else { child < parent }
+                    })
+                }
+                _ => false,
+            });
+            Expression::UnaryOperation { operator, operand: Box::new(operand) }
+        }
+        // COLLATE binds tighter than any operator
+        Expression::Collate { expr, collation } => {
+            let expr = group_if(regroup(*expr), |child| {
+                matches!(child, Expression::BinaryOperation { .. } | Expression::UnaryOperation { .. })
+            });
+            Expression::Collate { expr: Box::new(expr), collation }
+        }
+        other => other,
+    }
+}
+
+fn group_if(expr: Expression, needs_group: impl Fn(&Expression) -> bool) -> Expression {
+    if needs_group(&expr) {
+        Expression::Grouped(Box::new(expr))
+    } else {
+        expr
+    }
+}
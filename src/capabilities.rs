@@ -0,0 +1,75 @@
+// capabilities.rs — what the grammar and subsystems of this build support
+// Author: Sreerag Devadasan
+//
+// Embedded users can build a smaller parser by leaving default features
+// out: "ddl", "dml", "cte" and "window" each add a part of the grammar,
+// and without one those statements or clauses fail to parse with an error
+// naming the feature, while "engine" adds the in-memory database and the
+// servers, proxy and test runner built on it. `capabilities()` reports
+// the features a build was compiled with, for code that would rather
+// check than find out from a parse error.
+
+use crate::prelude::*;
+
+/// The features this build of the crate was compiled with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    pub std: bool,
+    /// CREATE, DROP and ALTER statements.
+    pub ddl: bool,
+    /// INSERT, MERGE, COPY and LOAD DATA.
+    pub dml: bool,
+    /// WITH queries.
+    pub cte: bool,
+    /// OVER clauses and QUALIFY.
+    pub window: bool,
+    pub engine: bool,
+    pub trace: bool,
+    pub arrow: bool,
+    pub sqlparser: bool,
+    pub mmap: bool,
+}
+
+impl Capabilities {
+    /// Every feature by its Cargo name, with whether it is enabled.
+    pub fn features(&self) -> Vec<(&'static str, bool)> {
+        vec![
+            ("std", self.std),
+            ("ddl", self.ddl),
+            ("dml", self.dml),
+            ("cte", self.cte),
+            ("window", self.window),
+            ("engine", self.engine),
+            ("trace", self.trace),
+            ("arrow", self.arrow),
+            ("sqlparser", self.sqlparser),
+            ("mmap", self.mmap),
+        ]
+    }
+
+    /// The names of the enabled features.
+    pub fn enabled(&self) -> Vec<&'static str> {
+        self.features().into_iter().filter(|(_, enabled)| *enabled).map(|(name, _)| name).collect()
+    }
+}
+
+pub const fn capabilities() -> Capabilities {
+    Capabilities {
+        std: cfg!(feature = "std"),
+        ddl: cfg!(feature = "ddl"),
+        dml: cfg!(feature = "dml"),
+        cte: cfg!(feature = "cte"),
+        window: cfg!(feature = "window"),
+        engine: cfg!(feature = "engine"),
+        trace: cfg!(feature = "trace"),
+        arrow: cfg!(feature = "arrow"),
+        sqlparser: cfg!(feature = "sqlparser"),
+        mmap: cfg!(all(feature = "mmap", unix)),
+    }
+}
+
+/// The error for grammar this build was compiled without.
+#[cfg(not(all(feature = "ddl", feature = "dml", feature = "cte", feature = "window")))]
+pub(crate) fn missing(what: &str, feature: &str) -> String {
+    format!("{} need a build with the \"{}\" feature", what, feature)
+}
@@ -0,0 +1,95 @@
+// catalog.rs — a schema shared between threads, read through snapshots
+// Author: Sreerag Devadasan
+//
+// A `SharedCatalog` lets one thread apply DDL while others (a completion
+// server, a query analyzer) keep working against the schema. Readers take
+// a `CatalogSnapshot`: an immutable, versioned copy that is just a
+// reference count on the current schema, so holding one never blocks a
+// writer and a writer never changes a snapshot under its reader. Writers
+// copy the schema only while an older snapshot is still alive.
+
+use std::ops::Deref;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::ast::Statement;
+use crate::batch::split_statements;
+use crate::parser::parse_sql;
+use crate::schema_diff::{Schema, SchemaError};
+
+#[derive(Debug, Default)]
+struct State {
+    version: u64,
+    schema: Arc<Schema>,
+}
+
+/// A schema that can be read and updated from many threads. Clones share
+/// the same catalog.
+#[derive(Debug, Clone, Default)]
+pub struct SharedCatalog {
+    state: Arc<RwLock<State>>,
+}
+
+/// The schema as of one version of a catalog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatalogSnapshot {
+    pub version: u64,
+    schema: Arc<Schema>,
+}
+
+impl Deref for CatalogSnapshot {
+    type Target = Schema;
+
+    fn deref(&self) -> &Schema {
+        &self.schema
+    }
+}
+
+impl SharedCatalog {
+    pub fn new(schema: Schema) -> Self {
+        Self { state: Arc::new(RwLock::new(State { version: 0, schema: Arc::new(schema) })) }
+    }
+
+    /// The current schema; later updates don't affect it.
+    pub fn snapshot(&self) -> CatalogSnapshot {
+        let state = self.state.read().unwrap_or_else(PoisonError::into_inner);
+        CatalogSnapshot { version: state.version, schema: Arc::clone(&state.schema) }
+    }
+
+    /// Incremented by every update that changes the schema.
+    pub fn version(&self) -> u64 {
+        self.state.read().unwrap_or_else(PoisonError::into_inner).version
+    }
+
+    /// Changes the schema with `update`, which returns whether it changed
+    /// anything, and returns the new version.
+    pub fn update(&self, update: impl FnOnce(&mut Schema) -> bool) -> u64 {
+        let mut state = self.state.write().unwrap_or_else(PoisonError::into_inner);
+        if update(Arc::make_mut(&mut state.schema)) {
+            state.version += 1;
+        }
+        state.version
+    }
+
+    /// Applies a CREATE or DROP TABLE, ignoring other statements; returns
+    /// the new version.
+    pub fn apply(&self, statement: &Statement) -> u64 {
+        self.update(|schema| schema.apply(statement))
+    }
+
+    /// Applies the DDL of a script as one update: either every statement
+    /// parses and the whole script is applied, or nothing is.
+    pub fn apply_sql(&self, script: &str) -> Result<u64, SchemaError> {
+        let statements = split_statements(script)
+            .into_iter()
+            .enumerate()
+            .map(|(index, (_, sql))| {
+                parse_sql(sql).map_err(|error| SchemaError::Parse {
+                    source: "<input>".to_string(),
+                    statement: index + 1,
+                    error,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.update(|schema| statements.iter().fold(false, |changed, statement| schema.apply(statement) | changed)))
+    }
+}
@@ -0,0 +1,762 @@
+// cli.rs — the command line: its commands, their parsing, shell completions and man page
+// Author: Sreerag Devadasan
+//
+// Every command, with its arguments and options, is described once in
+// `COMMANDS`. `parse` checks a command line against that description
+// (unknown commands and options, missing arguments and values, values
+// outside their choices or that aren't dialects), and the usage text, the
+// completion scripts for bash, zsh, fish and PowerShell, and the roff man
+// page are all generated from it, so they can't drift from what the binary
+// accepts. Options may come before or after the command; `--name value`
+// and `--name=value` are both read.
+
+use std::fmt;
+use std::fmt::Write as _;
+
+use crate::dialect::Dialect;
+
+pub const BINARY: &str = "sreerag_devadasan";
+
+/// What the value of an argument or option is, for checking it and for
+/// completing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Text,
+    Number,
+    File,
+    Directory,
+    /// A file or a directory.
+    Path,
+    Dialect,
+    /// One of a fixed set of words.
+    Choice(&'static [&'static str]),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Positional {
+    pub name: &'static str,
+    pub kind: ValueKind,
+    pub required: bool,
+    /// Whether the last positional may be given any number of times.
+    pub repeated: bool,
+}
+
+/// An option, `--name` or `--name value`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flag {
+    pub name: &'static str,
+    /// The name and kind of the option's value, if it takes one.
+    pub value: Option<(&'static str, ValueKind)>,
+    pub about: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Command {
+    pub name: &'static str,
+    pub about: &'static str,
+    pub positionals: &'static [Positional],
+    pub flags: &'static [Flag],
+}
+
+const fn required(name: &'static str, kind: ValueKind) -> Positional {
+    Positional { name, kind, required: true, repeated: false }
+}
+
+const fn optional(name: &'static str, kind: ValueKind) -> Positional {
+    Positional { name, kind, required: false, repeated: false }
+}
+
+const fn repeated(name: &'static str, kind: ValueKind) -> Positional {
+    Positional { name, kind, required: true, repeated: true }
+}
+
+const fn switch(name: &'static str, about: &'static str) -> Flag {
+    Flag { name, value: None, about }
+}
+
+const fn valued(name: &'static str, value: &'static str, kind: ValueKind, about: &'static str) -> Flag {
+    Flag { name, value: Some((value, kind)), about }
+}
+
+const JSON: Flag = switch("json", "print the report as JSON");
+const METRICS: Flag =
+    valued("metrics", "address", ValueKind::Text, "serve Prometheus metrics at http://address/metrics");
+pub const SHELLS: [&str; 4] = ["bash", "zsh", "fish", "powershell"];
+
+/// The options every command takes.
+pub const GLOBAL_FLAGS: &[Flag] = &[
+    valued("color", "when", ValueKind::Choice(&["auto", "always", "never"]), "when to color output"),
+    valued("theme", "theme", ValueKind::Choice(&["light", "dark"]), "the colors to use"),
+    switch("trace", "show how the REPL parses each statement, as a tree (needs the trace feature)"),
+    switch("strict", "accept standard SQL only, rejecting dialect extensions and warning about nonstandard constructs"),
+    switch("help", "print this help"),
+];
+
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "anonymize",
+        about: "anonymize queries read from stdin",
+        positionals: &[],
+        flags: &[switch("hash", "replace literals with hashes rather than placeholders")],
+    },
+    Command {
+        name: "translate",
+        about: "translate queries read from stdin",
+        positionals: &[required("from", ValueKind::Dialect), required("to", ValueKind::Dialect)],
+        flags: &[],
+    },
+    Command {
+        name: "minify",
+        about: "print queries read from stdin in canonical one-line form",
+        positionals: &[optional("dialect", ValueKind::Dialect)],
+        flags: &[],
+    },
+    Command {
+        name: "codegen",
+        about: "print Rust code building the AST of queries read from stdin",
+        positionals: &[optional("dialect", ValueKind::Dialect)],
+        flags: &[],
+    },
+    Command {
+        name: "graph",
+        about: "print the AST of queries read from stdin as a diagram",
+        positionals: &[optional("dialect", ValueKind::Dialect)],
+        flags: &[
+            valued("format", "format", ValueKind::Choice(&["dot", "mermaid"]), "Graphviz DOT (the default) or Mermaid"),
+        ],
+    },
+    Command {
+        name: "lineage",
+        about: "print the source columns of each output column of queries read from stdin",
+        positionals: &[optional("dialect", ValueKind::Dialect)],
+        flags: &[
+            valued("schema", "path", ValueKind::Path, "the CREATE TABLE statements to expand * and resolve names with"),
+            valued(
+                "format",
+                "format",
+                ValueKind::Choice(&["text", "dot", "json"]),
+                "one line per column (the default), a Graphviz DOT graph, or JSON",
+            ),
+        ],
+    },
+    Command {
+        name: "inline-views",
+        about: "print queries read from stdin with the views of a schema inlined as subqueries",
+        positionals: &[required("schema", ValueKind::Path), optional("dialect", ValueKind::Dialect)],
+        flags: &[],
+    },
+    Command {
+        name: "explain",
+        about: "print the plan of queries read from stdin, with estimated row counts",
+        positionals: &[optional("dialect", ValueKind::Dialect)],
+        flags: &[valued("stats", "file", ValueKind::File, "table statistics to estimate with")],
+    },
+    Command {
+        name: "features",
+        about: "print the SQL standard features queries read from stdin need, and the conformance level",
+        positionals: &[optional("dialect", ValueKind::Dialect)],
+        flags: &[],
+    },
+    Command {
+        name: "migrations",
+        about: "check a directory of migrations",
+        positionals: &[required("dir", ValueKind::Directory)],
+        flags: &[switch("ddl-only", "reject statements other than DDL"), JSON],
+    },
+    Command {
+        name: "schema-diff",
+        about: "diff two schema dumps",
+        positionals: &[required("old", ValueKind::Path), required("new", ValueKind::Path)],
+        flags: &[switch("alter", "print the ALTER TABLE statements that apply the changes")],
+    },
+    Command {
+        name: "dead-code",
+        about: "list the tables and columns of a schema that no query of a workload references",
+        positionals: &[required("schema", ValueKind::Path), required("queries", ValueKind::Path)],
+        flags: &[JSON],
+    },
+    Command {
+        name: "query-diff",
+        about: "clause-level diff of two query files",
+        positionals: &[required("old", ValueKind::File), required("new", ValueKind::File)],
+        flags: &[JSON],
+    },
+    Command {
+        name: "conformance",
+        about: "parse pass rates of SQL corpora",
+        positionals: &[repeated("path", ValueKind::Path)],
+        flags: &[JSON, switch("failures", "list the statements that failed")],
+    },
+    Command {
+        name: "logictest",
+        about: "run sqllogictest files against the engine",
+        positionals: &[repeated("file.slt", ValueKind::File)],
+        flags: &[],
+    },
+    Command {
+        name: "check",
+        about: "report the statements of scripts that fail to parse",
+        positionals: &[repeated("file.sql", ValueKind::File)],
+        flags: &[
+            JSON,
+            switch("progress", "draw a progress bar on stderr while each file is parsed"),
+            switch("mmap", "map the files into memory instead of reading them (needs the mmap feature)"),
+        ],
+    },
+    Command {
+        name: "lint",
+        about: "report what the lint rules find in scripts, failing when a rule set to error finds something",
+        positionals: &[repeated("file.sql", ValueKind::File)],
+        flags: &[
+            valued("dialect", "dialect", ValueKind::Dialect, "the dialect to parse statements in"),
+            valued("rule", "name=level", ValueKind::Text, "make a rule an error, warn or off; may be repeated"),
+            valued("large-tables", "tables", ValueKind::Text, "comma-separated tables that need a LIMIT to SELECT"),
+            valued("indexed", "columns", ValueKind::Text, "comma-separated indexed columns, as table.column"),
+        ],
+    },
+    Command {
+        name: "html-report",
+        about: "print an HTML report of a script: its statements, ASTs, lint findings and table dependencies",
+        positionals: &[required("file.sql", ValueKind::File)],
+        flags: &[valued("dialect", "dialect", ValueKind::Dialect, "the dialect to parse statements in")],
+    },
+    Command {
+        name: "render",
+        about: "print the SQL a dbt-style template file expands to",
+        positionals: &[required("file.sql", ValueKind::File)],
+        flags: &[valued("var", "name=value", ValueKind::Text, "a variable of the template; may be repeated")],
+    },
+    Command {
+        name: "pg-server",
+        about: "serve the in-memory engine over the Postgres protocol",
+        positionals: &[optional("address", ValueKind::Text)],
+        flags: &[METRICS],
+    },
+    Command {
+        name: "mysql-server",
+        about: "serve the in-memory engine over the MySQL protocol",
+        positionals: &[optional("address", ValueKind::Text)],
+        flags: &[METRICS],
+    },
+    Command {
+        name: "engine",
+        about: "run queries read from stdin, logging changes to <log>",
+        positionals: &[optional("log", ValueKind::File)],
+        flags: &[valued("versions", "n", ValueKind::Number, "keep the last n versions of the tables for AS OF")],
+    },
+    Command {
+        name: "log-stats",
+        about: "group the statements of a query log by shape",
+        positionals: &[required("log", ValueKind::File)],
+        flags: &[
+            valued("format", "format", ValueKind::Choice(&["plain", "postgres", "mysql"]), "the log's format"),
+            valued("dialect", "dialect", ValueKind::Dialect, "the dialect to parse statements in"),
+            valued("top", "n", ValueKind::Number, "show only the n most frequent shapes"),
+            JSON,
+            switch("csv", "print the shapes as CSV"),
+        ],
+    },
+    Command {
+        name: "tutorial",
+        about: "learn SQL through staged exercises, checked as you answer",
+        positionals: &[optional("dialect", ValueKind::Dialect)],
+        flags: &[],
+    },
+    Command {
+        name: "completions",
+        about: "print a shell completion script",
+        positionals: &[required("shell", ValueKind::Choice(&SHELLS))],
+        flags: &[],
+    },
+    Command { name: "man", about: "print the man page", positionals: &[], flags: &[] },
+];
+
+// === Parsing ===
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliError {
+    UnknownCommand(String),
+    UnknownOption(String),
+    MissingValue(&'static str),
+    UnexpectedValue(&'static str),
+    MissingArgument { command: &'static str, argument: &'static str },
+    UnexpectedArgument(String),
+    InvalidValue { name: &'static str, value: String, expected: String },
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::UnknownCommand(name) => write!(f, "Unknown command: {}", name),
+            CliError::UnknownOption(option) => write!(f, "Unknown option: {}", option),
+            CliError::MissingValue(name) => write!(f, "--{} needs a value", name),
+            CliError::UnexpectedValue(name) => write!(f, "--{} takes no value", name),
+            CliError::MissingArgument { command, argument } => write!(f, "{} needs <{}>", command, argument),
+            CliError::UnexpectedArgument(argument) => write!(f, "Unexpected argument: {}", argument),
+            CliError::InvalidValue { name, value, expected } => {
+                write!(f, "Invalid <{}> {}: expected {}", name, value, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// A command line checked against `COMMANDS`: the command (none for the
+/// REPL), its positional arguments and the options given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Matches {
+    pub command: Option<&'static str>,
+    pub positionals: Vec<String>,
+    options: Vec<(&'static str, Option<String>)>,
+}
+
+impl Matches {
+    pub fn positional(&self, index: usize) -> Option<&str> {
+        self.positionals.get(index).map(String::as_str)
+    }
+
+    /// Whether the option was given.
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| *option == name)
+    }
+
+    /// The value of the option, the last one if it was given more than
+    /// once.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(option, _)| *option == name).and_then(|(_, value)| value.as_deref())
+    }
+
+    /// Every value of the option, in the order given.
+    pub fn values(&self, name: &str) -> Vec<&str> {
+        self.options.iter().filter(|(option, _)| *option == name).filter_map(|(_, value)| value.as_deref()).collect()
+    }
+}
+
+/// Checks a command line, without the binary's name.
+pub fn parse(args: &[String]) -> Result<Matches, CliError> {
+    let mut matches = Matches::default();
+    let mut command: Option<&Command> = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if let Some(option) = arg.strip_prefix("--") {
+            let (name, inline) = match option.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (option, None),
+            };
+            let flags = GLOBAL_FLAGS.iter().chain(command.map_or(&[][..], |command| command.flags));
+            let flag = flags.into_iter().find(|flag| flag.name == name);
+            let flag = flag.ok_or_else(|| CliError::UnknownOption(arg.clone()))?;
+            let value = match (flag.value, inline) {
+                (Some((value_name, kind)), inline) => {
+                    let value = inline.or_else(|| args.next().cloned()).ok_or(CliError::MissingValue(flag.name))?;
+                    check(value_name, kind, &value)?;
+                    Some(value)
+                }
+                (None, Some(_)) => return Err(CliError::UnexpectedValue(flag.name)),
+                (None, None) => None,
+            };
+            matches.options.push((flag.name, value));
+        } else if let Some(command) = command {
+            let index = matches.positionals.len();
+            let last = command.positionals.last().filter(|positional| positional.repeated);
+            let positional = command.positionals.get(index).or(last);
+            let positional = positional.ok_or_else(|| CliError::UnexpectedArgument(arg.clone()))?;
+            check(positional.name, positional.kind, arg)?;
+            matches.positionals.push(arg.clone());
+        } else {
+            let found = COMMANDS.iter().find(|command| command.name == arg);
+            let found = found.ok_or_else(|| CliError::UnknownCommand(arg.clone()))?;
+            matches.command = Some(found.name);
+            command = Some(found);
+        }
+    }
+    if let Some(command) = command {
+        let missing = command.positionals.iter().filter(|positional| positional.required).nth(matches.positionals.len());
+        if let Some(positional) = missing {
+            return Err(CliError::MissingArgument { command: command.name, argument: positional.name });
+        }
+    }
+    Ok(matches)
+}
+
+fn check(name: &'static str, kind: ValueKind, value: &str) -> Result<(), CliError> {
+    let expected = match kind {
+        ValueKind::Number if value.parse::<usize>().is_err() => "a number".to_string(),
+        ValueKind::Dialect if Dialect::from_name(value).is_none() => format!("one of {}", dialect_names().join(", ")),
+        ValueKind::Choice(choices) if !choices.iter().any(|choice| choice.eq_ignore_ascii_case(value)) => {
+            format!("one of {}", choices.join(", "))
+        }
+        _ => return Ok(()),
+    };
+    Err(CliError::InvalidValue { name, value: value.to_string(), expected })
+}
+
+fn dialect_names() -> Vec<&'static str> {
+    Dialect::ALL.iter().map(Dialect::name).collect()
+}
+
+/// The words a value of `kind` may be, if they're a fixed set.
+fn words(kind: ValueKind) -> Option<Vec<&'static str>> {
+    match kind {
+        ValueKind::Dialect => Some(dialect_names()),
+        ValueKind::Choice(choices) => Some(choices.to_vec()),
+        _ => None,
+    }
+}
+
+// === Usage ===
+
+impl Flag {
+    /// `--name` or `--name <value>`.
+    fn synopsis(&self) -> String {
+        match self.value {
+            Some((_, ValueKind::Choice(choices))) if choices.len() <= 3 => {
+                format!("--{} {}", self.name, choices.join("|"))
+            }
+            Some((value, _)) => format!("--{} <{}>", self.name, value),
+            None => format!("--{}", self.name),
+        }
+    }
+}
+
+impl Command {
+    /// The command with its arguments and options, e.g.
+    /// `check <file.sql>... [--json]`.
+    pub fn synopsis(&self) -> String {
+        let mut parts = vec![self.name.to_string()];
+        for positional in self.positionals {
+            let mut part = format!("<{}>", positional.name);
+            if positional.repeated {
+                part.push_str("...");
+            }
+            parts.push(if positional.required { part } else { format!("[{}]", part) });
+        }
+        parts.extend(self.flags.iter().map(|flag| format!("[{}]", flag.synopsis())));
+        parts.join(" ")
+    }
+}
+
+/// The help text: the global options and every command.
+pub fn usage() -> String {
+    let globals: Vec<String> = GLOBAL_FLAGS.iter().map(|flag| format!("[{}]", flag.synopsis())).collect();
+    let mut text = format!("Usage: {} {} [command]\n", BINARY, globals.join(" "));
+    let mut line = |synopsis: &str, about: &str| {
+        if synopsis.len() < 38 {
+            let _ = writeln!(text, "  {:<38}{}", synopsis, about);
+        } else {
+            let _ = writeln!(text, "  {}\n  {:<38}{}", synopsis, "", about);
+        }
+    };
+    line("(no command)", "interactive REPL, set up by ~/.minisqlrc and MINISQL_* variables");
+    for command in COMMANDS {
+        line(&command.synopsis(), command.about);
+    }
+    let _ = writeln!(text, "Dialects: {}", dialect_names().join(", "));
+    text
+}
+
+// === Completions ===
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+}
+
+impl Shell {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            "powershell" => Some(Shell::PowerShell),
+            _ => None,
+        }
+    }
+}
+
+/// The completion script for `shell`.
+pub fn completions(shell: Shell) -> String {
+    match shell {
+        Shell::Bash => bash(),
+        Shell::Zsh => zsh(),
+        Shell::Fish => fish(),
+        Shell::PowerShell => powershell(),
+    }
+}
+
+fn command_names() -> Vec<&'static str> {
+    COMMANDS.iter().map(|command| command.name).collect()
+}
+
+/// Every option of `command`, global ones included, as `--name`.
+fn option_words(command: Option<&Command>) -> Vec<String> {
+    let flags = command.map_or(&[][..], |command| command.flags).iter().chain(GLOBAL_FLAGS);
+    flags.map(|flag| format!("--{}", flag.name)).collect()
+}
+
+/// Every option that takes a value, each name once.
+fn valued_flags() -> Vec<&'static Flag> {
+    let mut flags: Vec<&Flag> = Vec::new();
+    for flag in GLOBAL_FLAGS.iter().chain(COMMANDS.iter().flat_map(|command| command.flags)) {
+        if flag.value.is_some() && !flags.iter().any(|seen| seen.name == flag.name) {
+            flags.push(flag);
+        }
+    }
+    flags
+}
+
+/// The bash expression completing a value of `kind` into COMPREPLY.
+fn bash_values(kind: ValueKind) -> String {
+    match (kind, words(kind)) {
+        (_, Some(words)) => format!("COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", words.join(" ")),
+        (ValueKind::File | ValueKind::Path, _) => "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string(),
+        (ValueKind::Directory, _) => "COMPREPLY=($(compgen -d -- \"$cur\"))".to_string(),
+        _ => "COMPREPLY=()".to_string(),
+    }
+}
+
+fn bash() -> String {
+    let valued = valued_flags();
+    let valued_names: Vec<String> = valued.iter().map(|flag| format!("--{}", flag.name)).collect();
+    let mut script = format!("# bash completion for {}\n_{}() {{\n", BINARY, BINARY);
+    script.push_str("    local cur=${COMP_WORDS[COMP_CWORD]} prev=${COMP_WORDS[COMP_CWORD-1]}\n");
+    script.push_str("    local command=\"\" n=0 i\n");
+    script.push_str("    for ((i = 1; i < COMP_CWORD; i++)); do\n        case ${COMP_WORDS[i]} in\n");
+    let _ = writeln!(script, "            {}) ((i++)) ;;", valued_names.join("|"));
+    script.push_str("            -*) ;;\n");
+    script.push_str("            *) if [[ -z $command ]]; then command=${COMP_WORDS[i]}; else ((n++)); fi ;;\n");
+    script.push_str("        esac\n    done\n    case $prev in\n");
+    for flag in &valued {
+        let (_, kind) = flag.value.unwrap_or(("", ValueKind::Text));
+        let _ = writeln!(script, "        --{}) {}; return ;;", flag.name, bash_values(kind));
+    }
+    script.push_str("    esac\n    if [[ $cur == -* ]]; then\n        case $command in\n");
+    for command in COMMANDS {
+        let words = option_words(Some(command)).join(" ");
+        let _ = writeln!(script, "            {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;", command.name, words);
+    }
+    let words = option_words(None).join(" ");
+    let _ = writeln!(script, "            *) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;", words);
+    script.push_str("        esac\n        return\n    fi\n    case $command in\n");
+    let _ = writeln!(script, "        \"\") COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;", command_names().join(" "));
+    for command in COMMANDS.iter().filter(|command| !command.positionals.is_empty()) {
+        let _ = writeln!(script, "        {})\n            case $n in", command.name);
+        for (index, positional) in command.positionals.iter().enumerate() {
+            let pattern = if positional.repeated { "*".to_string() } else { index.to_string() };
+            let _ = writeln!(script, "                {}) {} ;;", pattern, bash_values(positional.kind));
+        }
+        script.push_str("            esac ;;\n");
+    }
+    let _ = write!(script, "    esac\n}}\ncomplete -F _{} {}\n", BINARY, BINARY);
+    script
+}
+
+/// Text for a single-quoted zsh or fish string.
+fn quoted(text: &str) -> String {
+    text.replace('\'', "'\\''")
+}
+
+/// The zsh `_arguments` action completing a value of `kind`.
+fn zsh_action(kind: ValueKind) -> String {
+    match (kind, words(kind)) {
+        (_, Some(words)) => format!("({})", words.join(" ")),
+        (ValueKind::File | ValueKind::Path, _) => "_files".to_string(),
+        (ValueKind::Directory, _) => "_files -/".to_string(),
+        _ => " ".to_string(),
+    }
+}
+
+/// The `_arguments` spec of an option, e.g. `'--top[about]:n: '`.
+fn zsh_flag(flag: &Flag) -> String {
+    let about = quoted(&flag.about.replace('[', "\\[").replace(']', "\\]"));
+    match flag.value {
+        Some((value, kind)) => format!("'--{}[{}]:{}:{}'", flag.name, about, value, zsh_action(kind)),
+        None => format!("'--{}[{}]'", flag.name, about),
+    }
+}
+
+fn zsh() -> String {
+    let mut script = format!("#compdef {}\n\n_{}() {{\n    local -a commands\n    commands=(\n", BINARY, BINARY);
+    for command in COMMANDS {
+        let _ = writeln!(script, "        '{}:{}'", command.name, quoted(&command.about.replace(':', "\\:")));
+    }
+    script.push_str("    )\n    local state line\n    _arguments -C \\\n");
+    for flag in GLOBAL_FLAGS {
+        let _ = writeln!(script, "        {} \\", zsh_flag(flag));
+    }
+    script.push_str("        '1: :->command' \\\n        '*:: :->args'\n    case $state in\n");
+    script.push_str("        command) _describe command commands ;;\n        args)\n            case $line[1] in\n");
+    for command in COMMANDS {
+        let mut specs: Vec<String> = command.flags.iter().chain(GLOBAL_FLAGS).map(zsh_flag).collect();
+        for (index, positional) in command.positionals.iter().enumerate() {
+            let action = zsh_action(positional.kind);
+            specs.push(match (positional.repeated, positional.required) {
+                (true, _) => format!("'*:{}:{}'", positional.name, action),
+                (false, true) => format!("'{}:{}:{}'", index + 1, positional.name, action),
+                (false, false) => format!("'{}::{}:{}'", index + 1, positional.name, action),
+            });
+        }
+        let _ = writeln!(script, "                {}) _arguments {} ;;", command.name, specs.join(" "));
+    }
+    let _ = write!(script, "            esac ;;\n    esac\n}}\n\n_{} \"$@\"\n", BINARY);
+    script
+}
+
+/// The fish `complete` arguments completing a value of `kind`.
+fn fish_values(kind: ValueKind) -> String {
+    match (kind, words(kind)) {
+        (_, Some(words)) => format!("-x -a '{}'", words.join(" ")),
+        (ValueKind::File | ValueKind::Path, _) => "-F".to_string(),
+        (ValueKind::Directory, _) => "-x -a '(__fish_complete_directories)'".to_string(),
+        _ => "-x".to_string(),
+    }
+}
+
+fn fish_flag(condition: &str, flag: &Flag) -> String {
+    let values = flag.value.map_or(String::new(), |(_, kind)| format!(" {}", fish_values(kind)));
+    format!("complete -c {}{} -l {}{} -d '{}'\n", BINARY, condition, flag.name, values, quoted(flag.about))
+}
+
+fn fish() -> String {
+    let commands = command_names().join(" ");
+    let mut script = format!("# fish completion for {}\ncomplete -c {} -f\n", BINARY, BINARY);
+    for flag in GLOBAL_FLAGS {
+        script.push_str(&fish_flag("", flag));
+    }
+    for command in COMMANDS {
+        let _ = writeln!(
+            script,
+            "complete -c {} -n 'not __fish_seen_subcommand_from {}' -a {} -d '{}'",
+            BINARY,
+            commands,
+            command.name,
+            quoted(command.about)
+        );
+    }
+    for command in COMMANDS {
+        let condition = format!(" -n '__fish_seen_subcommand_from {}'", command.name);
+        for flag in command.flags {
+            script.push_str(&fish_flag(&condition, flag));
+        }
+        // Fish can't tell positionals apart, so they all complete the same
+        if let Some(positional) = command.positionals.first() {
+            let _ = writeln!(script, "complete -c {}{} {}", BINARY, condition, fish_values(positional.kind));
+        }
+    }
+    script
+}
+
+/// A PowerShell array of single-quoted words.
+fn powershell_array(words: &[String]) -> String {
+    let words: Vec<String> = words.iter().map(|word| format!("'{}'", word.replace('\'', "''"))).collect();
+    format!("@({})", words.join(", "))
+}
+
+fn powershell() -> String {
+    let mut script = format!("# PowerShell completion for {}\n", BINARY);
+    let _ = writeln!(script, "Register-ArgumentCompleter -Native -CommandName {} -ScriptBlock {{", BINARY);
+    script.push_str("    param($wordToComplete, $commandAst, $cursorPosition)\n");
+    script.push_str("    $words = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object { $_.ToString() })\n");
+    script.push_str("    if ($wordToComplete) { $words = @($words | Select-Object -SkipLast 1) }\n");
+    script.push_str("    $command = $words | Where-Object { $_ -notlike '-*' } | Select-Object -First 1\n");
+    script.push_str("    $previous = if ($words.Count) { $words[-1] } else { '' }\n");
+    script.push_str("    $candidates = switch ($previous) {\n");
+    for flag in valued_flags() {
+        if let Some(words) = flag.value.and_then(|(_, kind)| words(kind)) {
+            let words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+            let _ = writeln!(script, "        '--{}' {{ {} }}", flag.name, powershell_array(&words));
+        }
+    }
+    script.push_str("        default {\n            switch ($command) {\n");
+    for command in COMMANDS {
+        let mut candidates = option_words(Some(command));
+        if let Some(words) = command.positionals.first().and_then(|positional| words(positional.kind)) {
+            candidates.extend(words.iter().map(|word| word.to_string()));
+        }
+        let _ = writeln!(script, "                '{}' {{ {} }}", command.name, powershell_array(&candidates));
+    }
+    let mut candidates: Vec<String> = command_names().iter().map(|name| name.to_string()).collect();
+    candidates.extend(option_words(None));
+    let _ = writeln!(script, "                default {{ {} }}", powershell_array(&candidates));
+    script.push_str("            }\n        }\n    }\n");
+    script.push_str("    $candidates | Where-Object { $_ -like \"$wordToComplete*\" } | ForEach-Object {\n");
+    script.push_str("        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)\n");
+    script.push_str("    }\n}\n");
+    script
+}
+
+// === Man page ===
+
+/// Text escaped for roff.
+fn roff(text: &str) -> String {
+    text.replace('\\', "\\e").replace('-', "\\-")
+}
+
+/// A roff paragraph tag for an option, e.g. `\fB\-\-top\fR \fIn\fR`.
+fn roff_flag(flag: &Flag) -> String {
+    match flag.value {
+        Some((value, _)) => format!("\\fB\\-\\-{}\\fR \\fI{}\\fR", roff(flag.name), roff(value)),
+        None => format!("\\fB\\-\\-{}\\fR", roff(flag.name)),
+    }
+}
+
+/// The man page, in roff.
+pub fn man_page() -> String {
+    let mut page = format!(
+        ".TH {} 1 \"\" \"{} {}\" \"User Commands\"\n",
+        BINARY.to_ascii_uppercase().replace('_', "\\_"),
+        roff(BINARY),
+        env!("CARGO_PKG_VERSION")
+    );
+    let _ = writeln!(page, ".SH NAME\n{} \\- parse, translate and run SQL", roff(BINARY));
+    let _ = writeln!(page, ".SH SYNOPSIS\n.B {}\n[\\fIoptions\\fR] [\\fIcommand\\fR] [\\fIarguments\\fR]", roff(BINARY));
+    page.push_str(".SH DESCRIPTION\nWith no command, reads SQL statements interactively and prints what they parse to.\n");
+    page.push_str("The commands below read queries from standard input or from files, diff schemas and queries,\n");
+    page.push_str("and serve an in-memory engine over the Postgres and MySQL protocols.\n");
+    let _ = writeln!(page, "Dialects are {}.", dialect_names().join(", "));
+    page.push_str(".SH OPTIONS\n");
+    for flag in GLOBAL_FLAGS {
+        let _ = writeln!(page, ".TP\n{}\n{}.", roff_flag(flag), roff(&capitalized(flag.about)));
+    }
+    page.push_str(".SH COMMANDS\n");
+    for command in COMMANDS {
+        let arguments: Vec<String> = command
+            .positionals
+            .iter()
+            .map(|positional| {
+                let name = format!("\\fI{}\\fR{}", roff(positional.name), if positional.repeated { "..." } else { "" });
+                if positional.required { name } else { format!("[{}]", name) }
+            })
+            .collect();
+        let _ = writeln!(page, ".TP\n\\fB{}\\fR {}\n{}.", roff(command.name), arguments.join(" "), roff(&capitalized(command.about)));
+        if !command.flags.is_empty() {
+            page.push_str(".RS\n");
+            for flag in command.flags {
+                let _ = writeln!(page, ".TP\n{}\n{}.", roff_flag(flag), roff(&capitalized(flag.about)));
+            }
+            page.push_str(".RE\n");
+        }
+    }
+    page.push_str(".SH ENVIRONMENT\n");
+    for (name, about) in [
+        ("MINISQLRC", "The REPL's settings file, instead of ~/.minisqlrc."),
+        ("MINISQL_DIALECT, MINISQL_PROMPT, MINISQL_COLOR, MINISQL_THEME", "Override the settings of the same name."),
+        ("MINISQL_OUTPUT, MINISQL_HISTORY_SIZE, MINISQL_SCHEMA", "Override the settings of the same name."),
+        ("NO_COLOR", "Turns color off unless MINISQL_COLOR or \\-\\-color turns it on."),
+        ("SQL_TRACE", "Prints the parse call tree, when built with the trace feature."),
+    ] {
+        let _ = writeln!(page, ".TP\n.B {}\n{}", name, about);
+    }
+    page.push_str(".SH FILES\n.TP\n.I ~/.minisqlrc\nThe REPL's settings, in TOML.\n");
+    page.push_str(".TP\n.I ~/.minisql_history\nThe lines entered in the REPL.\n");
+    page
+}
+
+fn capitalized(text: &str) -> String {
+    let mut chars = text.chars();
+    chars.next().map_or(String::new(), |first| first.to_uppercase().chain(chars).collect())
+}
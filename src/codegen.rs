@@ -0,0 +1,786 @@
+// codegen.rs — Rust source that constructs a parsed statement's AST
+// Author: Sreerag Devadasan
+//
+// `to_rust` turns a statement into the Rust expression that builds it, so
+// a parser test can be written by parsing the SQL once and pasting the
+// output instead of typing out the tree by hand. The code uses the AST's
+// own constructors: enum and struct literals, `Box::new`, `vec![...]`,
+// `Ident::new` and `.to_string()`. It expects `sreerag_devadasan::ast::*`
+// (plus `span::Span` for unparsed statements and `extension::Extension`
+// for custom ones) to be in scope. A custom statement is written as its
+// own `Debug` output, which is its code when `Debug` is derived. Lines are
+// broken rustfmt-style once a node no longer fits in 100 columns.
+
+use crate::prelude::*;
+use crate::ast::{
+    Constraint, CopyOption, CopyTarget, Cte, DBType, Expression, FunctionParameter, GrantObject, GrantedPrivilege,
+    GroupingElement, Hint, Join, LoadDataFormat, LockingClause, MergeAction, MergeClause, MergeSource, OrderByExpr,
+    RoleOption,
+    RoutineType, SelectItem, Statement, TableAlias, TableColumn, TableFactor, TableSample, TriggerBody,
+    WildcardOptions, WindowSpec,
+};
+use crate::ident::Ident;
+use crate::dialect::Dialect;
+use crate::parser::parse_sql_with_dialect;
+use crate::tokenizer::ParseError;
+
+const MAX_WIDTH: usize = 100;
+const INDENT: usize = 4;
+
+/// The Rust expression that constructs `statement`.
+pub fn to_rust(statement: &Statement) -> String {
+    let mut out = String::new();
+    statement_code(statement).write(0, 0, &mut out);
+    out
+}
+
+/// Parses `sql` in `dialect` and returns the Rust code for its AST.
+pub fn sql_to_rust(sql: &str, dialect: Dialect) -> Result<String, ParseError> {
+    Ok(to_rust(&parse_sql_with_dialect(sql, dialect)?))
+}
+
+/// A node of the generated code, laid out on one line when it fits.
+/// `graph` reads the same tree to draw the AST.
+pub(crate) enum Code {
+    Atom(String),
+    /// `head(item, ...)`, `vec![item, ...]` or a tuple.
+    Seq { head: &'static str, open: char, close: char, items: Vec<Code> },
+    /// `path { field: value, ... }`
+    Struct { path: &'static str, fields: Vec<(&'static str, Code)> },
+}
+
+impl Code {
+    fn flat(&self) -> String {
+        match self {
+            Code::Atom(text) => text.clone(),
+            Code::Seq { head, open, close, items } => {
+                let items: Vec<String> = items.iter().map(Code::flat).collect();
+                format!("{}{}{}{}", head, open, items.join(", "), close)
+            }
+            Code::Struct { path, fields } => {
+                let fields: Vec<String> = fields.iter().map(|(name, value)| format!("{}: {}", name, value.flat())).collect();
+                format!("{} {{ {} }}", path, fields.join(", "))
+            }
+        }
+    }
+
+    /// Appends the code to `out` at `column`, with continuation lines
+    /// indented by `indent`.
+    fn write(&self, column: usize, indent: usize, out: &mut String) {
+        let flat = self.flat();
+        if column + flat.len() <= MAX_WIDTH {
+            out.push_str(&flat);
+            return;
+        }
+        let inner = " ".repeat(indent + INDENT);
+        match self {
+            Code::Atom(text) => out.push_str(text),
+            // A single argument hugs the parentheses: `Some(Foo {` ... `})`
+            Code::Seq { head, open, close, items } if items.len() == 1 => {
+                out.push_str(head);
+                out.push(*open);
+                items[0].write(column + head.len() + 1, indent, out);
+                out.push(*close);
+            }
+            Code::Seq { head, open, close, items } => {
+                out.push_str(head);
+                out.push(*open);
+                out.push('\n');
+                for item in items {
+                    out.push_str(&inner);
+                    item.write(indent + INDENT, indent + INDENT, out);
+                    out.push_str(",\n");
+                }
+                out.push_str(&" ".repeat(indent));
+                out.push(*close);
+            }
+            Code::Struct { path, fields } => {
+                out.push_str(path);
+                out.push_str(" {\n");
+                for (name, value) in fields {
+                    out.push_str(&inner);
+                    out.push_str(name);
+                    out.push_str(": ");
+                    value.write(indent + INDENT + name.len() + 2, indent + INDENT, out);
+                    out.push_str(",\n");
+                }
+                out.push_str(&" ".repeat(indent));
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn atom(text: impl Into<String>) -> Code {
+    Code::Atom(text.into())
+}
+
+fn call(head: &'static str, items: Vec<Code>) -> Code {
+    Code::Seq { head, open: '(', close: ')', items }
+}
+
+fn structure(path: &'static str, fields: Vec<(&'static str, Code)>) -> Code {
+    Code::Struct { path, fields }
+}
+
+fn string(text: &str) -> Code {
+    atom(format!("{:?}.to_string()", text))
+}
+
+fn boxed(code: Code) -> Code {
+    call("Box::new", vec![code])
+}
+
+fn list<T>(items: &[T], item: impl Fn(&T) -> Code) -> Code {
+    if items.is_empty() {
+        atom("Vec::new()")
+    } else {
+        Code::Seq { head: "vec!", open: '[', close: ']', items: items.iter().map(item).collect() }
+    }
+}
+
+fn option<T>(value: &Option<T>, some: impl Fn(&T) -> Code) -> Code {
+    match value {
+        Some(value) => call("Some", vec![some(value)]),
+        None => atom("None"),
+    }
+}
+
+fn strings(items: &[String]) -> Code {
+    list(items, |item| string(item))
+}
+
+fn optional_string(value: &Option<String>) -> Code {
+    option(value, |text| string(text))
+}
+
+/// `Ident::new("name")`, or `Ident::quoted` for a quoted name.
+fn ident(name: &Ident) -> Code {
+    let value = atom(format!("{:?}", name.value));
+    match name.quote {
+        None => call("Ident::new", vec![value]),
+        Some(quote) => call("Ident::quoted", vec![value, atom(format!("QuoteStyle::{:?}", quote))]),
+    }
+}
+
+fn idents(names: &[Ident]) -> Code {
+    list(names, ident)
+}
+
+fn optional_ident(name: &Option<Ident>) -> Code {
+    option(name, ident)
+}
+
+fn expression(expr: &Expression) -> Code {
+    match expr {
+        Expression::Identifier(name) => call("Expression::Identifier", vec![ident(name)]),
+        Expression::CompoundIdentifier(parts) => call("Expression::CompoundIdentifier", vec![idents(parts)]),
+        Expression::Number(n) => call("Expression::Number", vec![atom(n.to_string())]),
+        Expression::Decimal(text) => call("Expression::Decimal", vec![string(text)]),
+        Expression::String(text) => call("Expression::String", vec![string(text)]),
+        Expression::UnaryOperation { operator, operand } => structure(
+            "Expression::UnaryOperation",
+            vec![
+                ("operator", atom(format!("UnaryOperator::{:?}", operator))),
+                ("operand", boxed(expression(operand))),
+            ],
+        ),
+        Expression::BinaryOperation { left_operand, operator, right_operand } => structure(
+            "Expression::BinaryOperation",
+            vec![
+                ("left_operand", boxed(expression(left_operand))),
+                ("operator", atom(format!("BinaryOperator::{:?}", operator))),
+                ("right_operand", boxed(expression(right_operand))),
+            ],
+        ),
+        Expression::Boolean(value) => call("Expression::Boolean", vec![atom(value.to_string())]),
+        Expression::Null => atom("Expression::Null"),
+        Expression::Grouped(inner) => call("Expression::Grouped", vec![boxed(expression(inner))]),
+        Expression::Placeholder(name) => call("Expression::Placeholder", vec![string(name)]),
+        Expression::Function { name, args, over } => structure(
+            "Expression::Function",
+            vec![
+                ("name", string(name)),
+                ("args", list(args, expression)),
+                ("over", option(over, |window| boxed(window_spec(window)))),
+            ],
+        ),
+        Expression::TypedString { data_type: ty, value } => {
+            structure("Expression::TypedString", vec![("data_type", data_type(ty)), ("value", string(value))])
+        }
+        Expression::Collate { expr, collation } => structure(
+            "Expression::Collate",
+            vec![("expr", boxed(expression(expr))), ("collation", ident(collation))],
+        ),
+        Expression::Cast { expr, data_type: ty } => {
+            structure("Expression::Cast", vec![("expr", boxed(expression(expr))), ("data_type", data_type(ty))])
+        }
+        Expression::PrefixedString { prefix, value } => structure(
+            "Expression::PrefixedString",
+            vec![("prefix", atom(format!("StringPrefix::{:?}", prefix))), ("value", string(value))],
+        ),
+    }
+}
+
+fn expressions(exprs: &[Expression]) -> Code {
+    list(exprs, expression)
+}
+
+fn rows(rows: &[Vec<Expression>]) -> Code {
+    list(rows, |row| expressions(row))
+}
+
+fn window_spec(window: &WindowSpec) -> Code {
+    if *window == WindowSpec::default() {
+        return atom("WindowSpec::default()");
+    }
+    structure(
+        "WindowSpec",
+        vec![("partition_by", expressions(&window.partition_by)), ("order_by", list(&window.order_by, order_by_expr))],
+    )
+}
+
+fn order_by_expr(item: &OrderByExpr) -> Code {
+    structure(
+        "OrderByExpr",
+        vec![("expr", expression(&item.expr)), ("asc", option(&item.asc, |asc| atom(asc.to_string())))],
+    )
+}
+
+fn data_type(data_type: &DBType) -> Code {
+    match data_type {
+        DBType::Int => atom("DBType::Int"),
+        DBType::Varchar(length) => call("DBType::Varchar", vec![atom(length.to_string())]),
+        DBType::Bool => atom("DBType::Bool"),
+        DBType::Date => atom("DBType::Date"),
+        DBType::Time => atom("DBType::Time"),
+        DBType::Timestamp => atom("DBType::Timestamp"),
+        DBType::Decimal(precision, scale) => {
+            call("DBType::Decimal", vec![atom(precision.to_string()), atom(scale.to_string())])
+        }
+    }
+}
+
+fn constraint(constraint: &Constraint) -> Code {
+    match constraint {
+        Constraint::NotNull => atom("Constraint::NotNull"),
+        Constraint::PrimaryKey => atom("Constraint::PrimaryKey"),
+        Constraint::Check(expr) => call("Constraint::Check", vec![expression(expr)]),
+        Constraint::Collate(name) => call("Constraint::Collate", vec![ident(name)]),
+    }
+}
+
+fn table_column(column: &TableColumn) -> Code {
+    structure(
+        "TableColumn",
+        vec![
+            ("column_name", ident(&column.column_name)),
+            ("column_type", data_type(&column.column_type)),
+            ("constraints", list(&column.constraints, constraint)),
+        ],
+    )
+}
+
+fn function_parameter(parameter: &FunctionParameter) -> Code {
+    structure(
+        "FunctionParameter",
+        vec![("name", optional_string(&parameter.name)), ("data_type", routine_type(&parameter.data_type))],
+    )
+}
+
+fn routine_type(data_type: &RoutineType) -> Code {
+    match data_type {
+        RoutineType::Known(known) => call("RoutineType::Known", vec![self::data_type(known)]),
+        RoutineType::Named(name) => call("RoutineType::Named", vec![string(name)]),
+    }
+}
+
+fn role_option(option: &RoleOption) -> Code {
+    match option {
+        RoleOption::Login(value) => call("RoleOption::Login", vec![atom(value.to_string())]),
+        RoleOption::Superuser(value) => call("RoleOption::Superuser", vec![atom(value.to_string())]),
+        RoleOption::CreateDb(value) => call("RoleOption::CreateDb", vec![atom(value.to_string())]),
+        RoleOption::CreateRole(value) => call("RoleOption::CreateRole", vec![atom(value.to_string())]),
+        RoleOption::Password(expr) => call("RoleOption::Password", vec![expression(expr)]),
+    }
+}
+
+fn hint(hint: &Hint) -> Code {
+    structure("Hint", vec![("name", string(&hint.name)), ("args", strings(&hint.args))])
+}
+
+fn table_alias(alias: &TableAlias) -> Code {
+    structure("TableAlias", vec![("name", ident(&alias.name)), ("columns", idents(&alias.columns))])
+}
+
+fn table_sample(sample: &TableSample) -> Code {
+    structure(
+        "TableSample",
+        vec![
+            ("method", string(&sample.method)),
+            ("percent", expression(&sample.percent)),
+            ("repeatable", option(&sample.repeatable, expression)),
+        ],
+    )
+}
+
+fn table_factor(factor: &TableFactor) -> Code {
+    match factor {
+        TableFactor::Table { name, alias, sample } => structure(
+            "TableFactor::Table",
+            vec![
+                ("name", ident(name)),
+                ("alias", option(alias, table_alias)),
+                ("sample", option(sample, table_sample)),
+            ],
+        ),
+        TableFactor::AsOf { name, version, alias } => structure(
+            "TableFactor::AsOf",
+            vec![
+                ("name", ident(name)),
+                ("version", atom(version.to_string())),
+                ("alias", option(alias, table_alias)),
+            ],
+        ),
+        TableFactor::Derived { lateral, subquery, alias } => structure(
+            "TableFactor::Derived",
+            vec![
+                ("lateral", atom(lateral.to_string())),
+                ("subquery", boxed(statement_code(subquery))),
+                ("alias", option(alias, table_alias)),
+            ],
+        ),
+        TableFactor::Function { lateral, name, args, alias } => structure(
+            "TableFactor::Function",
+            vec![
+                ("lateral", atom(lateral.to_string())),
+                ("name", string(name)),
+                ("args", expressions(args)),
+                ("alias", option(alias, table_alias)),
+            ],
+        ),
+        TableFactor::Pivot { table, aggregate, column, values, alias } => structure(
+            "TableFactor::Pivot",
+            vec![
+                ("table", boxed(table_factor(table))),
+                ("aggregate", expression(aggregate)),
+                ("column", ident(column)),
+                ("values", expressions(values)),
+                ("alias", option(alias, table_alias)),
+            ],
+        ),
+        TableFactor::Unpivot { table, value, name, columns, alias } => structure(
+            "TableFactor::Unpivot",
+            vec![
+                ("table", boxed(table_factor(table))),
+                ("value", ident(value)),
+                ("name", ident(name)),
+                ("columns", idents(columns)),
+                ("alias", option(alias, table_alias)),
+            ],
+        ),
+    }
+}
+
+fn join(join: &Join) -> Code {
+    structure(
+        "Join",
+        vec![
+            ("kind", atom(format!("JoinKind::{:?}", join.kind))),
+            ("relation", table_factor(&join.relation)),
+            ("on", option(&join.on, expression)),
+        ],
+    )
+}
+
+fn select_item(item: &SelectItem) -> Code {
+    structure("SelectItem", vec![("expr", expression(&item.expr)), ("alias", optional_ident(&item.alias))])
+}
+
+fn wildcard_options(wildcard: &WildcardOptions) -> Code {
+    if wildcard.is_empty() {
+        return atom("WildcardOptions::default()");
+    }
+    let replace = list(&wildcard.replace, |(expr, name)| Code::Seq {
+        head: "",
+        open: '(',
+        close: ')',
+        items: vec![expression(expr), ident(name)],
+    });
+    structure("WildcardOptions", vec![("exclude", idents(&wildcard.exclude)), ("replace", replace)])
+}
+
+fn grouping_element(element: &GroupingElement) -> Code {
+    match element {
+        GroupingElement::Expression(expr) => call("GroupingElement::Expression", vec![expression(expr)]),
+        GroupingElement::Rollup(exprs) => call("GroupingElement::Rollup", vec![expressions(exprs)]),
+        GroupingElement::Cube(exprs) => call("GroupingElement::Cube", vec![expressions(exprs)]),
+        GroupingElement::GroupingSets(sets) => call("GroupingElement::GroupingSets", vec![rows(sets)]),
+    }
+}
+
+fn locking_clause(clause: &LockingClause) -> Code {
+    structure(
+        "LockingClause",
+        vec![
+            ("strength", atom(format!("LockStrength::{:?}", clause.strength))),
+            ("of", idents(&clause.of)),
+            ("wait", option(&clause.wait, |wait| atom(format!("LockWait::{:?}", wait)))),
+        ],
+    )
+}
+
+fn cte(cte: &Cte) -> Code {
+    structure(
+        "Cte",
+        vec![
+            ("name", ident(&cte.name)),
+            ("columns", idents(&cte.columns)),
+            ("query", boxed(statement_code(&cte.query))),
+        ],
+    )
+}
+
+/// `column = value` pairs as a vector of tuples.
+fn assignments(assignments: &[(Ident, Expression)]) -> Code {
+    list(assignments, |(column, value)| Code::Seq {
+        head: "",
+        open: '(',
+        close: ')',
+        items: vec![ident(column), expression(value)],
+    })
+}
+
+fn merge_clause(clause: &MergeClause) -> Code {
+    let action = match &clause.action {
+        MergeAction::Update(set) => call("MergeAction::Update", vec![assignments(set)]),
+        MergeAction::Delete => atom("MergeAction::Delete"),
+        MergeAction::Insert { columns, values } => structure(
+            "MergeAction::Insert",
+            vec![("columns", option(columns, |columns| idents(columns))), ("values", expressions(values))],
+        ),
+    };
+    structure(
+        "MergeClause",
+        vec![
+            ("matched", atom(clause.matched.to_string())),
+            ("condition", option(&clause.condition, expression)),
+            ("action", action),
+        ],
+    )
+}
+
+fn copy_option(copy: &CopyOption) -> Code {
+    structure("CopyOption", vec![("name", string(&copy.name)), ("value", option(&copy.value, expression))])
+}
+
+fn load_data_format(format: &LoadDataFormat) -> Code {
+    if *format == LoadDataFormat::default() {
+        return atom("LoadDataFormat::default()");
+    }
+    structure(
+        "LoadDataFormat",
+        vec![
+            ("fields_terminated_by", optional_string(&format.fields_terminated_by)),
+            ("fields_enclosed_by", optional_string(&format.fields_enclosed_by)),
+            ("optionally_enclosed", atom(format.optionally_enclosed.to_string())),
+            ("fields_escaped_by", optional_string(&format.fields_escaped_by)),
+            ("lines_starting_by", optional_string(&format.lines_starting_by)),
+            ("lines_terminated_by", optional_string(&format.lines_terminated_by)),
+        ],
+    )
+}
+
+fn privileges(privileges: &[GrantedPrivilege]) -> Code {
+    list(privileges, |granted| {
+        structure(
+            "GrantedPrivilege",
+            vec![
+                ("privilege", atom(format!("Privilege::{:?}", granted.privilege))),
+                ("columns", idents(&granted.columns)),
+            ],
+        )
+    })
+}
+
+fn grant_object(object: &GrantObject) -> Code {
+    match object {
+        GrantObject::Table(name) => call("GrantObject::Table", vec![ident(name)]),
+        GrantObject::Sequence(name) => call("GrantObject::Sequence", vec![ident(name)]),
+        GrantObject::Schema(name) => call("GrantObject::Schema", vec![ident(name)]),
+        GrantObject::Function(name) => call("GrantObject::Function", vec![ident(name)]),
+    }
+}
+
+pub(crate) fn statement_code(statement: &Statement) -> Code {
+    match statement {
+        Statement::Select {
+            hints,
+            columns,
+            wildcard,
+            from,
+            joins,
+            selection,
+            group_by,
+            qualify,
+            order_by,
+            limit,
+            offset,
+            locking,
+        } => structure(
+            "Statement::Select",
+            vec![
+                ("hints", list(hints, hint)),
+                ("columns", list(columns, select_item)),
+                ("wildcard", wildcard_options(wildcard)),
+                ("from", boxed(table_factor(from))),
+                ("joins", list(joins, join)),
+                ("selection", option(selection, expression)),
+                ("group_by", list(group_by, grouping_element)),
+                ("qualify", option(qualify, expression)),
+                ("order_by", list(order_by, order_by_expr)),
+                ("limit", option(limit, |n| atom(n.to_string()))),
+                ("offset", option(offset, |n| atom(n.to_string()))),
+                ("locking", list(locking, locking_clause)),
+            ],
+        ),
+        Statement::CreateTable { table_name, column_list } => structure(
+            "Statement::CreateTable",
+            vec![("table_name", ident(table_name)), ("column_list", list(column_list, table_column))],
+        ),
+        Statement::DropTable { table_name, if_exists } => structure(
+            "Statement::DropTable",
+            vec![("table_name", ident(table_name)), ("if_exists", atom(if_exists.to_string()))],
+        ),
+        Statement::CreateFunction { or_replace, procedure, name, parameters, returns, language, body } => structure(
+            "Statement::CreateFunction",
+            vec![
+                ("or_replace", atom(or_replace.to_string())),
+                ("procedure", atom(procedure.to_string())),
+                ("name", string(name)),
+                ("parameters", list(parameters, function_parameter)),
+                ("returns", option(returns, routine_type)),
+                ("language", optional_string(language)),
+                ("body", string(body)),
+            ],
+        ),
+        Statement::Insert { table_name, columns, rows: values } => structure(
+            "Statement::Insert",
+            vec![
+                ("table_name", ident(table_name)),
+                ("columns", option(columns, |columns| idents(columns))),
+                ("rows", rows(values)),
+            ],
+        ),
+        Statement::CreateView { or_replace, name, columns, query } => structure(
+            "Statement::CreateView",
+            vec![
+                ("or_replace", atom(or_replace.to_string())),
+                ("name", ident(name)),
+                ("columns", idents(columns)),
+                ("query", boxed(statement_code(query))),
+            ],
+        ),
+        Statement::InsertSelect { table_name, columns, query } => structure(
+            "Statement::InsertSelect",
+            vec![
+                ("table_name", ident(table_name)),
+                ("columns", option(columns, |columns| idents(columns))),
+                ("query", boxed(statement_code(query))),
+            ],
+        ),
+        Statement::Update { table_name, alias, assignments: set, selection } => structure(
+            "Statement::Update",
+            vec![
+                ("table_name", ident(table_name)),
+                ("alias", optional_ident(alias)),
+                ("assignments", assignments(set)),
+                ("selection", option(selection, expression)),
+            ],
+        ),
+        Statement::Delete { table_name, alias, selection } => structure(
+            "Statement::Delete",
+            vec![
+                ("table_name", ident(table_name)),
+                ("alias", optional_ident(alias)),
+                ("selection", option(selection, expression)),
+            ],
+        ),
+        Statement::Grant { privileges: granted, object, grantees, with_grant_option } => structure(
+            "Statement::Grant",
+            vec![
+                ("privileges", privileges(granted)),
+                ("object", grant_object(object)),
+                ("grantees", strings(grantees)),
+                ("with_grant_option", atom(with_grant_option.to_string())),
+            ],
+        ),
+        Statement::Revoke { privileges: revoked, object, grantees } => structure(
+            "Statement::Revoke",
+            vec![
+                ("privileges", privileges(revoked)),
+                ("object", grant_object(object)),
+                ("grantees", strings(grantees)),
+            ],
+        ),
+        Statement::GrantRole { roles, grantees, with_admin_option } => structure(
+            "Statement::GrantRole",
+            vec![
+                ("roles", strings(roles)),
+                ("grantees", strings(grantees)),
+                ("with_admin_option", atom(with_admin_option.to_string())),
+            ],
+        ),
+        Statement::RevokeRole { roles, grantees } => structure(
+            "Statement::RevokeRole",
+            vec![("roles", strings(roles)), ("grantees", strings(grantees))],
+        ),
+        Statement::CreateRole { role_name, options } => structure(
+            "Statement::CreateRole",
+            vec![("role_name", string(role_name)), ("options", list(options, role_option))],
+        ),
+        Statement::CreateTrigger {
+            name,
+            timing,
+            events,
+            update_columns,
+            table_name,
+            for_each_row,
+            condition,
+            body,
+        } => {
+            let body = match body {
+                TriggerBody::Execute { procedure, function, args } => structure(
+                    "TriggerBody::Execute",
+                    vec![
+                        ("procedure", atom(procedure.to_string())),
+                        ("function", string(function)),
+                        ("args", expressions(args)),
+                    ],
+                ),
+                TriggerBody::Block(text) => call("TriggerBody::Block", vec![string(text)]),
+                TriggerBody::Statement(inner) => call("TriggerBody::Statement", vec![boxed(statement_code(inner))]),
+            };
+            structure(
+                "Statement::CreateTrigger",
+                vec![
+                    ("name", string(name)),
+                    ("timing", atom(format!("TriggerTiming::{:?}", timing))),
+                    ("events", list(events, |event| atom(format!("TriggerEvent::{:?}", event)))),
+                    ("update_columns", list(update_columns, ident)),
+                    ("table_name", ident(table_name)),
+                    ("for_each_row", atom(for_each_row.to_string())),
+                    ("condition", option(condition, expression)),
+                    ("body", body),
+                ],
+            )
+        }
+        Statement::AlterRole { role_name, options } => structure(
+            "Statement::AlterRole",
+            vec![("role_name", string(role_name)), ("options", list(options, role_option))],
+        ),
+        Statement::Merge { target, target_alias, source, source_alias, on, clauses } => {
+            let source = match source {
+                MergeSource::Table(name) => call("MergeSource::Table", vec![ident(name)]),
+                MergeSource::Query(query) => call("MergeSource::Query", vec![boxed(statement_code(query))]),
+            };
+            structure(
+                "Statement::Merge",
+                vec![
+                    ("target", ident(target)),
+                    ("target_alias", optional_ident(target_alias)),
+                    ("source", source),
+                    ("source_alias", optional_ident(source_alias)),
+                    ("on", expression(on)),
+                    ("clauses", list(clauses, merge_clause)),
+                ],
+            )
+        }
+        Statement::Copy { table_name, columns, from, target, options } => {
+            let target = match target {
+                CopyTarget::File(path) => call("CopyTarget::File", vec![string(path)]),
+                CopyTarget::Stdin => atom("CopyTarget::Stdin"),
+                CopyTarget::Stdout => atom("CopyTarget::Stdout"),
+            };
+            structure(
+                "Statement::Copy",
+                vec![
+                    ("table_name", ident(table_name)),
+                    ("columns", option(columns, |columns| idents(columns))),
+                    ("from", atom(from.to_string())),
+                    ("target", target),
+                    ("options", list(options, copy_option)),
+                ],
+            )
+        }
+        Statement::LoadData { local, file, duplicates, table_name, format, ignore_lines, columns } => structure(
+            "Statement::LoadData",
+            vec![
+                ("local", atom(local.to_string())),
+                ("file", string(file)),
+                ("duplicates", option(duplicates, |handling| atom(format!("DuplicateHandling::{:?}", handling)))),
+                ("table_name", ident(table_name)),
+                ("format", load_data_format(format)),
+                ("ignore_lines", option(ignore_lines, |n| atom(n.to_string()))),
+                ("columns", option(columns, |columns| idents(columns))),
+            ],
+        ),
+        Statement::SetVariable { scope, name, value } => structure(
+            "Statement::SetVariable",
+            vec![
+                ("scope", option(scope, |scope| atom(format!("SetScope::{:?}", scope)))),
+                ("name", string(name)),
+                ("value", expressions(value)),
+            ],
+        ),
+        Statement::Prepare { name, parameter_types, statement } => structure(
+            "Statement::Prepare",
+            vec![
+                ("name", string(name)),
+                ("parameter_types", list(parameter_types, data_type)),
+                ("statement", boxed(statement_code(statement))),
+            ],
+        ),
+        Statement::Explain { analyze, statement } => structure(
+            "Statement::Explain",
+            vec![("analyze", atom(analyze.to_string())), ("statement", boxed(statement_code(statement)))],
+        ),
+        Statement::Execute { name, parameters } => structure(
+            "Statement::Execute",
+            vec![("name", string(name)), ("parameters", expressions(parameters))],
+        ),
+        Statement::Deallocate { name } => structure("Statement::Deallocate", vec![("name", optional_string(name))]),
+        Statement::Transaction { command } => structure(
+            "Statement::Transaction",
+            vec![("command", atom(format!("TransactionCommand::{:?}", command)))],
+        ),
+        Statement::Values { rows: values } => structure("Statement::Values", vec![("rows", rows(values))]),
+        Statement::SetOperation { operator, all, left, right } => structure(
+            "Statement::SetOperation",
+            vec![
+                ("operator", atom(format!("SetOperator::{:?}", operator))),
+                ("all", atom(all.to_string())),
+                ("left", boxed(statement_code(left))),
+                ("right", boxed(statement_code(right))),
+            ],
+        ),
+        Statement::With { recursive, ctes, body } => structure(
+            "Statement::With",
+            vec![
+                ("recursive", atom(recursive.to_string())),
+                ("ctes", list(ctes, cte)),
+                ("body", boxed(statement_code(body))),
+            ],
+        ),
+        Statement::Unparsed { raw_sql, span } => structure(
+            "Statement::Unparsed",
+            vec![
+                ("raw_sql", string(raw_sql)),
+                ("span", atom(format!("Span::new({}, {})", span.start, span.end))),
+            ],
+        ),
+        Statement::Custom(statement) => {
+            call("Statement::Custom", vec![call("Extension::new", vec![atom(format!("{:?}", statement))])])
+        }
+    }
+}
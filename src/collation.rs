@@ -0,0 +1,88 @@
+// collation.rs — how strings compare
+// Author: Sreerag Devadasan
+//
+// A collation decides when two strings are equal and which sorts first.
+// BINARY compares them code point by code point, as Rust's `str` does, and
+// is what every comparison uses unless told otherwise. NOCASE compares
+// their lowercase forms, so 'Ann' = 'ann' and 'b' sorts after 'A'.
+//
+// A column takes one with `COLLATE name` in CREATE TABLE, and an expression
+// with the postfix `expr COLLATE name`, which binds tighter than any
+// operator. A comparison whose operands name two different collations is
+// an error, and one written in the expression wins over that of a column.
+// The engine uses a column's collation for WHERE and ORDER BY on it.
+//
+// Locale-aware collations would need ICU's tables, which this crate, having
+// no dependencies, doesn't ship.
+
+use alloc::borrow::Cow;
+use core::cmp::Ordering;
+
+use crate::prelude::*;
+use crate::ast::Expression;
+use crate::eval::EvalError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Collation {
+    #[default]
+    Binary,
+    NoCase,
+}
+
+impl Collation {
+    /// The collation named `name`, without regard to case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "binary" => Some(Collation::Binary),
+            "nocase" => Some(Collation::NoCase),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Collation::Binary => "BINARY",
+            Collation::NoCase => "NOCASE",
+        }
+    }
+
+    /// The form of `text` this collation compares, so that two strings are
+    /// equal under it when their keys are.
+    pub fn key<'s>(&self, text: &'s str) -> Cow<'s, str> {
+        match self {
+            Collation::Binary => Cow::Borrowed(text),
+            Collation::NoCase => Cow::Owned(text.to_lowercase()),
+        }
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.key(a).cmp(&self.key(b))
+    }
+
+    /// The collation a comparison of `left` and `right` uses, if either
+    /// names one with COLLATE.
+    pub fn between(left: &Expression, right: &Expression) -> Result<Option<Self>, EvalError> {
+        match (Collation::of(left)?, Collation::of(right)?) {
+            (Some(a), Some(b)) if a != b => {
+                Err(EvalError::TypeMismatch(format!("collations {} and {} conflict", a.name(), b.name())))
+            }
+            (a, b) => Ok(a.or(b)),
+        }
+    }
+
+    /// The collation an operand names with COLLATE, if it does.
+    pub fn of(expr: &Expression) -> Result<Option<Self>, EvalError> {
+        match expr {
+            Expression::Collate { collation, .. } => {
+                Collation::from_name(collation.as_str()).map(Some).ok_or_else(|| unknown(collation.as_str()))
+            }
+            Expression::Grouped(inner) => Collation::of(inner),
+            _ => Ok(None),
+        }
+    }
+}
+
+pub(crate) fn unknown(name: &str) -> EvalError {
+    EvalError::UnknownCollation(name.to_string())
+}
@@ -0,0 +1,667 @@
+// columnar.rs — column-wise table storage with vectorized filters and aggregates
+// Author: Sreerag Devadasan
+//
+// A `ColumnarTable` keeps each column in one typed vector (integers,
+// booleans, strings, dates, times or timestamps) next to a validity bitmap
+// that marks its non-NULL slots. Filters run a column at a time rather than a row at a time: every
+// subexpression evaluates to a whole vector of results, and a predicate to
+// a pair of bitmaps (the rows where it is TRUE and where it is FALSE; a row
+// in neither is NULL), so AND, OR and NOT, with the three-valued logic of
+// semantics.rs, are bit operations on 64 rows at
+// once. Comparisons, integer arithmetic and LIKE over columns and literals
+// are vectorized, except on dates and times. Any other expression, and any that would raise an error
+// (a type mismatch, an overflow), is handed to the tree-walking evaluator
+// row by row instead, so both layouts accept, reject and fail on the same
+// rows.
+
+use alloc::borrow::Cow;
+use core::cmp::Ordering;
+
+use crate::prelude::*;
+use crate::ast::{BinaryOperator, DBType, Expression, UnaryOperator};
+use crate::datetime::{Date, Time, Timestamp};
+use crate::decimal::Decimal;
+use crate::eval::{apply_binary, apply_unary, builtin, evaluate_handling, like, literal_value, EvalError, Value};
+use crate::semantics::{accepts, ErrorHandling};
+
+// === Bitmaps ===
+
+/// A fixed number of bits, one per row.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bitmap {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl Bitmap {
+    /// `len` bits, all set to `value`.
+    pub fn new(len: usize, value: bool) -> Self {
+        let mut bitmap = Self { words: vec![if value { u64::MAX } else { 0 }; len.div_ceil(64)], len };
+        bitmap.clear_tail();
+        bitmap
+    }
+
+    /// `len` bits where bit `i` is `f(i)`.
+    pub fn from_fn(len: usize, mut f: impl FnMut(usize) -> bool) -> Self {
+        let words = (0..len.div_ceil(64))
+            .map(|w| {
+                let start = w * 64;
+                (start..len.min(start + 64)).fold(0, |word, i| word | (u64::from(f(i)) << (i - start)))
+            })
+            .collect();
+        Self { words, len }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether bit `i` is set; false past the end.
+    pub fn get(&self, i: usize) -> bool {
+        i < self.len && (self.words[i / 64] >> (i % 64)) & 1 == 1
+    }
+
+    pub fn push(&mut self, value: bool) {
+        if self.len.is_multiple_of(64) {
+            self.words.push(0);
+        }
+        self.words[self.len / 64] |= u64::from(value) << (self.len % 64);
+        self.len += 1;
+    }
+
+    /// The number of set bits.
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// The indices of the set bits, in order.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(w, &word)| {
+            let mut rest = word;
+            core::iter::from_fn(move || {
+                (rest != 0).then(|| {
+                    let bit = rest.trailing_zeros() as usize;
+                    rest &= rest - 1;
+                    w * 64 + bit
+                })
+            })
+        })
+    }
+
+    /// Panics if the bitmaps differ in length, as for `or`.
+    pub fn and(&self, other: &Bitmap) -> Bitmap {
+        self.zip(other, |a, b| a & b)
+    }
+
+    pub fn or(&self, other: &Bitmap) -> Bitmap {
+        self.zip(other, |a, b| a | b)
+    }
+
+    pub fn not(&self) -> Bitmap {
+        let mut bitmap = Bitmap { words: self.words.iter().map(|word| !word).collect(), len: self.len };
+        bitmap.clear_tail();
+        bitmap
+    }
+
+    fn zip(&self, other: &Bitmap, f: impl Fn(u64, u64) -> u64) -> Bitmap {
+        assert_eq!(self.len, other.len, "bitmaps of different lengths");
+        Bitmap { words: self.words.iter().zip(&other.words).map(|(&a, &b)| f(a, b)).collect(), len: self.len }
+    }
+
+    /// Clears the unused bits of the last word, which `count` and `ones`
+    /// would otherwise see.
+    fn clear_tail(&mut self) {
+        if let Some(last) = self.words.last_mut().filter(|_| !self.len.is_multiple_of(64)) {
+            *last &= (1 << (self.len % 64)) - 1;
+        }
+    }
+}
+
+// === Columns ===
+
+/// The values of one column. A NULL slot holds the type's default value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnValues {
+    Int(Vec<i64>),
+    Bool(Vec<bool>),
+    Text(Vec<String>),
+    Date(Vec<Date>),
+    Time(Vec<Time>),
+    Timestamp(Vec<Timestamp>),
+    Decimal(Vec<Decimal>),
+}
+
+/// One column of a `ColumnarTable`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub values: ColumnValues,
+    /// Set for the slots that aren't NULL.
+    pub validity: Bitmap,
+}
+
+impl Column {
+    pub fn new(column_type: &DBType) -> Self {
+        let values = match column_type {
+            DBType::Int => ColumnValues::Int(Vec::new()),
+            DBType::Bool => ColumnValues::Bool(Vec::new()),
+            DBType::Varchar(_) => ColumnValues::Text(Vec::new()),
+            DBType::Date => ColumnValues::Date(Vec::new()),
+            DBType::Time => ColumnValues::Time(Vec::new()),
+            DBType::Timestamp => ColumnValues::Timestamp(Vec::new()),
+            DBType::Decimal(..) => ColumnValues::Decimal(Vec::new()),
+        };
+        Self { values, validity: Bitmap::default() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.validity.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.validity.is_empty()
+    }
+
+    /// The value in slot `i`.
+    pub fn get(&self, i: usize) -> Value {
+        if !self.validity.get(i) {
+            return Value::Null;
+        }
+        match &self.values {
+            ColumnValues::Int(values) => Value::Int(values[i]),
+            ColumnValues::Bool(values) => Value::Bool(values[i]),
+            ColumnValues::Text(values) => Value::String(values[i].clone()),
+            ColumnValues::Date(values) => Value::Date(values[i]),
+            ColumnValues::Time(values) => Value::Time(values[i]),
+            ColumnValues::Timestamp(values) => Value::Timestamp(values[i]),
+            ColumnValues::Decimal(values) => Value::Decimal(values[i]),
+        }
+    }
+
+    fn holds(&self, value: &Value) -> bool {
+        matches!(
+            (&self.values, value),
+            (_, Value::Null)
+                | (ColumnValues::Int(_), Value::Int(_))
+                | (ColumnValues::Bool(_), Value::Bool(_))
+                | (ColumnValues::Text(_), Value::String(_))
+                | (ColumnValues::Date(_), Value::Date(_))
+                | (ColumnValues::Time(_), Value::Time(_))
+                | (ColumnValues::Timestamp(_), Value::Timestamp(_))
+                | (ColumnValues::Decimal(_), Value::Decimal(_))
+        )
+    }
+
+    /// Appends a value that `holds` accepted.
+    fn push(&mut self, value: &Value) {
+        match (&mut self.values, value) {
+            (ColumnValues::Int(values), Value::Int(n)) => values.push(*n),
+            (ColumnValues::Bool(values), Value::Bool(b)) => values.push(*b),
+            (ColumnValues::Text(values), Value::String(s)) => values.push(s.clone()),
+            (ColumnValues::Date(values), Value::Date(date)) => values.push(*date),
+            (ColumnValues::Time(values), Value::Time(time)) => values.push(*time),
+            (ColumnValues::Timestamp(values), Value::Timestamp(timestamp)) => values.push(*timestamp),
+            (ColumnValues::Decimal(values), Value::Decimal(decimal)) => values.push(*decimal),
+            (ColumnValues::Int(values), _) => values.push(0),
+            (ColumnValues::Bool(values), _) => values.push(false),
+            (ColumnValues::Text(values), _) => values.push(String::new()),
+            (ColumnValues::Date(values), _) => values.push(Date::default()),
+            (ColumnValues::Time(values), _) => values.push(Time::default()),
+            (ColumnValues::Timestamp(values), _) => values.push(Timestamp::default()),
+            (ColumnValues::Decimal(values), _) => values.push(Decimal::default()),
+        }
+        self.validity.push(*value != Value::Null);
+    }
+}
+
+// === Tables ===
+
+/// A table stored column by column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnarTable {
+    columns: Vec<Column>,
+    len: usize,
+}
+
+impl ColumnarTable {
+    /// An empty table with one column of each type.
+    pub fn new<'a>(types: impl IntoIterator<Item = &'a DBType>) -> Self {
+        Self { columns: types.into_iter().map(Column::new).collect(), len: 0 }
+    }
+
+    pub fn from_rows<'a>(types: impl IntoIterator<Item = &'a DBType>, rows: &[Vec<Value>]) -> Result<Self, EvalError> {
+        let mut table = Self::new(types);
+        for row in rows {
+            table.push_row(row)?;
+        }
+        Ok(table)
+    }
+
+    /// A table of existing columns, which must all be of the same length.
+    pub fn from_columns(columns: Vec<Column>) -> Result<Self, EvalError> {
+        let len = columns.first().map_or(0, Column::len);
+        for column in &columns {
+            let values = match &column.values {
+                ColumnValues::Int(values) => values.len(),
+                ColumnValues::Bool(values) => values.len(),
+                ColumnValues::Text(values) => values.len(),
+                ColumnValues::Date(values) => values.len(),
+                ColumnValues::Time(values) => values.len(),
+                ColumnValues::Timestamp(values) => values.len(),
+                ColumnValues::Decimal(values) => values.len(),
+            };
+            if values != len || column.len() != len {
+                return Err(EvalError::TypeMismatch("columns of different lengths".to_string()));
+            }
+        }
+        Ok(Self { columns, len })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Appends a row holding one value of the right type (or NULL) per
+    /// column; a row that doesn't fit leaves the table unchanged.
+    pub fn push_row(&mut self, row: &[Value]) -> Result<(), EvalError> {
+        if row.len() != self.columns.len() {
+            let message = format!("expected {} values per row, found {}", self.columns.len(), row.len());
+            return Err(EvalError::TypeMismatch(message));
+        }
+        if let Some((_, value)) = self.columns.iter().zip(row).find(|(column, value)| !column.holds(value)) {
+            return Err(EvalError::TypeMismatch(format!("cannot store {:?} in this column", value)));
+        }
+        for (column, value) in self.columns.iter_mut().zip(row) {
+            column.push(value);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Row `i`, one value per column.
+    pub fn row(&self, i: usize) -> Vec<Value> {
+        self.columns.iter().map(|column| column.get(i)).collect()
+    }
+
+    /// Every row, or only the selected ones.
+    pub fn rows(&self, selection: Option<&Bitmap>) -> Vec<Vec<Value>> {
+        self.project(&(0..self.columns.len()).collect::<Vec<_>>(), selection)
+    }
+
+    /// Every row, or only the selected ones, holding just the values of
+    /// the listed columns.
+    pub fn project(&self, columns: &[usize], selection: Option<&Bitmap>) -> Vec<Vec<Value>> {
+        let row = |i: usize| columns.iter().map(|&c| self.columns[c].get(i)).collect();
+        match selection {
+            Some(selection) => selection.ones().map(row).collect(),
+            None => (0..self.len).map(row).collect(),
+        }
+    }
+
+    /// The rows a WHERE expression accepts, where `names[i]` names column
+    /// `i`. NULL counts as false, as for `eval::matches`.
+    pub fn filter(&self, expr: &Expression, names: &[String]) -> Result<Bitmap, EvalError> {
+        self.filter_handling(expr, names, &ErrorHandling::STRICT)
+    }
+
+    /// Like `filter`, with arithmetic errors giving what `errors` says.
+    /// Whole columns are only computed where they don't fail, so the rows
+    /// left to evaluate one by one are where errors are handled.
+    pub fn filter_handling(
+        &self,
+        expr: &Expression,
+        names: &[String],
+        errors: &ErrorHandling,
+    ) -> Result<Bitmap, EvalError> {
+        let vectorized = Vectorizer { table: self, names }.vector(expr);
+        if let Some(truth) = vectorized.as_ref().and_then(|vector| vector.truth(self.len)) {
+            return Ok(truth.into_owned().true_rows);
+        }
+        let mut accepted = Bitmap::default();
+        for i in 0..self.len {
+            accepted.push(accepts(&evaluate_handling(expr, names, &self.row(i), &builtin, errors)?));
+        }
+        Ok(accepted)
+    }
+
+    /// Computes an aggregate over column `column`, or over whole rows for
+    /// `COUNT(*)` when it is `None`, optionally reading only the selected
+    /// rows. NULLs are skipped; SUM, MIN and MAX of no values are NULL.
+    pub fn aggregate(
+        &self,
+        aggregate: Aggregate,
+        column: Option<usize>,
+        selection: Option<&Bitmap>,
+    ) -> Result<Value, EvalError> {
+        let Some(index) = column else {
+            return match aggregate {
+                Aggregate::Count => Ok(Value::Int(selection.map_or(self.len, Bitmap::count) as i64)),
+                other => Err(EvalError::TypeMismatch(format!("{}(*) needs a column", other.name()))),
+            };
+        };
+        let column = self.columns.get(index).ok_or_else(|| EvalError::UnknownColumn(format!("#{}", index)))?;
+        let rows = match selection {
+            Some(selection) => column.validity.and(selection),
+            None => column.validity.clone(),
+        };
+        let largest = aggregate == Aggregate::Max;
+        Ok(match (aggregate, &column.values) {
+            (Aggregate::Count, _) => Value::Int(rows.count() as i64),
+            (Aggregate::Sum, _) if rows.count() == 0 => Value::Null,
+            (Aggregate::Sum, ColumnValues::Int(values)) => {
+                let sum = rows.ones().try_fold(0i64, |sum, i| sum.checked_add(values[i]));
+                Value::Int(sum.ok_or(EvalError::Overflow)?)
+            }
+            (Aggregate::Sum, ColumnValues::Decimal(values)) => {
+                Value::Decimal(rows.ones().try_fold(Decimal::default(), |sum, i| sum.add(&values[i]))?)
+            }
+            (Aggregate::Sum, _) => return Err(EvalError::TypeMismatch("SUM needs a numeric column".to_string())),
+            (_, ColumnValues::Int(values)) => extreme(values, &rows, largest).map_or(Value::Null, |n| Value::Int(*n)),
+            (_, ColumnValues::Bool(values)) => extreme(values, &rows, largest).map_or(Value::Null, |b| Value::Bool(*b)),
+            (_, ColumnValues::Text(values)) => {
+                extreme(values, &rows, largest).map_or(Value::Null, |s| Value::String(s.clone()))
+            }
+            (_, ColumnValues::Date(values)) => extreme(values, &rows, largest).map_or(Value::Null, |d| Value::Date(*d)),
+            (_, ColumnValues::Time(values)) => extreme(values, &rows, largest).map_or(Value::Null, |t| Value::Time(*t)),
+            (_, ColumnValues::Timestamp(values)) => {
+                extreme(values, &rows, largest).map_or(Value::Null, |t| Value::Timestamp(*t))
+            }
+            (_, ColumnValues::Decimal(values)) => {
+                extreme(values, &rows, largest).map_or(Value::Null, |d| Value::Decimal(*d))
+            }
+        })
+    }
+}
+
+/// The smallest or largest of the values in the selected slots.
+fn extreme<'v, T: Ord>(values: &'v [T], rows: &Bitmap, largest: bool) -> Option<&'v T> {
+    let selected = rows.ones().map(|i| &values[i]);
+    if largest {
+        selected.max()
+    } else {
+        selected.min()
+    }
+}
+
+// === Aggregates ===
+
+/// Aggregate functions computed over a column of a `ColumnarTable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Count,
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "COUNT" => Some(Aggregate::Count),
+            "SUM" => Some(Aggregate::Sum),
+            "MIN" => Some(Aggregate::Min),
+            "MAX" => Some(Aggregate::Max),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aggregate::Count => "COUNT",
+            Aggregate::Sum => "SUM",
+            Aggregate::Min => "MIN",
+            Aggregate::Max => "MAX",
+        }
+    }
+}
+
+// === Vectorized evaluation ===
+
+/// A predicate's result for each row: TRUE, FALSE, or NULL where neither
+/// bit is set.
+#[derive(Debug, Clone)]
+struct Truth {
+    true_rows: Bitmap,
+    false_rows: Bitmap,
+}
+
+impl Truth {
+    fn constant(len: usize, value: Option<bool>) -> Self {
+        Self { true_rows: Bitmap::new(len, value == Some(true)), false_rows: Bitmap::new(len, value == Some(false)) }
+    }
+
+    /// Rows where `test` is `Some(true)` or `Some(false)`.
+    fn from_fn(len: usize, mut test: impl FnMut(usize) -> Option<bool>) -> Self {
+        let (mut true_words, mut false_words) = (Vec::new(), Vec::new());
+        for start in (0..len).step_by(64) {
+            let (mut true_word, mut false_word) = (0u64, 0u64);
+            for i in start..len.min(start + 64) {
+                match test(i) {
+                    Some(true) => true_word |= 1 << (i - start),
+                    Some(false) => false_word |= 1 << (i - start),
+                    None => {}
+                }
+            }
+            true_words.push(true_word);
+            false_words.push(false_word);
+        }
+        Self { true_rows: Bitmap { words: true_words, len }, false_rows: Bitmap { words: false_words, len } }
+    }
+
+    fn get(&self, i: usize) -> Option<bool> {
+        if self.true_rows.get(i) {
+            Some(true)
+        } else {
+            self.false_rows.get(i).then_some(false)
+        }
+    }
+}
+
+/// The value of an expression on every row of a table.
+enum Vector<'t> {
+    /// The same value for every row.
+    Scalar(Value),
+    Int(Cow<'t, [i64]>, Cow<'t, Bitmap>),
+    Text(&'t [String], &'t Bitmap),
+    Bool(Truth),
+}
+
+/// One operand of an operator applied row by row.
+enum Operand<'v, T> {
+    Scalar(&'v T),
+    Vector(&'v [T], &'v Bitmap),
+}
+
+impl<'v, T> Operand<'v, T> {
+    /// The operand's value on row `i`, unless it is NULL.
+    fn get(&self, i: usize) -> Option<&'v T> {
+        match *self {
+            Operand::Scalar(value) => Some(value),
+            Operand::Vector(values, validity) => validity.get(i).then(|| &values[i]),
+        }
+    }
+}
+
+impl Vector<'_> {
+    fn ints(&self) -> Option<Operand<'_, i64>> {
+        match self {
+            Vector::Scalar(Value::Int(n)) => Some(Operand::Scalar(n)),
+            Vector::Int(values, validity) => Some(Operand::Vector(values, validity)),
+            _ => None,
+        }
+    }
+
+    fn texts(&self) -> Option<Operand<'_, String>> {
+        match self {
+            Vector::Scalar(Value::String(s)) => Some(Operand::Scalar(s)),
+            Vector::Text(values, validity) => Some(Operand::Vector(values, validity)),
+            _ => None,
+        }
+    }
+
+    /// The vector as a predicate, if it holds booleans.
+    fn truth(&self, len: usize) -> Option<Cow<'_, Truth>> {
+        match self {
+            Vector::Bool(truth) => Some(Cow::Borrowed(truth)),
+            Vector::Scalar(Value::Bool(b)) => Some(Cow::Owned(Truth::constant(len, Some(*b)))),
+            Vector::Scalar(Value::Null) => Some(Cow::Owned(Truth::constant(len, None))),
+            _ => None,
+        }
+    }
+}
+
+/// Evaluates expressions a column at a time; `None` means the expression
+/// has to be evaluated row by row.
+struct Vectorizer<'t> {
+    table: &'t ColumnarTable,
+    names: &'t [String],
+}
+
+impl<'t> Vectorizer<'t> {
+    fn vector(&self, expr: &Expression) -> Option<Vector<'t>> {
+        match expr {
+            Expression::Identifier(name) => self.column(name),
+            Expression::CompoundIdentifier(parts) => {
+                self.column(&parts.iter().map(|part| part.as_str()).collect::<Vec<_>>().join("."))
+            }
+            Expression::Grouped(inner) => self.vector(inner),
+            Expression::UnaryOperation { operator, operand } => match (operator, self.vector(operand)?) {
+                (_, Vector::Scalar(value)) => apply_unary(operator, value).ok().map(Vector::Scalar),
+                (UnaryOperator::Not, vector) => {
+                    let truth = vector.truth(self.table.len)?;
+                    Some(Vector::Bool(Truth { true_rows: truth.false_rows.clone(), false_rows: truth.true_rows.clone() }))
+                }
+                (UnaryOperator::Negate, vector) => {
+                    let ints = vector.ints()?;
+                    let negated = (0..self.table.len)
+                        .map(|i| ints.get(i).map_or(Some(0), |n| n.checked_neg()))
+                        .collect::<Option<Vec<_>>>()?;
+                    let Vector::Int(_, validity) = vector else { return None };
+                    Some(Vector::Int(Cow::Owned(negated), validity))
+                }
+                (UnaryOperator::IsNull | UnaryOperator::IsNotNull, vector) => {
+                    let valid = match &vector {
+                        Vector::Int(_, validity) => validity.as_ref().clone(),
+                        Vector::Text(_, validity) => (*validity).clone(),
+                        Vector::Bool(truth) => truth.true_rows.or(&truth.false_rows),
+                        Vector::Scalar(_) => return None,
+                    };
+                    let nulls = valid.not();
+                    Some(Vector::Bool(if *operator == UnaryOperator::IsNull {
+                        Truth { true_rows: nulls, false_rows: valid }
+                    } else {
+                        Truth { true_rows: valid, false_rows: nulls }
+                    }))
+                }
+            },
+            Expression::BinaryOperation { left_operand, operator, right_operand } => {
+                self.binary(operator, self.vector(left_operand)?, self.vector(right_operand)?)
+            }
+            // Collated comparisons and casts run row by row
+            Expression::Function { .. } | Expression::Collate { .. } | Expression::Cast { .. } => None,
+            literal => literal_value(literal).ok().map(Vector::Scalar),
+        }
+    }
+
+    fn column(&self, name: &str) -> Option<Vector<'t>> {
+        let column = &self.table.columns[self.names.iter().position(|n| n == name)?];
+        let validity = &column.validity;
+        Some(match &column.values {
+            ColumnValues::Int(values) => Vector::Int(Cow::Borrowed(values), Cow::Borrowed(validity)),
+            ColumnValues::Text(values) => Vector::Text(values, validity),
+            ColumnValues::Bool(values) => {
+                let true_rows = Bitmap::from_fn(values.len(), |i| values[i]).and(validity);
+                Vector::Bool(Truth { false_rows: true_rows.not().and(validity), true_rows })
+            }
+            ColumnValues::Date(_) | ColumnValues::Time(_) | ColumnValues::Timestamp(_) | ColumnValues::Decimal(_) => {
+                return None
+            }
+        })
+    }
+
+    fn binary(&self, operator: &BinaryOperator, left: Vector<'t>, right: Vector<'t>) -> Option<Vector<'t>> {
+        use BinaryOperator::*;
+
+        let len = self.table.len;
+        if let (Vector::Scalar(a), Vector::Scalar(b)) = (&left, &right) {
+            return apply_binary(operator, a.clone(), b.clone()).ok().map(Vector::Scalar);
+        }
+        match operator {
+            And | Or => {
+                let (a, b) = (left.truth(len)?, right.truth(len)?);
+                Some(Vector::Bool(if *operator == And {
+                    Truth { true_rows: a.true_rows.and(&b.true_rows), false_rows: a.false_rows.or(&b.false_rows) }
+                } else {
+                    Truth { true_rows: a.true_rows.or(&b.true_rows), false_rows: a.false_rows.and(&b.false_rows) }
+                }))
+            }
+            // NULL is an operand like any other here, so these are left to the
+            // row-by-row evaluator
+            IsDistinctFrom | IsNotDistinctFrom => None,
+            _ if matches!(left, Vector::Scalar(Value::Null)) || matches!(right, Vector::Scalar(Value::Null)) => {
+                Some(Vector::Scalar(Value::Null))
+            }
+            Add | Subtract | Multiply | Divide => {
+                let (a, b) = (left.ints()?, right.ints()?);
+                let validity = Bitmap::from_fn(len, |i| a.get(i).is_some() && b.get(i).is_some());
+                // Division by zero and overflow are left to the row-by-row
+                // evaluator, which reports them
+                let values = (0..len)
+                    .map(|i| match (a.get(i), b.get(i)) {
+                        (Some(&x), Some(&y)) => match operator {
+                            Add => x.checked_add(y),
+                            Subtract => x.checked_sub(y),
+                            Multiply => x.checked_mul(y),
+                            _ => x.checked_div(y),
+                        },
+                        _ => Some(0),
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Vector::Int(Cow::Owned(values), Cow::Owned(validity)))
+            }
+            // Left to the row-by-row evaluator
+            Concat => None,
+            Like | ILike => {
+                let (text, pattern) = (left.texts()?, right.texts()?);
+                Some(Vector::Bool(Truth::from_fn(len, |i| {
+                    let (text, pattern) = (text.get(i)?, pattern.get(i)?);
+                    Some(if *operator == ILike {
+                        like(&text.to_lowercase(), &pattern.to_lowercase())
+                    } else {
+                        like(text, pattern)
+                    })
+                })))
+            }
+            _ => {
+                let holds: fn(Ordering) -> bool = match operator {
+                    Equals => Ordering::is_eq,
+                    NotEquals => Ordering::is_ne,
+                    GreaterThan => Ordering::is_gt,
+                    GreaterThanOrEqual => Ordering::is_ge,
+                    LessThan => Ordering::is_lt,
+                    _ => Ordering::is_le,
+                };
+                let truth = if let (Some(a), Some(b)) = (left.ints(), right.ints()) {
+                    Truth::from_fn(len, |i| Some(holds(a.get(i)?.cmp(b.get(i)?))))
+                } else if let (Some(a), Some(b)) = (left.texts(), right.texts()) {
+                    Truth::from_fn(len, |i| Some(holds(a.get(i)?.cmp(b.get(i)?))))
+                } else {
+                    let (a, b) = (left.truth(len)?, right.truth(len)?);
+                    Truth::from_fn(len, |i| Some(holds(a.get(i)?.cmp(&b.get(i)?))))
+                };
+                Some(Vector::Bool(truth))
+            }
+        }
+    }
+}
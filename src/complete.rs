@@ -0,0 +1,140 @@
+// complete.rs — context-aware completion candidates for partial queries
+// Author: Sreerag Devadasan
+//
+// The parser decides what may come next: the text before the cursor is
+// parsed as far as it goes, and the tokens it would have accepted there
+// (see `parser::expected_tokens`) are the candidates. Keywords and words
+// such as QUALIFY or PIVOT are offered as they are; where the parser
+// would take the name of a table or a column, the schema supplies the
+// names. Columns are taken from the tables the query names when the
+// schema knows them (even if FROM comes after the cursor), otherwise from
+// every table. Anywhere else an identifier is a new name, such as an
+// alias, and nothing is offered for it.
+//
+// `tab` is what the REPL's TAB key does with the candidates.
+
+use crate::prelude::*;
+use crate::dialect::Dialect;
+use crate::parser::{expected_after, Name};
+use crate::schema_diff::Schema;
+use crate::tokenizer::{tokenize_with_dialect, Keyword, Token, TokenKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Keyword,
+    Table,
+    Column,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub text: String,
+    pub kind: CompletionKind,
+}
+
+/// A line after TAB: the completion's text inserted at the cursor, and the
+/// candidates to list when there was more than one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tab {
+    pub line: String,
+    pub cursor: usize,
+    pub choices: Vec<Completion>,
+}
+
+/// Returns the completions for the word at byte offset `cursor` in `sql`.
+pub fn complete(sql: &str, cursor: usize, schema: &Schema) -> Vec<Completion> {
+    complete_with_dialect(sql, cursor, schema, Dialect::Generic)
+}
+
+/// Like `complete`, with the grammar of `dialect`.
+pub fn complete_with_dialect(sql: &str, cursor: usize, schema: &Schema, dialect: Dialect) -> Vec<Completion> {
+    let (prefix_start, end) = word_at(sql, cursor);
+    let prefix = &sql[prefix_start..end];
+    let Some(expected) = expected_after(&sql[..prefix_start], dialect) else { return Vec::new() };
+
+    let mut candidates = Vec::new();
+    if expected.names.contains(&Name::Table) {
+        candidates.extend(schema.tables.keys().map(|t| Completion { text: t.clone(), kind: CompletionKind::Table }));
+    }
+    if expected.names.contains(&Name::Column) {
+        candidates.extend(columns(sql, schema, dialect));
+    }
+    for kind in &expected.kinds {
+        if let TokenKind::Keyword(_) | TokenKind::Word(_) = kind {
+            candidates.push(Completion { text: kind.to_string(), kind: CompletionKind::Keyword });
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter(|c| c.text.get(..prefix.len()).is_some_and(|start| start.eq_ignore_ascii_case(prefix)))
+        .collect()
+}
+
+/// Completes the word at `cursor` in `line`: a single candidate replaces
+/// it whole, followed by a space, and several as far as they agree, to be
+/// listed.
+pub fn tab(line: &str, cursor: usize, schema: &Schema, dialect: Dialect) -> Tab {
+    let (start, end) = word_at(line, cursor);
+    let choices = complete_with_dialect(line, end, schema, dialect);
+    let word = match choices.as_slice() {
+        [] => line[start..end].to_string(),
+        [only] if line[end..].starts_with(char::is_whitespace) => only.text.clone(),
+        [only] => format!("{} ", only.text),
+        [first, rest @ ..] => {
+            let mut common = first.text.len();
+            for choice in rest {
+                let pairs = first.text.bytes().zip(choice.text.bytes());
+                common = common.min(pairs.take_while(|(a, b)| a.eq_ignore_ascii_case(b)).count());
+            }
+            while !first.text.is_char_boundary(common) {
+                common -= 1;
+            }
+            // All of them start with the word typed, so this is no shorter
+            first.text[..common].to_string()
+        }
+    };
+    let choices = if choices.len() > 1 { choices } else { Vec::new() };
+    Tab { line: format!("{}{}{}", &line[..start], word, &line[end..]), cursor: start + word.len(), choices }
+}
+
+/// The start of the word that ends at `cursor`, and `cursor` itself, moved
+/// back onto a character boundary of `sql`.
+fn word_at(sql: &str, cursor: usize) -> (usize, usize) {
+    let mut end = cursor.min(sql.len());
+    while !sql.is_char_boundary(end) {
+        end -= 1;
+    }
+    let before = &sql[..end];
+    let start = before
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |i| i + before[i..].chars().next().map_or(1, char::len_utf8));
+    (start, end)
+}
+
+/// Columns of the tables the query names, or of every table if it names
+/// none the schema knows.
+fn columns(sql: &str, schema: &Schema, dialect: Dialect) -> Vec<Completion> {
+    let tokens = tokenize_with_dialect(sql, dialect);
+    let named = tokens.windows(2).filter_map(|pair| match pair {
+        [Token::Keyword(Keyword::From | Keyword::Table | Keyword::Into), Token::Identifier(name)] => {
+            schema.tables.get(&name.value)
+        }
+        // JOIN and UPDATE are words rather than keywords
+        [Token::Identifier(word), Token::Identifier(name)]
+            if word.eq_ignore_ascii_case("join") || word.eq_ignore_ascii_case("update") =>
+        {
+            schema.tables.get(&name.value)
+        }
+        _ => None,
+    });
+    let mut tables: Vec<_> = named.collect();
+    if tables.is_empty() {
+        tables = schema.tables.values().collect();
+    }
+
+    let mut names: Vec<String> = tables.into_iter().flatten().map(|c| c.column_name.value.clone()).collect();
+    names.sort();
+    names.dedup();
+    names.into_iter().map(|text| Completion { text, kind: CompletionKind::Column }).collect()
+}
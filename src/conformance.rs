@@ -0,0 +1,217 @@
+// conformance.rs — parse pass rates over external SQL corpora
+// Author: Sreerag Devadasan
+//
+// Corpora are read through small adapters that turn a file into a list of
+// categorized statements:
+//   * sqllogictest files (`.slt`, `.test`): the SQL of every `statement`
+//     and `query` record, categorized by its leading keywords;
+//   * plain corpus files (anything else): statements separated by `;`,
+//     with `-- category: <name>` lines setting the category of the
+//     statements that follow (leading keywords otherwise).
+// Only parsing is checked: a statement passes if it parses, or if a
+// sqllogictest record expects an error and it doesn't.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::batch::split_statements;
+use crate::json;
+use crate::parser::parse_sql;
+
+/// One statement of a corpus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorpusEntry {
+    pub category: String,
+    pub sql: String,
+    /// `file:line` where the statement starts.
+    pub location: String,
+    /// The corpus expects this statement to be rejected.
+    pub expect_error: bool,
+}
+
+/// Pass counts per category, plus the statements that failed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConformanceReport {
+    /// Category → (passed, total).
+    pub categories: BTreeMap<String, (usize, usize)>,
+    /// Location and error of every failing statement.
+    pub failures: Vec<(String, String)>,
+}
+
+impl ConformanceReport {
+    pub fn passed(&self) -> usize {
+        self.categories.values().map(|(passed, _)| passed).sum()
+    }
+
+    pub fn total(&self) -> usize {
+        self.categories.values().map(|(_, total)| total).sum()
+    }
+
+    pub fn to_json(&self) -> String {
+        let categories = self.categories.iter().map(|(name, (passed, total))| {
+            json::object([
+                ("category", json::string(name)),
+                ("passed", passed.to_string()),
+                ("total", total.to_string()),
+            ])
+        });
+        json::object([
+            ("passed", self.passed().to_string()),
+            ("total", self.total().to_string()),
+            ("categories", json::array(categories)),
+        ])
+    }
+}
+
+fn percent(passed: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        passed as f64 * 100.0 / total as f64
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, (passed, total)) in &self.categories {
+            writeln!(f, "{:<20} {:>6}/{:<6} {:>6.1}%", name, passed, total, percent(*passed, *total))?;
+        }
+        let (passed, total) = (self.passed(), self.total());
+        write!(f, "{:<20} {:>6}/{:<6} {:>6.1}%", "TOTAL", passed, total, percent(passed, total))
+    }
+}
+
+/// Parses every entry and tallies the results.
+pub fn run(entries: &[CorpusEntry]) -> ConformanceReport {
+    let mut report = ConformanceReport::default();
+    for entry in entries {
+        let result = parse_sql(&entry.sql);
+        let passed = result.is_err() == entry.expect_error;
+        let counts = report.categories.entry(entry.category.clone()).or_default();
+        counts.1 += 1;
+        if passed {
+            counts.0 += 1;
+        } else {
+            let error = match result {
+                Err(e) => e.to_string(),
+                Ok(_) => "expected an error, but it parsed".to_string(),
+            };
+            report.failures.push((entry.location.clone(), error));
+        }
+    }
+    report
+}
+
+/// Loads a corpus file, or every file in a directory, with the adapter
+/// matching each file's extension.
+pub fn load(path: impl AsRef<Path>) -> io::Result<Vec<CorpusEntry>> {
+    let path = path.as_ref();
+    let mut files: Vec<PathBuf> = Vec::new();
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            files.push(entry?.path());
+        }
+        files.sort();
+    } else {
+        files.push(path.to_path_buf());
+    }
+
+    let mut entries = Vec::new();
+    for file in files {
+        let text = fs::read_to_string(&file)?;
+        let name = file.display().to_string();
+        let sqllogictest = file.extension().is_some_and(|e| e == "slt" || e == "test");
+        entries.extend(if sqllogictest { from_sqllogictest(&name, &text) } else { from_corpus(&name, &text) });
+    }
+    Ok(entries)
+}
+
+/// The category of a statement without an explicit one: its first keyword,
+/// or the first two for CREATE, DROP and ALTER (`CREATE TABLE`).
+pub fn category_of(sql: &str) -> String {
+    let mut words = sql.split_whitespace().map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_uppercase());
+    match words.next() {
+        Some(first) if matches!(first.as_str(), "CREATE" | "DROP" | "ALTER") => match words.next() {
+            Some(second) => format!("{} {}", first, second),
+            None => first,
+        },
+        Some(first) => first,
+        None => "EMPTY".to_string(),
+    }
+}
+
+/// Plain corpus adapter; see the module comment.
+pub fn from_corpus(name: &str, text: &str) -> Vec<CorpusEntry> {
+    let mut entries = Vec::new();
+    let mut category: Option<String> = None;
+    let mut block = String::new();
+    let mut block_line = 1;
+
+    let mut flush = |block: &mut String, line: usize, category: &Option<String>| {
+        for (_, sql) in split_statements(block) {
+            entries.push(CorpusEntry {
+                category: category.clone().unwrap_or_else(|| category_of(sql)),
+                sql: sql.to_string(),
+                location: format!("{}:{}", name, line),
+                expect_error: false,
+            });
+        }
+        block.clear();
+    };
+
+    for (number, line) in text.lines().enumerate() {
+        if let Some(name) = line.trim().strip_prefix("-- category:") {
+            flush(&mut block, block_line, &category);
+            category = Some(name.trim().to_string());
+            block_line = number + 2;
+        } else if !line.trim_start().starts_with("--") {
+            if block.trim().is_empty() {
+                block_line = number + 1;
+            }
+            block.push_str(line);
+            block.push('\n');
+        }
+    }
+    flush(&mut block, block_line, &category);
+    entries
+}
+
+/// sqllogictest adapter: takes the SQL of `statement ok`, `statement error`
+/// and `query` records and ignores expected results and control records.
+pub fn from_sqllogictest(name: &str, text: &str) -> Vec<CorpusEntry> {
+    let mut entries = Vec::new();
+    let mut lines = text.lines().enumerate().peekable();
+
+    while let Some((number, line)) = lines.next() {
+        let line = line.trim();
+        let expect_error = match line.split_whitespace().next() {
+            Some("statement") => line.split_whitespace().nth(1) == Some("error"),
+            Some("query") => false,
+            _ => continue,
+        };
+
+        let mut sql = String::new();
+        while let Some((_, next)) = lines.next_if(|(_, l)| !l.trim().is_empty() && l.trim() != "----") {
+            sql.push_str(next);
+            sql.push('\n');
+        }
+        // Skip the expected results of a query
+        if lines.next_if(|(_, l)| l.trim() == "----").is_some() {
+            while lines.next_if(|(_, l)| !l.trim().is_empty()).is_some() {}
+        }
+
+        let sql = sql.trim().trim_end_matches(';').to_string();
+        if !sql.is_empty() {
+            entries.push(CorpusEntry {
+                category: category_of(&sql),
+                sql,
+                location: format!("{}:{}", name, number + 2),
+                expect_error,
+            });
+        }
+    }
+    entries
+}
@@ -0,0 +1,518 @@
+// datetime.rs — DATE, TIME and TIMESTAMP values and the functions on them
+// Author: Sreerag Devadasan
+//
+// A `Date` counts days from 1970-01-01 in the proleptic Gregorian
+// calendar, between the years 1 and 9999; a `Time` counts microseconds
+// from midnight; a `Timestamp` is a date and a time together, counted in
+// microseconds from 1970-01-01 00:00:00. None of them has a time zone.
+//
+// Values are written `2024-01-31`, `13:45:00.25` and `2024-01-31 13:45:00`
+// (a `T` may replace the space), both in typed literals such as
+// `DATE '2024-01-31'` and in strings compared with a value of one of these
+// types: `created_at >= '2024-01-01'` parses the string as a timestamp, so
+// the common filters need no literal syntax at all. A date compares with a
+// timestamp as its midnight.
+//
+// `date_part(field, value)` takes one field of a value as an integer, and
+// `date_trunc(field, value)` sets everything finer than the field to its
+// first value (`EXTRACT(field FROM value)` is parsed as `date_part`). The
+// fields are year, quarter, month, week (ISO 8601, weeks starting on
+// Monday), day, hour, minute, second, dow (0 for Sunday), doy and epoch
+// (seconds from 1970-01-01 00:00:00). Adding an integer to a date moves it
+// by that many days, and one date minus another is the days between them.
+
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::prelude::*;
+use crate::ast::BinaryOperator;
+use crate::eval::{EvalError, Value};
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SECOND;
+
+// === Values ===
+
+/// A calendar date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Date {
+    days: i32,
+}
+
+/// A time of day, to the microsecond.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Time {
+    micros: i64,
+}
+
+/// A date and a time of day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Timestamp {
+    micros: i64,
+}
+
+impl Date {
+    /// The date, if `month` and `day` exist in `year` and the year is
+    /// between 1 and 9999.
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Option<Self> {
+        let valid = (1..=9999).contains(&year) && (1..=12).contains(&month) && day >= 1 && day <= days_in(year, month);
+        valid.then(|| Date { days: days_from_civil(year, month, day) })
+    }
+
+    /// The date `days` days after 1970-01-01, if it is in range.
+    pub fn from_days(days: i64) -> Option<Self> {
+        let (first, last) = (days_from_civil(1, 1, 1), days_from_civil(9999, 12, 31));
+        let days = i32::try_from(days).ok().filter(|days| (first..=last).contains(days))?;
+        Some(Date { days })
+    }
+
+    /// Days after 1970-01-01, negative before it.
+    pub fn days(self) -> i32 {
+        self.days
+    }
+
+    pub fn year(self) -> i32 {
+        civil_from_days(self.days).0
+    }
+
+    pub fn month(self) -> u32 {
+        civil_from_days(self.days).1
+    }
+
+    pub fn day(self) -> u32 {
+        civil_from_days(self.days).2
+    }
+
+    /// 0 for Sunday through 6 for Saturday.
+    pub fn weekday(self) -> u32 {
+        // 1970-01-01 was a Thursday
+        (i64::from(self.days) + 4).rem_euclid(7) as u32
+    }
+
+    /// The day of the year, from 1.
+    pub fn ordinal(self) -> u32 {
+        (self.days - days_from_civil(self.year(), 1, 1) + 1) as u32
+    }
+
+    /// The ISO 8601 week number, from 1 to 53; the days of a week before
+    /// its year's first Thursday belong to the last week of the year before.
+    pub fn iso_week(self) -> u32 {
+        let monday_based = (self.weekday() + 6) % 7;
+        let week = (self.ordinal() as i64 - monday_based as i64 + 9) / 7;
+        if week < 1 {
+            weeks_in(self.year() - 1)
+        } else if week as u32 > weeks_in(self.year()) {
+            1
+        } else {
+            week as u32
+        }
+    }
+
+    /// `YYYY-MM-DD`, with one or two digits for the month and day.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.splitn(3, '-');
+        let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+        if year.len() != 4 || !(1..=2).contains(&month.len()) || !(1..=2).contains(&day.len()) {
+            return None;
+        }
+        Date::from_ymd(digits(year)? as i32, digits(month)? as u32, digits(day)? as u32)
+    }
+}
+
+impl Time {
+    pub fn from_hms_micro(hour: u32, minute: u32, second: u32, micro: u32) -> Option<Self> {
+        let valid = hour < 24 && minute < 60 && second < 60 && micro < 1_000_000;
+        let seconds = i64::from(hour) * 3600 + i64::from(minute) * 60 + i64::from(second);
+        valid.then(|| Time { micros: seconds * MICROS_PER_SECOND + i64::from(micro) })
+    }
+
+    /// The time `micros` microseconds after midnight, if that is before the
+    /// next midnight.
+    pub fn from_micros(micros: i64) -> Option<Self> {
+        (0..MICROS_PER_DAY).contains(&micros).then_some(Time { micros })
+    }
+
+    /// Microseconds after midnight.
+    pub fn micros(self) -> i64 {
+        self.micros
+    }
+
+    pub fn hour(self) -> u32 {
+        (self.micros / (3600 * MICROS_PER_SECOND)) as u32
+    }
+
+    pub fn minute(self) -> u32 {
+        (self.micros / (60 * MICROS_PER_SECOND) % 60) as u32
+    }
+
+    pub fn second(self) -> u32 {
+        (self.micros / MICROS_PER_SECOND % 60) as u32
+    }
+
+    pub fn microsecond(self) -> u32 {
+        (self.micros % MICROS_PER_SECOND) as u32
+    }
+
+    /// `HH:MM`, `HH:MM:SS` or `HH:MM:SS.ffffff` with one to six digits
+    /// of fraction.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (clock, fraction) = match text.split_once('.') {
+            Some((clock, fraction)) if (1..=6).contains(&fraction.len()) => (clock, fraction),
+            Some(_) => return None,
+            None => (text, ""),
+        };
+        let mut parts = clock.split(':');
+        let mut next = || parts.next().filter(|part| part.len() == 2).and_then(digits);
+        let (hour, minute) = (next()?, next()?);
+        let second = if clock.len() > 5 { next()? } else { 0 };
+        if parts.next().is_some() || (clock.len() == 5 && !fraction.is_empty()) {
+            return None;
+        }
+        let micro = if fraction.is_empty() { 0 } else { digits(fraction)? * 10u64.pow(6 - fraction.len() as u32) };
+        Time::from_hms_micro(hour as u32, minute as u32, second as u32, micro as u32)
+    }
+}
+
+impl Timestamp {
+    pub fn new(date: Date, time: Time) -> Self {
+        Timestamp { micros: i64::from(date.days) * MICROS_PER_DAY + time.micros }
+    }
+
+    /// The timestamp `micros` microseconds after 1970-01-01 00:00:00, if its
+    /// date is in range.
+    pub fn from_micros(micros: i64) -> Option<Self> {
+        Date::from_days(micros.div_euclid(MICROS_PER_DAY))?;
+        Some(Timestamp { micros })
+    }
+
+    /// Microseconds after 1970-01-01 00:00:00, negative before it.
+    pub fn micros(self) -> i64 {
+        self.micros
+    }
+
+    pub fn date(self) -> Date {
+        Date { days: self.micros.div_euclid(MICROS_PER_DAY) as i32 }
+    }
+
+    pub fn time(self) -> Time {
+        Time { micros: self.micros.rem_euclid(MICROS_PER_DAY) }
+    }
+
+    /// A date, then optionally a space or `T` and a time; a date alone is
+    /// its midnight.
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.split_once([' ', 'T']) {
+            Some((date, time)) => Some(Timestamp::new(Date::parse(date)?, Time::parse(time)?)),
+            None => Some(Timestamp::from(Date::parse(text)?)),
+        }
+    }
+}
+
+/// Midnight on the date.
+impl From<Date> for Timestamp {
+    fn from(date: Date) -> Self {
+        Timestamp::new(date, Time { micros: 0 })
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = civil_from_days(self.days);
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// The fraction of a second is only shown when there is one, without
+/// trailing zeros.
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.hour(), self.minute(), self.second())?;
+        if self.microsecond() != 0 {
+            let fraction = format!("{:06}", self.microsecond());
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.date(), self.time())
+    }
+}
+
+// === Calendar arithmetic ===
+
+fn is_leap(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// 53 when the year starts on a Thursday, or is a leap year that starts
+/// on a Wednesday.
+fn weeks_in(year: i32) -> u32 {
+    let first = Date { days: days_from_civil(year, 1, 1) }.weekday();
+    if first == 4 || (first == 3 && is_leap(year)) {
+        53
+    } else {
+        52
+    }
+}
+
+/// Days from 1970-01-01, counting years from March so that the leap day
+/// comes last.
+fn days_from_civil(year: i32, month: u32, day: u32) -> i32 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year as i32;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i32) -> (i32, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 } as u32;
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+/// An unsigned number of ASCII digits.
+fn digits(text: &str) -> Option<u64> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+// === Fields ===
+
+/// The parts of a value that `date_part` and `date_trunc` name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Year,
+    Quarter,
+    Month,
+    Week,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    DayOfWeek,
+    DayOfYear,
+    Epoch,
+}
+
+impl Field {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "year" => Field::Year,
+            "quarter" => Field::Quarter,
+            "month" => Field::Month,
+            "week" => Field::Week,
+            "day" => Field::Day,
+            "hour" => Field::Hour,
+            "minute" => Field::Minute,
+            "second" => Field::Second,
+            "dow" => Field::DayOfWeek,
+            "doy" => Field::DayOfYear,
+            "epoch" => Field::Epoch,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Year => "year",
+            Field::Quarter => "quarter",
+            Field::Month => "month",
+            Field::Week => "week",
+            Field::Day => "day",
+            Field::Hour => "hour",
+            Field::Minute => "minute",
+            Field::Second => "second",
+            Field::DayOfWeek => "dow",
+            Field::DayOfYear => "doy",
+            Field::Epoch => "epoch",
+        }
+    }
+
+    /// Whether the field is part of the date rather than the time of day.
+    fn of_date(self) -> bool {
+        !matches!(self, Field::Hour | Field::Minute | Field::Second | Field::Epoch)
+    }
+}
+
+/// `field` of a date and a time of day; a date's time is midnight.
+fn part(field: Field, date: Date, time: Time) -> i64 {
+    match field {
+        Field::Year => i64::from(date.year()),
+        Field::Quarter => i64::from((date.month() - 1) / 3 + 1),
+        Field::Month => i64::from(date.month()),
+        Field::Week => i64::from(date.iso_week()),
+        Field::Day => i64::from(date.day()),
+        Field::Hour => i64::from(time.hour()),
+        Field::Minute => i64::from(time.minute()),
+        Field::Second => i64::from(time.second()),
+        Field::DayOfWeek => i64::from(date.weekday()),
+        Field::DayOfYear => i64::from(date.ordinal()),
+        Field::Epoch => Timestamp::new(date, time).micros.div_euclid(MICROS_PER_SECOND),
+    }
+}
+
+/// The first date of the period `field` names that holds `date`.
+fn truncate_date(field: Field, date: Date) -> Date {
+    let (year, month, _) = civil_from_days(date.days);
+    let days = match field {
+        Field::Year => days_from_civil(year, 1, 1),
+        Field::Quarter => days_from_civil(year, (month - 1) / 3 * 3 + 1, 1),
+        Field::Month => days_from_civil(year, month, 1),
+        Field::Week => date.days - ((date.weekday() + 6) % 7) as i32,
+        _ => date.days,
+    };
+    Date { days }
+}
+
+fn truncate_time(field: Field, time: Time) -> Time {
+    let unit = match field {
+        Field::Hour => 3600 * MICROS_PER_SECOND,
+        Field::Minute => 60 * MICROS_PER_SECOND,
+        Field::Second => MICROS_PER_SECOND,
+        _ => MICROS_PER_DAY,
+    };
+    Time { micros: time.micros - time.micros % unit }
+}
+
+// === Functions and operators ===
+
+/// Calls the builtin function `name`, or returns `None` when there is no
+/// builtin of that name.
+pub(crate) fn call(name: &str, args: &[Value]) -> Option<Result<Value, EvalError>> {
+    let truncate = match name.to_ascii_lowercase().as_str() {
+        "date_part" => false,
+        "date_trunc" => true,
+        _ => return None,
+    };
+    Some(match args {
+        [Value::Null, _] | [_, Value::Null] => Ok(Value::Null),
+        [Value::String(field), value] => match Field::from_name(field) {
+            Some(field) if truncate => date_trunc(field, value),
+            Some(field) => date_part(field, value),
+            None => Err(EvalError::TypeMismatch(format!("{} is not a field of a date or time", field))),
+        },
+        _ => Err(EvalError::TypeMismatch(format!("{} takes a field name and a date, time or timestamp", name))),
+    })
+}
+
+fn date_part(field: Field, value: &Value) -> Result<Value, EvalError> {
+    let midnight = Time { micros: 0 };
+    match *value {
+        Value::Date(date) => Ok(Value::Int(part(field, date, midnight))),
+        Value::Timestamp(timestamp) => Ok(Value::Int(part(field, timestamp.date(), timestamp.time()))),
+        Value::Time(time) if !field.of_date() => {
+            let epoch = Date { days: 0 };
+            Ok(Value::Int(part(field, epoch, time)))
+        }
+        ref other => Err(cannot("extract", field, other)),
+    }
+}
+
+fn date_trunc(field: Field, value: &Value) -> Result<Value, EvalError> {
+    if matches!(field, Field::DayOfWeek | Field::DayOfYear | Field::Epoch) {
+        return Err(EvalError::TypeMismatch(format!("cannot truncate to {}", field.name())));
+    }
+    match *value {
+        Value::Date(date) => Ok(Value::Date(truncate_date(field, date))),
+        Value::Timestamp(timestamp) if field.of_date() => {
+            Ok(Value::Timestamp(Timestamp::from(truncate_date(field, timestamp.date()))))
+        }
+        Value::Timestamp(timestamp) => {
+            Ok(Value::Timestamp(Timestamp::new(timestamp.date(), truncate_time(field, timestamp.time()))))
+        }
+        Value::Time(time) if !field.of_date() => Ok(Value::Time(truncate_time(field, time))),
+        ref other => Err(cannot("truncate", field, other)),
+    }
+}
+
+fn cannot(doing: &str, field: Field, value: &Value) -> EvalError {
+    EvalError::TypeMismatch(format!("cannot {} {} from {:?}", doing, field.name(), value))
+}
+
+/// The order of two values when either is a date, time or timestamp, or
+/// `None` when neither is. A string is read as the other value's type.
+pub(crate) fn compare(left: &Value, right: &Value) -> Option<Result<Ordering, EvalError>> {
+    let temporal = |value: &Value| matches!(value, Value::Date(_) | Value::Time(_) | Value::Timestamp(_));
+    let timestamp = |value: &Value| match *value {
+        Value::Date(date) => Some(Timestamp::from(date)),
+        Value::Timestamp(timestamp) => Some(timestamp),
+        _ => None,
+    };
+    let ordering = match (left, right) {
+        _ if !temporal(left) && !temporal(right) => return None,
+        (Value::String(text), value) | (value, Value::String(text)) => {
+            let Some(parsed) = parse_as(value, text) else {
+                return Some(Err(EvalError::TypeMismatch(format!("{:?} is not a {}", text, kind(value)))));
+            };
+            let ordering = compare(value, &parsed)?;
+            return Some(if matches!(left, Value::String(_)) { ordering.map(Ordering::reverse) } else { ordering });
+        }
+        (Value::Time(a), Value::Time(b)) => a.cmp(b),
+        _ => match (timestamp(left), timestamp(right)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => return Some(Err(EvalError::TypeMismatch(format!("cannot compare {:?} and {:?}", left, right)))),
+        },
+    };
+    Some(Ok(ordering))
+}
+
+/// `text` read as a value of the same type as `value`.
+fn parse_as(value: &Value, text: &str) -> Option<Value> {
+    match value {
+        Value::Date(_) => Date::parse(text).map(Value::Date),
+        Value::Time(_) => Time::parse(text).map(Value::Time),
+        _ => Timestamp::parse(text).map(Value::Timestamp),
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Date(_) => "date",
+        Value::Time(_) => "time",
+        _ => "timestamp",
+    }
+}
+
+/// A date plus or minus a number of days, or the days between two dates;
+/// `None` for any other operator or operands.
+pub(crate) fn arithmetic(operator: &BinaryOperator, left: &Value, right: &Value) -> Option<Result<Value, EvalError>> {
+    let subtract = match operator {
+        BinaryOperator::Add => false,
+        BinaryOperator::Subtract => true,
+        _ => return None,
+    };
+    let shift = |date: &Date, days: i64| {
+        let days = if subtract { days.checked_neg() } else { Some(days) };
+        let shifted = days.and_then(|days| i64::from(date.days).checked_add(days)).and_then(Date::from_days);
+        shifted.map(Value::Date).ok_or(EvalError::Overflow)
+    };
+    match (left, right) {
+        (Value::Date(date), Value::Int(days)) => Some(shift(date, *days)),
+        (Value::Int(days), Value::Date(date)) if !subtract => Some(shift(date, *days)),
+        (Value::Date(a), Value::Date(b)) if subtract => Some(Ok(Value::Int(i64::from(a.days) - i64::from(b.days)))),
+        _ => None,
+    }
+}
@@ -0,0 +1,145 @@
+// dead_code.rs — the tables and columns no query of a workload references
+// Author: Sreerag Devadasan
+//
+// Before a column is dropped, every query that might read or write it
+// has to be found. `unused` runs `dependencies` over the statements of a
+// workload and reports the tables of a `Schema` that none of them names,
+// and the columns of the other tables that none of them may use: the
+// candidates for a DROP in a migration. `analyze_workload` first splits
+// every `.sql` file of a directory into statements and parses them on the
+// batch parser's worker threads.
+//
+// A use is sometimes assumed where there is none (an unqualified name
+// counts for each table of its query that has the column), but never
+// missed, except in statements that fail to parse. The report lists
+// those, as what they use can't be known.
+
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{fs, io, path::Path, path::PathBuf};
+
+use crate::prelude::*;
+use crate::ast::Statement;
+#[cfg(feature = "std")]
+use crate::batch::{parse_many, split_statements};
+use crate::dependencies::{dependencies, Dependencies};
+use crate::json;
+use crate::schema_diff::Schema;
+
+/// The schema objects a workload never references.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeadCode {
+    /// How many statements the workload has, parsed or not.
+    pub statements: usize,
+    /// `file:line` and error of every statement that failed to parse.
+    pub failures: Vec<(String, String)>,
+    pub tables: Vec<String>,
+    /// The unreferenced columns of the tables that are referenced, as
+    /// `(table, column)`.
+    pub columns: Vec<(String, String)>,
+}
+
+impl DeadCode {
+    pub fn to_json(&self) -> String {
+        let failures = self.failures.iter().map(|(location, error)| {
+            json::object([("location", json::string(location)), ("error", json::string(error))])
+        });
+        let columns = self.columns.iter().map(|(table, column)| {
+            json::object([("table", json::string(table)), ("column", json::string(column))])
+        });
+        json::object([
+            ("statements", self.statements.to_string()),
+            ("failures", json::array(failures)),
+            ("tables", json::array(self.tables.iter().map(|table| json::string(table)))),
+            ("columns", json::array(columns)),
+        ])
+    }
+}
+
+impl fmt::Display for DeadCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for table in &self.tables {
+            writeln!(f, "unused table {}", table)?;
+        }
+        for (table, column) in &self.columns {
+            writeln!(f, "unused column {}.{}", table, column)?;
+        }
+        for (location, error) in &self.failures {
+            writeln!(f, "⚠️ {}: {}", location, error)?;
+        }
+        write!(
+            f,
+            "{} statements, {} failed to parse: {} unused tables, {} unused columns",
+            self.statements,
+            self.failures.len(),
+            self.tables.len(),
+            self.columns.len()
+        )?;
+        if !self.failures.is_empty() {
+            write!(f, " (what the failed statements use is unknown, and may be listed)")?;
+        }
+        Ok(())
+    }
+}
+
+/// The tables and columns of `schema` none of `statements` references.
+pub fn unused<'a>(schema: &Schema, statements: impl IntoIterator<Item = &'a Statement>) -> DeadCode {
+    let found: Vec<Dependencies> = statements.into_iter().map(dependencies).collect();
+    let mut report = DeadCode { statements: found.len(), ..DeadCode::default() };
+    for (table, columns) in &schema.tables {
+        if !found.iter().any(|dependencies| dependencies.uses_table(table)) {
+            report.tables.push(table.clone());
+            continue;
+        }
+        for column in columns {
+            let name = column.column_name.as_str();
+            if !found.iter().any(|dependencies| dependencies.uses_column(table, name)) {
+                report.columns.push((table.clone(), name.to_string()));
+            }
+        }
+    }
+    report
+}
+
+/// Parses the workload at `path`, a `.sql` file or a directory of them,
+/// and reports what of `schema` none of its statements references.
+#[cfg(feature = "std")]
+pub fn analyze_workload(path: impl AsRef<Path>, schema: &Schema) -> io::Result<DeadCode> {
+    let path = path.as_ref();
+    let mut files: Vec<PathBuf> = Vec::new();
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let file = entry?.path();
+            if file.extension().is_some_and(|e| e == "sql") {
+                files.push(file);
+            }
+        }
+        files.sort();
+    } else {
+        files.push(path.to_path_buf());
+    }
+
+    let mut scripts = Vec::new();
+    for file in files {
+        scripts.push((file.display().to_string(), fs::read_to_string(&file)?));
+    }
+    let mut locations = Vec::new();
+    let mut sql = Vec::new();
+    for (name, script) in &scripts {
+        for (span, statement) in split_statements(script) {
+            let line = script[..span.start].matches('\n').count() + 1;
+            locations.push(format!("{}:{}", name, line));
+            sql.push(statement);
+        }
+    }
+
+    let results = parse_many(&sql);
+    let mut report = unused(schema, results.iter().filter_map(|result| result.as_ref().ok()));
+    report.statements = results.len();
+    for (location, result) in locations.into_iter().zip(&results) {
+        if let Err(e) = result {
+            report.failures.push((location, e.to_string()));
+        }
+    }
+    Ok(report)
+}
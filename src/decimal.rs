@@ -0,0 +1,267 @@
+// decimal.rs — exact decimal numbers for DECIMAL columns
+// Author: Sreerag Devadasan
+//
+// A `Decimal` is an integer mantissa and a scale, the number of its digits
+// after the point: 19.99 is 1999 at scale 2. It holds up to 38 digits in
+// all, and arithmetic on it is exact, or rounds half away from zero where
+// it must, so sums of prices come out to the cent. Numbers written with a
+// point, such as `9.99`, are decimals; an integer next to a decimal takes
+// part as a decimal of scale 0, and a string compared with a decimal is
+// read as one, like the strings compared with dates (see datetime.rs).
+//
+// The scale of a result follows from those of the operands, so that
+// `price * quantity` has as many digits after the point as the product
+// needs:
+//
+// - a sum or difference takes the larger scale of the two, `1.5 + 0.25`
+//   being `1.75`;
+// - a product takes the sum of the scales, `1.5 * 0.25` being `0.375`;
+// - a quotient takes the larger scale of the two, but at least
+//   `DIVISION_SCALE`, and is rounded there: `1.00 / 3` is `0.333333`.
+//
+// A result with more than 38 digits is an overflow. A DECIMAL(p, s) column
+// rounds what it's given to s digits after the point and refuses a value
+// that then needs more than p digits in all.
+
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::prelude::*;
+use crate::ast::BinaryOperator;
+use crate::eval::{EvalError, Value};
+
+/// The most digits a decimal can have, before and after the point.
+pub const MAX_PRECISION: u8 = 38;
+
+/// The fewest digits after the point that a quotient is computed to.
+pub const DIVISION_SCALE: u8 = 6;
+
+const LIMIT: u128 = 10u128.pow(MAX_PRECISION as u32);
+
+/// An exact decimal number. Decimals of different scales that are the same
+/// number are equal: 1.5 = 1.50.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    /// The largest decimal, 38 nines.
+    pub const MAX: Decimal = Decimal { mantissa: LIMIT as i128 - 1, scale: 0 };
+
+    /// The largest value of a DECIMAL(`precision`, `scale`) column, or its
+    /// smallest when `negative`.
+    pub fn largest(precision: u8, scale: u8, negative: bool) -> Self {
+        let mantissa = pow10(precision.min(MAX_PRECISION)).unwrap_or(LIMIT as i128) - 1;
+        Decimal { mantissa: if negative { -mantissa } else { mantissa }, scale: scale.min(precision) }
+    }
+
+    /// `mantissa` divided by 10 to the power `scale`, if both fit in 38
+    /// digits.
+    pub fn new(mantissa: i128, scale: u8) -> Option<Self> {
+        (scale <= MAX_PRECISION && mantissa.unsigned_abs() < LIMIT).then_some(Decimal { mantissa, scale })
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// The number of digits, counting those after the point: 2 for 0.05,
+    /// 3 for 19.9 and 1 for 0.
+    pub fn precision(&self) -> u8 {
+        let mut digits = 1;
+        let mut rest = self.mantissa.unsigned_abs() / 10;
+        while rest > 0 {
+            digits += 1;
+            rest /= 10;
+        }
+        digits.max(self.scale)
+    }
+
+    /// Reads `-12.50`, `3` or `.5`; the scale is the number of digits
+    /// written after the point.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if whole.len() + fraction.len() == 0 || !all_digits(whole) || !all_digits(fraction) {
+            return None;
+        }
+        let scale = u8::try_from(fraction.len()).ok()?;
+        let mut mantissa: i128 = 0;
+        for b in whole.bytes().chain(fraction.bytes()) {
+            mantissa = mantissa.checked_mul(10)?.checked_add(i128::from(b - b'0'))?;
+        }
+        Decimal::new(if negative { -mantissa } else { mantissa }, scale)
+    }
+
+    /// The same number with `scale` digits after the point, rounded half
+    /// away from zero when that drops digits.
+    pub fn rescale(&self, scale: u8) -> Option<Self> {
+        if scale >= self.scale {
+            let mantissa = self.mantissa.checked_mul(pow10(scale - self.scale)?)?;
+            return Decimal::new(mantissa, scale);
+        }
+        let divisor = pow10(self.scale - scale)?;
+        Decimal::new(divide_rounding(self.mantissa, divisor), scale)
+    }
+
+    /// The value a DECIMAL(`precision`, `scale`) column stores for this
+    /// number, if it fits.
+    pub fn fit(&self, precision: u8, scale: u8) -> Option<Self> {
+        self.rescale(scale).filter(|fitted| fitted.precision() <= precision)
+    }
+
+    /// `-self`; every decimal has a negation.
+    pub fn negate(&self) -> Self {
+        Decimal { mantissa: -self.mantissa, scale: self.scale }
+    }
+
+    pub fn add(&self, other: &Decimal) -> Result<Self, EvalError> {
+        let scale = self.scale.max(other.scale);
+        let (a, b) = (self.rescale(scale), other.rescale(scale));
+        let sum = a.zip(b).and_then(|(a, b)| a.mantissa.checked_add(b.mantissa));
+        sum.and_then(|mantissa| Decimal::new(mantissa, scale)).ok_or(EvalError::Overflow)
+    }
+
+    pub fn subtract(&self, other: &Decimal) -> Result<Self, EvalError> {
+        self.add(&other.negate())
+    }
+
+    pub fn multiply(&self, other: &Decimal) -> Result<Self, EvalError> {
+        let mantissa = self.mantissa.checked_mul(other.mantissa).ok_or(EvalError::Overflow)?;
+        let product = Decimal { mantissa, scale: self.scale + other.scale };
+        // Past 38 digits after the point only rounding makes it fit
+        let scale = product.scale.min(MAX_PRECISION);
+        let divisor = pow10(product.scale - scale).ok_or(EvalError::Overflow)?;
+        Decimal::new(divide_rounding(mantissa, divisor), scale).ok_or(EvalError::Overflow)
+    }
+
+    pub fn divide(&self, other: &Decimal) -> Result<Self, EvalError> {
+        if other.mantissa == 0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        // a / 10^sa ÷ b / 10^sb at scale s is a * 10^(s + sb - sa) / b
+        let scale = self.scale.max(other.scale).max(DIVISION_SCALE);
+        let shift = pow10(scale + other.scale - self.scale).ok_or(EvalError::Overflow)?;
+        let dividend = self.mantissa.checked_mul(shift).ok_or(EvalError::Overflow)?;
+        Decimal::new(divide_rounding(dividend, other.mantissa), scale).ok_or(EvalError::Overflow)
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(n: i64) -> Self {
+        Decimal { mantissa: i128::from(n), scale: 0 }
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        // A mantissa that overflows at the larger scale is the larger in
+        // magnitude, since the other one fits in 38 digits there
+        match (self.rescale(scale), other.rescale(scale)) {
+            (Some(a), Some(b)) => a.mantissa.cmp(&b.mantissa),
+            (None, _) => self.mantissa.signum().cmp(&0),
+            (_, None) => 0.cmp(&other.mantissa.signum()),
+        }
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = usize::from(self.scale);
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        if fraction.is_empty() {
+            write!(f, "{}{}", sign, whole)
+        } else {
+            write!(f, "{}{}.{}", sign, whole, fraction)
+        }
+    }
+}
+
+fn pow10(exponent: u8) -> Option<i128> {
+    10i128.checked_pow(u32::from(exponent))
+}
+
+/// `a / b` rounded half away from zero.
+fn divide_rounding(a: i128, b: i128) -> i128 {
+    let (quotient, remainder) = (a / b, a % b);
+    if remainder.unsigned_abs() >= b.unsigned_abs() - remainder.unsigned_abs() {
+        quotient + a.signum() * b.signum()
+    } else {
+        quotient
+    }
+}
+
+// === Operators on values ===
+
+/// A decimal, or an integer next to one, as a decimal.
+fn decimal_operands(left: &Value, right: &Value) -> Option<(Decimal, Decimal)> {
+    match (left, right) {
+        (Value::Decimal(a), Value::Decimal(b)) => Some((*a, *b)),
+        (Value::Decimal(a), Value::Int(b)) => Some((*a, Decimal::from(*b))),
+        (Value::Int(a), Value::Decimal(b)) => Some((Decimal::from(*a), *b)),
+        _ => None,
+    }
+}
+
+/// Compares a decimal with a decimal, an integer or a string read as a
+/// decimal; `None` when neither side is a decimal.
+pub(crate) fn compare(left: &Value, right: &Value) -> Option<Result<Ordering, EvalError>> {
+    if let Some((a, b)) = decimal_operands(left, right) {
+        return Some(Ok(a.cmp(&b)));
+    }
+    let (decimal, text, flipped) = match (left, right) {
+        (Value::Decimal(decimal), Value::String(text)) => (decimal, text, false),
+        (Value::String(text), Value::Decimal(decimal)) => (decimal, text, true),
+        (Value::Decimal(_), other) | (other, Value::Decimal(_)) => {
+            return Some(Err(EvalError::TypeMismatch(format!("cannot compare a decimal and {:?}", other))));
+        }
+        _ => return None,
+    };
+    let Some(parsed) = Decimal::parse(text.trim()) else {
+        return Some(Err(EvalError::TypeMismatch(format!("{:?} is not a decimal", text))));
+    };
+    let ordering = decimal.cmp(&parsed);
+    Some(Ok(if flipped { ordering.reverse() } else { ordering }))
+}
+
+/// The four arithmetic operators on decimals and integers; `None` when
+/// neither operand is a decimal.
+pub(crate) fn arithmetic(operator: &BinaryOperator, left: &Value, right: &Value) -> Option<Result<Value, EvalError>> {
+    let (a, b) = decimal_operands(left, right)?;
+    let result = match operator {
+        BinaryOperator::Add => a.add(&b),
+        BinaryOperator::Subtract => a.subtract(&b),
+        BinaryOperator::Multiply => a.multiply(&b),
+        BinaryOperator::Divide => a.divide(&b),
+        _ => return None,
+    };
+    Some(result.map(Value::Decimal))
+}
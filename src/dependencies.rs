@@ -0,0 +1,272 @@
+// dependencies.rs — the tables and columns a statement reads or writes
+// Author: Sreerag Devadasan
+//
+// `dependencies` lists what a statement would break on if it went from
+// the schema: the tables it names and, for each column name it mentions,
+// the tables the column may belong to. A qualified name (`u.id`) belongs
+// to the table its qualifier names or aliases; an unqualified one to any
+// table of its SELECT (or of the UPDATE, DELETE or MERGE), as which one
+// has it takes a schema to tell. Names bound by WITH and the aliases of
+// subqueries aren't tables: the columns read through them are found in
+// the queries that define them. `*`, and an INSERT, COPY or LOAD DATA
+// without a column list, use every column of their tables.
+//
+// Only queries, DML and the queries of views count. Other DDL and
+// privilege statements name tables without depending on what they hold.
+
+use crate::prelude::*;
+use crate::ast::{
+    Expression, GroupingElement, MergeAction, MergeSource, SelectItem, Statement, TableFactor, TriggerBody,
+};
+use crate::ident::Ident;
+
+/// A column name a statement uses, with the tables it may be a column of.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnReference {
+    pub column: String,
+    /// The table its qualifier names, or every table the name is in scope
+    /// of when it isn't qualified.
+    pub tables: Vec<String>,
+}
+
+/// What one statement reads or writes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dependencies {
+    /// Every table, once each in source order.
+    pub tables: Vec<String>,
+    pub columns: Vec<ColumnReference>,
+    /// The tables the statement uses every column of.
+    pub all_columns: Vec<String>,
+}
+
+impl Dependencies {
+    /// Whether the statement names `table` (case-insensitively).
+    pub fn uses_table(&self, table: &str) -> bool {
+        self.tables.iter().any(|name| name.eq_ignore_ascii_case(table))
+    }
+
+    /// Whether the statement may use `column` of `table`.
+    pub fn uses_column(&self, table: &str, column: &str) -> bool {
+        self.all_columns.iter().any(|name| name.eq_ignore_ascii_case(table))
+            || self.columns.iter().any(|reference| {
+                reference.column.eq_ignore_ascii_case(column)
+                    && reference.tables.iter().any(|name| name.eq_ignore_ascii_case(table))
+            })
+    }
+}
+
+/// The tables and columns `statement`, and every statement nested in it,
+/// depends on.
+pub fn dependencies(statement: &Statement) -> Dependencies {
+    let mut collector = Collector::default();
+    collector.statement(statement);
+    collector.found
+}
+
+/// A FROM item, by the name its columns are qualified with, and the table
+/// it is, if it's one.
+#[derive(Clone, Copy)]
+struct Source<'a> {
+    name: &'a str,
+    table: Option<&'a str>,
+}
+
+#[derive(Default)]
+struct Collector<'a> {
+    /// The names bound by the WITH clauses around the current statement.
+    ctes: Vec<&'a str>,
+    found: Dependencies,
+}
+
+impl<'a> Collector<'a> {
+    fn statement(&mut self, statement: &'a Statement) {
+        match statement {
+            Statement::Select { columns, wildcard, from, joins, selection, group_by, qualify, order_by, .. } => {
+                let mut scope = Vec::new();
+                self.factor(from, &mut scope);
+                for join in joins {
+                    self.factor(&join.relation, &mut scope);
+                }
+                if columns.iter().any(SelectItem::is_wildcard) {
+                    self.all_columns(&scope);
+                }
+                for column in &wildcard.exclude {
+                    self.unqualified(column, &scope);
+                }
+                let mut exprs: Vec<&Expression> =
+                    columns.iter().filter(|item| !item.is_wildcard()).map(|item| &item.expr).collect();
+                exprs.extend(wildcard.replace.iter().map(|(expr, _)| expr));
+                exprs.extend(joins.iter().flat_map(|join| &join.on));
+                exprs.extend(selection.iter().chain(group_by.iter().flat_map(GroupingElement::expressions)));
+                exprs.extend(qualify);
+                exprs.extend(order_by.iter().map(|item| &item.expr));
+                self.expressions(exprs, &scope);
+            }
+            Statement::SetOperation { left, right, .. } => {
+                self.statement(left);
+                self.statement(right);
+            }
+            Statement::With { ctes, body, .. } => {
+                let outer = self.ctes.len();
+                self.ctes.extend(ctes.iter().map(|cte| cte.name.as_str()));
+                for cte in ctes {
+                    self.statement(&cte.query);
+                }
+                self.statement(body);
+                self.ctes.truncate(outer);
+            }
+            Statement::Insert { table_name, columns, .. }
+            | Statement::Copy { table_name, columns, .. }
+            | Statement::LoadData { table_name, columns, .. } => {
+                let scope = [self.target(table_name, None)];
+                match columns {
+                    Some(columns) => columns.iter().for_each(|column| self.unqualified(column, &scope)),
+                    None => self.all_columns(&scope),
+                }
+            }
+            Statement::InsertSelect { table_name, columns, query } => {
+                let scope = [self.target(table_name, None)];
+                match columns {
+                    Some(columns) => columns.iter().for_each(|column| self.unqualified(column, &scope)),
+                    None => self.all_columns(&scope),
+                }
+                self.statement(query);
+            }
+            Statement::Update { table_name, alias, assignments, selection } => {
+                let scope = [self.target(table_name, alias.as_ref())];
+                for (column, _) in assignments {
+                    self.unqualified(column, &scope);
+                }
+                let values = assignments.iter().map(|(_, value)| value);
+                self.expressions(values.chain(selection).collect(), &scope);
+            }
+            Statement::Delete { table_name, alias, selection } => {
+                let scope = [self.target(table_name, alias.as_ref())];
+                self.expressions(selection.iter().collect(), &scope);
+            }
+            Statement::Merge { target, target_alias, source, source_alias, on, clauses } => {
+                let into = [self.target(target, target_alias.as_ref())];
+                let mut scope = into.to_vec();
+                match source {
+                    MergeSource::Table(name) => scope.push(self.target(name, source_alias.as_ref())),
+                    MergeSource::Query(query) => self.statement(query),
+                }
+                for clause in clauses {
+                    match &clause.action {
+                        MergeAction::Update(assignments) => {
+                            assignments.iter().for_each(|(column, _)| self.unqualified(column, &into));
+                        }
+                        MergeAction::Insert { columns: Some(columns), .. } => {
+                            columns.iter().for_each(|column| self.unqualified(column, &into));
+                        }
+                        MergeAction::Insert { columns: None, .. } => self.all_columns(&into),
+                        MergeAction::Delete => {}
+                    }
+                }
+                let exprs = core::iter::once(on).chain(clauses.iter().flat_map(|clause| clause.expressions()));
+                self.expressions(exprs.collect(), &scope);
+            }
+            Statement::CreateView { query, .. } => self.statement(query),
+            Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => self.statement(statement),
+            Statement::CreateTrigger { body: TriggerBody::Statement(statement), .. } => self.statement(statement),
+            _ => {}
+        }
+    }
+
+    /// Adds the sources of a FROM item to `scope`, and what its subqueries
+    /// and arguments use.
+    fn factor(&mut self, factor: &'a TableFactor, scope: &mut Vec<Source<'a>>) {
+        match factor {
+            TableFactor::Table { name, alias, .. } | TableFactor::AsOf { name, alias, .. } => {
+                let bound = self.ctes.iter().any(|cte| cte.eq_ignore_ascii_case(name));
+                let source = if bound {
+                    Source { name: alias.as_ref().map_or(name, |alias| &alias.name), table: None }
+                } else {
+                    self.target(name, alias.as_ref().map(|alias| &alias.name))
+                };
+                scope.push(source);
+            }
+            TableFactor::Derived { subquery, alias, .. } => {
+                self.statement(subquery);
+                if let Some(alias) = alias {
+                    scope.push(Source { name: &alias.name, table: None });
+                }
+            }
+            TableFactor::Function { args, alias, .. } => {
+                self.expressions(args.iter().collect(), scope);
+                if let Some(alias) = alias {
+                    scope.push(Source { name: &alias.name, table: None });
+                }
+            }
+            TableFactor::Pivot { table, aggregate, column, values, alias } => {
+                self.factor(table, scope);
+                self.unqualified(column, scope);
+                self.expressions(core::iter::once(aggregate).chain(values).collect(), scope);
+                self.rebind(alias.as_ref().map(|alias| &alias.name), scope);
+            }
+            TableFactor::Unpivot { table, columns, alias, .. } => {
+                self.factor(table, scope);
+                columns.iter().for_each(|column| self.unqualified(column, scope));
+                self.rebind(alias.as_ref().map(|alias| &alias.name), scope);
+            }
+        }
+    }
+
+    /// Names the table of the last source `alias` as well, as the alias of
+    /// a PIVOT or UNPIVOT is that of the table it reshapes.
+    fn rebind(&mut self, alias: Option<&'a Ident>, scope: &mut Vec<Source<'a>>) {
+        if let (Some(alias), Some(last)) = (alias, scope.last().copied()) {
+            scope.push(Source { name: alias, table: last.table });
+        }
+    }
+
+    /// The source of a table a statement names, adding it to the tables.
+    fn target(&mut self, table: &'a Ident, alias: Option<&'a Ident>) -> Source<'a> {
+        if !self.found.uses_table(table) {
+            self.found.tables.push(table.to_string());
+        }
+        Source { name: alias.unwrap_or(table), table: Some(table) }
+    }
+
+    fn expressions(&mut self, exprs: Vec<&'a Expression>, scope: &[Source<'a>]) {
+        for expr in exprs {
+            expr.walk(&mut |expr| match expr {
+                // Not the `*` of COUNT(*)
+                Expression::Identifier(name) if name != "*" => self.unqualified(name, scope),
+                Expression::CompoundIdentifier(parts) if parts.len() >= 2 => {
+                    let (qualifier, column) = (&parts[parts.len() - 2], &parts[parts.len() - 1]);
+                    match scope.iter().find(|source| source.name.eq_ignore_ascii_case(qualifier)) {
+                        Some(Source { table: Some(table), .. }) => self.column(column, vec![table.to_string()]),
+                        // A column of a subquery or CTE, found where it's defined
+                        Some(_) => {}
+                        // A qualifier that names nothing here could be any table
+                        None => self.unqualified(column, scope),
+                    }
+                }
+                _ => {}
+            });
+        }
+    }
+
+    fn unqualified(&mut self, column: &str, scope: &[Source<'a>]) {
+        let tables: Vec<String> = scope.iter().filter_map(|source| source.table).map(str::to_string).collect();
+        if !tables.is_empty() {
+            self.column(column, tables);
+        }
+    }
+
+    fn column(&mut self, column: &str, tables: Vec<String>) {
+        let reference = ColumnReference { column: column.to_string(), tables };
+        if !self.found.columns.contains(&reference) {
+            self.found.columns.push(reference);
+        }
+    }
+
+    fn all_columns(&mut self, scope: &[Source<'a>]) {
+        for table in scope.iter().filter_map(|source| source.table) {
+            if !self.found.all_columns.iter().any(|name| name.eq_ignore_ascii_case(table)) {
+                self.found.all_columns.push(table.to_string());
+            }
+        }
+    }
+}
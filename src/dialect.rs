@@ -0,0 +1,142 @@
+// dialect.rs — SQL dialects and their lexical differences
+// Author: Sreerag Devadasan
+
+use crate::prelude::*;
+use crate::parser::parse_sql_with_dialect;
+use crate::pratt::PrecedenceTable;
+use crate::semantics::{ErrorHandling, OnError};
+use crate::tokenizer::{ParseError, Tokenizer};
+
+/// SQL dialects understood by the tokenizer and the SQL printer.
+///
+/// `Generic` is the parser's original behaviour: both `'...'` and `"..."`
+/// are string literals. `Warehouse` covers the common ground of Snowflake,
+/// BigQuery and DuckDB: QUALIFY, ILIKE and `* EXCLUDE (...)`/`* REPLACE (...)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    #[default]
+    Generic,
+    Ansi,
+    MySql,
+    Postgres,
+    SqlServer,
+    Warehouse,
+}
+
+impl Dialect {
+    pub const ALL: [Dialect; 6] = [
+        Dialect::Generic,
+        Dialect::Ansi,
+        Dialect::MySql,
+        Dialect::Postgres,
+        Dialect::SqlServer,
+        Dialect::Warehouse,
+    ];
+
+    /// Looks a dialect up by its command-line name (case-insensitive).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|d| d.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dialect::Generic => "generic",
+            Dialect::Ansi => "ansi",
+            Dialect::MySql => "mysql",
+            Dialect::Postgres => "postgres",
+            Dialect::SqlServer => "sqlserver",
+            Dialect::Warehouse => "warehouse",
+        }
+    }
+
+    /// Whether `"..."` delimits an identifier rather than a string. BigQuery
+    /// reads it as a string instead; see `ParserOptions::double_quoted_strings`.
+    pub fn double_quoted_identifiers(&self) -> bool {
+        matches!(self, Dialect::Ansi | Dialect::Postgres | Dialect::SqlServer | Dialect::Warehouse)
+    }
+
+    /// Whether a backslash escapes the character after it in any quoted
+    /// string, as in MySQL. Elsewhere only an `E'...'` string has escapes.
+    pub fn backslash_escapes(&self) -> bool {
+        matches!(self, Dialect::MySql)
+    }
+
+    /// Whether `[...]` delimits an identifier.
+    pub fn bracketed_identifiers(&self) -> bool {
+        matches!(self, Dialect::SqlServer)
+    }
+
+    /// Whether ILIKE, case-insensitive LIKE, is an operator.
+    pub fn supports_ilike(&self) -> bool {
+        matches!(self, Dialect::Postgres | Dialect::Warehouse)
+    }
+
+    /// Whether a SELECT may filter on window functions with QUALIFY, and
+    /// modify `*` with EXCLUDE and REPLACE.
+    pub fn supports_warehouse_extensions(&self) -> bool {
+        matches!(self, Dialect::Warehouse)
+    }
+
+    /// Whether a FROM item may be followed by PIVOT and UNPIVOT.
+    pub fn supports_pivot(&self) -> bool {
+        matches!(self, Dialect::SqlServer | Dialect::Warehouse)
+    }
+
+    /// SQL Server has no TRUE/FALSE literals and uses 1/0 instead.
+    pub fn supports_boolean_literals(&self) -> bool {
+        !matches!(self, Dialect::SqlServer)
+    }
+
+    /// What arithmetic errors give in this dialect; see semantics.rs. Only
+    /// MySQL doesn't raise them all: `1 / 0` is NULL there and a string
+    /// that isn't a number casts to the number it starts with, or 0.
+    pub fn error_handling(&self) -> ErrorHandling {
+        match self {
+            Dialect::MySql => ErrorHandling {
+                division_by_zero: OnError::Null,
+                invalid_cast: OnError::Saturate,
+                ..ErrorHandling::STRICT
+            },
+            _ => ErrorHandling::STRICT,
+        }
+    }
+
+    /// How tightly the binary operators bind when parsing this dialect.
+    pub fn precedence(&self) -> &'static PrecedenceTable {
+        match self {
+            Dialect::Generic => &PrecedenceTable::GENERIC,
+            Dialect::Postgres => &PrecedenceTable::POSTGRES,
+            Dialect::MySql => &PrecedenceTable::MYSQL,
+            Dialect::Warehouse => &PrecedenceTable::WAREHOUSE,
+            Dialect::Ansi | Dialect::SqlServer => &PrecedenceTable::STANDARD,
+        }
+    }
+
+    /// Quotes an identifier in this dialect's style, but only when it
+    /// wouldn't survive tokenizing as a plain identifier.
+    pub fn quote_identifier(&self, name: &str) -> String {
+        if !needs_quoting(name) {
+            return name.to_string();
+        }
+        self.quote(name)
+    }
+
+    /// Quotes an identifier in this dialect's style.
+    pub fn quote(&self, name: &str) -> String {
+        match self {
+            Dialect::Generic | Dialect::MySql => format!("`{}`", name.replace('`', "``")),
+            Dialect::Ansi | Dialect::Postgres | Dialect::Warehouse => format!("\"{}\"", name.replace('"', "\"\"")),
+            Dialect::SqlServer => format!("[{}]", name.replace(']', "]]")),
+        }
+    }
+}
+
+/// Parses `sql` as dialect `from` and prints it back as dialect `to`.
+pub fn translate(sql: &str, from: Dialect, to: Dialect) -> Result<String, ParseError> {
+    Ok(parse_sql_with_dialect(sql, from)?.to_sql(to))
+}
+
+fn needs_quoting(name: &str) -> bool {
+    let starts_ok = name.chars().next().is_some_and(char::is_alphabetic);
+    !starts_ok || !name.chars().all(|c| c.is_alphanumeric() || c == '_') || Tokenizer::is_keyword(name)
+}
@@ -0,0 +1,339 @@
+// dsl.rs — typed query builder over the AST
+// Author: Sreerag Devadasan
+//
+// `sql_table!` declares a table as a Rust type with one typed column per
+// field, and `from`/`insert_into` build queries against it:
+//
+//     sql_table! {
+//         pub struct Users = "users" { id: u64, name: String, active: bool }
+//     }
+//     let query = from::<Users>().select(Users::name()).filter(Users::active().eq(true));
+//
+// Columns carry their table and value type, so comparing `id` with a
+// string or filtering a `Users` query on another table's column doesn't
+// compile. Queries lower to ordinary `Statement`s and print with the
+// dialect printer like parsed ones.
+
+use core::marker::PhantomData;
+use core::ops::Not;
+
+use crate::prelude::*;
+use crate::ast::{BinaryOperator, Expression, Ident, OrderByExpr, SelectItem, Statement, UnaryOperator};
+use crate::dialect::Dialect;
+
+/// A table declared with `sql_table!`.
+pub trait Table {
+    /// The table's name in SQL.
+    const NAME: &'static str;
+    /// Its column names, in declaration order.
+    const COLUMNS: &'static [&'static str];
+}
+
+/// Rust types that can be stored in a column.
+pub trait SqlValue {
+    fn to_expression(self) -> Expression;
+}
+
+impl SqlValue for u64 {
+    fn to_expression(self) -> Expression {
+        Expression::Number(self)
+    }
+}
+
+impl SqlValue for String {
+    fn to_expression(self) -> Expression {
+        Expression::String(self)
+    }
+}
+
+impl SqlValue for bool {
+    fn to_expression(self) -> Expression {
+        Expression::Boolean(self)
+    }
+}
+
+/// A column of table `T` holding values of type `V`.
+pub struct Column<T, V> {
+    name: &'static str,
+    marker: PhantomData<fn() -> (T, V)>,
+}
+
+impl<T, V> Clone for Column<T, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, V> Copy for Column<T, V> {}
+
+/// What a column of table `T` holding `V` can be compared with: a value
+/// of that type or another column of the same table and type.
+pub trait Operand<T, V> {
+    fn into_expression(self) -> Expression;
+}
+
+impl<T> Operand<T, u64> for u64 {
+    fn into_expression(self) -> Expression {
+        self.to_expression()
+    }
+}
+
+impl<T> Operand<T, String> for String {
+    fn into_expression(self) -> Expression {
+        self.to_expression()
+    }
+}
+
+impl<T> Operand<T, String> for &str {
+    fn into_expression(self) -> Expression {
+        self.to_string().to_expression()
+    }
+}
+
+impl<T> Operand<T, bool> for bool {
+    fn into_expression(self) -> Expression {
+        self.to_expression()
+    }
+}
+
+impl<T, V> Operand<T, V> for Column<T, V> {
+    fn into_expression(self) -> Expression {
+        Expression::Identifier(Ident::new(self.name))
+    }
+}
+
+impl<T: Table, V> Column<T, V> {
+    /// Used by `sql_table!`; `name` must be one of `T::COLUMNS`.
+    pub const fn new(name: &'static str) -> Self {
+        Self { name, marker: PhantomData }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn compare(self, operator: BinaryOperator, other: impl Operand<T, V>) -> Predicate<T> {
+        Predicate::new(Expression::BinaryOperation {
+            left_operand: Box::new(Operand::<T, V>::into_expression(self)),
+            operator,
+            right_operand: Box::new(other.into_expression()),
+        })
+    }
+
+    pub fn eq(self, other: impl Operand<T, V>) -> Predicate<T> {
+        self.compare(BinaryOperator::Equals, other)
+    }
+
+    pub fn ne(self, other: impl Operand<T, V>) -> Predicate<T> {
+        self.compare(BinaryOperator::NotEquals, other)
+    }
+
+    pub fn gt(self, other: impl Operand<T, V>) -> Predicate<T> {
+        self.compare(BinaryOperator::GreaterThan, other)
+    }
+
+    pub fn ge(self, other: impl Operand<T, V>) -> Predicate<T> {
+        self.compare(BinaryOperator::GreaterThanOrEqual, other)
+    }
+
+    pub fn lt(self, other: impl Operand<T, V>) -> Predicate<T> {
+        self.compare(BinaryOperator::LessThan, other)
+    }
+
+    pub fn le(self, other: impl Operand<T, V>) -> Predicate<T> {
+        self.compare(BinaryOperator::LessThanOrEqual, other)
+    }
+}
+
+impl<T: Table> Column<T, String> {
+    pub fn like(self, pattern: &str) -> Predicate<T> {
+        self.compare(BinaryOperator::Like, pattern)
+    }
+}
+
+/// A boolean condition on the columns of table `T`.
+pub struct Predicate<T> {
+    expr: Expression,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Predicate<T> {
+    fn clone(&self) -> Self {
+        Predicate::new(self.expr.clone())
+    }
+}
+
+impl<T> Predicate<T> {
+    fn new(expr: Expression) -> Self {
+        Self { expr, marker: PhantomData }
+    }
+
+    fn combine(self, operator: BinaryOperator, other: Predicate<T>) -> Self {
+        Predicate::new(Expression::BinaryOperation {
+            left_operand: Box::new(self.expr),
+            operator,
+            right_operand: Box::new(other.expr),
+        })
+    }
+
+    pub fn and(self, other: Predicate<T>) -> Self {
+        self.combine(BinaryOperator::And, other)
+    }
+
+    /// `self OR other`, parenthesized so it can be AND-ed safely.
+    pub fn or(self, other: Predicate<T>) -> Self {
+        Predicate::new(Expression::Grouped(Box::new(self.combine(BinaryOperator::Or, other).expr)))
+    }
+
+    pub fn into_expression(self) -> Expression {
+        self.expr
+    }
+}
+
+impl<T> Not for Predicate<T> {
+    type Output = Predicate<T>;
+
+    fn not(self) -> Predicate<T> {
+        Predicate::new(Expression::UnaryOperation {
+            operator: UnaryOperator::Not,
+            operand: Box::new(Expression::Grouped(Box::new(self.expr))),
+        })
+    }
+}
+
+/// Starts a `SELECT * FROM` table `T`.
+pub fn from<T: Table>() -> Select<T> {
+    Select { columns: Vec::new(), selection: None, order_by: Vec::new(), limit: None, offset: None, marker: PhantomData }
+}
+
+/// A SELECT from table `T`; every column is selected until `select` is
+/// called.
+pub struct Select<T> {
+    columns: Vec<Ident>,
+    selection: Option<Predicate<T>>,
+    order_by: Vec<OrderByExpr>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Table> Select<T> {
+    /// Adds a column to the select list.
+    pub fn select<V>(mut self, column: Column<T, V>) -> Self {
+        self.columns.push(Ident::new(column.name));
+        self
+    }
+
+    /// Adds a condition; several are AND-ed together.
+    pub fn filter(mut self, predicate: Predicate<T>) -> Self {
+        self.selection = Some(match self.selection.take() {
+            Some(existing) => existing.and(predicate),
+            None => predicate,
+        });
+        self
+    }
+
+    pub fn order_by<V>(mut self, column: Column<T, V>) -> Self {
+        self.order_by.push(OrderByExpr { expr: Expression::Identifier(Ident::new(column.name)), asc: None });
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn to_statement(&self) -> Statement {
+        let columns = if self.columns.is_empty() {
+            vec![SelectItem::wildcard()]
+        } else {
+            self.columns.iter().cloned().map(SelectItem::column).collect()
+        };
+        Statement::new_select(
+            columns,
+            Ident::new(T::NAME),
+            self.selection.clone().map(Predicate::into_expression),
+            self.order_by.clone(),
+            self.limit,
+            self.offset,
+        )
+    }
+
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        self.to_statement().to_sql(dialect)
+    }
+}
+
+impl<T: Table> From<Select<T>> for Statement {
+    fn from(select: Select<T>) -> Statement {
+        select.to_statement()
+    }
+}
+
+/// Starts an `INSERT INTO` table `T` of one row.
+pub fn insert_into<T: Table>() -> Insert<T> {
+    Insert { columns: Vec::new(), values: Vec::new(), marker: PhantomData }
+}
+
+/// An INSERT of one row into table `T`, built column by column.
+pub struct Insert<T> {
+    columns: Vec<Ident>,
+    values: Vec<Expression>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Table> Insert<T> {
+    pub fn value<V: SqlValue>(mut self, column: Column<T, V>, value: impl Into<V>) -> Self {
+        self.columns.push(Ident::new(column.name));
+        self.values.push(value.into().to_expression());
+        self
+    }
+
+    pub fn to_statement(&self) -> Statement {
+        Statement::Insert {
+            table_name: Ident::new(T::NAME),
+            columns: Some(self.columns.clone()),
+            rows: vec![self.values.clone()],
+        }
+    }
+
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        self.to_statement().to_sql(dialect)
+    }
+}
+
+impl<T: Table> From<Insert<T>> for Statement {
+    fn from(insert: Insert<T>) -> Statement {
+        insert.to_statement()
+    }
+}
+
+/// Declares a table for the typed query builder, written
+/// `struct Users = "users" { id: u64, name: String }`: a unit struct
+/// implementing `Table`, with a method returning each typed column.
+#[macro_export]
+macro_rules! sql_table {
+    ($(#[$meta:meta])* $vis:vis struct $table:ident = $name:literal { $($column:ident: $type:ty),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        $vis struct $table;
+
+        impl $crate::dsl::Table for $table {
+            const NAME: &'static str = $name;
+            const COLUMNS: &'static [&'static str] = &[$(stringify!($column)),*];
+        }
+
+        impl $table {
+            $(
+                pub const fn $column() -> $crate::dsl::Column<$table, $type> {
+                    $crate::dsl::Column::new(stringify!($column))
+                }
+            )*
+        }
+    };
+}
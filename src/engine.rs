@@ -0,0 +1,1918 @@
+// engine.rs — a small in-memory database that executes parsed statements
+// Author: Sreerag Devadasan
+//
+// Tables live in memory as rows of `eval::Value`s. INSERT enforces the
+// column types and constraints declared by CREATE TABLE (NOT NULL,
+// PRIMARY KEY uniqueness, CHECK, VARCHAR length); a batch of rows is
+// checked in full before any of it is stored, its primary keys sorted to
+// find duplicates rather than comparing each row's key with all the
+// others. An INSERT of many rows, such as one from a dump file, looks each
+// column's constraints up once and reads literals without the evaluator
+// (`set_batch_inserts` turns that off to compare the two). UPDATE and
+// DELETE change or remove the rows their WHERE holds for (every row
+// without one); UPDATE computes each new value from the row as it was and
+// checks the new rows as INSERT does, and a failed UPDATE changes nothing.
+// SELECT reads a table or a
+// derived table (a parenthesized SELECT or VALUES), filters with the
+// tree-walking evaluator, sorts by the ORDER BY items, columns or
+// expressions, each ascending unless DESC (NULLs first ascending), applies
+// OFFSET and LIMIT, then projects the selected columns.
+// A DATE, TIME or TIMESTAMP column also takes a string such as
+// '2024-01-31', stored as a value of the column's type (see datetime.rs).
+// A column declared with COLLATE (see collation.rs) compares its strings
+// by that collation in WHERE, in ORDER BY and among PRIMARY KEY values.
+// The columns of VALUES are named column1, column2, ... unless an alias
+// renames them. `Database::query` maps result rows onto Rust structs that
+// implement `FromRow`, field by column name.
+//
+// Functions written in Rust are registered with `register_scalar` and
+// `register_aggregate` and called from SQL like builtins (see
+// functions.rs).
+//
+// A SELECT's compiled plan, its resolved columns and its WHERE clause as
+// bytecode, is kept in a cache by the query's fingerprint and reused by
+// later queries that differ from it only in their literals (see
+// plan_cache.rs). An application passes its values to
+// `execute_with_params` for the placeholders of a statement, rather than
+// writing them into the SQL (see params.rs); the bound statements share a
+// plan too.
+//
+// Whole results can be cached as well: with `set_result_cache_capacity`,
+// a SELECT run again while the tables it reads are unchanged returns the
+// rows from before without scanning, and every write to a table drops the
+// results that read it (see result_cache.rs).
+//
+// EXPLAIN prints the planner's estimates for a query, from statistics the
+// engine collects over its own tables (see planner.rs); EXPLAIN ANALYZE
+// also runs it, and each step shows the rows it actually produced and the
+// time taken so far, so the two can be compared line by line.
+//
+// Every committed change, a write outside a transaction or a COMMIT, is a
+// new version of the database. With `set_history_limit` the tables as of
+// recent versions are kept, and `FROM t AS OF VERSION n` reads a table as
+// it was then: the snapshots are the copy-on-write ones described next,
+// so a version costs a table's rows only once that table is written again.
+//
+// Tables are shared copy-on-write, so a snapshot of the database copies
+// reference counts rather than rows, and a table's rows are copied only
+// when it is written while a snapshot still holds it. BEGIN takes such a
+// snapshot: ROLLBACK restores it, COMMIT drops it. A failed statement
+// changes nothing and leaves the transaction open. For several sessions
+// sharing one database, `Database::begin` hands out a `Transaction` that
+// works on its own snapshot, isolated from other sessions' commits; its
+// commit fails if another session committed a change to a table it also
+// changed (first committer wins, per table).
+//
+// A database created with `Storage::Columnar` stores new tables column by
+// column (see columnar.rs) and evaluates a scan's WHERE clause on whole
+// columns, without materializing the rows it rejects; that suits large
+// analytical scans, while the default row layout suits many small writes.
+//
+// Overflows, divisions by zero and invalid casts raise an error by default;
+// `Database::set_error_handling` has them give NULL or saturate instead,
+// for a database that should behave like MySQL, say, with
+// `Dialect::MySql.error_handling()` (see semantics.rs).
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::cmp::Ordering;
+use core::fmt;
+use core::mem;
+use core::time::Duration;
+
+use crate::prelude::*;
+use crate::anonymize::{anonymize_expression, AnonymizeMode};
+use crate::ast::{
+    Constraint, DBType, Expression, Ident, OrderByExpr, SelectItem, Statement, TableAlias, TableColumn, TableFactor,
+    TransactionCommand,
+};
+use crate::analyzer::{FunctionKind, Registry, SqlType};
+use crate::bytecode::Program;
+use crate::collation::{self, Collation};
+use crate::columnar::ColumnarTable;
+use crate::datetime::{Date, Time, Timestamp};
+use crate::decimal::Decimal;
+use crate::eval::{evaluate, evaluate_handling, literal_value, EvalError, Value};
+use crate::functions::Functions;
+use crate::hash::{fingerprint, hash_statement};
+use crate::parser::parse_sql;
+use crate::params::Parameters;
+use crate::plan_cache::{parameters, CacheStats, CompiledSelect, PlanCache, SortKey};
+use crate::planner::{plan, Actual, Plan, Step};
+use crate::result_cache::{ResultCache, ResultCacheStats};
+use crate::semantics::{accepts, ErrorHandling};
+use crate::stats::{Statistics, TableStats};
+use crate::tokenizer::ParseError;
+use crate::visit::{walk, Visitor};
+
+/// The buckets of the histograms `Database::statistics` collects.
+const HISTOGRAM_BUCKETS: usize = 10;
+
+// === Results and errors ===
+
+/// The rows produced by a statement; empty for statements other than SELECT.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Errors that can happen while executing a statement.
+#[derive(Debug)]
+pub enum EngineError {
+    Parse(ParseError),
+    Eval(EvalError),
+    /// The statement parses but the engine can't execute it, e.g. GRANT.
+    Unsupported(&'static str),
+    /// A clause the engine can't execute, e.g. JOIN.
+    UnsupportedFeature(&'static str),
+    NoSuchTable(String),
+    TableExists(String),
+    UnknownColumn(String),
+    DuplicateColumn(String),
+    ColumnCount { expected: usize, found: usize },
+    TypeMismatch { column: String, value: Value },
+    NotNull(String),
+    DuplicateKey(String),
+    CheckFailed(String),
+    ValueTooLong { column: String, max: usize },
+    /// A number with more digits than a DECIMAL column has room for.
+    OutOfRange { column: String, data_type: DBType },
+    /// A table alias lists more column names than the table has.
+    AliasColumns { alias: String, available: usize },
+    /// A result value doesn't convert to the Rust type of the field it
+    /// maps to.
+    FieldType { column: String, expected: &'static str, value: Value },
+    /// BEGIN inside a transaction.
+    TransactionInProgress,
+    /// COMMIT or ROLLBACK outside a transaction.
+    NoTransaction,
+    /// Another session committed a change to this table after the
+    /// transaction began.
+    WriteConflict(String),
+    /// An INSERT, UPDATE or DELETE on a table registered from outside the
+    /// engine.
+    ReadOnly(String),
+    /// A statement given a different number of values than it has
+    /// parameters.
+    ParameterCount { expected: usize, found: usize },
+    /// A statement using more than one style of placeholder.
+    MixedParameters,
+    /// A value given for a parameter the statement doesn't have, or a
+    /// placeholder such as `$0` that names none.
+    UnknownParameter(String),
+    /// A parameter given no value by name.
+    MissingParameter(String),
+    /// A parameter whose value its column can't hold or be compared with.
+    ParameterType { parameter: String, column: String, value: Value },
+    /// AS OF VERSION a version the database doesn't keep the tables of.
+    NoSuchVersion(u64),
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Parse(e) => write!(f, "{}", e),
+            EngineError::Eval(e) => write!(f, "{}", e),
+            EngineError::Unsupported(kind) => write!(f, "{} statements are not supported by the engine", kind),
+            EngineError::UnsupportedFeature(feature) => write!(f, "{} is not supported by the engine", feature),
+            EngineError::NoSuchTable(t) => write!(f, "No such table: {}", t),
+            EngineError::TableExists(t) => write!(f, "Table already exists: {}", t),
+            EngineError::UnknownColumn(c) => write!(f, "Unknown column: {}", c),
+            EngineError::DuplicateColumn(c) => write!(f, "Column listed twice: {}", c),
+            EngineError::ColumnCount { expected, found } => {
+                write!(f, "Expected {} values per row, found {}", expected, found)
+            }
+            EngineError::TypeMismatch { column, value } => write!(f, "Column {} can't hold {:?}", column, value),
+            EngineError::NotNull(c) => write!(f, "Column {} can't be NULL", c),
+            EngineError::DuplicateKey(c) => write!(f, "Duplicate value in primary key column {}", c),
+            EngineError::CheckFailed(c) => write!(f, "CHECK constraint on column {} failed", c),
+            EngineError::ValueTooLong { column, max } => {
+                write!(f, "Value for column {} is longer than {} characters", column, max)
+            }
+            EngineError::OutOfRange { column, data_type } => {
+                write!(f, "Value for column {} is out of range for {}", column, data_type)
+            }
+            EngineError::AliasColumns { alias, available } => {
+                write!(f, "Alias {} names more columns than the {} available", alias, available)
+            }
+            EngineError::FieldType { column, expected, value } => {
+                write!(f, "Column {} holds {:?}, which isn't {}", column, value, expected)
+            }
+            EngineError::TransactionInProgress => write!(f, "A transaction is already in progress"),
+            EngineError::NoTransaction => write!(f, "No transaction is in progress"),
+            EngineError::WriteConflict(t) => {
+                write!(f, "Table {} was changed by a concurrent transaction; the transaction was rolled back", t)
+            }
+            EngineError::ReadOnly(t) => write!(f, "Table {} is read-only", t),
+            EngineError::ParameterCount { expected, found } => {
+                write!(f, "Expected {} parameter values, found {}", expected, found)
+            }
+            EngineError::MixedParameters => write!(f, "A statement can't mix ?, $n and :name placeholders"),
+            EngineError::UnknownParameter(p) => write!(f, "No such parameter: {}", p),
+            EngineError::MissingParameter(p) => write!(f, "No value for parameter {}", p),
+            EngineError::NoSuchVersion(version) => write!(f, "Version {} of the database isn't kept", version),
+            EngineError::ParameterType { parameter, column, value } => {
+                write!(f, "Parameter {} is for column {}, which can't hold {:?}", parameter, column, value)
+            }
+        }
+    }
+}
+
+impl core::error::Error for EngineError {}
+
+impl From<ParseError> for EngineError {
+    fn from(e: ParseError) -> Self {
+        EngineError::Parse(e)
+    }
+}
+
+impl From<EvalError> for EngineError {
+    fn from(e: EvalError) -> Self {
+        EngineError::Eval(e)
+    }
+}
+
+// === Typed rows ===
+
+/// A row of a result, with access to its values by column name.
+#[derive(Debug, Clone, Copy)]
+pub struct Row<'a> {
+    columns: &'a [String],
+    values: &'a [Value],
+}
+
+impl<'a> Row<'a> {
+    /// The first value named `column`, converted to `T`.
+    pub fn get<T: FromValue>(&self, column: &str) -> Result<T, EngineError> {
+        let value = self
+            .columns
+            .iter()
+            .position(|name| name == column)
+            .map(|i| &self.values[i])
+            .ok_or_else(|| EngineError::UnknownColumn(column.to_string()))?;
+        T::from_value(value).ok_or_else(|| EngineError::FieldType {
+            column: column.to_string(),
+            expected: T::EXPECTED,
+            value: value.clone(),
+        })
+    }
+}
+
+/// Rust types a result value can be read as. NULL only converts to
+/// `Option`.
+pub trait FromValue: Sized {
+    /// Names the type in conversion errors.
+    const EXPECTED: &'static str;
+
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+impl FromValue for i64 {
+    const EXPECTED: &'static str = "an integer";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Int(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for bool {
+    const EXPECTED: &'static str = "a boolean";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for String {
+    const EXPECTED: &'static str = "a string";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Date {
+    const EXPECTED: &'static str = "a date";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Date(date) => Some(*date),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Time {
+    const EXPECTED: &'static str = "a time";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Time(time) => Some(*time),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Timestamp {
+    const EXPECTED: &'static str = "a timestamp";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Timestamp(timestamp) => Some(*timestamp),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Decimal {
+    const EXPECTED: &'static str = "a decimal";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Decimal(decimal) => Some(*decimal),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Value {
+    const EXPECTED: &'static str = "a value";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    const EXPECTED: &'static str = T::EXPECTED;
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => Some(None),
+            other => T::from_value(other).map(Some),
+        }
+    }
+}
+
+/// Types built from a result row, usually with `impl_from_row!`.
+pub trait FromRow: Sized {
+    fn from_row(row: Row<'_>) -> Result<Self, EngineError>;
+}
+
+impl QueryResult {
+    /// Every row converted to `T`.
+    pub fn rows_as<T: FromRow>(&self) -> Result<Vec<T>, EngineError> {
+        self.rows.iter().map(|values| T::from_row(Row { columns: &self.columns, values })).collect()
+    }
+}
+
+/// Implements `FromRow` for a struct by reading each listed field from the
+/// result column of the same name:
+/// `impl_from_row!(User { id, name })`.
+#[macro_export]
+macro_rules! impl_from_row {
+    ($type:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::engine::FromRow for $type {
+            fn from_row(row: $crate::engine::Row<'_>) -> Result<Self, $crate::engine::EngineError> {
+                Ok(Self { $($field: row.get(stringify!($field))?),* })
+            }
+        }
+    };
+}
+
+// === Tables ===
+
+/// How a database lays out the tables it creates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Storage {
+    /// Each row's values together.
+    #[default]
+    Rows,
+    /// Each column's values together, for vectorized scans.
+    Columnar,
+}
+
+/// A table's definition and its rows, each holding one value per column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub columns: Vec<TableColumn>,
+    data: TableData,
+    read_only: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TableData {
+    Rows(Vec<Vec<Value>>),
+    Columns(ColumnarTable),
+}
+
+impl Table {
+    fn new(columns: Vec<TableColumn>, storage: Storage) -> Self {
+        let data = match storage {
+            Storage::Rows => TableData::Rows(Vec::new()),
+            Storage::Columnar => TableData::Columns(ColumnarTable::new(columns.iter().map(|c| &c.column_type))),
+        };
+        Self { columns, data, read_only: false }
+    }
+
+    pub fn storage(&self) -> Storage {
+        match self.data {
+            TableData::Rows(_) => Storage::Rows,
+            TableData::Columns(_) => Storage::Columnar,
+        }
+    }
+
+    /// The rows in insertion order; a columnar table assembles them.
+    pub fn rows(&self) -> Cow<'_, [Vec<Value>]> {
+        match &self.data {
+            TableData::Rows(rows) => Cow::Borrowed(rows),
+            TableData::Columns(columns) => Cow::Owned(columns.rows(None)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.data {
+            TableData::Rows(rows) => rows.len(),
+            TableData::Columns(columns) => columns.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// True for a table added by `Database::register`.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// The columns of a table with `Storage::Columnar`.
+    pub fn columnar(&self) -> Option<&ColumnarTable> {
+        match &self.data {
+            TableData::Rows(_) => None,
+            TableData::Columns(columns) => Some(columns),
+        }
+    }
+
+    fn column_names(&self) -> Vec<String> {
+        self.columns.iter().map(|c| c.column_name.value.clone()).collect()
+    }
+
+    fn column_index(&self, name: &str) -> Result<usize, EngineError> {
+        self.columns
+            .iter()
+            .position(|c| c.column_name == name)
+            .ok_or_else(|| EngineError::UnknownColumn(name.to_string()))
+    }
+
+    /// A string given for a DATE, TIME or TIMESTAMP column read as a value
+    /// of that type, and a date given for a TIMESTAMP as its midnight; any
+    /// other value is left as it is, for `check_value`.
+    fn coerce(column: &TableColumn, value: Value) -> Result<Value, EngineError> {
+        let parsed = match (&column.column_type, &value) {
+            (DBType::Date, Value::String(text)) => Date::parse(text).map(Value::Date),
+            (DBType::Time, Value::String(text)) => Time::parse(text).map(Value::Time),
+            (DBType::Timestamp, Value::String(text)) => Timestamp::parse(text).map(Value::Timestamp),
+            (DBType::Timestamp, Value::Date(date)) => Some(Value::Timestamp(Timestamp::from(*date))),
+            // A decimal column rounds to its scale, and numbers that still
+            // don't fit are out of range rather than of the wrong type
+            (DBType::Decimal(precision, scale), Value::Int(_) | Value::Decimal(_) | Value::String(_)) => {
+                let decimal = match &value {
+                    Value::Int(n) => Some(Decimal::from(*n)),
+                    Value::Decimal(decimal) => Some(*decimal),
+                    Value::String(text) => Decimal::parse(text.trim()),
+                    _ => None,
+                };
+                if let Some(decimal) = decimal {
+                    let out_of_range = || EngineError::OutOfRange {
+                        column: column.column_name.value.clone(),
+                        data_type: column.column_type.clone(),
+                    };
+                    return decimal.fit(*precision, *scale).map(Value::Decimal).ok_or_else(out_of_range);
+                }
+                None
+            }
+            _ => return Ok(value),
+        };
+        parsed.ok_or_else(|| EngineError::TypeMismatch { column: column.column_name.value.clone(), value })
+    }
+
+    /// Checks one value against its column's type and NOT NULL/PRIMARY KEY.
+    fn check_value(column: &TableColumn, value: &Value) -> Result<(), EngineError> {
+        Table::check_type(column, is_required(column), value)
+    }
+
+    /// `check_value`, with whether the column is NOT NULL already known.
+    fn check_type(column: &TableColumn, required: bool, value: &Value) -> Result<(), EngineError> {
+        let name = &column.column_name.value;
+        match (&column.column_type, value) {
+            (_, Value::Null) if required => Err(EngineError::NotNull(name.clone())),
+            (_, Value::Null) | (DBType::Int, Value::Int(_)) | (DBType::Bool, Value::Bool(_)) => Ok(()),
+            (DBType::Date, Value::Date(_)) | (DBType::Time, Value::Time(_)) => Ok(()),
+            (DBType::Timestamp, Value::Timestamp(_)) | (DBType::Decimal(..), Value::Decimal(_)) => Ok(()),
+            (DBType::Varchar(max), Value::String(s)) if s.chars().count() > *max => {
+                Err(EngineError::ValueTooLong { column: name.clone(), max: *max })
+            }
+            (DBType::Varchar(_), Value::String(_)) => Ok(()),
+            _ => Err(EngineError::TypeMismatch { column: name.clone(), value: value.clone() }),
+        }
+    }
+
+    /// Checks the CHECK constraints of every column; a NULL result passes.
+    fn check_row(&self, names: &[String], row: &[Value]) -> Result<(), EngineError> {
+        for column in &self.columns {
+            for constraint in &column.constraints {
+                if let Constraint::Check(expr) = constraint {
+                    if evaluate(expr, names, row)? == Value::Bool(false) {
+                        return Err(EngineError::CheckFailed(column.column_name.value.clone()));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// === Database ===
+
+type Tables = BTreeMap<String, Arc<Table>>;
+
+/// An in-memory collection of tables.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Database {
+    tables: Tables,
+    /// The tables as of BEGIN, while a transaction is open.
+    snapshot: Option<Tables>,
+    storage: Storage,
+    plans: PlanCache,
+    results: ResultCache,
+    /// The changes committed so far; see `version`.
+    version: u64,
+    /// The committed tables as of each kept version.
+    history: BTreeMap<u64, Tables>,
+    /// The most versions `history` keeps.
+    history_limit: usize,
+    functions: Functions,
+    errors: ErrorHandling,
+    /// Whether large INSERTs take the batch path; see `insert`.
+    per_row_inserts: bool,
+}
+
+/// The fewest rows an INSERT has for it to take the batch path.
+const BATCH_INSERT_ROWS: usize = 32;
+
+impl Database {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An empty database whose tables are laid out as `storage`.
+    pub fn with_storage(storage: Storage) -> Self {
+        Self { storage, ..Self::default() }
+    }
+
+    pub fn storage(&self) -> Storage {
+        self.storage
+    }
+
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.get(name).map(|table| &**table)
+    }
+
+    /// Adds a read-only table holding `data`, such as one read from a
+    /// file; `columns` describes its columns in order. DROP TABLE removes
+    /// it like any other table.
+    pub fn register(&mut self, name: &str, columns: Vec<TableColumn>, data: ColumnarTable) -> Result<(), EngineError> {
+        if self.tables.contains_key(name) {
+            return Err(EngineError::TableExists(name.to_string()));
+        }
+        if columns.len() != data.columns().len() {
+            return Err(EngineError::ColumnCount { expected: columns.len(), found: data.columns().len() });
+        }
+        let table = Table { columns, data: TableData::Columns(data), read_only: true };
+        self.tables.insert(name.to_string(), Arc::new(table));
+        self.wrote(name);
+        Ok(())
+    }
+
+    /// The changes committed so far. Every write outside a transaction,
+    /// and every COMMIT of one that changed a table, is a new version.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Keeps the tables as of the last `versions` versions, the current
+    /// one among them, for queries that read a table AS OF VERSION n; zero,
+    /// the default, keeps none. Versions share the tables they have in
+    /// common, but a table one of them holds is copied when next written.
+    pub fn set_history_limit(&mut self, versions: usize) {
+        self.history_limit = versions;
+        while self.history.len() > versions {
+            self.history.pop_first();
+        }
+        if !self.history.contains_key(&self.version) {
+            self.keep_version();
+        }
+    }
+
+    /// The versions whose tables are kept, oldest first.
+    pub fn kept_versions(&self) -> Vec<u64> {
+        self.history.keys().copied().collect()
+    }
+
+    /// True between BEGIN and COMMIT or ROLLBACK.
+    pub fn in_transaction(&self) -> bool {
+        self.snapshot.is_some()
+    }
+
+    /// Starts a transaction on a snapshot of the committed tables, for one
+    /// of several sessions sharing the database. Its changes make no new
+    /// versions until the database commits it.
+    pub fn begin(&self) -> Transaction {
+        Transaction {
+            base: self.tables.clone(),
+            working: Database {
+                tables: self.tables.clone(),
+                snapshot: Some(self.tables.clone()),
+                storage: self.storage,
+                plans: PlanCache::new(self.plans.capacity()),
+                results: ResultCache::new(self.results.capacity()),
+                version: self.version,
+                history: self.history.clone(),
+                history_limit: self.history_limit,
+                functions: self.functions.clone(),
+                errors: self.errors,
+                per_row_inserts: self.per_row_inserts,
+            },
+        }
+    }
+
+    /// Applies the changes of `transaction`, unless another transaction
+    /// committed a change to one of the same tables since it began.
+    pub fn commit(&mut self, transaction: Transaction) -> Result<(), EngineError> {
+        let Transaction { base, working } = transaction;
+        let unchanged = |a: Option<&Arc<Table>>, b: Option<&Arc<Table>>| match (a, b) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        let mut names: Vec<&String> = base.keys().chain(working.tables.keys()).collect();
+        names.sort();
+        names.dedup();
+        let written: Vec<&String> =
+            names.into_iter().filter(|name| !unchanged(base.get(*name), working.tables.get(*name))).collect();
+        if let Some(name) = written.iter().find(|name| !unchanged(base.get(**name), self.tables.get(**name))) {
+            return Err(EngineError::WriteConflict(name.to_string()));
+        }
+        let changed = !written.is_empty();
+        for name in written {
+            match working.tables.get(name) {
+                Some(table) => self.tables.insert(name.clone(), Arc::clone(table)),
+                None => self.tables.remove(name),
+            };
+            self.results.written(name);
+        }
+        // All of it is one version
+        if changed && self.snapshot.is_none() {
+            self.new_version();
+        }
+        Ok(())
+    }
+
+    /// Executes a statement for a session that may hold a `Transaction`:
+    /// BEGIN opens one in `transaction`, the statements up to COMMIT or
+    /// ROLLBACK run inside it, and the rest run on the database directly.
+    pub fn execute_in(
+        &mut self,
+        transaction: &mut Option<Transaction>,
+        statement: &Statement,
+    ) -> Result<QueryResult, EngineError> {
+        let command = match statement {
+            Statement::Transaction { command } => Some(*command),
+            _ => None,
+        };
+        match (command, transaction.take()) {
+            (Some(TransactionCommand::Begin), None) => {
+                *transaction = Some(self.begin());
+                Ok(QueryResult::default())
+            }
+            (Some(TransactionCommand::Commit), Some(open)) => self.commit(open).map(|()| QueryResult::default()),
+            (Some(TransactionCommand::Rollback), Some(_)) => Ok(QueryResult::default()),
+            (_, Some(mut open)) => {
+                let result = open.execute(statement);
+                *transaction = Some(open);
+                result
+            }
+            (_, None) => self.execute(statement),
+        }
+    }
+
+    /// Parses and executes one statement.
+    pub fn execute_sql(&mut self, sql: &str) -> Result<QueryResult, EngineError> {
+        let statement = parse_sql(sql)?;
+        self.execute(&statement)
+    }
+
+    /// Executes `statement` with `values` for its parameters, in order; see
+    /// params.rs. A value stored in or compared with a column must be one
+    /// the column can hold.
+    pub fn execute_with_params(&mut self, statement: &Statement, values: &[Value]) -> Result<QueryResult, EngineError> {
+        let parameters = Parameters::of(statement)?;
+        let values = parameters.order(values)?;
+        self.execute_bound(statement, &parameters, &values)
+    }
+
+    /// Like `execute_with_params`, with the values of `:name` parameters
+    /// given by name.
+    pub fn execute_with_named_params(
+        &mut self,
+        statement: &Statement,
+        values: &[(&str, Value)],
+    ) -> Result<QueryResult, EngineError> {
+        let parameters = Parameters::of(statement)?;
+        let values = parameters.order_named(values)?;
+        self.execute_bound(statement, &parameters, &values)
+    }
+
+    fn execute_bound(
+        &mut self,
+        statement: &Statement,
+        parameters: &Parameters,
+        values: &[Value],
+    ) -> Result<QueryResult, EngineError> {
+        for (placeholder, occurrence, column) in self.parameter_columns(statement) {
+            let Some(value) = parameters.index(&placeholder, occurrence).map(|index| &values[index]) else {
+                continue;
+            };
+            if !holds(&column.column_type, value) {
+                let parameter = match placeholder.as_str() {
+                    "?" => format!("?{}", occurrence + 1),
+                    _ => placeholder,
+                };
+                let column = column.column_name.value.clone();
+                return Err(EngineError::ParameterType { parameter, column, value: value.clone() });
+            }
+        }
+        self.execute(&parameters.bind(statement, values))
+    }
+
+    /// The placeholders an INSERT stores in a column, or a WHERE clause
+    /// compares with one, each with its position among the placeholders
+    /// and the column.
+    fn parameter_columns(&self, statement: &Statement) -> Vec<(String, usize, TableColumn)> {
+        let placeholder = |expr: &Expression| match expr {
+            Expression::Placeholder(name) => Some(name.clone()),
+            _ => None,
+        };
+        let mut found = Vec::new();
+        let mut occurrence = 0;
+        match statement {
+            Statement::Insert { table_name, columns, rows } => {
+                let Some(table) = self.tables.get(table_name.as_str()) else { return found };
+                let targets: Vec<Option<usize>> = match columns {
+                    Some(names) => names.iter().map(|name| table.column_index(name).ok()).collect(),
+                    None => (0..table.columns.len()).map(Some).collect(),
+                };
+                for (i, expr) in rows.iter().flat_map(|row| row.iter().enumerate()) {
+                    if let (Some(name), Some(Some(index))) = (placeholder(expr), targets.get(i)) {
+                        found.push((name, occurrence, table.columns[*index].clone()));
+                    }
+                    expr.walk(&mut |expr| occurrence += usize::from(placeholder(expr).is_some()));
+                }
+            }
+            // Only a table's own column names, not those an alias gives it
+            Statement::Select { from, selection: Some(selection), .. } => {
+                let TableFactor::Table { name, alias: None, .. } = &**from else { return found };
+                let Some(table) = self.tables.get(name.as_str()) else { return found };
+                let column = |expr: &Expression| match expr {
+                    Expression::Identifier(name) => table.column_index(name).ok(),
+                    _ => None,
+                };
+                // An operation is visited before its operands, and the
+                // column among them holds no placeholder
+                selection.walk(&mut |expr| {
+                    if let Expression::BinaryOperation { left_operand, right_operand, .. } = expr {
+                        let pair = match (column(left_operand), column(right_operand)) {
+                            (Some(index), None) => placeholder(right_operand).map(|name| (name, index)),
+                            (None, Some(index)) => placeholder(left_operand).map(|name| (name, index)),
+                            _ => None,
+                        };
+                        if let Some((name, index)) = pair {
+                            found.push((name, occurrence, table.columns[index].clone()));
+                        }
+                    }
+                    occurrence += usize::from(placeholder(expr).is_some());
+                });
+            }
+            Statement::Explain { statement, .. } => return self.parameter_columns(statement),
+            _ => {}
+        }
+        found
+    }
+
+    /// Executes a query and converts its rows to `T`.
+    pub fn query<T: FromRow>(&mut self, sql: &str) -> Result<Vec<T>, EngineError> {
+        self.execute_sql(sql)?.rows_as()
+    }
+
+    pub fn execute(&mut self, statement: &Statement) -> Result<QueryResult, EngineError> {
+        match statement {
+            Statement::CreateTable { table_name, column_list } => {
+                if self.tables.contains_key(table_name.as_str()) {
+                    return Err(EngineError::TableExists(table_name.value.clone()));
+                }
+                for (i, column) in column_list.iter().enumerate() {
+                    if column_list[..i].iter().any(|c| c.column_name == column.column_name) {
+                        return Err(EngineError::DuplicateColumn(column.column_name.value.clone()));
+                    }
+                    for constraint in &column.constraints {
+                        if let Constraint::Collate(name) = constraint {
+                            Collation::from_name(name.as_str()).ok_or_else(|| collation::unknown(name.as_str()))?;
+                        }
+                    }
+                }
+                let table = Table::new(column_list.clone(), self.storage);
+                self.tables.insert(table_name.value.clone(), Arc::new(table));
+                self.wrote(table_name.as_str());
+                Ok(QueryResult::default())
+            }
+            Statement::DropTable { table_name, if_exists } => {
+                if self.tables.remove(table_name.as_str()).is_some() {
+                    self.wrote(table_name.as_str());
+                } else if !if_exists {
+                    return Err(EngineError::NoSuchTable(table_name.value.clone()));
+                }
+                Ok(QueryResult::default())
+            }
+            Statement::Insert { table_name, columns, rows } => {
+                self.insert(table_name, columns.as_deref(), rows)?;
+                Ok(QueryResult::default())
+            }
+            Statement::Update { table_name, assignments, selection, .. } => {
+                self.update(table_name, assignments, selection.as_ref())?;
+                Ok(QueryResult::default())
+            }
+            Statement::Delete { table_name, selection, .. } => {
+                self.delete(table_name, selection.as_ref())?;
+                Ok(QueryResult::default())
+            }
+            Statement::Select { .. } => self.run_cached(statement),
+            Statement::Explain { analyze, statement } => {
+                let plan = self.explain(statement, *analyze)?;
+                let rows = plan.to_string().lines().map(|line| vec![Value::String(line.to_string())]).collect();
+                Ok(QueryResult { columns: vec!["QUERY PLAN".to_string()], rows })
+            }
+            Statement::Values { rows } => values(rows, &self.functions, &self.errors),
+            Statement::Transaction { command } => {
+                match (command, self.snapshot.take()) {
+                    (TransactionCommand::Begin, None) => self.snapshot = Some(self.tables.clone()),
+                    (TransactionCommand::Begin, Some(snapshot)) => {
+                        self.snapshot = Some(snapshot);
+                        return Err(EngineError::TransactionInProgress);
+                    }
+                    (TransactionCommand::Commit, Some(snapshot)) => {
+                        if !self.changed(&snapshot).is_empty() {
+                            self.new_version();
+                        }
+                    }
+                    (TransactionCommand::Rollback, Some(snapshot)) => {
+                        let tables = mem::replace(&mut self.tables, snapshot);
+                        for name in self.changed(&tables) {
+                            self.results.written(&name);
+                        }
+                    }
+                    (_, None) => return Err(EngineError::NoTransaction),
+                }
+                Ok(QueryResult::default())
+            }
+            other => Err(EngineError::Unsupported(other.kind())),
+        }
+    }
+
+    /// The names of the tables that differ from those of `before`.
+    fn changed(&self, before: &Tables) -> Vec<String> {
+        let mut changed: Vec<&String> = before.keys().chain(self.tables.keys()).collect();
+        changed.sort();
+        changed.dedup();
+        changed.retain(|name| match (before.get(*name), self.tables.get(*name)) {
+            (Some(a), Some(b)) => !Arc::ptr_eq(a, b),
+            _ => true,
+        });
+        changed.into_iter().cloned().collect()
+    }
+
+    /// Records a write to table `name`: the cached results that read it
+    /// are dropped, and outside a transaction it is a new version.
+    fn wrote(&mut self, name: &str) {
+        self.results.written(name);
+        if self.snapshot.is_none() {
+            self.new_version();
+        }
+    }
+
+    /// Moves on to the next version, keeping its tables if the history has
+    /// room.
+    fn new_version(&mut self) {
+        self.version += 1;
+        self.keep_version();
+    }
+
+    /// Keeps the committed tables as the current version, dropping the
+    /// oldest versions beyond the limit.
+    fn keep_version(&mut self) {
+        if self.history_limit == 0 {
+            return;
+        }
+        let committed = self.snapshot.as_ref().unwrap_or(&self.tables).clone();
+        self.history.insert(self.version, committed);
+        while self.history.len() > self.history_limit {
+            self.history.pop_first();
+        }
+    }
+
+    /// The table `name`, as of `version` if given.
+    fn table_at(&self, name: &str, version: Option<u64>) -> Result<&Arc<Table>, EngineError> {
+        let tables = match version {
+            Some(version) => self.history.get(&version).ok_or(EngineError::NoSuchVersion(version))?,
+            None => &self.tables,
+        };
+        tables.get(name).ok_or_else(|| EngineError::NoSuchTable(name.to_string()))
+    }
+
+    /// Runs a SELECT, or returns the result it had before from the result
+    /// cache, if none of the tables it reads was written since. A query AS
+    /// OF a version isn't cached, since the history may stop keeping it.
+    fn run_cached(&mut self, statement: &Statement) -> Result<QueryResult, EngineError> {
+        let uncached = statement.expressions().into_iter().any(calls_function) || reads_history(statement);
+        if self.results.capacity() == 0 || uncached {
+            return self.run(statement, None);
+        }
+        let key = hash_statement(statement);
+        if let Some(result) = self.results.get(key, statement) {
+            return Ok(result);
+        }
+        let result = self.run(statement, None)?;
+        self.results.insert(key, statement, &statement.tables(), result.clone());
+        Ok(result)
+    }
+
+    /// The compiled plan of a SELECT over a source of `columns`, from the
+    /// plan cache when it holds one.
+    fn compile(&mut self, statement: &Statement, columns: &[String]) -> Result<Arc<CompiledSelect>, EngineError> {
+        let Statement::Select { columns: selected, wildcard, selection, order_by, .. } = statement else {
+            return Err(EngineError::Unsupported(statement.kind()));
+        };
+        let key = (self.plans.capacity() > 0).then(|| fingerprint(statement));
+        if let Some(plan) = key.and_then(|key| self.plans.get(key, columns)) {
+            return Ok(plan);
+        }
+        // `*` expands to every column of the source not in EXCLUDE; the
+        // items that aren't columns are computed after the source's columns
+        let excluded = wildcard.exclude.iter().map(|c| column_index(columns, c)).collect::<Result<Vec<_>, _>>()?;
+        let (mut projection, mut items) = (Vec::new(), Vec::new());
+        let mut computed = columns.len();
+        for (item, selected) in selected.iter().enumerate() {
+            if selected.is_wildcard() {
+                projection.extend((0..columns.len()).filter(|i| !excluded.contains(i)));
+            } else if let Some(column) = selected.as_column() {
+                projection.push(column_index(columns, column)?);
+            } else {
+                projection.push(computed);
+                computed += 1;
+            }
+            items.resize(projection.len(), item);
+        }
+        let mut sort_keys = Vec::new();
+        for item in order_by {
+            let index = match sort_column(&item.expr) {
+                Some(column) => column_index(columns, column)?,
+                None => {
+                    computed += 1;
+                    computed - 1
+                }
+            };
+            sort_keys.push(SortKey { index, descending: item.asc == Some(false) });
+        }
+        let filter = selection.as_ref().and_then(|expr| {
+            let template = anonymize_expression(expr, AnonymizeMode::Placeholders);
+            Program::compile_as(&template, columns, true, &self.errors).ok()
+        });
+        let plan = Arc::new(CompiledSelect { source: columns.to_vec(), filter, projection, items, sort_keys });
+        if let Some(key) = key {
+            self.plans.insert(key, Arc::clone(&plan));
+        }
+        Ok(plan)
+    }
+
+    /// Lets SQL run by this database call `function` as `name`, taking
+    /// arguments of the types in `params`; see functions.rs.
+    pub fn register_scalar(
+        &mut self,
+        name: &str,
+        params: &[SqlType],
+        returns: SqlType,
+        function: impl Fn(&[Value]) -> Result<Value, EvalError> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.functions.scalar(name, params, returns, function);
+        self
+    }
+
+    /// Registers an aggregate that starts from `initial` and folds in the
+    /// arguments of each row with `step`.
+    pub fn register_aggregate(
+        &mut self,
+        name: &str,
+        params: &[SqlType],
+        returns: SqlType,
+        initial: Value,
+        step: impl Fn(Value, &[Value]) -> Result<Value, EvalError> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.functions.aggregate(name, params, returns, initial, step);
+        self
+    }
+
+    /// The signatures of the builtin functions and the registered ones,
+    /// for `analyzer::analyze`.
+    pub fn registry(&self) -> Registry {
+        self.functions.registry()
+    }
+
+    /// The registered aggregate `function` over every value of `column`
+    /// in `table`.
+    pub fn aggregate(&self, function: &str, table: &str, column: &str) -> Result<Value, EngineError> {
+        let data = self.tables.get(table).ok_or_else(|| EngineError::NoSuchTable(table.to_string()))?;
+        let index = data.column_index(column)?;
+        let rows = data.rows();
+        Ok(self.functions.fold(function, rows.iter().map(|row| core::slice::from_ref(&row[index])))?)
+    }
+
+    /// How the plan cache has done; see plan_cache.rs.
+    pub fn plan_cache_stats(&self) -> CacheStats {
+        self.plans.stats()
+    }
+
+    /// Keeps at most `capacity` compiled plans; zero turns the cache off.
+    pub fn set_plan_cache_capacity(&mut self, capacity: usize) {
+        self.plans.set_capacity(capacity);
+    }
+
+    /// How the result cache has done; see result_cache.rs.
+    pub fn result_cache_stats(&self) -> ResultCacheStats {
+        self.results.stats()
+    }
+
+    /// Keeps the results of at most `capacity` SELECTs, returned again
+    /// while their tables are unchanged; zero, the default, turns the
+    /// cache off.
+    pub fn set_result_cache_capacity(&mut self, capacity: usize) {
+        self.results.set_capacity(capacity);
+    }
+
+    /// What overflows, divisions by zero and invalid casts give from now
+    /// on; see semantics.rs. Plans compiled before are dropped, since
+    /// folding their constants depended on it, and so are cached results.
+    pub fn set_error_handling(&mut self, errors: ErrorHandling) {
+        self.errors = errors;
+        self.plans = PlanCache::new(self.plans.capacity());
+        self.results.clear();
+    }
+
+    pub fn error_handling(&self) -> ErrorHandling {
+        self.errors
+    }
+
+    /// Whether INSERTs of many rows take the batch path, as they do by
+    /// default; turning it off is for comparing the two.
+    pub fn set_batch_inserts(&mut self, enabled: bool) {
+        self.per_row_inserts = !enabled;
+    }
+
+    /// The plan of a query, estimated from statistics of the tables it
+    /// reads. With `analyze` the query is also run, and each step of the
+    /// plan records the rows it produced and the time taken.
+    pub fn explain(&mut self, statement: &Statement, analyze: bool) -> Result<Plan, EngineError> {
+        let mut plan = plan(statement, &self.statistics()).ok_or(EngineError::Unsupported(statement.kind()))?;
+        if analyze {
+            self.run(statement, Some(&mut plan))?;
+        }
+        Ok(plan)
+    }
+
+    /// Statistics of every table, with histograms of its integer columns.
+    pub fn statistics(&self) -> Statistics {
+        let mut statistics = Statistics::new();
+        for (name, table) in &self.tables {
+            statistics.set(name, TableStats::collect(table, HISTOGRAM_BUCKETS));
+        }
+        statistics
+    }
+
+    /// Runs a query, recording in `plan`, when given, what each of its
+    /// steps produced.
+    fn run(&mut self, statement: &Statement, mut plan: Option<&mut Plan>) -> Result<QueryResult, EngineError> {
+        let clock = Clock::start();
+        match statement {
+            // Row locks are meaningless with a single writer, so FOR UPDATE
+            // and FOR SHARE are accepted and ignored
+            Statement::Select { columns, wildcard, from, joins, selection, group_by, qualify, order_by, limit, offset, .. } => {
+                if !joins.is_empty() {
+                    return Err(EngineError::UnsupportedFeature("JOIN"));
+                }
+                if !group_by.is_empty() {
+                    return Err(EngineError::UnsupportedFeature("GROUP BY"));
+                }
+                if qualify.is_some() {
+                    return Err(EngineError::UnsupportedFeature("QUALIFY"));
+                }
+                if !wildcard.replace.is_empty() {
+                    return Err(EngineError::UnsupportedFeature("* REPLACE"));
+                }
+                // Each item is computed for one row at a time
+                let registry = self.functions.registry();
+                if columns.iter().any(|item| aggregates(&item.expr, &registry)) {
+                    return Err(EngineError::UnsupportedFeature("An aggregate or window function in the select list"));
+                }
+                // The columns the select list and ORDER BY read, unless `*` reads them all
+                let used: Option<Vec<&str>> = (!columns.iter().any(SelectItem::is_wildcard)).then(|| {
+                    let mut names = Vec::new();
+                    let ordered = order_by.iter().map(|item| &item.expr);
+                    for expr in columns.iter().map(|item| &item.expr).chain(ordered) {
+                        expr.walk(&mut |expr| {
+                            if let Expression::Identifier(name) = expr {
+                                names.push(name.as_str());
+                            }
+                        });
+                    }
+                    names
+                });
+                // Comparisons with a collated column are made by its collation
+                let collations = self.collations(from);
+                let collated =
+                    selection.as_ref().filter(|_| !collations.is_empty()).map(|expr| collate(expr, &collations));
+                let selection = collated.as_ref().or(selection.as_ref());
+                // The FROM item's step is under those of LIMIT and WHERE
+                let mut source_step = plan.as_deref_mut().map(source_of);
+                let subquery = source_step.as_deref_mut().and_then(|step| step.inputs.first_mut());
+                let (source, unapplied, read) = self.scan(from, selection, used.as_deref(), subquery)?;
+                if let Some(step) = source_step {
+                    step.actual = Some(Actual { rows: read as u64, time: clock.elapsed() });
+                }
+                let compiled = self.compile(statement, &source.columns)?;
+                let source = match (unapplied, &compiled.filter) {
+                    // The plan's bytecode is of the WHERE clause as written
+                    (Some(expr), Some(program)) if collated.is_none() => {
+                        let values = parameters(expr)?;
+                        if values.len() == program.parameters() {
+                            filter_compiled(source, &program.bind(&values))?
+                        } else {
+                            // Placeholders of its own, which only the evaluator reports
+                            filter(source, Some(expr), &self.functions, &self.errors)?
+                        }
+                    }
+                    (unapplied, _) => filter(source, unapplied, &self.functions, &self.errors)?,
+                };
+                let filtered = Actual { rows: source.rows.len() as u64, time: clock.elapsed() };
+                let source = compute(source, columns, order_by, &self.functions, &self.errors)?;
+                let sort_collations = order_by
+                    .iter()
+                    .map(|item| sort_collation(&item.expr, &collations))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut result = select(source, &compiled, &sort_collations, *limit, *offset);
+                // Named by the statement rather than the plan, which is shared
+                // with statements that only differ in their literals
+                for (name, &item) in result.columns.iter_mut().zip(&compiled.items) {
+                    if !columns[item].is_wildcard() {
+                        *name = columns[item].output_name();
+                    }
+                }
+                if let Some(plan) = plan {
+                    record(plan, filtered, Actual { rows: result.rows.len() as u64, time: clock.elapsed() });
+                }
+                Ok(result)
+            }
+            statement => {
+                let result = self.execute(statement)?;
+                if let Some(plan) = plan {
+                    plan.actual = Some(Actual { rows: result.rows.len() as u64, time: clock.elapsed() });
+                }
+                Ok(result)
+            }
+        }
+    }
+
+    /// The collated columns of a FROM item that is a table, by the names a
+    /// query gives them.
+    fn collations(&self, from: &TableFactor) -> Vec<(String, Collation)> {
+        let (TableFactor::Table { name, alias, .. } | TableFactor::AsOf { name, alias, .. }) = from else {
+            return Vec::new();
+        };
+        let Ok(table) = self.table_at(name, as_of(from)) else { return Vec::new() };
+        let mut names = table.column_names();
+        if rename(&mut names, alias.as_ref()).is_err() {
+            return Vec::new();
+        }
+        collated(names, table)
+    }
+
+    /// The columns and rows of a FROM item, renamed by its alias, with the
+    /// part of `selection` the scan didn't apply itself and the number of
+    /// rows it read. A columnar scan leaves out the columns not in `used`.
+    /// `plan` is that of a subquery, recorded as it runs.
+    fn scan<'e>(
+        &mut self,
+        from: &TableFactor,
+        selection: Option<&'e Expression>,
+        used: Option<&[&str]>,
+        plan: Option<&mut Plan>,
+    ) -> Result<(QueryResult, Option<&'e Expression>, usize), EngineError> {
+        match from {
+            TableFactor::Table { sample: Some(_), .. } => Err(EngineError::UnsupportedFeature("TABLESAMPLE")),
+            TableFactor::Table { name, alias, .. } | TableFactor::AsOf { name, alias, .. } => {
+                let table = self.table_at(name, as_of(from))?;
+                let mut columns = table.column_names();
+                rename(&mut columns, alias.as_ref())?;
+                let data = match &table.data {
+                    TableData::Rows(rows) => {
+                        return Ok((QueryResult { columns, rows: rows.clone() }, selection, rows.len()));
+                    }
+                    TableData::Columns(data) => data,
+                };
+                // Calls to registered functions are left to the engine, with
+                // every column they might read
+                if selection.is_some_and(calls_function) {
+                    let all: Vec<usize> = (0..columns.len()).collect();
+                    return Ok((QueryResult { columns, rows: data.project(&all, None) }, selection, table.len()));
+                }
+                // The filter runs over the columns, so only the accepted rows
+                // are assembled, from only the columns read later
+                let accepted = selection.map(|expr| data.filter_handling(expr, &columns, &self.errors)).transpose()?;
+                let read = |i: usize| used.is_none_or(|used| used.contains(&columns[i].as_str()));
+                let kept: Vec<usize> = (0..columns.len()).filter(|&i| read(i)).collect();
+                let rows = data.project(&kept, accepted.as_ref());
+                let columns = kept.into_iter().map(|i| columns[i].clone()).collect();
+                Ok((QueryResult { columns, rows }, None, table.len()))
+            }
+            TableFactor::Derived { subquery, alias, .. } => {
+                let mut result = self.run(subquery, plan)?;
+                rename(&mut result.columns, alias.as_ref())?;
+                let read = result.rows.len();
+                Ok((result, selection, read))
+            }
+            TableFactor::Function { .. } => Err(EngineError::UnsupportedFeature("A table function")),
+            TableFactor::Pivot { .. } => Err(EngineError::UnsupportedFeature("PIVOT")),
+            TableFactor::Unpivot { .. } => Err(EngineError::UnsupportedFeature("UNPIVOT")),
+        }
+    }
+
+    fn insert(&mut self, table_name: &str, columns: Option<&[Ident]>, rows: &[Vec<Expression>]) -> Result<(), EngineError> {
+        let table = self.writable(table_name)?;
+
+        // Position in the table of each listed value
+        let targets: Vec<usize> = match columns {
+            Some(names) => {
+                let mut targets = Vec::new();
+                for name in names {
+                    let index = table.column_index(name)?;
+                    if targets.contains(&index) {
+                        return Err(EngineError::DuplicateColumn(name.value.clone()));
+                    }
+                    targets.push(index);
+                }
+                targets
+            }
+            None => (0..table.columns.len()).collect(),
+        };
+
+        let batch = !self.per_row_inserts && rows.len() >= BATCH_INSERT_ROWS;
+        let new_rows = if batch {
+            self.batch_rows(table, &targets, rows)?
+        } else {
+            self.new_rows(table, &targets, rows)?
+        };
+
+        // Primary keys must be unique across the stored rows and the new ones
+        for (index, column) in table.columns.iter().enumerate() {
+            if !column.constraints.contains(&Constraint::PrimaryKey) {
+                continue;
+            }
+            if sorted_duplicate(table, index, &new_rows, column_collation(column)) {
+                return Err(EngineError::DuplicateKey(column.column_name.value.clone()));
+            }
+        }
+
+        // Only now, so that a failed INSERT doesn't copy a shared table
+        let Some(table) = self.tables.get_mut(table_name).filter(|_| !new_rows.is_empty()) else { return Ok(()) };
+        let stored = match &mut Arc::make_mut(table).data {
+            TableData::Rows(rows) => {
+                rows.extend(new_rows);
+                Ok(())
+            }
+            TableData::Columns(columns) => new_rows.iter().try_for_each(|row| columns.push_row(row)),
+        };
+        self.wrote(table_name);
+        Ok(stored?)
+    }
+
+    /// Sets the assigned columns of the rows `selection` holds for, each
+    /// value computed from the row as it was. The new rows are checked as
+    /// an INSERT's are, and primary keys must stay unique; a failed UPDATE
+    /// changes no row.
+    fn update(
+        &mut self,
+        table_name: &str,
+        assignments: &[(Ident, Expression)],
+        selection: Option<&Expression>,
+    ) -> Result<(), EngineError> {
+        let table = self.writable(table_name)?;
+        let mut targets = Vec::new();
+        for (name, _) in assignments {
+            let index = table.column_index(name)?;
+            if targets.contains(&index) {
+                return Err(EngineError::DuplicateColumn(name.value.clone()));
+            }
+            targets.push(index);
+        }
+        let matched = self.matching(table, selection)?;
+        if !matched.contains(&true) {
+            return Ok(());
+        }
+        let names = table.column_names();
+        let call = |name: &str, args: &[Value]| self.functions.call(name, args);
+        let mut rows = table.rows().into_owned();
+        for (row, _) in rows.iter_mut().zip(&matched).filter(|(_, &matched)| matched) {
+            let mut values = Vec::with_capacity(assignments.len());
+            for (_, expr) in assignments {
+                values.push(evaluate_handling(expr, &names, row, &call, &self.errors)?);
+            }
+            for (&index, value) in targets.iter().zip(values) {
+                let column = &table.columns[index];
+                let value = Table::coerce(column, value)?;
+                Table::check_value(column, &value)?;
+                row[index] = value;
+            }
+            table.check_row(&names, row)?;
+        }
+        for &index in &targets {
+            let column = &table.columns[index];
+            let key = column.constraints.contains(&Constraint::PrimaryKey);
+            if key && duplicate_key(&rows, index, column_collation(column)) {
+                return Err(EngineError::DuplicateKey(column.column_name.value.clone()));
+            }
+        }
+        self.replace_rows(table_name, rows)
+    }
+
+    /// Removes the rows `selection` holds for, every row without one.
+    fn delete(&mut self, table_name: &str, selection: Option<&Expression>) -> Result<(), EngineError> {
+        let table = self.writable(table_name)?;
+        let matched = self.matching(table, selection)?;
+        if !matched.contains(&true) {
+            return Ok(());
+        }
+        let rows = table.rows();
+        let kept = rows.iter().zip(&matched).filter(|(_, &matched)| !matched).map(|(row, _)| row.clone()).collect();
+        self.replace_rows(table_name, kept)
+    }
+
+    /// The table an INSERT, UPDATE or DELETE writes.
+    fn writable(&self, table_name: &str) -> Result<&Table, EngineError> {
+        let table = self.tables.get(table_name).ok_or_else(|| EngineError::NoSuchTable(table_name.to_string()))?;
+        if table.read_only {
+            return Err(EngineError::ReadOnly(table_name.to_string()));
+        }
+        Ok(table)
+    }
+
+    /// Whether `selection` holds for each row of `table`, comparing its
+    /// collated columns by their collations as a SELECT does.
+    fn matching(&self, table: &Table, selection: Option<&Expression>) -> Result<Vec<bool>, EngineError> {
+        let Some(expr) = selection else { return Ok(vec![true; table.len()]) };
+        let names = table.column_names();
+        let expr = collate(expr, &collated(names.clone(), table));
+        let call = |name: &str, args: &[Value]| self.functions.call(name, args);
+        let rows = table.rows();
+        let accepted = rows.iter().map(|row| Ok(accepts(&evaluate_handling(&expr, &names, row, &call, &self.errors)?)));
+        accepted.collect()
+    }
+
+    /// Stores `rows` as all of a table's rows, in its layout.
+    fn replace_rows(&mut self, table_name: &str, rows: Vec<Vec<Value>>) -> Result<(), EngineError> {
+        let table = &self.tables[table_name];
+        // Built before the table is copied, in case the rows don't fit its columns
+        let data = match &table.data {
+            TableData::Rows(_) => TableData::Rows(rows),
+            TableData::Columns(_) => {
+                TableData::Columns(ColumnarTable::from_rows(table.columns.iter().map(|c| &c.column_type), &rows)?)
+            }
+        };
+        let table = self.tables.get_mut(table_name).expect("the table was just read");
+        Arc::make_mut(table).data = data;
+        self.wrote(table_name);
+        Ok(())
+    }
+
+    /// The rows of an INSERT into `table`, each evaluated and checked in
+    /// turn; `targets` are the columns its values are for.
+    fn new_rows(
+        &self,
+        table: &Table,
+        targets: &[usize],
+        rows: &[Vec<Expression>],
+    ) -> Result<Vec<Vec<Value>>, EngineError> {
+        let names = table.column_names();
+        let mut new_rows: Vec<Vec<Value>> = Vec::new();
+        for exprs in rows {
+            if exprs.len() != targets.len() {
+                return Err(EngineError::ColumnCount { expected: targets.len(), found: exprs.len() });
+            }
+            let mut row = vec![Value::Null; table.columns.len()];
+            for (expr, &index) in exprs.iter().zip(targets) {
+                let call = |name: &str, args: &[Value]| self.functions.call(name, args);
+                let value = evaluate_handling(expr, &[], &[], &call, &self.errors)?;
+                row[index] = Table::coerce(&table.columns[index], value)?;
+            }
+            for (column, value) in table.columns.iter().zip(&row) {
+                Table::check_value(column, value)?;
+            }
+            table.check_row(&names, &row)?;
+            new_rows.push(row);
+        }
+        Ok(new_rows)
+    }
+
+    /// `new_rows` for an INSERT of many rows, such as one from a dump file:
+    /// each column's constraints are looked up once for all of its values,
+    /// and literals are read without the evaluator. Of several errors in
+    /// the rows, the one raised is the one `new_rows` would raise: the
+    /// first error of the first row that fails.
+    fn batch_rows(
+        &self,
+        table: &Table,
+        targets: &[usize],
+        rows: &[Vec<Expression>],
+    ) -> Result<Vec<Vec<Value>>, EngineError> {
+        let required: Vec<bool> = table.columns.iter().map(is_required).collect();
+        let mut constraints = table.columns.iter().flat_map(|column| &column.constraints);
+        let checked = constraints.any(|constraint| matches!(constraint, Constraint::Check(_)));
+        // A NOT NULL column no value is given for, which every row leaves NULL
+        let unset = table.columns.iter().enumerate().find(|&(index, _)| required[index] && !targets.contains(&index));
+        let names = if checked { table.column_names() } else { Vec::new() };
+        let call = |name: &str, args: &[Value]| self.functions.call(name, args);
+        let row = |exprs: &[Expression]| -> Result<Vec<Value>, EngineError> {
+            if exprs.len() != targets.len() {
+                return Err(EngineError::ColumnCount { expected: targets.len(), found: exprs.len() });
+            }
+            if let Some((_, column)) = unset {
+                return Err(EngineError::NotNull(column.column_name.value.clone()));
+            }
+            let mut row = vec![Value::Null; table.columns.len()];
+            for (expr, &index) in exprs.iter().zip(targets) {
+                let value = match expr {
+                    Expression::Number(_)
+                    | Expression::String(_)
+                    | Expression::Boolean(_)
+                    | Expression::Decimal(_)
+                    | Expression::Null
+                    | Expression::TypedString { .. }
+                    | Expression::PrefixedString { .. } => literal_value(expr),
+                    expr => evaluate_handling(expr, &[], &[], &call, &self.errors),
+                };
+                let column = &table.columns[index];
+                let value = Table::coerce(column, value?)?;
+                Table::check_type(column, required[index], &value)?;
+                row[index] = value;
+            }
+            if checked {
+                table.check_row(&names, &row)?;
+            }
+            Ok(row)
+        };
+        let mut new_rows = Vec::with_capacity(rows.len());
+        for (i, exprs) in rows.iter().enumerate() {
+            match row(exprs) {
+                Ok(row) => new_rows.push(row),
+                // The row on its own fails as it would row by row, the checks
+                // of one column before the next in its row
+                Err(error) => return Err(self.new_rows(table, targets, &rows[i..=i]).err().unwrap_or(error)),
+            }
+        }
+        Ok(new_rows)
+    }
+}
+
+/// A session's transaction on a database shared with other sessions: it
+/// sees the tables as they were at `Database::begin`, plus its own changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transaction {
+    base: Tables,
+    working: Database,
+}
+
+impl Transaction {
+    /// Runs a statement inside the transaction; BEGIN, COMMIT and ROLLBACK
+    /// are for the database the transaction came from.
+    pub fn execute(&mut self, statement: &Statement) -> Result<QueryResult, EngineError> {
+        match statement {
+            Statement::Transaction { command: TransactionCommand::Begin } => Err(EngineError::TransactionInProgress),
+            Statement::Transaction { .. } => Err(EngineError::Unsupported(statement.kind())),
+            other => self.working.execute(other),
+        }
+    }
+
+    pub fn execute_sql(&mut self, sql: &str) -> Result<QueryResult, EngineError> {
+        let statement = parse_sql(sql)?;
+        self.execute(&statement)
+    }
+
+    /// The tables as the transaction sees them.
+    pub fn database(&self) -> &Database {
+        &self.working
+    }
+}
+
+/// Evaluates the rows of a VALUES statement.
+fn values(rows: &[Vec<Expression>], functions: &Functions, errors: &ErrorHandling) -> Result<QueryResult, EngineError> {
+    let call = |name: &str, args: &[Value]| functions.call(name, args);
+    let width = rows.first().map_or(0, Vec::len);
+    let mut result = QueryResult { columns: (1..=width).map(|i| format!("column{}", i)).collect(), rows: Vec::new() };
+    for exprs in rows {
+        if exprs.len() != width {
+            return Err(EngineError::ColumnCount { expected: width, found: exprs.len() });
+        }
+        let row = exprs.iter().map(|expr| evaluate_handling(expr, &[], &[], &call, errors)).collect::<Result<_, _>>()?;
+        result.rows.push(row);
+    }
+    Ok(result)
+}
+
+/// Renames the first columns to the names listed by a table alias.
+fn rename(columns: &mut [String], alias: Option<&TableAlias>) -> Result<(), EngineError> {
+    if let Some(TableAlias { name, columns: names }) = alias {
+        if names.len() > columns.len() {
+            return Err(EngineError::AliasColumns { alias: name.value.clone(), available: columns.len() });
+        }
+        for (column, name) in columns.iter_mut().zip(names) {
+            *column = name.value.clone();
+        }
+    }
+    Ok(())
+}
+
+/// Whether a column of `data_type` can hold `value`, or one that INSERT
+/// converts it to, such as a date written as a string.
+fn holds(data_type: &DBType, value: &Value) -> bool {
+    match (data_type, value) {
+        (_, Value::Null) | (DBType::Int, Value::Int(_)) | (DBType::Bool, Value::Bool(_)) => true,
+        (DBType::Varchar(_), Value::String(_)) => true,
+        (DBType::Date, Value::Date(_)) | (DBType::Time, Value::Time(_)) => true,
+        (DBType::Timestamp, Value::Timestamp(_) | Value::Date(_)) => true,
+        (DBType::Decimal(..), Value::Decimal(_) | Value::Int(_)) => true,
+        (DBType::Date, Value::String(text)) => Date::parse(text).is_some(),
+        (DBType::Time, Value::String(text)) => Time::parse(text).is_some(),
+        (DBType::Timestamp, Value::String(text)) => Timestamp::parse(text).is_some(),
+        (DBType::Decimal(..), Value::String(text)) => Decimal::parse(text.trim()).is_some(),
+        _ => false,
+    }
+}
+
+fn column_index(names: &[String], name: &str) -> Result<usize, EngineError> {
+    names.iter().position(|n| n == name).ok_or_else(|| EngineError::UnknownColumn(name.to_string()))
+}
+
+/// The rows of `source` that `selection` holds for.
+fn filter(
+    source: QueryResult,
+    selection: Option<&Expression>,
+    functions: &Functions,
+    errors: &ErrorHandling,
+) -> Result<QueryResult, EngineError> {
+    let Some(expr) = selection else { return Ok(source) };
+    let call = |name: &str, args: &[Value]| functions.call(name, args);
+    let mut rows = Vec::new();
+    for row in source.rows {
+        if accepts(&evaluate_handling(expr, &source.columns, &row, &call, errors)?) {
+            rows.push(row);
+        }
+    }
+    Ok(QueryResult { columns: source.columns, rows })
+}
+
+/// `source` with a column for each item of the select list `items` that
+/// isn't `*` or a column, holding the item's value in each row, then one
+/// for each ORDER BY item that isn't a column.
+fn compute(
+    source: QueryResult,
+    items: &[SelectItem],
+    order_by: &[OrderByExpr],
+    functions: &Functions,
+    errors: &ErrorHandling,
+) -> Result<QueryResult, EngineError> {
+    let selected = items.iter().filter(|item| !item.is_wildcard() && item.as_column().is_none());
+    let mut computed: Vec<(&Expression, String)> = selected.map(|item| (&item.expr, item.output_name())).collect();
+    let ordered = order_by.iter().filter(|item| sort_column(&item.expr).is_none());
+    computed.extend(ordered.map(|item| (&item.expr, item.expr.to_string())));
+    if computed.is_empty() {
+        return Ok(source);
+    }
+    let call = |name: &str, args: &[Value]| functions.call(name, args);
+    let QueryResult { mut columns, mut rows } = source;
+    for row in &mut rows {
+        let mut values = Vec::with_capacity(computed.len());
+        for (expr, _) in &computed {
+            values.push(evaluate_handling(expr, &columns, row, &call, errors)?);
+        }
+        row.extend(values);
+    }
+    columns.extend(computed.into_iter().map(|(_, name)| name));
+    Ok(QueryResult { columns, rows })
+}
+
+/// The column an ORDER BY item sorts by, when it is one, collated or not.
+fn sort_column(expr: &Expression) -> Option<&Ident> {
+    match expr {
+        Expression::Identifier(name) => Some(name),
+        Expression::Collate { expr, .. } => match &**expr {
+            Expression::Identifier(name) => Some(name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The collation an ORDER BY item sorts by: the one it names, else its
+/// column's, else BINARY.
+fn sort_collation(expr: &Expression, collations: &[(String, Collation)]) -> Result<Collation, EngineError> {
+    match expr {
+        Expression::Collate { collation: name, .. } => {
+            Ok(Collation::from_name(name.as_str()).ok_or_else(|| collation::unknown(name.as_str()))?)
+        }
+        Expression::Identifier(name) => {
+            Ok(collations.iter().find(|(c, _)| name == c).map_or(Collation::Binary, |&(_, c)| c))
+        }
+        _ => Ok(Collation::Binary),
+    }
+}
+
+/// The rows of `source` that the compiled WHERE clause `program` holds for.
+fn filter_compiled(source: QueryResult, program: &Program) -> Result<QueryResult, EngineError> {
+    let mut stack = Vec::new();
+    let mut rows = Vec::new();
+    for row in source.rows {
+        if program.matches(&row, &mut stack)? {
+            rows.push(row);
+        }
+    }
+    Ok(QueryResult { columns: source.columns, rows })
+}
+
+/// Sorts, limits and projects the filtered rows of a SELECT, comparing the
+/// strings of each sort key by its collation.
+fn select(
+    source: QueryResult,
+    compiled: &CompiledSelect,
+    collations: &[Collation],
+    limit: Option<u64>,
+    offset: Option<u64>,
+) -> QueryResult {
+    let (names, mut rows) = (source.columns, source.rows);
+    let (projection, sort_keys) = (&compiled.projection, &compiled.sort_keys);
+    // A stable sort keeps insertion order between equal keys
+    rows.sort_by(|a, b| {
+        let mut orderings = sort_keys.iter().zip(collations).map(|(key, &c)| {
+            let ordering = compare_values(&a[key.index], &b[key.index], c);
+            if key.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        orderings.find(|o| o.is_ne()).unwrap_or(Ordering::Equal)
+    });
+
+    let skip = offset.unwrap_or(0) as usize;
+    let take = limit.map_or(usize::MAX, |n| n as usize);
+    let rows = rows
+        .into_iter()
+        .skip(skip)
+        .take(take)
+        .map(|row| projection.iter().map(|&i| row[i].clone()).collect())
+        .collect();
+
+    QueryResult { columns: projection.iter().map(|&i| names[i].clone()).collect(), rows }
+}
+
+/// Total order used by ORDER BY: NULL first, then by value.
+fn compare_values(a: &Value, b: &Value, collation: Collation) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Less,
+        (_, Value::Null) => Ordering::Greater,
+        (Value::Int(a), Value::Int(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::String(a), Value::String(b)) => collation.compare(a, b),
+        (Value::Date(a), Value::Date(b)) => a.cmp(b),
+        (Value::Time(a), Value::Time(b)) => a.cmp(b),
+        (Value::Timestamp(a), Value::Timestamp(b)) => a.cmp(b),
+        (Value::Decimal(a), Value::Decimal(b)) => a.cmp(b),
+        // Columns are typed, so mixed values never meet in a sort
+        _ => Ordering::Equal,
+    }
+}
+
+/// The collation a column is declared with, BINARY unless it names one.
+fn column_collation(column: &TableColumn) -> Collation {
+    let named = column.constraints.iter().find_map(|constraint| match constraint {
+        Constraint::Collate(name) => Collation::from_name(name.as_str()),
+        _ => None,
+    });
+    named.unwrap_or_default()
+}
+
+/// Whether a column is NOT NULL, or a primary key, which is too.
+fn is_required(column: &TableColumn) -> bool {
+    column.constraints.iter().any(|c| matches!(c, Constraint::NotNull | Constraint::PrimaryKey))
+}
+
+/// Whether column `index` holds a key twice once `new_rows` are added to
+/// `table`. The new keys are sorted, and each stored key is looked up among
+/// them, rather than comparing every pair: a single row costs one pass over
+/// the table, and however many rows no more than a pass and a sort.
+fn sorted_duplicate(table: &Table, index: usize, new_rows: &[Vec<Value>], collation: Collation) -> bool {
+    let mut keys: Vec<&Value> = new_rows.iter().map(|row| &row[index]).collect();
+    keys.sort_by(|a, b| compare_values(a, b, collation));
+    if keys.windows(2).any(|pair| same_key(collation, pair[0], pair[1])) {
+        return true;
+    }
+    let new = |stored: &Value| {
+        let found = keys.binary_search_by(|key| compare_values(key, stored, collation));
+        found.is_ok_and(|i| same_key(collation, keys[i], stored))
+    };
+    match &table.data {
+        TableData::Rows(rows) => rows.iter().any(|row| new(&row[index])),
+        TableData::Columns(columns) => {
+            let column = &columns.columns()[index];
+            (0..column.len()).any(|i| new(&column.get(i)))
+        }
+    }
+}
+
+/// Whether column `index` holds a key twice among `rows`.
+fn duplicate_key(rows: &[Vec<Value>], index: usize, collation: Collation) -> bool {
+    let mut keys: Vec<&Value> = rows.iter().map(|row| &row[index]).collect();
+    keys.sort_by(|a, b| compare_values(a, b, collation));
+    keys.windows(2).any(|pair| same_key(collation, pair[0], pair[1]))
+}
+
+/// Whether two values of a column are the same key by its collation.
+fn same_key(collation: Collation, a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => collation.compare(a, b).is_eq(),
+        (a, b) => a == b,
+    }
+}
+
+/// The columns among `names`, those of `table` in order, that have a
+/// collation other than BINARY, with it.
+fn collated(names: Vec<String>, table: &Table) -> Vec<(String, Collation)> {
+    let collations = table.columns.iter().map(column_collation);
+    names.into_iter().zip(collations).filter(|(_, collation)| *collation != Collation::Binary).collect()
+}
+
+/// `expr` with each column of `collations` that is compared in it marked
+/// with the column's collation, unless the comparison names its own.
+fn collate(expr: &Expression, collations: &[(String, Collation)]) -> Expression {
+    let mark = |operand: &Expression, explicit: bool| match operand {
+        Expression::Identifier(name) if !explicit => match collations.iter().find(|(c, _)| name == c) {
+            Some((_, collation)) => Expression::Collate {
+                expr: Box::new(operand.clone()),
+                collation: Ident::new(collation.name()),
+            },
+            None => operand.clone(),
+        },
+        operand => collate(operand, collations),
+    };
+    match expr {
+        Expression::BinaryOperation { left_operand, operator, right_operand } => {
+            let explicit = Collation::between(left_operand, right_operand) != Ok(None);
+            Expression::BinaryOperation {
+                left_operand: Box::new(mark(left_operand, explicit)),
+                operator: operator.clone(),
+                right_operand: Box::new(mark(right_operand, explicit)),
+            }
+        }
+        Expression::UnaryOperation { operator, operand } => {
+            Expression::UnaryOperation { operator: operator.clone(), operand: Box::new(collate(operand, collations)) }
+        }
+        Expression::Grouped(inner) => Expression::Grouped(Box::new(collate(inner, collations))),
+        other => other.clone(),
+    }
+}
+
+/// The step of a SELECT's plan that reads its FROM item, under those of
+/// LIMIT and WHERE.
+fn source_of(mut plan: &mut Plan) -> &mut Plan {
+    while matches!(plan.step, Step::Limit { .. } | Step::Filter(_)) && !plan.inputs.is_empty() {
+        plan = &mut plan.inputs[0];
+    }
+    plan
+}
+
+/// Records what a SELECT's WHERE and LIMIT produced in their steps.
+fn record(plan: &mut Plan, mut filtered: Actual, selected: Actual) {
+    let mut step = plan;
+    if let Step::Limit { .. } = step.step {
+        step.actual = Some(selected);
+        let Some(input) = step.inputs.first_mut() else { return };
+        step = input;
+    } else {
+        // The top step also takes the time of sorting and projecting
+        filtered.time = selected.time;
+        if let Some(actual) = &mut step.actual {
+            actual.time = selected.time;
+        }
+    }
+    if let Step::Filter(_) = step.step {
+        step.actual = Some(filtered);
+    }
+}
+
+/// Wall time since a query started, for EXPLAIN ANALYZE; without std
+/// there is no clock, and it reads zero.
+#[derive(Debug, Clone, Copy)]
+struct Clock {
+    #[cfg(feature = "std")]
+    started: std::time::Instant,
+}
+
+impl Clock {
+    #[cfg(feature = "std")]
+    fn start() -> Self {
+        Clock { started: std::time::Instant::now() }
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn start() -> Self {
+        Clock {}
+    }
+
+    #[cfg(feature = "std")]
+    fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// The version a FROM item reads its table as of, if not the current one.
+fn as_of(factor: &TableFactor) -> Option<u64> {
+    match factor {
+        TableFactor::AsOf { version, .. } => Some(*version),
+        _ => None,
+    }
+}
+
+/// Whether `statement` reads a table AS OF a version anywhere.
+fn reads_history(statement: &Statement) -> bool {
+    struct History(bool);
+    impl Visitor for History {
+        fn table_factor(&mut self, factor: &TableFactor) {
+            self.0 |= matches!(factor, TableFactor::AsOf { .. });
+        }
+    }
+    let mut history = History(false);
+    walk(statement, &mut history);
+    history.0
+}
+
+/// Whether `expr` calls a function anywhere.
+/// Whether `expr` calls an aggregate or window function of `registry`, or
+/// any function with OVER.
+fn aggregates(expr: &Expression, registry: &Registry) -> bool {
+    let mut found = false;
+    expr.walk(&mut |expr| {
+        if let Expression::Function { name, over, .. } = expr {
+            let signatures = registry.signatures(name);
+            found |= over.is_some() || signatures.iter().any(|signature| signature.kind != FunctionKind::Scalar);
+        }
+    });
+    found
+}
+
+fn calls_function(expr: &Expression) -> bool {
+    let mut calls = false;
+    expr.walk(&mut |expr| calls |= matches!(expr, Expression::Function { .. }));
+    calls
+}
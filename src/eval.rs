@@ -0,0 +1,533 @@
+// eval.rs — evaluation of expressions against a single row
+// Author: Sreerag Devadasan
+
+use core::fmt;
+
+use crate::prelude::*;
+use crate::ast::{BinaryOperator, DBType, Expression, StringPrefix, UnaryOperator};
+use crate::collation::{self, Collation};
+use crate::datetime::{self, Date, Time, Timestamp};
+use crate::decimal::{self, Decimal};
+use crate::semantics::{self, accepts, truth, ErrorHandling};
+
+// === Values and errors ===
+
+/// A runtime value produced while evaluating an expression against a row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Int(i64),
+    Bool(bool),
+    String(String),
+    Date(Date),
+    Time(Time),
+    Timestamp(Timestamp),
+    Decimal(Decimal),
+}
+
+/// Errors that can happen while evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    UnknownColumn(String),
+    TypeMismatch(String),
+    DivisionByZero,
+    Overflow,
+    UnknownFunction(String),
+    UnknownCollation(String),
+    /// A CAST of a value that isn't one of the type.
+    InvalidCast(String),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::UnknownColumn(c) => write!(f, "Unknown column: {}", c),
+            EvalError::TypeMismatch(e) => write!(f, "Type mismatch: {}", e),
+            EvalError::DivisionByZero => write!(f, "Division by zero"),
+            EvalError::Overflow => write!(f, "Integer overflow"),
+            EvalError::UnknownFunction(name) => write!(f, "Unknown function: {}", name),
+            EvalError::UnknownCollation(name) => write!(f, "Unknown collation: {}", name),
+            EvalError::InvalidCast(e) => write!(f, "Invalid cast: {}", e),
+        }
+    }
+}
+
+impl core::error::Error for EvalError {}
+
+// === Tree-walking evaluator ===
+
+/// Evaluates `expr` against one row, where `columns[i]` names `row[i]`.
+///
+/// Column names are resolved on every call; use `bytecode::Program` when the
+/// same expression is evaluated for many rows.
+pub fn evaluate(expr: &Expression, columns: &[String], row: &[Value]) -> Result<Value, EvalError> {
+    evaluate_calling(expr, columns, row, &builtin)
+}
+
+/// Calls one of the builtin functions, `date_part` and `date_trunc` (see
+/// datetime.rs).
+pub fn builtin(name: &str, args: &[Value]) -> Result<Value, EvalError> {
+    datetime::call(name, args).unwrap_or_else(|| Err(EvalError::UnknownFunction(name.to_string())))
+}
+
+/// A function call's name and argument values to its result.
+pub type Call<'f> = dyn Fn(&str, &[Value]) -> Result<Value, EvalError> + 'f;
+
+/// Like `evaluate`, making the function calls of `expr` through `call`;
+/// window functions are never called.
+pub fn evaluate_calling(
+    expr: &Expression,
+    columns: &[String],
+    row: &[Value],
+    call: &Call<'_>,
+) -> Result<Value, EvalError> {
+    evaluate_handling(expr, columns, row, call, &ErrorHandling::STRICT)
+}
+
+/// Like `evaluate_calling`, with overflows, divisions by zero and invalid
+/// casts giving what `errors` says (see semantics.rs).
+pub fn evaluate_handling(
+    expr: &Expression,
+    columns: &[String],
+    row: &[Value],
+    call: &Call<'_>,
+    errors: &ErrorHandling,
+) -> Result<Value, EvalError> {
+    let evaluate = |expr| evaluate_handling(expr, columns, row, call, errors);
+    match expr {
+        Expression::Identifier(name) => columns
+            .iter()
+            .position(|c| name == c)
+            .map(|i| row[i].clone())
+            .ok_or_else(|| EvalError::UnknownColumn(name.value.clone())),
+        Expression::CompoundIdentifier(parts) => {
+            let name = parts.iter().map(|part| part.as_str()).collect::<Vec<_>>().join(".");
+            columns.iter().position(|c| *c == name).map(|i| row[i].clone()).ok_or(EvalError::UnknownColumn(name))
+        }
+        Expression::Grouped(inner) => evaluate(inner),
+        Expression::UnaryOperation { operator, operand } => {
+            let value = evaluate(operand)?;
+            apply_unary_under(operator, value, errors)
+        }
+        Expression::BinaryOperation { left_operand, operator, right_operand } => {
+            let collation = Collation::between(left_operand, right_operand)?;
+            let left = evaluate(left_operand)?;
+            let right = evaluate(right_operand)?;
+            match collation {
+                Some(collation) => {
+                    let saturated = saturate(operator, &left, &right);
+                    let result = apply_collated(operator, collation, left, right);
+                    result.or_else(|error| errors.recover(error, || saturated))
+                }
+                None => apply_binary_under(operator, left, right, errors),
+            }
+        }
+        Expression::Cast { expr, data_type } => {
+            let value = evaluate(expr)?;
+            cast_under(&value, data_type, errors)
+        }
+        Expression::Collate { expr, collation } => {
+            Collation::from_name(collation.as_str()).ok_or_else(|| collation::unknown(collation.as_str()))?;
+            evaluate(expr)
+        }
+        Expression::Function { name, over: Some(_), .. } => Err(EvalError::UnknownFunction(name.clone())),
+        Expression::Function { name, args, .. } => {
+            let args = args.iter().map(evaluate).collect::<Result<Vec<_>, _>>()?;
+            call(name, &args)
+        }
+        literal => literal_value(literal),
+    }
+}
+
+/// Returns true when a WHERE expression accepts the row (NULL counts as false).
+pub fn matches(expr: &Expression, columns: &[String], row: &[Value]) -> Result<bool, EvalError> {
+    Ok(accepts(&evaluate(expr, columns, row)?))
+}
+
+/// Like `matches`, making function calls through `call`.
+pub fn matches_calling(
+    expr: &Expression,
+    columns: &[String],
+    row: &[Value],
+    call: &Call<'_>,
+) -> Result<bool, EvalError> {
+    Ok(accepts(&evaluate_calling(expr, columns, row, call)?))
+}
+
+// === Operator semantics shared with the bytecode VM ===
+//
+// NULLs follow the three-valued logic of semantics.rs.
+
+/// Converts a literal expression into its runtime value.
+pub(crate) fn literal_value(expr: &Expression) -> Result<Value, EvalError> {
+    match expr {
+        Expression::Number(n) => i64::try_from(*n).map(Value::Int).map_err(|_| EvalError::Overflow),
+        Expression::String(s) => Ok(Value::String(s.clone())),
+        Expression::Boolean(b) => Ok(Value::Bool(*b)),
+        Expression::Decimal(text) => Decimal::parse(text).map(Value::Decimal).ok_or(EvalError::Overflow),
+        Expression::Null => Ok(Value::Null),
+        Expression::TypedString { data_type, value } => {
+            let parsed = match data_type {
+                DBType::Date => Date::parse(value).map(Value::Date),
+                DBType::Time => Time::parse(value).map(Value::Time),
+                DBType::Timestamp => Timestamp::parse(value).map(Value::Timestamp),
+                _ => None,
+            };
+            parsed.ok_or_else(|| EvalError::TypeMismatch(format!("{:?} is not a valid {}", value, data_type)))
+        }
+        Expression::PrefixedString { prefix, value } => prefixed_value(*prefix, value),
+        other => Err(EvalError::TypeMismatch(format!("{:?} is not a literal", other))),
+    }
+}
+
+/// The value of a prefixed string. As in MySQL, a `B` string is the integer
+/// its bits spell and an `X` string the text its bytes spell in UTF-8.
+fn prefixed_value(prefix: StringPrefix, value: &str) -> Result<Value, EvalError> {
+    match prefix {
+        StringPrefix::National | StringPrefix::Escape => Ok(Value::String(value.to_string())),
+        StringPrefix::Bit if value.is_empty() => Ok(Value::Int(0)),
+        StringPrefix::Bit => {
+            let bits = u64::from_str_radix(value, 2).map_err(|_| EvalError::Overflow)?;
+            i64::try_from(bits).map(Value::Int).map_err(|_| EvalError::Overflow)
+        }
+        StringPrefix::Hex => {
+            let not_text = || EvalError::TypeMismatch(format!("X'{}' is not UTF-8 text", value));
+            if !value.len().is_multiple_of(2) {
+                return Err(not_text());
+            }
+            let bytes = (0..value.len()).step_by(2).map(|i| u8::from_str_radix(&value[i..i + 2], 16));
+            let bytes: Vec<u8> = bytes.collect::<Result<_, _>>().map_err(|_| not_text())?;
+            String::from_utf8(bytes).map(Value::String).map_err(|_| not_text())
+        }
+    }
+}
+
+pub(crate) fn apply_unary(operator: &UnaryOperator, value: Value) -> Result<Value, EvalError> {
+    match (operator, value) {
+        (UnaryOperator::IsNull, value) => Ok(Value::Bool(semantics::is_null(&value))),
+        (UnaryOperator::IsNotNull, value) => Ok(Value::Bool(!semantics::is_null(&value))),
+        (_, Value::Null) => Ok(Value::Null),
+        (UnaryOperator::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+        (UnaryOperator::Negate, Value::Int(n)) => n.checked_neg().map(Value::Int).ok_or(EvalError::Overflow),
+        (UnaryOperator::Negate, Value::Decimal(d)) => Ok(Value::Decimal(d.negate())),
+        (op, v) => Err(EvalError::TypeMismatch(format!("cannot apply {:?} to {:?}", op, v))),
+    }
+}
+
+pub(crate) fn apply_binary(operator: &BinaryOperator, left: Value, right: Value) -> Result<Value, EvalError> {
+    use BinaryOperator::*;
+
+    match operator {
+        And => Ok(semantics::value(semantics::and(truth(&left)?, truth(&right)?))),
+        Or => Ok(semantics::value(semantics::or(truth(&left)?, truth(&right)?))),
+        IsDistinctFrom | IsNotDistinctFrom => {
+            let distinct = semantics::distinct(&left, &right, |a, b| compare(&Equals, a, b))?;
+            Ok(Value::Bool(distinct == (*operator == IsDistinctFrom)))
+        }
+        _ if semantics::is_strict(operator) && (left == Value::Null || right == Value::Null) => Ok(Value::Null),
+        Add | Subtract | Multiply | Divide => match (left, right) {
+            (Value::Int(a), Value::Int(b)) => arithmetic(operator, a, b).map(Value::Int),
+            (a, b) => datetime::arithmetic(operator, &a, &b)
+                .or_else(|| decimal::arithmetic(operator, &a, &b))
+                .unwrap_or_else(|| {
+                    Err(EvalError::TypeMismatch(format!("cannot apply {:?} to {:?} and {:?}", operator, a, b)))
+                }),
+        },
+        Like | ILike => match (left, right) {
+            (Value::String(text), Value::String(pattern)) if *operator == ILike => {
+                Ok(Value::Bool(like(&text.to_lowercase(), &pattern.to_lowercase())))
+            }
+            (Value::String(text), Value::String(pattern)) => Ok(Value::Bool(like(&text, &pattern))),
+            (a, b) => Err(EvalError::TypeMismatch(format!("cannot apply {:?} to {:?} and {:?}", operator, a, b))),
+        },
+        Concat => match (left, right) {
+            (Value::String(a), Value::String(b)) => Ok(Value::String(a + &b)),
+            (a, b) => Err(EvalError::TypeMismatch(format!("cannot apply {:?} to {:?} and {:?}", operator, a, b))),
+        },
+        _ => compare(operator, &left, &right),
+    }
+}
+
+/// `apply_unary` with its errors handled as `errors` says.
+pub(crate) fn apply_unary_under(
+    operator: &UnaryOperator,
+    value: Value,
+    errors: &ErrorHandling,
+) -> Result<Value, EvalError> {
+    let saturated = extreme(&value, &value, -signum(&value));
+    apply_unary(operator, value).or_else(|error| errors.recover(error, || saturated))
+}
+
+/// `apply_binary` with its errors handled as `errors` says.
+pub(crate) fn apply_binary_under(
+    operator: &BinaryOperator,
+    left: Value,
+    right: Value,
+    errors: &ErrorHandling,
+) -> Result<Value, EvalError> {
+    let saturated = saturate(operator, &left, &right);
+    apply_binary(operator, left, right).or_else(|error| errors.recover(error, || saturated))
+}
+
+/// `apply_binary` with two strings compared by `collation`, which only
+/// comparisons and LIKE do.
+pub(crate) fn apply_collated(
+    operator: &BinaryOperator,
+    collation: Collation,
+    left: Value,
+    right: Value,
+) -> Result<Value, EvalError> {
+    use BinaryOperator::*;
+
+    match (operator, left, right) {
+        (Add | Subtract | Multiply | Divide | And | Or, left, right) => apply_binary(operator, left, right),
+        (_, Value::String(left), Value::String(right)) => {
+            let key = |text: String| Value::String(collation.key(&text).into_owned());
+            apply_binary(operator, key(left), key(right))
+        }
+        (_, left, right) => apply_binary(operator, left, right),
+    }
+}
+
+/// Applies a comparison operator without taking ownership of the operands.
+pub(crate) fn compare(operator: &BinaryOperator, left: &Value, right: &Value) -> Result<Value, EvalError> {
+    use BinaryOperator::*;
+
+    let ordering = match (left, right) {
+        (Value::Null, _) | (_, Value::Null) => return Ok(Value::Null),
+        (Value::Int(a), Value::Int(b)) => a.cmp(b),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => match datetime::compare(left, right).or_else(|| decimal::compare(left, right)) {
+            Some(ordering) => ordering?,
+            None => return Err(EvalError::TypeMismatch(format!("cannot compare {:?} and {:?}", left, right))),
+        },
+    };
+    Ok(Value::Bool(match operator {
+        Equals => ordering.is_eq(),
+        NotEquals => ordering.is_ne(),
+        GreaterThan => ordering.is_gt(),
+        GreaterThanOrEqual => ordering.is_ge(),
+        LessThan => ordering.is_lt(),
+        LessThanOrEqual => ordering.is_le(),
+        other => return Err(EvalError::TypeMismatch(format!("{:?} is not a comparison", other))),
+    }))
+}
+
+/// LIKE matching: `%` matches any run of characters and `_` exactly one.
+pub(crate) fn like(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    // Greedy matching with backtracking to the most recent `%`
+    let (mut t, mut p) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('%') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '_' || c == text[t] => {
+                t += 1;
+                p += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '%')
+}
+
+fn arithmetic(operator: &BinaryOperator, a: i64, b: i64) -> Result<i64, EvalError> {
+    let result = match operator {
+        BinaryOperator::Add => a.checked_add(b),
+        BinaryOperator::Subtract => a.checked_sub(b),
+        BinaryOperator::Multiply => a.checked_mul(b),
+        _ if b == 0 => return Err(EvalError::DivisionByZero),
+        _ => a.checked_div(b),
+    };
+    result.ok_or(EvalError::Overflow)
+}
+
+
+// === CAST ===
+
+/// `CAST(value AS data_type)`; NULL casts to every type. A value of a type
+/// that never casts to `data_type`, such as a date to INT, is a type
+/// mismatch, and one whose type may but that isn't one of `data_type`, such
+/// as `'abc'` to INT, an invalid cast.
+pub(crate) fn cast(value: &Value, data_type: &DBType) -> Result<Value, EvalError> {
+    let invalid = || EvalError::InvalidCast(format!("{:?} to {}", value, data_type));
+    let cast = match (value, data_type) {
+        (Value::Null, _) => Value::Null,
+        (Value::Int(n), DBType::Int) => Value::Int(*n),
+        (Value::Bool(b), DBType::Int) => Value::Int(i64::from(*b)),
+        (Value::Decimal(d), DBType::Int) => {
+            let whole = d.rescale(0).ok_or(EvalError::Overflow)?;
+            Value::Int(i64::try_from(whole.mantissa()).map_err(|_| EvalError::Overflow)?)
+        }
+        (Value::String(text), DBType::Int) => {
+            let text = text.trim();
+            match text.parse() {
+                Ok(n) => Value::Int(n),
+                // All digits, so only too many of them
+                Err(_) if is_integer(text) => return Err(EvalError::Overflow),
+                Err(_) => return Err(invalid()),
+            }
+        }
+        (Value::Bool(b), DBType::Bool) => Value::Bool(*b),
+        (Value::Int(n), DBType::Bool) => Value::Bool(*n != 0),
+        (Value::String(text), DBType::Bool) => match text.trim().to_ascii_lowercase().as_str() {
+            "true" | "t" | "yes" | "y" | "on" | "1" => Value::Bool(true),
+            "false" | "f" | "no" | "n" | "off" | "0" => Value::Bool(false),
+            _ => return Err(invalid()),
+        },
+        (value, DBType::Varchar(length)) => Value::String(text(value).chars().take(*length).collect()),
+        (Value::Int(_) | Value::Decimal(_) | Value::String(_), DBType::Decimal(precision, scale)) => {
+            let decimal = match value {
+                Value::Int(n) => Decimal::from(*n),
+                Value::Decimal(d) => *d,
+                _ => number(value).ok_or_else(invalid)?,
+            };
+            Value::Decimal(decimal.fit(*precision, *scale).ok_or(EvalError::Overflow)?)
+        }
+        (Value::String(text), DBType::Date) => Value::Date(Date::parse(text.trim()).ok_or_else(invalid)?),
+        (Value::String(text), DBType::Time) => Value::Time(Time::parse(text.trim()).ok_or_else(invalid)?),
+        (Value::String(text), DBType::Timestamp) => {
+            Value::Timestamp(Timestamp::parse(text.trim()).ok_or_else(invalid)?)
+        }
+        (Value::Date(date), DBType::Date) => Value::Date(*date),
+        (Value::Timestamp(timestamp), DBType::Date) => Value::Date(timestamp.date()),
+        (Value::Time(time), DBType::Time) => Value::Time(*time),
+        (Value::Timestamp(timestamp), DBType::Time) => Value::Time(timestamp.time()),
+        (Value::Timestamp(timestamp), DBType::Timestamp) => Value::Timestamp(*timestamp),
+        (Value::Date(date), DBType::Timestamp) => Value::Timestamp(Timestamp::from(*date)),
+        (value, data_type) => return Err(EvalError::TypeMismatch(format!("cannot cast {:?} to {}", value, data_type))),
+    };
+    Ok(cast)
+}
+
+/// `cast` with its errors handled as `errors` says.
+pub(crate) fn cast_under(value: &Value, data_type: &DBType, errors: &ErrorHandling) -> Result<Value, EvalError> {
+    cast(value, data_type).or_else(|error| errors.recover(error, || saturate_cast(value, data_type)))
+}
+
+/// The text a value casts to VARCHAR as.
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Int(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Date(date) => date.to_string(),
+        Value::Time(time) => time.to_string(),
+        Value::Timestamp(timestamp) => timestamp.to_string(),
+        Value::Decimal(d) => d.to_string(),
+    }
+}
+
+fn is_integer(text: &str) -> bool {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// A string read as a decimal, ignoring the spaces around it.
+fn number(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::String(text) => Decimal::parse(text.trim()),
+        _ => None,
+    }
+}
+
+// === Saturation ===
+//
+// What an overflow gives when it saturates, and a division by zero or an
+// invalid cast too: the largest or the smallest value of the result's type.
+
+/// -1, 0 or 1 by the sign of a number, and 0 for anything else.
+fn signum(value: &Value) -> i32 {
+    match value {
+        Value::Int(n) => n.signum() as i32,
+        Value::Decimal(d) => d.mantissa().signum() as i32,
+        _ => 0,
+    }
+}
+
+/// The largest value of the type of `left operator right` when `sign` is
+/// positive and the smallest when it's negative; NULL when it's 0, as for
+/// `0 / 0`, or the type has no extremes.
+fn extreme(left: &Value, right: &Value, sign: i32) -> Value {
+    let decimal = matches!(left, Value::Decimal(_)) || matches!(right, Value::Decimal(_));
+    match (sign.signum(), left, right) {
+        (0, _, _) => Value::Null,
+        (1, _, _) if decimal => Value::Decimal(Decimal::MAX),
+        (_, _, _) if decimal => Value::Decimal(Decimal::MAX.negate()),
+        (1, Value::Int(_), Value::Int(_)) => Value::Int(i64::MAX),
+        (_, Value::Int(_), Value::Int(_)) => Value::Int(i64::MIN),
+        _ => Value::Null,
+    }
+}
+
+/// What `left operator right` saturates to, by the sign its exact result
+/// would have; a quotient by zero takes that of the dividend.
+fn saturate(operator: &BinaryOperator, left: &Value, right: &Value) -> Value {
+    let (a, b) = (signum(left), signum(right));
+    let sign = match operator {
+        // Only operands of the same sign overflow a sum
+        BinaryOperator::Add if a == 0 => b,
+        BinaryOperator::Subtract if a == 0 => -b,
+        BinaryOperator::Add | BinaryOperator::Subtract => a,
+        BinaryOperator::Divide if b == 0 => a,
+        _ => a * b,
+    };
+    extreme(left, right, sign)
+}
+
+/// What a CAST that failed gives when it saturates. A string reads as the
+/// number its first characters make, or 0, as MySQL reads it; that number
+/// and one too large for an INT or a DECIMAL column give the value of the
+/// type nearest to them. Other types have no such value, and give NULL.
+fn saturate_cast(value: &Value, data_type: &DBType) -> Value {
+    let number = match value {
+        Value::String(text) => leading_number(text),
+        Value::Int(n) => Decimal::from(*n),
+        Value::Decimal(d) => *d,
+        _ => return Value::Null,
+    };
+    let sign = number.mantissa().signum() as i32;
+    match data_type {
+        DBType::Int => match number.rescale(0).and_then(|whole| i64::try_from(whole.mantissa()).ok()) {
+            Some(n) => Value::Int(n),
+            None => extreme(&Value::Int(0), &Value::Int(0), sign),
+        },
+        DBType::Decimal(precision, scale) => match number.fit(*precision, *scale) {
+            Some(fitted) => Value::Decimal(fitted),
+            None => Value::Decimal(Decimal::largest(*precision, *scale, sign < 0)),
+        },
+        _ => Value::Null,
+    }
+}
+
+/// The number at the start of `text`, after any spaces: 0 for `'abc'`, 12
+/// for `'12abc'` and -1.5 for `' -1.5.2'`.
+fn leading_number(text: &str) -> Decimal {
+    let text = text.trim_start();
+    let digits = |from: usize| from + text[from..].bytes().take_while(u8::is_ascii_digit).count();
+    let whole = digits(usize::from(text.starts_with(['-', '+'])));
+    let end = if text[whole..].starts_with('.') { digits(whole + 1) } else { whole };
+    let negative = text.starts_with('-');
+    match Decimal::parse(&text[..end]) {
+        Some(number) => number,
+        // Too many digits, or none
+        None if text[..end].bytes().any(|b| b.is_ascii_digit()) => {
+            if negative { Decimal::MAX.negate() } else { Decimal::MAX }
+        }
+        None => Decimal::default(),
+    }
+}
@@ -0,0 +1,162 @@
+// extension.rs — statements added by downstream grammars
+// Author: Sreerag Devadasan
+//
+// A crate that needs statements of its own (`ANALYZE MYEXT t`, a vendor's
+// `VACUUM`) registers a handler for their leading keyword in an
+// `Extensions` set and parses with it, through `ParserSession::
+// with_extensions` or `SQLParser::with_extensions`. When a statement
+// starts with a registered word the handler gets the parser, positioned on
+// that word, and reads the tokens with the parser's own `eat`, `expect_*`
+// and `parse_expression` steps. It returns its statement, which the AST
+// carries as `Statement::Custom`, or `None` to leave the tokens to the
+// builtin grammar, which then parses them from the start as if the
+// handler had never run.
+//
+// The statement itself is any type implementing `CustomStatement`. Its
+// value is kept behind a trait object in an `Extension`, which clones,
+// compares and prints through it, and `downcast_ref` gets it back.
+
+use core::any::Any;
+use core::fmt;
+
+use alloc::sync::Arc;
+
+use crate::prelude::*;
+use crate::dialect::Dialect;
+use crate::parser::SQLParser;
+use crate::tokenizer::{ParseError, Token};
+
+/// A statement type defined outside the crate.
+pub trait CustomStatement: fmt::Debug + Clone + PartialEq + Send + Sync + 'static {
+    /// The name `Statement::kind` reports, such as `ANALYZE`.
+    fn kind(&self) -> &'static str;
+
+    /// The statement as SQL of `dialect`, without a trailing semicolon.
+    fn to_sql(&self, dialect: Dialect) -> String;
+}
+
+/// The object-safe part of `CustomStatement`, for the trait object.
+trait Node: fmt::Debug + Send + Sync {
+    fn kind(&self) -> &'static str;
+    fn to_sql(&self, dialect: Dialect) -> String;
+    fn clone_node(&self) -> Box<dyn Node>;
+    fn eq_node(&self, other: &dyn Node) -> bool;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: CustomStatement> Node for T {
+    fn kind(&self) -> &'static str {
+        CustomStatement::kind(self)
+    }
+
+    fn to_sql(&self, dialect: Dialect) -> String {
+        CustomStatement::to_sql(self, dialect)
+    }
+
+    fn clone_node(&self) -> Box<dyn Node> {
+        Box::new(self.clone())
+    }
+
+    fn eq_node(&self, other: &dyn Node) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A custom statement as the AST holds it. Two are equal when they hold
+/// equal values of the same type.
+pub struct Extension(Box<dyn Node>);
+
+impl Extension {
+    pub fn new(statement: impl CustomStatement) -> Self {
+        Extension(Box::new(statement))
+    }
+
+    /// The statement, if it is a `T`.
+    pub fn downcast_ref<T: CustomStatement>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref()
+    }
+
+    pub fn kind(&self) -> &'static str {
+        self.0.kind()
+    }
+
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        self.0.to_sql(dialect)
+    }
+}
+
+impl Clone for Extension {
+    fn clone(&self) -> Self {
+        Extension(self.0.clone_node())
+    }
+}
+
+impl PartialEq for Extension {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_node(other.0.as_ref())
+    }
+}
+
+/// The statement's own `Debug`, so ASTs read as if it were built in.
+impl fmt::Debug for Extension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+/// Parses a custom statement from the parser's position, or returns
+/// `Ok(None)` to hand the tokens to the builtin grammar.
+pub type Handler = dyn Fn(&mut SQLParser<'_>) -> Result<Option<Extension>, ParseError> + Send + Sync;
+
+/// Statement handlers by leading keyword.
+#[derive(Clone, Default)]
+pub struct Extensions {
+    handlers: Vec<(String, Arc<Handler>)>,
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls `handler` for statements starting with `keyword`, matched
+    /// case-insensitively, whether it's a word of its own or one of the
+    /// grammar's keywords. Handlers for the same keyword are tried in the
+    /// order they were registered, until one returns a statement.
+    pub fn register(
+        &mut self,
+        keyword: &str,
+        handler: impl Fn(&mut SQLParser<'_>) -> Result<Option<Extension>, ParseError> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.handlers.push((keyword.to_uppercase(), Arc::new(handler)));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// The handlers for statements starting with `token`.
+    pub(crate) fn handlers<'e>(&'e self, token: Option<&Token>) -> impl Iterator<Item = &'e Handler> + 'e {
+        let word = match token {
+            Some(Token::Identifier(name)) if !name.is_quoted() => Some(name.to_uppercase()),
+            Some(Token::Keyword(keyword)) => Some(keyword.as_str().to_string()),
+            _ => None,
+        };
+        self.handlers
+            .iter()
+            .filter(move |(keyword, _)| word.as_deref() == Some(keyword.as_str()))
+            .map(|(_, handler)| handler.as_ref())
+    }
+}
+
+/// Lists the registered keywords; handlers have no `Debug`.
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.handlers.iter().map(|(keyword, _)| keyword)).finish()
+    }
+}
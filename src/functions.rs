@@ -0,0 +1,193 @@
+// functions.rs — SQL functions written in Rust, for the engine to call
+// Author: Sreerag Devadasan
+//
+// The evaluator knows operators but no functions, so the engine's SQL can
+// be extended without forking it: `Database::register_scalar` takes a
+// closure from a call's argument values to its result, and
+// `register_aggregate` a starting value and a closure that folds each
+// row's arguments into it. Each comes with the types of its parameters and
+// of its result. A call is checked against them before the closure runs
+// (the number of arguments, and the type of each argument that isn't
+// NULL), and `Database::registry` declares them to the analyzer, so
+// `analyze` checks queries against the functions the engine really has.
+//
+//   db.register_scalar("reverse", &[SqlType::Text], SqlType::Text, |args| match &args[0] {
+//       Value::String(s) => Ok(Value::String(s.chars().rev().collect())),
+//       other => Ok(other.clone()),
+//   });
+//   db.execute_sql("SELECT name FROM users WHERE reverse(name) = 'nna'")?;
+//
+// Scalar functions can be called wherever the engine evaluates
+// expressions: select lists, WHERE clauses, INSERT and VALUES rows. The
+// engine has no GROUP BY and computes a select list one row at a time, so
+// aggregates are run over a table's column with `Database::aggregate` for
+// now.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::fmt;
+
+use crate::prelude::*;
+use crate::analyzer::{Registry, SqlType};
+use crate::eval::{builtin, EvalError, Value};
+
+/// A scalar function: the arguments of a call to its result.
+pub type ScalarFn = dyn Fn(&[Value]) -> Result<Value, EvalError> + Send + Sync;
+
+/// An aggregate's step: the value so far and a row's arguments to the
+/// next value.
+pub type AggregateFn = dyn Fn(Value, &[Value]) -> Result<Value, EvalError> + Send + Sync;
+
+#[derive(Clone)]
+enum Body {
+    Scalar(Arc<ScalarFn>),
+    Aggregate { initial: Value, step: Arc<AggregateFn> },
+}
+
+#[derive(Clone)]
+struct Function {
+    params: Vec<SqlType>,
+    returns: SqlType,
+    body: Body,
+}
+
+/// The functions registered with a database, by name without regard to
+/// case; registering a name again replaces its function.
+#[derive(Clone, Default)]
+pub struct Functions {
+    functions: BTreeMap<String, Function>,
+}
+
+impl fmt::Debug for Functions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.functions.keys()).finish()
+    }
+}
+
+/// The same names with the same signatures and the same closures.
+impl PartialEq for Functions {
+    fn eq(&self, other: &Self) -> bool {
+        let same = |(a_name, a): (&String, &Function), (b_name, b): (&String, &Function)| {
+            let body = match (&a.body, &b.body) {
+                (Body::Scalar(a), Body::Scalar(b)) => Arc::ptr_eq(a, b),
+                (Body::Aggregate { initial: a, step: a_step }, Body::Aggregate { initial: b, step: b_step }) => {
+                    a == b && Arc::ptr_eq(a_step, b_step)
+                }
+                _ => false,
+            };
+            a_name == b_name && a.params == b.params && a.returns == b.returns && body
+        };
+        self.functions.len() == other.functions.len()
+            && self.functions.iter().zip(&other.functions).all(|(a, b)| same(a, b))
+    }
+}
+
+impl Functions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn scalar(
+        &mut self,
+        name: &str,
+        params: &[SqlType],
+        returns: SqlType,
+        function: impl Fn(&[Value]) -> Result<Value, EvalError> + Send + Sync + 'static,
+    ) -> &mut Self {
+        let body = Body::Scalar(Arc::new(function));
+        self.functions.insert(name.to_lowercase(), Function { params: params.to_vec(), returns, body });
+        self
+    }
+
+    /// An aggregate of one or more arguments per row, starting from
+    /// `initial`; NULL arguments are passed to `step` like any other.
+    pub fn aggregate(
+        &mut self,
+        name: &str,
+        params: &[SqlType],
+        returns: SqlType,
+        initial: Value,
+        step: impl Fn(Value, &[Value]) -> Result<Value, EvalError> + Send + Sync + 'static,
+    ) -> &mut Self {
+        let body = Body::Aggregate { initial, step: Arc::new(step) };
+        self.functions.insert(name.to_lowercase(), Function { params: params.to_vec(), returns, body });
+        self
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.contains_key(&name.to_lowercase())
+    }
+
+    /// Calls the scalar function `name`, or the builtin of that name when
+    /// none is registered.
+    pub fn call(&self, name: &str, args: &[Value]) -> Result<Value, EvalError> {
+        if !self.contains(name) {
+            return builtin(name, args);
+        }
+        let function = self.get(name, args)?;
+        match &function.body {
+            Body::Scalar(scalar) => scalar(args),
+            Body::Aggregate { .. } => {
+                Err(EvalError::TypeMismatch(format!("{} is an aggregate, so it can't be called on one row", name)))
+            }
+        }
+    }
+
+    /// Folds the arguments of each row into the aggregate `name`.
+    pub fn fold<'r>(&self, name: &str, rows: impl IntoIterator<Item = &'r [Value]>) -> Result<Value, EvalError> {
+        let Some(Function { body: Body::Aggregate { initial, step }, .. }) = self.functions.get(&name.to_lowercase())
+        else {
+            return Err(EvalError::UnknownFunction(name.to_string()));
+        };
+        let mut value = initial.clone();
+        for args in rows {
+            self.get(name, args)?;
+            value = step(value, args)?;
+        }
+        Ok(value)
+    }
+
+    /// The builtin signatures, then those of these functions.
+    pub fn registry(&self) -> Registry {
+        let mut registry = Registry::new();
+        for (name, function) in &self.functions {
+            match function.body {
+                Body::Scalar(_) => registry.scalar(name, &function.params, function.returns),
+                Body::Aggregate { .. } => registry.aggregate(name, &function.params, function.returns),
+            };
+        }
+        registry
+    }
+
+    /// The function `name`, if `args` fit its parameters.
+    fn get(&self, name: &str, args: &[Value]) -> Result<&Function, EvalError> {
+        let function =
+            self.functions.get(&name.to_lowercase()).ok_or_else(|| EvalError::UnknownFunction(name.to_string()))?;
+        if args.len() != function.params.len() {
+            let message = format!("{} takes {} argument(s), but is given {}", name, function.params.len(), args.len());
+            return Err(EvalError::TypeMismatch(message));
+        }
+        for (i, (arg, &param)) in args.iter().zip(&function.params).enumerate() {
+            let found = type_of(arg);
+            if !found.fits(param) {
+                let message =
+                    format!("argument {} of {} should be {}, but is {}", i + 1, name, param.name(), found.name());
+                return Err(EvalError::TypeMismatch(message));
+            }
+        }
+        Ok(function)
+    }
+}
+
+fn type_of(value: &Value) -> SqlType {
+    match value {
+        Value::Int(_) => SqlType::Integer,
+        Value::String(_) => SqlType::Text,
+        Value::Bool(_) => SqlType::Boolean,
+        Value::Date(_) => SqlType::Date,
+        Value::Time(_) => SqlType::Time,
+        Value::Timestamp(_) => SqlType::Timestamp,
+        Value::Decimal(_) => SqlType::Decimal,
+        Value::Null => SqlType::Any,
+    }
+}
@@ -0,0 +1,183 @@
+// graph.rs — the AST of a statement as a Graphviz DOT or Mermaid diagram
+// Author: Sreerag Devadasan
+//
+// `Statement::to_dot` and `to_mermaid` draw a statement as a tree of
+// boxes, for documentation and slides. The tree is read off the code
+// `codegen` generates, so every node the AST has is covered: a struct or
+// enum variant becomes a box titled by its name, its scalar fields (names,
+// numbers, operators, flags) are listed inside the box, and each field
+// holding another node becomes an edge labelled with the field's name, with
+// an index for list items (`joins[0]`). Empty fields (None, false, empty
+// lists, defaults) are left out, and `Box` and `Some` are looked through.
+
+use core::fmt::Write as _;
+
+use crate::prelude::*;
+use crate::ast::Statement;
+use crate::codegen::{statement_code, Code};
+
+impl Statement {
+    /// The AST as a Graphviz `digraph`, for `dot -Tsvg`.
+    pub fn to_dot(&self) -> String {
+        let graph = Graph::of(self);
+        let mut out = String::from("digraph ast {\n    node [shape=box, fontname=\"monospace\"];\n");
+        for (id, node) in graph.nodes.iter().enumerate() {
+            let label: Vec<String> = node.lines().iter().map(|line| escape_dot(line)).collect();
+            let _ = writeln!(out, "    n{} [label=\"{}\"];", id, label.join("\\n"));
+        }
+        for (from, to, label) in &graph.edges {
+            let _ = writeln!(out, "    n{} -> n{} [label=\"{}\"];", from, to, escape_dot(label));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// The AST as a Mermaid flowchart, for Markdown that renders Mermaid.
+    pub fn to_mermaid(&self) -> String {
+        let graph = Graph::of(self);
+        let mut out = String::from("flowchart TD\n");
+        for (id, node) in graph.nodes.iter().enumerate() {
+            let label: Vec<String> = node.lines().iter().map(|line| escape_mermaid(line)).collect();
+            let _ = writeln!(out, "    n{}[\"{}\"]", id, label.join("<br/>"));
+        }
+        for (from, to, label) in &graph.edges {
+            let _ = writeln!(out, "    n{} -->|\"{}\"| n{}", from, escape_mermaid(label), to);
+        }
+        out
+    }
+}
+
+/// Boxes and the labelled edges between them by index, both in the order
+/// they were reached, depth first.
+#[derive(Debug, Default)]
+pub(crate) struct Graph {
+    pub(crate) nodes: Vec<Node>,
+    pub(crate) edges: Vec<(usize, usize, String)>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Node {
+    pub(crate) title: String,
+    /// `field: value` lines, or bare values for the fields of a tuple
+    /// variant.
+    pub(crate) details: Vec<String>,
+}
+
+impl Node {
+    fn lines(&self) -> Vec<&str> {
+        core::iter::once(self.title.as_str()).chain(self.details.iter().map(String::as_str)).collect()
+    }
+}
+
+impl Graph {
+    pub(crate) fn of(statement: &Statement) -> Self {
+        let mut graph = Graph::default();
+        graph.node(&statement_code(statement));
+        graph
+    }
+
+    /// Adds the box for `code` and everything under it, returning its index.
+    fn node(&mut self, code: &Code) -> usize {
+        let id = self.nodes.len();
+        match code {
+            Code::Seq { head: "Some" | "Box::new", items, .. } if items.len() == 1 => return self.node(&items[0]),
+            Code::Struct { path, fields } => {
+                self.nodes.push(Node { title: short_name(path).to_string(), details: Vec::new() });
+                for (name, value) in fields {
+                    self.field(id, name, value);
+                }
+            }
+            Code::Seq { head, items, .. } => {
+                self.nodes.push(Node { title: short_name(head).to_string(), details: Vec::new() });
+                for (i, item) in items.iter().enumerate() {
+                    match scalar(item) {
+                        Some(value) => self.nodes[id].details.push(value),
+                        None => self.children(id, &i.to_string(), item),
+                    }
+                }
+            }
+            Code::Atom(text) => self.nodes.push(Node { title: clean(text), details: Vec::new() }),
+        }
+        id
+    }
+
+    /// Adds the field `name` of the box `parent`: inside it when scalar,
+    /// as an edge otherwise.
+    fn field(&mut self, parent: usize, name: &str, value: &Code) {
+        if is_empty(value) {
+            return;
+        }
+        match scalar(value) {
+            Some(value) => self.nodes[parent].details.push(format!("{}: {}", name, value)),
+            None => self.children(parent, name, value),
+        }
+    }
+
+    /// Links `parent` to the box for `value`, or to one box per item when
+    /// it's a list.
+    fn children(&mut self, parent: usize, label: &str, value: &Code) {
+        match value {
+            Code::Seq { head: "Some" | "Box::new", items, .. } if items.len() == 1 => {
+                self.children(parent, label, &items[0]);
+            }
+            Code::Seq { head: "vec!", items, .. } => {
+                for (i, item) in items.iter().enumerate() {
+                    self.children(parent, &format!("{}[{}]", label, i), item);
+                }
+            }
+            _ => {
+                // The child's box is the next one added, and its edge goes
+                // before the edges under it
+                self.edges.push((parent, self.nodes.len(), label.to_string()));
+                self.node(value);
+            }
+        }
+    }
+}
+
+/// A leaf value as shown in a box, or None for a node of its own. A list
+/// of leaves is one value, and unit variants such as `Expression::Null`
+/// are leaves too, as are names: `"a"`, or `"a" (Double)` when quoted.
+fn scalar(code: &Code) -> Option<String> {
+    match code {
+        Code::Atom(text) => Some(clean(text)),
+        Code::Seq { head: "Some" | "Box::new" | "Ident::new", items, .. } if items.len() == 1 => scalar(&items[0]),
+        Code::Seq { head: "Ident::quoted", items, .. } if items.len() == 2 => {
+            Some(format!("{} ({})", scalar(&items[0])?, scalar(&items[1])?))
+        }
+        Code::Seq { head: "vec!", items, .. } => {
+            let values: Option<Vec<String>> = items.iter().map(scalar).collect();
+            values.map(|values| format!("[{}]", values.join(", ")))
+        }
+        _ => None,
+    }
+}
+
+fn is_empty(code: &Code) -> bool {
+    matches!(code, Code::Atom(text) if text == "None" || text == "false" || text == "Vec::new()" || text.ends_with("::default()"))
+}
+
+/// `Statement::Select` to `Select`.
+fn short_name(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+/// An atom of generated code as it reads in a box: `"a".to_string()` to
+/// `"a"`, `BinaryOperator::Add` to `Add`.
+fn clean(text: &str) -> String {
+    if let Some(string) = text.strip_suffix(".to_string()") {
+        string.to_string()
+    } else if text.starts_with('"') {
+        text.to_string()
+    } else {
+        short_name(text).to_string()
+    }
+}
+
+pub(crate) fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('#', "#35;").replace('"', "#quot;").replace('<', "#lt;").replace('>', "#gt;")
+}
@@ -0,0 +1,848 @@
+// hash.rs — deterministic, version-tagged hashing of the AST
+// Author: Sreerag Devadasan
+//
+// The hash only depends on the meaning of the tree, not on how the query
+// was written: whitespace, keyword case, quote style and redundant
+// parentheses don't change it. It's stable across runs, platforms and Rust
+// versions, so it can be used as a persistent cache key. Hash
+// `canonical::canonicalize(stmt)` instead to also ignore operand order.
+//
+// Bump AST_HASH_VERSION whenever the encoding below changes, so stale cache
+// entries are never matched by accident.
+
+use core::fmt;
+
+use crate::prelude::*;
+use crate::anonymize::{anonymize, AnonymizeMode};
+use crate::ast::{
+    BinaryOperator, Constraint, CopyTarget, DBType, DuplicateHandling, Expression, GrantObject, GrantedPrivilege,
+    GroupingElement, JoinKind, MergeAction, MergeSource,
+    OrderByExpr, RoleOption, RoutineType, SetScope, Statement, TableFactor, TransactionCommand, TriggerBody,
+    TriggerEvent, TriggerTiming, UnaryOperator,
+};
+use crate::dialect::Dialect;
+
+/// Version of the hash encoding; part of every `AstHash`.
+pub const AST_HASH_VERSION: u32 = 13;
+
+/// A stable hash of a statement, tagged with the encoding version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AstHash {
+    pub version: u32,
+    pub value: u64,
+}
+
+impl fmt::Display for AstHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}:{:016x}", self.version, self.value)
+    }
+}
+
+pub fn hash_statement(statement: &Statement) -> AstHash {
+    let mut hasher = StableHasher::new();
+    hasher.statement(statement);
+    AstHash { version: AST_HASH_VERSION, value: hasher.finish() }
+}
+
+/// The hash of the statement with its literals replaced by placeholders:
+/// queries that differ only in their values share a fingerprint.
+pub fn fingerprint(statement: &Statement) -> AstHash {
+    hash_statement(&anonymize(statement, AnonymizeMode::Placeholders))
+}
+
+pub fn hash_expression(expr: &Expression) -> AstHash {
+    let mut hasher = StableHasher::new();
+    hasher.expression(expr);
+    AstHash { version: AST_HASH_VERSION, value: hasher.finish() }
+}
+
+/// 64-bit FNV-1a. Used instead of std's `DefaultHasher`, whose output may
+/// change between Rust releases.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hasher = StableHasher::new();
+    hasher.bytes(bytes);
+    hasher.finish()
+}
+
+/// Feeds a tagged, length-prefixed encoding of the tree into FNV-1a, so that
+/// different trees can't produce the same byte stream.
+struct StableHasher {
+    state: u64,
+}
+
+impl StableHasher {
+    fn new() -> Self {
+        Self { state: 0xcbf2_9ce4_8422_2325 }
+    }
+
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state = (self.state ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn tag(&mut self, tag: u8) {
+        self.bytes(&[tag]);
+    }
+
+    fn number(&mut self, n: u64) {
+        self.bytes(&n.to_le_bytes());
+    }
+
+    fn string(&mut self, s: &str) {
+        self.number(s.len() as u64);
+        self.bytes(s.as_bytes());
+    }
+
+    /// Identifiers go in by their text alone: how they were quoted is how
+    /// they were written.
+    fn strings(&mut self, list: &[impl AsRef<str>]) {
+        self.number(list.len() as u64);
+        for s in list {
+            self.string(s.as_ref());
+        }
+    }
+
+    fn optional_number(&mut self, n: Option<u64>) {
+        match n {
+            Some(n) => {
+                self.tag(1);
+                self.number(n);
+            }
+            None => self.tag(0),
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            // Hints steer the plan, not the result, so they aren't hashed
+            Statement::Select {
+                hints: _,
+                columns,
+                wildcard,
+                from,
+                joins,
+                selection,
+                group_by,
+                qualify,
+                order_by,
+                limit,
+                offset,
+                locking,
+            } => {
+                self.tag(b'S');
+                self.number(columns.len() as u64);
+                for item in columns {
+                    self.expression(&item.expr);
+                    self.optional_string(item.alias.as_deref());
+                }
+                self.strings(&wildcard.exclude);
+                self.number(wildcard.replace.len() as u64);
+                for (expr, column) in &wildcard.replace {
+                    self.expression(expr);
+                    self.string(column);
+                }
+                self.table_factor(from);
+                self.number(joins.len() as u64);
+                for join in joins {
+                    self.tag(match join.kind {
+                        JoinKind::Inner => b'i',
+                        JoinKind::Left => b'l',
+                        JoinKind::Right => b'r',
+                        JoinKind::Full => b'f',
+                        JoinKind::Cross => b'x',
+                    });
+                    self.table_factor(&join.relation);
+                    self.optional_expression(join.on.as_ref());
+                }
+                match selection {
+                    Some(expr) => {
+                        self.tag(1);
+                        self.expression(expr);
+                    }
+                    None => self.tag(0),
+                }
+                self.number(group_by.len() as u64);
+                for element in group_by {
+                    match element {
+                        GroupingElement::Expression(expr) => {
+                            self.tag(b'e');
+                            self.expression(expr);
+                        }
+                        GroupingElement::Rollup(exprs) => {
+                            self.tag(b'r');
+                            self.rows(core::slice::from_ref(exprs));
+                        }
+                        GroupingElement::Cube(exprs) => {
+                            self.tag(b'c');
+                            self.rows(core::slice::from_ref(exprs));
+                        }
+                        GroupingElement::GroupingSets(sets) => {
+                            self.tag(b's');
+                            self.rows(sets);
+                        }
+                    }
+                }
+                self.optional_expression(qualify.as_ref());
+                self.order_by(order_by);
+                self.optional_number(*limit);
+                self.optional_number(*offset);
+                self.number(locking.len() as u64);
+                for clause in locking {
+                    self.tag(clause.strength as u8);
+                    self.strings(&clause.of);
+                    self.tag(clause.wait.map_or(0, |wait| wait as u8 + 1));
+                }
+            }
+            Statement::CreateTable { table_name, column_list } => {
+                self.tag(b'C');
+                self.string(table_name);
+                self.number(column_list.len() as u64);
+                for column in column_list {
+                    self.string(&column.column_name);
+                    self.column_type(&column.column_type);
+                    self.number(column.constraints.len() as u64);
+                    for constraint in &column.constraints {
+                        match constraint {
+                            Constraint::NotNull => self.tag(b'n'),
+                            Constraint::PrimaryKey => self.tag(b'p'),
+                            Constraint::Check(expr) => {
+                                self.tag(b'c');
+                                self.expression(expr);
+                            }
+                            Constraint::Collate(name) => {
+                                self.tag(b'l');
+                                self.string(name);
+                            }
+                        }
+                    }
+                }
+            }
+            Statement::DropTable { table_name, if_exists } => {
+                self.tag(b'D');
+                self.string(table_name);
+                self.tag(*if_exists as u8);
+            }
+            Statement::Insert { table_name, columns, rows } => {
+                self.tag(b'I');
+                self.string(table_name);
+                match columns {
+                    Some(list) => {
+                        self.tag(1);
+                        self.strings(list);
+                    }
+                    None => self.tag(0),
+                }
+                self.rows(rows);
+            }
+            Statement::CreateView { or_replace, name, columns, query } => {
+                self.tag(b'w');
+                self.tag(*or_replace as u8);
+                self.string(name);
+                self.strings(columns);
+                self.statement(query);
+            }
+            Statement::InsertSelect { table_name, columns, query } => {
+                self.tag(b'J');
+                self.string(table_name);
+                match columns {
+                    Some(list) => {
+                        self.tag(1);
+                        self.strings(list);
+                    }
+                    None => self.tag(0),
+                }
+                self.statement(query);
+            }
+            Statement::Update { table_name, alias, assignments, selection } => {
+                self.tag(b'u');
+                self.string(table_name);
+                self.optional_string(alias.as_deref());
+                self.number(assignments.len() as u64);
+                for (column, value) in assignments {
+                    self.string(column);
+                    self.expression(value);
+                }
+                self.optional_expression(selection.as_ref());
+            }
+            Statement::Delete { table_name, alias, selection } => {
+                self.tag(b'd');
+                self.string(table_name);
+                self.optional_string(alias.as_deref());
+                self.optional_expression(selection.as_ref());
+            }
+            Statement::SetOperation { operator, all, left, right } => {
+                self.tag(b'O');
+                self.tag(*operator as u8);
+                self.tag(*all as u8);
+                self.statement(left);
+                self.statement(right);
+            }
+            Statement::With { recursive, ctes, body } => {
+                self.tag(b'T');
+                self.tag(*recursive as u8);
+                self.number(ctes.len() as u64);
+                for cte in ctes {
+                    self.string(&cte.name);
+                    self.strings(&cte.columns);
+                    self.statement(&cte.query);
+                }
+                self.statement(body);
+            }
+            Statement::Values { rows } => {
+                self.tag(b'W');
+                self.rows(rows);
+            }
+            Statement::Grant { privileges, object, grantees, with_grant_option } => {
+                self.tag(b'G');
+                self.privileges(privileges);
+                self.grant_object(object);
+                self.strings(grantees);
+                self.tag(*with_grant_option as u8);
+            }
+            Statement::Revoke { privileges, object, grantees } => {
+                self.tag(b'R');
+                self.privileges(privileges);
+                self.grant_object(object);
+                self.strings(grantees);
+            }
+            Statement::GrantRole { roles, grantees, with_admin_option } => {
+                self.tag(b'h');
+                self.strings(roles);
+                self.strings(grantees);
+                self.tag(*with_admin_option as u8);
+            }
+            Statement::RevokeRole { roles, grantees } => {
+                self.tag(b'k');
+                self.strings(roles);
+                self.strings(grantees);
+            }
+            Statement::CreateFunction { or_replace, procedure, name, parameters, returns, language, body } => {
+                self.tag(b'F');
+                self.tag(*or_replace as u8);
+                self.tag(*procedure as u8);
+                self.string(name);
+                self.number(parameters.len() as u64);
+                for parameter in parameters {
+                    self.optional_string(parameter.name.as_deref());
+                    self.routine_type(&parameter.data_type);
+                }
+                match returns {
+                    Some(returns) => {
+                        self.tag(1);
+                        self.routine_type(returns);
+                    }
+                    None => self.tag(0),
+                }
+                self.optional_string(language.as_deref());
+                self.string(body);
+            }
+            Statement::CreateRole { role_name, options } => {
+                self.tag(b'r');
+                self.string(role_name);
+                self.role_options(options);
+            }
+            Statement::CreateTrigger {
+                name,
+                timing,
+                events,
+                update_columns,
+                table_name,
+                for_each_row,
+                condition,
+                body,
+            } => {
+                self.tag(b'g');
+                self.string(name);
+                self.tag(match timing {
+                    TriggerTiming::Before => 0,
+                    TriggerTiming::After => 1,
+                    TriggerTiming::InsteadOf => 2,
+                });
+                self.number(events.len() as u64);
+                for event in events {
+                    self.tag(match event {
+                        TriggerEvent::Insert => 0,
+                        TriggerEvent::Update => 1,
+                        TriggerEvent::Delete => 2,
+                        TriggerEvent::Truncate => 3,
+                    });
+                }
+                self.strings(update_columns);
+                self.string(table_name);
+                self.tag(*for_each_row as u8);
+                self.optional_expression(condition.as_ref());
+                match body {
+                    TriggerBody::Execute { procedure, function, args } => {
+                        self.tag(b'e');
+                        self.tag(*procedure as u8);
+                        self.string(function);
+                        self.rows(core::slice::from_ref(args));
+                    }
+                    TriggerBody::Block(text) => {
+                        self.tag(b'b');
+                        self.string(text);
+                    }
+                    TriggerBody::Statement(statement) => {
+                        self.tag(b's');
+                        self.statement(statement);
+                    }
+                }
+            }
+            Statement::AlterRole { role_name, options } => {
+                self.tag(b'a');
+                self.string(role_name);
+                self.role_options(options);
+            }
+            Statement::Merge { target, target_alias, source, source_alias, on, clauses } => {
+                self.tag(b'M');
+                self.string(target);
+                self.optional_string(target_alias.as_deref());
+                match source {
+                    MergeSource::Table(name) => {
+                        self.tag(b't');
+                        self.string(name);
+                    }
+                    MergeSource::Query(query) => {
+                        self.tag(b'q');
+                        self.statement(query);
+                    }
+                }
+                self.optional_string(source_alias.as_deref());
+                self.expression(on);
+                self.number(clauses.len() as u64);
+                for clause in clauses {
+                    self.tag(clause.matched as u8);
+                    match &clause.condition {
+                        Some(expr) => {
+                            self.tag(1);
+                            self.expression(expr);
+                        }
+                        None => self.tag(0),
+                    }
+                    match &clause.action {
+                        MergeAction::Update(assignments) => {
+                            self.tag(b'u');
+                            self.number(assignments.len() as u64);
+                            for (column, value) in assignments {
+                                self.string(column);
+                                self.expression(value);
+                            }
+                        }
+                        MergeAction::Delete => self.tag(b'd'),
+                        MergeAction::Insert { columns, values } => {
+                            self.tag(b'i');
+                            match columns {
+                                Some(list) => {
+                                    self.tag(1);
+                                    self.strings(list);
+                                }
+                                None => self.tag(0),
+                            }
+                            self.number(values.len() as u64);
+                            for value in values {
+                                self.expression(value);
+                            }
+                        }
+                    }
+                }
+            }
+            Statement::Copy { table_name, columns, from, target, options } => {
+                self.tag(b'Y');
+                self.string(table_name);
+                self.optional_strings(columns.as_deref());
+                self.tag(*from as u8);
+                match target {
+                    CopyTarget::File(path) => {
+                        self.tag(b'f');
+                        self.string(path);
+                    }
+                    CopyTarget::Stdin => self.tag(b'i'),
+                    CopyTarget::Stdout => self.tag(b'o'),
+                }
+                self.number(options.len() as u64);
+                for option in options {
+                    self.string(&option.name);
+                    match &option.value {
+                        Some(value) => {
+                            self.tag(1);
+                            self.expression(value);
+                        }
+                        None => self.tag(0),
+                    }
+                }
+            }
+            Statement::LoadData { local, file, duplicates, table_name, format, ignore_lines, columns } => {
+                self.tag(b'L');
+                self.tag(*local as u8);
+                self.string(file);
+                self.tag(match duplicates {
+                    None => 0,
+                    Some(DuplicateHandling::Replace) => 1,
+                    Some(DuplicateHandling::Ignore) => 2,
+                });
+                self.string(table_name);
+                self.optional_string(format.fields_terminated_by.as_deref());
+                self.optional_string(format.fields_enclosed_by.as_deref());
+                self.tag(format.optionally_enclosed as u8);
+                self.optional_string(format.fields_escaped_by.as_deref());
+                self.optional_string(format.lines_starting_by.as_deref());
+                self.optional_string(format.lines_terminated_by.as_deref());
+                self.optional_number(*ignore_lines);
+                self.optional_strings(columns.as_deref());
+            }
+            Statement::SetVariable { scope, name, value } => {
+                self.tag(b'V');
+                self.tag(match scope {
+                    None => 0,
+                    Some(SetScope::Session) => 1,
+                    Some(SetScope::Local) => 2,
+                    Some(SetScope::Global) => 3,
+                });
+                self.string(name);
+                self.number(value.len() as u64);
+                for expr in value {
+                    self.expression(expr);
+                }
+            }
+            Statement::Prepare { name, parameter_types, statement } => {
+                self.tag(b'P');
+                self.string(name);
+                self.number(parameter_types.len() as u64);
+                for column_type in parameter_types {
+                    self.column_type(column_type);
+                }
+                self.statement(statement);
+            }
+            Statement::Explain { analyze, statement } => {
+                self.tag(b'A');
+                self.number(u64::from(*analyze));
+                self.statement(statement);
+            }
+            Statement::Execute { name, parameters } => {
+                self.tag(b'E');
+                self.string(name);
+                self.number(parameters.len() as u64);
+                for expr in parameters {
+                    self.expression(expr);
+                }
+            }
+            Statement::Deallocate { name } => {
+                self.tag(b'X');
+                self.optional_string(name.as_deref());
+            }
+            Statement::Transaction { command } => {
+                self.tag(b'Z');
+                self.tag(match command {
+                    TransactionCommand::Begin => 0,
+                    TransactionCommand::Commit => 1,
+                    TransactionCommand::Rollback => 2,
+                });
+            }
+            // Only the text: the same statement elsewhere in a script is equal
+            Statement::Unparsed { raw_sql, .. } => {
+                self.tag(b'U');
+                self.string(raw_sql);
+            }
+            // Custom statements are opaque, so they hash as their printed SQL
+            Statement::Custom(statement) => {
+                self.tag(b'X');
+                self.string(statement.kind());
+                self.string(&statement.to_sql(Dialect::Generic));
+            }
+        }
+    }
+
+    fn routine_type(&mut self, data_type: &RoutineType) {
+        match data_type {
+            RoutineType::Known(known) => self.column_type(known),
+            RoutineType::Named(name) => {
+                self.tag(b'N');
+                self.string(name);
+            }
+        }
+    }
+
+    fn column_type(&mut self, column_type: &DBType) {
+        match column_type {
+            DBType::Int => self.tag(b'i'),
+            DBType::Bool => self.tag(b'b'),
+            DBType::Varchar(length) => {
+                self.tag(b'v');
+                self.number(*length as u64);
+            }
+            DBType::Date => self.tag(b'd'),
+            DBType::Time => self.tag(b't'),
+            DBType::Timestamp => self.tag(b's'),
+            DBType::Decimal(precision, scale) => {
+                self.tag(b'n');
+                self.number(u64::from(*precision));
+                self.number(u64::from(*scale));
+            }
+        }
+    }
+
+    fn table_factor(&mut self, factor: &TableFactor) {
+        let alias = match factor {
+            TableFactor::Table { name, alias, sample } => {
+                self.tag(b't');
+                self.string(name);
+                match sample {
+                    Some(sample) => {
+                        self.tag(1);
+                        self.string(&sample.method);
+                        self.expression(&sample.percent);
+                        self.optional_expression(sample.repeatable.as_ref());
+                    }
+                    None => self.tag(0),
+                }
+                alias
+            }
+            TableFactor::AsOf { name, version, alias } => {
+                self.tag(b'h');
+                self.string(name);
+                self.number(*version);
+                alias
+            }
+            TableFactor::Derived { lateral, subquery, alias } => {
+                self.tag(b'q');
+                self.tag(*lateral as u8);
+                self.statement(subquery);
+                alias
+            }
+            TableFactor::Function { lateral, name, args, alias } => {
+                self.tag(b'F');
+                self.tag(*lateral as u8);
+                self.string(name);
+                self.number(args.len() as u64);
+                for arg in args {
+                    self.expression(arg);
+                }
+                alias
+            }
+            TableFactor::Pivot { table, aggregate, column, values, alias } => {
+                self.tag(b'p');
+                self.table_factor(table);
+                self.expression(aggregate);
+                self.string(column);
+                self.rows(core::slice::from_ref(values));
+                alias
+            }
+            TableFactor::Unpivot { table, value, name, columns, alias } => {
+                self.tag(b'u');
+                self.table_factor(table);
+                self.string(value);
+                self.string(name);
+                self.strings(columns);
+                alias
+            }
+        };
+        match alias {
+            Some(alias) => {
+                self.tag(1);
+                self.string(&alias.name);
+                self.strings(&alias.columns);
+            }
+            None => self.tag(0),
+        }
+    }
+
+    fn rows(&mut self, rows: &[Vec<Expression>]) {
+        self.number(rows.len() as u64);
+        for row in rows {
+            self.number(row.len() as u64);
+            for value in row {
+                self.expression(value);
+            }
+        }
+    }
+
+    fn optional_strings(&mut self, list: Option<&[impl AsRef<str>]>) {
+        match list {
+            Some(list) => {
+                self.tag(1);
+                self.strings(list);
+            }
+            None => self.tag(0),
+        }
+    }
+
+    fn optional_string(&mut self, s: Option<&str>) {
+        match s {
+            Some(s) => {
+                self.tag(1);
+                self.string(s);
+            }
+            None => self.tag(0),
+        }
+    }
+
+    fn optional_expression(&mut self, expr: Option<&Expression>) {
+        match expr {
+            Some(expr) => {
+                self.tag(1);
+                self.expression(expr);
+            }
+            None => self.tag(0),
+        }
+    }
+
+    fn order_by(&mut self, items: &[OrderByExpr]) {
+        self.number(items.len() as u64);
+        for item in items {
+            self.expression(&item.expr);
+            self.tag(item.asc.map_or(0, |asc| asc as u8 + 1));
+        }
+    }
+
+    fn privileges(&mut self, privileges: &[GrantedPrivilege]) {
+        self.number(privileges.len() as u64);
+        for granted in privileges {
+            self.tag(granted.privilege as u8);
+            self.strings(&granted.columns);
+        }
+    }
+
+    fn grant_object(&mut self, object: &GrantObject) {
+        let (tag, name) = match object {
+            GrantObject::Table(name) => (0, name),
+            GrantObject::Sequence(name) => (1, name),
+            GrantObject::Schema(name) => (2, name),
+            GrantObject::Function(name) => (3, name),
+        };
+        self.tag(tag);
+        self.string(name);
+    }
+
+    fn role_options(&mut self, options: &[RoleOption]) {
+        self.number(options.len() as u64);
+        for option in options {
+            let (tag, set) = match option {
+                RoleOption::Login(set) => (b'l', set),
+                RoleOption::Superuser(set) => (b's', set),
+                RoleOption::CreateDb(set) => (b'd', set),
+                RoleOption::CreateRole(set) => (b'c', set),
+                RoleOption::Password(expr) => {
+                    self.tag(b'p');
+                    self.expression(expr);
+                    continue;
+                }
+            };
+            self.tag(tag);
+            self.tag(*set as u8);
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        match expr {
+            // Parentheses are already reflected in the shape of the tree
+            Expression::Grouped(inner) => self.expression(inner),
+            Expression::Identifier(name) => {
+                self.tag(b'I');
+                self.string(name);
+            }
+            Expression::CompoundIdentifier(parts) => {
+                self.tag(b'Q');
+                self.strings(parts);
+            }
+            Expression::Number(n) => {
+                self.tag(b'N');
+                self.number(*n);
+            }
+            Expression::Decimal(text) => {
+                self.tag(b'D');
+                self.string(text);
+            }
+            Expression::String(s) => {
+                self.tag(b'T');
+                self.string(s);
+            }
+            Expression::Boolean(b) => {
+                self.tag(b'B');
+                self.tag(*b as u8);
+            }
+            Expression::Null => self.tag(b'0'),
+            Expression::Placeholder(name) => {
+                self.tag(b'P');
+                self.string(name);
+            }
+            Expression::TypedString { data_type, value } => {
+                self.tag(b'K');
+                self.column_type(data_type);
+                self.string(value);
+            }
+            Expression::PrefixedString { prefix, value } => {
+                self.tag(b'H');
+                self.tag(prefix.letter() as u8);
+                self.string(value);
+            }
+            Expression::Collate { expr, collation } => {
+                self.tag(b'L');
+                self.string(collation);
+                self.expression(expr);
+            }
+            Expression::Cast { expr, data_type } => {
+                self.tag(b'C');
+                self.column_type(data_type);
+                self.expression(expr);
+            }
+            Expression::UnaryOperation { operator, operand } => {
+                self.tag(b'U');
+                self.tag(match operator {
+                    UnaryOperator::Not => 0,
+                    UnaryOperator::Negate => 1,
+                    UnaryOperator::IsNull => 2,
+                    UnaryOperator::IsNotNull => 3,
+                });
+                self.expression(operand);
+            }
+            Expression::BinaryOperation { left_operand, operator, right_operand } => {
+                self.tag(b'O');
+                self.tag(binary_tag(operator));
+                self.expression(left_operand);
+                self.expression(right_operand);
+            }
+            Expression::Function { name, args, over } => {
+                self.tag(b'F');
+                self.string(name);
+                self.rows(core::slice::from_ref(args));
+                match over {
+                    Some(window) => {
+                        self.tag(1);
+                        self.rows(core::slice::from_ref(&window.partition_by));
+                        self.order_by(&window.order_by);
+                    }
+                    None => self.tag(0),
+                }
+            }
+        }
+    }
+}
+
+// Explicit tags rather than discriminant values, so reordering the enum
+// can't silently change the hash
+fn binary_tag(operator: &BinaryOperator) -> u8 {
+    match operator {
+        BinaryOperator::Equals => 0,
+        BinaryOperator::NotEquals => 1,
+        BinaryOperator::GreaterThan => 2,
+        BinaryOperator::GreaterThanOrEqual => 3,
+        BinaryOperator::LessThan => 4,
+        BinaryOperator::LessThanOrEqual => 5,
+        BinaryOperator::And => 6,
+        BinaryOperator::Or => 7,
+        BinaryOperator::Add => 8,
+        BinaryOperator::Subtract => 9,
+        BinaryOperator::Multiply => 10,
+        BinaryOperator::Divide => 11,
+        BinaryOperator::Like => 12,
+        BinaryOperator::ILike => 13,
+        BinaryOperator::Concat => 14,
+        BinaryOperator::IsDistinctFrom => 15,
+        BinaryOperator::IsNotDistinctFrom => 16,
+    }
+}
@@ -0,0 +1,241 @@
+// html.rs — a self-contained HTML report of a SQL script
+// Author: Sreerag Devadasan
+//
+// `html_report` turns a script into one HTML page that needs nothing else
+// to be viewed: the styles are inline and the dependency graph is an
+// inline SVG. Every statement is shown highlighted, with its parse error
+// marked in the source, or with its AST as a tree of collapsible
+// `<details>` and what `lint` finds in it. The graph at the end links
+// each statement to the tables it writes (solid lines) and reads (dashed).
+
+use std::fmt::Write as _;
+
+use crate::ast::Statement;
+use crate::dialect::Dialect;
+use crate::graph::Graph;
+use crate::lint::{lint, Finding, Severity};
+use crate::report::report_script;
+use crate::session::ParserSession;
+use crate::span::error_span;
+use crate::style::{highlight_with, Role};
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+pre { background: #f6f8fa; padding: 0.8em; border-radius: 4px; overflow-x: auto; }
+section { border-top: 1px solid #ddd; margin-top: 1.5em; }
+.kw { color: #0033b3; font-weight: bold; } .str { color: #067d17; } .num { color: #1750eb; }
+.ph { color: #871094; } .com { color: #8c8c8c; font-style: italic; } .err { color: #c00; }
+mark { background: #fdd; color: #c00; }
+details { margin-left: 1.2em; } summary { cursor: pointer; font-family: monospace; }
+.field { margin-left: 1.2em; font-family: monospace; color: #555; }
+.error { color: #c00; } .warning { color: #a60; }
+svg text { font-family: monospace; font-size: 12px; }
+.write { stroke: #333; } .read { stroke: #888; stroke-dasharray: 4 3; }
+";
+
+/// The report of `script` parsed with `session`, as an HTML page titled
+/// `title`.
+pub fn html_report(title: &str, script: &str, session: &ParserSession) -> String {
+    let dialect = session.options().dialect;
+    let report = report_script(session, script);
+    let findings: Vec<Vec<Finding>> =
+        report.statements.iter().map(|statement| statement.result.as_ref().map(lint).unwrap_or_default()).collect();
+    let count = |severity| findings.iter().flatten().filter(|finding| finding.severity == severity).count();
+
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>{}</style>\n</head>\n<body>\n",
+        escape(title),
+        STYLE
+    );
+    let _ = writeln!(out, "<h1>{}</h1>", escape(title));
+    let _ = writeln!(
+        out,
+        "<p>{} statements, {} failed to parse, {} lint errors, {} lint warnings</p>",
+        report.statements.len(),
+        report.failures().count(),
+        count(Severity::Error),
+        count(Severity::Warning)
+    );
+
+    for (i, (statement, findings)) in report.statements.iter().zip(&findings).enumerate() {
+        let sql = statement.span.slice(script);
+        let _ = writeln!(out, "<section id=\"s{}\">\n<h2>#{} (line {})</h2>", i + 1, i + 1, statement.line);
+        match &statement.result {
+            Ok(parsed) => {
+                let _ = writeln!(out, "<pre>{}</pre>", highlight(sql, dialect));
+                out.push_str("<details>\n<summary>AST</summary>\n");
+                let graph = Graph::of(parsed);
+                tree(&graph, 0, None, &mut out);
+                out.push_str("</details>\n");
+                if !findings.is_empty() {
+                    out.push_str("<ul>\n");
+                    for finding in findings {
+                        let severity = finding.severity.name();
+                        let _ = writeln!(
+                            out,
+                            "<li class=\"{}\">{} [{}]: {}</li>",
+                            severity,
+                            severity,
+                            finding.rule,
+                            escape(&finding.message)
+                        );
+                    }
+                    out.push_str("</ul>\n");
+                }
+            }
+            Err(e) => {
+                let _ = writeln!(out, "<pre>{}</pre>", mark_error(sql, session));
+                let _ = writeln!(out, "<p class=\"error\">{}</p>", escape(&e.to_string()));
+            }
+        }
+        out.push_str("</section>\n");
+    }
+
+    let parsed: Vec<(usize, &Statement)> = report
+        .statements
+        .iter()
+        .enumerate()
+        .filter_map(|(i, statement)| statement.result.as_ref().ok().map(|parsed| (i, parsed)))
+        .collect();
+    out.push_str("<section>\n<h2>Dependencies</h2>\n");
+    out.push_str(&dependency_graph(&parsed));
+    out.push_str("</section>\n</body>\n</html>\n");
+    out
+}
+
+/// `sql` with its tokens wrapped in spans classed by role.
+fn highlight(sql: &str, dialect: Dialect) -> String {
+    highlight_with(sql, dialect, |role, text| {
+        let class = match role {
+            Some(Role::Keyword) => "kw",
+            Some(Role::String) => "str",
+            Some(Role::Number) => "num",
+            Some(Role::Placeholder) => "ph",
+            Some(Role::Comment) => "com",
+            Some(Role::Error) => "err",
+            _ => return escape(text),
+        };
+        format!("<span class=\"{}\">{}</span>", class, escape(text))
+    })
+}
+
+/// `sql` with the token its parse fails at in a `<mark>`.
+fn mark_error(sql: &str, session: &ParserSession) -> String {
+    match error_span(sql, session.options().dialect) {
+        Some(span) if span.end <= sql.len() => format!(
+            "{}<mark>{}</mark>{}",
+            escape(&sql[..span.start]),
+            escape(span.slice(sql)),
+            escape(&sql[span.end..])
+        ),
+        _ => escape(sql),
+    }
+}
+
+/// The box `id` of `graph` and the boxes under it as nested `<details>`,
+/// the ones without children as plain lines.
+fn tree(graph: &Graph, id: usize, label: Option<&str>, out: &mut String) {
+    let node = &graph.nodes[id];
+    let title = match label {
+        Some(label) => format!("{}: {}", escape(label), escape(&node.title)),
+        None => escape(&node.title),
+    };
+    let children: Vec<_> = graph.edges.iter().filter(|(from, ..)| *from == id).collect();
+    if children.is_empty() && node.details.is_empty() {
+        let _ = writeln!(out, "<div class=\"field\">{}</div>", title);
+        return;
+    }
+    let _ = writeln!(out, "<details open>\n<summary>{}</summary>", title);
+    for detail in &node.details {
+        let _ = writeln!(out, "<div class=\"field\">{}</div>", escape(detail));
+    }
+    for (_, to, label) in children {
+        tree(graph, *to, Some(label), out);
+    }
+    out.push_str("</details>\n");
+}
+
+/// The tables `statement` writes to: the table or view it creates, the
+/// table it drops, inserts into, updates, deletes from or loads, or the
+/// target of a MERGE.
+fn writes(statement: &Statement) -> Vec<&str> {
+    match statement {
+        Statement::CreateTable { table_name, .. }
+        | Statement::DropTable { table_name, .. }
+        | Statement::Insert { table_name, .. }
+        | Statement::InsertSelect { table_name, .. }
+        | Statement::Update { table_name, .. }
+        | Statement::Delete { table_name, .. }
+        | Statement::LoadData { table_name, .. }
+        | Statement::Copy { table_name, from: true, .. } => vec![table_name],
+        Statement::Merge { target, .. } | Statement::CreateView { name: target, .. } => vec![target],
+        Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => writes(statement),
+        _ => Vec::new(),
+    }
+}
+
+/// Statements on the left, tables on the right, and a line for every
+/// table a statement writes or reads.
+fn dependency_graph(statements: &[(usize, &Statement)]) -> String {
+    const ROW: usize = 28;
+    let mut tables: Vec<&str> = Vec::new();
+    let mut edges = Vec::new();
+    for (row, (_, statement)) in statements.iter().enumerate() {
+        let written = writes(statement);
+        for table in statement.tables() {
+            let column = match tables.iter().position(|known| known.eq_ignore_ascii_case(table)) {
+                Some(column) => column,
+                None => {
+                    tables.push(table);
+                    tables.len() - 1
+                }
+            };
+            edges.push((row, column, written.iter().any(|name| name.eq_ignore_ascii_case(table))));
+        }
+    }
+
+    let height = ROW * statements.len().max(tables.len()).max(1) + 10;
+    let y = |row: usize| 10 + row * ROW + ROW / 2;
+    let mut out = String::new();
+    let _ = writeln!(out, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"520\" height=\"{}\">", height);
+    for (row, (i, statement)) in statements.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "<a href=\"#s{}\"><rect x=\"10\" y=\"{}\" width=\"160\" height=\"20\" fill=\"#eef\" stroke=\"#99c\"/>\
+             <text x=\"16\" y=\"{}\">#{} {}</text></a>",
+            i + 1,
+            y(row) - 10,
+            y(row) + 4,
+            i + 1,
+            statement.kind()
+        );
+    }
+    for (row, table) in tables.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "<rect x=\"350\" y=\"{}\" width=\"160\" height=\"20\" fill=\"#efe\" stroke=\"#9c9\"/>\
+             <text x=\"356\" y=\"{}\">{}</text>",
+            y(row) - 10,
+            y(row) + 4,
+            escape(table)
+        );
+    }
+    for (row, column, write) in edges {
+        let _ = writeln!(
+            out,
+            "<line class=\"{}\" x1=\"170\" y1=\"{}\" x2=\"350\" y2=\"{}\"/>",
+            if write { "write" } else { "read" },
+            y(row),
+            y(column)
+        );
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
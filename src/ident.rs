@@ -0,0 +1,213 @@
+// ident.rs — identifiers as written, with dialect-aware comparison
+// Author: Sreerag Devadasan
+//
+// An `Ident` keeps the text of a name together with how it was quoted,
+// because the two behave differently: an unquoted name is case-insensitive
+// and each dialect folds it to one case (Postgres to lower case, the
+// standard to upper case), while a quoted one is taken exactly as written.
+// So in Postgres `users`, `USERS` and `"users"` are one table but `"Users"`
+// is another. `eq_in` compares two names the way a dialect does, and
+// `normalized` gives the folded form to use as a lookup key.
+//
+// `==` compares the text and the quoting exactly, as the rest of the AST
+// does, and an `Ident` derefs to its text, so code that only needs the
+// name can keep treating it as a `&str`.
+//
+// `closest` finds the name a misspelt one was probably meant to be, for
+// the "did you mean" of the tutorial's keywords and the analyzer's tables
+// and columns.
+
+use core::fmt;
+use core::ops::Deref;
+
+use crate::prelude::*;
+use crate::dialect::Dialect;
+
+/// The quotes around a quoted identifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum QuoteStyle {
+    /// `"name"`, the standard.
+    Double,
+    /// `` `name` ``, as in MySQL.
+    Backtick,
+    /// `[name]`, as in SQL Server.
+    Bracket,
+}
+
+impl QuoteStyle {
+    /// The opening and closing quote characters.
+    pub fn delimiters(&self) -> (char, char) {
+        match self {
+            QuoteStyle::Double => ('"', '"'),
+            QuoteStyle::Backtick => ('`', '`'),
+            QuoteStyle::Bracket => ('[', ']'),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Ident {
+    /// The name without its quotes, with doubled quote characters undone.
+    pub value: String,
+    /// `None` for a bare name.
+    pub quote: Option<QuoteStyle>,
+}
+
+impl Ident {
+    /// A bare, unquoted name.
+    pub fn new(value: impl Into<String>) -> Self {
+        Ident { value: value.into(), quote: None }
+    }
+
+    pub fn quoted(value: impl Into<String>, quote: QuoteStyle) -> Self {
+        Ident { value: value.into(), quote: Some(quote) }
+    }
+
+    pub fn is_quoted(&self) -> bool {
+        self.quote.is_some()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+
+    /// The name as `dialect` resolves it: folded to the dialect's case when
+    /// unquoted, as written when quoted.
+    pub fn normalized(&self, dialect: Dialect) -> String {
+        match self.quote {
+            Some(_) => self.value.clone(),
+            None if folds_to_upper(dialect) => self.value.to_uppercase(),
+            None => self.value.to_lowercase(),
+        }
+    }
+
+    /// True when `dialect` reads both names as the same object.
+    pub fn eq_in(&self, other: &Ident, dialect: Dialect) -> bool {
+        self.normalized(dialect) == other.normalized(dialect)
+    }
+
+    /// The name as SQL of `dialect`: quoted in the dialect's style if it
+    /// was quoted, since the quotes may be what keeps its case, and
+    /// otherwise only when it wouldn't read back as a plain name.
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        match self.quote {
+            Some(_) => dialect.quote(&self.value),
+            None => dialect.quote_identifier(&self.value),
+        }
+    }
+}
+
+/// Whether unquoted names are upper-cased, as the standard says, rather
+/// than lower-cased, as Postgres, MySQL and SQL Server effectively do.
+fn folds_to_upper(dialect: Dialect) -> bool {
+    matches!(dialect, Dialect::Generic | Dialect::Ansi | Dialect::Warehouse)
+}
+
+impl Deref for Ident {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl AsRef<str> for Ident {
+    fn as_ref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl From<&str> for Ident {
+    fn from(value: &str) -> Self {
+        Ident::new(value)
+    }
+}
+
+impl From<String> for Ident {
+    fn from(value: String) -> Self {
+        Ident::new(value)
+    }
+}
+
+impl PartialEq<str> for Ident {
+    fn eq(&self, other: &str) -> bool {
+        self.value == other
+    }
+}
+
+impl PartialEq<&str> for Ident {
+    fn eq(&self, other: &&str) -> bool {
+        self.value == *other
+    }
+}
+
+impl PartialEq<String> for Ident {
+    fn eq(&self, other: &String) -> bool {
+        &self.value == other
+    }
+}
+
+/// As written, quotes included.
+impl fmt::Display for Ident {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.quote {
+            None => f.write_str(&self.value),
+            Some(quote) => {
+                let (open, close) = quote.delimiters();
+                let mut doubled = String::new();
+                doubled.push(close);
+                doubled.push(close);
+                write!(f, "{}{}{}", open, self.value.replace(close, &doubled), close)
+            }
+        }
+    }
+}
+
+/// Bare names debug as their text, so that ASTs read as they did when
+/// names were plain strings; quoted ones show their quotes.
+impl fmt::Debug for Ident {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.quote {
+            None => fmt::Debug::fmt(&self.value, f),
+            Some(quote) => f.debug_tuple("Ident").field(&self.value).field(&quote).finish(),
+        }
+    }
+}
+
+/// The candidate `name` is most likely a misspelling of: one edit away, or
+/// two for names of six letters or more, ignoring case. Names shorter than
+/// four letters are too close to too many others to guess at.
+pub(crate) fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let name = name.to_ascii_uppercase();
+    if name.len() < 4 {
+        return None;
+    }
+    let allowed = if name.len() < 6 { 1 } else { 2 };
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.len() >= 4)
+        .map(|candidate| (edit_distance(&name, &candidate.to_ascii_uppercase()), candidate))
+        .filter(|(distance, _)| (1..=allowed).contains(distance))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance, counting a swap of neighbouring letters as one
+/// edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut rows = vec![(0..=b.len()).collect::<Vec<usize>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1).min(row[j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
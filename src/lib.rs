@@ -0,0 +1,133 @@
+// lib.rs — library entry point for the Mini SQL Parser
+// Author: Sreerag Devadasan
+//
+// Everything except the file, thread, network and template APIs works
+// without std (only `alloc`) when the default "std" feature is disabled.
+// The other default features add grammar and the engine, and can be left
+// out for a smaller parser; `capabilities()` says what a build has.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+/// The alloc types that std's prelude would otherwise provide.
+mod prelude {
+    pub(crate) use alloc::boxed::Box;
+    pub(crate) use alloc::format;
+    pub(crate) use alloc::string::{String, ToString};
+    pub(crate) use alloc::vec;
+    pub(crate) use alloc::vec::Vec;
+}
+
+/// Traces the rest of the enclosing block as a span named `$name` when the
+/// "trace" feature is enabled; expands to nothing otherwise.
+macro_rules! trace_span {
+    ($name:expr, $position:expr) => {
+        #[cfg(feature = "trace")]
+        let _span = crate::trace::TraceSpan::enter($name, $position);
+    };
+}
+
+/// Reports one event of kind `$kind` named `$name` when the "trace"
+/// feature is enabled; expands to nothing otherwise.
+macro_rules! trace_event {
+    ($name:expr, $position:expr, $kind:expr) => {
+        #[cfg(feature = "trace")]
+        crate::trace::emit(crate::trace::TraceEvent { name: $name, position: $position, kind: $kind });
+    };
+}
+
+#[cfg(feature = "trace")]
+pub mod trace;
+pub mod tokenizer;
+pub mod pratt;
+pub mod parser;
+pub mod extension;
+pub mod ast;
+pub mod ident;
+pub mod visit;
+pub mod eval;
+pub mod datetime;
+pub mod decimal;
+pub mod semantics;
+pub mod collation;
+pub mod bytecode;
+pub mod batch;
+pub mod anonymize;
+pub mod dialect;
+pub mod printer;
+#[cfg(feature = "std")]
+pub mod template;
+pub mod json;
+#[cfg(feature = "std")]
+pub mod migrations;
+pub mod schema_diff;
+#[cfg(feature = "std")]
+pub mod catalog;
+pub mod query_diff;
+pub mod canonical;
+pub mod codegen;
+pub mod graph;
+pub mod sexp;
+pub mod lint;
+pub mod dependencies;
+pub mod dead_code;
+pub mod lineage;
+pub mod analyzer;
+pub mod stats;
+pub mod planner;
+pub mod dsl;
+pub mod hash;
+pub mod span;
+pub mod rename;
+pub mod views;
+pub mod complete;
+pub mod session;
+pub mod profile;
+pub mod standard;
+pub mod style;
+pub mod tutorial;
+#[cfg(feature = "std")]
+pub mod conformance;
+#[cfg(feature = "engine")]
+pub mod columnar;
+#[cfg(feature = "engine")]
+pub mod engine;
+#[cfg(feature = "engine")]
+pub mod functions;
+#[cfg(feature = "engine")]
+pub mod plan_cache;
+#[cfg(feature = "engine")]
+pub mod result_cache;
+#[cfg(feature = "engine")]
+pub mod params;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "sqlparser")]
+pub mod sqlparser;
+#[cfg(all(feature = "mmap", unix))]
+pub mod mmap;
+#[cfg(all(feature = "std", feature = "engine"))]
+pub mod wal;
+#[cfg(all(feature = "std", feature = "engine"))]
+pub mod logictest;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "std")]
+pub mod html;
+#[cfg(all(feature = "std", feature = "engine"))]
+pub mod pgwire;
+#[cfg(all(feature = "std", feature = "engine"))]
+pub mod mysqlwire;
+#[cfg(all(feature = "std", feature = "engine"))]
+pub mod proxy;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod repl;
+#[cfg(feature = "std")]
+pub mod cli;
+pub mod querylog;
+pub mod capabilities;
+
+pub use capabilities::capabilities;
@@ -0,0 +1,528 @@
+// lineage.rs — the source columns each output column of a query derives from
+// Author: Sreerag Devadasan
+//
+// `lineage` turns a query, or an INSERT ... SELECT, into a graph of
+// columns: those of the tables it reads, those of its CTEs and subqueries
+// in FROM, and the columns it returns or writes, with an edge from each
+// column to every column whose values it is made of. Names are followed
+// through table aliases, the column lists of CTEs and aliases, `*` (with
+// its EXCLUDE and REPLACE), and the arms of set operations, which line up
+// by position. `Lineage::sources` reads off the table columns an output
+// column comes from in the end, and `to_dot` draws the graph.
+//
+// Only the flow of values counts: the columns a WHERE, JOIN or GROUP BY
+// reads decide which rows there are, not what is in them, and are left
+// out. Without a schema, `*` on a table stands for one `table.*` column
+// and an unqualified name belongs to every table of its FROM that might
+// have it; with one, both are resolved to the columns the tables have.
+// PIVOT and UNPIVOT are traced to the table under them.
+
+use core::fmt::{self, Write as _};
+
+use crate::prelude::*;
+use crate::ast::{Expression, Statement, TableFactor, WildcardOptions};
+use crate::graph::escape_dot;
+use crate::ident::Ident;
+use crate::json;
+use crate::schema_diff::Schema;
+
+/// What a column in the graph is a column of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationKind {
+    Table,
+    /// A table-valued function in FROM.
+    Function,
+    /// A name bound by WITH.
+    Cte,
+    /// A subquery in FROM, by its alias.
+    Subquery,
+    /// The rows the statement returns, the table an INSERT writes, or the
+    /// view a CREATE VIEW defines.
+    Output,
+}
+
+impl RelationKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            RelationKind::Table => "table",
+            RelationKind::Function => "function",
+            RelationKind::Cte => "cte",
+            RelationKind::Subquery => "subquery",
+            RelationKind::Output => "output",
+        }
+    }
+}
+
+/// One column of the graph. The output columns of a query belong to a
+/// relation with an empty name; those of an INSERT to its table, and those
+/// of a CREATE VIEW to the view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnNode {
+    pub kind: RelationKind,
+    pub relation: String,
+    pub column: String,
+}
+
+impl fmt::Display for ColumnNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.relation.is_empty() {
+            write!(f, "{}", self.column)
+        } else {
+            write!(f, "{}.{}", self.relation, self.column)
+        }
+    }
+}
+
+/// The columns of a statement and what each is derived from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lineage {
+    pub nodes: Vec<ColumnNode>,
+    /// `(from, to)` indices into `nodes`: `to` is derived from `from`.
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl Lineage {
+    /// The indices of the columns the statement returns or writes, in order.
+    pub fn outputs(&self) -> Vec<usize> {
+        (0..self.nodes.len()).filter(|&id| self.nodes[id].kind == RelationKind::Output).collect()
+    }
+
+    /// The table and function columns node `id` is derived from, through
+    /// any number of CTEs and subqueries.
+    pub fn sources(&self, id: usize) -> Vec<&ColumnNode> {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            for &(from, to) in &self.edges {
+                if to == id && !seen[from] {
+                    seen[from] = true;
+                    pending.push(from);
+                }
+            }
+        }
+        let base = |node: &ColumnNode| matches!(node.kind, RelationKind::Table | RelationKind::Function);
+        self.nodes.iter().zip(seen).filter(|(node, seen)| *seen && base(node)).map(|(node, _)| node).collect()
+    }
+
+    /// The sources of the output column named `column`.
+    pub fn sources_of(&self, column: &str) -> Vec<&ColumnNode> {
+        let output = self.outputs().into_iter().find(|&id| self.nodes[id].column.eq_ignore_ascii_case(column));
+        output.map_or_else(Vec::new, |id| self.sources(id))
+    }
+
+    /// The graph as a Graphviz `digraph`, one cluster per relation.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph lineage {\n    rankdir=LR;\n");
+        out.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+        let mut relations: Vec<(RelationKind, &str)> = Vec::new();
+        for node in &self.nodes {
+            if !relations.contains(&(node.kind, node.relation.as_str())) {
+                relations.push((node.kind, &node.relation));
+            }
+        }
+        for (cluster, &(kind, relation)) in relations.iter().enumerate() {
+            let label = match kind {
+                RelationKind::Table => relation.to_string(),
+                RelationKind::Function => format!("{}()", relation),
+                RelationKind::Cte => format!("WITH {}", relation),
+                RelationKind::Subquery => format!("({})", relation),
+                RelationKind::Output if relation.is_empty() => "result".to_string(),
+                RelationKind::Output => relation.to_string(),
+            };
+            let _ = writeln!(out, "    subgraph cluster_{} {{", cluster);
+            let _ = writeln!(out, "        label=\"{}\";", escape_dot(&label));
+            match kind {
+                RelationKind::Cte | RelationKind::Subquery => out.push_str("        style=dashed;\n"),
+                RelationKind::Output => out.push_str("        style=bold;\n"),
+                RelationKind::Table | RelationKind::Function => {}
+            }
+            for (id, node) in self.nodes.iter().enumerate() {
+                if node.kind == kind && node.relation == relation {
+                    let _ = writeln!(out, "        n{} [label=\"{}\"];", id, escape_dot(&node.column));
+                }
+            }
+            out.push_str("    }\n");
+        }
+        for (from, to) in &self.edges {
+            let _ = writeln!(out, "    n{} -> n{};", from, to);
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let node = |node: &ColumnNode| {
+            json::object([
+                ("kind", json::string(node.kind.name())),
+                ("relation", json::string(&node.relation)),
+                ("column", json::string(&node.column)),
+            ])
+        };
+        let columns = self.outputs().into_iter().map(|id| {
+            json::object([
+                ("column", json::string(&self.nodes[id].column)),
+                ("sources", json::array(self.sources(id).into_iter().map(node))),
+            ])
+        });
+        let edges = self.edges.iter().map(|(from, to)| json::array([from.to_string(), to.to_string()]));
+        json::object([
+            ("columns", json::array(columns)),
+            ("nodes", json::array(self.nodes.iter().map(node))),
+            ("edges", json::array(edges)),
+        ])
+    }
+}
+
+/// One line per output column: `name <- table.column, ...`.
+impl fmt::Display for Lineage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, id) in self.outputs().into_iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let sources: Vec<String> = self.sources(id).iter().map(|node| node.to_string()).collect();
+            if sources.is_empty() {
+                write!(f, "{} <- no columns", self.nodes[id])?;
+            } else {
+                write!(f, "{} <- {}", self.nodes[id], sources.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The column lineage of a query, an INSERT ... SELECT or the query of a
+/// CREATE VIEW (also under PREPARE or EXPLAIN); `None` for other
+/// statements. `schema`, when given,
+/// lists the columns of the tables.
+pub fn lineage(statement: &Statement, schema: Option<&Schema>) -> Option<Lineage> {
+    let mut builder = Builder { schema, lineage: Lineage::default(), ctes: Vec::new() };
+    match statement {
+        Statement::Select { .. }
+        | Statement::SetOperation { .. }
+        | Statement::With { .. }
+        | Statement::Values { .. } => {
+            let columns = builder.query(statement);
+            let names: Vec<String> = columns.iter().map(|column| column.name.clone()).collect();
+            builder.output("", &names, &columns);
+        }
+        Statement::InsertSelect { table_name, columns, query } => {
+            let produced = builder.query(query);
+            let names: Vec<String> = match columns {
+                Some(columns) => columns.iter().map(Ident::to_string).collect(),
+                None => match builder.table_columns(table_name) {
+                    Some(columns) => columns,
+                    None => produced.iter().map(|column| column.name.clone()).collect(),
+                },
+            };
+            builder.output(table_name, &names, &produced);
+        }
+        Statement::CreateView { name, columns, query, .. } => {
+            let produced = builder.query(query);
+            let names: Vec<String> = renamed(produced.clone(), columns).into_iter().map(|column| column.name).collect();
+            builder.output(name, &names, &produced);
+        }
+        Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => {
+            return lineage(statement, schema)
+        }
+        _ => return None,
+    }
+    Some(builder.lineage)
+}
+
+/// A column of a relation, and the graph nodes its values come from.
+#[derive(Debug, Clone)]
+struct Column {
+    name: String,
+    nodes: Vec<usize>,
+}
+
+/// A FROM item, by the name its columns are qualified with.
+struct Source<'a> {
+    name: &'a str,
+    columns: Columns<'a>,
+}
+
+enum Columns<'a> {
+    /// Those of a CTE or subquery, known from its query.
+    Known(Vec<Column>),
+    /// Those of a table or function, listed when the schema has the table.
+    Table { kind: RelationKind, name: &'a str, listed: Option<Vec<String>> },
+}
+
+struct Builder<'a> {
+    schema: Option<&'a Schema>,
+    lineage: Lineage,
+    /// The CTEs in scope, inner ones last.
+    ctes: Vec<(&'a str, Vec<Column>)>,
+}
+
+impl<'a> Builder<'a> {
+    /// The output columns of a query, adding the columns it reads to the
+    /// graph.
+    fn query(&mut self, statement: &'a Statement) -> Vec<Column> {
+        match statement {
+            Statement::Select { columns, wildcard, from, joins, .. } => {
+                let mut scope = Vec::new();
+                self.factor(from, &mut scope);
+                for join in joins {
+                    self.factor(&join.relation, &mut scope);
+                }
+                let mut output = Vec::new();
+                for item in columns {
+                    if item.is_wildcard() {
+                        output.extend(self.wildcard(wildcard, &scope));
+                    } else {
+                        let nodes = self.expression(&item.expr, &scope);
+                        output.push(Column { name: item.output_name(), nodes });
+                    }
+                }
+                output
+            }
+            Statement::SetOperation { left, right, .. } => {
+                let mut output = self.query(left);
+                for (column, other) in output.iter_mut().zip(self.query(right)) {
+                    for node in other.nodes {
+                        if !column.nodes.contains(&node) {
+                            column.nodes.push(node);
+                        }
+                    }
+                }
+                output
+            }
+            Statement::With { recursive, ctes, body } => {
+                let outer = self.ctes.len();
+                for cte in ctes {
+                    let anchor = match &*cte.query {
+                        Statement::SetOperation { left, right, .. } if *recursive => Some((left, right)),
+                        _ => None,
+                    };
+                    // The recursive arm reads the columns the anchor defines
+                    let query = anchor.map_or(&*cte.query, |(left, _)| &**left);
+                    let columns = self.query(query);
+                    let columns = self.relation(RelationKind::Cte, &cte.name, columns, &cte.columns);
+                    self.ctes.push((&cte.name, columns));
+                    if let Some((_, right)) = anchor {
+                        let defined = self.ctes.last().map(|(_, columns)| columns.clone()).unwrap_or_default();
+                        for (column, other) in defined.iter().zip(self.query(right)) {
+                            other.nodes.iter().for_each(|&node| self.edge(node, column.nodes[0]));
+                        }
+                    }
+                }
+                let output = self.query(body);
+                self.ctes.truncate(outer);
+                output
+            }
+            Statement::Values { rows } => {
+                let width = rows.first().map_or(0, Vec::len);
+                (1..=width).map(|i| Column { name: format!("column{}", i), nodes: Vec::new() }).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Adds the sources of a FROM item to `scope`.
+    fn factor(&mut self, factor: &'a TableFactor, scope: &mut Vec<Source<'a>>) {
+        match factor {
+            TableFactor::Table { name, alias, .. } | TableFactor::AsOf { name, alias, .. } => {
+                let qualifier = alias.as_ref().map_or(name.as_str(), |alias| alias.name.as_str());
+                let renames = alias.as_ref().map_or(&[][..], |alias| &alias.columns[..]);
+                let cte = self.ctes.iter().rev().find(|(cte, _)| cte.eq_ignore_ascii_case(name));
+                let columns = match (cte, self.table_columns(name)) {
+                    (Some((_, columns)), _) => Columns::Known(renamed(columns.clone(), renames)),
+                    (None, Some(listed)) if !renames.is_empty() => {
+                        let columns = listed
+                            .iter()
+                            .map(|column| {
+                                let node = self.node(RelationKind::Table, name, column);
+                                Column { name: column.clone(), nodes: vec![node] }
+                            })
+                            .collect();
+                        Columns::Known(renamed(columns, renames))
+                    }
+                    (None, listed) => Columns::Table { kind: RelationKind::Table, name, listed },
+                };
+                scope.push(Source { name: qualifier, columns });
+            }
+            TableFactor::Derived { subquery, alias, .. } => {
+                let columns = self.query(subquery);
+                let (name, renames) = alias.as_ref().map_or(("", &[][..]), |alias| (&alias.name, &alias.columns[..]));
+                let columns = self.relation(RelationKind::Subquery, name, columns, renames);
+                scope.push(Source { name, columns: Columns::Known(columns) });
+            }
+            TableFactor::Function { name, alias, .. } => {
+                let qualifier = alias.as_ref().map_or(name.as_str(), |alias| alias.name.as_str());
+                let columns = Columns::Table { kind: RelationKind::Function, name, listed: None };
+                scope.push(Source { name: qualifier, columns });
+            }
+            TableFactor::Pivot { table, alias, .. } | TableFactor::Unpivot { table, alias, .. } => {
+                self.factor(table, scope);
+                if let (Some(alias), Some(source)) = (alias, scope.last_mut()) {
+                    source.name = &alias.name;
+                }
+            }
+        }
+    }
+
+    /// The columns `*` stands for, less those EXCLUDE drops and with the
+    /// expressions REPLACE puts in.
+    fn wildcard(&mut self, wildcard: &'a WildcardOptions, scope: &[Source<'a>]) -> Vec<Column> {
+        let mut output = Vec::new();
+        for source in scope {
+            match &source.columns {
+                Columns::Known(columns) => output.extend(columns.iter().cloned()),
+                Columns::Table { kind, name, listed: Some(listed) } => {
+                    for column in listed {
+                        let node = self.node(*kind, name, column);
+                        output.push(Column { name: column.clone(), nodes: vec![node] });
+                    }
+                }
+                Columns::Table { kind, name, listed: None } => {
+                    let node = self.node(*kind, name, "*");
+                    output.push(Column { name: "*".to_string(), nodes: vec![node] });
+                }
+            }
+        }
+        output.retain(|column| !wildcard.exclude.iter().any(|name| name.eq_ignore_ascii_case(&column.name)));
+        for (expr, name) in &wildcard.replace {
+            let nodes = self.expression(expr, scope);
+            match output.iter_mut().find(|column| name.eq_ignore_ascii_case(&column.name)) {
+                Some(column) => column.nodes = nodes,
+                None => output.push(Column { name: name.to_string(), nodes }),
+            }
+        }
+        output
+    }
+
+    /// The nodes of every column `expr` reads.
+    fn expression(&mut self, expr: &'a Expression, scope: &[Source<'a>]) -> Vec<usize> {
+        let mut nodes = Vec::new();
+        expr.walk(&mut |expr| {
+            let found = match expr {
+                // Not the `*` of COUNT(*)
+                Expression::Identifier(name) if name != "*" => self.unqualified(name, scope),
+                Expression::CompoundIdentifier(parts) if parts.len() >= 2 => {
+                    self.qualified(&parts[parts.len() - 2], &parts[parts.len() - 1], scope)
+                }
+                _ => Vec::new(),
+            };
+            for node in found {
+                if !nodes.contains(&node) {
+                    nodes.push(node);
+                }
+            }
+        });
+        nodes
+    }
+
+    /// The nodes an unqualified column name may be: the columns of that
+    /// name among the sources, or, when none has it, a column of each
+    /// table whose columns aren't known.
+    fn unqualified(&mut self, column: &str, scope: &[Source<'a>]) -> Vec<usize> {
+        let mut nodes = Vec::new();
+        for source in scope {
+            match &source.columns {
+                Columns::Known(columns) => nodes.extend(known(columns, column)),
+                Columns::Table { kind, name, listed: Some(listed) } => {
+                    if listed.iter().any(|name| name.eq_ignore_ascii_case(column)) {
+                        nodes.push(self.node(*kind, name, column));
+                    }
+                }
+                Columns::Table { listed: None, .. } => {}
+            }
+        }
+        if nodes.is_empty() {
+            for source in scope {
+                if let Columns::Table { kind, name, listed: None } = &source.columns {
+                    nodes.push(self.node(*kind, name, column));
+                }
+            }
+        }
+        nodes
+    }
+
+    fn qualified(&mut self, qualifier: &str, column: &str, scope: &[Source<'a>]) -> Vec<usize> {
+        match scope.iter().find(|source| source.name.eq_ignore_ascii_case(qualifier)) {
+            Some(Source { columns: Columns::Known(columns), .. }) => known(columns, column),
+            Some(Source { columns: Columns::Table { kind, name, .. }, .. }) => vec![self.node(*kind, name, column)],
+            // A qualifier that names nothing here could be any of them
+            None => self.unqualified(column, scope),
+        }
+    }
+
+    /// The columns of a CTE or subquery as nodes of their own, renamed by
+    /// its column list, each with edges from what it's made of.
+    fn relation(&mut self, kind: RelationKind, name: &str, columns: Vec<Column>, renames: &[Ident]) -> Vec<Column> {
+        renamed(columns, renames)
+            .into_iter()
+            .map(|column| {
+                let id = self.push(ColumnNode { kind, relation: name.to_string(), column: column.name.clone() });
+                column.nodes.iter().for_each(|&node| self.edge(node, id));
+                Column { name: column.name, nodes: vec![id] }
+            })
+            .collect()
+    }
+
+    /// The output columns, in order, with edges from the columns they are
+    /// filled from by position. A `*` whose columns aren't known may stand
+    /// for any column after it whose name doesn't line up.
+    fn output(&mut self, relation: &str, names: &[String], produced: &[Column]) {
+        for (i, name) in names.iter().enumerate() {
+            let node = ColumnNode { kind: RelationKind::Output, relation: relation.to_string(), column: name.clone() };
+            let id = self.push(node);
+            let aligned = produced.get(i).is_some_and(|column| column.name.eq_ignore_ascii_case(name));
+            let stars = produced.iter().take(i).filter(|column| !aligned && column.name == "*");
+            for column in stars.chain(produced.get(i)) {
+                column.nodes.iter().for_each(|&node| self.edge(node, id));
+            }
+        }
+    }
+
+    /// The column names `schema` has for `table`.
+    fn table_columns(&self, table: &str) -> Option<Vec<String>> {
+        let (_, columns) = self.schema?.tables.iter().find(|(name, _)| name.eq_ignore_ascii_case(table))?;
+        Some(columns.iter().map(|column| column.column_name.to_string()).collect())
+    }
+
+    /// The node of a table or function column, added on first use.
+    fn node(&mut self, kind: RelationKind, relation: &str, column: &str) -> usize {
+        let existing = self.lineage.nodes.iter().position(|node| {
+            node.kind == kind
+                && node.relation.eq_ignore_ascii_case(relation)
+                && node.column.eq_ignore_ascii_case(column)
+        });
+        existing.unwrap_or_else(|| {
+            self.push(ColumnNode { kind, relation: relation.to_string(), column: column.to_string() })
+        })
+    }
+
+    fn push(&mut self, node: ColumnNode) -> usize {
+        self.lineage.nodes.push(node);
+        self.lineage.nodes.len() - 1
+    }
+
+    fn edge(&mut self, from: usize, to: usize) {
+        if from != to && !self.lineage.edges.contains(&(from, to)) {
+            self.lineage.edges.push((from, to));
+        }
+    }
+}
+
+/// The nodes of the column named `column`; a `*` of unknown columns may
+/// hold any name.
+fn known(columns: &[Column], column: &str) -> Vec<usize> {
+    let named = columns.iter().filter(|known| known.name.eq_ignore_ascii_case(column));
+    let found: Vec<usize> = named.flat_map(|known| known.nodes.iter().copied()).collect();
+    if !found.is_empty() {
+        return found;
+    }
+    columns.iter().filter(|known| known.name == "*").flat_map(|known| known.nodes.iter().copied()).collect()
+}
+
+/// `columns` with the leading ones renamed by an alias's column list.
+fn renamed(mut columns: Vec<Column>, renames: &[Ident]) -> Vec<Column> {
+    for (column, name) in columns.iter_mut().zip(renames) {
+        column.name = name.to_string();
+    }
+    columns
+}
@@ -0,0 +1,568 @@
+// lint.rs — rules flagging statements that parse but are likely mistakes
+// Author: Sreerag Devadasan
+//
+// Each `Rule` looks at one parsed statement and reports what it finds in
+// it, with a severity: an error for something that can't be what was
+// meant (`x = NULL` is never true), a warning for something that works
+// but is fragile or costly (`SELECT *`, LIMIT without ORDER BY). `lint`
+// runs every rule of `RULES`. Rules about queries see every SELECT in the
+// statement, including those in CTEs, set operations and FROM subqueries.
+//
+// The `unused-*` rules find what legacy queries accumulate: CTEs nothing
+// reads, columns a CTE or subquery selects that the query around it never
+// names, and aliases no column is qualified with. Names are matched
+// without regard to case or to which table they're from, so a use is
+// sometimes assumed where there is none, but never missed.
+//
+// Other rules guard a production database against costly statements:
+// UPDATE and DELETE without WHERE, reading a large table whole, LIKE
+// patterns starting with a wildcard and functions wrapping an indexed
+// column, which both keep an index from narrowing the scan. Which tables
+// are large and which columns indexed come from a `LintConfig`, which can
+// also make any rule an error, a warning, or turn it off, so a deploy gate
+// can fail on exactly what its team cares about.
+
+use crate::prelude::*;
+use crate::ast::{BinaryOperator, Cte, Expression, Ident, JoinKind, MergeSource, SelectItem, Statement, TableFactor};
+use crate::dialect::Dialect;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    /// Looks a severity up by its name, or `warn` (case-insensitive).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "warning" | "warn" => Some(Severity::Warning),
+            "error" => Some(Severity::Error),
+            _ => None,
+        }
+    }
+}
+
+/// What the rules are told about the database, and the severity each one
+/// reports with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintConfig {
+    /// Rules given a severity other than their own, or turned off with
+    /// `None`.
+    pub levels: Vec<(String, Option<Severity>)>,
+    /// Tables too large to SELECT from without a LIMIT.
+    pub large_tables: Vec<String>,
+    /// Indexed columns, as `table.column`.
+    pub indexed_columns: Vec<String>,
+}
+
+impl LintConfig {
+    /// Sets a rule's level from `name=level`, the level being `error`,
+    /// `warn` or `off`.
+    pub fn set_level(&mut self, setting: &str) -> Result<(), String> {
+        let (name, level) = setting.split_once('=').ok_or_else(|| format!("expected name=level, not {}", setting))?;
+        let rule = RULES.iter().find(|rule| rule.name.eq_ignore_ascii_case(name.trim()));
+        let rule = rule.ok_or_else(|| format!("no rule is named {}", name.trim()))?;
+        let severity = match level.trim() {
+            level if level.eq_ignore_ascii_case("off") => None,
+            level => Some(Severity::from_name(level).ok_or_else(|| format!("{} isn't error, warn or off", level))?),
+        };
+        self.levels.retain(|(other, _)| other != rule.name);
+        self.levels.push((rule.name.to_string(), severity));
+        Ok(())
+    }
+
+    /// The severity `rule` reports with, or `None` when it's off.
+    pub fn severity(&self, rule: &Rule) -> Option<Severity> {
+        match self.levels.iter().find(|(name, _)| name.eq_ignore_ascii_case(rule.name)) {
+            Some((_, level)) => *level,
+            None => Some(rule.severity),
+        }
+    }
+
+    fn is_large(&self, table: &str) -> bool {
+        self.large_tables.iter().any(|large| large.eq_ignore_ascii_case(table))
+    }
+
+    /// Whether `column` is indexed in one of `tables`.
+    fn is_indexed(&self, tables: &[&str], column: &str) -> bool {
+        self.indexed_columns.iter().any(|indexed| match indexed.rsplit_once('.') {
+            Some((table, name)) => {
+                name.eq_ignore_ascii_case(column) && tables.iter().any(|t| t.eq_ignore_ascii_case(table))
+            }
+            None => indexed.eq_ignore_ascii_case(column),
+        })
+    }
+}
+
+/// One problem a rule found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// The name of the rule that found it.
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+pub struct Rule {
+    /// A kebab-case name such as `select-star`.
+    pub name: &'static str,
+    pub severity: Severity,
+    pub description: &'static str,
+    /// One message per problem found in the statement.
+    check: fn(&Statement, &LintConfig) -> Vec<String>,
+}
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        name: "null-comparison",
+        severity: Severity::Error,
+        description: "= or != with NULL, which is never true",
+        check: |statement, _| {
+            let mut messages = Vec::new();
+            for expr in statement.expressions() {
+                expr.walk(&mut |expr| {
+                    if let Expression::BinaryOperation { left_operand, operator, right_operand } = expr {
+                        let null = |e: &Expression| *e == Expression::Null;
+                        let comparison = matches!(operator, BinaryOperator::Equals | BinaryOperator::NotEquals);
+                        if comparison && (null(left_operand) || null(right_operand)) {
+                            let sql = expr.to_sql(Dialect::Generic);
+                            let test = if *operator == BinaryOperator::Equals { "IS NULL" } else { "IS NOT NULL" };
+                            let why = "is never true, whatever the other side holds";
+                            messages.push(format!("`{}` {}; use {}", sql, why, test));
+                        }
+                    }
+                });
+            }
+            messages
+        },
+    },
+    Rule {
+        name: "select-star",
+        severity: Severity::Warning,
+        description: "SELECT *, whose columns change with the table",
+        check: |statement, _| {
+            selects(statement)
+                .into_iter()
+                .filter(|select| match select {
+                    Statement::Select { columns, .. } => columns.iter().any(SelectItem::is_wildcard),
+                    _ => false,
+                })
+                .map(|_| "SELECT * returns whatever columns the table has; list the ones needed".to_string())
+                .collect()
+        },
+    },
+    Rule {
+        name: "limit-without-order",
+        severity: Severity::Warning,
+        description: "LIMIT or OFFSET without ORDER BY, which picks arbitrary rows",
+        check: |statement, _| {
+            selects(statement)
+                .into_iter()
+                .filter(|select| match select {
+                    Statement::Select { order_by, limit, offset, .. } => {
+                        order_by.is_empty() && (limit.is_some() || offset.is_some())
+                    }
+                    _ => false,
+                })
+                .map(|_| "LIMIT without ORDER BY returns any rows, not necessarily the same ones twice".to_string())
+                .collect()
+        },
+    },
+    Rule {
+        name: "cross-join",
+        severity: Severity::Warning,
+        description: "joins without a condition, which pair every row with every row",
+        check: |statement, _| {
+            let mut messages = Vec::new();
+            for select in selects(statement) {
+                if let Statement::Select { joins, .. } = select {
+                    for join in joins.iter().filter(|join| join.kind == JoinKind::Cross || join.on.is_none()) {
+                        let table = join.relation.table_name().unwrap_or("a subquery");
+                        messages.push(format!("the join with {} pairs every row with every row of the others", table));
+                    }
+                }
+            }
+            messages
+        },
+    },
+    Rule {
+        name: "drop-without-if-exists",
+        severity: Severity::Warning,
+        description: "DROP TABLE without IF EXISTS, which fails if the table is missing",
+        check: |statement, _| match statement {
+            Statement::DropTable { table_name, if_exists: false } => {
+                vec![format!("DROP TABLE {} fails when the table doesn't exist; add IF EXISTS", table_name)]
+            }
+            _ => Vec::new(),
+        },
+    },
+    Rule {
+        name: "update-without-where",
+        severity: Severity::Error,
+        description: "UPDATE without WHERE, which changes every row",
+        check: |statement, _| match inner(statement) {
+            Statement::Update { table_name, selection: None, .. } => {
+                vec![format!("UPDATE {} has no WHERE, so it changes every row of the table", table_name)]
+            }
+            _ => Vec::new(),
+        },
+    },
+    Rule {
+        name: "delete-without-where",
+        severity: Severity::Error,
+        description: "DELETE without WHERE, which removes every row",
+        check: |statement, _| match inner(statement) {
+            Statement::Delete { table_name, selection: None, .. } => {
+                vec![format!("DELETE FROM {} has no WHERE, so it removes every row of the table", table_name)]
+            }
+            _ => Vec::new(),
+        },
+    },
+    Rule {
+        name: "unbounded-select",
+        severity: Severity::Warning,
+        description: "SELECT without LIMIT from a table configured as large",
+        check: |statement, config| {
+            let mut messages = Vec::new();
+            for select in selects(statement) {
+                if let Statement::Select { limit: None, .. } = select {
+                    for table in from_items(select).filter_map(TableFactor::table_name) {
+                        if config.is_large(table) {
+                            messages.push(format!("{} is large, and this SELECT from it has no LIMIT", table));
+                        }
+                    }
+                }
+            }
+            messages
+        },
+    },
+    Rule {
+        name: "leading-wildcard-like",
+        severity: Severity::Warning,
+        description: "LIKE patterns starting with a wildcard, which no index can narrow",
+        check: |statement, _| {
+            let mut messages = Vec::new();
+            for expr in predicates(statement) {
+                expr.walk(&mut |expr| {
+                    if let Expression::BinaryOperation {
+                        operator: BinaryOperator::Like | BinaryOperator::ILike,
+                        right_operand,
+                        ..
+                    } = expr
+                    {
+                        if matches!(&**right_operand, Expression::String(pattern) if pattern.starts_with(['%', '_'])) {
+                            let sql = expr.to_sql(Dialect::Generic);
+                            messages.push(format!("`{}` starts with a wildcard, so it scans every row", sql));
+                        }
+                    }
+                });
+            }
+            messages
+        },
+    },
+    Rule {
+        name: "function-on-indexed-column",
+        severity: Severity::Warning,
+        description: "functions applied to an indexed column in a condition, which keep the index from being used",
+        check: |statement, config| {
+            let tables = statement.tables();
+            let indexed = |expr: &Expression| match expr {
+                Expression::Identifier(name) => config.is_indexed(&tables, name),
+                Expression::CompoundIdentifier(parts) => {
+                    parts.last().is_some_and(|name| config.is_indexed(&tables, name))
+                }
+                _ => false,
+            };
+            let mut messages = Vec::new();
+            for expr in predicates(statement) {
+                expr.walk(&mut |expr| {
+                    let wrapped = match expr {
+                        Expression::Function { args, over: None, .. } => args.iter().any(indexed),
+                        Expression::Cast { expr, .. } => indexed(expr),
+                        _ => false,
+                    };
+                    if wrapped {
+                        let sql = expr.to_sql(Dialect::Generic);
+                        messages.push(format!("`{}` wraps an indexed column, so the index can't be used", sql));
+                    }
+                });
+            }
+            messages
+        },
+    },
+    Rule {
+        name: "unused-cte",
+        severity: Severity::Warning,
+        description: "CTEs that nothing in the statement reads",
+        check: |statement, _| {
+            let mut messages = Vec::new();
+            for (ctes, body) in withs(statement) {
+                for (i, cte) in ctes.iter().enumerate() {
+                    if !cte_used(ctes, i, body) {
+                        messages.push(format!("the CTE {} is never read; remove it", cte.name));
+                    }
+                }
+            }
+            messages
+        },
+    },
+    Rule {
+        name: "unused-column",
+        severity: Severity::Warning,
+        description: "columns of a CTE or FROM subquery that the query around it never uses",
+        check: |statement, _| {
+            let mut messages = Vec::new();
+            for (ctes, body) in withs(statement) {
+                for (i, cte) in ctes.iter().enumerate() {
+                    // An unread CTE is the other rule's finding
+                    if !cte_used(ctes, i, body) {
+                        continue;
+                    }
+                    let others = ctes.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, cte)| cte.query.as_ref());
+                    let mut outer = Mentions::default();
+                    for query in others.chain([body]) {
+                        selects(query).into_iter().for_each(|select| outer.add(select, true));
+                    }
+                    for column in outer.unused(&cte.columns, &cte.query) {
+                        messages.push(format!("the column {} of the CTE {} is never used", column, cte.name));
+                    }
+                }
+            }
+            for select in selects(statement) {
+                let mut outer = Mentions::default();
+                outer.add(select, false);
+                for factor in from_items(select) {
+                    if let TableFactor::Derived { subquery, alias: Some(alias), .. } = factor {
+                        for column in outer.unused(&alias.columns, subquery) {
+                            let message = format!("the column {} of the subquery {} is never used", column, alias.name);
+                            messages.push(message);
+                        }
+                    }
+                }
+            }
+            messages
+        },
+    },
+    Rule {
+        name: "unused-alias",
+        severity: Severity::Warning,
+        description: "table aliases that no column is qualified with",
+        check: |statement, _| {
+            let mut messages = Vec::new();
+            for select in selects(statement) {
+                // Qualifiers written in the SELECT and anything nested in it
+                let mut qualifiers = Vec::new();
+                for nested in selects(select) {
+                    for expr in nested.expressions() {
+                        expr.walk(&mut |expr| {
+                            if let Expression::CompoundIdentifier(parts) = expr {
+                                qualifiers.extend(parts.iter().rev().skip(1).map(|part| part.as_str()));
+                            }
+                        });
+                    }
+                }
+                for factor in from_items(select) {
+                    // A subquery needs its alias whether it's used or not
+                    let (name, alias) = match factor {
+                        TableFactor::Table { name, alias: Some(alias), .. } => (name.as_str(), alias),
+                        TableFactor::Function { name, alias: Some(alias), .. } => (name.as_str(), alias),
+                        _ => continue,
+                    };
+                    if !qualifiers.iter().any(|qualifier| qualifier.eq_ignore_ascii_case(&alias.name)) {
+                        let message = format!("the alias {} of {} is never qualified with; drop it", alias.name, name);
+                        messages.push(message);
+                    }
+                }
+            }
+            messages
+        },
+    },
+];
+
+/// What every rule finds in `statement`, rule by rule.
+pub fn lint(statement: &Statement) -> Vec<Finding> {
+    lint_with_config(statement, &LintConfig::default())
+}
+
+/// What the rules `config` leaves on find in `statement`, with the
+/// severities it gives them.
+pub fn lint_with_config(statement: &Statement, config: &LintConfig) -> Vec<Finding> {
+    RULES
+        .iter()
+        .filter_map(|rule| config.severity(rule).map(|severity| (rule, severity)))
+        .flat_map(|(rule, severity)| {
+            let found = (rule.check)(statement, config).into_iter();
+            found.map(move |message| Finding { rule: rule.name, severity, message })
+        })
+        .collect()
+}
+
+/// The statement a PREPARE or EXPLAIN is of, or `statement` itself.
+fn inner(statement: &Statement) -> &Statement {
+    match statement {
+        Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => inner(statement),
+        statement => statement,
+    }
+}
+
+/// The conditions rows are filtered or joined by: the WHERE, ON and
+/// QUALIFY clauses of every SELECT in `statement`, and the WHERE of an
+/// UPDATE or DELETE.
+fn predicates(statement: &Statement) -> Vec<&Expression> {
+    let mut exprs = Vec::new();
+    for select in selects(statement) {
+        if let Statement::Select { joins, selection, qualify, .. } = select {
+            exprs.extend(joins.iter().flat_map(|join| &join.on));
+            exprs.extend(selection.iter().chain(qualify));
+        }
+    }
+    if let Statement::Update { selection: Some(selection), .. } | Statement::Delete { selection: Some(selection), .. } =
+        inner(statement)
+    {
+        exprs.push(selection);
+    }
+    exprs
+}
+
+/// Every SELECT in `statement`, outer ones first.
+fn selects(statement: &Statement) -> Vec<&Statement> {
+    fn factor_selects<'a>(factor: &'a TableFactor, out: &mut Vec<&'a Statement>) {
+        match factor {
+            TableFactor::Derived { subquery, .. } => collect(subquery, out),
+            TableFactor::Pivot { table, .. } | TableFactor::Unpivot { table, .. } => factor_selects(table, out),
+            TableFactor::Table { .. } | TableFactor::AsOf { .. } | TableFactor::Function { .. } => {}
+        }
+    }
+    fn collect<'a>(statement: &'a Statement, out: &mut Vec<&'a Statement>) {
+        match statement {
+            Statement::Select { from, joins, .. } => {
+                out.push(statement);
+                factor_selects(from, out);
+                for join in joins {
+                    factor_selects(&join.relation, out);
+                }
+            }
+            Statement::SetOperation { left, right, .. } => {
+                collect(left, out);
+                collect(right, out);
+            }
+            Statement::With { ctes, body, .. } => {
+                for cte in ctes {
+                    collect(&cte.query, out);
+                }
+                collect(body, out);
+            }
+            Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => collect(statement, out),
+            Statement::Merge { source: MergeSource::Query(query), .. } => collect(query, out),
+            _ => {}
+        }
+    }
+    let mut out = Vec::new();
+    collect(statement, &mut out);
+    out
+}
+
+/// The CTEs and body of every WITH in `statement`.
+fn withs(statement: &Statement) -> Vec<(&[Cte], &Statement)> {
+    let mut out = Vec::new();
+    let mut queries = vec![statement];
+    for select in selects(statement) {
+        queries.extend(from_items(select).filter_map(|factor| match factor {
+            TableFactor::Derived { subquery, .. } => Some(subquery.as_ref()),
+            _ => None,
+        }));
+    }
+    while let Some(query) = queries.pop() {
+        match query {
+            Statement::With { ctes, body, .. } => {
+                out.push((ctes.as_slice(), body.as_ref()));
+                queries.extend(ctes.iter().map(|cte| cte.query.as_ref()));
+                queries.push(body);
+            }
+            Statement::SetOperation { left, right, .. } => queries.extend([left.as_ref(), right.as_ref()]),
+            Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => queries.push(statement),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// The FROM item and the joined ones of a SELECT.
+fn from_items(select: &Statement) -> impl Iterator<Item = &TableFactor> {
+    let (from, joins) = match select {
+        Statement::Select { from, joins, .. } => (Some(from.as_ref()), joins.as_slice()),
+        _ => (None, &[][..]),
+    };
+    from.into_iter().chain(joins.iter().map(|join| &join.relation))
+}
+
+/// Whether the body or another of `ctes` reads `ctes[i]`; the CTE reading
+/// itself, as a recursive one does, doesn't count.
+fn cte_used(ctes: &[Cte], i: usize, body: &Statement) -> bool {
+    let name = ctes[i].name.as_str();
+    body.references_table(name) || ctes.iter().enumerate().any(|(j, cte)| j != i && cte.query.references_table(name))
+}
+
+/// The column names SELECTs mention, and whether one of them selects `*`,
+/// which uses every column there is.
+#[derive(Default)]
+struct Mentions<'a> {
+    names: Vec<&'a str>,
+    star: bool,
+}
+
+impl<'a> Mentions<'a> {
+    /// Adds the names in `select`'s list, ORDER BY and expressions; those in
+    /// its FROM subqueries too when `nested`.
+    fn add(&mut self, select: &'a Statement, nested: bool) {
+        let Statement::Select { columns, wildcard, joins, selection, group_by, qualify, order_by, .. } = select else {
+            return;
+        };
+        self.star |= columns.iter().any(SelectItem::is_wildcard);
+        let exprs: Vec<&Expression> = if nested {
+            select.expressions()
+        } else {
+            let mut exprs: Vec<&Expression> =
+                columns.iter().filter(|item| !item.is_wildcard()).map(|item| &item.expr).collect();
+            exprs.extend(wildcard.replace.iter().map(|(expr, _)| expr));
+            exprs.extend(joins.iter().flat_map(|join| &join.on));
+            exprs.extend(selection.iter().chain(group_by.iter().flat_map(|element| element.expressions())));
+            exprs.extend(qualify);
+            exprs.extend(order_by.iter().map(|item| &item.expr));
+            exprs
+        };
+        for expr in exprs {
+            expr.walk(&mut |expr| match expr {
+                Expression::Identifier(name) => self.names.push(name),
+                Expression::CompoundIdentifier(parts) => self.names.extend(parts.last().map(|part| part.as_str())),
+                _ => {}
+            });
+        }
+    }
+
+    /// The columns of `query`, named by `renamed` when it isn't empty, that
+    /// none of the mentions use. A query whose columns aren't listed, or
+    /// one read with `*`, has none.
+    fn unused(&self, renamed: &'a [Ident], query: &'a Statement) -> Vec<&'a str> {
+        if self.star {
+            return Vec::new();
+        }
+        let columns: Vec<&str> = match query {
+            _ if !renamed.is_empty() => renamed.iter().map(|column| column.as_str()).collect(),
+            // Of the columns with a name
+            Statement::Select { columns, .. } if !columns.iter().any(SelectItem::is_wildcard) => {
+                columns.iter().filter_map(|item| item.alias.as_ref().or(item.as_column())).map(Ident::as_str).collect()
+            }
+            _ => return Vec::new(),
+        };
+        columns.into_iter().filter(|column| !self.names.iter().any(|name| name.eq_ignore_ascii_case(column))).collect()
+    }
+}
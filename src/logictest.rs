@@ -0,0 +1,209 @@
+// logictest.rs — runs sqllogictest files against the in-memory engine
+// Author: Sreerag Devadasan
+//
+// Supported records:
+//   * `statement ok` / `statement error`: the SQL must succeed or fail;
+//   * `query <types> [nosort|rowsort|valuesort]` (or `query error`): the
+//     SQL must succeed and, after `----`, produce the listed values;
+//   * `skipif <engine>` / `onlyif <engine>` before a record, where this
+//     engine's name is "mini";
+//   * `halt` stops the file; `hash-threshold` and other control lines are
+//     ignored.
+// Values are compared one by one, so results may be written one value per
+// line or one row per line. NULL prints as "NULL", the empty string as
+// "(empty)", booleans as "true"/"false". Every file runs against a fresh
+// database.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::engine::{Database, QueryResult};
+use crate::eval::Value;
+
+/// The engine name matched by `skipif` and `onlyif`.
+pub const ENGINE_NAME: &str = "mini";
+
+/// How query results are ordered before comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    NoSort,
+    RowSort,
+    ValueSort,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expectation {
+    Ok,
+    Error,
+    Rows { types: String, sort: SortMode, values: Vec<String> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Record {
+    /// `file:line` of the record header.
+    location: String,
+    sql: String,
+    expect: Expectation,
+}
+
+/// Records passed and the location and reason of every failure.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogicTestReport {
+    pub file: String,
+    pub passed: usize,
+    pub failures: Vec<(String, String)>,
+}
+
+impl LogicTestReport {
+    pub fn total(&self) -> usize {
+        self.passed + self.failures.len()
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for LogicTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (location, reason) in &self.failures {
+            writeln!(f, "❌ {}: {}", location, reason)?;
+        }
+        write!(f, "{}: {}/{} records passed", self.file, self.passed, self.total())
+    }
+}
+
+/// Reads and runs one sqllogictest file.
+pub fn run_file(path: impl AsRef<Path>) -> io::Result<LogicTestReport> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)?;
+    Ok(run(&path.display().to_string(), &text))
+}
+
+/// Runs the records of `text` (named `name` in failure locations).
+pub fn run(name: &str, text: &str) -> LogicTestReport {
+    let mut database = Database::new();
+    let mut report = LogicTestReport { file: name.to_string(), ..Default::default() };
+    for record in parse_records(name, text) {
+        match check(&mut database, &record) {
+            Ok(()) => report.passed += 1,
+            Err(reason) => report.failures.push((record.location, reason)),
+        }
+    }
+    report
+}
+
+fn check(database: &mut Database, record: &Record) -> Result<(), String> {
+    let result = database.execute_sql(&record.sql);
+    match (&record.expect, result) {
+        (Expectation::Ok, Ok(_)) | (Expectation::Error, Err(_)) => Ok(()),
+        (Expectation::Ok | Expectation::Rows { .. }, Err(e)) => Err(e.to_string()),
+        (Expectation::Error, Ok(_)) => Err("expected an error, but it succeeded".to_string()),
+        (Expectation::Rows { types, sort, values }, Ok(result)) => {
+            if !types.is_empty() && types.len() != result.columns.len() {
+                return Err(format!("expected {} columns, found {}", types.len(), result.columns.len()));
+            }
+            let actual = render(&result, *sort);
+            if &actual == values {
+                Ok(())
+            } else {
+                Err(format!("expected [{}], found [{}]", values.join(" "), actual.join(" ")))
+            }
+        }
+    }
+}
+
+/// Flattens a result into its values in comparison order.
+fn render(result: &QueryResult, sort: SortMode) -> Vec<String> {
+    let mut rows: Vec<Vec<String>> =
+        result.rows.iter().map(|row| row.iter().map(format_value).collect()).collect();
+    if sort == SortMode::RowSort {
+        rows.sort();
+    }
+    let mut values: Vec<String> = rows.into_iter().flatten().flat_map(split_words).collect();
+    if sort == SortMode::ValueSort {
+        values.sort();
+    }
+    values
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Int(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::String(s) if s.is_empty() => "(empty)".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Date(date) => date.to_string(),
+        Value::Time(time) => time.to_string(),
+        Value::Timestamp(timestamp) => timestamp.to_string(),
+        Value::Decimal(decimal) => decimal.to_string(),
+    }
+}
+
+/// Values are compared word by word, as the expected results are.
+fn split_words(value: String) -> Vec<String> {
+    value.split_whitespace().map(str::to_string).collect()
+}
+
+fn parse_records(name: &str, text: &str) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut lines = text.lines().enumerate().peekable();
+    let mut skip = false;
+
+    while let Some((number, line)) = lines.next() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let expect = match words.as_slice() {
+            ["halt", ..] if !skip => break,
+            ["skipif", engine, ..] => {
+                skip |= *engine == ENGINE_NAME;
+                continue;
+            }
+            ["onlyif", engine, ..] => {
+                skip |= *engine != ENGINE_NAME;
+                continue;
+            }
+            ["statement", "error", ..] | ["query", "error", ..] => Expectation::Error,
+            ["statement", ..] => Expectation::Ok,
+            ["query", rest @ ..] => Expectation::Rows {
+                types: rest.first().map_or(String::new(), |t| t.to_string()),
+                sort: match rest.get(1) {
+                    Some(&"rowsort") => SortMode::RowSort,
+                    Some(&"valuesort") => SortMode::ValueSort,
+                    _ => SortMode::NoSort,
+                },
+                values: Vec::new(),
+            },
+            // Blank lines, comments and other control records
+            _ => {
+                if words.is_empty() {
+                    skip = false;
+                }
+                continue;
+            }
+        };
+
+        let mut sql = String::new();
+        while let Some((_, next)) = lines.next_if(|(_, l)| !l.trim().is_empty() && l.trim() != "----") {
+            sql.push_str(next);
+            sql.push('\n');
+        }
+        let mut results = Vec::new();
+        if lines.next_if(|(_, l)| l.trim() == "----").is_some() {
+            while let Some((_, next)) = lines.next_if(|(_, l)| !l.trim().is_empty()) {
+                results.extend(next.split_whitespace().map(str::to_string));
+            }
+        }
+        let expect = match expect {
+            Expectation::Rows { types, sort, .. } => Expectation::Rows { types, sort, values: results },
+            other => other,
+        };
+
+        if !std::mem::take(&mut skip) {
+            records.push(Record { location: format!("{}:{}", name, number + 1), sql, expect });
+        }
+    }
+    records
+}
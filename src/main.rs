@@ -1,36 +1,260 @@
-// Import project modules
-mod tokenizer;
-mod pratt;
-mod parser;
-mod ast;
-
 // Import standard IO for reading user input and flushing output
-use std::io::{self, Write};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process;
+use std::sync::OnceLock;
+#[cfg(feature = "engine")]
+use std::thread;
+use std::time::{Duration, Instant};
 
-// Import the tokenizer components
-use tokenizer::{Tokenizer, Token};
+// Import the tokenizer's types from the library crate
+use sreerag_devadasan::tokenizer::{ParseError, Progress, Token};
 
 // Import the SQLParser to parse the tokens into SQL AST
-use parser::SQLParser;
+use sreerag_devadasan::parser::SQLParser;
+use sreerag_devadasan::session::ParserOptions;
+
+// Import the parsing profiles, `--strict` choosing the strict one
+use sreerag_devadasan::profile::Profile;
+
+// Import the REPL's settings and history
+use sreerag_devadasan::ast::Statement;
+use sreerag_devadasan::repl::{self, ColorMode, History, OutputMode, ReplConfig};
+
+// Import completion, which the REPL's TAB key runs
+use sreerag_devadasan::complete::tab;
+
+// Import the analyzer, which checks names against the REPL's schema
+use sreerag_devadasan::analyzer::{analyze, Problem, Registry};
+
+// Import the description of the command line, for parsing arguments,
+// the usage text and the `completions` and `man` subcommands
+use sreerag_devadasan::cli::{self, Matches, Shell};
+
+// Import the styling of output, set by `--color` and `--theme`
+use sreerag_devadasan::span::error_span;
+use sreerag_devadasan::style::{Role, Styler, Theme};
+
+// Import the anonymizer used by the `anonymize` subcommand
+use sreerag_devadasan::anonymize::{anonymize_sql, AnonymizeMode};
+
+// Import the minifier for the `minify` subcommand
+use sreerag_devadasan::canonical::{minify, minify_sql};
+
+// Import the code generator for the `codegen` subcommand
+use sreerag_devadasan::codegen::sql_to_rust;
+
+// Import the parser entry point for the `graph` subcommand, which draws
+// the parsed statements with `Statement::to_dot` and `to_mermaid`
+use sreerag_devadasan::parser::parse_sql_with_dialect;
+
+// Import the planner and statistics for the `explain` subcommand
+use sreerag_devadasan::planner::plan;
+use sreerag_devadasan::stats::Statistics;
+
+// Import the conformance labels of the `features` subcommand
+use sreerag_devadasan::standard::conformance;
+
+// Import dialects for the `translate` subcommand
+use sreerag_devadasan::dialect::{translate, Dialect};
+
+// Import the migration checker for the `migrations` subcommand
+use sreerag_devadasan::migrations::{analyze_directory, MigrationOptions};
+
+// Import the schema differ for the `schema-diff` subcommand
+use sreerag_devadasan::schema_diff::{diff, Schema};
+
+// Import the workload analysis for the `dead-code` subcommand
+use sreerag_devadasan::dead_code::analyze_workload;
+
+// Import the column lineage of the `lineage` subcommand
+use sreerag_devadasan::lineage::lineage;
+
+// Import the view expansion of the `inline-views` subcommand
+use sreerag_devadasan::views::expand_views;
+
+// Import the query differ for the `query-diff` subcommand
+use sreerag_devadasan::query_diff;
+
+// Import the corpus runner for the `conformance` subcommand
+use sreerag_devadasan::conformance;
+
+// Import the sqllogictest runner for the `logictest` subcommand
+#[cfg(feature = "engine")]
+use sreerag_devadasan::logictest;
+
+// Import the script report for the `check` subcommand
+use sreerag_devadasan::report::{report_script, report_script_with_progress, ScriptReport};
+use sreerag_devadasan::session::ParserSession;
+
+// Import memory-mapped input for `check --mmap`
+#[cfg(all(feature = "mmap", unix))]
+use sreerag_devadasan::mmap::MappedFile;
+
+// Import the lint rules and their settings for the `lint` subcommand
+use sreerag_devadasan::lint::{lint_with_config, LintConfig, Severity};
+
+// Import the HTML report for the `html-report` subcommand
+use sreerag_devadasan::html::html_report;
+
+// Import the template expansion for the `render` subcommand
+use sreerag_devadasan::template::{render, TemplateContext};
+
+// Import the fake Postgres server for the `pg-server` subcommand
+#[cfg(feature = "engine")]
+use sreerag_devadasan::engine::Database;
+#[cfg(feature = "engine")]
+use sreerag_devadasan::pgwire;
+
+// Import the fake MySQL server for the `mysql-server` subcommand
+#[cfg(feature = "engine")]
+use sreerag_devadasan::mysqlwire;
+
+// Import the metrics endpoint for the server subcommands' `--metrics` option
+#[cfg(feature = "engine")]
+use sreerag_devadasan::metrics::{self, Metrics};
+
+// Import the durable database for the `engine` subcommand
+#[cfg(feature = "engine")]
+use sreerag_devadasan::wal::DurableDatabase;
+
+// Import the log analyzer for the `log-stats` subcommand
+use sreerag_devadasan::querylog::{self, LogFormat};
+
+// Import the exercises of the `tutorial` subcommand
+use sreerag_devadasan::tutorial::{Feedback, Tutorial, EXERCISES};
 
 /// Entry point for the Mini SQL Parser CLI application.
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let matches = cli::parse(&args).unwrap_or_else(|e| {
+        eprintln!("❌ {}", e);
+        usage();
+    });
+    if matches.flag("help") {
+        print!("{}", cli::usage());
+        return;
+    }
+
+    // With the "trace" feature, SQL_TRACE=1 prints the parse call tree to stderr
+    #[cfg(feature = "trace")]
+    if env::var_os("SQL_TRACE").is_some() && !matches.flag("trace") {
+        sreerag_devadasan::trace::set_subscriber(sreerag_devadasan::trace::stderr_subscriber());
+    }
+    #[cfg(not(feature = "trace"))]
+    if matches.flag("trace") {
+        eprintln!("❌ --trace needs a build with the trace feature: cargo build --features trace");
+        process::exit(2);
+    }
+
+    // Only the REPL reads the settings file; --color and --theme apply to
+    // every command, over the settings
+    let settings = if matches.command.is_none() { ReplConfig::load() } else { ReplConfig::from_env() };
+    let mut config = settings.unwrap_or_else(|e| {
+        eprintln!("❌ Couldn't read the settings: {}", e);
+        process::exit(1);
+    });
+    config.color = matches.value("color").and_then(ColorMode::from_name).unwrap_or(config.color);
+    config.theme = matches.value("theme").and_then(Theme::from_name).unwrap_or(config.theme);
+    let _ = STYLER.set(config.styler());
+
+    match matches.command {
+        None => run_repl(config, matches.flag("trace"), profile(&matches)),
+        Some("anonymize") => run_anonymize(&matches),
+        Some("translate") => run_translate(&matches),
+        Some("minify") => run_minify(&matches),
+        Some("codegen") => run_codegen(&matches),
+        Some("graph") => run_graph(&matches),
+        Some("lineage") => run_lineage(&matches),
+        Some("inline-views") => run_inline_views(&matches),
+        Some("explain") => run_explain(&matches),
+        Some("features") => run_features(&matches),
+        Some("migrations") => run_migrations(&matches),
+        Some("schema-diff") => run_schema_diff(&matches),
+        Some("dead-code") => run_dead_code(&matches),
+        Some("query-diff") => run_query_diff(&matches),
+        Some("conformance") => run_conformance(&matches),
+        #[cfg(feature = "engine")]
+        Some("logictest") => run_logictest(&matches),
+        Some("check") => run_check(&matches),
+        Some("lint") => run_lint(&matches),
+        Some("html-report") => run_html_report(&matches),
+        Some("render") => run_render(&matches),
+        #[cfg(feature = "engine")]
+        Some("pg-server") => run_pg_server(&matches),
+        #[cfg(feature = "engine")]
+        Some("mysql-server") => run_mysql_server(&matches),
+        #[cfg(feature = "engine")]
+        Some("engine") => run_engine(&matches),
+        #[cfg(not(feature = "engine"))]
+        Some(command @ ("logictest" | "pg-server" | "mysql-server" | "engine")) => {
+            eprintln!("❌ {} needs a build with the engine feature: cargo build --features engine", command);
+            process::exit(2);
+        }
+        Some("log-stats") => run_log_stats(&matches),
+        Some("tutorial") => run_tutorial(&matches),
+        Some("completions") => run_completions(&matches),
+        Some("man") => print!("{}", cli::man_page()),
+        Some(_) => usage(),
+    }
+}
+
+/// Prints command-line usage and exits with an error code.
+fn usage() -> ! {
+    eprint!("{}", cli::usage());
+    process::exit(2);
+}
+
+/// The parsing profile the command line asks for.
+fn profile(matches: &Matches) -> Profile {
+    if matches.flag("strict") {
+        Profile::Strict
+    } else {
+        Profile::Dialect
+    }
+}
+
+/// Interactive read-parse-print loop. With `trace`, each statement is
+/// preceded by the tree of parser decisions that produced it; with the
+/// strict profile, followed by warnings about its nonstandard SQL.
+fn run_repl(mut config: ReplConfig, trace: bool, profile: Profile) {
+    // Load the schema files that statements are checked against
+    let mut schema = Schema::default();
+    for path in &config.schema {
+        match Schema::load(path) {
+            Ok(loaded) => {
+                schema.tables.extend(loaded.tables);
+                schema.views.extend(loaded.views);
+            }
+            Err(e) => {
+                eprintln!("❌ Couldn't load the schema {}: {}", path.display(), e);
+                process::exit(1);
+            }
+        }
+    }
+    let styler = styler();
+    let mut history = History::open(config.history_size);
+
     // Greeting message
     println!("🔷Welcome to the Mini SQL Parser command-line tool");
-    println!("Enter your SQL query below, or type 'exit' to leave.\n");
+    println!("Enter your SQL query below, 'history' to list earlier ones, or 'exit' to leave.");
+    println!("TAB completes keywords, and the tables and columns of the schema.");
+    println!("'output ast' shows trees as Debug instead of s-expressions, and 'output sexp' switches back.\n");
 
     // Begin a REPL-style input loop
     loop {
-        // Prompt the user for input
-        print!("sql> ");
-        io::stdout().flush().unwrap(); // Flush to ensure prompt is displayed
-
-        // Read the input query from the user
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
-            eprintln!("❌Couldn't read the input."); // Handle read error
-            continue;
-        }
+        // Prompt the user for input, TAB completing from the schema
+        let prompt = styler.paint(Role::Prompt, &config.prompt);
+        let dialect = config.dialect;
+        let input = match repl::read_line(&prompt, &mut |line| tab(line, line.len(), &schema, dialect)) {
+            Ok(Some(input)) => input,
+            Ok(None) => break, // End of input (Ctrl-D or closed pipe)
+            Err(_) => {
+                eprintln!("❌Couldn't read the input."); // Handle read error
+                continue;
+            }
+        };
 
         // Trim whitespace from input and check for exit command
         let input = input.trim();
@@ -41,35 +265,763 @@ fn main() {
             println!("👋 closing the SQL parser. Bye!");
             break; // Exit the loop and end the program
         }
-
-        // Tokenize the user input into a list of SQL tokens
-        let mut tokenizer = Tokenizer::new(input);
-        let mut tokens = Vec::new();
-
-        loop {
-            let token = tokenizer.next_token();
-            if token == Token::Eof {
-                tokens.push(token); // Push EOF token and break
-                break;
+        if input.eq_ignore_ascii_case("history") {
+            for (i, line) in history.entries().enumerate() {
+                println!("{:>5}  {}", i + 1, line);
+            }
+            continue;
+        }
+        // `output <mode>` changes how statements are shown, for the rest of the session
+        if let Some(name) = input.strip_prefix("output ").or_else(|| input.strip_prefix("OUTPUT ")) {
+            match OutputMode::from_name(name.trim()) {
+                Some(mode) => config.output = mode,
+                None => eprintln!("❌ Unknown output mode {} (expected sexp, ast, sql or minified)", name.trim()),
             }
-            tokens.push(token); // Push valid token to token list
+            continue;
         }
+        history.push(input);
+
+        // Tokenize the user input into a list of SQL tokens
+        let session = ParserSession::new(ParserOptions { dialect: config.dialect, profile, ..Default::default() });
+        let tokens = session.tokenize(input);
 
         // Optional: Uncomment to debug tokens
         // println!("🔹 Tokens: {:?}", tokens);
 
         // Parse the tokens into a SQL AST (Abstract Syntax Tree)
-        let mut parser = SQLParser::new(&tokens);
-        match parser.parse_statement() {
+        let parsed = tokens.and_then(|tokens| {
+            let mut parser = SQLParser::with_options(&tokens, *session.options());
+            if trace { traced_parse(&mut parser, &tokens) } else { parser.parse_to_end() }
+        });
+        match parsed {
             Ok(statement) => {
-                // Successfully parsed SQL statement
-                println!("✅ Processed Statement:\n{:#?}\n", statement);
+                // Successfully parsed SQL statement, shown as the settings say
+                let shown = match config.output {
+                    OutputMode::Sexp => statement.to_sexp(),
+                    OutputMode::Ast => format!("{:#?}", statement),
+                    OutputMode::Sql => styler.highlight(&statement.to_sql(config.dialect), config.dialect),
+                    OutputMode::Minified => styler.highlight(&minify(&statement, config.dialect), config.dialect),
+                };
+                println!("{}\n{}\n", styler.paint(Role::Success, "✅ Processed Statement:"), shown);
+                for warning in session.warnings(input) {
+                    eprintln!("{}", styler.paint(Role::Warning, &format!("⚠️ {}", warning.message)));
+                    eprintln!("  {}", styler.underline(input, warning.span).replace('\n', "\n  "));
+                }
+
+                // Warn about tables and columns the loaded schema doesn't
+                // have, then let CREATE and DROP TABLE update it
+                if !config.schema.is_empty() {
+                    for problem in analyze(&statement, &Registry::new(), Some(&schema)) {
+                        let naming = matches!(
+                            problem,
+                            Problem::UnknownTable { .. }
+                                | Problem::UnknownColumn { .. }
+                                | Problem::DuplicateAlias(_)
+                                | Problem::AmbiguousColumn { .. }
+                                | Problem::ShadowedTable(_)
+                        );
+                        if naming {
+                            eprintln!("{}", styler.paint(Role::Warning, &format!("⚠️ {}", problem)));
+                        }
+                    }
+                }
+                schema.apply(&statement);
             }
             Err(e) => {
-                // Error while parsing SQL
-                eprintln!("❌ Parse Error: {}\n", e);
+                // Error while parsing SQL, pointing at where the parse stopped
+                eprintln!("{}", styler.paint(Role::Error, &format!("❌ Parse Error: {}", e)));
+                if let Some(span) = error_span(input, config.dialect) {
+                    eprintln!("  {}", styler.underline(input, span).replace('\n', "\n  "));
+                }
+                eprintln!();
+            }
+        }
+    }
+
+    if let Err(e) = history.save() {
+        eprintln!("❌ Couldn't save the history: {}", e);
+    }
+}
+
+/// Parses to the end, printing the trace tree of the parse first.
+#[cfg(feature = "trace")]
+fn traced_parse(parser: &mut SQLParser, tokens: &[Token]) -> Result<Statement, ParseError> {
+    use sreerag_devadasan::trace;
+    let (parsed, events) = trace::collect(|| parser.parse_to_end());
+    println!("{}\n{}", styler().paint(Role::Header, "🔍 Parse trace:"), trace::render_tree(&events, tokens));
+    parsed
+}
+
+/// Without the "trace" feature `--trace` is refused at start, so this is
+/// never reached.
+#[cfg(not(feature = "trace"))]
+fn traced_parse(parser: &mut SQLParser, _tokens: &[Token]) -> Result<Statement, ParseError> {
+    parser.parse_to_end()
+}
+
+static STYLER: OnceLock<Styler> = OnceLock::new();
+
+/// How output is colored, as set up at start.
+fn styler() -> Styler {
+    STYLER.get().copied().unwrap_or_default()
+}
+
+/// Walks through the tutorial's exercises, checking each answer read from
+/// stdin. Besides SQL, an answer may be 'hint' for the solution, 'skip' to
+/// move on without answering, or 'exit'.
+fn run_tutorial(matches: &Matches) {
+    let dialect = matches.positional(0).and_then(Dialect::from_name).unwrap_or_default();
+    let styler = styler();
+    let mut tutorial = Tutorial::new(dialect);
+    println!("🔷Welcome to the SQL tutorial: {} exercises, one concept at a time.", EXERCISES.len());
+    println!("Answer with a SQL statement, 'hint' to see a solution, 'skip' to move on, or 'exit' to leave.");
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut shown = None;
+    while let Some(exercise) = tutorial.current() {
+        if shown != Some(tutorial.stage()) {
+            shown = Some(tutorial.stage());
+            let heading = format!("\n📘 Lesson {}/{}: {}", tutorial.stage() + 1, EXERCISES.len(), exercise.concept);
+            println!("{}", styler.paint(Role::Header, &heading));
+            println!("{}\n👉 {}", exercise.lesson, exercise.task);
+        }
+        print!("{}", styler.paint(Role::Prompt, "tutorial> "));
+        io::stdout().flush().unwrap();
+        let Some(Ok(line)) = lines.next() else {
+            println!();
+            return;
+        };
+        let input = line.trim();
+        match input.to_ascii_lowercase().as_str() {
+            "" => continue,
+            "exit" => return,
+            "skip" => {
+                tutorial.skip();
+                continue;
+            }
+            "hint" => {
+                println!("💡 One answer: {}", styler.highlight(exercise.solution, dialect));
+                continue;
+            }
+            _ => {}
+        }
+        match tutorial.submit(input) {
+            Feedback::Correct => println!("{}", styler.paint(Role::Success, "✅ Correct!")),
+            Feedback::Incorrect(missing) => {
+                println!("{}", styler.paint(Role::Warning, &format!("🤔 Not yet: {}", missing)));
+            }
+            Feedback::ParseError { message, hint, span } => {
+                println!("{}", styler.paint(Role::Error, &format!("❌ Parse Error: {}", message)));
+                if let Some(span) = span {
+                    println!("  {}", styler.underline(input, span).replace('\n', "\n  "));
+                }
+                println!("💡 {}", hint);
             }
         }
     }
+    println!("\n🎉 That's every exercise. Well done!");
+}
+
+/// Reads newline-delimited queries from stdin and prints each one with its
+/// literals anonymized.
+fn run_anonymize(matches: &Matches) {
+    let mode = if matches.flag("hash") {
+        AnonymizeMode::Hashed
+    } else {
+        AnonymizeMode::Placeholders
+    };
+
+    for_each_input_line(|line| anonymize_sql(line, mode).map_err(|e| e.to_string()));
+}
+
+/// Reads newline-delimited queries from stdin and re-emits each one in
+/// another dialect, e.g. `translate mysql postgres`.
+fn run_translate(matches: &Matches) {
+    let dialect = |index| matches.positional(index).and_then(Dialect::from_name).unwrap_or_else(|| usage());
+    let (from, to) = (dialect(0), dialect(1));
+
+    for_each_input_line(|line| translate(line, from, to).map(|sql| styler().highlight(&sql, to)).map_err(|e| e.to_string()));
+}
+
+/// Reads newline-delimited queries from stdin and prints each one minified,
+/// in the given dialect (generic by default).
+fn run_minify(matches: &Matches) {
+    let dialect = matches.positional(0).map_or(Some(Dialect::Generic), Dialect::from_name).unwrap_or_else(|| usage());
+
+    for_each_input_line(|line| {
+        minify_sql(line, dialect).map(|sql| styler().highlight(&sql, dialect)).map_err(|e| e.to_string())
+    });
+}
+
+/// Reads newline-delimited queries from stdin and prints the Rust code that
+/// constructs each one's AST, parsed in the given dialect (generic by default).
+fn run_codegen(matches: &Matches) {
+    let dialect = matches.positional(0).map_or(Some(Dialect::Generic), Dialect::from_name).unwrap_or_else(|| usage());
+
+    for_each_input_line(|line| sql_to_rust(line, dialect).map_err(|e| e.to_string()));
+}
+
+/// Reads newline-delimited queries from stdin and prints the AST of each as
+/// a DOT digraph, or a Mermaid flowchart with `--format mermaid`.
+fn run_graph(matches: &Matches) {
+    let dialect = matches.positional(0).map_or(Some(Dialect::Generic), Dialect::from_name).unwrap_or_else(|| usage());
+    let mermaid = matches.value("format").is_some_and(|format| format.eq_ignore_ascii_case("mermaid"));
+
+    for_each_input_line(|line| {
+        let statement = parse_sql_with_dialect(line, dialect).map_err(|e| e.to_string())?;
+        let graph = if mermaid { statement.to_mermaid() } else { statement.to_dot() };
+        Ok(graph.trim_end().to_string())
+    });
+}
+
+/// Reads newline-delimited queries from stdin and prints, for each output
+/// column, the table columns it derives from, or the whole lineage graph
+/// as DOT or JSON.
+fn run_lineage(matches: &Matches) {
+    let dialect = matches.positional(0).map_or(Some(Dialect::Generic), Dialect::from_name).unwrap_or_else(|| usage());
+    let schema = matches.value("schema").map(|path| {
+        Schema::load(path).unwrap_or_else(|e| {
+            eprintln!("❌ Couldn't load schema {}: {}", path, e);
+            process::exit(1);
+        })
+    });
+    let format = matches.value("format").unwrap_or("text").to_ascii_lowercase();
+
+    for_each_input_line(|line| {
+        let statement = parse_sql_with_dialect(line, dialect).map_err(|e| e.to_string())?;
+        let lineage = lineage(&statement, schema.as_ref())
+            .ok_or_else(|| format!("{} isn't a query, INSERT ... SELECT or CREATE VIEW", statement.kind()))?;
+        Ok(match format.as_str() {
+            "dot" => lineage.to_dot().trim_end().to_string(),
+            "json" => lineage.to_json(),
+            _ => lineage.to_string(),
+        })
+    });
 }
 
+/// Reads newline-delimited queries from stdin and prints each with the
+/// views of the schema it reads replaced by their queries.
+fn run_inline_views(matches: &Matches) {
+    let path = matches.positional(0).unwrap_or_else(|| usage());
+    let dialect = matches.positional(1).map_or(Some(Dialect::Generic), Dialect::from_name).unwrap_or_else(|| usage());
+    let schema = Schema::load(path).unwrap_or_else(|e| {
+        eprintln!("❌ Couldn't load schema {}: {}", path, e);
+        process::exit(1);
+    });
+
+    for_each_input_line(|line| {
+        let statement = parse_sql_with_dialect(line, dialect).map_err(|e| e.to_string())?;
+        let expanded = expand_views(&statement, &schema).map_err(|e| e.to_string())?;
+        Ok(expanded.to_sql(dialect))
+    });
+}
+
+/// Reads newline-delimited queries from stdin and prints, for each, the
+/// feature of the SQL standard each of its constructs needs and the
+/// conformance level it needs overall.
+fn run_features(matches: &Matches) {
+    let dialect = matches.positional(0).map_or(Some(Dialect::Generic), Dialect::from_name).unwrap_or_else(|| usage());
+
+    for_each_input_line(|line| conformance(line, dialect).map(|report| report.to_string()).map_err(|e| e.to_string()));
+}
+
+/// Prints the plan of each stdin line, estimated with the statistics of
+/// `--stats` or with the planner's defaults.
+fn run_explain(matches: &Matches) {
+    let dialect = matches.positional(0).map_or(Some(Dialect::Generic), Dialect::from_name).unwrap_or_else(|| usage());
+    let statistics = match matches.value("stats") {
+        None => Statistics::new(),
+        Some(path) => match fs::read_to_string(path).map_err(|e| e.to_string()) {
+            Ok(text) => Statistics::parse(&text).unwrap_or_else(|e| {
+                eprintln!("❌ Couldn't read {}: {}", path, e);
+                process::exit(1);
+            }),
+            Err(e) => {
+                eprintln!("❌ Couldn't read {}: {}", path, e);
+                process::exit(1);
+            }
+        },
+    };
+
+    for_each_input_line(|line| {
+        let statement = parse_sql_with_dialect(line, dialect).map_err(|e| e.to_string())?;
+        let plan = plan(&statement, &statistics).ok_or_else(|| format!("{} isn't a query", statement.kind()))?;
+        Ok(plan.to_string().trim_end().to_string())
+    });
+}
+
+/// Checks a directory of numbered migration files and prints a report,
+/// exiting with status 1 if any file has problems.
+fn run_migrations(matches: &Matches) {
+    let Some(dir) = matches.positional(0) else { usage() };
+    let options = MigrationOptions { ddl_only: matches.flag("ddl-only") };
+
+    let report = match analyze_directory(dir, &options) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("❌ Couldn't read {}: {}", dir, e);
+            process::exit(1);
+        }
+    };
+
+    if matches.flag("json") {
+        println!("{}", report.to_json());
+    } else {
+        println!("{}", report);
+    }
+    if !report.is_ok() {
+        process::exit(1);
+    }
+}
+
+/// Parses external corpora (sqllogictest or plain `.sql` files) and prints
+/// the pass rate of each statement category.
+fn run_conformance(matches: &Matches) {
+    let mut entries = Vec::new();
+    for path in &matches.positionals {
+        match conformance::load(path) {
+            Ok(loaded) => entries.extend(loaded),
+            Err(e) => {
+                eprintln!("❌ Couldn't read {}: {}", path, e);
+                process::exit(1);
+            }
+        }
+    }
+    let report = conformance::run(&entries);
+
+    if matches.flag("json") {
+        println!("{}", report.to_json());
+    } else {
+        println!("{}", report);
+    }
+    if matches.flag("failures") {
+        for (location, error) in &report.failures {
+            println!("❌ {}: {}", location, error);
+        }
+    }
+}
+
+/// Executes sqllogictest files against the in-memory engine, exiting with
+/// status 1 if any record fails.
+#[cfg(feature = "engine")]
+fn run_logictest(matches: &Matches) {
+    let mut ok = true;
+    for path in &matches.positionals {
+        match logictest::run_file(path) {
+            Ok(report) => {
+                println!("{}", report);
+                ok &= report.is_ok();
+            }
+            Err(e) => {
+                eprintln!("❌ Couldn't read {}: {}", path, e);
+                process::exit(1);
+            }
+        }
+    }
+    if !ok {
+        process::exit(1);
+    }
+}
+
+/// Parses every statement of each script and reports the ones that fail,
+/// exiting with status 1 if any do.
+fn run_check(matches: &Matches) {
+    let session = ParserSession::new(ParserOptions { profile: profile(matches), ..ParserOptions::default() });
+    let mut ok = true;
+    for path in &matches.positionals {
+        ok &= with_script(path, matches.flag("mmap"), |script| {
+            let report = check_script(&session, path, script, matches.flag("progress"));
+            if matches.flag("json") {
+                println!("{}", report.to_json());
+            } else {
+                println!("{}\n{}", path, report);
+            }
+            report.is_ok()
+        });
+    }
+    if !ok {
+        process::exit(1);
+    }
+}
+
+/// Calls `check` with the text of the file at `path`: mapped into memory
+/// with `mmap`, read into a string otherwise.
+fn with_script(path: &str, mmap: bool, check: impl FnOnce(&str) -> bool) -> bool {
+    let failed = |e: io::Error| -> ! {
+        eprintln!("❌ Couldn't read {}: {}", path, e);
+        process::exit(1);
+    };
+    if mmap {
+        #[cfg(all(feature = "mmap", unix))]
+        {
+            let file = MappedFile::open(path).unwrap_or_else(|e| failed(e));
+            return check(file.as_str().unwrap_or_else(|e| failed(e)));
+        }
+        #[cfg(not(all(feature = "mmap", unix)))]
+        {
+            eprintln!("❌ --mmap needs a Unix build with the mmap feature: cargo build --features mmap");
+            process::exit(2);
+        }
+    }
+    check(&fs::read_to_string(path).unwrap_or_else(|e| failed(e)))
+}
+
+/// The report of `script`, drawing a progress bar on stderr while it's
+/// parsed when `progress` is set.
+fn check_script(session: &ParserSession, path: &str, script: &str, progress: bool) -> ScriptReport {
+    if !progress {
+        return report_script(session, script);
+    }
+    let started = Instant::now();
+    let mut drawn: Option<Instant> = None;
+    let report = report_script_with_progress(session, script, |progress| {
+        // Redrawing for every statement would slow down big files
+        let now = Instant::now();
+        let done = progress.offset == progress.total;
+        if done || drawn.is_none_or(|at| now - at >= Duration::from_millis(100)) {
+            drawn = Some(now);
+            eprint!("\r{}", progress_bar(path, progress, started.elapsed()));
+        }
+    });
+    eprintln!();
+    report
+}
+
+/// A one-line progress bar for `path`, with the time left estimated from
+/// the rate so far.
+fn progress_bar(path: &str, progress: Progress, elapsed: Duration) -> String {
+    const WIDTH: usize = 30;
+    let fraction = progress.fraction();
+    let filled = (fraction * WIDTH as f64) as usize;
+    let remaining = if progress.offset == progress.total {
+        format!("done in {:.1}s", elapsed.as_secs_f64())
+    } else if fraction > 0.0 {
+        format!("~{:.0}s left", elapsed.as_secs_f64() * (1.0 - fraction) / fraction)
+    } else {
+        String::new()
+    };
+    format!(
+        "{} [{}{}] {:3.0}% {:.1}/{:.1} MB, {} tokens, {}",
+        path,
+        "#".repeat(filled),
+        ".".repeat(WIDTH - filled),
+        fraction * 100.0,
+        progress.offset as f64 / 1e6,
+        progress.total as f64 / 1e6,
+        progress.tokens,
+        remaining
+    )
+}
+
+/// Prints what the lint rules find in each statement of the scripts, as
+/// `path:line: severity [rule] message`, and exits with 1 when one of them
+/// is an error or a statement doesn't parse, so a deploy can be gated on it.
+fn run_lint(matches: &Matches) {
+    let dialect = matches.value("dialect").and_then(Dialect::from_name).unwrap_or(Dialect::Generic);
+    let list = |name: &str| -> Vec<String> {
+        let values = matches.value(name).unwrap_or_default().split(',');
+        values.map(str::trim).filter(|value| !value.is_empty()).map(str::to_string).collect()
+    };
+    let mut config =
+        LintConfig { large_tables: list("large-tables"), indexed_columns: list("indexed"), ..LintConfig::default() };
+    for setting in matches.values("rule") {
+        if let Err(e) = config.set_level(setting) {
+            eprintln!("❌ --rule {}: {}", setting, e);
+            process::exit(2);
+        }
+    }
+    let session = ParserSession::new(ParserOptions { dialect, profile: profile(matches), ..ParserOptions::default() });
+    let mut ok = true;
+    for path in &matches.positionals {
+        ok &= with_script(path, false, |script| {
+            let mut ok = true;
+            for statement in report_script(&session, script).statements {
+                match &statement.result {
+                    Ok(parsed) => {
+                        for finding in lint_with_config(parsed, &config) {
+                            ok &= finding.severity != Severity::Error;
+                            let (line, severity) = (statement.line, finding.severity.name());
+                            println!("{}:{}: {} [{}] {}", path, line, severity, finding.rule, finding.message);
+                        }
+                    }
+                    Err(e) => {
+                        ok = false;
+                        println!("{}:{}: error [parse] {}", path, statement.line, e);
+                    }
+                }
+            }
+            ok
+        });
+    }
+    if !ok {
+        process::exit(1);
+    }
+}
+
+/// Prints a self-contained HTML page reporting on every statement of a
+/// script.
+fn run_html_report(matches: &Matches) {
+    let Some(path) = matches.positional(0) else { usage() };
+    let dialect = matches.value("dialect").and_then(Dialect::from_name).unwrap_or(Dialect::Generic);
+    let script = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("❌ Couldn't read {}: {}", path, e);
+        process::exit(1);
+    });
+    let session = ParserSession::new(ParserOptions { dialect, profile: profile(matches), ..ParserOptions::default() });
+    print!("{}", html_report(path, &script, &session));
+}
+
+/// Prints the SQL of a dbt-style template file, with the variables of its
+/// `--var name=value` options.
+fn run_render(matches: &Matches) {
+    let Some(path) = matches.positional(0) else { usage() };
+    let mut context = TemplateContext::new();
+    for setting in matches.values("var") {
+        let Some((name, value)) = setting.split_once('=') else {
+            eprintln!("❌ --var {}: expected name=value", setting);
+            process::exit(2);
+        };
+        context = context.with_variable(name.trim(), value);
+    }
+    let template = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("❌ Couldn't read {}: {}", path, e);
+        process::exit(1);
+    });
+    match render(&template, &context) {
+        Ok(sql) => print!("{}", sql),
+        Err(e) => {
+            eprintln!("❌ {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Compares two schema dumps (files or directories) and prints the changes,
+/// or with `--alter` the ALTER TABLE statements that apply them.
+fn run_schema_diff(matches: &Matches) {
+    let [old, new] = matches.positionals.as_slice() else { usage() };
+
+    let load = |path: &str| {
+        Schema::load(path).unwrap_or_else(|e| {
+            eprintln!("❌ Couldn't load schema {}: {}", path, e);
+            process::exit(1);
+        })
+    };
+    let changes = diff(&load(old), &load(new));
+
+    if matches.flag("alter") {
+        for change in &changes {
+            for statement in change.to_alter_sql(Dialect::Generic) {
+                if statement.starts_with("--") {
+                    println!("{}", statement);
+                } else {
+                    println!("{};", statement);
+                }
+            }
+        }
+    } else if changes.is_empty() {
+        println!("✅ Schemas are identical");
+    } else {
+        for change in &changes {
+            println!("{}", change);
+        }
+    }
+}
+
+/// Prints the tables and columns of a schema dump that none of the queries
+/// of a workload, a `.sql` file or a directory of them, references.
+fn run_dead_code(matches: &Matches) {
+    let [schema, queries] = matches.positionals.as_slice() else { usage() };
+    let schema = Schema::load(schema).unwrap_or_else(|e| {
+        eprintln!("❌ Couldn't load schema {}: {}", schema, e);
+        process::exit(1);
+    });
+    let report = analyze_workload(queries, &schema).unwrap_or_else(|e| {
+        eprintln!("❌ Couldn't read {}: {}", queries, e);
+        process::exit(1);
+    });
+    if matches.flag("json") {
+        println!("{}", report.to_json());
+    } else {
+        println!("{}", report);
+    }
+}
+
+/// Compares the queries in two files clause by clause and prints the
+/// changes.
+fn run_query_diff(matches: &Matches) {
+    let [old, new] = matches.positionals.as_slice() else { usage() };
+
+    let read = |path: &str| {
+        fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("❌ Couldn't read {}: {}", path, e);
+            process::exit(1);
+        })
+    };
+    let (old, new) = (read(old), read(new));
+    let query = |text: &str| text.trim().trim_end_matches(';').to_string();
+    let changes = match query_diff::diff(&query(&old), &query(&new), Dialect::Generic) {
+        Ok(changes) => changes,
+        Err(e) => {
+            eprintln!("❌ Parse Error: {}", e);
+            process::exit(1);
+        }
+    };
+
+    if matches.flag("json") {
+        println!("{}", query_diff::to_json(&changes));
+    } else if changes.is_empty() {
+        println!("✅ Queries are equivalent");
+    } else {
+        for change in &changes {
+            println!("{}", change);
+        }
+    }
+}
+
+/// Serves an empty in-memory database to Postgres clients, on
+/// 127.0.0.1:5432 unless another address is given.
+#[cfg(feature = "engine")]
+fn run_pg_server(matches: &Matches) {
+    let address = server_address(matches, "127.0.0.1:5432");
+
+    eprintln!("Listening on {}", address);
+    if let Err(e) = pgwire::serve(address, Database::new()) {
+        eprintln!("❌ Couldn't serve on {}: {}", address, e);
+        process::exit(1);
+    }
+}
+
+/// Serves an empty in-memory database to MySQL clients, on
+/// 127.0.0.1:3306 unless another address is given.
+#[cfg(feature = "engine")]
+fn run_mysql_server(matches: &Matches) {
+    let address = server_address(matches, "127.0.0.1:3306");
+
+    eprintln!("Listening on {}", address);
+    if let Err(e) = mysqlwire::serve(address, Database::new()) {
+        eprintln!("❌ Couldn't serve on {}: {}", address, e);
+        process::exit(1);
+    }
+}
+
+/// The address a server listens on, `default` unless one is given;
+/// starts the metrics endpoint when `--metrics` asks for one.
+#[cfg(feature = "engine")]
+fn server_address<'a>(matches: &'a Matches, default: &'a str) -> &'a str {
+    if let Some(address) = matches.value("metrics") {
+        serve_metrics(address.to_string());
+    }
+    matches.positional(0).unwrap_or(default)
+}
+
+/// Installs a metrics registry and serves it on `address` in the
+/// background.
+#[cfg(feature = "engine")]
+fn serve_metrics(address: String) {
+    let registry = Metrics::new();
+    let _ = metrics::install(registry.clone());
+    eprintln!("Serving metrics on http://{}/metrics", address);
+    thread::spawn(move || {
+        if let Err(e) = metrics::serve(&address, registry) {
+            eprintln!("❌ Couldn't serve metrics on {}: {}", address, e);
+            process::exit(1);
+        }
+    });
+}
+
+/// Prints the completion script of a shell, e.g. for
+/// `sreerag_devadasan completions bash > /etc/bash_completion.d/sreerag_devadasan`.
+fn run_completions(matches: &Matches) {
+    let shell = matches.positional(0).and_then(Shell::from_name).unwrap_or_else(|| usage());
+    print!("{}", cli::completions(shell));
+}
+
+/// Executes newline-delimited statements from stdin against the engine.
+/// With a log file the database is replayed from it first, and every
+/// change is on disk before its statement is reported done. `--versions`
+/// keeps recent versions of the tables for queries AS OF VERSION.
+#[cfg(feature = "engine")]
+fn run_engine(matches: &Matches) {
+    let versions = matches.value("versions").map_or(0, |n| n.parse().unwrap_or_else(|_| usage()));
+    let mut durable = matches.positional(0).map(|path| {
+        DurableDatabase::open(path).unwrap_or_else(|e| {
+            eprintln!("❌ Couldn't open {}: {}", path, e);
+            process::exit(1);
+        })
+    });
+    if let Some(discarded) = durable.as_ref().map(DurableDatabase::discarded).filter(|n| *n > 0) {
+        eprintln!("Discarded {} bytes of an unfinished write at the end of the log", discarded);
+    }
+    let mut memory = Database::new();
+    memory.set_history_limit(versions);
+    if let Some(database) = &mut durable {
+        database.set_history_limit(versions);
+    }
+
+    for_each_input_line(|line| {
+        let result = match &mut durable {
+            Some(database) => database.execute_sql(line).map_err(|e| e.to_string()),
+            None => memory.execute_sql(line).map_err(|e| e.to_string()),
+        };
+        result.map(|result| styler().table(&result))
+    });
+}
+
+/// Reads a Postgres log, MySQL slow query log or file of one statement per
+/// line (detected unless `--format` says which) and prints its statements
+/// grouped by shape, most frequent first.
+fn run_log_stats(matches: &Matches) {
+    let Some(path) = matches.positional(0) else { usage() };
+    let format = matches.value("format").map(|name| LogFormat::from_name(name).unwrap_or_else(|| usage()));
+    let dialect = matches.value("dialect").map(|name| Dialect::from_name(name).unwrap_or_else(|| usage()));
+    let top = matches.value("top").map_or(usize::MAX, |n| n.parse().unwrap_or_else(|_| usage()));
+    let output = match (matches.flag("json"), matches.flag("csv")) {
+        (true, _) => "json",
+        (_, true) => "csv",
+        _ => "text",
+    };
+
+    let log = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("❌ Couldn't read {}: {}", path, e);
+        process::exit(1);
+    });
+    let format = format.unwrap_or_else(|| LogFormat::detect(&log));
+    let dialect = dialect.unwrap_or(match format {
+        LogFormat::Postgres => Dialect::Postgres,
+        LogFormat::MySqlSlow => Dialect::MySql,
+        LogFormat::Plain => Dialect::Generic,
+    });
+    let mut stats = querylog::analyze(&querylog::read_entries(&log, format), dialect);
+
+    match output {
+        "json" => println!("{}", stats.to_json(top)),
+        "csv" => print!("{}", stats.to_csv(top)),
+        _ => {
+            stats.shapes.truncate(top);
+            println!("{}", stats);
+        }
+    }
+}
+
+/// Runs `handle` on every non-empty stdin line, printing its output or
+/// reporting its error (with the line number) on stderr.
+fn for_each_input_line(mut handle: impl FnMut(&str) -> Result<String, String>) {
+    for (number, line) in io::stdin().lock().lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("❌Couldn't read the input: {}", e);
+                process::exit(1);
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match handle(&line) {
+            Ok(output) => println!("{}", output),
+            Err(e) => eprintln!("{}", styler().paint(Role::Error, &format!("❌ Line {}: {}", number + 1, e))),
+        }
+    }
+}
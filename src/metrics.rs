@@ -0,0 +1,271 @@
+// metrics.rs — counters and histograms for the long-running modes
+// Author: Sreerag Devadasan
+//
+// The servers and the proxy report what they parse and run through a
+// small facade: `timed_parse` and `timed_query` wrap a parse or an engine
+// call, and do nothing more than run it until a registry has been put in
+// place with `install`. A `Metrics` registry holds counters and
+// fixed-bucket histograms, each a family of series told apart by their
+// labels, and renders them in the Prometheus text exposition format.
+// `serve` answers GET /metrics with that text over plain HTTP, for a
+// Prometheus server to scrape.
+//
+// The metrics reported:
+//
+//   sql_statements_parsed_total            statements that parsed
+//   sql_parse_errors_total{code}           parses that failed, by `ParseError::code`
+//   sql_parse_duration_seconds             parse latency, successful or not
+//   sql_engine_query_duration_seconds      latency of statements run by the engine
+//   sql_engine_query_errors_total          statements the engine rejected
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, ToSocketAddrs};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::thread;
+use std::time::Instant;
+
+use crate::tokenizer::ParseError;
+
+pub const STATEMENTS_PARSED: &str = "sql_statements_parsed_total";
+pub const PARSE_ERRORS: &str = "sql_parse_errors_total";
+pub const PARSE_DURATION: &str = "sql_parse_duration_seconds";
+pub const QUERY_DURATION: &str = "sql_engine_query_duration_seconds";
+pub const QUERY_ERRORS: &str = "sql_engine_query_errors_total";
+
+/// Upper bounds in seconds of the histogram buckets. Parsing a statement
+/// takes microseconds, so the low end is finely divided.
+pub const LATENCY_BUCKETS: [f64; 12] = [0.00001, 0.00005, 0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+/// Longest HTTP request head read before the request is refused.
+const MAX_REQUEST_LENGTH: usize = 8 << 10;
+
+// === Registry ===
+
+/// A registry of metric families. Clones share the same metrics, so one
+/// can be installed while another is served.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    families: Arc<Mutex<BTreeMap<&'static str, Family>>>,
+}
+
+#[derive(Debug)]
+struct Family {
+    help: &'static str,
+    histogram: bool,
+    series: BTreeMap<Labels, Series>,
+}
+
+/// Label names and values, in the order they were given.
+type Labels = Vec<(String, String)>;
+
+#[derive(Debug)]
+enum Series {
+    Counter(u64),
+    /// Observations per bucket of `LATENCY_BUCKETS` plus the overflow
+    /// bucket, their sum and their count.
+    Histogram { buckets: [u64; LATENCY_BUCKETS.len() + 1], sum: f64, count: u64 },
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `by` to a counter, creating it at zero first.
+    pub fn increment(&self, name: &'static str, help: &'static str, labels: &[(&str, &str)], by: u64) {
+        self.update(name, help, labels, Series::Counter(0), |series| {
+            if let Series::Counter(value) = series {
+                *value += by;
+            }
+        });
+    }
+
+    /// Records one observation, in seconds, in a histogram.
+    pub fn observe(&self, name: &'static str, help: &'static str, labels: &[(&str, &str)], value: f64) {
+        let empty = Series::Histogram { buckets: [0; LATENCY_BUCKETS.len() + 1], sum: 0.0, count: 0 };
+        self.update(name, help, labels, empty, |series| {
+            if let Series::Histogram { buckets, sum, count } = series {
+                buckets[LATENCY_BUCKETS.iter().take_while(|&&bound| value > bound).count()] += 1;
+                *sum += value;
+                *count += 1;
+            }
+        });
+    }
+
+    /// The value of a counter, or the number of observations of a
+    /// histogram; zero for a series that doesn't exist.
+    pub fn count(&self, name: &str, labels: &[(&str, &str)]) -> u64 {
+        let families = self.families.lock().unwrap_or_else(PoisonError::into_inner);
+        match families.get(name).and_then(|family| family.series.get(&owned(labels))) {
+            Some(Series::Counter(value)) => *value,
+            Some(Series::Histogram { count, .. }) => *count,
+            None => 0,
+        }
+    }
+
+    /// Every family in the Prometheus text exposition format, by name.
+    pub fn render(&self) -> String {
+        let families = self.families.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut text = String::new();
+        for (name, family) in families.iter() {
+            let kind = if family.histogram { "histogram" } else { "counter" };
+            let _ = writeln!(text, "# HELP {} {}", name, family.help);
+            let _ = writeln!(text, "# TYPE {} {}", name, kind);
+            for (labels, series) in &family.series {
+                match series {
+                    Series::Counter(value) => {
+                        let _ = writeln!(text, "{}{} {}", name, render_labels(labels, None), value);
+                    }
+                    Series::Histogram { buckets, sum, count } => {
+                        let mut cumulative = 0;
+                        let bounds = LATENCY_BUCKETS.iter().map(f64::to_string).chain(["+Inf".to_string()]);
+                        for (bound, observed) in bounds.zip(buckets) {
+                            cumulative += observed;
+                            let labels = render_labels(labels, Some(&bound));
+                            let _ = writeln!(text, "{}_bucket{} {}", name, labels, cumulative);
+                        }
+                        let _ = writeln!(text, "{}_sum{} {}", name, render_labels(labels, None), sum);
+                        let _ = writeln!(text, "{}_count{} {}", name, render_labels(labels, None), count);
+                    }
+                }
+            }
+        }
+        text
+    }
+
+    /// Runs `update` on a series, creating its family and the series
+    /// itself, from `empty`, as needed.
+    fn update(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&str, &str)],
+        empty: Series,
+        update: impl FnOnce(&mut Series),
+    ) {
+        let histogram = matches!(empty, Series::Histogram { .. });
+        let mut families = self.families.lock().unwrap_or_else(PoisonError::into_inner);
+        let family = families.entry(name).or_insert_with(|| Family { help, histogram, series: BTreeMap::new() });
+        // A name keeps the kind it was first used as; other updates are dropped
+        if family.histogram == histogram {
+            update(family.series.entry(owned(labels)).or_insert(empty));
+        }
+    }
+}
+
+fn owned(labels: &[(&str, &str)]) -> Labels {
+    labels.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+}
+
+/// `{name="value",...}`, with a bucket's `le` label last; empty when
+/// there are no labels.
+fn render_labels(labels: &Labels, bound: Option<&str>) -> String {
+    let mut pairs: Vec<String> = labels.iter().map(|(name, value)| format!("{}=\"{}\"", name, escape(value))).collect();
+    if let Some(bound) = bound {
+        pairs.push(format!("le=\"{}\"", bound));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+// === Facade ===
+
+static INSTALLED: OnceLock<Metrics> = OnceLock::new();
+
+/// Makes `metrics` the registry the instrumented code reports to. Only
+/// the first call succeeds; later ones hand their registry back.
+pub fn install(metrics: Metrics) -> Result<(), Metrics> {
+    INSTALLED.set(metrics)
+}
+
+/// The installed registry, if there is one.
+pub fn installed() -> Option<&'static Metrics> {
+    INSTALLED.get()
+}
+
+/// Runs `parse`, counting its outcome and timing it.
+pub fn timed_parse<T>(parse: impl FnOnce() -> Result<T, ParseError>) -> Result<T, ParseError> {
+    let Some(metrics) = installed() else {
+        return parse();
+    };
+    let started = Instant::now();
+    let result = parse();
+    metrics.observe(PARSE_DURATION, "Time spent parsing a statement", &[], started.elapsed().as_secs_f64());
+    match &result {
+        Ok(_) => metrics.increment(STATEMENTS_PARSED, "Statements parsed", &[], 1),
+        Err(e) => metrics.increment(PARSE_ERRORS, "Statements that failed to parse", &[("code", e.code())], 1),
+    }
+    result
+}
+
+/// Runs `query` against the engine, timing it and counting its failure.
+pub fn timed_query<T, E>(query: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+    let Some(metrics) = installed() else {
+        return query();
+    };
+    let started = Instant::now();
+    let result = query();
+    metrics.observe(QUERY_DURATION, "Time spent running a statement", &[], started.elapsed().as_secs_f64());
+    if result.is_err() {
+        metrics.increment(QUERY_ERRORS, "Statements the engine rejected", &[], 1);
+    }
+    result
+}
+
+// === HTTP endpoint ===
+
+/// Accepts scrapes on `address` forever, one thread per connection.
+pub fn serve(address: impl ToSocketAddrs, metrics: Metrics) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let metrics = metrics.clone();
+        thread::spawn(move || handle_request(stream, &metrics));
+    }
+    Ok(())
+}
+
+/// Answers one HTTP request on `stream` and closes it: the metrics for
+/// GET /metrics, 404 for any other path.
+pub fn handle_request<S: Read + Write>(mut stream: S, metrics: &Metrics) -> io::Result<()> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") && request.len() <= MAX_REQUEST_LENGTH {
+        let n = stream.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..n]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or("").split_whitespace();
+    let method = request_line.next().unwrap_or("");
+    let path = request_line.next().map(|target| target.split('?').next().unwrap_or(target));
+    let (status, content_type, body) = match (method, path) {
+        ("GET" | "HEAD", Some("/metrics")) => ("200 OK", "text/plain; version=0.0.4", metrics.render()),
+        ("GET" | "HEAD", Some(_)) => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+        (_, Some(_)) => ("405 Method Not Allowed", "text/plain", "Method not allowed\n".to_string()),
+        _ => ("400 Bad Request", "text/plain", "Bad request\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()
+}
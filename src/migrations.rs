@@ -0,0 +1,153 @@
+// migrations.rs — static checks over a directory of numbered .sql migrations
+// Author: Sreerag Devadasan
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::ast::Statement;
+use crate::batch::split_statements;
+use crate::json;
+use crate::parser::parse_sql;
+
+/// Which checks to run on every migration file.
+#[derive(Debug, Clone, Default)]
+pub struct MigrationOptions {
+    /// Reject statements that don't change the schema (e.g. SELECT).
+    pub ddl_only: bool,
+}
+
+/// A problem found in a migration file. `statement` is the 1-based index of
+/// the offending statement, or `None` for problems with the file itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub statement: Option<usize>,
+    pub message: String,
+}
+
+/// The result of checking one migration file.
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub number: Option<u64>,
+    pub statements: usize,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// The result of checking a whole migrations directory, ordered by number.
+#[derive(Debug, Clone, Default)]
+pub struct MigrationReport {
+    pub files: Vec<FileReport>,
+}
+
+impl MigrationReport {
+    pub fn is_ok(&self) -> bool {
+        self.files.iter().all(|f| f.diagnostics.is_empty())
+    }
+
+    pub fn to_json(&self) -> String {
+        let files = self.files.iter().map(|file| {
+            let diagnostics = file.diagnostics.iter().map(|d| {
+                json::object([
+                    ("statement", d.statement.map_or("null".to_string(), |n| n.to_string())),
+                    ("message", json::string(&d.message)),
+                ])
+            });
+            json::object([
+                ("path", json::string(&file.path.display().to_string())),
+                ("number", file.number.map_or("null".to_string(), |n| n.to_string())),
+                ("statements", file.statements.to_string()),
+                ("ok", file.diagnostics.is_empty().to_string()),
+                ("diagnostics", json::array(diagnostics)),
+            ])
+        });
+        json::object([("ok", self.is_ok().to_string()), ("files", json::array(files))])
+    }
+}
+
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for file in &self.files {
+            let status = if file.diagnostics.is_empty() { "✅" } else { "❌" };
+            writeln!(f, "{} {} ({} statements)", status, file.path.display(), file.statements)?;
+            for diagnostic in &file.diagnostics {
+                match diagnostic.statement {
+                    Some(n) => writeln!(f, "    statement {}: {}", n, diagnostic.message)?,
+                    None => writeln!(f, "    {}", diagnostic.message)?,
+                }
+            }
+        }
+        let failed = self.files.iter().filter(|f| !f.diagnostics.is_empty()).count();
+        write!(f, "{} files checked, {} with problems", self.files.len(), failed)
+    }
+}
+
+/// Checks every `.sql` file in `dir`. Files are expected to start with their
+/// migration number (`0001_init.sql`) and are reported in numeric order.
+pub fn analyze_directory(dir: impl AsRef<Path>, options: &MigrationOptions) -> io::Result<MigrationReport> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "sql") {
+            let script = fs::read_to_string(&path)?;
+            files.push(analyze_file(path, &script, options));
+        }
+    }
+
+    // Un-numbered files sort last; ties fall back to the file name
+    files.sort_by(|a, b| (a.number.is_none(), a.number, &a.path).cmp(&(b.number.is_none(), b.number, &b.path)));
+
+    let mut seen: HashMap<u64, PathBuf> = HashMap::new();
+    for file in &mut files {
+        match file.number {
+            Some(n) => {
+                if let Some(first) = seen.get(&n) {
+                    file.diagnostics.push(Diagnostic {
+                        statement: None,
+                        message: format!("migration number {} is also used by {}", n, first.display()),
+                    });
+                } else {
+                    seen.insert(n, file.path.clone());
+                }
+            }
+            None => file.diagnostics.push(Diagnostic {
+                statement: None,
+                message: "file name does not start with a migration number".to_string(),
+            }),
+        }
+    }
+
+    Ok(MigrationReport { files })
+}
+
+/// Checks the statements of one migration file.
+pub fn analyze_file(path: PathBuf, script: &str, options: &MigrationOptions) -> FileReport {
+    let statements = split_statements(script);
+    let mut diagnostics = Vec::new();
+
+    for (index, (_, sql)) in statements.iter().enumerate() {
+        let statement = Some(index + 1);
+        match parse_sql(sql) {
+            Err(e) => diagnostics.push(Diagnostic { statement, message: format!("parse error: {}", e) }),
+            Ok(Statement::DropTable { table_name, if_exists: false }) => diagnostics.push(Diagnostic {
+                statement,
+                message: format!("DROP TABLE {} without IF EXISTS", table_name),
+            }),
+            Ok(parsed) if options.ddl_only && !parsed.is_ddl() => diagnostics.push(Diagnostic {
+                statement,
+                message: format!("{} is not allowed in a DDL-only migration", parsed.kind()),
+            }),
+            Ok(_) => {}
+        }
+    }
+
+    FileReport { number: migration_number(&path), path, statements: statements.len(), diagnostics }
+}
+
+fn migration_number(path: &Path) -> Option<u64> {
+    let name = path.file_name()?.to_str()?;
+    let digits: String = name.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
@@ -0,0 +1,97 @@
+// mmap.rs — memory-mapped .sql files, for parsing dumps too big to read into memory
+// Author: Sreerag Devadasan
+//
+// `MappedFile` maps a file read-only and hands it out as a `&str`, so the
+// batch functions split and parse it where it lies: the statements are
+// slices of the mapping and the operating system pages the file in and
+// out as they're read, instead of the whole dump being copied into one
+// `String` first. Only the statement being parsed is copied, into the
+// tokenizer's buffer.
+//
+// The crate has no dependencies, so `mmap` and `munmap` are declared here
+// rather than taken from the libc crate; this is the crate's only unsafe
+// code. It's Unix-only and behind the "mmap" feature. The mapping is
+// private and read-only, but like every mapping it sees changes another
+// process makes to the file: don't map files that are being written.
+
+use std::ffi::c_void;
+use std::fs::File;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::ptr;
+use std::slice;
+use std::str;
+
+use crate::ast::Statement;
+use crate::batch::parse_script;
+use crate::tokenizer::ParseError;
+
+const PROT_READ: i32 = 1;
+const MAP_PRIVATE: i32 = 2;
+
+extern "C" {
+    fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, offset: i64) -> *mut c_void;
+    fn munmap(addr: *mut c_void, len: usize) -> i32;
+}
+
+/// A file mapped read-only into memory, unmapped on drop.
+#[derive(Debug)]
+pub struct MappedFile {
+    ptr: *mut c_void,
+    len: usize,
+}
+
+// The mapping is read-only and owned by the value, like a `Box<[u8]>`
+unsafe impl Send for MappedFile {}
+unsafe impl Sync for MappedFile {}
+
+impl MappedFile {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "file too large to map"))?;
+        // mmap rejects empty mappings
+        if len == 0 {
+            return Ok(MappedFile { ptr: ptr::null_mut(), len });
+        }
+        // SAFETY: a fresh private read-only mapping of an open file, which
+        // stays valid after the file is closed
+        let ptr = unsafe { mmap(ptr::null_mut(), len, PROT_READ, MAP_PRIVATE, file.as_raw_fd(), 0) };
+        if ptr as isize == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(MappedFile { ptr, len })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        // SAFETY: the mapping is `len` readable bytes until `self` is dropped
+        unsafe { slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+
+    /// The file as text; an `InvalidData` error if it isn't UTF-8.
+    pub fn as_str(&self) -> io::Result<&str> {
+        str::from_utf8(self.as_bytes()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: unmaps the mapping made in `open`, which nothing
+            // borrows any more
+            unsafe {
+                munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
+
+/// Like `batch::parse_file_parallel`, but maps the file instead of reading it.
+pub fn parse_file_mapped(path: impl AsRef<Path>) -> io::Result<Vec<Result<Statement, ParseError>>> {
+    let file = MappedFile::open(path)?;
+    Ok(parse_script(file.as_str()?))
+}
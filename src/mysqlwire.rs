@@ -0,0 +1,323 @@
+// mysqlwire.rs — a fake MySQL server backed by the in-memory engine
+// Author: Sreerag Devadasan
+//
+// Speaks enough of the MySQL client/server protocol for the mysql client
+// and drivers to connect and run text queries: a v10 handshake offering
+// mysql_native_password (any credentials are accepted), then COM_QUERY,
+// COM_INIT_DB, COM_PING and COM_QUIT. Each query holds one statement, as
+// without CLIENT_MULTI_STATEMENTS. Results go out as text result sets
+// ended by EOF packets. As with the Postgres frontend, all connections of
+// a server share one database, and each has its own transactions.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, ToSocketAddrs};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+use crate::ast::Statement;
+use crate::dialect::Dialect;
+use crate::engine::{Database, EngineError, QueryResult, Transaction};
+use crate::eval::Value;
+use crate::metrics::{timed_parse, timed_query};
+use crate::parser::parse_sql_with_dialect;
+
+const PROTOCOL_VERSION: u8 = 10;
+const SERVER_VERSION: &str = "8.0.0-mini";
+const AUTH_PLUGIN: &str = "mysql_native_password";
+
+// Capability flags
+const CLIENT_LONG_PASSWORD: u32 = 0x1;
+const CLIENT_LONG_FLAG: u32 = 0x4;
+const CLIENT_CONNECT_WITH_DB: u32 = 0x8;
+const CLIENT_PROTOCOL_41: u32 = 0x200;
+const CLIENT_TRANSACTIONS: u32 = 0x2000;
+const CLIENT_SECURE_CONNECTION: u32 = 0x8000;
+const CLIENT_PLUGIN_AUTH: u32 = 0x8_0000;
+const CAPABILITIES: u32 = CLIENT_LONG_PASSWORD
+    | CLIENT_LONG_FLAG
+    | CLIENT_CONNECT_WITH_DB
+    | CLIENT_PROTOCOL_41
+    | CLIENT_TRANSACTIONS
+    | CLIENT_SECURE_CONNECTION
+    | CLIENT_PLUGIN_AUTH;
+
+const SERVER_STATUS_IN_TRANS: u16 = 0x1;
+const SERVER_STATUS_AUTOCOMMIT: u16 = 0x2;
+
+// Commands
+const COM_QUIT: u8 = 0x01;
+const COM_INIT_DB: u8 = 0x02;
+const COM_QUERY: u8 = 0x03;
+const COM_PING: u8 = 0x0e;
+
+// Column types and character sets
+const MYSQL_TYPE_TINY: u8 = 0x01;
+const MYSQL_TYPE_LONGLONG: u8 = 0x08;
+const MYSQL_TYPE_DATE: u8 = 0x0a;
+const MYSQL_TYPE_TIME: u8 = 0x0b;
+const MYSQL_TYPE_DATETIME: u8 = 0x0c;
+const MYSQL_TYPE_NEWDECIMAL: u8 = 0xf6;
+const MYSQL_TYPE_VAR_STRING: u8 = 0xfd;
+const UTF8_GENERAL_CI: u16 = 33;
+const BINARY: u16 = 63;
+
+/// Longest packet payload: larger ones would be split, which isn't
+/// supported.
+const MAX_PACKET_LENGTH: usize = 0xff_ffff;
+
+/// Accepts connections on `address` forever, one thread per connection,
+/// all sharing `database`.
+pub fn serve(address: impl ToSocketAddrs, database: Database) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    let database = Arc::new(Mutex::new(database));
+    for (connection_id, stream) in (1..).zip(listener.incoming()) {
+        let stream = stream?;
+        let database = Arc::clone(&database);
+        thread::spawn(move || handle_connection(stream, &database, connection_id));
+    }
+    Ok(())
+}
+
+/// Runs one client session on `stream` until the client quits or
+/// disconnects.
+pub fn handle_connection<S: Read + Write>(
+    stream: S,
+    database: &Mutex<Database>,
+    connection_id: u32,
+) -> io::Result<()> {
+    let mut connection = Connection { stream, sequence: 0, transaction: None };
+    connection.handshake(connection_id)?;
+    loop {
+        let packet = match connection.read_packet() {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            result => result?,
+        };
+        match packet.split_first() {
+            Some((&COM_QUIT, _)) => return Ok(()),
+            Some((&COM_INIT_DB | &COM_PING, _)) => connection.ok(0)?,
+            Some((&COM_QUERY, sql)) => {
+                let sql = String::from_utf8_lossy(sql).into_owned();
+                let mut database = database.lock().unwrap_or_else(PoisonError::into_inner);
+                connection.query(&mut database, sql.trim().trim_end_matches(';'))?;
+            }
+            _ => connection.error(1047, "08S01", "Unknown command")?,
+        }
+        connection.stream.flush()?;
+    }
+}
+
+/// A stream, the sequence id of the next packet on it, and the session's
+/// open transaction.
+struct Connection<S> {
+    stream: S,
+    sequence: u8,
+    transaction: Option<Transaction>,
+}
+
+impl<S: Read + Write> Connection<S> {
+    /// Sends the server greeting and accepts whatever the client answers.
+    fn handshake(&mut self, connection_id: u32) -> io::Result<()> {
+        // The scramble the client hashes its password with; never checked
+        let scramble = *b"mini-sql-scramble!!!";
+        let mut greeting = vec![PROTOCOL_VERSION];
+        put_nul_string(&mut greeting, SERVER_VERSION);
+        greeting.extend_from_slice(&connection_id.to_le_bytes());
+        greeting.extend_from_slice(&scramble[..8]);
+        greeting.push(0);
+        greeting.extend_from_slice(&(CAPABILITIES as u16).to_le_bytes());
+        greeting.push(UTF8_GENERAL_CI as u8);
+        greeting.extend_from_slice(&self.status().to_le_bytes());
+        greeting.extend_from_slice(&((CAPABILITIES >> 16) as u16).to_le_bytes());
+        greeting.push(scramble.len() as u8 + 1);
+        greeting.extend_from_slice(&[0; 10]);
+        greeting.extend_from_slice(&scramble[8..]);
+        greeting.push(0);
+        put_nul_string(&mut greeting, AUTH_PLUGIN);
+        self.write_packet(&greeting)?;
+        self.stream.flush()?;
+
+        self.read_packet()?;
+        self.ok(0)?;
+        self.stream.flush()
+    }
+
+    fn query(&mut self, database: &mut Database, sql: &str) -> io::Result<()> {
+        let result = timed_parse(|| parse_sql_with_dialect(sql, Dialect::MySql))
+            .map_err(EngineError::from)
+            .and_then(|statement| {
+                timed_query(|| execute(database, &mut self.transaction, &statement)).map(|result| (statement, result))
+            });
+        match result {
+            Ok((Statement::Insert { rows, .. }, _)) => self.ok(rows.len() as u64),
+            Ok((_, result)) if !result.columns.is_empty() => self.result_set(&result),
+            Ok(_) => self.ok(0),
+            Err(e) => {
+                let (code, state) = match e {
+                    EngineError::Parse(_) => (1064, "42000"),
+                    EngineError::Unsupported(_) | EngineError::UnsupportedFeature(_) => (1235, "42000"),
+                    EngineError::NoSuchTable(_) => (1146, "42S02"),
+                    EngineError::TableExists(_) => (1050, "42S01"),
+                    EngineError::UnknownColumn(_) => (1054, "42S22"),
+                    EngineError::NotNull(_) => (1048, "23000"),
+                    EngineError::DuplicateKey(_) => (1062, "23000"),
+                    EngineError::CheckFailed(_) => (3819, "HY000"),
+                    EngineError::WriteConflict(_) => (1213, "40001"),
+                    EngineError::ReadOnly(_) => (1036, "HY000"),
+                    _ => (1105, "HY000"),
+                };
+                self.error(code, state, &e.to_string())
+            }
+        }
+    }
+
+    /// The column count, a definition per column, EOF, the rows, EOF. A
+    /// column's type is that of its first non-NULL value, text if it has
+    /// none.
+    fn result_set(&mut self, result: &QueryResult) -> io::Result<()> {
+        let mut count = Vec::new();
+        put_length(&mut count, result.columns.len() as u64);
+        self.write_packet(&count)?;
+
+        for (i, name) in result.columns.iter().enumerate() {
+            let first = result.rows.iter().map(|row| &row[i]).find(|value| **value != Value::Null);
+            let (column_type, charset, length) = match first {
+                Some(Value::Int(_)) => (MYSQL_TYPE_LONGLONG, BINARY, 20u32),
+                Some(Value::Bool(_)) => (MYSQL_TYPE_TINY, BINARY, 1),
+                Some(Value::Date(_)) => (MYSQL_TYPE_DATE, BINARY, 10),
+                Some(Value::Time(_)) => (MYSQL_TYPE_TIME, BINARY, 15),
+                Some(Value::Timestamp(_)) => (MYSQL_TYPE_DATETIME, BINARY, 26),
+                // 38 digits, a sign and a point
+                Some(Value::Decimal(_)) => (MYSQL_TYPE_NEWDECIMAL, BINARY, 40),
+                _ => (MYSQL_TYPE_VAR_STRING, UTF8_GENERAL_CI, 255),
+            };
+            let mut definition = Vec::new();
+            for text in ["def", "", "", "", name, name] {
+                put_length_string(&mut definition, text.as_bytes());
+            }
+            definition.push(0x0c); // length of the fixed fields
+            definition.extend_from_slice(&charset.to_le_bytes());
+            definition.extend_from_slice(&length.to_le_bytes());
+            definition.push(column_type);
+            definition.extend_from_slice(&0u16.to_le_bytes()); // flags
+            definition.push(0); // decimals
+            definition.extend_from_slice(&[0; 2]);
+            self.write_packet(&definition)?;
+        }
+        self.eof()?;
+
+        for row in &result.rows {
+            let mut data = Vec::new();
+            for value in row {
+                match value {
+                    Value::Null => data.push(0xfb),
+                    Value::Int(n) => put_length_string(&mut data, n.to_string().as_bytes()),
+                    Value::Bool(b) => put_length_string(&mut data, if *b { b"1" } else { b"0" }),
+                    Value::String(s) => put_length_string(&mut data, s.as_bytes()),
+                    Value::Date(date) => put_length_string(&mut data, date.to_string().as_bytes()),
+                    Value::Time(time) => put_length_string(&mut data, time.to_string().as_bytes()),
+                    Value::Timestamp(timestamp) => put_length_string(&mut data, timestamp.to_string().as_bytes()),
+                    Value::Decimal(decimal) => put_length_string(&mut data, decimal.to_string().as_bytes()),
+                }
+            }
+            self.write_packet(&data)?;
+        }
+        self.eof()
+    }
+
+    fn ok(&mut self, affected_rows: u64) -> io::Result<()> {
+        let mut packet = vec![0x00];
+        put_length(&mut packet, affected_rows);
+        put_length(&mut packet, 0); // last insert id
+        packet.extend_from_slice(&self.status().to_le_bytes());
+        packet.extend_from_slice(&0u16.to_le_bytes()); // warnings
+        self.write_packet(&packet)
+    }
+
+    fn eof(&mut self) -> io::Result<()> {
+        let mut packet = vec![0xfe];
+        packet.extend_from_slice(&0u16.to_le_bytes()); // warnings
+        packet.extend_from_slice(&self.status().to_le_bytes());
+        self.write_packet(&packet)
+    }
+
+    /// The server status flags sent in OK and EOF packets.
+    fn status(&self) -> u16 {
+        match self.transaction {
+            Some(_) => SERVER_STATUS_IN_TRANS,
+            None => SERVER_STATUS_AUTOCOMMIT,
+        }
+    }
+
+    fn error(&mut self, code: u16, state: &str, message: &str) -> io::Result<()> {
+        let mut packet = vec![0xff];
+        packet.extend_from_slice(&code.to_le_bytes());
+        packet.push(b'#');
+        packet.extend_from_slice(state.as_bytes());
+        packet.extend_from_slice(message.as_bytes());
+        self.write_packet(&packet)
+    }
+
+    /// Reads one packet; the next reply continues its sequence.
+    fn read_packet(&mut self) -> io::Result<Vec<u8>> {
+        let mut header = [0; 4];
+        self.stream.read_exact(&mut header)?;
+        let length = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+        if length == MAX_PACKET_LENGTH {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "packets over 16 MiB are not supported"));
+        }
+        self.sequence = header[3].wrapping_add(1);
+        let mut payload = vec![0; length];
+        self.stream.read_exact(&mut payload)?;
+        Ok(payload)
+    }
+
+    fn write_packet(&mut self, payload: &[u8]) -> io::Result<()> {
+        let length = (payload.len() as u32).to_le_bytes();
+        self.stream.write_all(&[length[0], length[1], length[2], self.sequence])?;
+        self.stream.write_all(payload)?;
+        self.sequence = self.sequence.wrapping_add(1);
+        Ok(())
+    }
+}
+
+/// Executes a statement in the session's transaction, if it has one; SET
+/// is accepted and ignored, since drivers send it while connecting.
+fn execute(
+    database: &mut Database,
+    transaction: &mut Option<Transaction>,
+    statement: &Statement,
+) -> Result<QueryResult, EngineError> {
+    match statement {
+        Statement::SetVariable { .. } => Ok(QueryResult::default()),
+        other => database.execute_in(transaction, other),
+    }
+}
+
+/// A length-encoded integer.
+fn put_length(buffer: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=250 => buffer.push(n as u8),
+        251..=0xffff => {
+            buffer.push(0xfc);
+            buffer.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x1_0000..=0xff_ffff => {
+            buffer.push(0xfd);
+            buffer.extend_from_slice(&(n as u32).to_le_bytes()[..3]);
+        }
+        _ => {
+            buffer.push(0xfe);
+            buffer.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
+fn put_length_string(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    put_length(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+fn put_nul_string(buffer: &mut Vec<u8>, text: &str) {
+    buffer.extend_from_slice(text.as_bytes());
+    buffer.push(0);
+}
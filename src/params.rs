@@ -0,0 +1,230 @@
+// params.rs — values bound to the placeholders of a statement
+// Author: Sreerag Devadasan
+//
+// An application running a statement with values from its users passes
+// them next to the SQL rather than writing them into it, so no value can
+// change what the statement means. A statement's placeholders are all of
+// one style:
+//
+// - `?` takes the values in the order the placeholders are written;
+// - `$n` takes the nth value, so `$1` may appear more than once;
+// - `:name` takes, for each distinct name, the next value in the order the
+//   names first appear, or the value given for it by name.
+//
+// Binding checks that there are as many values as parameters, then puts
+// each value in place of its placeholders as a literal, so a bound query
+// has the fingerprint of any other binding of it and shares its compiled
+// plan (see plan_cache.rs). `Database::execute_with_params` also checks the
+// value of a parameter stored in or compared with a column against the
+// column's type. Only the clauses the engine runs have parameters: the
+// select list, WHERE clause and FROM subqueries of a SELECT, the rows of
+// INSERT and VALUES, and those of the statement an EXPLAIN is of.
+
+use core::mem;
+
+use crate::prelude::*;
+use crate::ast::{BinaryOperator, DBType, Expression, Statement, TableFactor, UnaryOperator};
+use crate::engine::EngineError;
+use crate::eval::Value;
+
+/// The parameters of a statement.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Parameters {
+    style: Option<Style>,
+    /// The placeholder of each parameter, in the order of the values.
+    names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Anonymous,
+    Numbered,
+    Named,
+}
+
+impl Parameters {
+    /// The parameters of `statement`, which mustn't mix placeholder styles.
+    pub fn of(statement: &Statement) -> Result<Self, EngineError> {
+        let mut placeholders = Vec::new();
+        for_each_placeholder(&mut statement.clone(), &mut |placeholder| {
+            if let Expression::Placeholder(name) = placeholder {
+                placeholders.push(mem::take(name));
+            }
+        });
+        let mut parameters = Parameters::default();
+        for placeholder in &placeholders {
+            let style = match placeholder.chars().next() {
+                Some('?') => Style::Anonymous,
+                Some('$') => Style::Numbered,
+                _ => Style::Named,
+            };
+            if parameters.style.is_some_and(|seen| seen != style) {
+                return Err(EngineError::MixedParameters);
+            }
+            parameters.style = Some(style);
+        }
+        parameters.names = match parameters.style {
+            Some(Style::Anonymous) => (1..=placeholders.len()).map(|i| format!("?{}", i)).collect(),
+            Some(Style::Numbered) => {
+                let mut last = 0;
+                for placeholder in &placeholders {
+                    match placeholder[1..].parse::<usize>() {
+                        Ok(n) if n > 0 => last = last.max(n),
+                        _ => return Err(EngineError::UnknownParameter(placeholder.clone())),
+                    }
+                }
+                (1..=last).map(|n| format!("${}", n)).collect()
+            }
+            _ => {
+                let mut names: Vec<String> = Vec::new();
+                for placeholder in placeholders {
+                    if !names.contains(&placeholder) {
+                        names.push(placeholder);
+                    }
+                }
+                names
+            }
+        };
+        Ok(parameters)
+    }
+
+    /// The number of values the statement takes.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// The parameters as written, in the order of the values; the `?`s are
+    /// given as `?1`, `?2`, ....
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// `values` in parameter order, if there is one for each parameter.
+    pub fn order(&self, values: &[Value]) -> Result<Vec<Value>, EngineError> {
+        if values.len() != self.len() {
+            return Err(EngineError::ParameterCount { expected: self.len(), found: values.len() });
+        }
+        Ok(values.to_vec())
+    }
+
+    /// The values given by name, `id` or `:id`, in parameter order; every
+    /// parameter needs one and every name must be a parameter's.
+    pub fn order_named(&self, values: &[(&str, Value)]) -> Result<Vec<Value>, EngineError> {
+        let is = |name: &str, parameter: &str| parameter == name || parameter.strip_prefix(':') == Some(name);
+        if let Some((name, _)) = values.iter().find(|(name, _)| !self.names.iter().any(|p| is(name, p))) {
+            return Err(EngineError::UnknownParameter(name.to_string()));
+        }
+        self.names
+            .iter()
+            .map(|parameter| match values.iter().find(|(name, _)| is(name, parameter)) {
+                Some((_, value)) => Ok(value.clone()),
+                None => Err(EngineError::MissingParameter(parameter.clone())),
+            })
+            .collect()
+    }
+
+    /// The index among the values of the parameter a placeholder is for.
+    /// The `?`s are told apart by `occurrence`, their position among the
+    /// placeholders of the statement, counting from zero.
+    pub fn index(&self, placeholder: &str, occurrence: usize) -> Option<usize> {
+        match self.style {
+            Some(Style::Anonymous) => (occurrence < self.len()).then_some(occurrence),
+            _ => self.names.iter().position(|name| name == placeholder),
+        }
+    }
+
+    /// `statement` with each placeholder replaced by the literal of its
+    /// value; `values` are in parameter order.
+    pub fn bind(&self, statement: &Statement, values: &[Value]) -> Statement {
+        let mut bound = statement.clone();
+        let mut occurrence = 0;
+        for_each_placeholder(&mut bound, &mut |placeholder| {
+            let Expression::Placeholder(name) = placeholder else { return };
+            let index = self.index(name, occurrence);
+            occurrence += 1;
+            if let Some(value) = index.and_then(|index| values.get(index)) {
+                *placeholder = literal(value);
+            }
+        });
+        bound
+    }
+}
+
+/// Calls `f` for each placeholder in the clauses that have parameters, in
+/// the order they're written.
+fn for_each_placeholder(statement: &mut Statement, f: &mut impl FnMut(&mut Expression)) {
+    match statement {
+        Statement::Select { columns, from, selection, .. } => {
+            columns.iter_mut().for_each(|item| placeholders_in(&mut item.expr, f));
+            if let TableFactor::Derived { subquery, .. } = &mut **from {
+                for_each_placeholder(subquery, f);
+            }
+            if let Some(selection) = selection {
+                placeholders_in(selection, f);
+            }
+        }
+        Statement::Insert { rows, .. } | Statement::Values { rows } => {
+            rows.iter_mut().flatten().for_each(|expr| placeholders_in(expr, f));
+        }
+        Statement::Update { assignments, selection, .. } => {
+            assignments.iter_mut().for_each(|(_, value)| placeholders_in(value, f));
+            if let Some(selection) = selection {
+                placeholders_in(selection, f);
+            }
+        }
+        Statement::Delete { selection: Some(selection), .. } => placeholders_in(selection, f),
+        Statement::InsertSelect { query, .. } => for_each_placeholder(query, f),
+        Statement::Explain { statement, .. } => for_each_placeholder(statement, f),
+        _ => {}
+    }
+}
+
+fn placeholders_in(expr: &mut Expression, f: &mut impl FnMut(&mut Expression)) {
+    match expr {
+        Expression::Placeholder(_) => f(expr),
+        Expression::UnaryOperation { operand, .. } => placeholders_in(operand, f),
+        Expression::BinaryOperation { left_operand, right_operand, .. } => {
+            placeholders_in(left_operand, f);
+            placeholders_in(right_operand, f);
+        }
+        Expression::Grouped(inner)
+        | Expression::Collate { expr: inner, .. }
+        | Expression::Cast { expr: inner, .. } => placeholders_in(inner, f),
+        Expression::Function { args, over, .. } => {
+            args.iter_mut().for_each(|arg| placeholders_in(arg, f));
+            if let Some(window) = over {
+                window.partition_by.iter_mut().for_each(|expr| placeholders_in(expr, f));
+                window.order_by.iter_mut().for_each(|item| placeholders_in(&mut item.expr, f));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The literal that evaluates to `value`.
+pub fn literal(value: &Value) -> Expression {
+    let negative = |operand| Expression::UnaryOperation { operator: UnaryOperator::Negate, operand: Box::new(operand) };
+    let typed = |data_type, value: String| Expression::TypedString { data_type, value };
+    match value {
+        Value::Null => Expression::Null,
+        Value::Bool(b) => Expression::Boolean(*b),
+        Value::String(s) => Expression::String(s.clone()),
+        // The digits of the smallest integer don't fit in one
+        Value::Int(i64::MIN) => Expression::BinaryOperation {
+            left_operand: Box::new(negative(Expression::Number(i64::MAX as u64))),
+            operator: BinaryOperator::Subtract,
+            right_operand: Box::new(Expression::Number(1)),
+        },
+        Value::Int(n) if *n < 0 => negative(Expression::Number(n.unsigned_abs())),
+        Value::Int(n) => Expression::Number(*n as u64),
+        Value::Decimal(d) if d.mantissa() < 0 => negative(Expression::Decimal(d.negate().to_string())),
+        Value::Decimal(d) => Expression::Decimal(d.to_string()),
+        Value::Date(date) => typed(DBType::Date, date.to_string()),
+        Value::Time(time) => typed(DBType::Time, time.to_string()),
+        Value::Timestamp(timestamp) => typed(DBType::Timestamp, timestamp.to_string()),
+    }
+}
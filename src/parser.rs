@@ -1,127 +1,1917 @@
-use crate::tokenizer::{Token, Keyword};
-use crate::ast::{Statement};
-use crate::pratt::PrattParser;
-use crate::tokenizer::ParseError;
+use core::ops::Range;
+
+use crate::prelude::*;
+use crate::tokenizer::{Token, TokenKind, Keyword};
+use crate::ast::{
+    DBType, Expression, GrantObject, GrantedPrivilege, GroupingElement, Hint, Join, JoinKind, LockStrength, LockWait,
+    LockingClause, OrderByExpr, Privilege, SelectItem, SetOperator, SetScope, Statement, TableAlias, TableFactor,
+    TableSample, TransactionCommand, WildcardOptions,
+};
+#[cfg(feature = "cte")]
+use crate::ast::Cte;
+#[cfg(feature = "ddl")]
+use crate::ast::{
+    Constraint, FunctionParameter, RoleOption, RoutineType, TableColumn, TriggerBody, TriggerEvent, TriggerTiming,
+};
+#[cfg(feature = "dml")]
+use crate::ast::{CopyOption, CopyTarget, DuplicateHandling, LoadDataFormat, MergeAction, MergeClause, MergeSource};
+#[cfg(not(all(feature = "ddl", feature = "dml", feature = "cte")))]
+use crate::capabilities::missing;
+use crate::extension::Extensions;
+use crate::decimal::MAX_PRECISION;
+use crate::ident::Ident;
+use crate::pratt::{decimal_type, PrattParser};
+use crate::tokenizer::{tokenize_with_dialect, tokenize_with_errors, Limit, ParseError};
+use crate::dialect::Dialect;
+use crate::session::ParserOptions;
+
+/// Words that may follow a FROM item but can't be its alias.
+const NON_ALIAS_WORDS: &[&str] = &[
+    "JOIN", "INNER", "LEFT", "RIGHT", "FULL", "CROSS", "NATURAL", "TABLESAMPLE", "FOR", "UNION", "INTERSECT", "EXCEPT",
+    "GROUP", "QUALIFY", "PIVOT", "UNPIVOT",
+];
+
+/// The words of `NON_ALIAS_WORDS` that start a join rather than being one,
+/// so that only a following word (OUTER, JOIN) makes them join words.
+const JOIN_PREFIXES: &[&str] = &["INNER", "LEFT", "RIGHT", "FULL", "CROSS", "NATURAL"];
+
+/// The privileges of GRANT and REVOKE that are words rather than
+/// keywords, as SELECT and INSERT are.
+const PRIVILEGE_WORDS: [&str; 8] = ["ALL", "UPDATE", "DELETE", "TRUNCATE", "REFERENCES", "TRIGGER", "USAGE", "EXECUTE"];
 
 pub struct SQLParser<'a> {
     tokens: &'a [Token],
     position: usize,
+    marks: Marks,
+    expected: ExpectedSet,
+    options: ParserOptions,
+    extensions: Option<&'a Extensions>,
+    /// How many queries, FROM items and statements the current token is
+    /// inside; expressions nest further on top of it.
+    depth: usize,
+}
+
+/// The kinds of token the parser tested for at the furthest position it
+/// reached. Every check goes through `record`, so when parsing stops this
+/// is exactly the set of tokens that would have let it continue.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExpectedSet {
+    pub(crate) at: usize,
+    pub(crate) kinds: Vec<TokenKind>,
+    /// What an identifier at `at` would name, for completion; any other
+    /// identifier there is a new name, such as an alias.
+    pub(crate) names: Vec<Name>,
+}
+
+/// Something existing that an identifier names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Name {
+    Table,
+    Column,
+}
+
+impl ExpectedSet {
+    pub(crate) fn record(&mut self, at: usize, kind: TokenKind) {
+        if at > self.at {
+            self.at = at;
+            self.kinds.clear();
+            self.names.clear();
+        }
+        if at == self.at && !self.kinds.contains(&kind) {
+            self.kinds.push(kind);
+        }
+    }
+
+    /// Records an identifier at `at` that names a table or a column.
+    pub(crate) fn name(&mut self, at: usize, name: Name) {
+        self.record(at, TokenKind::Identifier);
+        if at == self.at && !self.names.contains(&name) {
+            self.names.push(name);
+        }
+    }
+
+    /// Records what `other`, the set of a parser started further on, looked for.
+    pub(crate) fn merge(&mut self, other: &ExpectedSet) {
+        for kind in &other.kinds {
+            self.record(other.at, *kind);
+        }
+        for name in &other.names {
+            self.name(other.at, *name);
+        }
+    }
+
+    /// The error for stopping at the furthest position.
+    pub(crate) fn error(&self, tokens: &[Token]) -> ParseError {
+        ParseError::Unexpected {
+            found: tokens.get(self.at).cloned().unwrap_or(Token::Eof),
+            expected: self.kinds.clone(),
+        }
+    }
+}
+
+/// Token indices of the names of the parsed statement, in source order,
+/// and the token ranges of its nodes, recorded as each is built. Each list
+/// of ranges is in the order `visit::walk` visits the nodes, parents first,
+/// so a node's range is inserted ahead of those recorded inside it.
+/// `span::parse_with_source_map` turns them into byte ranges.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Marks {
+    pub(crate) table: Option<usize>,
+    pub(crate) columns: Vec<usize>,
+    pub(crate) order_by: Vec<Range<usize>>,
+    pub(crate) statements: Vec<Range<usize>>,
+    pub(crate) factors: Vec<Range<usize>>,
+    pub(crate) joins: Vec<Range<usize>>,
+    pub(crate) expressions: Vec<Range<usize>>,
 }
 
 impl<'a> SQLParser<'a> {
+    /// Creates a parser with the default options, which include a limit on
+    /// expression nesting depth.
     pub fn new(tokens: &'a [Token]) -> Self {
-        Self { tokens, position: 0 }
+        Self::with_options(tokens, ParserOptions::default())
     }
 
-    fn peek(&self) -> Option<&Token> {
+    /// Creates a parser that applies the toggles and limits of `options`.
+    pub fn with_options(tokens: &'a [Token], options: ParserOptions) -> Self {
+        Self {
+            tokens,
+            position: 0,
+            marks: Marks::default(),
+            expected: ExpectedSet::default(),
+            options,
+            extensions: None,
+            depth: 0,
+        }
+    }
+
+    /// Lets the handlers of `extensions` parse the statements starting with
+    /// their keywords.
+    pub fn with_extensions(mut self, extensions: &'a Extensions) -> Self {
+        self.extensions = Some(extensions);
+        self
+    }
+
+    /// Number of tokens consumed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Index of the furthest token the parser tested, which is where a
+    /// failed parse got stuck even if it backtracked from there.
+    pub fn furthest(&self) -> usize {
+        self.expected.at
+    }
+
+    pub(crate) fn marks(&self) -> &Marks {
+        &self.marks
+    }
+
+    /// The token kinds that would have been accepted at the furthest
+    /// position the parser reached, e.g. after a failed `parse_statement`.
+    pub fn expected(&self) -> &[TokenKind] {
+        &self.expected.kinds
+    }
+
+    /// The next token, without consuming it.
+    pub fn peek(&self) -> Option<&Token> {
     self.tokens.get(self.position)
 }
 
-    fn advance(&mut self) -> Option<&Token> {
+    /// Consumes the next token, whatever it is.
+    pub fn advance(&mut self) -> Option<&Token> {
+        trace_event!("token", self.position, crate::trace::TraceKind::Token);
         let token = self.tokens.get(self.position);
         self.position += 1;
         token
     }
 
+    /// Records that `kind` is acceptable here and tests the current token.
+    pub fn check(&mut self, kind: TokenKind) -> bool {
+        self.expected.record(self.position, kind);
+        self.peek().is_some_and(|token| kind.matches(token))
+    }
+
+    /// Like `check`, recording all of `kinds`.
+    fn check_any(&mut self, kinds: &[TokenKind]) -> bool {
+        let mut found = false;
+        for kind in kinds {
+            found |= self.check(*kind);
+        }
+        found
+    }
+
+    /// Consumes the current token if it is of `kind`.
+    pub fn eat(&mut self, kind: TokenKind) -> bool {
+        let found = self.check(kind);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    /// Runs `parse` one level deeper, failing rather than recursing once
+    /// that is deeper than `max_expression_depth`. Queries, FROM items and
+    /// the statements inside EXPLAIN and PREPARE count towards the limit as
+    /// expressions do, so no input nests deep enough to overflow the stack.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, ParseError>) -> Result<T, ParseError> {
+        if let Some(max) = self.options.max_expression_depth {
+            if self.depth >= max {
+                return Err(ParseError::LimitExceeded { limit: Limit::ExpressionDepth, max });
+            }
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn unexpected(&self) -> ParseError {
+        self.expected.error(self.tokens)
+    }
+
+    /// Fails if a feature disabled in the options is used.
+    fn require(&self, enabled: bool, feature: &str) -> Result<(), ParseError> {
+        if enabled {
+            Ok(())
+        } else {
+            Err(ParseError::General(format!("{} are disabled by the parser options", feature)))
+        }
+    }
+
+    pub fn expect(&mut self, kind: TokenKind) -> Result<(), ParseError> {
+        if self.eat(kind) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
     fn expect_keyword(&mut self, keyword: Keyword) -> Result<(), ParseError> {
+        self.expect(TokenKind::Keyword(keyword))
+    }
+
+    pub fn expect_number(&mut self) -> Result<u64, ParseError> {
+        if !self.check(TokenKind::Number) || matches!(self.peek(), Some(Token::Decimal(_))) {
+            return Err(self.unexpected());
+        }
         match self.advance() {
-            Some(Token::Keyword(k)) if *k == keyword => Ok(()),
-            Some(_tok) => Err(ParseError::ExpectedKeyword(format!("{:?}", keyword))),
-            None => Err(ParseError::UnexpectedEnd),
+            Some(Token::Number(n)) => Ok(*n),
+            _ => unreachable!("checked for a number"),
+        }
+    }
+
+    /// Consumes one of the non-reserved words such as ROWS or ONLY, matched
+    /// case-insensitively against the identifier text.
+    pub fn expect_word(&mut self, words: &[&'static str]) -> Result<(), ParseError> {
+        for word in words {
+            if self.eat(TokenKind::Word(word)) {
+                return Ok(());
+            }
+        }
+        Err(self.unexpected())
+    }
+
+    /// Consumes whichever of the non-reserved `words` comes next, if any.
+    fn eat_word_of(&mut self, words: &[&'static str]) -> Option<&'static str> {
+        let mut found = None;
+        for word in words {
+            if found.is_none() && self.check(TokenKind::Word(word)) {
+                found = Some(*word);
+            }
+        }
+        if found.is_some() {
+            self.advance();
+        }
+        found
+    }
+
+    /// Parses `item` repeatedly, separated by commas.
+    pub fn comma_separated<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        let mut items = vec![item(self)?];
+        while self.eat(TokenKind::Comma) {
+            items.push(item(self)?);
         }
+        Ok(items)
     }
 
-    fn expect_identifier(&mut self) -> Result<String, ParseError> {
+    pub fn expect_string(&mut self) -> Result<String, ParseError> {
+        if !self.check(TokenKind::String) {
+            return Err(self.unexpected());
+        }
+        // Bits and bytes aren't text
+        match self.peek() {
+            Some(Token::String(s)) => {
+                let s = s.clone();
+                self.advance();
+                Ok(s)
+            }
+            Some(Token::PrefixedString(prefix, s)) if prefix.is_text() => {
+                let s = s.clone();
+                self.advance();
+                Ok(s)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    pub fn expect_identifier(&mut self) -> Result<Ident, ParseError> {
+        if !self.check(TokenKind::Identifier) {
+            return Err(self.unexpected());
+        }
         match self.advance() {
             Some(Token::Identifier(name)) => Ok(name.clone()),
-            Some(_) => Err(ParseError::ExpectedIdentifier),
-            None => Err(ParseError::UnexpectedEnd),
+            _ => unreachable!("checked for an identifier"),
         }
     }
 
-    #[allow(dead_code)]
-    fn debug_print(&self, message: &str) {
-    println!("[DEBUG] {} at position {}", message, self.position);
-}
+    /// The name of an existing table, which completion offers tables for.
+    fn expect_table_name(&mut self) -> Result<Ident, ParseError> {
+        self.expected.name(self.position, Name::Table);
+        self.expect_identifier()
+    }
+
+    /// The name of a column of a table, which completion offers columns for.
+    fn expect_column_name(&mut self) -> Result<Ident, ParseError> {
+        self.expected.name(self.position, Name::Column);
+        self.expect_identifier()
+    }
 
+    /// An identifier as plain text, for the names the AST keeps as strings
+    /// (functions, roles, prepared statements and the like).
+    fn expect_name(&mut self) -> Result<String, ParseError> {
+        Ok(self.expect_identifier()?.value)
+    }
 
     pub fn parse_statement(&mut self) -> Result<Statement, ParseError> {
-        match self.peek() {
-            Some(Token::Keyword(Keyword::Select)) => self.parse_select(),
-            Some(tok) => Err(ParseError::UnknownStartOfStatement(format!("Unexpected start of statement: {:?}", tok))),
-            None => Err(ParseError::General("Empty input".to_string())),
+        trace_span!("statement", self.position);
+        let (start, first) = (self.position, self.marks.statements.len());
+        if let Some(extensions) = self.extensions {
+            // Whatever a handler parsed is inside the custom statement, out
+            // of sight of the source map
+            let marks = self.marks.clone();
+            for handler in extensions.handlers(self.peek()) {
+                if let Some(statement) = handler(self)? {
+                    self.marks = marks;
+                    self.marks.statements.push(start..self.position);
+                    return Ok(Statement::Custom(statement));
+                }
+                self.position = start;
+                self.marks = marks.clone();
+            }
+        }
+        if self.check_any(&[
+            TokenKind::Keyword(Keyword::Select),
+            TokenKind::Keyword(Keyword::Values),
+            TokenKind::Keyword(Keyword::With),
+            TokenKind::LeftParentheses,
+        ]) {
+            return self.parse_query();
+        }
+        let statement = if self.check(TokenKind::Keyword(Keyword::Create)) {
+            self.require(self.options.allow_ddl, "DDL statements")?;
+            self.parse_create()
+        } else if self.check(TokenKind::Keyword(Keyword::Drop)) {
+            self.require(self.options.allow_ddl, "DDL statements")?;
+            self.parse_drop_table()
+        } else if self.check(TokenKind::Keyword(Keyword::Insert)) {
+            self.parse_insert()
+        } else if self.check(TokenKind::Word("UPDATE")) {
+            self.parse_update()
+        } else if self.check(TokenKind::Word("DELETE")) {
+            self.parse_delete()
+        } else if self.check_any(&[TokenKind::Keyword(Keyword::Grant), TokenKind::Keyword(Keyword::Revoke)]) {
+            self.parse_grant_or_revoke()
+        } else if self.check(TokenKind::Keyword(Keyword::Alter)) {
+            self.parse_alter()
+        } else if self.check(TokenKind::Keyword(Keyword::Merge)) {
+            self.parse_merge()
+        } else if self.check(TokenKind::Keyword(Keyword::Set)) {
+            self.parse_set()
+        } else if self.check(TokenKind::Word("PREPARE")) {
+            self.parse_prepare()
+        } else if self.check(TokenKind::Word("EXECUTE")) {
+            self.parse_execute()
+        } else if self.check(TokenKind::Word("DEALLOCATE")) {
+            self.parse_deallocate()
+        } else if self.check(TokenKind::Word("EXPLAIN")) {
+            self.parse_explain()
+        } else if ["BEGIN", "START", "COMMIT", "END", "ROLLBACK"].iter().any(|word| self.check(TokenKind::Word(word))) {
+            self.parse_transaction()
+        } else if self.check(TokenKind::Word("COPY")) {
+            self.parse_copy()
+        } else if self.check(TokenKind::Word("LOAD")) {
+            self.parse_load_data()
+        } else {
+            match self.peek() {
+                Some(Token::Eof) | None => Err(ParseError::General("Empty input".to_string())),
+                Some(tok) => Err(ParseError::UnknownStartOfStatement(format!("Unexpected start of statement: {:?}", tok))),
+            }
+        }?;
+        self.marks.statements.insert(first, start..self.position);
+        Ok(statement)
+    }
+
+    /// Parses one statement that must make up the whole input, apart from
+    /// an optional trailing semicolon.
+    pub fn parse_to_end(&mut self) -> Result<Statement, ParseError> {
+        let statement = self.parse_statement()?;
+        self.eat(TokenKind::Semicolon);
+        self.expect(TokenKind::Eof)?;
+        Ok(statement)
+    }
+
+    /// `[WITH ...] operand {UNION|INTERSECT|EXCEPT [ALL|DISTINCT] operand}`,
+    /// where INTERSECT binds tighter than UNION and EXCEPT. The ORDER BY
+    /// and row limits of the last SELECT stay with that SELECT.
+    fn parse_query(&mut self) -> Result<Statement, ParseError> {
+        self.nested(Self::parse_query_body)
+    }
+
+    fn parse_query_body(&mut self) -> Result<Statement, ParseError> {
+        if self.check(TokenKind::Keyword(Keyword::With)) {
+            return self.parse_with();
+        }
+        let (start, first) = (self.position, self.marks.statements.len());
+        let mut left = self.parse_intersection()?;
+        while let Some(word) = self.eat_word_of(&["UNION", "EXCEPT"]) {
+            let operator = if word == "UNION" { SetOperator::Union } else { SetOperator::Except };
+            let all = self.parse_set_quantifier();
+            let right = self.parse_intersection()?;
+            left = Statement::SetOperation { operator, all, left: Box::new(left), right: Box::new(right) };
+            self.marks.statements.insert(first, start..self.position);
         }
+        Ok(left)
+    }
+
+    fn parse_intersection(&mut self) -> Result<Statement, ParseError> {
+        let (start, first) = (self.position, self.marks.statements.len());
+        let mut left = self.parse_query_operand()?;
+        while self.eat(TokenKind::Word("INTERSECT")) {
+            let all = self.parse_set_quantifier();
+            let right = self.parse_query_operand()?;
+            left = Statement::SetOperation {
+                operator: SetOperator::Intersect,
+                all,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+            self.marks.statements.insert(first, start..self.position);
+        }
+        Ok(left)
+    }
+
+    /// `ALL` (true) or `[DISTINCT]` (false) after a set operator.
+    fn parse_set_quantifier(&mut self) -> bool {
+        if self.eat(TokenKind::Word("ALL")) {
+            return true;
+        }
+        self.eat(TokenKind::Word("DISTINCT"));
+        false
+    }
+
+    /// SELECT, VALUES or a parenthesized query.
+    fn parse_query_operand(&mut self) -> Result<Statement, ParseError> {
+        if self.check(TokenKind::Keyword(Keyword::Select)) {
+            self.parse_select()
+        } else if self.check(TokenKind::Keyword(Keyword::Values)) {
+            self.parse_values()
+        } else if self.eat(TokenKind::LeftParentheses) {
+            let query = self.parse_query()?;
+            self.expect(TokenKind::RightParentheses)?;
+            Ok(query)
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    /// WITH [RECURSIVE] name [(columns)] AS (query), ... query
+    #[cfg(feature = "cte")]
+    fn parse_with(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("with", self.position);
+        let (start, first) = (self.position, self.marks.statements.len());
+        self.expect_keyword(Keyword::With)?;
+        let recursive = self.eat(TokenKind::Word("RECURSIVE"));
+        let ctes = self.comma_separated(|p| {
+            let name = p.expect_identifier()?;
+            let mut columns = Vec::new();
+            if p.check(TokenKind::LeftParentheses) {
+                columns = p.parse_column_names()?;
+            }
+            p.expect_keyword(Keyword::As)?;
+            p.expect(TokenKind::LeftParentheses)?;
+            let query = p.parse_query()?;
+            p.expect(TokenKind::RightParentheses)?;
+            Ok(Cte { name, columns, query: Box::new(query) })
+        })?;
+        let body = self.parse_query()?;
+        self.marks.statements.insert(first, start..self.position);
+        Ok(Statement::With { recursive, ctes, body: Box::new(body) })
     }
 
     fn parse_select(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("select", self.position);
+        let (start, first) = (self.position, self.marks.statements.len());
         self.expect_keyword(Keyword::Select)?;
 
+        // Optimizer hints: `/*+ ... */` comments and MySQL's STRAIGHT_JOIN
+        let mut hints = Vec::new();
+        while let Some(Token::Hint(text)) = self.peek() {
+            hints.extend(Hint::parse_list(text));
+            self.advance();
+        }
+        if self.eat(TokenKind::Word("STRAIGHT_JOIN")) {
+            hints.push(Hint { name: "STRAIGHT_JOIN".to_string(), args: Vec::new() });
+        }
+
+        // Handle optional SQL Server style TOP n / TOP (n)
+        let mut limit = None;
+        if self.eat(TokenKind::Keyword(Keyword::Top)) {
+            self.require(self.options.allow_row_limits, "Row limiting clauses")?;
+            if self.eat(TokenKind::LeftParentheses) {
+                limit = Some(self.expect_number()?);
+                self.expect(TokenKind::RightParentheses)?;
+            } else {
+                limit = Some(self.expect_number()?);
+            }
+        }
+
         let mut columns = Vec::new();
+        let mut wildcard = WildcardOptions::default();
 
-        // Parse column list until we hit FROM
+        // The select list: `*` or an expression with an optional alias, up to FROM
         loop {
-            match self.advance() {
-                Some(Token::Identifier(name)) => columns.push(name.clone()),
-                Some(Token::Comma) => continue,
-                Some(Token::Keyword(Keyword::From)) => break,
-                Some(tok) => {
-                    return Err(ParseError::General(format!("Unexpected token in column list: {:?}", tok)))
-                }
-                None => {
-                    return Err(ParseError::General("Unexpected end of input while reading columns.".to_string()))
+            self.marks.columns.push(self.position);
+            if self.check(TokenKind::Multiply) {
+                // The `*` is an identifier to the AST
+                self.marks.expressions.push(self.position..self.position + 1);
+                self.advance();
+                columns.push(SelectItem::wildcard());
+                if self.options.supports_warehouse_extensions() {
+                    self.parse_wildcard_options(&mut wildcard)?;
                 }
+            } else {
+                let expr = self.parse_expression()?;
+                let alias = if self.eat(TokenKind::Keyword(Keyword::As)) || self.check(TokenKind::Identifier) {
+                    Some(self.expect_identifier()?)
+                } else {
+                    None
+                };
+                columns.push(SelectItem { expr, alias });
+            }
+            if !self.eat(TokenKind::Comma) {
+                break;
             }
         }
+        self.expect_keyword(Keyword::From)?;
 
-        let table = self.expect_identifier()?;
+        let from = self.parse_table_factor()?;
+        // Joined items don't change the table of the statement
+        let table_mark = self.marks.table;
+        let mut joins = Vec::new();
+        loop {
+            // A join spans its keywords, table and condition; a comma join
+            // only its table
+            let (at, first) = (self.position, self.marks.joins.len());
+            let start = if self.peek() == Some(&Token::Comma) { at + 1 } else { at };
+            let Some(kind) = self.parse_join_kind()? else { break };
+            let relation = self.parse_table_factor()?;
+            let on = if kind == JoinKind::Cross {
+                None
+            } else {
+                self.expect_keyword(Keyword::On)?;
+                Some(self.parse_expression()?)
+            };
+            joins.push(Join { kind, relation, on });
+            self.marks.joins.insert(first, start..self.position);
+        }
+        self.marks.table = table_mark;
         let mut selection = None;
 
         // Handle optional WHERE clause
-        if let Some(Token::Keyword(Keyword::Where)) = self.peek() {
-            self.advance(); // consume WHERE
-            let remaining_tokens = &self.tokens[self.position..];
-            let mut expr_parser = PrattParser::new(remaining_tokens);
-            let expr = expr_parser
-                .parse_expression(1)
-                .map_err(ParseError::InvalidExpression)?;
-            selection = Some(expr);
+        if self.eat(TokenKind::Keyword(Keyword::Where)) {
+            selection = Some(self.parse_expression()?);
+        }
+
+        // Handle optional GROUP BY clause
+        let mut group_by = Vec::new();
+        if self.eat(TokenKind::Word("GROUP")) {
+            self.expect_keyword(Keyword::By)?;
+            group_by = self.comma_separated(|p| p.parse_grouping_element())?;
+        }
+
+        // Handle optional QUALIFY, a WHERE clause over window functions
+        let mut qualify = None;
+        let warehouse = self.options.supports_warehouse_extensions();
+        if cfg!(feature = "window") && warehouse && self.eat(TokenKind::Word("QUALIFY")) {
+            qualify = Some(self.parse_expression()?);
         }
 
         // Handle optional ORDER BY clause
-        let mut order_by = None;
+        let mut order_by = Vec::new();
 
-        if let Some(Token::Keyword(Keyword::Order)) = self.peek() {
-            self.advance(); // consume ORDER
+        if self.eat(TokenKind::Keyword(Keyword::Order)) {
             self.expect_keyword(Keyword::By)?; // expect BY
 
-            let mut order_columns = Vec::new();
+            order_by = self.comma_separated(|p| {
+                let start = p.position;
+                let expr = p.parse_expression()?;
+                let asc = match p.eat_word_of(&["ASC", "DESC"]) {
+                    Some("ASC") => Some(true),
+                    Some(_) => Some(false),
+                    None => None,
+                };
+                p.marks.order_by.push(start..p.position);
+                Ok(OrderByExpr { expr, asc })
+            })?;
 
-            loop {
-                match self.advance() {
-                    Some(Token::Identifier(name)) => order_columns.push(name.clone()),
-                    Some(Token::Comma) => continue,
-                    Some(Token::Semicolon) | Some(Token::Eof) => break,
-                    Some(tok) => {
-                        return Err(ParseError::General(format!("Unexpected token in ORDER BY: {:?}", tok)))
+            // Only row limiting or the end of the statement may follow
+            if !self.check_any(&[
+                TokenKind::Keyword(Keyword::Limit),
+                TokenKind::Keyword(Keyword::Offset),
+                TokenKind::Keyword(Keyword::Fetch),
+                TokenKind::Word("FOR"),
+                TokenKind::RightParentheses,
+                TokenKind::Semicolon,
+                TokenKind::Eof,
+            ]) {
+                return Err(self.unexpected());
+            }
+        }
+
+        // Handle optional row limiting: LIMIT n [OFFSET m], MySQL's LIMIT m, n,
+        // and ANSI OFFSET m ROWS FETCH FIRST n ROWS ONLY
+        let mut offset = None;
+
+        if self.eat(TokenKind::Keyword(Keyword::Limit)) {
+            self.require(self.options.allow_row_limits, "Row limiting clauses")?;
+            let first = self.expect_number()?;
+            if self.eat(TokenKind::Comma) {
+                offset = Some(first);
+                limit = Some(self.expect_number()?);
+            } else {
+                limit = Some(first);
+            }
+        }
+
+        if self.eat(TokenKind::Keyword(Keyword::Offset)) {
+            self.require(self.options.allow_row_limits, "Row limiting clauses")?;
+            offset = Some(self.expect_number()?);
+            if !self.eat(TokenKind::Word("ROW")) {
+                self.eat(TokenKind::Word("ROWS"));
+            }
+        }
+
+        if self.eat(TokenKind::Keyword(Keyword::Fetch)) {
+            self.require(self.options.allow_row_limits, "Row limiting clauses")?;
+            self.expect_word(&["FIRST", "NEXT"])?;
+            limit = Some(self.expect_number()?);
+            self.expect_word(&["ROW", "ROWS"])?;
+            self.expect_word(&["ONLY"])?;
+        }
+
+        let mut locking = Vec::new();
+        while self.eat(TokenKind::Word("FOR")) {
+            locking.push(self.parse_locking_clause()?);
+        }
+
+        self.marks.statements.insert(first, start..self.position);
+        Ok(Statement::Select {
+            hints,
+            columns,
+            wildcard,
+            from: Box::new(from),
+            joins,
+            selection,
+            group_by,
+            qualify,
+            order_by,
+            limit,
+            offset,
+            locking,
+        })
+    }
+
+    /// `EXCLUDE name`, `EXCLUDE (name, ...)` and `REPLACE (expr AS name, ...)`
+    /// after a `*`.
+    fn parse_wildcard_options(&mut self, wildcard: &mut WildcardOptions) -> Result<(), ParseError> {
+        if self.eat(TokenKind::Word("EXCLUDE")) {
+            // Not marked: the source map's columns are the select list's
+            if self.eat(TokenKind::LeftParentheses) {
+                wildcard.exclude.extend(self.comma_separated(|p| p.expect_column_name())?);
+                self.expect(TokenKind::RightParentheses)?;
+            } else {
+                wildcard.exclude.push(self.expect_column_name()?);
+            }
+        }
+        if self.eat(TokenKind::Word("REPLACE")) {
+            self.expect(TokenKind::LeftParentheses)?;
+            let items = self.comma_separated(|p| {
+                let expr = p.parse_expression()?;
+                p.expect_keyword(Keyword::As)?;
+                Ok((expr, p.expect_identifier()?))
+            })?;
+            wildcard.replace.extend(items);
+            self.expect(TokenKind::RightParentheses)?;
+        }
+        Ok(())
+    }
+
+    /// An expression, ROLLUP(...), CUBE(...) or GROUPING SETS (...).
+    fn parse_grouping_element(&mut self) -> Result<GroupingElement, ParseError> {
+        match self.eat_word_of(&["ROLLUP", "CUBE", "GROUPING"]) {
+            Some("ROLLUP") => Ok(GroupingElement::Rollup(self.parse_value_list()?)),
+            Some("CUBE") => Ok(GroupingElement::Cube(self.parse_value_list()?)),
+            Some(_) => {
+                self.expect_word(&["SETS"])?;
+                self.expect(TokenKind::LeftParentheses)?;
+                let sets = self.comma_separated(|p| {
+                    if !p.eat(TokenKind::LeftParentheses) {
+                        return Ok(vec![p.parse_expression()?]);
+                    }
+                    let mut set = Vec::new();
+                    if !p.eat(TokenKind::RightParentheses) {
+                        set = p.comma_separated(|p| p.parse_expression())?;
+                        p.expect(TokenKind::RightParentheses)?;
                     }
-                    None => return Err(ParseError::UnexpectedEnd),
+                    Ok(set)
+                })?;
+                self.expect(TokenKind::RightParentheses)?;
+                Ok(GroupingElement::GroupingSets(sets))
+            }
+            None => Ok(GroupingElement::Expression(self.parse_expression()?)),
+        }
+    }
+
+    /// A FROM item, followed in dialects that have them by any number of
+    /// PIVOT and UNPIVOT operators.
+    fn parse_table_factor(&mut self) -> Result<TableFactor, ParseError> {
+        let (start, first) = (self.position, self.marks.factors.len());
+        let mut factor = self.nested(Self::parse_table_primary)?;
+        self.marks.factors.insert(first, start..self.position);
+        while self.options.supports_pivot() {
+            factor = match self.eat_word_of(&["PIVOT", "UNPIVOT"]) {
+                Some("PIVOT") => self.parse_pivot(factor)?,
+                Some(_) => self.parse_unpivot(factor)?,
+                None => break,
+            };
+            self.marks.factors.insert(first, start..self.position);
+        }
+        Ok(factor)
+    }
+
+    /// `(aggregate FOR column IN (value, ...)) [alias]`, after PIVOT.
+    fn parse_pivot(&mut self, table: TableFactor) -> Result<TableFactor, ParseError> {
+        self.expect(TokenKind::LeftParentheses)?;
+        let aggregate = self.parse_expression()?;
+        self.expect_word(&["FOR"])?;
+        let column = self.expect_column_name()?;
+        self.expect_word(&["IN"])?;
+        let values = self.parse_value_list()?;
+        self.expect(TokenKind::RightParentheses)?;
+        let alias = self.parse_table_alias()?;
+        Ok(TableFactor::Pivot { table: Box::new(table), aggregate, column, values, alias })
+    }
+
+    /// `(value FOR name IN (column, ...)) [alias]`, after UNPIVOT.
+    fn parse_unpivot(&mut self, table: TableFactor) -> Result<TableFactor, ParseError> {
+        self.expect(TokenKind::LeftParentheses)?;
+        let value = self.expect_identifier()?;
+        self.expect_word(&["FOR"])?;
+        let name = self.expect_identifier()?;
+        self.expect_word(&["IN"])?;
+        self.expect(TokenKind::LeftParentheses)?;
+        let columns = self.comma_separated(|p| p.expect_column_name())?;
+        self.expect(TokenKind::RightParentheses)?;
+        self.expect(TokenKind::RightParentheses)?;
+        let alias = self.parse_table_alias()?;
+        Ok(TableFactor::Unpivot { table: Box::new(table), value, name, columns, alias })
+    }
+
+    /// A table name, optionally AS OF VERSION n, a parenthesized SELECT or
+    /// VALUES, or a table function call, each optionally LATERAL (apart
+    /// from tables) and aliased.
+    fn parse_table_primary(&mut self) -> Result<TableFactor, ParseError> {
+        let lateral = self.eat(TokenKind::Word("LATERAL"));
+        if self.eat(TokenKind::LeftParentheses) {
+            let subquery = self.parse_query()?;
+            self.expect(TokenKind::RightParentheses)?;
+            // The subquery's table isn't the table of this statement
+            self.marks.table = None;
+            let alias = self.parse_table_alias()?;
+            return Ok(TableFactor::Derived { lateral, subquery: Box::new(subquery), alias });
+        }
+
+        let mark = self.position;
+        let name = self.expect_table_name()?;
+        if self.eat(TokenKind::LeftParentheses) {
+            let mut args = Vec::new();
+            if !self.eat(TokenKind::RightParentheses) {
+                args = self.comma_separated(|p| p.parse_expression())?;
+                self.expect(TokenKind::RightParentheses)?;
+            }
+            let alias = self.parse_table_alias()?;
+            return Ok(TableFactor::Function { lateral, name: name.value, args, alias });
+        }
+        if lateral {
+            return Err(self.unexpected());
+        }
+        self.marks.table = Some(mark);
+        // AS OF VERSION comes before the alias, which may also start with AS
+        let next = self.tokens.get(self.position + 1);
+        let of = matches!(next, Some(Token::Identifier(word)) if word.eq_ignore_ascii_case("OF"));
+        if self.check(TokenKind::Keyword(Keyword::As)) && of {
+            self.position += 2;
+            self.expect_word(&["VERSION"])?;
+            let version = self.expect_number()?;
+            let alias = self.parse_table_alias()?;
+            return Ok(TableFactor::AsOf { name, version, alias });
+        }
+        let alias = self.parse_table_alias()?;
+        let sample = if self.eat(TokenKind::Word("TABLESAMPLE")) { Some(self.parse_table_sample()?) } else { None };
+        Ok(TableFactor::Table { name, alias, sample })
+    }
+
+    /// `method (percent) [REPEATABLE (seed)]`, after TABLESAMPLE.
+    fn parse_table_sample(&mut self) -> Result<TableSample, ParseError> {
+        let method = self.expect_identifier()?.to_uppercase();
+        self.expect(TokenKind::LeftParentheses)?;
+        let percent = self.parse_expression()?;
+        self.expect(TokenKind::RightParentheses)?;
+        let mut repeatable = None;
+        if self.eat(TokenKind::Word("REPEATABLE")) {
+            self.expect(TokenKind::LeftParentheses)?;
+            repeatable = Some(self.parse_expression()?);
+            self.expect(TokenKind::RightParentheses)?;
+        }
+        Ok(TableSample { method, percent, repeatable })
+    }
+
+    /// `UPDATE|NO KEY UPDATE|SHARE|KEY SHARE [OF table, ...] [NOWAIT|SKIP
+    /// LOCKED]`, after FOR.
+    fn parse_locking_clause(&mut self) -> Result<LockingClause, ParseError> {
+        let strength = match self.eat_word_of(&["UPDATE", "SHARE", "NO"]) {
+            Some("UPDATE") => LockStrength::Update,
+            Some("SHARE") => LockStrength::Share,
+            Some(_) => {
+                self.expect_keyword(Keyword::Key)?;
+                self.expect_word(&["UPDATE"])?;
+                LockStrength::NoKeyUpdate
+            }
+            None => {
+                self.expect_keyword(Keyword::Key)?;
+                self.expect_word(&["SHARE"])?;
+                LockStrength::KeyShare
+            }
+        };
+        let mut of = Vec::new();
+        if self.eat(TokenKind::Word("OF")) {
+            of = self.comma_separated(|p| p.expect_table_name())?;
+        }
+        let wait = match self.eat_word_of(&["NOWAIT", "SKIP"]) {
+            Some("NOWAIT") => Some(LockWait::NoWait),
+            Some(_) => {
+                self.expect_word(&["LOCKED"])?;
+                Some(LockWait::SkipLocked)
+            }
+            None => None,
+        };
+        Ok(LockingClause { strength, of, wait })
+    }
+
+    /// The start of a join: `,`, `CROSS JOIN`, `[INNER] JOIN` or
+    /// `LEFT|RIGHT|FULL [OUTER] JOIN`.
+    fn parse_join_kind(&mut self) -> Result<Option<JoinKind>, ParseError> {
+        if self.eat(TokenKind::Comma) {
+            return Ok(Some(JoinKind::Cross));
+        }
+        let kind = match self.eat_word_of(&["JOIN", "INNER", "LEFT", "RIGHT", "FULL", "CROSS"]) {
+            Some("JOIN") => return Ok(Some(JoinKind::Inner)),
+            Some("INNER") => JoinKind::Inner,
+            Some("LEFT") => JoinKind::Left,
+            Some("RIGHT") => JoinKind::Right,
+            Some("FULL") => JoinKind::Full,
+            Some(_) => JoinKind::Cross,
+            None => return Ok(None),
+        };
+        if matches!(kind, JoinKind::Left | JoinKind::Right | JoinKind::Full) {
+            self.eat(TokenKind::Word("OUTER"));
+        }
+        self.expect_word(&["JOIN"])?;
+        Ok(Some(kind))
+    }
+
+    /// `[AS] alias [(column, ...)]`, if present. Join words are never taken
+    /// as an alias, except that LEFT, RIGHT and the other words a join may
+    /// start with are one when no word follows them (`FROM t left WHERE`).
+    fn parse_table_alias(&mut self) -> Result<Option<TableAlias>, ParseError> {
+        if !self.eat(TokenKind::Keyword(Keyword::As)) {
+            let join_word = match self.peek() {
+                Some(Token::Identifier(word)) if JOIN_PREFIXES.iter().any(|w| word.eq_ignore_ascii_case(w)) => {
+                    matches!(self.tokens.get(self.position + 1), Some(Token::Identifier(_)))
                 }
+                Some(Token::Identifier(word)) => NON_ALIAS_WORDS.iter().any(|w| word.eq_ignore_ascii_case(w)),
+                _ => false,
+            };
+            if join_word || !self.check(TokenKind::Identifier) {
+                return Ok(None);
             }
+        }
+        let name = self.expect_identifier()?;
+        let mut columns = Vec::new();
+        if self.check(TokenKind::LeftParentheses) {
+            columns = self.parse_column_names()?;
+        }
+        Ok(Some(TableAlias { name, columns }))
+    }
+
+    /// VALUES (...), (...)
+    fn parse_values(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("values", self.position);
+        let (start, first) = (self.position, self.marks.statements.len());
+        self.expect_keyword(Keyword::Values)?;
+        let rows = self.comma_separated(|p| p.parse_value_list())?;
+        self.marks.statements.insert(first, start..self.position);
+        Ok(Statement::Values { rows })
+    }
+
+    /// Parses an expression starting at the current token with the Pratt
+    /// parser, then skips the tokens it consumed.
+    pub fn parse_expression(&mut self) -> Result<Expression, ParseError> {
+        trace_span!("expression", self.position);
+        // The expression's depth counts from that of the query it is in
+        let max_depth = self.options.max_expression_depth.map(|max| max - self.depth);
+        let mut expr_parser = PrattParser::with_max_depth(self.tokens, max_depth)
+            .with_dialect(self.options.dialect)
+            .with_ilike(self.options.supports_ilike())
+            .starting_at(self.position);
+        let result = expr_parser.parse_expression(1);
 
-            order_by = Some(order_columns);
+        // Merge what the expression parser looked for into our own set
+        self.expected.merge(expr_parser.expected_set());
+
+        let expr = result.map_err(|message| match self.options.max_expression_depth {
+            Some(max) if expr_parser.depth_exceeded() => ParseError::LimitExceeded { limit: Limit::ExpressionDepth, max },
+            _ if expr_parser.compiled_out() => ParseError::General(message),
+            _ => self.unexpected(),
+        })?;
+        self.position = expr_parser.position();
+        self.marks.expressions.extend(expr_parser.take_nodes());
+        Ok(expr)
+    }
+
+    /// CREATE TABLE, CREATE ROLE, CREATE TRIGGER, or CREATE [OR REPLACE]
+    /// VIEW|FUNCTION|PROCEDURE.
+    #[cfg(feature = "ddl")]
+    fn parse_create(&mut self) -> Result<Statement, ParseError> {
+        self.expect_keyword(Keyword::Create)?;
+        if self.eat(TokenKind::Keyword(Keyword::Or)) {
+            self.expect_word(&["REPLACE"])?;
+            if self.check(TokenKind::Word("VIEW")) {
+                self.parse_create_view(true)
+            } else {
+                self.parse_create_function(true)
+            }
+        } else if self.check(TokenKind::Word("VIEW")) {
+            self.parse_create_view(false)
+        } else if self.check_any(&[TokenKind::Word("FUNCTION"), TokenKind::Word("PROCEDURE")]) {
+            self.parse_create_function(false)
+        } else if self.check(TokenKind::Keyword(Keyword::Table)) {
+            self.parse_create_table()
+        } else if self.check(TokenKind::Word("TRIGGER")) {
+            self.parse_create_trigger()
+        } else if self.eat(TokenKind::Word("ROLE")) {
+            let role_name = self.expect_name()?;
+            let options = self.parse_role_options()?;
+            Ok(Statement::CreateRole { role_name, options })
+        } else {
+            Err(self.unexpected())
         }
+    }
 
-Ok(Statement::Select {
-    columns,
-    table,
-    selection,
-    order_by,
-    limit: None,
-})
+    #[cfg(feature = "ddl")]
+    fn parse_create_table(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("create_table", self.position);
+        self.expect_keyword(Keyword::Table)?;
+        self.marks.table = Some(self.position);
+        let table_name = self.expect_identifier()?;
+        self.expect(TokenKind::LeftParentheses)?;
 
+        let mut column_list = Vec::new();
+        loop {
+            column_list.push(self.parse_column_definition()?);
+            if self.eat(TokenKind::Comma) {
+                continue;
+            }
+            self.expect(TokenKind::RightParentheses)?;
+            break;
+        }
 
+        Ok(Statement::CreateTable { table_name, column_list })
+    }
 
+    /// The rest of CREATE [OR REPLACE] VIEW name [(columns)] AS query.
+    #[cfg(feature = "ddl")]
+    fn parse_create_view(&mut self, or_replace: bool) -> Result<Statement, ParseError> {
+        trace_span!("create_view", self.position);
+        self.expect_word(&["VIEW"])?;
+        self.marks.table = Some(self.position);
+        let name = self.expect_identifier()?;
+        let columns = if self.check(TokenKind::LeftParentheses) { self.parse_column_names()? } else { Vec::new() };
+        self.expect_keyword(Keyword::As)?;
+        let query = self.parse_query()?;
+        Ok(Statement::CreateView { or_replace, name, columns, query: Box::new(query) })
+    }
 
+    /// The rest of CREATE FUNCTION or CREATE PROCEDURE: the signature, then
+    /// AS 'body' and LANGUAGE name in either order. The body is usually
+    /// dollar-quoted (`$$ ... $$`) and is not parsed.
+    #[cfg(feature = "ddl")]
+    fn parse_create_function(&mut self, or_replace: bool) -> Result<Statement, ParseError> {
+        trace_span!("create_function", self.position);
+        let procedure = match self.eat_word_of(&["FUNCTION", "PROCEDURE"]) {
+            Some(word) => word == "PROCEDURE",
+            None => return Err(self.unexpected()),
+        };
+        let name = self.expect_name()?;
+        self.expect(TokenKind::LeftParentheses)?;
+        let mut parameters = Vec::new();
+        if !self.eat(TokenKind::RightParentheses) {
+            parameters = self.comma_separated(|p| {
+                // An identifier is the parameter's name unless it is all or the start of its type
+                let typed = matches!(
+                    p.tokens.get(p.position + 1),
+                    Some(Token::Comma | Token::RightParentheses | Token::Dot | Token::LeftParentheses)
+                );
+                let name = if p.check(TokenKind::Identifier) && !typed { Some(p.expect_name()?) } else { None };
+                Ok(FunctionParameter { name, data_type: p.parse_routine_type(false)? })
+            })?;
+            self.expect(TokenKind::RightParentheses)?;
+        }
+        let returns = if self.eat(TokenKind::Word("RETURNS")) { Some(self.parse_routine_type(true)?) } else { None };
 
+        let (mut body, mut language) = (None, None);
+        loop {
+            if body.is_none() && self.eat(TokenKind::Keyword(Keyword::As)) {
+                body = Some(self.expect_string()?);
+            } else if language.is_none() && self.eat(TokenKind::Word("LANGUAGE")) {
+                language = Some(if self.check(TokenKind::String) { self.expect_string()? } else { self.expect_name()? });
+            } else {
+                break;
+            }
+        }
+        let Some(body) = body else { return Err(self.unexpected()) };
+        Ok(Statement::CreateFunction { or_replace, procedure, name, parameters, returns, language, body })
     }
-}
\ No newline at end of file
+
+    /// The type of a parameter, or of what a routine `returns`: a column
+    /// type, or else the tokens up to the end of the parameter (or to AS or
+    /// LANGUAGE), as written.
+    #[cfg(feature = "ddl")]
+    fn parse_routine_type(&mut self, returns: bool) -> Result<RoutineType, ParseError> {
+        let start = self.position;
+        let ends = |p: &mut Self| {
+            if returns {
+                let words = [TokenKind::Keyword(Keyword::As), TokenKind::Word("LANGUAGE"), TokenKind::Semicolon];
+                p.check_any(&words) || p.check(TokenKind::Eof)
+            } else {
+                p.check_any(&[TokenKind::Comma, TokenKind::RightParentheses])
+            }
+        };
+        if let Ok(data_type) = self.parse_type() {
+            if ends(self) {
+                return Ok(RoutineType::Known(data_type));
+            }
+        }
+        self.position = start;
+        let (mut text, mut depth) = (String::new(), 0);
+        while depth > 0 || !ends(self) {
+            let word = match self.peek() {
+                Some(Token::Keyword(keyword)) => keyword.as_str().to_string(),
+                Some(Token::Identifier(ident)) => ident.to_string(),
+                Some(Token::Number(n)) => n.to_string(),
+                Some(Token::Dot) => ".".to_string(),
+                Some(Token::Comma) if depth > 0 => ",".to_string(),
+                Some(Token::LeftParentheses) => "(".to_string(),
+                Some(Token::RightParentheses) if depth > 0 => ")".to_string(),
+                _ => return Err(self.unexpected()),
+            };
+            match word.as_str() {
+                "(" => depth += 1,
+                ")" => depth -= 1,
+                _ => {}
+            }
+            // Words are spaced apart; punctuation is written up against them, a comma followed by a space
+            let joined = text.ends_with(['.', '(', ' ']) || matches!(word.as_str(), "." | "(" | ")" | ",");
+            if !text.is_empty() && !joined {
+                text.push(' ');
+            }
+            text.push_str(&word);
+            if word == "," {
+                text.push(' ');
+            }
+            self.advance();
+        }
+        if text.is_empty() {
+            return Err(self.unexpected());
+        }
+        Ok(RoutineType::Named(text))
+    }
+
+    /// The rest of CREATE TRIGGER. FOR EACH may be left out (Postgres,
+    /// meaning FOR EACH STATEMENT); the body is EXECUTE FUNCTION f(...), a
+    /// `BEGIN ... END` block kept as written, or a single statement.
+    #[cfg(feature = "ddl")]
+    fn parse_create_trigger(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("create_trigger", self.position);
+        self.expect_word(&["TRIGGER"])?;
+        let name = self.expect_name()?;
+        let timing = match self.eat_word_of(&["BEFORE", "AFTER", "INSTEAD"]) {
+            Some("BEFORE") => TriggerTiming::Before,
+            Some("AFTER") => TriggerTiming::After,
+            Some(_) => {
+                self.expect_word(&["OF"])?;
+                TriggerTiming::InsteadOf
+            }
+            None => return Err(self.unexpected()),
+        };
+        let (mut events, mut update_columns) = (Vec::new(), Vec::new());
+        loop {
+            events.push(if self.eat(TokenKind::Keyword(Keyword::Insert)) {
+                TriggerEvent::Insert
+            } else {
+                match self.eat_word_of(&["UPDATE", "DELETE", "TRUNCATE"]) {
+                    Some("UPDATE") => {
+                        if self.eat(TokenKind::Word("OF")) {
+                            update_columns.extend(self.comma_separated(|p| {
+                                p.marks.columns.push(p.position);
+                                p.expect_identifier()
+                            })?);
+                        }
+                        TriggerEvent::Update
+                    }
+                    Some("DELETE") => TriggerEvent::Delete,
+                    Some(_) => TriggerEvent::Truncate,
+                    None => return Err(self.unexpected()),
+                }
+            });
+            if !self.eat(TokenKind::Keyword(Keyword::Or)) {
+                break;
+            }
+        }
+        self.expect_keyword(Keyword::On)?;
+        self.marks.table = Some(self.position);
+        let table_name = self.expect_table_name()?;
+
+        let mut for_each_row = false;
+        if self.eat(TokenKind::Word("FOR")) {
+            self.eat(TokenKind::Word("EACH"));
+            for_each_row = match self.eat_word_of(&["ROW", "STATEMENT"]) {
+                Some(word) => word == "ROW",
+                None => return Err(self.unexpected()),
+            };
+        }
+        let mut condition = None;
+        if self.eat(TokenKind::Keyword(Keyword::When)) {
+            self.expect(TokenKind::LeftParentheses)?;
+            condition = Some(self.parse_expression()?);
+            self.expect(TokenKind::RightParentheses)?;
+        }
+
+        let body = if self.check(TokenKind::Block) {
+            match self.advance() {
+                Some(Token::Block(text)) => TriggerBody::Block(text.clone()),
+                _ => unreachable!("checked for a block"),
+            }
+        } else if self.eat(TokenKind::Word("EXECUTE")) {
+            let procedure = match self.eat_word_of(&["FUNCTION", "PROCEDURE"]) {
+                Some(word) => word == "PROCEDURE",
+                None => return Err(self.unexpected()),
+            };
+            let function = self.expect_name()?;
+            self.expect(TokenKind::LeftParentheses)?;
+            let mut args = Vec::new();
+            if !self.eat(TokenKind::RightParentheses) {
+                args = self.comma_separated(|p| p.parse_expression())?;
+                self.expect(TokenKind::RightParentheses)?;
+            }
+            TriggerBody::Execute { procedure, function, args }
+        } else if self.check(TokenKind::Word("BEGIN")) {
+            // The tokenizer found no END closing the block
+            return Err(ParseError::General("Unterminated BEGIN ... END block".to_string()));
+        } else {
+            TriggerBody::Statement(Box::new(self.parse_statement()?))
+        };
+        Ok(Statement::CreateTrigger { name, timing, events, update_columns, table_name, for_each_row, condition, body })
+    }
+
+    #[cfg(feature = "ddl")]
+    fn parse_column_definition(&mut self) -> Result<TableColumn, ParseError> {
+        trace_span!("column_definition", self.position);
+        self.marks.columns.push(self.position);
+        let column_name = self.expect_identifier()?;
+
+        let column_type = self.parse_type()?;
+
+        // Constraints run until the comma or closing parenthesis
+        let mut constraints = Vec::new();
+        loop {
+            if self.eat(TokenKind::Keyword(Keyword::Primary)) {
+                self.expect_keyword(Keyword::Key)?;
+                constraints.push(Constraint::PrimaryKey);
+            } else if self.eat(TokenKind::Keyword(Keyword::Not)) {
+                self.expect_keyword(Keyword::Null)?;
+                constraints.push(Constraint::NotNull);
+            } else if self.eat(TokenKind::Keyword(Keyword::Check)) {
+                self.expect(TokenKind::LeftParentheses)?;
+                constraints.push(Constraint::Check(self.parse_expression()?));
+                self.expect(TokenKind::RightParentheses)?;
+            } else if self.eat(TokenKind::Word("COLLATE")) {
+                constraints.push(Constraint::Collate(self.expect_identifier()?));
+            } else {
+                break;
+            }
+        }
+
+        Ok(TableColumn { column_name, column_type, constraints })
+    }
+
+    fn parse_type(&mut self) -> Result<DBType, ParseError> {
+        if self.eat(TokenKind::Keyword(Keyword::Int)) {
+            Ok(DBType::Int)
+        } else if self.eat(TokenKind::Keyword(Keyword::Bool)) {
+            Ok(DBType::Bool)
+        } else if self.eat(TokenKind::Keyword(Keyword::Varchar)) {
+            self.expect(TokenKind::LeftParentheses)
+                .map_err(|_| ParseError::General("VARCHAR requires a length, e.g. VARCHAR(255)".to_string()))?;
+            let length = self.expect_number()? as usize;
+            self.expect(TokenKind::RightParentheses)?;
+            Ok(DBType::Varchar(length))
+        } else if let Some(word) = self.eat_word_of(&["DATE", "TIME", "TIMESTAMP"]) {
+            Ok(match word {
+                "DATE" => DBType::Date,
+                "TIME" => DBType::Time,
+                _ => DBType::Timestamp,
+            })
+        } else if self.eat_word_of(&["DECIMAL", "NUMERIC"]).is_some() {
+            // DECIMAL [(precision [, scale])], by default of scale 0
+            let (mut precision, mut scale) = (MAX_PRECISION as u64, 0);
+            if self.eat(TokenKind::LeftParentheses) {
+                precision = self.expect_number()?;
+                if self.eat(TokenKind::Comma) {
+                    scale = self.expect_number()?;
+                }
+                self.expect(TokenKind::RightParentheses)?;
+            }
+            decimal_type(precision, scale).map_err(ParseError::General)
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    #[cfg(feature = "ddl")]
+    fn parse_drop_table(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("drop_table", self.position);
+        self.expect_keyword(Keyword::Drop)?;
+        self.expect_keyword(Keyword::Table)?;
+
+        let mut if_exists = false;
+        if self.eat(TokenKind::Keyword(Keyword::If)) {
+            self.expect_keyword(Keyword::Exists)?;
+            if_exists = true;
+        }
+
+        self.marks.table = Some(self.position);
+        let table_name = self.expect_table_name()?;
+        Ok(Statement::DropTable { table_name, if_exists })
+    }
+
+    /// ALTER ROLE name [WITH] option...
+    #[cfg(feature = "ddl")]
+    fn parse_alter(&mut self) -> Result<Statement, ParseError> {
+        self.expect_keyword(Keyword::Alter)?;
+        self.expect_word(&["ROLE"])?;
+        let role_name = self.expect_name()?;
+        let options = self.parse_role_options()?;
+        if options.is_empty() {
+            return Err(self.unexpected());
+        }
+        Ok(Statement::AlterRole { role_name, options })
+    }
+
+    #[cfg(feature = "ddl")]
+    fn parse_role_options(&mut self) -> Result<Vec<RoleOption>, ParseError> {
+        trace_span!("role_options", self.position);
+        self.eat(TokenKind::Keyword(Keyword::With));
+        let mut options = Vec::new();
+        loop {
+            let option = match self.eat_word_of(&[
+                "LOGIN", "NOLOGIN", "SUPERUSER", "NOSUPERUSER", "CREATEDB", "NOCREATEDB", "CREATEROLE",
+                "NOCREATEROLE", "PASSWORD",
+            ]) {
+                Some("LOGIN") => RoleOption::Login(true),
+                Some("NOLOGIN") => RoleOption::Login(false),
+                Some("SUPERUSER") => RoleOption::Superuser(true),
+                Some("NOSUPERUSER") => RoleOption::Superuser(false),
+                Some("CREATEDB") => RoleOption::CreateDb(true),
+                Some("NOCREATEDB") => RoleOption::CreateDb(false),
+                Some("CREATEROLE") => RoleOption::CreateRole(true),
+                Some("NOCREATEROLE") => RoleOption::CreateRole(false),
+                Some(_) => {
+                    self.marks.expressions.push(self.position..self.position + 1);
+                    if self.eat(TokenKind::Keyword(Keyword::Null)) {
+                        RoleOption::Password(Expression::Null)
+                    } else {
+                        RoleOption::Password(Expression::String(self.expect_string()?))
+                    }
+                }
+                None => return Ok(options),
+            };
+            options.push(option);
+        }
+    }
+
+    /// GRANT privileges ON object TO grantees [WITH GRANT OPTION], or
+    /// REVOKE privileges ON object FROM grantees; each privilege may list
+    /// columns, as in UPDATE (a, b). Roles in place of the privileges, with
+    /// no ON, grant or revoke membership of them: GRANT roles TO grantees
+    /// [WITH ADMIN OPTION], REVOKE roles FROM grantees.
+    fn parse_grant_or_revoke(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("grant", self.position);
+        let grant = self.eat(TokenKind::Keyword(Keyword::Grant));
+        if !grant {
+            self.expect_keyword(Keyword::Revoke)?;
+        }
+
+        let mut privileges = vec![TokenKind::Keyword(Keyword::Select), TokenKind::Keyword(Keyword::Insert)];
+        privileges.extend(PRIVILEGE_WORDS.map(TokenKind::Word));
+        let (privileges, roles) = if self.check_any(&privileges) {
+            (self.comma_separated(|p| p.parse_granted_privilege())?, Vec::new())
+        } else {
+            (Vec::new(), self.comma_separated(|p| p.expect_name())?)
+        };
+
+        let object = if !privileges.is_empty() {
+            self.expect_keyword(Keyword::On)?;
+            let kind = self.eat_word_of(&["SEQUENCE", "SCHEMA", "FUNCTION"]);
+            if kind.is_none() {
+                self.eat(TokenKind::Keyword(Keyword::Table));
+                self.marks.table = Some(self.position);
+            }
+            Some(match kind {
+                None => GrantObject::Table(self.expect_table_name()?),
+                Some("SEQUENCE") => GrantObject::Sequence(self.expect_identifier()?),
+                Some("SCHEMA") => GrantObject::Schema(self.expect_identifier()?),
+                Some(_) => GrantObject::Function(self.expect_identifier()?),
+            })
+        } else {
+            None
+        };
+
+        if grant {
+            self.expect_keyword(Keyword::To)?;
+        } else {
+            self.expect_keyword(Keyword::From)?;
+        }
+        let grantees = self.comma_separated(|p| p.expect_name())?;
+
+        let Some(object) = object else {
+            if !grant {
+                return Ok(Statement::RevokeRole { roles, grantees });
+            }
+            let with_admin_option = self.eat(TokenKind::Keyword(Keyword::With));
+            if with_admin_option {
+                self.expect_word(&["ADMIN"])?;
+                self.expect_word(&["OPTION"])?;
+            }
+            return Ok(Statement::GrantRole { roles, grantees, with_admin_option });
+        };
+        if !grant {
+            return Ok(Statement::Revoke { privileges, object, grantees });
+        }
+        let with_grant_option = self.eat(TokenKind::Keyword(Keyword::With));
+        if with_grant_option {
+            self.expect_keyword(Keyword::Grant)?;
+            self.expect_word(&["OPTION"])?;
+        }
+        Ok(Statement::Grant { privileges, object, grantees, with_grant_option })
+    }
+
+    /// A privilege of GRANT or REVOKE and the columns it is limited to.
+    fn parse_granted_privilege(&mut self) -> Result<GrantedPrivilege, ParseError> {
+        let privilege = if self.eat(TokenKind::Keyword(Keyword::Select)) {
+            Privilege::Select
+        } else if self.eat(TokenKind::Keyword(Keyword::Insert)) {
+            Privilege::Insert
+        } else {
+            match self.eat_word_of(&PRIVILEGE_WORDS) {
+                Some("ALL") => {
+                    self.eat(TokenKind::Word("PRIVILEGES"));
+                    Privilege::All
+                }
+                Some("UPDATE") => Privilege::Update,
+                Some("DELETE") => Privilege::Delete,
+                Some("TRUNCATE") => Privilege::Truncate,
+                Some("REFERENCES") => Privilege::References,
+                Some("TRIGGER") => Privilege::Trigger,
+                Some("USAGE") => Privilege::Usage,
+                Some(_) => Privilege::Execute,
+                None => return Err(self.unexpected()),
+            }
+        };
+        let mut columns = Vec::new();
+        if self.eat(TokenKind::LeftParentheses) {
+            columns = self.comma_separated(|p| {
+                p.marks.columns.push(p.position);
+                p.expect_identifier()
+            })?;
+            self.expect(TokenKind::RightParentheses)?;
+        }
+        Ok(GrantedPrivilege { privilege, columns })
+    }
+
+    /// `[AS] alias`, if present.
+    #[cfg(feature = "dml")]
+    fn parse_alias(&mut self) -> Result<Option<Ident>, ParseError> {
+        if self.eat(TokenKind::Keyword(Keyword::As)) || self.check(TokenKind::Identifier) {
+            Ok(Some(self.expect_identifier()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// `(column, ...)`
+    fn parse_column_names(&mut self) -> Result<Vec<Ident>, ParseError> {
+        self.expect(TokenKind::LeftParentheses)?;
+        let columns = self.comma_separated(|p| {
+            p.marks.columns.push(p.position);
+            p.expect_column_name()
+        })?;
+        self.expect(TokenKind::RightParentheses)?;
+        Ok(columns)
+    }
+
+    /// `(expression, ...)`
+    fn parse_value_list(&mut self) -> Result<Vec<Expression>, ParseError> {
+        self.expect(TokenKind::LeftParentheses)?;
+        let values = self.comma_separated(|p| p.parse_expression())?;
+        self.expect(TokenKind::RightParentheses)?;
+        Ok(values)
+    }
+
+    #[cfg(feature = "dml")]
+    fn parse_merge(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("merge", self.position);
+        self.expect_keyword(Keyword::Merge)?;
+        self.expect_keyword(Keyword::Into)?;
+        let target_mark = self.position;
+        let target = self.expect_table_name()?;
+        let target_alias = self.parse_alias()?;
+
+        self.expect_keyword(Keyword::Using)?;
+        let source = if self.eat(TokenKind::LeftParentheses) {
+            let query = self.parse_query()?;
+            self.expect(TokenKind::RightParentheses)?;
+            MergeSource::Query(Box::new(query))
+        } else {
+            MergeSource::Table(self.expect_table_name()?)
+        };
+        // The statement's table is the target, not the subquery's table
+        self.marks.table = Some(target_mark);
+        let source_alias = self.parse_alias()?;
+
+        self.expect_keyword(Keyword::On)?;
+        let on = self.parse_expression()?;
+
+        let mut clauses = Vec::new();
+        while self.eat(TokenKind::Keyword(Keyword::When)) {
+            clauses.push(self.parse_merge_clause()?);
+        }
+        if clauses.is_empty() {
+            self.expect_keyword(Keyword::When)?;
+        }
+
+        Ok(Statement::Merge { target, target_alias, source, source_alias, on, clauses })
+    }
+
+    #[cfg(feature = "dml")]
+    fn parse_merge_clause(&mut self) -> Result<MergeClause, ParseError> {
+        let matched = !self.eat(TokenKind::Keyword(Keyword::Not));
+        self.expect_word(&["MATCHED"])?;
+        let condition = if self.eat(TokenKind::Keyword(Keyword::And)) { Some(self.parse_expression()?) } else { None };
+        self.expect_keyword(Keyword::Then)?;
+
+        let action = if matched {
+            match self.eat_word_of(&["UPDATE", "DELETE"]) {
+                Some("UPDATE") => MergeAction::Update(self.parse_assignments()?),
+                Some(_) => MergeAction::Delete,
+                None => return Err(self.unexpected()),
+            }
+        } else {
+            self.expect_keyword(Keyword::Insert)?;
+            let columns =
+                if self.check(TokenKind::LeftParentheses) { Some(self.parse_column_names()?) } else { None };
+            self.expect_keyword(Keyword::Values)?;
+            MergeAction::Insert { columns, values: self.parse_value_list()? }
+        };
+        Ok(MergeClause { matched, condition, action })
+    }
+
+    /// PREPARE name [(types)] AS statement; the statement is parsed in full.
+    fn parse_prepare(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("prepare", self.position);
+        self.expect_word(&["PREPARE"])?;
+        let name = self.expect_name()?;
+        let mut parameter_types = Vec::new();
+        if self.eat(TokenKind::LeftParentheses) {
+            parameter_types = self.comma_separated(|p| p.parse_type())?;
+            self.expect(TokenKind::RightParentheses)?;
+        }
+        self.expect_keyword(Keyword::As)?;
+        let statement = Box::new(self.nested(Self::parse_statement)?);
+        Ok(Statement::Prepare { name, parameter_types, statement })
+    }
+
+    fn parse_execute(&mut self) -> Result<Statement, ParseError> {
+        self.expect_word(&["EXECUTE"])?;
+        let name = self.expect_name()?;
+        let parameters = if self.check(TokenKind::LeftParentheses) { self.parse_value_list()? } else { Vec::new() };
+        Ok(Statement::Execute { name, parameters })
+    }
+
+    fn parse_deallocate(&mut self) -> Result<Statement, ParseError> {
+        self.expect_word(&["DEALLOCATE"])?;
+        self.eat(TokenKind::Word("PREPARE"));
+        let name = if self.eat(TokenKind::Word("ALL")) { None } else { Some(self.expect_name()?) };
+        Ok(Statement::Deallocate { name })
+    }
+
+    /// EXPLAIN [ANALYZE] statement
+    fn parse_explain(&mut self) -> Result<Statement, ParseError> {
+        self.expect_word(&["EXPLAIN"])?;
+        let analyze = self.eat(TokenKind::Word("ANALYZE"));
+        let statement = Box::new(self.nested(Self::parse_statement)?);
+        Ok(Statement::Explain { analyze, statement })
+    }
+
+    fn parse_transaction(&mut self) -> Result<Statement, ParseError> {
+        let command = match self.eat_word_of(&["BEGIN", "START", "COMMIT", "END", "ROLLBACK"]) {
+            Some("START") => {
+                self.expect_word(&["TRANSACTION"])?;
+                return Ok(Statement::Transaction { command: TransactionCommand::Begin });
+            }
+            Some("BEGIN") => TransactionCommand::Begin,
+            Some("ROLLBACK") => TransactionCommand::Rollback,
+            _ => TransactionCommand::Commit,
+        };
+        self.eat_word_of(&["TRANSACTION", "WORK"]);
+        Ok(Statement::Transaction { command })
+    }
+
+    fn parse_set(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("set", self.position);
+        self.expect_keyword(Keyword::Set)?;
+
+        // A scope word, unless it is itself the variable (`SET local = 1`)
+        let mut scope = None;
+        let next_is_assignment =
+            matches!(self.tokens.get(self.position + 1), Some(Token::Equal | Token::Keyword(Keyword::To)));
+        if !next_is_assignment {
+            scope = match self.eat_word_of(&["SESSION", "LOCAL", "GLOBAL"]) {
+                Some("SESSION") => Some(SetScope::Session),
+                Some("LOCAL") => Some(SetScope::Local),
+                Some(_) => Some(SetScope::Global),
+                None => None,
+            };
+        }
+
+        let name = if self.eat(TokenKind::Word("NAMES")) {
+            "NAMES".to_string()
+        } else if self.eat(TokenKind::Word("TIME")) {
+            self.expect_word(&["ZONE"])?;
+            "TIME ZONE".to_string()
+        } else {
+            let mut name = self.expect_name()?;
+            while self.eat(TokenKind::Dot) {
+                name.push('.');
+                name.push_str(&self.expect_name()?);
+            }
+            if !self.eat(TokenKind::Equal) {
+                self.expect_keyword(Keyword::To)?;
+            }
+            name
+        };
+
+        let value = self.comma_separated(|p| {
+            // ON is reserved, but a common setting value
+            if p.check(TokenKind::Keyword(Keyword::On)) {
+                p.marks.expressions.push(p.position..p.position + 1);
+                p.advance();
+                Ok(Expression::Identifier(Ident::new("ON")))
+            } else {
+                p.parse_expression()
+            }
+        })?;
+        Ok(Statement::SetVariable { scope, name, value })
+    }
+
+    /// COPY table [(columns)] FROM|TO 'file'|STDIN|STDOUT [[WITH] (options)]
+    #[cfg(feature = "dml")]
+    fn parse_copy(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("copy", self.position);
+        self.expect_word(&["COPY"])?;
+        self.marks.table = Some(self.position);
+        let table_name = self.expect_table_name()?;
+        let columns = if self.check(TokenKind::LeftParentheses) { Some(self.parse_column_names()?) } else { None };
+
+        let from = self.eat(TokenKind::Keyword(Keyword::From));
+        if !from {
+            self.expect_keyword(Keyword::To)?;
+        }
+        let target = match self.eat_word_of(&["STDIN", "STDOUT"]) {
+            Some("STDIN") => CopyTarget::Stdin,
+            Some(_) => CopyTarget::Stdout,
+            None => CopyTarget::File(self.expect_string()?),
+        };
+
+        let mut options = Vec::new();
+        if self.eat(TokenKind::Keyword(Keyword::With)) || self.check(TokenKind::LeftParentheses) {
+            self.expect(TokenKind::LeftParentheses)?;
+            options = self.comma_separated(|p| p.parse_copy_option())?;
+            self.expect(TokenKind::RightParentheses)?;
+        }
+
+        Ok(Statement::Copy { table_name, columns, from, target, options })
+    }
+
+    #[cfg(feature = "dml")]
+    fn parse_copy_option(&mut self) -> Result<CopyOption, ParseError> {
+        // NULL is reserved but also the name of an option
+        let name = if self.eat(TokenKind::Keyword(Keyword::Null)) {
+            "NULL".to_string()
+        } else {
+            self.expect_identifier()?.to_uppercase()
+        };
+        let value = match self.peek() {
+            Some(Token::Identifier(word)) => Some(Expression::Identifier(word.clone())),
+            Some(Token::String(s)) => Some(Expression::String(s.clone())),
+            Some(Token::PrefixedString(prefix, value)) => {
+                Some(Expression::PrefixedString { prefix: *prefix, value: value.clone() })
+            }
+            Some(Token::Number(n)) => Some(Expression::Number(*n)),
+            Some(Token::Decimal(text)) => Some(Expression::Decimal(text.clone())),
+            Some(Token::Keyword(Keyword::True)) => Some(Expression::Boolean(true)),
+            Some(Token::Keyword(Keyword::False)) => Some(Expression::Boolean(false)),
+            _ => None,
+        };
+        if value.is_some() {
+            self.advance();
+        } else if !self.check_any(&[TokenKind::Comma, TokenKind::RightParentheses]) {
+            self.check_any(&[TokenKind::Identifier, TokenKind::String, TokenKind::Number]);
+            return Err(self.unexpected());
+        }
+        Ok(CopyOption { name, value })
+    }
+
+    /// LOAD DATA [LOCAL] INFILE 'file' [REPLACE|IGNORE] INTO TABLE table
+    /// [FIELDS|COLUMNS ...] [LINES ...] [IGNORE n LINES|ROWS] [(columns)]
+    #[cfg(feature = "dml")]
+    fn parse_load_data(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("load_data", self.position);
+        self.expect_word(&["LOAD"])?;
+        self.expect_word(&["DATA"])?;
+        let local = self.eat(TokenKind::Word("LOCAL"));
+        self.expect_word(&["INFILE"])?;
+        let file = self.expect_string()?;
+        let duplicates = match self.eat_word_of(&["REPLACE", "IGNORE"]) {
+            Some("REPLACE") => Some(DuplicateHandling::Replace),
+            Some(_) => Some(DuplicateHandling::Ignore),
+            None => None,
+        };
+        self.expect_keyword(Keyword::Into)?;
+        self.expect_keyword(Keyword::Table)?;
+        self.marks.table = Some(self.position);
+        let table_name = self.expect_table_name()?;
+
+        let mut format = LoadDataFormat::default();
+        if self.eat_word_of(&["FIELDS", "COLUMNS"]).is_some() {
+            let mut any = false;
+            loop {
+                if self.eat(TokenKind::Word("TERMINATED")) {
+                    self.expect_keyword(Keyword::By)?;
+                    format.fields_terminated_by = Some(self.expect_string()?);
+                } else if self.eat(TokenKind::Word("OPTIONALLY")) {
+                    format.optionally_enclosed = true;
+                    self.expect_word(&["ENCLOSED"])?;
+                    self.expect_keyword(Keyword::By)?;
+                    format.fields_enclosed_by = Some(self.expect_string()?);
+                } else if self.eat(TokenKind::Word("ENCLOSED")) {
+                    self.expect_keyword(Keyword::By)?;
+                    format.fields_enclosed_by = Some(self.expect_string()?);
+                } else if self.eat(TokenKind::Word("ESCAPED")) {
+                    self.expect_keyword(Keyword::By)?;
+                    format.fields_escaped_by = Some(self.expect_string()?);
+                } else if any {
+                    break;
+                } else {
+                    return Err(self.unexpected());
+                }
+                any = true;
+            }
+        }
+        if self.eat(TokenKind::Word("LINES")) {
+            let mut any = false;
+            loop {
+                if self.eat(TokenKind::Word("STARTING")) {
+                    self.expect_keyword(Keyword::By)?;
+                    format.lines_starting_by = Some(self.expect_string()?);
+                } else if self.eat(TokenKind::Word("TERMINATED")) {
+                    self.expect_keyword(Keyword::By)?;
+                    format.lines_terminated_by = Some(self.expect_string()?);
+                } else if any {
+                    break;
+                } else {
+                    return Err(self.unexpected());
+                }
+                any = true;
+            }
+        }
+
+        let mut ignore_lines = None;
+        if self.eat(TokenKind::Word("IGNORE")) {
+            ignore_lines = Some(self.expect_number()?);
+            self.expect_word(&["LINES", "ROWS"])?;
+        }
+        let columns = if self.check(TokenKind::LeftParentheses) { Some(self.parse_column_names()?) } else { None };
+
+        Ok(Statement::LoadData { local, file, duplicates, table_name, format, ignore_lines, columns })
+    }
+
+    #[cfg(feature = "dml")]
+    fn parse_insert(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("insert", self.position);
+        self.expect_keyword(Keyword::Insert)?;
+        self.expect_keyword(Keyword::Into)?;
+        self.marks.table = Some(self.position);
+        let table_name = self.expect_table_name()?;
+
+        let columns = if self.check(TokenKind::LeftParentheses) { Some(self.parse_column_names()?) } else { None };
+        if !self.eat(TokenKind::Keyword(Keyword::Values)) {
+            let query = self.parse_query()?;
+            return Ok(Statement::InsertSelect { table_name, columns, query: Box::new(query) });
+        }
+        let rows = self.comma_separated(|p| p.parse_value_list())?;
+
+        Ok(Statement::Insert { table_name, columns, rows })
+    }
+
+    /// UPDATE table [[AS] alias] SET column = value, ... [WHERE condition]
+    #[cfg(feature = "dml")]
+    fn parse_update(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("update", self.position);
+        self.expect_word(&["UPDATE"])?;
+        self.marks.table = Some(self.position);
+        let table_name = self.expect_table_name()?;
+        let alias = self.parse_alias()?;
+        let assignments = self.parse_assignments()?;
+        let selection = self.parse_where()?;
+        Ok(Statement::Update { table_name, alias, assignments, selection })
+    }
+
+    /// DELETE FROM table [[AS] alias] [WHERE condition]
+    #[cfg(feature = "dml")]
+    fn parse_delete(&mut self) -> Result<Statement, ParseError> {
+        trace_span!("delete", self.position);
+        self.expect_word(&["DELETE"])?;
+        self.expect_keyword(Keyword::From)?;
+        self.marks.table = Some(self.position);
+        let table_name = self.expect_table_name()?;
+        let alias = self.parse_alias()?;
+        let selection = self.parse_where()?;
+        Ok(Statement::Delete { table_name, alias, selection })
+    }
+
+    /// `SET column = value, ...`
+    #[cfg(feature = "dml")]
+    fn parse_assignments(&mut self) -> Result<Vec<(Ident, Expression)>, ParseError> {
+        self.expect_keyword(Keyword::Set)?;
+        self.comma_separated(|p| {
+            p.marks.columns.push(p.position);
+            let column = p.expect_column_name()?;
+            p.expect(TokenKind::Equal)?;
+            Ok((column, p.parse_expression()?))
+        })
+    }
+
+    /// `[WHERE condition]`
+    #[cfg(feature = "dml")]
+    fn parse_where(&mut self) -> Result<Option<Expression>, ParseError> {
+        if self.eat(TokenKind::Keyword(Keyword::Where)) {
+            Ok(Some(self.parse_expression()?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Stand-ins for the grammar a build leaves out, failing with an error
+/// that names the feature it needs.
+#[cfg(not(all(feature = "ddl", feature = "dml", feature = "cte")))]
+impl SQLParser<'_> {
+    #[cfg(not(feature = "cte"))]
+    fn parse_with(&mut self) -> Result<Statement, ParseError> {
+        Err(ParseError::General(missing("WITH queries", "cte")))
+    }
+
+    #[cfg(not(feature = "ddl"))]
+    fn parse_create(&mut self) -> Result<Statement, ParseError> {
+        Err(ParseError::General(missing("CREATE statements", "ddl")))
+    }
+
+    #[cfg(not(feature = "ddl"))]
+    fn parse_drop_table(&mut self) -> Result<Statement, ParseError> {
+        Err(ParseError::General(missing("DROP statements", "ddl")))
+    }
+
+    #[cfg(not(feature = "ddl"))]
+    fn parse_alter(&mut self) -> Result<Statement, ParseError> {
+        Err(ParseError::General(missing("ALTER statements", "ddl")))
+    }
+
+    #[cfg(not(feature = "dml"))]
+    fn parse_insert(&mut self) -> Result<Statement, ParseError> {
+        Err(ParseError::General(missing("INSERT statements", "dml")))
+    }
+
+    #[cfg(not(feature = "dml"))]
+    fn parse_update(&mut self) -> Result<Statement, ParseError> {
+        Err(ParseError::General(missing("UPDATE statements", "dml")))
+    }
+
+    #[cfg(not(feature = "dml"))]
+    fn parse_delete(&mut self) -> Result<Statement, ParseError> {
+        Err(ParseError::General(missing("DELETE statements", "dml")))
+    }
+
+    #[cfg(not(feature = "dml"))]
+    fn parse_merge(&mut self) -> Result<Statement, ParseError> {
+        Err(ParseError::General(missing("MERGE statements", "dml")))
+    }
+
+    #[cfg(not(feature = "dml"))]
+    fn parse_copy(&mut self) -> Result<Statement, ParseError> {
+        Err(ParseError::General(missing("COPY statements", "dml")))
+    }
+
+    #[cfg(not(feature = "dml"))]
+    fn parse_load_data(&mut self) -> Result<Statement, ParseError> {
+        Err(ParseError::General(missing("LOAD DATA statements", "dml")))
+    }
+}
+
+/// Tokenizes and parses a single SQL statement.
+pub fn parse_sql(input: &str) -> Result<Statement, ParseError> {
+    parse_sql_with_dialect(input, Dialect::Generic)
+}
+
+/// Like `parse_sql`, but tokenizes with the quoting rules of `dialect`.
+/// Input that doesn't tokenize fails with the lexical errors, before any
+/// parsing.
+pub fn parse_sql_with_dialect(input: &str, dialect: Dialect) -> Result<Statement, ParseError> {
+    let (tokens, _, errors) = tokenize_with_errors(input, dialect);
+    if !errors.is_empty() {
+        return Err(ParseError::Lex(errors));
+    }
+    SQLParser::with_options(&tokens, ParserOptions { dialect, ..ParserOptions::default() }).parse_to_end()
+}
+
+/// Lists every kind of token that may legally follow `prefix`, an
+/// incomplete statement. `Eof` and `;` are included when the prefix is
+/// already a complete statement. Returns an empty list if the prefix is invalid
+/// before its end.
+pub fn expected_tokens(prefix: &str, dialect: Dialect) -> Vec<TokenKind> {
+    expected_after(prefix, dialect).map_or_else(Vec::new, |expected| expected.kinds)
+}
+
+/// The expected set at the end of `prefix`, with what the identifiers it
+/// allows would name, or None if the prefix is invalid before its end.
+pub(crate) fn expected_after(prefix: &str, dialect: Dialect) -> Option<ExpectedSet> {
+    let tokens = tokenize_with_dialect(prefix, dialect);
+    let end = tokens.len() - 1; // the Eof token
+    let mut parser = SQLParser::with_options(&tokens, ParserOptions { dialect, ..ParserOptions::default() });
+    let complete = parser.parse_statement().is_ok() && parser.position() == end;
+
+    if parser.expected.at != end {
+        return None;
+    }
+    let mut expected = parser.expected;
+    if complete {
+        for kind in [TokenKind::Semicolon, TokenKind::Eof] {
+            expected.record(end, kind);
+        }
+    }
+    Some(expected)
+}
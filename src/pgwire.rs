@@ -0,0 +1,308 @@
+// pgwire.rs — a fake Postgres server backed by the in-memory engine
+// Author: Sreerag Devadasan
+//
+// Speaks enough of the Postgres v3 protocol for psql and client libraries
+// to connect and run simple queries: the startup handshake (SSL and GSS
+// encryption requests are refused, any user is let in without a
+// password), then Query messages, each of which may hold several
+// statements. Results go out as text-format rows. The extended protocol
+// (Parse/Bind/Execute) is answered with an error. All connections of a
+// server share one database, so a test can set up its fixtures over one
+// connection and read them back over another. A connection's BEGIN opens
+// a transaction of its own, which the other connections don't see until
+// it commits.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, ToSocketAddrs};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+use crate::ast::Statement;
+use crate::batch::statement_spans;
+use crate::dialect::Dialect;
+use crate::engine::{Database, EngineError, QueryResult, Transaction};
+use crate::eval::Value;
+use crate::metrics::{timed_parse, timed_query};
+use crate::parser::parse_sql_with_dialect;
+
+const PROTOCOL_VERSION: i32 = 196_608;
+const SSL_REQUEST: i32 = 80_877_103;
+const GSSENC_REQUEST: i32 = 80_877_104;
+const CANCEL_REQUEST: i32 = 80_877_102;
+
+/// Longest message accepted, so a bad length can't exhaust memory.
+const MAX_MESSAGE_LENGTH: usize = 16 << 20;
+
+/// Type OIDs of the column types the engine has.
+const INT8_OID: i32 = 20;
+const BOOL_OID: i32 = 16;
+const TEXT_OID: i32 = 25;
+const DATE_OID: i32 = 1082;
+const TIME_OID: i32 = 1083;
+const TIMESTAMP_OID: i32 = 1114;
+const NUMERIC_OID: i32 = 1700;
+
+/// Accepts connections on `address` forever, one thread per connection,
+/// all sharing `database`.
+pub fn serve(address: impl ToSocketAddrs, database: Database) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    let database = Arc::new(Mutex::new(database));
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let database = Arc::clone(&database);
+        thread::spawn(move || handle_connection(stream, &database));
+    }
+    Ok(())
+}
+
+/// Runs one client session on `stream` until the client terminates it or
+/// disconnects.
+pub fn handle_connection<S: Read + Write>(mut stream: S, database: &Mutex<Database>) -> io::Result<()> {
+    if !startup(&mut stream)? {
+        return Ok(());
+    }
+    let mut transaction = None;
+    let mut skipping_to_sync = false;
+    loop {
+        let mut tag = [0; 1];
+        match stream.read_exact(&mut tag) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            result => result?,
+        }
+        let body = read_body(&mut stream)?;
+        match tag[0] {
+            b'X' => return Ok(()),
+            b'Q' => {
+                let sql = String::from_utf8_lossy(body.strip_suffix(&[0]).unwrap_or(&body)).into_owned();
+                let mut database = database.lock().unwrap_or_else(PoisonError::into_inner);
+                simple_query(&mut stream, &mut database, &mut transaction, &sql)?;
+                ready_for_query(&mut stream, &transaction)?;
+            }
+            // Sync ends an extended-protocol exchange
+            b'S' => {
+                skipping_to_sync = false;
+                ready_for_query(&mut stream, &transaction)?;
+            }
+            _ if skipping_to_sync => {}
+            _ => {
+                error(&mut stream, "0A000", "only the simple query protocol is supported")?;
+                skipping_to_sync = true;
+            }
+        }
+        stream.flush()?;
+    }
+}
+
+/// Reads the startup packet, refusing encryption requests, and greets the
+/// client. False when the client asked to cancel a query instead.
+fn startup<S: Read + Write>(stream: &mut S) -> io::Result<bool> {
+    loop {
+        let body = read_body(stream)?;
+        let code = body.get(..4).map_or(0, |code| i32::from_be_bytes([code[0], code[1], code[2], code[3]]));
+        match code {
+            SSL_REQUEST | GSSENC_REQUEST => {
+                stream.write_all(b"N")?;
+                stream.flush()?;
+            }
+            CANCEL_REQUEST => return Ok(false),
+            PROTOCOL_VERSION => break,
+            _ => {
+                error(stream, "0A000", "unsupported frontend protocol")?;
+                stream.flush()?;
+                return Ok(false);
+            }
+        }
+    }
+
+    send(stream, b'R', &0i32.to_be_bytes())?;
+    for (name, value) in [
+        ("server_version", "14.0"),
+        ("server_encoding", "UTF8"),
+        ("client_encoding", "UTF8"),
+        ("DateStyle", "ISO, MDY"),
+        ("integer_datetimes", "on"),
+        ("standard_conforming_strings", "on"),
+    ] {
+        let mut body = Vec::new();
+        put_string(&mut body, name);
+        put_string(&mut body, value);
+        send(stream, b'S', &body)?;
+    }
+    let mut key = Vec::new();
+    key.extend_from_slice(&(std::process::id() as i32).to_be_bytes());
+    key.extend_from_slice(&0i32.to_be_bytes());
+    send(stream, b'K', &key)?;
+    send(stream, b'Z', b"I")?;
+    stream.flush()?;
+    Ok(true)
+}
+
+/// Runs the statements of a Query message in order, stopping at the first
+/// error as Postgres does.
+fn simple_query<S: Write>(
+    stream: &mut S,
+    database: &mut Database,
+    transaction: &mut Option<Transaction>,
+    sql: &str,
+) -> io::Result<()> {
+    let spans = statement_spans(sql);
+    if spans.is_empty() {
+        return send(stream, b'I', &[]);
+    }
+    for span in spans {
+        let result = timed_parse(|| parse_sql_with_dialect(span.slice(sql), Dialect::Postgres))
+            .map_err(EngineError::from)
+            .and_then(|statement| {
+                timed_query(|| execute(database, transaction, &statement)).map(|result| (statement, result))
+            });
+        match result {
+            Ok((statement, result)) => {
+                if !result.columns.is_empty() {
+                    send_rows(stream, &result)?;
+                }
+                send(stream, b'C', &nul_terminated(&command_tag(&statement, &result)))?;
+            }
+            Err(e) => {
+                let code = match e {
+                    EngineError::Parse(_) => "42601",
+                    EngineError::Unsupported(_) | EngineError::UnsupportedFeature(_) => "0A000",
+                    EngineError::NoSuchTable(_) => "42P01",
+                    EngineError::TableExists(_) => "42P07",
+                    EngineError::UnknownColumn(_) => "42703",
+                    EngineError::NotNull(_) => "23502",
+                    EngineError::DuplicateKey(_) => "23505",
+                    EngineError::CheckFailed(_) => "23514",
+                    EngineError::TransactionInProgress => "25001",
+                    EngineError::NoTransaction => "25P01",
+                    EngineError::WriteConflict(_) => "40001",
+                    EngineError::ReadOnly(_) => "25006",
+                    _ => "XX000",
+                };
+                return error(stream, code, &e.to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Executes a statement in the connection's transaction, if it has one;
+/// SET is accepted and ignored, since clients send it while connecting.
+fn execute(
+    database: &mut Database,
+    transaction: &mut Option<Transaction>,
+    statement: &Statement,
+) -> Result<QueryResult, EngineError> {
+    match statement {
+        Statement::SetVariable { .. } => Ok(QueryResult::default()),
+        other => database.execute_in(transaction, other),
+    }
+}
+
+/// ReadyForQuery, with the status idle or in a transaction.
+fn ready_for_query<S: Write>(stream: &mut S, transaction: &Option<Transaction>) -> io::Result<()> {
+    send(stream, b'Z', if transaction.is_some() { b"T" } else { b"I" })
+}
+
+/// The CommandComplete tag of a statement, e.g. "INSERT 0 2".
+fn command_tag(statement: &Statement, result: &QueryResult) -> String {
+    match statement {
+        Statement::Insert { rows, .. } => format!("INSERT 0 {}", rows.len()),
+        _ if !result.columns.is_empty() => format!("SELECT {}", result.rows.len()),
+        other => other.kind().to_string(),
+    }
+}
+
+/// RowDescription, then a DataRow per row. A column's type is that of its
+/// first non-NULL value, text if it has none.
+fn send_rows<S: Write>(stream: &mut S, result: &QueryResult) -> io::Result<()> {
+    let mut description = Vec::new();
+    description.extend_from_slice(&(result.columns.len() as i16).to_be_bytes());
+    for (i, name) in result.columns.iter().enumerate() {
+        let first = result.rows.iter().map(|row| &row[i]).find(|value| **value != Value::Null);
+        let (oid, length): (i32, i16) = match first {
+            Some(Value::Int(_)) => (INT8_OID, 8),
+            Some(Value::Bool(_)) => (BOOL_OID, 1),
+            Some(Value::Date(_)) => (DATE_OID, 4),
+            Some(Value::Time(_)) => (TIME_OID, 8),
+            Some(Value::Timestamp(_)) => (TIMESTAMP_OID, 8),
+            Some(Value::Decimal(_)) => (NUMERIC_OID, -1),
+            _ => (TEXT_OID, -1),
+        };
+        put_string(&mut description, name);
+        description.extend_from_slice(&0i32.to_be_bytes()); // table OID
+        description.extend_from_slice(&0i16.to_be_bytes()); // column number
+        description.extend_from_slice(&oid.to_be_bytes());
+        description.extend_from_slice(&length.to_be_bytes());
+        description.extend_from_slice(&(-1i32).to_be_bytes()); // type modifier
+        description.extend_from_slice(&0i16.to_be_bytes()); // text format
+    }
+    send(stream, b'T', &description)?;
+
+    for row in &result.rows {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(row.len() as i16).to_be_bytes());
+        for value in row {
+            let text = match value {
+                Value::Null => {
+                    data.extend_from_slice(&(-1i32).to_be_bytes());
+                    continue;
+                }
+                Value::Int(n) => n.to_string(),
+                Value::Bool(b) => (if *b { "t" } else { "f" }).to_string(),
+                Value::String(s) => s.clone(),
+                Value::Date(date) => date.to_string(),
+                Value::Time(time) => time.to_string(),
+                Value::Timestamp(timestamp) => timestamp.to_string(),
+                Value::Decimal(decimal) => decimal.to_string(),
+            };
+            data.extend_from_slice(&(text.len() as i32).to_be_bytes());
+            data.extend_from_slice(text.as_bytes());
+        }
+        send(stream, b'D', &data)?;
+    }
+    Ok(())
+}
+
+/// Sends an ErrorResponse with a SQLSTATE code.
+fn error<S: Write>(stream: &mut S, code: &str, message: &str) -> io::Result<()> {
+    let mut body = Vec::new();
+    for (field, value) in [(b'S', "ERROR"), (b'V', "ERROR"), (b'C', code), (b'M', message)] {
+        body.push(field);
+        put_string(&mut body, value);
+    }
+    body.push(0);
+    send(stream, b'E', &body)
+}
+
+/// Reads a length-prefixed message body; the length counts itself.
+fn read_body<S: Read>(stream: &mut S) -> io::Result<Vec<u8>> {
+    let mut length = [0; 4];
+    stream.read_exact(&mut length)?;
+    let length = i32::from_be_bytes(length);
+    let size = usize::try_from(length).ok().and_then(|length| length.checked_sub(4));
+    let Some(size) = size.filter(|size| *size <= MAX_MESSAGE_LENGTH) else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid message length"));
+    };
+    let mut body = vec![0; size];
+    stream.read_exact(&mut body)?;
+    Ok(body)
+}
+
+fn send<S: Write>(stream: &mut S, tag: u8, body: &[u8]) -> io::Result<()> {
+    let mut message = Vec::with_capacity(body.len() + 5);
+    message.push(tag);
+    message.extend_from_slice(&(body.len() as i32 + 4).to_be_bytes());
+    message.extend_from_slice(body);
+    stream.write_all(&message)
+}
+
+fn put_string(buffer: &mut Vec<u8>, text: &str) {
+    buffer.extend_from_slice(text.as_bytes());
+    buffer.push(0);
+}
+
+fn nul_terminated(text: &str) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    put_string(&mut buffer, text);
+    buffer
+}
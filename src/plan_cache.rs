@@ -0,0 +1,170 @@
+// plan_cache.rs — compiled SELECTs the engine reuses from one run to the next
+// Author: Sreerag Devadasan
+//
+// Before it reads a row, a SELECT has work to do that only depends on the
+// shape of the query: finding the columns it projects and sorts by, and
+// compiling its WHERE clause to bytecode with the column positions worked
+// out. The engine keeps that work in a `PlanCache`, keyed by the query's
+// fingerprint (see hash.rs), so queries that differ only in their literals,
+// such as one query run in a loop with different values, share a single
+// compiled plan: the WHERE clause is compiled with its literals as
+// parameters, and each run binds the values it was written with. A plan
+// is only reused over a source with the columns it was compiled for;
+// anything else is a miss, and the new plan replaces it.
+//
+// The cache holds at most `capacity` plans, evicting the least recently
+// used to make room, and counts its hits, misses and evictions. A capacity
+// of zero turns it off.
+//
+// Reusing a plan doesn't make a query measurably faster: the query is still
+// parsed and fingerprinted, and binding its literals costs about as much as
+// the compiling it saves. benches/plan_cache.rs times the same loop with
+// and without the cache, and the two come out even.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+use crate::prelude::*;
+use crate::ast::Expression;
+use crate::bytecode::Program;
+use crate::eval::{literal_value, EvalError, Value};
+use crate::hash::AstHash;
+
+/// The plans a new database keeps.
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// What running a SELECT needs besides its rows.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CompiledSelect {
+    /// The columns of the source the plan was compiled for.
+    pub(crate) source: Vec<String>,
+    /// The WHERE clause with its literals as parameters, unless it has
+    /// something the bytecode can't run, such as a function call.
+    pub(crate) filter: Option<Program>,
+    /// The positions in the source of the selected columns; those of
+    /// computed items follow the source's own columns.
+    pub(crate) projection: Vec<usize>,
+    /// The select item each of the projection's columns comes from.
+    pub(crate) items: Vec<usize>,
+    /// Where each ORDER BY item's value is: a column of the source, or one
+    /// computed after the select list's items.
+    pub(crate) sort_keys: Vec<SortKey>,
+}
+
+/// One ORDER BY item of a compiled SELECT.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SortKey {
+    pub(crate) index: usize,
+    pub(crate) descending: bool,
+}
+
+/// How the cache has done since the database was created.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// The plans held now.
+    pub entries: usize,
+}
+
+/// Compiled plans by fingerprint, least recently used evicted first.
+#[derive(Debug, Clone)]
+pub struct PlanCache {
+    capacity: usize,
+    /// Each plan with the tick it was last used at.
+    plans: BTreeMap<AstHash, (Arc<CompiledSelect>, u64)>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+impl Default for PlanCache {
+    fn default() -> Self {
+        PlanCache::new(DEFAULT_CAPACITY)
+    }
+}
+
+/// A cache doesn't change what a database holds or answers, so any two
+/// compare equal.
+impl PartialEq for PlanCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl PlanCache {
+    pub fn new(capacity: usize) -> Self {
+        PlanCache { capacity, plans: BTreeMap::new(), tick: 0, stats: CacheStats::default() }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes the capacity, evicting plans down to it.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.plans.len() > capacity {
+            self.evict();
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats { entries: self.plans.len(), ..self.stats }
+    }
+
+    /// The plan for `key` compiled over a source of `columns`, counting a
+    /// hit or a miss.
+    pub(crate) fn get(&mut self, key: AstHash, columns: &[String]) -> Option<Arc<CompiledSelect>> {
+        self.tick += 1;
+        match self.plans.get_mut(&key) {
+            Some((plan, used)) if plan.source == columns => {
+                *used = self.tick;
+                self.stats.hits += 1;
+                Some(Arc::clone(plan))
+            }
+            _ => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub(crate) fn insert(&mut self, key: AstHash, plan: Arc<CompiledSelect>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if !self.plans.contains_key(&key) && self.plans.len() >= self.capacity {
+            self.evict();
+        }
+        self.plans.insert(key, (plan, self.tick));
+    }
+
+    fn evict(&mut self) {
+        let oldest = self.plans.iter().min_by_key(|(_, (_, used))| *used).map(|(key, _)| *key);
+        if let Some(key) = oldest {
+            self.plans.remove(&key);
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+/// The values of the literals that fingerprinting replaces in `expr`, in
+/// the order they are written: the parameters of its compiled template.
+pub(crate) fn parameters(expr: &Expression) -> Result<Vec<Value>, EvalError> {
+    let mut values = Vec::new();
+    let mut error = None;
+    expr.walk(&mut |expr| {
+        let literal = matches!(
+            expr,
+            Expression::Number(_) | Expression::Decimal(_) | Expression::String(_) | Expression::TypedString { .. }
+        );
+        if literal {
+            match literal_value(expr) {
+                Ok(value) => values.push(value),
+                Err(e) => error = error.take().or(Some(e)),
+            }
+        }
+    });
+    error.map_or(Ok(values), Err)
+}
@@ -0,0 +1,512 @@
+// planner.rs — the steps of a query, with the rows each is estimated to produce
+// Author: Sreerag Devadasan
+//
+// `plan` lays a query out as a tree of steps: scans of tables, subqueries
+// and table functions at the leaves, then joins, a filter for WHERE,
+// grouping, a filter for QUALIFY and LIMIT, with set operations and WITH
+// above them. Each step carries an estimate of how many rows it produces,
+// from the table statistics given (see stats.rs) or, without them, from
+// the defaults below, and a cost: its rows plus the cost of its inputs.
+// `Plan`'s Display prints the tree the way EXPLAIN does, one step per line:
+//
+//   Filter age > 30  (rows=30000 cost=140000)
+//     -> Join INNER ON u.id = o.user_id  (rows=50000 cost=110000)
+//          -> Scan users AS u  (rows=10000 cost=10000)
+//          -> Scan orders AS o  (rows=50000 cost=50000)
+//
+// EXPLAIN ANALYZE in the engine runs the query and fills in each step's
+// `actual` rows and time next to the estimates.
+//
+// Inner and cross joins of up to MAX_REORDERED FROM items are put in the
+// order of least cost, their ON conditions moved to the first join that
+// can check them; outer joins and LATERAL items keep the order written,
+// which `plan_as_written` keeps for every query.
+//
+// Filters are estimated condition by condition, as if the columns were
+// independent: `column = value` keeps one row in the column's distinct
+// count, a range keeps the share of the column's histogram it covers, AND
+// multiplies and OR adds the shares. An equi-join keeps one pair of rows
+// in the larger distinct count of its two columns.
+
+use core::fmt;
+use core::time::Duration;
+
+use crate::prelude::*;
+use crate::ast::{
+    BinaryOperator, Expression, GroupingElement, Join, JoinKind, SetOperator, Statement, TableAlias, TableFactor,
+    UnaryOperator,
+};
+use crate::dialect::Dialect;
+use crate::stats::{ColumnStats, Statistics, TableStats};
+
+/// The rows of a table without statistics.
+pub const DEFAULT_ROWS: u64 = 1000;
+/// The most FROM items put in order by cost; the sets of items to plan
+/// double with each one more.
+pub const MAX_REORDERED: usize = 10;
+/// The share of rows equal to a value when the distinct count is unknown.
+const EQUALITY: f64 = 0.1;
+/// The share of rows in a range without a histogram.
+const RANGE: f64 = 1.0 / 3.0;
+/// The share of rows a LIKE pattern matches.
+const LIKE: f64 = 0.1;
+/// The share of rows any other condition keeps.
+const OTHER: f64 = 0.5;
+
+/// One step of a plan and the steps it reads from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub step: Step,
+    /// The estimated number of rows the step produces.
+    pub rows: u64,
+    /// The rows produced by the step and all those under it, which the
+    /// join order is chosen to keep low.
+    pub cost: u64,
+    /// What running the step produced, once EXPLAIN ANALYZE has run it.
+    pub actual: Option<Actual>,
+    pub inputs: Vec<Plan>,
+}
+
+/// The rows a step actually produced, and the wall time from the start of
+/// its query until it finished, steps under it included.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Actual {
+    pub rows: u64,
+    pub time: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Step {
+    /// A table, or a CTE of the statement, under the alias it was given.
+    Scan { table: String, alias: Option<String> },
+    /// A FROM subquery; its plan is the input.
+    Subquery { alias: Option<String> },
+    /// A table function such as `generate_series(1, 10)`.
+    Function { name: String },
+    /// VALUES rows.
+    Values,
+    Join { kind: JoinKind, on: Option<Expression> },
+    /// WHERE, or QUALIFY, keeping the rows the condition holds for.
+    Filter(Expression),
+    /// GROUP BY, one row per group.
+    Aggregate(Vec<Expression>),
+    Limit { limit: Option<u64>, offset: Option<u64> },
+    /// UNION, INTERSECT or EXCEPT of the two inputs.
+    SetOperation(String),
+    /// The CTE named, planned as its input.
+    Cte(String),
+    /// A WITH statement: its CTEs, then its body.
+    With,
+}
+
+/// The plan of a query, with its inner joins in the cheapest order, or
+/// None for other statements.
+pub fn plan(statement: &Statement, statistics: &Statistics) -> Option<Plan> {
+    Planner { statistics, reorder: true, ctes: Vec::new() }.query(statement)
+}
+
+/// The plan of a query with its joins in the order written, to compare
+/// with `plan`.
+pub fn plan_as_written(statement: &Statement, statistics: &Statistics) -> Option<Plan> {
+    Planner { statistics, reorder: false, ctes: Vec::new() }.query(statement)
+}
+
+impl Plan {
+    fn new(step: Step, rows: f64, inputs: Vec<Plan>) -> Self {
+        // At least one row, so shares of an estimate never reach zero
+        let rows = (rows + 0.5).max(1.0) as u64;
+        let cost = inputs.iter().fold(rows, |cost, input| cost.saturating_add(input.cost));
+        Plan { step, rows, cost, actual: None, inputs }
+    }
+
+    fn write(&self, depth: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if depth > 0 {
+            write!(f, "{:indent$}-> ", "", indent = 5 * depth - 3)?;
+        }
+        write!(f, "{}  (rows={} cost={})", self.step, self.rows, self.cost)?;
+        if let Some(Actual { rows, time }) = self.actual {
+            write!(f, " (actual rows={} time={:.3}ms)", rows, time.as_secs_f64() * 1000.0)?;
+        }
+        writeln!(f)?;
+        self.inputs.iter().try_for_each(|input| input.write(depth + 1, f))
+    }
+}
+
+/// The plan as EXPLAIN prints it.
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(0, f)
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sql = |expr: &Expression| expr.to_sql(Dialect::Generic);
+        match self {
+            Step::Scan { table, alias: Some(alias) } => write!(f, "Scan {} AS {}", table, alias),
+            Step::Scan { table, alias: None } => write!(f, "Scan {}", table),
+            Step::Subquery { alias: Some(alias) } => write!(f, "Subquery {}", alias),
+            Step::Subquery { alias: None } => write!(f, "Subquery"),
+            Step::Function { name } => write!(f, "Function {}", name),
+            Step::Values => write!(f, "Values"),
+            Step::Join { kind, on } => {
+                write!(f, "Join {}", format!("{:?}", kind).to_uppercase())?;
+                match on {
+                    Some(on) => write!(f, " ON {}", sql(on)),
+                    None => Ok(()),
+                }
+            }
+            Step::Filter(condition) => write!(f, "Filter {}", sql(condition)),
+            Step::Aggregate(keys) => {
+                let keys: Vec<String> = keys.iter().map(sql).collect();
+                write!(f, "Aggregate {}", keys.join(", "))
+            }
+            Step::Limit { limit, offset } => {
+                write!(f, "Limit")?;
+                if let Some(limit) = limit {
+                    write!(f, " {}", limit)?;
+                }
+                if let Some(offset) = offset {
+                    write!(f, " OFFSET {}", offset)?;
+                }
+                Ok(())
+            }
+            Step::SetOperation(operator) => write!(f, "{}", operator),
+            Step::Cte(name) => write!(f, "CTE {}", name),
+            Step::With => write!(f, "With"),
+        }
+    }
+}
+
+/// A FROM item in scope: the name columns are qualified with, and the
+/// statistics of its table when it is one.
+struct Source<'a> {
+    name: String,
+    stats: Option<&'a TableStats>,
+    rows: f64,
+}
+
+struct Planner<'a> {
+    statistics: &'a Statistics,
+    /// Whether inner joins are put in the cheapest order.
+    reorder: bool,
+    /// The rows of the CTEs planned so far.
+    ctes: Vec<(String, u64)>,
+}
+
+impl<'a> Planner<'a> {
+    fn query(&mut self, statement: &Statement) -> Option<Plan> {
+        match statement {
+            Statement::Select { from, joins, selection, group_by, qualify, limit, offset, .. } => {
+                let mut sources = Vec::new();
+                let mut plans = vec![self.factor(from, &mut sources)];
+                for join in joins {
+                    plans.push(self.factor(&join.relation, &mut sources));
+                }
+                // Outer joins keep their order, and a LATERAL item needs
+                // the ones before it
+                let factors = core::iter::once(from.as_ref()).chain(joins.iter().map(|join| &join.relation));
+                let lateral = factors.into_iter().any(|factor| {
+                    matches!(
+                        factor,
+                        TableFactor::Derived { lateral: true, .. } | TableFactor::Function { lateral: true, .. }
+                    )
+                });
+                let inner = joins.iter().all(|join| matches!(join.kind, JoinKind::Inner | JoinKind::Cross));
+                let mut plan = if self.reorder && inner && !lateral && plans.len() <= MAX_REORDERED {
+                    self.join_order(plans, joins, &sources)
+                } else {
+                    self.written_order(plans, joins, &sources)
+                };
+                if let Some(condition) = selection {
+                    plan = self.filter(condition, plan, &sources);
+                }
+                let keys: Vec<Expression> = group_by.iter().flat_map(GroupingElement::expressions).cloned().collect();
+                if !keys.is_empty() {
+                    let groups = keys.iter().map(|key| self.distinct(key, &sources).unwrap_or(10.0)).product::<f64>();
+                    let rows = groups.min(plan.rows as f64);
+                    plan = Plan::new(Step::Aggregate(keys), rows, vec![plan]);
+                }
+                if let Some(condition) = qualify {
+                    plan = self.filter(condition, plan, &sources);
+                }
+                if limit.is_some() || offset.is_some() {
+                    let rows = (plan.rows.saturating_sub(offset.unwrap_or(0))).min(limit.unwrap_or(u64::MAX));
+                    plan = Plan::new(Step::Limit { limit: *limit, offset: *offset }, rows as f64, vec![plan]);
+                }
+                Some(plan)
+            }
+            Statement::SetOperation { operator, all, left, right } => {
+                let (left, right) = (self.query(left)?, self.query(right)?);
+                let (l, r) = (left.rows as f64, right.rows as f64);
+                let rows = match operator {
+                    SetOperator::Union => l + r,
+                    SetOperator::Intersect => l.min(r),
+                    SetOperator::Except => l,
+                };
+                let name = format!("{:?}", operator).to_uppercase();
+                let name = if *all { format!("{} ALL", name) } else { name };
+                Some(Plan::new(Step::SetOperation(name), rows, vec![left, right]))
+            }
+            Statement::With { ctes, body, .. } => {
+                let mut inputs = Vec::new();
+                for cte in ctes {
+                    let query = self.query(&cte.query)?;
+                    self.ctes.push((cte.name.to_lowercase(), query.rows));
+                    inputs.push(Plan::new(Step::Cte(cte.name.to_string()), query.rows as f64, vec![query]));
+                }
+                let body = self.query(body)?;
+                let rows = body.rows as f64;
+                inputs.push(body);
+                Some(Plan::new(Step::With, rows, inputs))
+            }
+            Statement::Values { rows } => Some(Plan::new(Step::Values, rows.len() as f64, Vec::new())),
+            _ => None,
+        }
+    }
+
+    /// The joins as the query writes them.
+    fn written_order(&self, plans: Vec<Plan>, joins: &[Join], sources: &[Source]) -> Plan {
+        let mut plans = plans.into_iter();
+        let mut plan = plans.next().unwrap_or_else(|| Plan::new(Step::Values, 1.0, Vec::new()));
+        for (join, right) in joins.iter().zip(plans) {
+            let (left, right_rows) = (plan.rows as f64, right.rows as f64);
+            let share = join.on.as_ref().map_or(1.0, |on| self.share(on, sources));
+            let rows = match join.kind {
+                JoinKind::Inner | JoinKind::Cross => left * right_rows * share,
+                JoinKind::Left => (left * right_rows * share).max(left),
+                JoinKind::Right => (left * right_rows * share).max(right_rows),
+                JoinKind::Full => (left * right_rows * share).max(left + right_rows),
+            };
+            let step = Step::Join { kind: join.kind, on: join.on.clone() };
+            plan = Plan::new(step, rows, vec![plan, right]);
+        }
+        plan
+    }
+
+    /// The cheapest order of inner joins, found by dynamic programming over
+    /// the sets of FROM items: the best plan of each set joins one of its
+    /// items to the best plan of the others. The ON conditions are split at
+    /// AND and each is applied at the first join that has all the items it
+    /// reads. Of orders that cost the same, the written one is kept.
+    fn join_order(&self, plans: Vec<Plan>, joins: &[Join], sources: &[Source]) -> Plan {
+        let n = plans.len();
+        let all = (1usize << n) - 1;
+        let mut conditions = Vec::new();
+        for on in joins.iter().filter_map(|join| join.on.as_ref()) {
+            conjuncts(on, &mut conditions);
+        }
+        let conditions: Vec<(&Expression, usize, f64)> = conditions
+            .into_iter()
+            .map(|condition| (condition, self.reads(condition, sources, all), self.share(condition, sources)))
+            .collect();
+        let members = |set: usize| (0..n).filter(move |i| set & (1 << i) != 0);
+        // The rows of a set's join are the same whatever the order
+        let rows = |set: usize| {
+            let product: f64 = members(set).map(|i| plans[i].rows as f64).product();
+            let shares = conditions.iter().filter(|(_, reads, _)| reads & !set == 0).map(|(_, _, share)| share);
+            product * shares.product::<f64>()
+        };
+
+        let mut best: Vec<Option<Plan>> = vec![None; all + 1];
+        for (i, plan) in plans.iter().enumerate() {
+            best[1 << i] = Some(plan.clone());
+        }
+        for set in (1..=all).filter(|set: &usize| set.count_ones() > 1) {
+            let mut choice: Option<Plan> = None;
+            // Last items first, so that on a tie the written order wins
+            for i in members(set).collect::<Vec<_>>().into_iter().rev() {
+                let rest = set & !(1 << i);
+                let Some(left) = &best[rest] else { continue };
+                let on: Vec<&Expression> = conditions
+                    .iter()
+                    .filter(|(_, reads, _)| reads & !set == 0 && reads & !rest != 0)
+                    .map(|(condition, _, _)| *condition)
+                    .collect();
+                let kind = if on.is_empty() { JoinKind::Cross } else { JoinKind::Inner };
+                let step = Step::Join { kind, on: conjunction(on) };
+                let candidate = Plan::new(step, rows(set), vec![left.clone(), plans[i].clone()]);
+                if choice.as_ref().is_none_or(|choice| candidate.cost < choice.cost) {
+                    choice = Some(candidate);
+                }
+            }
+            best[set] = choice;
+        }
+        best[all].take().unwrap_or_else(|| Plan::new(Step::Values, 1.0, Vec::new()))
+    }
+
+    /// The FROM items `condition` reads, as a set of bits; all of them
+    /// when one of its columns can't be placed.
+    fn reads(&self, condition: &Expression, sources: &[Source], all: usize) -> usize {
+        let mut set = 0;
+        condition.walk(&mut |expr| {
+            let source = match expr {
+                Expression::Identifier(name) => {
+                    sources.iter().position(|source| source.stats.is_some_and(|stats| stats.column(name).is_some()))
+                }
+                Expression::CompoundIdentifier(parts) if parts.len() >= 2 => {
+                    let qualifier = &parts[parts.len() - 2];
+                    sources.iter().position(|source| source.name.eq_ignore_ascii_case(qualifier))
+                }
+                _ => return,
+            };
+            set |= source.map_or(all, |i| 1 << i);
+        });
+        if set == 0 {
+            all
+        } else {
+            set
+        }
+    }
+
+    /// The plan of a FROM item, adding it to `sources`.
+    fn factor(&mut self, factor: &TableFactor, sources: &mut Vec<Source<'a>>) -> Plan {
+        let alias_of = |alias: &Option<TableAlias>| alias.as_ref().map(|alias| alias.name.to_string());
+        let (plan, name, stats) = match factor {
+            TableFactor::Table { name, alias, .. } | TableFactor::AsOf { name, alias, .. } => {
+                let cte = self.ctes.iter().rev().find(|(cte, _)| cte.eq_ignore_ascii_case(name)).map(|(_, rows)| *rows);
+                let stats = if cte.is_some() { None } else { self.statistics.table(name) };
+                let rows = cte.or(stats.map(|stats| stats.rows)).unwrap_or(DEFAULT_ROWS);
+                let step = Step::Scan { table: name.to_string(), alias: alias_of(alias) };
+                (Plan::new(step, rows as f64, Vec::new()), alias_of(alias).unwrap_or_else(|| name.to_string()), stats)
+            }
+            TableFactor::Derived { subquery, alias, .. } => {
+                let input = self.query(subquery).unwrap_or_else(|| Plan::new(Step::Values, 1.0, Vec::new()));
+                let rows = input.rows as f64;
+                let plan = Plan::new(Step::Subquery { alias: alias_of(alias) }, rows, vec![input]);
+                (plan, alias_of(alias).unwrap_or_default(), None)
+            }
+            TableFactor::Function { name, alias, .. } => {
+                let plan = Plan::new(Step::Function { name: name.clone() }, DEFAULT_ROWS as f64, Vec::new());
+                (plan, alias_of(alias).unwrap_or_else(|| name.clone()), None)
+            }
+            TableFactor::Pivot { table, alias, .. } | TableFactor::Unpivot { table, alias, .. } => {
+                let mut inner = Vec::new();
+                let plan = self.factor(table, &mut inner);
+                (plan, alias_of(alias).unwrap_or_default(), None)
+            }
+        };
+        sources.push(Source { name, stats, rows: plan.rows as f64 });
+        plan
+    }
+
+    fn filter(&self, condition: &Expression, input: Plan, sources: &[Source]) -> Plan {
+        let rows = input.rows as f64 * self.share(condition, sources);
+        Plan::new(Step::Filter(condition.clone()), rows, vec![input])
+    }
+
+    /// The share of rows `condition` holds for.
+    fn share(&self, condition: &Expression, sources: &[Source]) -> f64 {
+        let share = match condition {
+            Expression::Grouped(inner) => self.share(inner, sources),
+            Expression::UnaryOperation { operator: UnaryOperator::Not, operand } => 1.0 - self.share(operand, sources),
+            Expression::Boolean(true) => 1.0,
+            Expression::Boolean(false) | Expression::Null => 0.0,
+            Expression::BinaryOperation { left_operand, operator, right_operand } => {
+                let (left, right) = (left_operand.as_ref(), right_operand.as_ref());
+                match operator {
+                    BinaryOperator::And => self.share(left, sources) * self.share(right, sources),
+                    BinaryOperator::Or => {
+                        let (a, b) = (self.share(left, sources), self.share(right, sources));
+                        a + b - a * b
+                    }
+                    BinaryOperator::Equals => self.equality(left, right, sources),
+                    BinaryOperator::NotEquals => 1.0 - self.equality(left, right, sources),
+                    BinaryOperator::Like | BinaryOperator::ILike => LIKE,
+                    BinaryOperator::LessThan
+                    | BinaryOperator::LessThanOrEqual
+                    | BinaryOperator::GreaterThan
+                    | BinaryOperator::GreaterThanOrEqual => self.range(left, operator, right, sources),
+                    _ => OTHER,
+                }
+            }
+            _ => OTHER,
+        };
+        share.clamp(0.0, 1.0)
+    }
+
+    /// `left = right`: one value in the distinct count of a column compared
+    /// with a value, or a pair in the larger of two columns compared.
+    fn equality(&self, left: &Expression, right: &Expression, sources: &[Source]) -> f64 {
+        match (self.distinct(left, sources), self.distinct(right, sources)) {
+            (Some(a), Some(b)) => 1.0 / a.max(b),
+            (Some(distinct), None) | (None, Some(distinct)) => 1.0 / distinct,
+            (None, None) => EQUALITY,
+        }
+    }
+
+    /// A comparison of a column with a number, from its histogram.
+    fn range(&self, left: &Expression, operator: &BinaryOperator, right: &Expression, sources: &[Source]) -> f64 {
+        // `18 < age` is `age > 18`
+        let (column, operator, value) = match (left, right) {
+            (column, Expression::Number(n)) => (column, operator.clone(), *n),
+            (Expression::Number(n), column) => {
+                let flipped = match operator {
+                    BinaryOperator::LessThan => BinaryOperator::GreaterThan,
+                    BinaryOperator::LessThanOrEqual => BinaryOperator::GreaterThanOrEqual,
+                    BinaryOperator::GreaterThan => BinaryOperator::LessThan,
+                    _ => BinaryOperator::LessThanOrEqual,
+                };
+                (column, flipped, *n)
+            }
+            _ => return RANGE,
+        };
+        let histogram = self.column(column, sources).and_then(|(stats, _)| stats.histogram.as_ref());
+        let (Some(histogram), Ok(value)) = (histogram, i64::try_from(value)) else { return RANGE };
+        match operator {
+            BinaryOperator::LessThan => histogram.fraction_below(value),
+            BinaryOperator::LessThanOrEqual => histogram.fraction_below(value.saturating_add(1)),
+            BinaryOperator::GreaterThan => 1.0 - histogram.fraction_below(value.saturating_add(1)),
+            _ => 1.0 - histogram.fraction_below(value),
+        }
+    }
+
+    /// The distinct count of the column `expr` names, at most the rows of
+    /// its table.
+    fn distinct(&self, expr: &Expression, sources: &[Source]) -> Option<f64> {
+        let (stats, rows) = self.column(expr, sources)?;
+        (stats.distinct > 0).then(|| (stats.distinct as f64).min(rows).max(1.0))
+    }
+
+    /// The statistics of the column `expr` names, with the rows of the
+    /// source it's from.
+    fn column<'s>(&self, expr: &Expression, sources: &'s [Source<'a>]) -> Option<(&'s ColumnStats, f64)> {
+        let (qualifier, name) = match expr {
+            Expression::Identifier(name) => (None, name.as_str()),
+            Expression::CompoundIdentifier(parts) if parts.len() >= 2 => {
+                (Some(parts[parts.len() - 2].as_str()), parts[parts.len() - 1].as_str())
+            }
+            _ => return None,
+        };
+        sources
+            .iter()
+            .filter(|source| qualifier.is_none_or(|qualifier| source.name.eq_ignore_ascii_case(qualifier)))
+            .find_map(|source| Some((source.stats?.column(name)?, source.rows)))
+    }
+}
+
+/// The conditions ANDed together in `expr`.
+fn conjuncts<'e>(expr: &'e Expression, out: &mut Vec<&'e Expression>) {
+    match expr {
+        Expression::BinaryOperation { left_operand, operator: BinaryOperator::And, right_operand } => {
+            conjuncts(left_operand, out);
+            conjuncts(right_operand, out);
+        }
+        Expression::Grouped(inner)
+            if matches!(**inner, Expression::BinaryOperation { operator: BinaryOperator::And, .. }) =>
+        {
+            conjuncts(inner, out)
+        }
+        expr => out.push(expr),
+    }
+}
+
+/// `conditions` ANDed together.
+fn conjunction(conditions: Vec<&Expression>) -> Option<Expression> {
+    conditions.into_iter().cloned().reduce(|left, right| Expression::BinaryOperation {
+        left_operand: Box::new(left),
+        operator: BinaryOperator::And,
+        right_operand: Box::new(right),
+    })
+}
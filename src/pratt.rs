@@ -1,14 +1,318 @@
-use crate::tokenizer::{Token, Keyword};
-use crate::ast::{Expression, BinaryOperator, UnaryOperator};
+use core::iter;
+use core::ops::Range;
+
+use crate::prelude::*;
+use crate::tokenizer::{Token, TokenKind, Keyword};
+use crate::ast::{DBType, Expression, BinaryOperator, UnaryOperator, WindowSpec};
+use crate::decimal::MAX_PRECISION;
+use crate::ident::Ident;
+#[cfg(feature = "window")]
+use crate::ast::OrderByExpr;
+use crate::dialect::Dialect;
+use crate::parser::{ExpectedSet, Name};
+#[cfg(not(feature = "window"))]
+use crate::capabilities::missing;
 
 pub struct PrattParser<'a> {
     tokens: &'a [Token],
     position: usize,
+    expected: ExpectedSet,
+    depth: usize,
+    max_depth: Option<usize>,
+    depth_exceeded: bool,
+    compiled_out: bool,
+    dialect: Dialect,
+    ilike: bool,
+    /// The token range of every node built, parents before children, as
+    /// `Expression::walk` visits them.
+    nodes: Vec<Range<usize>>,
+}
+
+/// Tokens that can start an operand.
+const OPERAND_START: [TokenKind; 10] = [
+    TokenKind::Identifier,
+    TokenKind::Number,
+    TokenKind::String,
+    TokenKind::Placeholder,
+    TokenKind::Keyword(Keyword::True),
+    TokenKind::Keyword(Keyword::False),
+    TokenKind::Keyword(Keyword::Null),
+    TokenKind::Keyword(Keyword::Not),
+    TokenKind::Minus,
+    TokenKind::LeftParentheses,
+];
+
+/// Tokens that can start a type, as after `::` or CAST's AS.
+const TYPE_START: [TokenKind; 8] = [
+    TokenKind::Keyword(Keyword::Int),
+    TokenKind::Keyword(Keyword::Bool),
+    TokenKind::Keyword(Keyword::Varchar),
+    TokenKind::Word("DATE"),
+    TokenKind::Word("TIME"),
+    TokenKind::Word("TIMESTAMP"),
+    TokenKind::Word("DECIMAL"),
+    TokenKind::Word("NUMERIC"),
+];
+
+/// Every binary operator with its precedence in the generic dialect;
+/// higher binds tighter, and all binary operators are left-associative.
+pub const OPERATOR_PRECEDENCE: [(BinaryOperator, u8); 15] = [
+    (BinaryOperator::Or, 1),
+    (BinaryOperator::And, 2),
+    (BinaryOperator::Equals, 3),
+    (BinaryOperator::NotEquals, 3),
+    (BinaryOperator::Like, 3),
+    (BinaryOperator::ILike, 3),
+    (BinaryOperator::GreaterThan, 4),
+    (BinaryOperator::GreaterThanOrEqual, 4),
+    (BinaryOperator::LessThan, 4),
+    (BinaryOperator::LessThanOrEqual, 4),
+    (BinaryOperator::Add, 5),
+    (BinaryOperator::Subtract, 5),
+    (BinaryOperator::Concat, 5),
+    (BinaryOperator::Multiply, 6),
+    (BinaryOperator::Divide, 6),
+];
+
+/// ANSI SQL's binary operators: every comparison and LIKE on one level,
+/// and `||` with `+`.
+const STANDARD_OPERATORS: [(BinaryOperator, u8); 15] = [
+    (BinaryOperator::Or, 1),
+    (BinaryOperator::And, 2),
+    (BinaryOperator::Equals, 3),
+    (BinaryOperator::NotEquals, 3),
+    (BinaryOperator::Like, 3),
+    (BinaryOperator::ILike, 3),
+    (BinaryOperator::GreaterThan, 3),
+    (BinaryOperator::GreaterThanOrEqual, 3),
+    (BinaryOperator::LessThan, 3),
+    (BinaryOperator::LessThanOrEqual, 3),
+    (BinaryOperator::Add, 5),
+    (BinaryOperator::Subtract, 5),
+    (BinaryOperator::Concat, 5),
+    (BinaryOperator::Multiply, 6),
+    (BinaryOperator::Divide, 6),
+];
+
+/// How tightly each binary operator binds in a dialect, as returned by
+/// `Dialect::precedence`. Dialects disagree mostly about comparisons: the
+/// generic dialect ranks `<` above `=`, the standard puts every comparison
+/// and LIKE on one level, and Postgres ranks LIKE, ILIKE and BETWEEN above
+/// the comparisons and `||` below `+`. MySQL reads `||` as OR, and only
+/// the generic dialect, Postgres and the warehouses have `::` casts.
+///
+/// A prefix operator's precedence is the lowest an operator may have and
+/// still be part of its operand: NOT takes in comparisons but stops at AND,
+/// so `NOT a = b AND c` is `(NOT (a = b)) AND c`, while unary minus binds
+/// tighter than any binary operator, so `-a * b` is `(-a) * b`. A cast
+/// binding tighter still makes `-a::int` a negated cast, `-(a::int)`;
+/// one looser than `negate` would cast the negation.
+///
+/// IS [NOT] NULL and IS [NOT] DISTINCT FROM bind as NOT does, as in
+/// Postgres: `a = b IS NULL` tests the comparison, `NOT a IS NULL` negates
+/// the test, and `a IS NULL AND b` stops at AND.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrecedenceTable {
+    pub operators: [(BinaryOperator, u8); 15],
+    /// The operator `||` stands for.
+    pub pipes: BinaryOperator,
+    /// `a [NOT] BETWEEN low AND high`, whose bounds are operands of this
+    /// precedence.
+    pub between: u8,
+    pub not: u8,
+    pub negate: u8,
+    /// The postfix cast `expr::type`, or 0 in a dialect without it.
+    pub cast: u8,
+}
+
+impl PrecedenceTable {
+    /// The generic dialect's table, `OPERATOR_PRECEDENCE`.
+    pub const GENERIC: PrecedenceTable = PrecedenceTable {
+        operators: OPERATOR_PRECEDENCE,
+        pipes: BinaryOperator::Concat,
+        between: 4,
+        not: 3,
+        negate: 7,
+        cast: 8,
+    };
+
+    /// ANSI SQL, shared by SQL Server.
+    pub const STANDARD: PrecedenceTable = PrecedenceTable {
+        operators: STANDARD_OPERATORS,
+        pipes: BinaryOperator::Concat,
+        between: 3,
+        not: 3,
+        negate: 7,
+        cast: 0,
+    };
+
+    /// The standard, with `||` as OR.
+    pub const MYSQL: PrecedenceTable = PrecedenceTable { pipes: BinaryOperator::Or, ..PrecedenceTable::STANDARD };
+
+    /// The standard, with `::` casts.
+    pub const WAREHOUSE: PrecedenceTable = PrecedenceTable { cast: 8, ..PrecedenceTable::STANDARD };
+
+    pub const POSTGRES: PrecedenceTable = PrecedenceTable {
+        operators: [
+            (BinaryOperator::Or, 1),
+            (BinaryOperator::And, 2),
+            (BinaryOperator::Equals, 3),
+            (BinaryOperator::NotEquals, 3),
+            (BinaryOperator::Like, 4),
+            (BinaryOperator::ILike, 4),
+            (BinaryOperator::GreaterThan, 3),
+            (BinaryOperator::GreaterThanOrEqual, 3),
+            (BinaryOperator::LessThan, 3),
+            (BinaryOperator::LessThanOrEqual, 3),
+            (BinaryOperator::Concat, 5),
+            (BinaryOperator::Add, 6),
+            (BinaryOperator::Subtract, 6),
+            (BinaryOperator::Multiply, 7),
+            (BinaryOperator::Divide, 7),
+        ],
+        pipes: BinaryOperator::Concat,
+        between: 4,
+        not: 3,
+        negate: 8,
+        cast: 9,
+    };
+
+    /// Every distinct table some dialect uses.
+    pub const ALL: [&'static PrecedenceTable; 5] = [
+        &PrecedenceTable::GENERIC,
+        &PrecedenceTable::STANDARD,
+        &PrecedenceTable::MYSQL,
+        &PrecedenceTable::WAREHOUSE,
+        &PrecedenceTable::POSTGRES,
+    ];
+
+    /// Precedence of an already-parsed binary operator.
+    pub fn of(&self, operator: &BinaryOperator) -> u8 {
+        match operator {
+            BinaryOperator::IsDistinctFrom | BinaryOperator::IsNotDistinctFrom => self.not,
+            operator => self.operators.iter().find(|(op, _)| op == operator).map_or(0, |(_, precedence)| *precedence),
+        }
+    }
+
+    /// Precedence `operator` parses its operand with; for IS [NOT] NULL, the
+    /// lowest an operator may have and be part of the operand before it.
+    pub fn unary(&self, operator: &UnaryOperator) -> u8 {
+        match operator {
+            UnaryOperator::Not | UnaryOperator::IsNull | UnaryOperator::IsNotNull => self.not,
+            UnaryOperator::Negate => self.negate,
+        }
+    }
+
+    /// The binary operator `token` stands for in this table's dialect.
+    pub fn operator(&self, token: &Token) -> Option<BinaryOperator> {
+        match token {
+            Token::Concat => Some(self.pipes.clone()),
+            token => binary_operator(token),
+        }
+    }
+
+    /// Precedence of `token` as a binary operator, or 0 if it isn't one.
+    pub fn token(&self, token: &Token) -> u8 {
+        self.operator(token).map_or(0, |operator| self.of(&operator))
+    }
 }
 
+const BINARY_OPERATORS: [Token; 13] = [
+    Token::Keyword(Keyword::Or),
+    Token::Keyword(Keyword::And),
+    Token::Equal,
+    Token::NotEqual,
+    Token::GreaterThan,
+    Token::GreaterThanOrEqual,
+    Token::LessThan,
+    Token::LessThanOrEqual,
+    Token::Plus,
+    Token::Minus,
+    Token::Concat,
+    Token::Multiply,
+    Token::Divide,
+];
+
 impl<'a> PrattParser<'a> {
     pub fn new(tokens: &'a [Token]) -> Self {
-        Self { tokens, position: 0 }
+        Self::with_max_depth(tokens, None)
+    }
+
+    /// Creates a parser that gives up on expressions nested deeper than
+    /// `max_depth`.
+    pub fn with_max_depth(tokens: &'a [Token], max_depth: Option<usize>) -> Self {
+        Self {
+            tokens,
+            position: 0,
+            expected: ExpectedSet::default(),
+            depth: 0,
+            max_depth,
+            depth_exceeded: false,
+            compiled_out: false,
+            dialect: Dialect::Generic,
+            ilike: false,
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Accepts the operators of `dialect` (such as ILIKE) as well as the
+    /// common ones.
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self.ilike = dialect.supports_ilike();
+        self
+    }
+
+    /// Overrides whether the dialect has the ILIKE operator.
+    pub fn with_ilike(mut self, ilike: bool) -> Self {
+        self.ilike = ilike;
+        self
+    }
+
+    /// True if parsing stopped because the expression was nested too deeply.
+    pub fn depth_exceeded(&self) -> bool {
+        self.depth_exceeded
+    }
+
+    /// True if parsing stopped at grammar this build was compiled without;
+    /// the error then names the feature it needs.
+    pub fn compiled_out(&self) -> bool {
+        self.compiled_out
+    }
+
+    /// Starts parsing at `tokens[position]` rather than at the first
+    /// token, so that positions are indices into the whole statement.
+    pub(crate) fn starting_at(mut self, position: usize) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Index of the next token, which is the number of tokens consumed so
+    /// far unless the parser started further in.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The token kinds that would have been accepted at the furthest
+    /// position reached.
+    pub fn expected(&self) -> &[TokenKind] {
+        &self.expected.kinds
+    }
+
+    pub(crate) fn expected_set(&self) -> &ExpectedSet {
+        &self.expected
+    }
+
+    /// Takes the token ranges of the nodes of the expressions parsed so
+    /// far, in the order `Expression::walk` visits them.
+    pub(crate) fn take_nodes(&mut self) -> Vec<Range<usize>> {
+        core::mem::take(&mut self.nodes)
+    }
+
+    /// Records that the node just built started at token `start`, ahead of
+    /// the nodes inside it, which were recorded from index `first` on.
+    fn node(&mut self, first: usize, start: usize) {
+        self.nodes.insert(first, start..self.position);
     }
 
     fn peek(&self) -> Option<&Token> {
@@ -16,12 +320,33 @@ impl<'a> PrattParser<'a> {
     }
 
     fn advance(&mut self) -> Option<&Token> {
+        trace_event!("token", self.position, crate::trace::TraceKind::Token);
         let token = self.tokens.get(self.position);
         self.position += 1;
         token
     }
 
+    /// Consumes the next token if it is the non-reserved word `word`.
+    fn eat_word(&mut self, word: &'static str) -> bool {
+        self.expected.record(self.position, TokenKind::Word(word));
+        if self.peek().is_some_and(|tok| TokenKind::Word(word).matches(tok)) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Precedence of `token` as a binary operator in this parser's dialect.
+    fn precedence(&self, token: &Token) -> u8 {
+        match token {
+            Token::Identifier(word) if word.eq_ignore_ascii_case("ILIKE") && !self.ilike => 0,
+            token => self.dialect.precedence().token(token),
+        }
+    }
+
     fn expect(&mut self, expected: &Token) -> Result<(), String> {
+        self.expected.record(self.position, expected.kind());
         match self.peek() {
             Some(tok) if tok == expected => {
                 self.advance();
@@ -31,28 +356,78 @@ impl<'a> PrattParser<'a> {
             None => Err(format!("Expected token {:?}, but found end of input", expected)),
         }
     }
-    // Optional debug method for tracing parsing steps
-    #[allow(dead_code)]
-   fn debug_print(&self, _message: &str) {
-    println!("[DEBUG] {} at position {}", _message, self.position);
-}
 
     pub fn parse_expression(&mut self, min_precedence: u8) -> Result<Expression, String> {
+        trace_span!("subexpression", self.position);
+        if self.max_depth.is_some_and(|max| self.depth >= max) {
+            self.depth_exceeded = true;
+            return Err(format!("Expression is nested deeper than the limit of {}", self.depth));
+        }
+        self.depth += 1;
+        let result = self.parse_operation(min_precedence);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_operation(&mut self, min_precedence: u8) -> Result<Expression, String> {
+        for kind in OPERAND_START {
+            self.expected.record(self.position, kind);
+        }
+        self.expected.name(self.position, Name::Column);
+        let (start, first) = (self.position, self.nodes.len());
         let mut left = match self.advance() {
-            Some(Token::Identifier(name)) => Expression::Identifier(name.clone()),
+            Some(Token::Identifier(name)) => {
+                let mut parts = vec![name.clone()];
+                while self.peek() == Some(&Token::Dot) {
+                    self.advance();
+                    match self.advance() {
+                        Some(Token::Identifier(part)) => parts.push(part.clone()),
+                        _ => {
+                            self.position -= 1;
+                            self.expected.record(self.position, TokenKind::Identifier);
+                            return Err("Expected an identifier after '.'".to_string());
+                        }
+                    }
+                }
+                let typed = match parts.as_slice() {
+                    [name] if name.quote.is_none() => typed_literal(&name.value),
+                    _ => None,
+                };
+                if let (Some(data_type), Some(Token::String(value))) = (typed, self.peek()) {
+                    let value = value.clone();
+                    self.advance();
+                    Expression::TypedString { data_type, value }
+                } else if parts.len() == 1 && self.is_extract(&parts[0]) {
+                    self.parse_extract()?
+                } else if parts.len() == 1 && self.is_cast(&parts[0]) {
+                    self.parse_cast()?
+                } else if parts.len() == 1 && self.peek() == Some(&Token::LeftParentheses) {
+                    self.parse_function(parts.remove(0).value)?
+                } else if parts.len() == 1 {
+                    Expression::Identifier(parts.remove(0))
+                } else {
+                    Expression::CompoundIdentifier(parts)
+                }
+            }
             Some(Token::Number(n)) => Expression::Number(*n),
+            Some(Token::Decimal(text)) => Expression::Decimal(text.clone()),
             Some(Token::String(s)) => Expression::String(s.clone()),
+            Some(Token::PrefixedString(prefix, value)) => {
+                Expression::PrefixedString { prefix: *prefix, value: value.clone() }
+            }
+            Some(Token::Placeholder(p)) => Expression::Placeholder(p.clone()),
             Some(Token::Keyword(Keyword::True)) => Expression::Boolean(true),
             Some(Token::Keyword(Keyword::False)) => Expression::Boolean(false),
+            Some(Token::Keyword(Keyword::Null)) => Expression::Null,
             Some(Token::Keyword(Keyword::Not)) => {
-                let expr = self.parse_expression(6)?; // Highest precedence for NOT
+                let expr = self.parse_expression(self.dialect.precedence().not)?;
                 Expression::UnaryOperation {
                     operator: UnaryOperator::Not,
                     operand: Box::new(expr),
                 }
             }
             Some(Token::Minus) => {
-                let expr = self.parse_expression(6)?;
+                let expr = self.parse_expression(self.dialect.precedence().negate)?;
                 Expression::UnaryOperation {
                     operator: UnaryOperator::Negate,
                     operand: Box::new(expr),
@@ -66,32 +441,104 @@ impl<'a> PrattParser<'a> {
             Some(t) => return Err(format!("Unexpected token at start of expression: {:?}", t)),
             None => return Err("Unexpected end of input while parsing expression".to_string()),
         };
+        self.node(first, start);
 
         loop {
-            let op = match self.peek() {
-                Some(tok) if get_precedence(tok) >= min_precedence => tok.clone(),
-                _ => break,
+            // `expr COLLATE name` binds tighter than any operator
+            self.expected.record(self.position, TokenKind::Word("COLLATE"));
+            if self.peek().is_some_and(|token| TokenKind::Word("COLLATE").matches(token)) {
+                self.advance();
+                let Some(Token::Identifier(collation)) = self.advance().cloned() else {
+                    self.position -= 1;
+                    self.expected.record(self.position, TokenKind::Identifier);
+                    return Err("Expected a collation name after COLLATE".to_string());
+                };
+                left = Expression::Collate { expr: Box::new(left), collation };
+                self.node(first, start);
+                continue;
+            }
+            let table = self.dialect.precedence();
+            for op in BINARY_OPERATORS.iter().filter(|op| table.token(op) >= min_precedence) {
+                self.expected.record(self.position, op.kind());
+            }
+            if table.of(&BinaryOperator::Like) >= min_precedence {
+                self.expected.record(self.position, TokenKind::Word("LIKE"));
+                if self.ilike {
+                    self.expected.record(self.position, TokenKind::Word("ILIKE"));
+                }
+            }
+            if table.cast > 0 && table.cast >= min_precedence {
+                self.expected.record(self.position, TokenKind::DoubleColon);
+                if self.peek() == Some(&Token::DoubleColon) {
+                    self.advance();
+                    let data_type = self.parse_type()?;
+                    left = Expression::Cast { expr: Box::new(left), data_type };
+                    self.node(first, start);
+                    continue;
+                }
+            }
+            if table.not >= min_precedence {
+                self.expected.record(self.position, TokenKind::Word("IS"));
+                let at = |offset: usize, kind: TokenKind| {
+                    self.tokens.get(self.position + offset).is_some_and(|token| kind.matches(token))
+                };
+                let after =
+                    [TokenKind::Keyword(Keyword::Not), TokenKind::Keyword(Keyword::Null), TokenKind::Word("DISTINCT")];
+                let is = at(0, TokenKind::Word("IS"));
+                // Without one of these after it, IS may be an alias
+                let test = is && after.iter().any(|kind| at(1, *kind));
+                if is {
+                    for kind in after {
+                        self.expected.record(self.position + 1, kind);
+                    }
+                }
+                if test {
+                    self.advance();
+                    left = self.parse_is(left, table.not)?;
+                    self.node(first, start);
+                    continue;
+                }
+            }
+            if table.between >= min_precedence {
+                self.expected.record(self.position, TokenKind::Word("BETWEEN"));
+                self.expected.record(self.position, TokenKind::Keyword(Keyword::Not));
+                let negated = self.peek() == Some(&Token::Keyword(Keyword::Not));
+                // NOT after an operand can only be NOT BETWEEN
+                if negated {
+                    self.expected.record(self.position + 1, TokenKind::Word("BETWEEN"));
+                }
+                let between = self.tokens.get(self.position + negated as usize);
+                if between.is_some_and(|token| TokenKind::Word("BETWEEN").matches(token)) {
+                    if negated {
+                        self.advance();
+                    }
+                    self.advance();
+                    left = self.parse_between(left, negated, table.between, first, start)?;
+                    continue;
+                }
+            }
+            let Some(op) = self.peek().cloned() else {
+                break;
             };
-
-            let precedence = get_precedence(&op);
+            let precedence = self.precedence(&op);
+            if precedence > 0 {
+                trace_event!(
+                    "operator",
+                    self.position,
+                    crate::trace::TraceKind::Precedence { precedence, min: min_precedence }
+                );
+            }
+            // An operator that binds less tightly than the minimum belongs
+            // to an enclosing call
+            if precedence == 0 || precedence < min_precedence {
+                break;
+            }
             self.advance(); // consume the operator
 
             let right = self.parse_expression(precedence + 1)?;
 
-            let operator = match op {
-                Token::Equal => BinaryOperator::Equals,
-                Token::NotEqual => BinaryOperator::NotEquals,
-                Token::GreaterThan => BinaryOperator::GreaterThan,
-                Token::GreaterThanOrEqual => BinaryOperator::GreaterThanOrEqual,
-                Token::LessThan => BinaryOperator::LessThan,
-                Token::LessThanOrEqual => BinaryOperator::LessThanOrEqual,
-                Token::Plus => BinaryOperator::Add,
-                Token::Minus => BinaryOperator::Subtract,
-                Token::Multiply => BinaryOperator::Multiply,
-                Token::Divide => BinaryOperator::Divide,
-                Token::Keyword(Keyword::And) => BinaryOperator::And,
-                Token::Keyword(Keyword::Or) => BinaryOperator::Or,
-                _ => return Err(format!("Unknown binary operator: {:?}", op)),
+            let Some(operator) = table.operator(&op) else {
+                return Err(format!("Unknown binary operator: {:?}", op));
             };
 
             left = Expression::BinaryOperation {
@@ -99,21 +546,302 @@ impl<'a> PrattParser<'a> {
                 operator,
                 right_operand: Box::new(right),
             };
+            self.node(first, start);
         }
 
         Ok(left)
     }
+
+    /// The rest of `left IS [NOT] NULL` or `left IS [NOT] DISTINCT FROM
+    /// right`, after IS; `right` is an operand of `precedence`.
+    fn parse_is(&mut self, left: Expression, precedence: u8) -> Result<Expression, String> {
+        self.expected.record(self.position, TokenKind::Keyword(Keyword::Not));
+        let negated = self.peek() == Some(&Token::Keyword(Keyword::Not));
+        if negated {
+            self.advance();
+        }
+        self.expected.record(self.position, TokenKind::Keyword(Keyword::Null));
+        if self.peek() == Some(&Token::Keyword(Keyword::Null)) {
+            self.advance();
+            let operator = if negated { UnaryOperator::IsNotNull } else { UnaryOperator::IsNull };
+            return Ok(Expression::UnaryOperation { operator, operand: Box::new(left) });
+        }
+        if !self.eat_word("DISTINCT") {
+            return Err("Expected NULL or DISTINCT FROM after IS".to_string());
+        }
+        self.expect(&Token::Keyword(Keyword::From))?;
+        let right = self.parse_expression(precedence + 1)?;
+        let operator = if negated { BinaryOperator::IsNotDistinctFrom } else { BinaryOperator::IsDistinctFrom };
+        Ok(Expression::BinaryOperation { left_operand: Box::new(left), operator, right_operand: Box::new(right) })
+    }
+
+    /// Whether `name` and the tokens after it start `EXTRACT(field FROM`;
+    /// any other call of a function named extract is an ordinary one.
+    fn is_extract(&self, name: &Ident) -> bool {
+        let next = |offset: usize| self.tokens.get(self.position + offset);
+        name.quote.is_none()
+            && name.value.eq_ignore_ascii_case("EXTRACT")
+            && next(0) == Some(&Token::LeftParentheses)
+            && matches!(next(1), Some(Token::Identifier(_)))
+            && next(2) == Some(&Token::Keyword(Keyword::From))
+    }
+
+    /// `EXTRACT(field FROM expr)`, read as `date_part('field', expr)`.
+    fn parse_extract(&mut self) -> Result<Expression, String> {
+        self.expect(&Token::LeftParentheses)?;
+        self.nodes.push(self.position..self.position + 1);
+        let Some(Token::Identifier(field)) = self.advance() else {
+            return Err("Expected a field name after EXTRACT(".to_string());
+        };
+        let field = Expression::String(field.value.to_lowercase());
+        self.expect(&Token::Keyword(Keyword::From))?;
+        let expr = self.parse_expression(1)?;
+        self.expect(&Token::RightParentheses)?;
+        Ok(Expression::Function { name: "date_part".to_string(), args: vec![field, expr], over: None })
+    }
+
+    /// The bounds of `expr [NOT] BETWEEN low AND high`, which the standard
+    /// defines as `expr >= low AND expr <= high` and which is read as that,
+    /// grouped, with NOT in front when negated. `expr` started at token
+    /// `start`, its nodes recorded from index `first` on.
+    ///
+    /// None of the nodes made up have text of their own: `expr >= low`
+    /// spans `expr BETWEEN low`, the others all of it, and both copies of
+    /// `expr` the one that was written.
+    fn parse_between(
+        &mut self,
+        expr: Expression,
+        negated: bool,
+        precedence: u8,
+        first: usize,
+        start: usize,
+    ) -> Result<Expression, String> {
+        let operand = self.nodes.split_off(first);
+        let low = self.parse_expression(precedence + 1)?;
+        let low_end = self.position;
+        let low_nodes = self.nodes.split_off(first);
+        self.expect(&Token::Keyword(Keyword::And))?;
+        let high = self.parse_expression(precedence + 1)?;
+        let high_nodes = self.nodes.split_off(first);
+
+        // [Grouped, NOT,] Grouped, AND, then each comparison
+        let all = start..self.position;
+        self.nodes.extend(iter::repeat_n(all.clone(), if negated { 4 } else { 2 }));
+        self.nodes.push(start..low_end);
+        self.nodes.extend(operand.iter().cloned().chain(low_nodes));
+        self.nodes.push(all);
+        self.nodes.extend(operand.into_iter().chain(high_nodes));
+        Ok(between(expr, negated, low, high))
+    }
+
+    fn is_cast(&self, name: &Ident) -> bool {
+        name.quote.is_none()
+            && name.value.eq_ignore_ascii_case("CAST")
+            && self.peek() == Some(&Token::LeftParentheses)
+    }
+
+    /// `CAST(expr AS type)`.
+    fn parse_cast(&mut self) -> Result<Expression, String> {
+        self.expect(&Token::LeftParentheses)?;
+        let expr = self.parse_expression(1)?;
+        self.expect(&Token::Keyword(Keyword::As))?;
+        let data_type = self.parse_type()?;
+        self.expect(&Token::RightParentheses)?;
+        Ok(Expression::Cast { expr: Box::new(expr), data_type })
+    }
+
+    /// A type as CREATE TABLE writes it.
+    fn parse_type(&mut self) -> Result<DBType, String> {
+        let number = |parser: &mut Self| match parser.advance() {
+            Some(Token::Number(n)) => Ok(*n),
+            _ => Err("Expected a number in the type".to_string()),
+        };
+        for kind in TYPE_START {
+            self.expected.record(self.position, kind);
+        }
+        let data_type = match self.advance() {
+            Some(Token::Keyword(Keyword::Int)) => DBType::Int,
+            Some(Token::Keyword(Keyword::Bool)) => DBType::Bool,
+            Some(Token::Keyword(Keyword::Varchar)) => {
+                self.expect(&Token::LeftParentheses)?;
+                let length = number(self)? as usize;
+                self.expect(&Token::RightParentheses)?;
+                DBType::Varchar(length)
+            }
+            Some(Token::Identifier(name)) if name.quote.is_none() => match name.value.to_ascii_uppercase().as_str() {
+                "DATE" => DBType::Date,
+                "TIME" => DBType::Time,
+                "TIMESTAMP" => DBType::Timestamp,
+                "DECIMAL" | "NUMERIC" => {
+                    let (mut precision, mut scale) = (MAX_PRECISION as u64, 0);
+                    if self.peek() == Some(&Token::LeftParentheses) {
+                        self.advance();
+                        precision = number(self)?;
+                        if self.peek() == Some(&Token::Comma) {
+                            self.advance();
+                            scale = number(self)?;
+                        }
+                        self.expect(&Token::RightParentheses)?;
+                    }
+                    decimal_type(precision, scale)?
+                }
+                _ => return Err(format!("Unknown type {}", name.value)),
+            },
+            Some(t) => return Err(format!("Expected a type, but found {:?}", t)),
+            None => return Err("Expected a type, but found end of input".to_string()),
+        };
+        Ok(data_type)
+    }
+
+    /// `name(args) [OVER (...)]`, with the current token the opening
+    /// parenthesis. The `*` of `COUNT(*)` is an argument kept as the
+    /// identifier `*`, as a select list keeps it.
+    fn parse_function(&mut self, name: String) -> Result<Expression, String> {
+        self.expect(&Token::LeftParentheses)?;
+        let mut args = Vec::new();
+        let star = self.peek() == Some(&Token::Multiply)
+            && self.tokens.get(self.position + 1) == Some(&Token::RightParentheses);
+        if star {
+            self.nodes.push(self.position..self.position + 1);
+            self.advance();
+            args.push(Expression::Identifier(Ident::new("*")));
+        } else if self.peek() != Some(&Token::RightParentheses) {
+            args = self.parse_expression_list()?;
+        }
+        self.expect(&Token::RightParentheses)?;
+
+        let over = if self.eat_word("OVER") { Some(Box::new(self.parse_window()?)) } else { None };
+        Ok(Expression::Function { name, args, over })
+    }
+
+    /// `([PARTITION BY expr, ...] [ORDER BY expr [ASC|DESC], ...])`
+    #[cfg(feature = "window")]
+    fn parse_window(&mut self) -> Result<WindowSpec, String> {
+        self.expect(&Token::LeftParentheses)?;
+        let mut window = WindowSpec::default();
+        if self.eat_word("PARTITION") {
+            self.expect(&Token::Keyword(Keyword::By))?;
+            window.partition_by = self.parse_expression_list()?;
+        }
+        self.expected.record(self.position, TokenKind::Keyword(Keyword::Order));
+        if self.peek() == Some(&Token::Keyword(Keyword::Order)) {
+            self.advance();
+            self.expect(&Token::Keyword(Keyword::By))?;
+            loop {
+                let expr = self.parse_expression(1)?;
+                let asc = if self.eat_word("ASC") {
+                    Some(true)
+                } else if self.eat_word("DESC") {
+                    Some(false)
+                } else {
+                    None
+                };
+                window.order_by.push(OrderByExpr { expr, asc });
+                if !self.eat_comma() {
+                    break;
+                }
+            }
+        }
+        self.expect(&Token::RightParentheses)?;
+        Ok(window)
+    }
+
+    #[cfg(not(feature = "window"))]
+    fn parse_window(&mut self) -> Result<WindowSpec, String> {
+        self.compiled_out = true;
+        Err(missing("Window functions", "window"))
+    }
+
+    fn parse_expression_list(&mut self) -> Result<Vec<Expression>, String> {
+        let mut exprs = vec![self.parse_expression(1)?];
+        while self.eat_comma() {
+            exprs.push(self.parse_expression(1)?);
+        }
+        Ok(exprs)
+    }
+
+    fn eat_comma(&mut self) -> bool {
+        self.expected.record(self.position, TokenKind::Comma);
+        if self.peek() == Some(&Token::Comma) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// The binary operator `token` stands for, if any.
+fn binary_operator(token: &Token) -> Option<BinaryOperator> {
+    Some(match token {
+        Token::Equal => BinaryOperator::Equals,
+        Token::NotEqual => BinaryOperator::NotEquals,
+        Token::GreaterThan => BinaryOperator::GreaterThan,
+        Token::GreaterThanOrEqual => BinaryOperator::GreaterThanOrEqual,
+        Token::LessThan => BinaryOperator::LessThan,
+        Token::LessThanOrEqual => BinaryOperator::LessThanOrEqual,
+        Token::Plus => BinaryOperator::Add,
+        Token::Minus => BinaryOperator::Subtract,
+        Token::Multiply => BinaryOperator::Multiply,
+        Token::Divide => BinaryOperator::Divide,
+        Token::Keyword(Keyword::And) => BinaryOperator::And,
+        Token::Keyword(Keyword::Or) => BinaryOperator::Or,
+        Token::Identifier(word) if word.eq_ignore_ascii_case("LIKE") => BinaryOperator::Like,
+        Token::Identifier(word) if word.eq_ignore_ascii_case("ILIKE") => BinaryOperator::ILike,
+        Token::Concat => BinaryOperator::Concat,
+        _ => return None,
+    })
+}
+
+/// `expr [NOT] BETWEEN low AND high` as the parser reads it:
+/// `(expr >= low AND expr <= high)`, inside `(NOT ...)` when negated.
+pub(crate) fn between(expr: Expression, negated: bool, low: Expression, high: Expression) -> Expression {
+    let bound = |operator, bound| Expression::BinaryOperation {
+        left_operand: Box::new(expr.clone()),
+        operator,
+        right_operand: Box::new(bound),
+    };
+    let range = Expression::Grouped(Box::new(Expression::BinaryOperation {
+        left_operand: Box::new(bound(BinaryOperator::GreaterThanOrEqual, low)),
+        operator: BinaryOperator::And,
+        right_operand: Box::new(bound(BinaryOperator::LessThanOrEqual, high)),
+    }));
+    if !negated {
+        return range;
+    }
+    let operand = Box::new(range);
+    Expression::Grouped(Box::new(Expression::UnaryOperation { operator: UnaryOperator::Not, operand }))
+}
+
+/// Precedence of `token` as a binary operator in the generic dialect, or 0
+/// if it isn't one.
+pub fn token_precedence(token: &Token) -> u8 {
+    PrecedenceTable::GENERIC.token(token)
+}
+
+/// Precedence of an already-parsed binary operator in the generic dialect,
+/// matching `token_precedence`.
+pub fn operator_precedence(operator: &BinaryOperator) -> u8 {
+    PrecedenceTable::GENERIC.of(operator)
+}
+
+/// DECIMAL(`precision`, `scale`), if a decimal can have that many digits.
+pub(crate) fn decimal_type(precision: u64, scale: u64) -> Result<DBType, String> {
+    if precision == 0 || precision > MAX_PRECISION as u64 || scale > precision {
+        return Err(format!(
+            "DECIMAL({}, {}) should have 1 to {} digits, with no more of them after the point",
+            precision, scale, MAX_PRECISION
+        ));
+    }
+    Ok(DBType::Decimal(precision as u8, scale as u8))
 }
 
-fn get_precedence(token: &Token) -> u8 {
-    match token {
-        Token::Keyword(Keyword::Or) => 1,
-        Token::Keyword(Keyword::And) => 2,
-        Token::Equal | Token::NotEqual => 3,
-        Token::GreaterThan | Token::GreaterThanOrEqual |
-        Token::LessThan | Token::LessThanOrEqual => 4,
-        Token::Plus | Token::Minus => 5,
-        Token::Multiply | Token::Divide => 6,
-        _ => 0,
+/// The type of a literal written as `name 'value'`, such as `DATE '2024-01-31'`.
+fn typed_literal(name: &str) -> Option<DBType> {
+    match name.to_ascii_uppercase().as_str() {
+        "DATE" => Some(DBType::Date),
+        "TIME" => Some(DBType::Time),
+        "TIMESTAMP" => Some(DBType::Timestamp),
+        _ => None,
     }
 }
@@ -0,0 +1,904 @@
+// printer.rs — turns the AST back into SQL text for a given dialect
+// Author: Sreerag Devadasan
+
+use core::fmt;
+
+use crate::prelude::*;
+use crate::ast::{
+    BinaryOperator, Constraint, CopyTarget, DBType, DuplicateHandling, Expression, GroupingElement, Hint, Join, JoinKind, LoadDataFormat,
+    GrantObject, GrantedPrivilege, LockStrength,
+    LockWait, LockingClause, MergeAction, MergeClause, MergeSource, OrderByExpr, Privilege, RoleOption, RoutineType,
+    SelectItem,
+    SetOperator, SetScope, Statement, StringPrefix, TableColumn, TableFactor, TransactionCommand, TriggerBody,
+    TriggerEvent, TriggerTiming,
+};
+use crate::dialect::Dialect;
+use crate::ident::Ident;
+
+// MySQL has no OFFSET without LIMIT; its manual recommends the largest
+// unsigned bigint as "no limit".
+const MYSQL_NO_LIMIT: u64 = u64::MAX;
+
+impl Expression {
+    /// Prints the expression as SQL in the given dialect.
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        match self {
+            Expression::Identifier(name) if name == "*" => "*".to_string(),
+            Expression::Identifier(name) => name.to_sql(dialect),
+            Expression::CompoundIdentifier(parts) => {
+                parts.iter().map(|p| p.to_sql(dialect)).collect::<Vec<_>>().join(".")
+            }
+            Expression::Number(n) => n.to_string(),
+            Expression::Decimal(text) => text.clone(),
+            Expression::String(s) => format!("'{}'", s.replace('\'', "''")),
+            Expression::UnaryOperation { operator, operand } if operator.is_postfix() => {
+                format!("{}{}", operand.to_sql(dialect), operator)
+            }
+            Expression::UnaryOperation { operator, operand } => format!("{}{}", operator, operand.to_sql(dialect)),
+            // Elsewhere ILIKE is spelled out as a LIKE of lowercased operands
+            Expression::BinaryOperation { left_operand, operator: BinaryOperator::ILike, right_operand }
+                if !dialect.supports_ilike() =>
+            {
+                let lower = |operand: &Expression| format!("LOWER({})", operand.to_sql(dialect));
+                format!("{} LIKE {}", lower(left_operand), lower(right_operand))
+            }
+            // MySQL reads `||` as OR
+            Expression::BinaryOperation { left_operand, operator: BinaryOperator::Concat, right_operand }
+                if dialect.precedence().pipes != BinaryOperator::Concat =>
+            {
+                format!("CONCAT({}, {})", left_operand.to_sql(dialect), right_operand.to_sql(dialect))
+            }
+            // MySQL has `<=>` for IS NOT DISTINCT FROM instead
+            Expression::BinaryOperation {
+                left_operand,
+                operator: operator @ (BinaryOperator::IsDistinctFrom | BinaryOperator::IsNotDistinctFrom),
+                right_operand,
+            } if dialect == Dialect::MySql => {
+                let same = format!("{} <=> {}", left_operand.to_sql(dialect), right_operand.to_sql(dialect));
+                if *operator == BinaryOperator::IsDistinctFrom { format!("NOT ({})", same) } else { same }
+            }
+            Expression::BinaryOperation { left_operand, operator, right_operand } => {
+                format!("{} {} {}", left_operand.to_sql(dialect), operator, right_operand.to_sql(dialect))
+            }
+            Expression::Boolean(b) if !dialect.supports_boolean_literals() => (*b as u8).to_string(),
+            Expression::Boolean(true) => "TRUE".to_string(),
+            Expression::Boolean(false) => "FALSE".to_string(),
+            Expression::Null => "NULL".to_string(),
+            Expression::Grouped(inner) => format!("({})", inner.to_sql(dialect)),
+            Expression::Placeholder(name) => name.clone(),
+            Expression::TypedString { data_type, value } => format!("{} '{}'", data_type, value.replace('\'', "''")),
+            Expression::PrefixedString { prefix: StringPrefix::Escape, value } => format!("E'{}'", escape(value)),
+            Expression::PrefixedString { prefix, value } => {
+                format!("{}'{}'", prefix.letter(), value.replace('\'', "''"))
+            }
+            Expression::Collate { expr, collation } => {
+                format!("{} COLLATE {}", expr.to_sql(dialect), collation.to_sql(dialect))
+            }
+            Expression::Cast { expr, data_type } => format!("CAST({} AS {})", expr.to_sql(dialect), data_type),
+            Expression::Function { name, args, over } => {
+                let mut sql = format!("{}({})", dialect.quote_identifier(name), expression_list(args, dialect));
+                if let Some(window) = over {
+                    let mut clauses = Vec::new();
+                    if !window.partition_by.is_empty() {
+                        clauses.push(format!("PARTITION BY {}", expression_list(&window.partition_by, dialect)));
+                    }
+                    if !window.order_by.is_empty() {
+                        clauses.push(format!("ORDER BY {}", order_by_list(&window.order_by, dialect)));
+                    }
+                    sql.push_str(&format!(" OVER ({})", clauses.join(" ")));
+                }
+                sql
+            }
+        }
+    }
+}
+
+impl SelectItem {
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        match &self.alias {
+            Some(alias) => format!("{} AS {}", self.expr.to_sql(dialect), alias.to_sql(dialect)),
+            None => self.expr.to_sql(dialect),
+        }
+    }
+}
+
+impl Statement {
+    /// Prints the statement as SQL in the given dialect, rewriting row
+    /// limits into the form that dialect understands.
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        match self {
+            Statement::Select {
+                hints,
+                columns,
+                wildcard,
+                from,
+                joins,
+                selection,
+                group_by,
+                qualify,
+                order_by,
+                limit,
+                offset,
+                locking,
+            } => {
+                let mut sql = String::from("SELECT ");
+                let (modifiers, comment): (Vec<&Hint>, Vec<&Hint>) =
+                    hints.iter().partition(|hint| Hint::MODIFIERS.contains(&hint.name.as_str()));
+                if !comment.is_empty() {
+                    let comment: Vec<String> = comment.iter().map(|hint| hint.to_sql()).collect();
+                    sql.push_str(&format!("/*+ {} */ ", comment.join(" ")));
+                }
+                for modifier in modifiers {
+                    sql.push_str(&format!("{} ", modifier.name));
+                }
+                if let (Dialect::SqlServer, Some(n), None) = (dialect, limit, offset) {
+                    sql.push_str(&format!("TOP {} ", n));
+                }
+                let columns: Vec<String> = columns.iter().map(|item| item.to_sql(dialect)).collect();
+                sql.push_str(&columns.join(", "));
+                if !wildcard.exclude.is_empty() {
+                    sql.push_str(&format!(" EXCLUDE ({})", ident_list(&wildcard.exclude, dialect)));
+                }
+                if !wildcard.replace.is_empty() {
+                    let items: Vec<String> = wildcard
+                        .replace
+                        .iter()
+                        .map(|(expr, column)| format!("{} AS {}", expr.to_sql(dialect), column.to_sql(dialect)))
+                        .collect();
+                    sql.push_str(&format!(" REPLACE ({})", items.join(", ")));
+                }
+                sql.push_str(&format!(" FROM {}", from.to_sql(dialect)));
+                for join in joins {
+                    sql.push_str(&join.to_sql(dialect));
+                }
+
+                if let Some(selection) = selection {
+                    sql.push_str(&format!(" WHERE {}", selection.to_sql(dialect)));
+                }
+                if !group_by.is_empty() {
+                    let elements: Vec<String> = group_by.iter().map(|e| e.to_sql(dialect)).collect();
+                    sql.push_str(&format!(" GROUP BY {}", elements.join(", ")));
+                }
+                if let Some(qualify) = qualify {
+                    sql.push_str(&format!(" QUALIFY {}", qualify.to_sql(dialect)));
+                }
+                if !order_by.is_empty() {
+                    sql.push_str(&format!(" ORDER BY {}", order_by_list(order_by, dialect)));
+                } else if dialect == Dialect::SqlServer && offset.is_some() {
+                    // SQL Server only allows OFFSET after an ORDER BY
+                    sql.push_str(" ORDER BY (SELECT NULL)");
+                }
+
+                match dialect {
+                    Dialect::Generic | Dialect::Postgres | Dialect::MySql | Dialect::Warehouse => {
+                        let limit = match (dialect, limit, offset) {
+                            (Dialect::MySql, None, Some(_)) => Some(MYSQL_NO_LIMIT),
+                            _ => *limit,
+                        };
+                        if let Some(n) = limit {
+                            sql.push_str(&format!(" LIMIT {}", n));
+                        }
+                        if let Some(m) = offset {
+                            sql.push_str(&format!(" OFFSET {}", m));
+                        }
+                    }
+                    Dialect::Ansi | Dialect::SqlServer => {
+                        if let Some(m) = offset {
+                            sql.push_str(&format!(" OFFSET {} ROWS", m));
+                        }
+                        match (dialect, limit, offset) {
+                            (Dialect::SqlServer, Some(n), Some(_)) => {
+                                sql.push_str(&format!(" FETCH NEXT {} ROWS ONLY", n))
+                            }
+                            (Dialect::Ansi, Some(n), _) => sql.push_str(&format!(" FETCH FIRST {} ROWS ONLY", n)),
+                            _ => {}
+                        }
+                    }
+                }
+                for clause in locking {
+                    sql.push_str(&clause.to_sql(dialect));
+                }
+                sql
+            }
+            Statement::CreateTable { table_name, column_list } => {
+                let columns: Vec<String> = column_list.iter().map(|c| c.to_sql(dialect)).collect();
+                format!("CREATE TABLE {} ({})", table_name.to_sql(dialect), columns.join(", "))
+            }
+            Statement::DropTable { table_name, if_exists } => format!(
+                "DROP TABLE {}{}",
+                if *if_exists { "IF EXISTS " } else { "" },
+                table_name.to_sql(dialect)
+            ),
+            Statement::Insert { table_name, columns, rows } => {
+                let mut sql = format!("INSERT INTO {}", table_name.to_sql(dialect));
+                if let Some(columns) = columns {
+                    let names: Vec<String> = columns.iter().map(|c| c.to_sql(dialect)).collect();
+                    sql.push_str(&format!(" ({})", names.join(", ")));
+                }
+                sql.push_str(&format!(" VALUES {}", value_rows(rows, dialect)));
+                sql
+            }
+            Statement::CreateView { or_replace, name, columns, query } => {
+                let replace = if *or_replace { "OR REPLACE " } else { "" };
+                let mut sql = format!("CREATE {}VIEW {}", replace, name.to_sql(dialect));
+                if !columns.is_empty() {
+                    let names: Vec<String> = columns.iter().map(|c| c.to_sql(dialect)).collect();
+                    sql.push_str(&format!(" ({})", names.join(", ")));
+                }
+                sql.push_str(&format!(" AS {}", query.to_sql(dialect)));
+                sql
+            }
+            Statement::InsertSelect { table_name, columns, query } => {
+                let mut sql = format!("INSERT INTO {}", table_name.to_sql(dialect));
+                if let Some(columns) = columns {
+                    let names: Vec<String> = columns.iter().map(|c| c.to_sql(dialect)).collect();
+                    sql.push_str(&format!(" ({})", names.join(", ")));
+                }
+                sql.push_str(&format!(" {}", query.to_sql(dialect)));
+                sql
+            }
+            Statement::Update { table_name, alias, assignments, selection } => {
+                let mut sql = format!("UPDATE {}", table_name.to_sql(dialect));
+                if let Some(alias) = alias {
+                    sql.push_str(&format!(" AS {}", alias.to_sql(dialect)));
+                }
+                let assignments: Vec<String> = assignments
+                    .iter()
+                    .map(|(column, value)| format!("{} = {}", column.to_sql(dialect), value.to_sql(dialect)))
+                    .collect();
+                sql.push_str(&format!(" SET {}", assignments.join(", ")));
+                if let Some(selection) = selection {
+                    sql.push_str(&format!(" WHERE {}", selection.to_sql(dialect)));
+                }
+                sql
+            }
+            Statement::Delete { table_name, alias, selection } => {
+                let mut sql = format!("DELETE FROM {}", table_name.to_sql(dialect));
+                if let Some(alias) = alias {
+                    sql.push_str(&format!(" AS {}", alias.to_sql(dialect)));
+                }
+                if let Some(selection) = selection {
+                    sql.push_str(&format!(" WHERE {}", selection.to_sql(dialect)));
+                }
+                sql
+            }
+            Statement::Values { rows } => format!("VALUES {}", value_rows(rows, dialect)),
+            Statement::SetOperation { operator, all, left, right } => {
+                let keyword = match operator {
+                    SetOperator::Union => "UNION",
+                    SetOperator::Intersect => "INTERSECT",
+                    SetOperator::Except => "EXCEPT",
+                };
+                // Operands are parenthesized where they would otherwise
+                // group differently: set operations are left-associative
+                // and INTERSECT binds tighter than UNION and EXCEPT
+                let left_needs_parens = match &**left {
+                    Statement::SetOperation { operator: inner, .. } => {
+                        *operator == SetOperator::Intersect && *inner != SetOperator::Intersect
+                    }
+                    Statement::With { .. } => true,
+                    _ => false,
+                };
+                let right_needs_parens = matches!(&**right, Statement::SetOperation { .. } | Statement::With { .. });
+                let operand = |query: &Statement, parens: bool| {
+                    if parens {
+                        format!("({})", query.to_sql(dialect))
+                    } else {
+                        query.to_sql(dialect)
+                    }
+                };
+                format!(
+                    "{} {}{} {}",
+                    operand(left, left_needs_parens),
+                    keyword,
+                    if *all { " ALL" } else { "" },
+                    operand(right, right_needs_parens)
+                )
+            }
+            Statement::With { recursive, ctes, body } => {
+                let ctes: Vec<String> = ctes
+                    .iter()
+                    .map(|cte| {
+                        let mut sql = cte.name.to_sql(dialect);
+                        if !cte.columns.is_empty() {
+                            sql.push_str(&format!("({})", ident_list(&cte.columns, dialect)));
+                        }
+                        format!("{} AS ({})", sql, cte.query.to_sql(dialect))
+                    })
+                    .collect();
+                format!(
+                    "WITH {}{} {}",
+                    if *recursive { "RECURSIVE " } else { "" },
+                    ctes.join(", "),
+                    body.to_sql(dialect)
+                )
+            }
+            Statement::Grant { privileges, object, grantees, with_grant_option } => format!(
+                "GRANT {} ON {} TO {}{}",
+                privilege_list(privileges, dialect),
+                object.to_sql(dialect),
+                quote_list(grantees, dialect),
+                if *with_grant_option { " WITH GRANT OPTION" } else { "" }
+            ),
+            Statement::Revoke { privileges, object, grantees } => format!(
+                "REVOKE {} ON {} FROM {}",
+                privilege_list(privileges, dialect),
+                object.to_sql(dialect),
+                quote_list(grantees, dialect)
+            ),
+            Statement::GrantRole { roles, grantees, with_admin_option } => format!(
+                "GRANT {} TO {}{}",
+                quote_list(roles, dialect),
+                quote_list(grantees, dialect),
+                if *with_admin_option { " WITH ADMIN OPTION" } else { "" }
+            ),
+            Statement::RevokeRole { roles, grantees } => {
+                format!("REVOKE {} FROM {}", quote_list(roles, dialect), quote_list(grantees, dialect))
+            }
+            Statement::CreateFunction { or_replace, procedure, name, parameters, returns, language, body } => {
+                let parameters: Vec<String> = parameters
+                    .iter()
+                    .map(|parameter| match &parameter.name {
+                        Some(name) => format!("{} {}", dialect.quote_identifier(name), parameter.data_type),
+                        None => parameter.data_type.to_string(),
+                    })
+                    .collect();
+                let mut sql = format!(
+                    "CREATE {}{} {}({})",
+                    if *or_replace { "OR REPLACE " } else { "" },
+                    if *procedure { "PROCEDURE" } else { "FUNCTION" },
+                    dialect.quote_identifier(name),
+                    parameters.join(", ")
+                );
+                if let Some(returns) = returns {
+                    sql.push_str(&format!(" RETURNS {}", returns));
+                }
+                // Dollar quotes keep the body readable unless it contains them
+                if body.contains("$$") {
+                    sql.push_str(&format!(" AS {}", string_literal(body)));
+                } else {
+                    sql.push_str(&format!(" AS $${}$$", body));
+                }
+                if let Some(language) = language {
+                    sql.push_str(&format!(" LANGUAGE {}", dialect.quote_identifier(language)));
+                }
+                sql
+            }
+            Statement::CreateRole { role_name, options } => {
+                format!("CREATE ROLE {}{}", dialect.quote_identifier(role_name), role_options(options, dialect))
+            }
+            Statement::CreateTrigger {
+                name,
+                timing,
+                events,
+                update_columns,
+                table_name,
+                for_each_row,
+                condition,
+                body,
+            } => {
+                let events: Vec<String> = events
+                    .iter()
+                    .map(|event| match event {
+                        TriggerEvent::Update if !update_columns.is_empty() => {
+                            let columns: Vec<String> = update_columns.iter().map(|c| c.to_sql(dialect)).collect();
+                            format!("UPDATE OF {}", columns.join(", "))
+                        }
+                        event => event.to_string(),
+                    })
+                    .collect();
+                let mut sql = format!(
+                    "CREATE TRIGGER {} {} {} ON {} FOR EACH {}",
+                    dialect.quote_identifier(name),
+                    timing,
+                    events.join(" OR "),
+                    table_name.to_sql(dialect),
+                    if *for_each_row { "ROW" } else { "STATEMENT" }
+                );
+                if let Some(condition) = condition {
+                    sql.push_str(&format!(" WHEN ({})", condition.to_sql(dialect)));
+                }
+                sql.push(' ');
+                sql.push_str(&match body {
+                    TriggerBody::Execute { procedure, function, args } => format!(
+                        "EXECUTE {} {}({})",
+                        if *procedure { "PROCEDURE" } else { "FUNCTION" },
+                        dialect.quote_identifier(function),
+                        expression_list(args, dialect)
+                    ),
+                    TriggerBody::Block(text) => text.clone(),
+                    TriggerBody::Statement(statement) => statement.to_sql(dialect),
+                });
+                sql
+            }
+            Statement::AlterRole { role_name, options } => {
+                format!("ALTER ROLE {}{}", dialect.quote_identifier(role_name), role_options(options, dialect))
+            }
+            Statement::Merge { target, target_alias, source, source_alias, on, clauses } => {
+                let alias = |alias: &Option<Ident>| match alias {
+                    Some(alias) => format!(" AS {}", alias.to_sql(dialect)),
+                    None => String::new(),
+                };
+                let source = match source {
+                    MergeSource::Table(name) => name.to_sql(dialect),
+                    MergeSource::Query(query) => format!("({})", query.to_sql(dialect)),
+                };
+                let mut sql = format!(
+                    "MERGE INTO {}{} USING {}{} ON {}",
+                    target.to_sql(dialect),
+                    alias(target_alias),
+                    source,
+                    alias(source_alias),
+                    on.to_sql(dialect)
+                );
+                for clause in clauses {
+                    sql.push(' ');
+                    sql.push_str(&clause.to_sql(dialect));
+                }
+                sql
+            }
+            Statement::Copy { table_name, columns, from, target, options } => {
+                let mut sql = format!("COPY {}", table_name.to_sql(dialect));
+                if let Some(columns) = columns {
+                    sql.push_str(&format!(" ({})", ident_list(columns, dialect)));
+                }
+                sql.push_str(if *from { " FROM " } else { " TO " });
+                sql.push_str(&match target {
+                    CopyTarget::File(path) => string_literal(path),
+                    CopyTarget::Stdin => "STDIN".to_string(),
+                    CopyTarget::Stdout => "STDOUT".to_string(),
+                });
+                if !options.is_empty() {
+                    let options: Vec<String> = options
+                        .iter()
+                        .map(|option| match &option.value {
+                            Some(value) => format!("{} {}", option.name, value.to_sql(dialect)),
+                            None => option.name.clone(),
+                        })
+                        .collect();
+                    sql.push_str(&format!(" WITH ({})", options.join(", ")));
+                }
+                sql
+            }
+            Statement::LoadData { local, file, duplicates, table_name, format, ignore_lines, columns } => {
+                let mut sql = format!("LOAD DATA {}INFILE {}", if *local { "LOCAL " } else { "" }, string_literal(file));
+                match duplicates {
+                    Some(DuplicateHandling::Replace) => sql.push_str(" REPLACE"),
+                    Some(DuplicateHandling::Ignore) => sql.push_str(" IGNORE"),
+                    None => {}
+                }
+                sql.push_str(&format!(" INTO TABLE {}", table_name.to_sql(dialect)));
+                sql.push_str(&format.to_sql());
+                if let Some(n) = ignore_lines {
+                    sql.push_str(&format!(" IGNORE {} LINES", n));
+                }
+                if let Some(columns) = columns {
+                    sql.push_str(&format!(" ({})", ident_list(columns, dialect)));
+                }
+                sql
+            }
+            Statement::SetVariable { scope, name, value } => {
+                let scope = match scope {
+                    Some(SetScope::Session) => "SESSION ",
+                    Some(SetScope::Local) => "LOCAL ",
+                    Some(SetScope::Global) => "GLOBAL ",
+                    None => "",
+                };
+                let value: Vec<String> = value
+                    .iter()
+                    .map(|v| match v {
+                        Expression::Identifier(word) if word == "ON" => word.value.clone(),
+                        other => other.to_sql(dialect),
+                    })
+                    .collect();
+                match name.as_str() {
+                    "NAMES" | "TIME ZONE" => format!("SET {}{} {}", scope, name, value.join(", ")),
+                    _ => {
+                        let name: Vec<String> = name.split('.').map(|part| dialect.quote_identifier(part)).collect();
+                        format!("SET {}{} = {}", scope, name.join("."), value.join(", "))
+                    }
+                }
+            }
+            Statement::Prepare { name, parameter_types, statement } => {
+                let mut sql = format!("PREPARE {}", dialect.quote_identifier(name));
+                if !parameter_types.is_empty() {
+                    let types: Vec<String> = parameter_types.iter().map(|t| t.to_string()).collect();
+                    sql.push_str(&format!(" ({})", types.join(", ")));
+                }
+                sql.push_str(&format!(" AS {}", statement.to_sql(dialect)));
+                sql
+            }
+            Statement::Explain { analyze: true, statement } => format!("EXPLAIN ANALYZE {}", statement.to_sql(dialect)),
+            Statement::Explain { analyze: false, statement } => format!("EXPLAIN {}", statement.to_sql(dialect)),
+            Statement::Execute { name, parameters } => {
+                let mut sql = format!("EXECUTE {}", dialect.quote_identifier(name));
+                if !parameters.is_empty() {
+                    let values: Vec<String> = parameters.iter().map(|v| v.to_sql(dialect)).collect();
+                    sql.push_str(&format!(" ({})", values.join(", ")));
+                }
+                sql
+            }
+            Statement::Deallocate { name: Some(name) } => format!("DEALLOCATE {}", dialect.quote_identifier(name)),
+            Statement::Deallocate { name: None } => "DEALLOCATE ALL".to_string(),
+            // BEGIN alone isn't standard, and opens a block in T-SQL
+            Statement::Transaction { command: TransactionCommand::Begin } => match dialect {
+                Dialect::Ansi => "START TRANSACTION".to_string(),
+                Dialect::SqlServer => "BEGIN TRANSACTION".to_string(),
+                _ => "BEGIN".to_string(),
+            },
+            Statement::Transaction { .. } => self.kind().to_string(),
+            Statement::Unparsed { raw_sql, .. } => raw_sql.clone(),
+            Statement::Custom(statement) => statement.to_sql(dialect),
+        }
+    }
+}
+
+impl Hint {
+    /// Prints the hint as it appears inside a hint comment, e.g. `INDEX(t idx)`.
+    pub fn to_sql(&self) -> String {
+        if self.args.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}({})", self.name, self.args.join(" "))
+        }
+    }
+}
+
+impl TableFactor {
+    /// Prints the item as it appears after FROM, with its alias.
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        let (mut sql, alias) = match self {
+            TableFactor::Table { name, alias, .. } => (name.to_sql(dialect), alias),
+            TableFactor::AsOf { name, version, alias } => {
+                (format!("{} AS OF VERSION {}", name.to_sql(dialect), version), alias)
+            }
+            TableFactor::Derived { lateral, subquery, alias } => {
+                (format!("{}({})", lateral_prefix(*lateral), subquery.to_sql(dialect)), alias)
+            }
+            TableFactor::Function { lateral, name, args, alias } => {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_sql(dialect)).collect();
+                (format!("{}{}({})", lateral_prefix(*lateral), dialect.quote_identifier(name), args.join(", ")), alias)
+            }
+            TableFactor::Pivot { table, aggregate, column, values, alias } => {
+                let sql = format!(
+                    "{} PIVOT ({} FOR {} IN ({}))",
+                    table.to_sql(dialect),
+                    aggregate.to_sql(dialect),
+                    column.to_sql(dialect),
+                    expression_list(values, dialect)
+                );
+                (sql, alias)
+            }
+            TableFactor::Unpivot { table, value, name, columns, alias } => {
+                let sql = format!(
+                    "{} UNPIVOT ({} FOR {} IN ({}))",
+                    table.to_sql(dialect),
+                    value.to_sql(dialect),
+                    name.to_sql(dialect),
+                    ident_list(columns, dialect)
+                );
+                (sql, alias)
+            }
+        };
+        if let Some(alias) = alias {
+            sql.push_str(&format!(" AS {}", alias.name.to_sql(dialect)));
+            if !alias.columns.is_empty() {
+                sql.push_str(&format!("({})", ident_list(&alias.columns, dialect)));
+            }
+        }
+        if let TableFactor::Table { sample: Some(sample), .. } = self {
+            sql.push_str(&format!(" TABLESAMPLE {} ({})", sample.method, sample.percent.to_sql(dialect)));
+            if let Some(seed) = &sample.repeatable {
+                sql.push_str(&format!(" REPEATABLE ({})", seed.to_sql(dialect)));
+            }
+        }
+        sql
+    }
+}
+
+impl Join {
+    /// Prints the join as it follows the previous FROM item, starting with
+    /// a space.
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        let keyword = match self.kind {
+            JoinKind::Inner => "JOIN",
+            JoinKind::Left => "LEFT JOIN",
+            JoinKind::Right => "RIGHT JOIN",
+            JoinKind::Full => "FULL JOIN",
+            JoinKind::Cross => "CROSS JOIN",
+        };
+        let mut sql = format!(" {} {}", keyword, self.relation.to_sql(dialect));
+        if let Some(on) = &self.on {
+            sql.push_str(&format!(" ON {}", on.to_sql(dialect)));
+        }
+        sql
+    }
+}
+
+impl GroupingElement {
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        let list = |exprs: &[Expression]| expression_list(exprs, dialect);
+        match self {
+            GroupingElement::Expression(expr) => expr.to_sql(dialect),
+            GroupingElement::Rollup(exprs) => format!("ROLLUP({})", list(exprs)),
+            GroupingElement::Cube(exprs) => format!("CUBE({})", list(exprs)),
+            GroupingElement::GroupingSets(sets) => {
+                let sets: Vec<String> = sets.iter().map(|set| format!("({})", list(set))).collect();
+                format!("GROUPING SETS ({})", sets.join(", "))
+            }
+        }
+    }
+}
+
+impl OrderByExpr {
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        match self.asc {
+            Some(true) => format!("{} ASC", self.expr.to_sql(dialect)),
+            Some(false) => format!("{} DESC", self.expr.to_sql(dialect)),
+            None => self.expr.to_sql(dialect),
+        }
+    }
+}
+
+impl LockingClause {
+    /// Prints the clause starting with a space, e.g. ` FOR UPDATE NOWAIT`.
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        let strength = match self.strength {
+            LockStrength::Update => "UPDATE",
+            LockStrength::NoKeyUpdate => "NO KEY UPDATE",
+            LockStrength::Share => "SHARE",
+            LockStrength::KeyShare => "KEY SHARE",
+        };
+        let mut sql = format!(" FOR {}", strength);
+        if !self.of.is_empty() {
+            sql.push_str(&format!(" OF {}", ident_list(&self.of, dialect)));
+        }
+        match self.wait {
+            Some(LockWait::NoWait) => sql.push_str(" NOWAIT"),
+            Some(LockWait::SkipLocked) => sql.push_str(" SKIP LOCKED"),
+            None => {}
+        }
+        sql
+    }
+}
+
+fn lateral_prefix(lateral: bool) -> &'static str {
+    if lateral {
+        "LATERAL "
+    } else {
+        ""
+    }
+}
+
+impl MergeClause {
+    /// Prints the clause as it appears in a MERGE, starting with WHEN.
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        let mut sql = String::from(if self.matched { "WHEN MATCHED" } else { "WHEN NOT MATCHED" });
+        if let Some(condition) = &self.condition {
+            sql.push_str(&format!(" AND {}", condition.to_sql(dialect)));
+        }
+        sql.push_str(" THEN ");
+        match &self.action {
+            MergeAction::Update(assignments) => {
+                let assignments: Vec<String> = assignments
+                    .iter()
+                    .map(|(column, value)| format!("{} = {}", column.to_sql(dialect), value.to_sql(dialect)))
+                    .collect();
+                sql.push_str(&format!("UPDATE SET {}", assignments.join(", ")));
+            }
+            MergeAction::Delete => sql.push_str("DELETE"),
+            MergeAction::Insert { columns, values } => {
+                sql.push_str("INSERT");
+                if let Some(columns) = columns {
+                    sql.push_str(&format!(" ({})", ident_list(columns, dialect)));
+                }
+                let values: Vec<String> = values.iter().map(|v| v.to_sql(dialect)).collect();
+                sql.push_str(&format!(" VALUES ({})", values.join(", ")));
+            }
+        }
+        sql
+    }
+}
+
+impl LoadDataFormat {
+    /// The FIELDS and LINES clauses, each preceded by a space; empty when
+    /// nothing is set.
+    pub fn to_sql(&self) -> String {
+        let mut sql = String::new();
+        let mut fields = Vec::new();
+        if let Some(s) = &self.fields_terminated_by {
+            fields.push(format!("TERMINATED BY {}", string_literal(s)));
+        }
+        if let Some(s) = &self.fields_enclosed_by {
+            let optionally = if self.optionally_enclosed { "OPTIONALLY " } else { "" };
+            fields.push(format!("{}ENCLOSED BY {}", optionally, string_literal(s)));
+        }
+        if let Some(s) = &self.fields_escaped_by {
+            fields.push(format!("ESCAPED BY {}", string_literal(s)));
+        }
+        if !fields.is_empty() {
+            sql.push_str(&format!(" FIELDS {}", fields.join(" ")));
+        }
+
+        let mut lines = Vec::new();
+        if let Some(s) = &self.lines_starting_by {
+            lines.push(format!("STARTING BY {}", string_literal(s)));
+        }
+        if let Some(s) = &self.lines_terminated_by {
+            lines.push(format!("TERMINATED BY {}", string_literal(s)));
+        }
+        if !lines.is_empty() {
+            sql.push_str(&format!(" LINES {}", lines.join(" ")));
+        }
+        sql
+    }
+}
+
+/// The text of an `E'...'` string that reads back as `text`.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for ch in text.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '\'' => escaped.push_str("\\'"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            ch if ch.is_control() => escaped.push_str(&format!("\\u{:04X}", ch as u32)),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn string_literal(s: &str) -> String {
+    Expression::String(s.to_string()).to_sql(Dialect::Generic)
+}
+
+/// `(a, b), (c, d)`
+fn value_rows(rows: &[Vec<Expression>], dialect: Dialect) -> String {
+    rows.iter().map(|row| format!("({})", expression_list(row, dialect))).collect::<Vec<_>>().join(", ")
+}
+
+fn expression_list(exprs: &[Expression], dialect: Dialect) -> String {
+    exprs.iter().map(|e| e.to_sql(dialect)).collect::<Vec<_>>().join(", ")
+}
+
+fn order_by_list(items: &[OrderByExpr], dialect: Dialect) -> String {
+    items.iter().map(|item| item.to_sql(dialect)).collect::<Vec<_>>().join(", ")
+}
+
+fn quote_list(names: &[String], dialect: Dialect) -> String {
+    names.iter().map(|n| dialect.quote_identifier(n)).collect::<Vec<_>>().join(", ")
+}
+
+fn ident_list(names: &[Ident], dialect: Dialect) -> String {
+    names.iter().map(|n| n.to_sql(dialect)).collect::<Vec<_>>().join(", ")
+}
+
+fn privilege_list(privileges: &[GrantedPrivilege], dialect: Dialect) -> String {
+    let privilege = |granted: &GrantedPrivilege| match granted.columns.as_slice() {
+        [] => granted.privilege.to_string(),
+        columns => format!("{} ({})", granted.privilege, ident_list(columns, dialect)),
+    };
+    privileges.iter().map(privilege).collect::<Vec<_>>().join(", ")
+}
+
+impl GrantObject {
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        match self {
+            GrantObject::Table(name) => name.to_sql(dialect),
+            GrantObject::Sequence(name) => format!("SEQUENCE {}", name.to_sql(dialect)),
+            GrantObject::Schema(name) => format!("SCHEMA {}", name.to_sql(dialect)),
+            GrantObject::Function(name) => format!("FUNCTION {}", name.to_sql(dialect)),
+        }
+    }
+}
+
+/// The options of CREATE/ALTER ROLE, each preceded by a space.
+fn role_options(options: &[RoleOption], dialect: Dialect) -> String {
+    options
+        .iter()
+        .map(|option| {
+            let flag = |set: bool, name: &str| format!(" {}{}", if set { "" } else { "NO" }, name);
+            match option {
+                RoleOption::Login(set) => flag(*set, "LOGIN"),
+                RoleOption::Superuser(set) => flag(*set, "SUPERUSER"),
+                RoleOption::CreateDb(set) => flag(*set, "CREATEDB"),
+                RoleOption::CreateRole(set) => flag(*set, "CREATEROLE"),
+                RoleOption::Password(expr) => format!(" PASSWORD {}", expr.to_sql(dialect)),
+            }
+        })
+        .collect()
+}
+
+impl fmt::Display for Privilege {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Privilege::All => write!(f, "ALL PRIVILEGES"),
+            Privilege::Select => write!(f, "SELECT"),
+            Privilege::Insert => write!(f, "INSERT"),
+            Privilege::Update => write!(f, "UPDATE"),
+            Privilege::Delete => write!(f, "DELETE"),
+            Privilege::Truncate => write!(f, "TRUNCATE"),
+            Privilege::References => write!(f, "REFERENCES"),
+            Privilege::Trigger => write!(f, "TRIGGER"),
+            Privilege::Usage => write!(f, "USAGE"),
+            Privilege::Execute => write!(f, "EXECUTE"),
+        }
+    }
+}
+
+impl fmt::Display for TriggerTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TriggerTiming::Before => write!(f, "BEFORE"),
+            TriggerTiming::After => write!(f, "AFTER"),
+            TriggerTiming::InsteadOf => write!(f, "INSTEAD OF"),
+        }
+    }
+}
+
+impl fmt::Display for TriggerEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TriggerEvent::Insert => write!(f, "INSERT"),
+            TriggerEvent::Update => write!(f, "UPDATE"),
+            TriggerEvent::Delete => write!(f, "DELETE"),
+            TriggerEvent::Truncate => write!(f, "TRUNCATE"),
+        }
+    }
+}
+
+impl TableColumn {
+    /// Prints the column definition as it appears inside CREATE TABLE.
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        let mut sql = format!("{} {}", self.column_name.to_sql(dialect), self.column_type);
+        for constraint in &self.constraints {
+            match constraint {
+                Constraint::NotNull => sql.push_str(" NOT NULL"),
+                Constraint::PrimaryKey => sql.push_str(" PRIMARY KEY"),
+                Constraint::Check(expr) => sql.push_str(&format!(" CHECK ({})", expr.to_sql(dialect))),
+                Constraint::Collate(name) => sql.push_str(&format!(" COLLATE {}", name.to_sql(dialect))),
+            }
+        }
+        sql
+    }
+}
+
+impl fmt::Display for DBType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DBType::Int => write!(f, "INT"),
+            DBType::Varchar(length) => write!(f, "VARCHAR({})", length),
+            DBType::Bool => write!(f, "BOOL"),
+            DBType::Date => write!(f, "DATE"),
+            DBType::Time => write!(f, "TIME"),
+            DBType::Timestamp => write!(f, "TIMESTAMP"),
+            DBType::Decimal(precision, scale) => write!(f, "DECIMAL({},{})", precision, scale),
+        }
+    }
+}
+
+impl fmt::Display for RoutineType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoutineType::Known(data_type) => write!(f, "{}", data_type),
+            RoutineType::Named(name) => f.write_str(name),
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_sql(Dialect::Generic))
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_sql(Dialect::Generic))
+    }
+}
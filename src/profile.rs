@@ -0,0 +1,99 @@
+// profile.rs — strict and lenient parsing profiles
+// Author: Sreerag Devadasan
+//
+// A profile narrows or widens the syntax the dialect of a `ParserOptions`
+// accepts. By default a parse follows the dialect. `Strict` is for teams
+// keeping their SQL portable: the extensions only some dialects have
+// (ILIKE, PIVOT, QUALIFY and `* EXCLUDE`, `[bracketed]` names) fail to
+// parse, and `nonstandard` warns about the constructs every dialect here
+// accepts but ANSI SQL doesn't, such as LIMIT and backquoted names.
+// `Lenient` accepts what any of the dialects does, whichever one is set;
+// the quoting of `"..."` still follows the dialect, as the two readings
+// can't both hold.
+
+use crate::prelude::*;
+use crate::ast::StringPrefix;
+use crate::dialect::Dialect;
+use crate::ident::QuoteStyle;
+use crate::span::Span;
+use crate::tokenizer::{Keyword, Token, Tokenizer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Profile {
+    /// What the dialect accepts.
+    #[default]
+    Dialect,
+    /// ANSI SQL, warning about nonstandard constructs.
+    Strict,
+    /// What any supported dialect accepts.
+    Lenient,
+}
+
+impl Profile {
+    pub const ALL: [Profile; 3] = [Profile::Dialect, Profile::Strict, Profile::Lenient];
+
+    /// Looks a profile up by its name (case-insensitive).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Profile::Dialect => "dialect",
+            Profile::Strict => "strict",
+            Profile::Lenient => "lenient",
+        }
+    }
+
+    /// Whether a parse accepts an extension the dialect does or doesn't
+    /// have, as `in_dialect` says.
+    pub fn allows(&self, in_dialect: bool) -> bool {
+        match self {
+            Profile::Dialect => in_dialect,
+            Profile::Strict => false,
+            Profile::Lenient => true,
+        }
+    }
+}
+
+/// A construct outside standard SQL, and where it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// A short name for the construct, such as `LIMIT`.
+    pub construct: &'static str,
+    pub message: &'static str,
+    pub span: Span,
+}
+
+/// The constructs of `sql`, read with the quoting rules of `dialect`, that
+/// standard SQL doesn't have.
+pub fn nonstandard(sql: &str, dialect: Dialect) -> Vec<Warning> {
+    nonstandard_tokens(sql, Tokenizer::with_dialect(sql, dialect))
+}
+
+/// Like `nonstandard`, reading `sql` with an already configured tokenizer.
+pub(crate) fn nonstandard_tokens(sql: &str, mut tokenizer: Tokenizer) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    loop {
+        let (token, span) = tokenizer.next_spanned();
+        let (construct, message) = match &token {
+            Token::Eof => return warnings,
+            Token::Keyword(Keyword::Limit) => ("LIMIT", "LIMIT isn't standard SQL; use FETCH FIRST n ROWS ONLY"),
+            Token::Keyword(Keyword::Top) => ("TOP", "TOP isn't standard SQL; use FETCH FIRST n ROWS ONLY"),
+            Token::Identifier(name) if name.quote == Some(QuoteStyle::Backtick) => {
+                ("backquoted name", "Backquoted names aren't standard SQL; quote names with \"...\"")
+            }
+            Token::Hint(_) => ("optimizer hint", "Optimizer hints aren't standard SQL"),
+            Token::PrefixedString(StringPrefix::Escape, _) => ("E'...' string", "E'...' strings aren't standard SQL"),
+            Token::String(_) if span.slice(sql).starts_with('$') => {
+                ("dollar-quoted string", "Dollar-quoted strings aren't standard SQL")
+            }
+            Token::String(_) if span.slice(sql).starts_with('"') => (
+                "double-quoted string",
+                "Double-quoted strings aren't standard SQL, where \"...\" is a name; use '...'",
+            ),
+            _ => continue,
+        };
+        warnings.push(Warning { construct, message, span });
+    }
+}
@@ -0,0 +1,323 @@
+// proxy.rs — validates and rewrites queries before they reach a database
+// Author: Sreerag Devadasan
+//
+// A `Proxy` sits between an application and its database. Every statement
+// is parsed with the proxy's session (so its limits and DDL rules apply),
+// checked against a `ProxyPolicy`, optionally rewritten, printed in the
+// session's dialect and handed to an `Upstream`: anything that can run SQL,
+// such as a client of the real database. The in-memory engine is one. A
+// log record goes to the logger for every statement, forwarded or not.
+//
+// The one rewrite so far is a tenant filter: queries on the listed tables
+// only see the rows whose tenant column holds the tenant's value. The
+// condition is AND-ed into WHERE, or into the ON of a LEFT JOIN; statements
+// that could touch other tenants' rows some other way (INSERT, COPY, RIGHT
+// and FULL joins, ...) are rejected rather than forwarded unfiltered.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::ast::{BinaryOperator, Cte, Expression, Ident, Join, JoinKind, Statement, TableFactor};
+use crate::engine::{Database, QueryResult};
+use crate::hash::{fingerprint, AstHash};
+use crate::json;
+use crate::metrics::{timed_parse, timed_query};
+use crate::session::ParserSession;
+use crate::tokenizer::ParseError;
+
+/// A database the proxy forwards statements to.
+pub trait Upstream {
+    fn execute(&mut self, sql: &str) -> Result<QueryResult, String>;
+}
+
+impl Upstream for Database {
+    fn execute(&mut self, sql: &str) -> Result<QueryResult, String> {
+        self.execute_sql(sql).map_err(|e| e.to_string())
+    }
+}
+
+/// Restricts queries on `tables` to the rows whose `column` equals `value`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TenantFilter {
+    pub column: String,
+    pub value: Expression,
+    /// Matched case-insensitively.
+    pub tables: Vec<String>,
+}
+
+/// What the proxy accepts and how it rewrites it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProxyPolicy {
+    /// Statement kinds (as in `Statement::kind`) allowed through; empty
+    /// allows every kind.
+    pub allowed_kinds: Vec<&'static str>,
+    /// Tables no statement may touch, matched case-insensitively.
+    pub denied_tables: Vec<String>,
+    pub tenant_filter: Option<TenantFilter>,
+}
+
+/// Why a statement wasn't executed.
+#[derive(Debug)]
+pub enum ProxyError {
+    Parse(ParseError),
+    /// The policy doesn't allow the statement.
+    Rejected(String),
+    /// The upstream database failed to run it.
+    Upstream(String),
+}
+
+impl fmt::Display for ProxyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProxyError::Parse(e) => write!(f, "{}", e),
+            ProxyError::Rejected(reason) => write!(f, "Rejected: {}", reason),
+            ProxyError::Upstream(e) => write!(f, "Upstream error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProxyError {}
+
+impl From<ParseError> for ProxyError {
+    fn from(e: ParseError) -> Self {
+        ProxyError::Parse(e)
+    }
+}
+
+/// What the proxy did with one statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryLog {
+    /// The statement as the client sent it.
+    pub sql: String,
+    /// `None` when it didn't parse.
+    pub kind: Option<&'static str>,
+    pub tables: Vec<String>,
+    /// Shared by statements that differ only in their literal values.
+    pub fingerprint: Option<AstHash>,
+    /// The SQL sent upstream, when the statement got that far.
+    pub forwarded: Option<String>,
+    /// Rows returned by the upstream database.
+    pub rows: Option<usize>,
+    pub error: Option<String>,
+    pub elapsed: Duration,
+}
+
+impl QueryLog {
+    pub fn to_json(&self) -> String {
+        let optional = |value: &Option<String>| value.as_deref().map_or("null".to_string(), json::string);
+        json::object([
+            ("sql", json::string(&self.sql)),
+            ("kind", self.kind.map_or("null".to_string(), json::string)),
+            ("tables", json::array(self.tables.iter().map(|table| json::string(table)))),
+            ("fingerprint", self.fingerprint.map_or("null".to_string(), |hash| json::string(&hash.to_string()))),
+            ("forwarded", optional(&self.forwarded)),
+            ("rows", self.rows.map_or("null".to_string(), |n| n.to_string())),
+            ("error", optional(&self.error)),
+            ("micros", self.elapsed.as_micros().to_string()),
+        ])
+    }
+}
+
+/// Receives the log record of every statement.
+pub type Logger = Box<dyn FnMut(&QueryLog) + Send>;
+
+/// Checks statements against a policy and forwards the approved ones.
+pub struct Proxy<U> {
+    session: ParserSession,
+    policy: ProxyPolicy,
+    upstream: U,
+    logger: Option<Logger>,
+}
+
+impl<U: Upstream> Proxy<U> {
+    pub fn new(session: ParserSession, policy: ProxyPolicy, upstream: U) -> Self {
+        Self { session, policy, upstream, logger: None }
+    }
+
+    /// Calls `logger` with the log record of every statement.
+    pub fn with_logger(mut self, logger: impl FnMut(&QueryLog) + Send + 'static) -> Self {
+        self.logger = Some(Box::new(logger));
+        self
+    }
+
+    pub fn upstream(&self) -> &U {
+        &self.upstream
+    }
+
+    /// Parses, checks and rewrites one statement, then runs it upstream.
+    pub fn execute(&mut self, sql: &str) -> Result<QueryResult, ProxyError> {
+        let started = Instant::now();
+        let mut log = QueryLog {
+            sql: sql.to_string(),
+            kind: None,
+            tables: Vec::new(),
+            fingerprint: None,
+            forwarded: None,
+            rows: None,
+            error: None,
+            elapsed: Duration::ZERO,
+        };
+        let result = self.run(sql, &mut log);
+        log.error = result.as_ref().err().map(ProxyError::to_string);
+        log.rows = result.as_ref().ok().map(|result| result.rows.len());
+        log.elapsed = started.elapsed();
+        if let Some(logger) = &mut self.logger {
+            logger(&log);
+        }
+        result
+    }
+
+    fn run(&mut self, sql: &str, log: &mut QueryLog) -> Result<QueryResult, ProxyError> {
+        let statement = timed_parse(|| self.session.parse(sql))?;
+        log.kind = Some(statement.kind());
+        log.tables = statement.tables().into_iter().map(str::to_string).collect();
+        log.fingerprint = Some(fingerprint(&statement));
+
+        let statement = self.check(statement).map_err(ProxyError::Rejected)?;
+        let forwarded = statement.to_sql(self.session.options().dialect);
+        log.forwarded = Some(forwarded.clone());
+        timed_query(|| self.upstream.execute(&forwarded)).map_err(ProxyError::Upstream)
+    }
+
+    /// Applies the policy: the statement to forward, or why it can't be.
+    fn check(&self, statement: Statement) -> Result<Statement, String> {
+        let policy = &self.policy;
+        if !policy.allowed_kinds.is_empty() && !policy.allowed_kinds.contains(&statement.kind()) {
+            return Err(format!("{} statements are not allowed", statement.kind()));
+        }
+        // Nothing is known about what an unparsed statement touches
+        let restricted = !policy.denied_tables.is_empty() || policy.tenant_filter.is_some();
+        if restricted && matches!(statement, Statement::Unparsed { .. }) {
+            return Err("statements that don't parse can't be checked".to_string());
+        }
+        let denied = |name: &str| policy.denied_tables.iter().any(|table| table.eq_ignore_ascii_case(name));
+        if let Some(table) = statement.tables().into_iter().find(|name| denied(name)) {
+            return Err(format!("table {} is not accessible", table));
+        }
+        match &policy.tenant_filter {
+            Some(filter) => filter.apply(&statement),
+            None => Ok(statement),
+        }
+    }
+}
+
+impl TenantFilter {
+    fn covers(&self, name: &str) -> bool {
+        self.tables.iter().any(|table| table.eq_ignore_ascii_case(name))
+    }
+
+    /// `statement` with the filter added to every query on a covered table.
+    pub fn apply(&self, statement: &Statement) -> Result<Statement, String> {
+        match statement {
+            Statement::Select { .. } => self.filter_select(statement),
+            Statement::SetOperation { operator, all, left, right } => Ok(Statement::SetOperation {
+                operator: *operator,
+                all: *all,
+                left: Box::new(self.apply(left)?),
+                right: Box::new(self.apply(right)?),
+            }),
+            Statement::With { recursive, ctes, body } => Ok(Statement::With {
+                recursive: *recursive,
+                ctes: ctes
+                    .iter()
+                    .map(|cte| Ok(Cte { query: Box::new(self.apply(&cte.query)?), ..cte.clone() }))
+                    .collect::<Result<_, String>>()?,
+                body: Box::new(self.apply(body)?),
+            }),
+            other => match other.tables().into_iter().find(|name| self.covers(name)) {
+                Some(table) => Err(format!("{} on {} can't be restricted to the tenant", other.kind(), table)),
+                None => Ok(other.clone()),
+            },
+        }
+    }
+
+    fn filter_select(&self, select: &Statement) -> Result<Statement, String> {
+        let Statement::Select { from, joins, selection, .. } = select else {
+            return Ok(select.clone());
+        };
+        let qualify = !joins.is_empty();
+        let mut conditions = Vec::new();
+        let from = self.filter_factor(from, qualify, &mut conditions)?;
+
+        let mut filtered_joins = Vec::new();
+        for join in joins {
+            let mut join_conditions = Vec::new();
+            let relation = self.filter_factor(&join.relation, qualify, &mut join_conditions)?;
+            let on = match join.kind {
+                _ if join_conditions.is_empty() => join.on.clone(),
+                JoinKind::Left => join_conditions.into_iter().fold(join.on.clone(), and),
+                JoinKind::Inner | JoinKind::Cross => {
+                    conditions.extend(join_conditions);
+                    join.on.clone()
+                }
+                JoinKind::Right | JoinKind::Full => {
+                    return Err("RIGHT and FULL joins of tenant tables can't be restricted to the tenant".to_string());
+                }
+            };
+            filtered_joins.push(Join { kind: join.kind, relation, on });
+        }
+
+        let mut filtered = select.clone();
+        if let Statement::Select { from: new_from, joins: new_joins, selection: new_selection, .. } = &mut filtered {
+            **new_from = from;
+            *new_joins = filtered_joins;
+            *new_selection = conditions.into_iter().fold(selection.clone(), and);
+        }
+        Ok(filtered)
+    }
+
+    /// The FROM item with its subqueries filtered; pushes the condition for
+    /// a covered table onto `conditions`.
+    fn filter_factor(
+        &self,
+        factor: &TableFactor,
+        qualify: bool,
+        conditions: &mut Vec<Expression>,
+    ) -> Result<TableFactor, String> {
+        match factor {
+            TableFactor::Table { name, alias, .. } | TableFactor::AsOf { name, alias, .. } if self.covers(name) => {
+                let own = Ident::new(self.column.as_str());
+                let column = match (alias, qualify) {
+                    (Some(alias), _) => Expression::CompoundIdentifier(vec![alias.name.clone(), own]),
+                    (None, true) => Expression::CompoundIdentifier(vec![name.clone(), own]),
+                    (None, false) => Expression::Identifier(own),
+                };
+                conditions.push(Expression::BinaryOperation {
+                    left_operand: Box::new(column),
+                    operator: BinaryOperator::Equals,
+                    right_operand: Box::new(self.value.clone()),
+                });
+                Ok(factor.clone())
+            }
+            TableFactor::Derived { lateral, subquery, alias } => Ok(TableFactor::Derived {
+                lateral: *lateral,
+                subquery: Box::new(self.apply(subquery)?),
+                alias: alias.clone(),
+            }),
+            TableFactor::Pivot { table, .. } | TableFactor::Unpivot { table, .. } => {
+                match table.table_name().filter(|name| self.covers(name)) {
+                    Some(name) => Err(format!("PIVOT and UNPIVOT of {} can't be restricted to the tenant", name)),
+                    None => Ok(factor.clone()),
+                }
+            }
+            TableFactor::Table { .. } | TableFactor::AsOf { .. } | TableFactor::Function { .. } => Ok(factor.clone()),
+        }
+    }
+}
+
+/// `existing AND condition`, or just `condition`. An OR on the left is
+/// parenthesized, since the printer relies on the tree's grouping.
+fn and(existing: Option<Expression>, condition: Expression) -> Option<Expression> {
+    let Some(existing) = existing else {
+        return Some(condition);
+    };
+    let left = match existing {
+        expr @ Expression::BinaryOperation { operator: BinaryOperator::Or, .. } => Expression::Grouped(Box::new(expr)),
+        expr => expr,
+    };
+    Some(Expression::BinaryOperation {
+        left_operand: Box::new(left),
+        operator: BinaryOperator::And,
+        right_operand: Box::new(condition),
+    })
+}
@@ -0,0 +1,166 @@
+// query_diff.rs — clause-level diff between two versions of a query
+// Author: Sreerag Devadasan
+//
+// Both queries are parsed and a SELECT is broken into its clauses, each a
+// list of printed items: the select list, FROM item, joins, WHERE
+// conjuncts (AND-ed predicates), GROUP BY elements, ORDER BY items and so
+// on. A reviewer sees "+ WHERE: active = TRUE" instead of a reflowed line
+// of text. Formatting, keyword case and the order of WHERE conjuncts and
+// GROUP BY elements don't count as changes. Other statements are compared
+// as a whole.
+
+use core::fmt;
+
+use crate::prelude::*;
+use crate::ast::{BinaryOperator, Expression, Statement};
+use crate::dialect::Dialect;
+use crate::json;
+use crate::parser::parse_sql_with_dialect;
+use crate::tokenizer::ParseError;
+
+/// One difference between two queries.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryChange {
+    Added { clause: &'static str, sql: String },
+    Removed { clause: &'static str, sql: String },
+    Changed { clause: &'static str, from: String, to: String },
+}
+
+impl QueryChange {
+    pub fn to_json(&self) -> String {
+        match self {
+            QueryChange::Added { clause, sql } => json::object([
+                ("change", json::string("added")),
+                ("clause", json::string(clause)),
+                ("sql", json::string(sql)),
+            ]),
+            QueryChange::Removed { clause, sql } => json::object([
+                ("change", json::string("removed")),
+                ("clause", json::string(clause)),
+                ("sql", json::string(sql)),
+            ]),
+            QueryChange::Changed { clause, from, to } => json::object([
+                ("change", json::string("changed")),
+                ("clause", json::string(clause)),
+                ("from", json::string(from)),
+                ("to", json::string(to)),
+            ]),
+        }
+    }
+}
+
+impl fmt::Display for QueryChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryChange::Added { clause, sql } => write!(f, "+ {}: {}", clause, sql),
+            QueryChange::Removed { clause, sql } => write!(f, "- {}: {}", clause, sql),
+            QueryChange::Changed { clause, from, to } => write!(f, "~ {}: {} → {}", clause, from, to),
+        }
+    }
+}
+
+/// Parses both queries in `dialect` and diffs them; see `diff_statements`.
+pub fn diff(old_sql: &str, new_sql: &str, dialect: Dialect) -> Result<Vec<QueryChange>, ParseError> {
+    let old = parse_sql_with_dialect(old_sql, dialect)?;
+    let new = parse_sql_with_dialect(new_sql, dialect)?;
+    Ok(diff_statements(&old, &new, dialect))
+}
+
+/// The changes that turn `old` into `new`, clause by clause in SELECT
+/// order, with items printed in `dialect`.
+pub fn diff_statements(old: &Statement, new: &Statement, dialect: Dialect) -> Vec<QueryChange> {
+    let (Some(old_clauses), Some(new_clauses)) = (clauses(old, dialect), clauses(new, dialect)) else {
+        let (from, to) = (old.to_sql(dialect), new.to_sql(dialect));
+        return if from == to { Vec::new() } else { vec![QueryChange::Changed { clause: "statement", from, to }] };
+    };
+
+    let mut changes = Vec::new();
+    for ((clause, old_items), (_, new_items)) in old_clauses.into_iter().zip(new_clauses) {
+        if old_items == new_items {
+            continue;
+        }
+        let ordered = !matches!(clause, "WHERE" | "GROUP BY");
+        let mut sorted = (old_items.clone(), new_items.clone());
+        sorted.0.sort();
+        sorted.1.sort();
+        if sorted.0 == sorted.1 {
+            if ordered {
+                changes.push(QueryChange::Changed { clause, from: old_items.join(", "), to: new_items.join(", ") });
+            }
+        } else if let ([from], [to]) = (old_items.as_slice(), new_items.as_slice()) {
+            changes.push(QueryChange::Changed { clause, from: from.clone(), to: to.clone() });
+        } else {
+            for sql in old_items.iter().filter(|item| !new_items.contains(item)) {
+                changes.push(QueryChange::Removed { clause, sql: sql.clone() });
+            }
+            for sql in new_items.iter().filter(|item| !old_items.contains(item)) {
+                changes.push(QueryChange::Added { clause, sql: sql.clone() });
+            }
+        }
+    }
+    changes
+}
+
+/// Encodes a list of changes as a JSON array.
+pub fn to_json(changes: &[QueryChange]) -> String {
+    json::array(changes.iter().map(QueryChange::to_json))
+}
+
+/// The clauses of a SELECT as printed items, always in the same order and
+/// with empty lists for absent clauses; `None` for other statements.
+fn clauses(statement: &Statement, dialect: Dialect) -> Option<Vec<(&'static str, Vec<String>)>> {
+    let Statement::Select {
+        hints,
+        columns,
+        wildcard,
+        from,
+        joins,
+        selection,
+        group_by,
+        qualify,
+        order_by,
+        limit,
+        offset,
+        locking,
+    } = statement
+    else {
+        return None;
+    };
+    let mut select: Vec<String> = columns.iter().map(|item| item.to_sql(dialect)).collect();
+    select.extend(wildcard.exclude.iter().map(|name| format!("EXCLUDE {}", name.to_sql(dialect))));
+    select.extend(
+        wildcard
+            .replace
+            .iter()
+            .map(|(expr, name)| format!("REPLACE {} AS {}", expr.to_sql(dialect), name.to_sql(dialect))),
+    );
+    let mut conjuncts = Vec::new();
+    if let Some(selection) = selection {
+        split_and(selection, &mut conjuncts);
+    }
+
+    Some(vec![
+        ("hint", hints.iter().map(|hint| hint.to_sql()).collect()),
+        ("SELECT", select),
+        ("FROM", vec![from.to_sql(dialect)]),
+        ("JOIN", joins.iter().map(|join| join.to_sql(dialect).trim_start().to_string()).collect()),
+        ("WHERE", conjuncts.iter().map(|expr| expr.to_sql(dialect)).collect()),
+        ("GROUP BY", group_by.iter().map(|element| element.to_sql(dialect)).collect()),
+        ("QUALIFY", qualify.iter().map(|expr| expr.to_sql(dialect)).collect()),
+        ("ORDER BY", order_by.iter().map(|item| item.to_sql(dialect)).collect()),
+        ("LIMIT", limit.iter().map(|n| n.to_string()).collect()),
+        ("OFFSET", offset.iter().map(|n| n.to_string()).collect()),
+        ("locking", locking.iter().map(|clause| clause.to_sql(dialect)).collect()),
+    ])
+}
+
+/// The operands of a chain of ANDs, left to right.
+fn split_and<'a>(expr: &'a Expression, out: &mut Vec<&'a Expression>) {
+    match expr {
+        Expression::BinaryOperation { left_operand, operator: BinaryOperator::And, right_operand } => {
+            split_and(left_operand, out);
+            split_and(right_operand, out);
+        }
+        other => out.push(other),
+    }
+}
@@ -0,0 +1,295 @@
+// querylog.rs — aggregate statistics over database query logs
+// Author: Sreerag Devadasan
+//
+// Reads the statements out of a Postgres server log (`log_statement` /
+// `log_min_duration_statement` lines, with tab-indented continuation
+// lines), a MySQL slow query log, or plain newline-delimited SQL, then
+// groups them by shape: statements with the same fingerprint, i.e. equal
+// apart from their literal values. Each shape gets a count and timings
+// (when the log has them); tables and statement kinds are counted per
+// statement.
+
+use alloc::collections::BTreeMap;
+use core::fmt;
+
+use crate::prelude::*;
+use crate::anonymize::{anonymize, AnonymizeMode};
+use crate::dialect::Dialect;
+use crate::hash::{fingerprint, AstHash};
+use crate::json;
+use crate::parser::parse_sql_with_dialect;
+
+/// The layout of a query log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One statement per line.
+    Plain,
+    Postgres,
+    /// The MySQL slow query log.
+    MySqlSlow,
+}
+
+impl LogFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "plain" => Some(LogFormat::Plain),
+            "postgres" => Some(LogFormat::Postgres),
+            "mysql" => Some(LogFormat::MySqlSlow),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the log's contents.
+    pub fn detect(text: &str) -> Self {
+        if text.lines().any(|line| line.starts_with("# Query_time:")) {
+            LogFormat::MySqlSlow
+        } else if text.lines().any(|line| line.contains("LOG:") && postgres_statement(line).is_some()) {
+            LogFormat::Postgres
+        } else {
+            LogFormat::Plain
+        }
+    }
+}
+
+/// One statement read from a log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    /// 1-based line the statement starts on.
+    pub line: usize,
+    pub sql: String,
+    /// How long the statement ran, in milliseconds, when logged.
+    pub duration_ms: Option<f64>,
+}
+
+/// Splits a log into its statements.
+pub fn read_entries(text: &str, format: LogFormat) -> Vec<LogEntry> {
+    match format {
+        LogFormat::Plain => text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with("--"))
+            .map(|(i, line)| LogEntry { line: i + 1, sql: line.to_string(), duration_ms: None })
+            .collect(),
+        LogFormat::Postgres => read_postgres(text),
+        LogFormat::MySqlSlow => read_mysql_slow(text),
+    }
+}
+
+/// The statement text and duration of a Postgres log line that logs one.
+fn postgres_statement(line: &str) -> Option<(&str, Option<f64>)> {
+    let duration = line
+        .split_once("duration: ")
+        .and_then(|(_, rest)| rest.split_once(" ms"))
+        .and_then(|(number, _)| number.trim().parse().ok());
+    if let Some((_, sql)) = line.split_once("statement: ") {
+        return Some((sql, duration));
+    }
+    // Extended-protocol statements: "execute <unnamed>: SELECT ..."
+    let (_, rest) = line.split_once("execute ")?;
+    let (name, sql) = rest.split_once(": ")?;
+    (!name.contains(' ')).then_some((sql, duration))
+}
+
+fn read_postgres(text: &str) -> Vec<LogEntry> {
+    let mut entries: Vec<LogEntry> = Vec::new();
+    let mut open = false;
+    for (i, line) in text.lines().enumerate() {
+        if line.starts_with('\t') || line.starts_with("    ") {
+            if let Some(entry) = entries.last_mut().filter(|_| open) {
+                entry.sql.push('\n');
+                entry.sql.push_str(line.trim());
+            }
+            continue;
+        }
+        open = false;
+        if !line.contains("LOG:") {
+            continue;
+        }
+        if let Some((sql, duration_ms)) = postgres_statement(line) {
+            entries.push(LogEntry { line: i + 1, sql: sql.trim().to_string(), duration_ms });
+            open = true;
+        }
+    }
+    entries
+}
+
+fn read_mysql_slow(text: &str) -> Vec<LogEntry> {
+    let mut entries = Vec::new();
+    let mut duration_ms = None;
+    let mut pending: Option<LogEntry> = None;
+    for (i, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix("# Query_time:") {
+            let seconds: Option<f64> = rest.split_whitespace().next().and_then(|n| n.parse().ok());
+            duration_ms = seconds.map(|s| s * 1000.0);
+            continue;
+        }
+        let lower = trimmed.to_ascii_lowercase();
+        // Comment headers, per-entry context and the banner of a restart
+        let header = trimmed.is_empty()
+            || trimmed.starts_with('#')
+            || lower.starts_with("set timestamp=")
+            || (lower.starts_with("use ") && pending.is_none())
+            || trimmed.contains(", Version: ")
+            || trimmed.starts_with("Tcp port:")
+            || (trimmed.starts_with("Time") && trimmed.contains("Id Command"));
+        if header && pending.is_none() {
+            continue;
+        }
+        let entry = pending.get_or_insert_with(|| LogEntry { line: i + 1, sql: String::new(), duration_ms });
+        if !entry.sql.is_empty() {
+            entry.sql.push('\n');
+        }
+        entry.sql.push_str(trimmed);
+        if let Some(sql) = entry.sql.strip_suffix(';') {
+            entry.sql = sql.trim_end().to_string();
+            entries.extend(pending.take());
+            duration_ms = None;
+        }
+    }
+    entries.extend(pending);
+    entries
+}
+
+/// Statements of one shape.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryShape {
+    pub fingerprint: AstHash,
+    pub kind: &'static str,
+    /// The first statement of the shape with its literals replaced by
+    /// placeholders.
+    pub example: String,
+    pub count: usize,
+    /// Statements with a logged duration, and their total and longest
+    /// durations in milliseconds.
+    pub timed: usize,
+    pub total_ms: f64,
+    pub max_ms: f64,
+}
+
+impl QueryShape {
+    pub fn mean_ms(&self) -> Option<f64> {
+        (self.timed > 0).then(|| self.total_ms / self.timed as f64)
+    }
+}
+
+/// What a log contains.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogStats {
+    pub statements: usize,
+    /// Lines of the statements that didn't parse.
+    pub failed: Vec<usize>,
+    /// Most frequent first.
+    pub shapes: Vec<QueryShape>,
+    /// Statements touching each table, most frequent first.
+    pub tables: Vec<(String, usize)>,
+    pub kinds: Vec<(&'static str, usize)>,
+}
+
+/// Parses every entry in `dialect` and aggregates the ones that parse.
+pub fn analyze(entries: &[LogEntry], dialect: Dialect) -> LogStats {
+    let mut stats = LogStats { statements: entries.len(), ..Default::default() };
+    let mut shapes: BTreeMap<AstHash, QueryShape> = BTreeMap::new();
+    let mut tables: BTreeMap<String, usize> = BTreeMap::new();
+    let mut kinds: BTreeMap<&'static str, usize> = BTreeMap::new();
+
+    for entry in entries {
+        let Ok(statement) = parse_sql_with_dialect(&entry.sql, dialect) else {
+            stats.failed.push(entry.line);
+            continue;
+        };
+        let shape = shapes.entry(fingerprint(&statement)).or_insert_with_key(|hash| QueryShape {
+            fingerprint: *hash,
+            kind: statement.kind(),
+            example: anonymize(&statement, AnonymizeMode::Placeholders).to_sql(dialect),
+            count: 0,
+            timed: 0,
+            total_ms: 0.0,
+            max_ms: 0.0,
+        });
+        shape.count += 1;
+        if let Some(ms) = entry.duration_ms {
+            shape.timed += 1;
+            shape.total_ms += ms;
+            shape.max_ms = shape.max_ms.max(ms);
+        }
+        for table in statement.tables() {
+            *tables.entry(table.to_string()).or_default() += 1;
+        }
+        *kinds.entry(statement.kind()).or_default() += 1;
+    }
+
+    stats.shapes = shapes.into_values().collect();
+    stats.shapes.sort_by(|a, b| {
+        b.count.cmp(&a.count).then(b.total_ms.total_cmp(&a.total_ms)).then_with(|| a.example.cmp(&b.example))
+    });
+    stats.tables = tables.into_iter().collect();
+    stats.tables.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    stats.kinds = kinds.into_iter().collect();
+    stats.kinds.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    stats
+}
+
+impl LogStats {
+    /// The stats as JSON, listing at most `top` shapes.
+    pub fn to_json(&self, top: usize) -> String {
+        let number = |ms: f64| format!("{:.3}", ms);
+        let shapes = self.shapes.iter().take(top).map(|shape| {
+            json::object([
+                ("fingerprint", json::string(&shape.fingerprint.to_string())),
+                ("kind", json::string(shape.kind)),
+                ("query", json::string(&shape.example)),
+                ("count", shape.count.to_string()),
+                ("total_ms", if shape.timed > 0 { number(shape.total_ms) } else { "null".to_string() }),
+                ("mean_ms", shape.mean_ms().map_or("null".to_string(), number)),
+                ("max_ms", if shape.timed > 0 { number(shape.max_ms) } else { "null".to_string() }),
+            ])
+        });
+        let counts = |items: &mut dyn Iterator<Item = (&str, usize)>| {
+            json::object(items.map(|(name, count)| (name, count.to_string())).collect::<Vec<_>>())
+        };
+        json::object([
+            ("statements", self.statements.to_string()),
+            ("failed", json::array(self.failed.iter().map(|line| line.to_string()))),
+            ("shapes", json::array(shapes)),
+            ("tables", counts(&mut self.tables.iter().map(|(name, count)| (name.as_str(), *count)))),
+            ("kinds", counts(&mut self.kinds.iter().map(|(kind, count)| (*kind, *count)))),
+        ])
+    }
+
+    /// One CSV row per shape, at most `top` of them, after a header row.
+    pub fn to_csv(&self, top: usize) -> String {
+        let mut csv = String::from("fingerprint,kind,count,total_ms,mean_ms,max_ms,query\n");
+        for shape in self.shapes.iter().take(top) {
+            let timing = |ms: f64| if shape.timed > 0 { format!("{:.3}", ms) } else { String::new() };
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},\"{}\"\n",
+                shape.fingerprint,
+                shape.kind,
+                shape.count,
+                timing(shape.total_ms),
+                shape.mean_ms().map_or(String::new(), |ms| format!("{:.3}", ms)),
+                timing(shape.max_ms),
+                shape.example.replace('"', "\"\"")
+            ));
+        }
+        csv
+    }
+}
+
+impl fmt::Display for LogStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} statements, {} failed to parse", self.statements, self.failed.len())?;
+        for shape in &self.shapes {
+            write!(f, "{:>6}×  {}", shape.count, shape.example)?;
+            match shape.mean_ms() {
+                Some(mean) => writeln!(f, "  (mean {:.3} ms)", mean)?,
+                None => writeln!(f)?,
+            }
+        }
+        let counts = |items: Vec<String>| items.join(", ");
+        writeln!(f, "Tables: {}", counts(self.tables.iter().map(|(t, n)| format!("{} {}", t, n)).collect()))?;
+        write!(f, "Kinds: {}", counts(self.kinds.iter().map(|(k, n)| format!("{} {}", k, n)).collect()))
+    }
+}
@@ -0,0 +1,462 @@
+// rename.rs — rename tables and columns, as AST rewrites or text edits
+// Author: Sreerag Devadasan
+//
+// Names are matched case-insensitively, like unquoted SQL identifiers. A
+// column reference is resolved against the FROM and JOIN items of the
+// SELECT it is in: `u.id` is a column of the item whose alias is `u`, or
+// of table `u` where it has no alias. Without a schema it isn't known which
+// item an unqualified name is a column of, so one is only renamed when
+// every item in scope is the table whose column is renamed.
+//
+// The text edits are worked out from the AST rewrite: a name is edited
+// where the renamed statement's node differs from the parsed one, so the
+// two never disagree about what a rename touches.
+
+use core::slice;
+
+use crate::prelude::*;
+use crate::ast::{
+    Constraint, Expression, GrantObject, GroupingElement, Ident, MergeSource, Statement, TableAlias, TableColumn,
+    TableFactor,
+};
+use crate::dialect::Dialect;
+use crate::span::{parse_with_source_map, SourceMap, Span};
+use crate::tokenizer::ParseError;
+
+/// Replace the bytes of `span` with `replacement`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub span: Span,
+    pub replacement: String,
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+/// The name the columns of a FROM item are qualified by, and the table it
+/// reads (`None` for subqueries and functions).
+fn scope_item(factor: &TableFactor) -> (&str, Option<&str>) {
+    fn aliased<'a>(alias: &'a Option<TableAlias>, name: &'a str) -> &'a str {
+        alias.as_ref().map_or(name, |alias| alias.name.as_str())
+    }
+    match factor {
+        TableFactor::Table { name, alias, .. } | TableFactor::AsOf { name, alias, .. } => {
+            (aliased(alias, name.as_str()), Some(name.as_str()))
+        }
+        TableFactor::Derived { alias, .. } => (aliased(alias, ""), None),
+        TableFactor::Function { name, alias, .. } => (aliased(alias, name.as_str()), None),
+        TableFactor::Pivot { table, alias, .. } | TableFactor::Unpivot { table, alias, .. } => {
+            let (qualifier, name) = scope_item(table);
+            (aliased(alias, qualifier), name)
+        }
+    }
+}
+
+/// The FROM and JOIN items the expressions of a SELECT, UPDATE or DELETE
+/// refer to, as `scope_item` gives them.
+fn scope(statement: &Statement) -> Vec<(&str, Option<&str>)> {
+    match statement {
+        Statement::Select { from, joins, .. } => {
+            core::iter::once(&**from).chain(joins.iter().map(|join| &join.relation)).map(scope_item).collect()
+        }
+        Statement::Update { table_name, alias, .. } | Statement::Delete { table_name, alias, .. } => {
+            vec![(alias.as_deref().unwrap_or(table_name), Some(table_name.as_str()))]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Returns a copy of the statement with table `old` renamed to `new`,
+/// along with the qualifiers that name it.
+pub fn rename_table(statement: &Statement, old: &str, new: &str) -> Statement {
+    // `old.column` names the table only where it has no alias
+    let qualified = scope(statement).iter().any(|&(qualifier, table)| {
+        same_name(qualifier, old) && table.is_some_and(|table| same_name(table, old))
+    });
+    let mut renamed = if qualified {
+        let requalify = |parts: &[Ident]| match parts {
+            [.., qualifier, _] if same_name(qualifier, old) => {
+                let mut parts = parts.to_vec();
+                let at = parts.len() - 2;
+                parts[at] = Ident::new(new);
+                Some(parts)
+            }
+            _ => None,
+        };
+        map_expressions(statement, &|expr| rename_references(expr, &requalify))
+    } else {
+        statement.clone()
+    };
+
+    match &mut renamed {
+        Statement::Select { from, joins, locking, .. } => {
+            rename_table_factor(from, old, new);
+            for join in joins {
+                rename_table_factor(&mut join.relation, old, new);
+            }
+            for table in locking.iter_mut().flat_map(|clause| &mut clause.of) {
+                if same_name(table, old) {
+                    *table = Ident::new(new);
+                }
+            }
+        }
+        Statement::CreateTable { table_name, .. }
+        | Statement::DropTable { table_name, .. }
+        | Statement::Insert { table_name, .. }
+        | Statement::Update { table_name, .. }
+        | Statement::Delete { table_name, .. }
+        | Statement::Grant { object: GrantObject::Table(table_name), .. }
+        | Statement::Revoke { object: GrantObject::Table(table_name), .. }
+        | Statement::Copy { table_name, .. }
+        | Statement::LoadData { table_name, .. }
+        | Statement::CreateTrigger { table_name, .. }
+            if same_name(table_name, old) =>
+        {
+            *table_name = Ident::new(new)
+        }
+        Statement::InsertSelect { table_name: name, query, .. } | Statement::CreateView { name, query, .. } => {
+            if same_name(name, old) {
+                *name = Ident::new(new);
+            }
+            **query = rename_table(query, old, new);
+        }
+        Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => {
+            **statement = rename_table(statement, old, new)
+        }
+        Statement::SetOperation { left, right, .. } => {
+            **left = rename_table(left, old, new);
+            **right = rename_table(right, old, new);
+        }
+        Statement::With { ctes, body, .. } => {
+            for cte in ctes {
+                *cte.query = rename_table(&cte.query, old, new);
+            }
+            **body = rename_table(body, old, new);
+        }
+        Statement::Merge { target, source, .. } => {
+            if same_name(target, old) {
+                *target = Ident::new(new);
+            }
+            match source {
+                MergeSource::Table(name) if same_name(name, old) => *name = Ident::new(new),
+                MergeSource::Query(query) => **query = rename_table(query, old, new),
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+    renamed
+}
+
+fn rename_table_factor(factor: &mut TableFactor, old: &str, new: &str) {
+    match factor {
+        TableFactor::Table { name, .. } | TableFactor::AsOf { name, .. } if same_name(name, old) => {
+            *name = Ident::new(new);
+        }
+        TableFactor::Derived { subquery, .. } => **subquery = rename_table(subquery, old, new),
+        TableFactor::Pivot { table, .. } | TableFactor::Unpivot { table, .. } => rename_table_factor(table, old, new),
+        _ => {}
+    }
+}
+
+/// Returns a copy of the statement with column `old` of `table` renamed to
+/// `new` everywhere it's referenced, in the statement and the queries in
+/// it. Statements that don't refer to the table are returned unchanged.
+pub fn rename_column(statement: &Statement, table: &str, old: &str, new: &str) -> Statement {
+    let rename = |name: &Ident| if same_name(name, old) { Ident::new(new) } else { name.clone() };
+    let on_table = statement.table_name().is_some_and(|name| same_name(name, table));
+    let scope = scope(statement);
+    let qualifiers: Vec<&str> = scope
+        .iter()
+        .filter(|(_, name)| name.is_some_and(|name| same_name(name, table)))
+        .map(|&(qualifier, _)| qualifier)
+        .collect();
+    let bare = !qualifiers.is_empty() && qualifiers.len() == scope.len();
+    let reference = |parts: &[Ident]| match parts {
+        [name] if bare && same_name(name, old) => Some(vec![Ident::new(new)]),
+        [.., qualifier, name] if same_name(name, old) && qualifiers.iter().any(|q| same_name(q, qualifier)) => {
+            let mut parts = parts.to_vec();
+            *parts.last_mut().unwrap() = Ident::new(new);
+            Some(parts)
+        }
+        _ => None,
+    };
+    let mut renamed = if qualifiers.is_empty() {
+        statement.clone()
+    } else {
+        map_expressions(statement, &|expr| rename_references(expr, &reference))
+    };
+
+    match &mut renamed {
+        Statement::Select { from, joins, wildcard, .. } => {
+            if bare {
+                wildcard.exclude.iter_mut().for_each(|column| *column = rename(column));
+                wildcard.replace.iter_mut().for_each(|(_, column)| *column = rename(column));
+            }
+            // Subqueries in FROM have scopes of their own
+            rename_column_in_factor(from, table, old, new);
+            for join in joins {
+                rename_column_in_factor(&mut join.relation, table, old, new);
+            }
+        }
+        Statement::CreateTable { column_list, .. } if on_table => {
+            *column_list = column_list
+                .iter()
+                .map(|column| TableColumn {
+                    column_name: rename(&column.column_name),
+                    column_type: column.column_type.clone(),
+                    constraints: column
+                        .constraints
+                        .iter()
+                        .map(|constraint| match constraint {
+                            Constraint::Check(e) => Constraint::Check(rename_references(e, &reference_of(old, new))),
+                            other => other.clone(),
+                        })
+                        .collect(),
+                })
+                .collect()
+        }
+        Statement::Insert { columns, .. } if on_table => {
+            columns.iter_mut().flatten().for_each(|column| *column = rename(column));
+        }
+        Statement::InsertSelect { columns, query, .. } => {
+            if on_table {
+                columns.iter_mut().flatten().for_each(|column| *column = rename(column));
+            }
+            **query = rename_column(query, table, old, new);
+        }
+        Statement::Update { assignments, .. } if on_table => {
+            assignments.iter_mut().for_each(|(column, _)| *column = rename(column));
+        }
+        Statement::CreateTrigger { update_columns, .. } if on_table => {
+            update_columns.iter_mut().for_each(|column| *column = rename(column));
+        }
+        Statement::Grant { privileges, .. } | Statement::Revoke { privileges, .. } if on_table => {
+            let columns = privileges.iter_mut().flat_map(|granted| &mut granted.columns);
+            columns.for_each(|column| *column = rename(column));
+        }
+        Statement::Copy { columns, .. } | Statement::LoadData { columns, .. } if on_table => {
+            columns.iter_mut().flatten().for_each(|column| *column = rename(column));
+        }
+        Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => {
+            **statement = rename_column(statement, table, old, new)
+        }
+        Statement::SetOperation { left, right, .. } => {
+            **left = rename_column(left, table, old, new);
+            **right = rename_column(right, table, old, new);
+        }
+        Statement::With { ctes, body, .. } => {
+            for cte in ctes {
+                *cte.query = rename_column(&cte.query, table, old, new);
+            }
+            **body = rename_column(body, table, old, new);
+        }
+        _ => {}
+    }
+    renamed
+}
+
+/// A CHECK constraint's columns are all of its table.
+fn reference_of<'a>(old: &'a str, new: &'a str) -> impl Fn(&[Ident]) -> Option<Vec<Ident>> + 'a {
+    move |parts| match parts {
+        [name] if same_name(name, old) => Some(vec![Ident::new(new)]),
+        _ => None,
+    }
+}
+
+fn rename_column_in_factor(factor: &mut TableFactor, table: &str, old: &str, new: &str) {
+    match factor {
+        TableFactor::Derived { subquery, .. } => **subquery = rename_column(subquery, table, old, new),
+        TableFactor::Pivot { table: inner, .. } | TableFactor::Unpivot { table: inner, .. } => {
+            rename_column_in_factor(inner, table, old, new)
+        }
+        _ => {}
+    }
+}
+
+/// A copy of a SELECT, UPDATE or DELETE with `f` applied to each of the
+/// expressions of its own clauses; other statements are copied as they are.
+fn map_expressions(statement: &Statement, f: &dyn Fn(&Expression) -> Expression) -> Statement {
+    let mut mapped = statement.clone();
+    match &mut mapped {
+        Statement::Select { columns, wildcard, joins, selection, group_by, qualify, order_by, .. } => {
+            columns.iter_mut().for_each(|item| item.expr = f(&item.expr));
+            wildcard.replace.iter_mut().for_each(|(expr, _)| *expr = f(expr));
+            for join in joins {
+                join.on = join.on.as_ref().map(f);
+            }
+            *selection = selection.as_ref().map(f);
+            *group_by = group_by.iter().map(|element| map_grouping_element(element, f)).collect();
+            *qualify = qualify.as_ref().map(f);
+            order_by.iter_mut().for_each(|item| item.expr = f(&item.expr));
+        }
+        Statement::Update { assignments, selection, .. } => {
+            assignments.iter_mut().for_each(|(_, value)| *value = f(value));
+            *selection = selection.as_ref().map(f);
+        }
+        Statement::Delete { selection, .. } => *selection = selection.as_ref().map(f),
+        _ => {}
+    }
+    mapped
+}
+
+fn map_grouping_element(element: &GroupingElement, f: &dyn Fn(&Expression) -> Expression) -> GroupingElement {
+    let list = |exprs: &[Expression]| exprs.iter().map(f).collect();
+    match element {
+        GroupingElement::Expression(expr) => GroupingElement::Expression(f(expr)),
+        GroupingElement::Rollup(exprs) => GroupingElement::Rollup(list(exprs)),
+        GroupingElement::Cube(exprs) => GroupingElement::Cube(list(exprs)),
+        GroupingElement::GroupingSets(sets) => GroupingElement::GroupingSets(sets.iter().map(|set| list(set)).collect()),
+    }
+}
+
+/// The parts of a column reference: `a` or `t.a`.
+fn reference_parts(expr: &Expression) -> Option<&[Ident]> {
+    match expr {
+        Expression::Identifier(name) => Some(slice::from_ref(name)),
+        Expression::CompoundIdentifier(parts) => Some(parts),
+        _ => None,
+    }
+}
+
+/// A copy of `expr` with each column reference replaced by the parts
+/// `rename` gives it, where it gives any.
+fn rename_references(expr: &Expression, rename: &dyn Fn(&[Ident]) -> Option<Vec<Ident>>) -> Expression {
+    if let Some(mut parts) = reference_parts(expr).and_then(rename) {
+        return match parts.len() {
+            1 => Expression::Identifier(parts.remove(0)),
+            _ => Expression::CompoundIdentifier(parts),
+        };
+    }
+    let recurse = |expr: &Expression| rename_references(expr, rename);
+    match expr {
+        Expression::UnaryOperation { operator, operand } => {
+            Expression::UnaryOperation { operator: operator.clone(), operand: Box::new(recurse(operand)) }
+        }
+        Expression::BinaryOperation { left_operand, operator, right_operand } => Expression::BinaryOperation {
+            left_operand: Box::new(recurse(left_operand)),
+            operator: operator.clone(),
+            right_operand: Box::new(recurse(right_operand)),
+        },
+        Expression::Grouped(inner) => Expression::Grouped(Box::new(recurse(inner))),
+        Expression::Collate { expr, collation } => {
+            Expression::Collate { expr: Box::new(recurse(expr)), collation: collation.clone() }
+        }
+        Expression::Cast { expr, data_type } => {
+            Expression::Cast { expr: Box::new(recurse(expr)), data_type: data_type.clone() }
+        }
+        Expression::Function { name, args, over } => Expression::Function {
+            name: name.clone(),
+            args: args.iter().map(recurse).collect(),
+            over: over.as_ref().map(|window| Box::new(window.map_expressions(recurse))),
+        },
+        other => other.clone(),
+    }
+}
+
+/// The edits that turn the text of `statement` into that of `renamed`, a
+/// rename of it: one per table and column reference whose name changed.
+fn reference_edits(statement: &Statement, renamed: &Statement, map: &SourceMap, dialect: Dialect) -> Vec<TextEdit> {
+    let mut edits = Vec::new();
+    let factors = map.factor_spans(statement).into_iter().zip(map.factor_spans(renamed));
+    for ((before, span), (after, _)) in factors {
+        let (TableFactor::Table { name, .. } | TableFactor::AsOf { name, .. }) = before else { continue };
+        if let Some(new) = after.table_name().filter(|new| name.as_str() != *new) {
+            // The factor starts with its name, as written
+            let span = Span::new(span.start, span.start + name.to_string().len());
+            edits.push(TextEdit { span, replacement: dialect.quote_identifier(new) });
+        }
+    }
+    let expressions = map.expression_spans(statement).into_iter().zip(map.expression_spans(renamed));
+    for ((before, span), (after, _)) in expressions {
+        let (Some(before), Some(after)) = (reference_parts(before), reference_parts(after)) else { continue };
+        if before != after {
+            let parts: Vec<String> = before
+                .iter()
+                .zip(after)
+                .map(|(old, new)| if old == new { old.to_string() } else { dialect.quote_identifier(new) })
+                .collect();
+            edits.push(TextEdit { span, replacement: parts.join(".") });
+        }
+    }
+    edits
+}
+
+/// Sorts edits by where they are, dropping repeats: both copies of the
+/// operand of a BETWEEN have its span.
+fn sorted(mut edits: Vec<TextEdit>) -> Vec<TextEdit> {
+    edits.sort_by_key(|edit| edit.span);
+    edits.dedup_by_key(|edit| edit.span);
+    edits
+}
+
+/// Computes the edits that rename table `old` to `new` in `sql`, in every
+/// FROM and JOIN item, subquery and qualified column, leaving the rest of
+/// the text (comments, spacing, case) untouched.
+pub fn rename_table_edits(sql: &str, dialect: Dialect, old: &str, new: &str) -> Result<Vec<TextEdit>, ParseError> {
+    let (statement, map) = parse_with_source_map(sql, dialect)?;
+    let renamed = rename_table(&statement, old, new);
+    let mut edits = reference_edits(&statement, &renamed, &map, dialect);
+    if statement.table_name() != renamed.table_name() {
+        let replacement = dialect.quote_identifier(new);
+        edits.extend(map.table.map(|span| TextEdit { span, replacement }));
+    }
+    Ok(sorted(edits))
+}
+
+/// Computes the edits that rename column `old` of `table` to `new` in `sql`.
+pub fn rename_column_edits(
+    sql: &str,
+    dialect: Dialect,
+    table: &str,
+    old: &str,
+    new: &str,
+) -> Result<Vec<TextEdit>, ParseError> {
+    let (statement, map) = parse_with_source_map(sql, dialect)?;
+    let renamed = rename_column(&statement, table, old, new);
+    let mut edits = reference_edits(&statement, &renamed, &map, dialect);
+
+    let names = named_columns(&statement).into_iter().zip(&map.columns);
+    for ((before, span), after) in names.zip(named_columns(&renamed)) {
+        if before != after {
+            edits.push(TextEdit { span: *span, replacement: dialect.quote_identifier(after) });
+        }
+    }
+    Ok(sorted(edits))
+}
+
+/// The column names a statement lists outside its expressions, in the
+/// order the source map has their spans. A SELECT's are all among its
+/// expressions.
+fn named_columns(statement: &Statement) -> Vec<&str> {
+    match statement {
+        Statement::CreateTable { column_list, .. } => column_list.iter().map(|c| c.column_name.as_str()).collect(),
+        Statement::Insert { columns, .. }
+        | Statement::InsertSelect { columns, .. }
+        | Statement::Copy { columns, .. }
+        | Statement::LoadData { columns, .. } => columns.iter().flatten().map(Ident::as_str).collect(),
+        Statement::CreateTrigger { update_columns, .. } => update_columns.iter().map(Ident::as_str).collect(),
+        Statement::Grant { privileges, .. } | Statement::Revoke { privileges, .. } => {
+            privileges.iter().flat_map(|granted| &granted.columns).map(Ident::as_str).collect()
+        }
+        Statement::Update { assignments, .. } => assignments.iter().map(|(column, _)| column.as_str()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Applies non-overlapping edits to `source`.
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> String {
+    let mut sorted: Vec<&TextEdit> = edits.iter().collect();
+    sorted.sort_by_key(|e| e.span);
+
+    let mut result = String::with_capacity(source.len());
+    let mut last = 0;
+    for edit in sorted {
+        result.push_str(&source[last..edit.span.start]);
+        result.push_str(&edit.replacement);
+        last = edit.span.end;
+    }
+    result.push_str(&source[last..]);
+    result
+}
@@ -0,0 +1,570 @@
+// repl.rs — settings and history of the interactive REPL
+// Author: Sreerag Devadasan
+//
+// Settings come from `~/.minisqlrc` (or the file `MINISQLRC` names), then
+// from `MINISQL_*` environment variables, which override the file. The
+// file is TOML, of which only what a settings file needs is read: `key =
+// value` lines with string, integer, boolean and string-array values
+// (arrays may span lines), `#` comments, and an optional `[repl]` table
+// header. Unknown keys are errors, so a misspelt setting doesn't go
+// unnoticed.
+//
+//   dialect = "postgres"          # any `Dialect` name
+//   prompt = "pg> "
+//   color = "auto"                # auto, always or never
+//   theme = "light"               # light or dark
+//   output = "sql"                # sexp, ast, sql or minified
+//   history_size = 500            # 0 keeps no history
+//   schema = ["schema.sql", "migrations/"]
+//
+// Relative schema paths are taken from the directory of the file that
+// names them. The environment variables are MINISQL_DIALECT,
+// MINISQL_PROMPT, MINISQL_COLOR, MINISQL_THEME, MINISQL_OUTPUT,
+// MINISQL_HISTORY_SIZE and MINISQL_SCHEMA (a path list, separated as in
+// PATH); NO_COLOR turns color off unless MINISQL_COLOR says otherwise.
+//
+// On a Unix terminal `read_line` reads a line a key at a time, with `stty`
+// turning off the terminal's own line editing meanwhile, so that TAB can
+// complete the word before the cursor (see complete.rs). Only typing,
+// backspace and TAB edit the line; other input reads a line at a time.
+
+use std::collections::VecDeque;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::complete::Tab;
+use crate::dialect::Dialect;
+use crate::style::{Styler, Theme};
+
+/// Errors raised while reading the settings.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io { path: PathBuf, error: io::Error },
+    /// A line of the file that isn't TOML this reader understands.
+    Syntax { line: usize, message: String },
+    /// A setting, from the file or the environment, with a bad name or value.
+    Invalid { setting: String, message: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            ConfigError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            ConfigError::Invalid { setting, message } => write!(f, "{}: {}", setting, message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    /// Color when stdout is a terminal.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "auto" => Some(ColorMode::Auto),
+            "always" => Some(ColorMode::Always),
+            "never" => Some(ColorMode::Never),
+            _ => None,
+        }
+    }
+
+    /// Whether to color output written to stdout.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorMode::Auto => io::stdout().is_terminal(),
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+/// How the REPL shows a statement that parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputMode {
+    /// The syntax tree as a one-line s-expression.
+    #[default]
+    Sexp,
+    /// The syntax tree as its pretty-printed `Debug`.
+    Ast,
+    /// The statement printed back as SQL of the dialect.
+    Sql,
+    /// The statement on one line, in canonical form.
+    Minified,
+}
+
+impl OutputMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sexp" => Some(OutputMode::Sexp),
+            "ast" => Some(OutputMode::Ast),
+            "sql" => Some(OutputMode::Sql),
+            "minified" => Some(OutputMode::Minified),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplConfig {
+    pub dialect: Dialect,
+    pub prompt: String,
+    pub color: ColorMode,
+    pub theme: Theme,
+    pub output: OutputMode,
+    /// Most lines of history kept between sessions.
+    pub history_size: usize,
+    /// Schema dumps (files or directories of `.sql` files) loaded at start.
+    pub schema: Vec<PathBuf>,
+}
+
+impl Default for ReplConfig {
+    fn default() -> Self {
+        Self {
+            dialect: Dialect::Generic,
+            prompt: "sql> ".to_string(),
+            color: ColorMode::Auto,
+            theme: Theme::Dark,
+            output: OutputMode::Sexp,
+            history_size: 1000,
+            schema: Vec::new(),
+        }
+    }
+}
+
+/// A value of the TOML subset.
+#[derive(Debug)]
+enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+impl ReplConfig {
+    /// Reads the settings file, if there is one, then the environment.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = ReplConfig::default();
+        let named = env::var_os("MINISQLRC").map(PathBuf::from);
+        if let Some(path) = named.clone().or_else(|| home().map(|home| home.join(".minisqlrc"))) {
+            match fs::read_to_string(&path) {
+                Ok(text) => config.apply_toml(&text, path.parent().unwrap_or(Path::new("")))?,
+                // Only a file asked for by name has to exist
+                Err(e) if e.kind() == io::ErrorKind::NotFound && named.is_none() => {}
+                Err(error) => return Err(ConfigError::Io { path, error }),
+            }
+        }
+        config.apply_env(unicode_vars())?;
+        Ok(config)
+    }
+
+    /// The defaults overridden by the environment alone, for the commands
+    /// that don't read the settings file.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let mut config = ReplConfig::default();
+        config.apply_env(unicode_vars())?;
+        Ok(config)
+    }
+
+    /// The defaults overridden by a settings file.
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        let mut config = ReplConfig::default();
+        config.apply_toml(text, Path::new(""))?;
+        Ok(config)
+    }
+
+    /// A styler in the theme when color is on, a plain one otherwise.
+    pub fn styler(&self) -> Styler {
+        if self.color.enabled() {
+            Styler::new(self.theme)
+        } else {
+            Styler::plain()
+        }
+    }
+
+    /// Applies the settings of a file, resolving relative schema paths
+    /// against `directory`.
+    pub fn apply_toml(&mut self, text: &str, directory: &Path) -> Result<(), ConfigError> {
+        let mut lines = text.lines().enumerate();
+        while let Some((index, line)) = lines.next() {
+            let line_number = index + 1;
+            let syntax = |message: &str| ConfigError::Syntax { line: line_number, message: message.to_string() };
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                if line == "[repl]" {
+                    continue;
+                }
+                return Err(syntax(&format!("unknown table {}", line)));
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(syntax("expected key = value"));
+            };
+            let key = key.trim().to_string();
+            // An array may go on over the lines that follow
+            let mut value = value.trim().to_string();
+            while unclosed_brackets(&value) {
+                match lines.next() {
+                    Some((_, next)) => value = format!("{} {}", value, strip_comment(next).trim()),
+                    None => return Err(syntax("unterminated array")),
+                }
+            }
+            let value = parse_value(&value).map_err(|message| syntax(&message))?;
+            self.set(&key, value, directory)?;
+        }
+        Ok(())
+    }
+
+    /// Applies the `MINISQL_*` (and `NO_COLOR`) variables among `vars`.
+    pub fn apply_env(&mut self, vars: impl IntoIterator<Item = (String, String)>) -> Result<(), ConfigError> {
+        let mut color_set = false;
+        let mut no_color = false;
+        for (name, value) in vars {
+            let invalid = |message: String| ConfigError::Invalid { setting: name.clone(), message };
+            match name.as_str() {
+                "MINISQL_DIALECT" => self.dialect = Dialect::from_name(&value).ok_or_else(|| invalid(bad_dialect(&value)))?,
+                "MINISQL_PROMPT" => self.prompt = value,
+                "MINISQL_COLOR" => {
+                    self.color = ColorMode::from_name(&value).ok_or_else(|| invalid(bad_color(&value)))?;
+                    color_set = true;
+                }
+                "MINISQL_THEME" => self.theme = Theme::from_name(&value).ok_or_else(|| invalid(bad_theme(&value)))?,
+                "MINISQL_OUTPUT" => self.output = OutputMode::from_name(&value).ok_or_else(|| invalid(bad_output(&value)))?,
+                "MINISQL_HISTORY_SIZE" => {
+                    self.history_size = value.trim().parse().map_err(|_| invalid(format!("not a size: {}", value)))?
+                }
+                "MINISQL_SCHEMA" => self.schema = env::split_paths(&value).collect(),
+                "NO_COLOR" => no_color = !value.is_empty(),
+                _ => {}
+            }
+        }
+        if no_color && !color_set {
+            self.color = ColorMode::Never;
+        }
+        Ok(())
+    }
+
+    fn set(&mut self, key: &str, value: Value, directory: &Path) -> Result<(), ConfigError> {
+        let invalid = |message: String| ConfigError::Invalid { setting: key.to_string(), message };
+        match (key, value) {
+            ("dialect", Value::String(name)) => {
+                self.dialect = Dialect::from_name(&name).ok_or_else(|| invalid(bad_dialect(&name)))?
+            }
+            ("prompt", Value::String(prompt)) => self.prompt = prompt,
+            ("color", Value::String(name)) => {
+                self.color = ColorMode::from_name(&name).ok_or_else(|| invalid(bad_color(&name)))?
+            }
+            // `color = false` reads naturally too
+            ("color", Value::Boolean(on)) => self.color = if on { ColorMode::Always } else { ColorMode::Never },
+            ("theme", Value::String(name)) => {
+                self.theme = Theme::from_name(&name).ok_or_else(|| invalid(bad_theme(&name)))?
+            }
+            ("output", Value::String(name)) => {
+                self.output = OutputMode::from_name(&name).ok_or_else(|| invalid(bad_output(&name)))?
+            }
+            ("history_size", Value::Integer(n)) => {
+                self.history_size = usize::try_from(n).map_err(|_| invalid(format!("not a size: {}", n)))?
+            }
+            ("schema", Value::String(path)) => self.schema = vec![directory.join(path)],
+            ("schema", Value::Array(paths)) => {
+                self.schema = paths
+                    .into_iter()
+                    .map(|path| match path {
+                        Value::String(path) => Ok(directory.join(path)),
+                        _ => Err(invalid("expected an array of paths".to_string())),
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+            ("dialect" | "prompt" | "color" | "theme" | "output" | "history_size" | "schema", value) => {
+                return Err(invalid(format!("unexpected value {:?}", value)))
+            }
+            _ => return Err(invalid("unknown setting".to_string())),
+        }
+        Ok(())
+    }
+}
+
+fn bad_dialect(name: &str) -> String {
+    let names: Vec<&str> = Dialect::ALL.iter().map(Dialect::name).collect();
+    format!("unknown dialect {} (expected one of {})", name, names.join(", "))
+}
+
+fn bad_color(name: &str) -> String {
+    format!("unknown color mode {} (expected auto, always or never)", name)
+}
+
+fn bad_theme(name: &str) -> String {
+    format!("unknown theme {} (expected light or dark)", name)
+}
+
+fn bad_output(name: &str) -> String {
+    format!("unknown output mode {} (expected sexp, ast, sql or minified)", name)
+}
+
+/// The characters of `text` outside strings, with their offsets.
+fn unquoted(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut quote = None;
+    let mut escaped = false;
+    text.char_indices().filter(move |&(_, c)| {
+        let outside = quote.is_none() && !matches!(c, '"' | '\'');
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => escaped = true,
+            (Some(q), _) if c == q && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            _ => escaped = false,
+        }
+        outside
+    })
+}
+
+/// The line up to a `#` that isn't inside a string.
+fn strip_comment(line: &str) -> &str {
+    unquoted(line).find(|&(_, c)| c == '#').map_or(line, |(i, _)| &line[..i])
+}
+
+/// Whether `text` opens more brackets than it closes.
+fn unclosed_brackets(text: &str) -> bool {
+    let depth = unquoted(text).fold(0i32, |depth, (_, c)| match c {
+        '[' => depth + 1,
+        ']' => depth - 1,
+        _ => depth,
+    });
+    depth > 0
+}
+
+/// Parses a whole value; the error says what's wrong with it.
+fn parse_value(text: &str) -> Result<Value, String> {
+    let (value, rest) = parse_prefix(text)?;
+    if rest.trim().is_empty() {
+        Ok(value)
+    } else {
+        Err(format!("unexpected {} after the value", rest.trim()))
+    }
+}
+
+/// Parses the value at the start of `text`, returning it and the rest.
+fn parse_prefix(text: &str) -> Result<(Value, &str), String> {
+    let text = text.trim_start();
+    if let Some(rest) = text.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((Value::String(value), &rest[i + 1..])),
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    other => return Err(format!("unknown escape \\{}", other.map_or(String::new(), String::from))),
+                },
+                c => value.push(c),
+            }
+        }
+        Err("unterminated string".to_string())
+    } else if let Some(rest) = text.strip_prefix('\'') {
+        // Literal strings have no escapes
+        let end = rest.find('\'').ok_or("unterminated string")?;
+        Ok((Value::String(rest[..end].to_string()), &rest[end + 1..]))
+    } else if let Some(mut rest) = text.strip_prefix('[') {
+        let mut items = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix(']') {
+                return Ok((Value::Array(items), after));
+            }
+            let (item, after) = parse_prefix(rest)?;
+            items.push(item);
+            rest = after.trim_start();
+            match rest.strip_prefix(',') {
+                Some(after) => rest = after,
+                None if rest.starts_with(']') => {}
+                None => return Err("expected , or ] in the array".to_string()),
+            }
+        }
+    } else {
+        let end = text.find(|c: char| c.is_whitespace() || c == ',' || c == ']').unwrap_or(text.len());
+        let (word, rest) = text.split_at(end);
+        match word {
+            "true" => Ok((Value::Boolean(true), rest)),
+            "false" => Ok((Value::Boolean(false), rest)),
+            "" => Err("expected a value".to_string()),
+            _ => match word.replace('_', "").parse() {
+                Ok(n) => Ok((Value::Integer(n), rest)),
+                Err(_) => Err(format!("unsupported value {}", word)),
+            },
+        }
+    }
+}
+
+/// The environment variables whose names and values are valid UTF-8.
+fn unicode_vars() -> impl Iterator<Item = (String, String)> {
+    env::vars_os().filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+}
+
+/// The user's home directory.
+pub fn home() -> Option<PathBuf> {
+    env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")).map(PathBuf::from)
+}
+
+// === History ===
+
+/// The lines entered in the REPL, newest last, kept in a file between
+/// sessions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct History {
+    path: Option<PathBuf>,
+    size: usize,
+    entries: VecDeque<String>,
+}
+
+impl History {
+    /// `~/.minisql_history`, keeping at most `size` lines.
+    pub fn open(size: usize) -> Self {
+        Self::load(home().map(|home| home.join(".minisql_history")), size)
+    }
+
+    /// The last `size` lines of the file at `path`, if it exists; with no
+    /// path, the history lasts only for the session.
+    pub fn load(path: Option<PathBuf>, size: usize) -> Self {
+        let mut history = Self { path, size, entries: VecDeque::new() };
+        let text = history.path.as_ref().and_then(|path| fs::read_to_string(path).ok()).unwrap_or_default();
+        for line in text.lines() {
+            history.push(line);
+        }
+        history
+    }
+
+    /// Adds a line, dropping the oldest ones over the size. A line the same
+    /// as the one before it isn't added again.
+    pub fn push(&mut self, line: &str) {
+        if self.size == 0 || line.trim().is_empty() || self.entries.back().is_some_and(|last| last == line) {
+            return;
+        }
+        if self.entries.len() == self.size {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(line.to_string());
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(String::as_str)
+    }
+
+    /// Writes the history back to its file, if it has one.
+    pub fn save(&self) -> io::Result<()> {
+        match &self.path {
+            Some(path) if self.size > 0 => {
+                let mut text = String::new();
+                for entry in &self.entries {
+                    text.push_str(entry);
+                    text.push('\n');
+                }
+                fs::write(path, text)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Shows `prompt` and reads a line, with its newline, or None at the end
+/// of the input. TAB replaces the line with the one `tab` makes of it and
+/// lists the choices it offers.
+pub fn read_line(prompt: &str, tab: &mut dyn FnMut(&str) -> Tab) -> io::Result<Option<String>> {
+    let mut stdout = io::stdout();
+    write!(stdout, "{}", prompt)?;
+    stdout.flush()?;
+    let Some(_raw) = io::stdin().is_terminal().then(RawMode::enter).flatten() else {
+        let mut line = String::new();
+        return Ok((io::stdin().read_line(&mut line)? > 0).then_some(line));
+    };
+
+    let mut stdin = io::stdin().lock();
+    let mut line = String::new();
+    // The bytes of a character read so far
+    let mut pending = Vec::new();
+    let mut byte = [0];
+    loop {
+        if stdin.read(&mut byte)? == 0 {
+            return Ok((!line.is_empty()).then_some(line));
+        }
+        match byte[0] {
+            b'\n' | b'\r' => {
+                writeln!(stdout)?;
+                line.push('\n');
+                return Ok(Some(line));
+            }
+            // Ctrl-D
+            4 if line.is_empty() => {
+                writeln!(stdout)?;
+                return Ok(None);
+            }
+            b'\t' => {
+                let done = tab(&line);
+                if !done.choices.is_empty() {
+                    let choices: Vec<&str> = done.choices.iter().map(|choice| choice.text.as_str()).collect();
+                    write!(stdout, "\n{}\n", choices.join("  "))?;
+                }
+                line = done.line;
+                write!(stdout, "\r\x1b[K{}{}", prompt, line)?;
+            }
+            // Backspace
+            0x7f | 8 => {
+                if line.pop().is_some() {
+                    write!(stdout, "\r\x1b[K{}{}", prompt, line)?;
+                }
+            }
+            // An escape sequence, such as an arrow key's, is skipped
+            0x1b => {
+                if stdin.read(&mut byte)? == 1 && byte[0] == b'[' {
+                    while stdin.read(&mut byte)? == 1 && !(0x40..=0x7e).contains(&byte[0]) {}
+                }
+            }
+            control if control < 0x20 => {}
+            byte => {
+                pending.push(byte);
+                if let Ok(text) = std::str::from_utf8(&pending) {
+                    write!(stdout, "{}", text)?;
+                    line.push_str(text);
+                    pending.clear();
+                } else if pending.len() == 4 {
+                    pending.clear();
+                }
+            }
+        }
+        stdout.flush()?;
+    }
+}
+
+/// The terminal without line editing or echo, until dropped.
+struct RawMode;
+
+impl RawMode {
+    fn enter() -> Option<RawMode> {
+        (cfg!(unix) && stty(&["-icanon", "-echo", "min", "1"])).then_some(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        stty(&["icanon", "echo"]);
+    }
+}
+
+fn stty(args: &[&str]) -> bool {
+    Command::new("stty").args(args).stdin(Stdio::inherit()).status().is_ok_and(|status| status.success())
+}
@@ -0,0 +1,150 @@
+// report.rs — per-statement parse reports for multi-statement scripts
+// Author: Sreerag Devadasan
+//
+// `report_script` parses every statement of a script on its own and keeps,
+// for each one, where it is in the file, what it parsed to (or why it
+// didn't) and how long it took. CI tooling can then annotate exactly the
+// statements that failed instead of rejecting the whole file. A strict
+// session also has each report list the statement's nonstandard SQL.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::ast::Statement;
+use crate::batch::statement_spans;
+use crate::json;
+use crate::profile::Warning;
+use crate::session::ParserSession;
+use crate::span::Span;
+use crate::tokenizer::{ParseError, Progress};
+
+/// How one statement of a script parsed.
+#[derive(Debug)]
+pub struct StatementReport {
+    /// Byte range of the statement in the script, without the surrounding
+    /// whitespace and the terminating `;`.
+    pub span: Span,
+    /// 1-based line the statement starts on.
+    pub line: usize,
+    pub result: Result<Statement, ParseError>,
+    pub elapsed: Duration,
+    /// What the statement has outside standard SQL, with spans into the
+    /// script, when the session's profile is strict.
+    pub warnings: Vec<Warning>,
+}
+
+/// Every statement of a script, in source order.
+#[derive(Debug, Default)]
+pub struct ScriptReport {
+    pub statements: Vec<StatementReport>,
+}
+
+impl ScriptReport {
+    /// The statements that failed to parse.
+    pub fn failures(&self) -> impl Iterator<Item = &StatementReport> {
+        self.statements.iter().filter(|statement| statement.result.is_err())
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Total parse time of all statements.
+    pub fn elapsed(&self) -> Duration {
+        self.statements.iter().map(|statement| statement.elapsed).sum()
+    }
+
+    pub fn to_json(&self) -> String {
+        let statements = self.statements.iter().map(|statement| {
+            let mut fields = vec![
+                ("start", statement.span.start.to_string()),
+                ("end", statement.span.end.to_string()),
+                ("line", statement.line.to_string()),
+                ("ok", statement.result.is_ok().to_string()),
+                ("micros", statement.elapsed.as_micros().to_string()),
+            ];
+            match &statement.result {
+                Ok(parsed) => fields.push(("kind", json::string(parsed.kind()))),
+                Err(e) => fields.push(("error", json::string(&e.to_string()))),
+            }
+            if !statement.warnings.is_empty() {
+                let warnings = statement.warnings.iter().map(|warning| {
+                    json::object([
+                        ("start", warning.span.start.to_string()),
+                        ("end", warning.span.end.to_string()),
+                        ("message", json::string(warning.message)),
+                    ])
+                });
+                fields.push(("warnings", json::array(warnings)));
+            }
+            json::object(fields)
+        });
+        json::object([
+            ("statements", json::array(statements)),
+            ("failed", self.failures().count().to_string()),
+        ])
+    }
+}
+
+impl fmt::Display for ScriptReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for statement in &self.statements {
+            if let Err(e) = &statement.result {
+                writeln!(
+                    f,
+                    "❌ line {} (bytes {}..{}): {}",
+                    statement.line, statement.span.start, statement.span.end, e
+                )?;
+            }
+            for warning in &statement.warnings {
+                let Span { start, end } = warning.span;
+                writeln!(f, "⚠️ line {} (bytes {}..{}): {}", statement.line, start, end, warning.message)?;
+            }
+        }
+        let total = self.statements.len();
+        write!(
+            f,
+            "{}/{} statements parsed in {:.3} ms",
+            total - self.failures().count(),
+            total,
+            self.elapsed().as_secs_f64() * 1000.0
+        )
+    }
+}
+
+/// Splits `script` into statements and parses each one with `session`.
+pub fn report_script(session: &ParserSession, script: &str) -> ScriptReport {
+    report_script_with_progress(session, script, |_| {})
+}
+
+/// Like `report_script`, but calls `report` after each statement, and once
+/// more at the end, with the bytes of the script and the tokens read so
+/// far, so a caller working through a large file can show a progress bar.
+pub fn report_script_with_progress(
+    session: &ParserSession,
+    script: &str,
+    mut report: impl FnMut(Progress),
+) -> ScriptReport {
+    let mut line = 1;
+    let mut counted = 0;
+    let mut tokens = 0;
+    let statements = statement_spans(script)
+        .into_iter()
+        .map(|span| {
+            line += script[counted..span.start].matches('\n').count();
+            counted = span.start;
+            let started = Instant::now();
+            let (result, read) = session.parse_counted(span.slice(script), span.start);
+            let elapsed = started.elapsed();
+            tokens += read;
+            report(Progress { offset: span.end, total: script.len(), tokens });
+            let mut warnings = session.warnings(span.slice(script));
+            for warning in &mut warnings {
+                warning.span = Span::new(span.start + warning.span.start, span.start + warning.span.end);
+            }
+            StatementReport { span, line, result, elapsed, warnings }
+        })
+        .collect();
+    report(Progress { offset: script.len(), total: script.len(), tokens });
+    ScriptReport { statements }
+}
@@ -0,0 +1,155 @@
+// result_cache.rs — the results of SELECTs the engine returns again while their tables are unchanged
+// Author: Sreerag Devadasan
+//
+// A `ResultCache` keeps the rows of SELECTs by the hash of the statement
+// (see hash.rs) and hands them back when the same statement runs again,
+// without reading a table. Unlike the plan cache, which shares one plan
+// between queries that differ in their literals (see plan_cache.rs), a
+// result is only reused for a statement identical to the one that
+// produced it, literals and all.
+//
+// Each table has a version, which the engine bumps on every write to it:
+// an INSERT that stores rows, an UPDATE or DELETE that changes or removes
+// some, CREATE and DROP TABLE, a committed
+// transaction or ROLLBACK changing it. A result remembers the versions of
+// the tables its query read; writing one of them drops the results that
+// read it at once, and a result whose versions aren't current any more is
+// never returned. The engine doesn't cache queries that call a registered
+// function, which may not return the same value twice, nor those reading a
+// table AS OF a version, which the history may stop keeping.
+//
+// The cache holds at most `capacity` results, evicting the least recently
+// used to make room, and counts its hits, misses, evictions and
+// invalidations. It is off until given a capacity.
+
+use alloc::collections::BTreeMap;
+
+use crate::prelude::*;
+use crate::ast::Statement;
+use crate::engine::QueryResult;
+use crate::hash::AstHash;
+
+/// How the result cache has done since the database was created.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ResultCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Results dropped because a table they read was written.
+    pub invalidations: u64,
+    /// The results held now.
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// The statement, so that two with the same hash never share a result.
+    statement: Statement,
+    /// The tables the query read, at the versions it read them.
+    versions: Vec<(String, u64)>,
+    result: QueryResult,
+    /// The tick the result was last used at.
+    used: u64,
+}
+
+/// Query results by statement hash, with the versions of the tables.
+#[derive(Debug, Clone, Default)]
+pub struct ResultCache {
+    capacity: usize,
+    results: BTreeMap<AstHash, Entry>,
+    /// Tables that were never written are at version zero.
+    versions: BTreeMap<String, u64>,
+    tick: u64,
+    stats: ResultCacheStats,
+}
+
+/// A cache doesn't change what a database holds or answers, so any two
+/// compare equal.
+impl PartialEq for ResultCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl ResultCache {
+    pub fn new(capacity: usize) -> Self {
+        ResultCache { capacity, ..ResultCache::default() }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes the capacity, evicting results down to it.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.results.len() > capacity {
+            self.evict();
+        }
+    }
+
+    pub fn stats(&self) -> ResultCacheStats {
+        ResultCacheStats { entries: self.results.len(), ..self.stats }
+    }
+
+    /// The version of table `name`.
+    pub fn version(&self, name: &str) -> u64 {
+        self.versions.get(name).copied().unwrap_or(0)
+    }
+
+    /// The stored result of `statement`, if the tables it read are still
+    /// at the same versions, counting a hit or a miss.
+    pub(crate) fn get(&mut self, key: AstHash, statement: &Statement) -> Option<QueryResult> {
+        self.tick += 1;
+        let current = |entry: &Entry| entry.versions.iter().all(|(name, version)| self.version(name) == *version);
+        match self.results.get(&key) {
+            Some(entry) if entry.statement == *statement && current(entry) => {
+                let entry = self.results.get_mut(&key)?;
+                entry.used = self.tick;
+                self.stats.hits += 1;
+                Some(entry.result.clone())
+            }
+            _ => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Stores the result of `statement`, which read `tables` as they are
+    /// now.
+    pub(crate) fn insert(&mut self, key: AstHash, statement: &Statement, tables: &[&str], result: QueryResult) {
+        if self.capacity == 0 {
+            return;
+        }
+        if !self.results.contains_key(&key) && self.results.len() >= self.capacity {
+            self.evict();
+        }
+        let versions = tables.iter().map(|name| (name.to_string(), self.version(name))).collect();
+        let entry = Entry { statement: statement.clone(), versions, result, used: self.tick };
+        self.results.insert(key, entry);
+    }
+
+    /// Records a write to table `name`, dropping the results that read it.
+    pub(crate) fn written(&mut self, name: &str) {
+        *self.versions.entry(name.to_string()).or_insert(0) += 1;
+        let before = self.results.len();
+        self.results.retain(|_, entry| !entry.versions.iter().any(|(table, _)| table == name));
+        self.stats.invalidations += (before - self.results.len()) as u64;
+    }
+
+    /// Drops every result, such as when what a query answers could change
+    /// without any table being written.
+    pub(crate) fn clear(&mut self) {
+        self.stats.invalidations += self.results.len() as u64;
+        self.results.clear();
+    }
+
+    fn evict(&mut self) {
+        let oldest = self.results.iter().min_by_key(|(_, entry)| entry.used).map(|(key, _)| *key);
+        if let Some(key) = oldest {
+            self.results.remove(&key);
+            self.stats.evictions += 1;
+        }
+    }
+}
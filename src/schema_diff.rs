@@ -0,0 +1,280 @@
+// schema_diff.rs — structural diff between two CREATE TABLE schema dumps
+// Author: Sreerag Devadasan
+
+use alloc::collections::BTreeMap;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{fs, io, path::Path};
+
+use crate::prelude::*;
+use crate::ast::{Constraint, DBType, Ident, Statement, TableColumn};
+use crate::batch::split_statements;
+use crate::dialect::Dialect;
+use crate::parser::parse_sql;
+use crate::tokenizer::ParseError;
+
+/// Errors raised while loading a schema dump.
+#[derive(Debug)]
+pub enum SchemaError {
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    Parse { source: String, statement: usize, error: ParseError },
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            SchemaError::Io(e) => write!(f, "I/O error: {}", e),
+            SchemaError::Parse { source, statement, error } => {
+                write!(f, "{}: statement {}: {}", source, statement, error)
+            }
+        }
+    }
+}
+
+impl core::error::Error for SchemaError {}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for SchemaError {
+    fn from(e: io::Error) -> Self {
+        SchemaError::Io(e)
+    }
+}
+
+/// Tables and their columns, and views, as built up by a sequence of
+/// CREATE/DROP TABLE and CREATE VIEW statements. Other statements are
+/// ignored.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schema {
+    pub tables: BTreeMap<String, Vec<TableColumn>>,
+    pub views: BTreeMap<String, View>,
+}
+
+/// The definition of a view: the column names it declares, if any, and
+/// its query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct View {
+    pub columns: Vec<Ident>,
+    pub query: Statement,
+}
+
+impl Schema {
+    /// Loads a schema from a `.sql` file or from every `.sql` file in a
+    /// directory (applied in file-name order).
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SchemaError> {
+        let path = path.as_ref();
+        let mut files = Vec::new();
+        if path.is_dir() {
+            for entry in fs::read_dir(path)? {
+                let file = entry?.path();
+                if file.extension().is_some_and(|e| e == "sql") {
+                    files.push(file);
+                }
+            }
+            files.sort();
+        } else {
+            files.push(path.to_path_buf());
+        }
+
+        let mut schema = Schema::default();
+        for file in files {
+            let script = fs::read_to_string(&file)?;
+            schema.apply_script(&script, &file.display().to_string())?;
+        }
+        Ok(schema)
+    }
+
+    /// Builds a schema from the statements of one script.
+    pub fn from_sql(script: &str) -> Result<Self, SchemaError> {
+        let mut schema = Schema::default();
+        schema.apply_script(script, "<input>")?;
+        Ok(schema)
+    }
+
+    fn apply_script(&mut self, script: &str, source: &str) -> Result<(), SchemaError> {
+        for (index, (_, sql)) in split_statements(script).into_iter().enumerate() {
+            let statement = parse_sql(sql).map_err(|error| SchemaError::Parse {
+                source: source.to_string(),
+                statement: index + 1,
+                error,
+            })?;
+            self.apply(&statement);
+        }
+        Ok(())
+    }
+
+    /// The columns of the table named `name`, matched case-insensitively.
+    pub fn table(&self, name: &str) -> Option<&[TableColumn]> {
+        self.tables.iter().find(|(table, _)| table.eq_ignore_ascii_case(name)).map(|(_, columns)| columns.as_slice())
+    }
+
+    /// The view named `name`, matched case-insensitively.
+    pub fn view(&self, name: &str) -> Option<&View> {
+        self.views.iter().find(|(view, _)| view.eq_ignore_ascii_case(name)).map(|(_, view)| view)
+    }
+
+    /// Applies a CREATE or DROP TABLE or a CREATE VIEW; false for any other
+    /// statement, and for dropping a table that doesn't exist.
+    pub fn apply(&mut self, statement: &Statement) -> bool {
+        match statement {
+            Statement::CreateTable { table_name, column_list } => {
+                self.tables.insert(table_name.value.clone(), column_list.clone());
+                true
+            }
+            Statement::CreateView { name, columns, query, .. } => {
+                let view = View { columns: columns.clone(), query: (**query).clone() };
+                self.views.insert(name.value.clone(), view);
+                true
+            }
+            Statement::DropTable { table_name, .. } => self.tables.remove(table_name.as_str()).is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// One difference between two schemas.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaChange {
+    AddTable { table: String, columns: Vec<TableColumn> },
+    DropTable { table: String },
+    AddColumn { table: String, column: TableColumn },
+    DropColumn { table: String, column: String },
+    RetypeColumn { table: String, column: String, from: DBType, to: DBType },
+    ChangeConstraints { table: String, column: String, from: Vec<Constraint>, to: Vec<Constraint> },
+}
+
+/// Computes the changes that turn `old` into `new`. Tables are compared in
+/// name order; columns keep the order they have in their schema. Tables and
+/// columns are matched by name ignoring case, as the analyzer looks them
+/// up, so a name that only changed case is no change.
+pub fn diff(old: &Schema, new: &Schema) -> Vec<SchemaChange> {
+    let mut changes = Vec::new();
+
+    for table in old.tables.keys().filter(|t| new.table(t).is_none()) {
+        changes.push(SchemaChange::DropTable { table: table.clone() });
+    }
+
+    for (table, new_columns) in &new.tables {
+        let Some(old_columns) = old.table(table) else {
+            changes.push(SchemaChange::AddTable { table: table.clone(), columns: new_columns.clone() });
+            continue;
+        };
+        let find = |columns: &[TableColumn], name: &str| {
+            columns.iter().find(|c| c.column_name.eq_ignore_ascii_case(name)).cloned()
+        };
+
+        for old_column in old_columns {
+            match find(new_columns, &old_column.column_name) {
+                None => changes.push(SchemaChange::DropColumn {
+                    table: table.clone(),
+                    column: old_column.column_name.value.clone(),
+                }),
+                Some(new_column) => {
+                    if new_column.column_type != old_column.column_type {
+                        changes.push(SchemaChange::RetypeColumn {
+                            table: table.clone(),
+                            column: new_column.column_name.value.clone(),
+                            from: old_column.column_type.clone(),
+                            to: new_column.column_type.clone(),
+                        });
+                    }
+                    if new_column.constraints != old_column.constraints {
+                        changes.push(SchemaChange::ChangeConstraints {
+                            table: table.clone(),
+                            column: new_column.column_name.value.clone(),
+                            from: old_column.constraints.clone(),
+                            to: new_column.constraints,
+                        });
+                    }
+                }
+            }
+        }
+
+        for new_column in new_columns {
+            if find(old_columns, &new_column.column_name).is_none() {
+                changes.push(SchemaChange::AddColumn { table: table.clone(), column: new_column.clone() });
+            }
+        }
+    }
+
+    changes
+}
+
+impl SchemaChange {
+    /// Returns the statements that apply this change. Changes that need a
+    /// constraint name (dropping a PRIMARY KEY or CHECK) are emitted as
+    /// `--` comments for a human to finish.
+    pub fn to_alter_sql(&self, dialect: Dialect) -> Vec<String> {
+        let q = |name: &str| dialect.quote_identifier(name);
+        match self {
+            SchemaChange::AddTable { table, columns } => {
+                let create = Statement::CreateTable { table_name: Ident::new(table.as_str()), column_list: columns.clone() };
+                vec![create.to_sql(dialect)]
+            }
+            SchemaChange::DropTable { table } => vec![format!("DROP TABLE {}", q(table))],
+            SchemaChange::AddColumn { table, column } => {
+                vec![format!("ALTER TABLE {} ADD COLUMN {}", q(table), column.to_sql(dialect))]
+            }
+            SchemaChange::DropColumn { table, column } => {
+                vec![format!("ALTER TABLE {} DROP COLUMN {}", q(table), q(column))]
+            }
+            SchemaChange::RetypeColumn { table, column, to, .. } => {
+                vec![format!("ALTER TABLE {} ALTER COLUMN {} SET DATA TYPE {}", q(table), q(column), to)]
+            }
+            SchemaChange::ChangeConstraints { table, column, from, to } => {
+                let mut statements = Vec::new();
+                for removed in from.iter().filter(|c| !to.contains(c)) {
+                    statements.push(match removed {
+                        Constraint::NotNull => format!("ALTER TABLE {} ALTER COLUMN {} DROP NOT NULL", q(table), q(column)),
+                        Constraint::Collate(name) => {
+                            format!("-- drop COLLATE {} on {}.{} by retyping it", name, table, column)
+                        }
+                        other => format!("-- drop {} on {}.{} by constraint name", describe(other), table, column),
+                    });
+                }
+                for added in to.iter().filter(|c| !from.contains(c)) {
+                    statements.push(match added {
+                        Constraint::NotNull => format!("ALTER TABLE {} ALTER COLUMN {} SET NOT NULL", q(table), q(column)),
+                        Constraint::PrimaryKey => format!("ALTER TABLE {} ADD PRIMARY KEY ({})", q(table), q(column)),
+                        Constraint::Check(expr) => format!("ALTER TABLE {} ADD CHECK ({})", q(table), expr.to_sql(dialect)),
+                        Constraint::Collate(name) => {
+                            format!("-- add COLLATE {} on {}.{} by retyping it", name, table, column)
+                        }
+                    });
+                }
+                statements
+            }
+        }
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaChange::AddTable { table, columns } => write!(f, "+ table {} ({} columns)", table, columns.len()),
+            SchemaChange::DropTable { table } => write!(f, "- table {}", table),
+            SchemaChange::AddColumn { table, column } => {
+                write!(f, "+ column {}.{} {}", table, column.column_name, column.column_type)
+            }
+            SchemaChange::DropColumn { table, column } => write!(f, "- column {}.{}", table, column),
+            SchemaChange::RetypeColumn { table, column, from, to } => {
+                write!(f, "~ column {}.{}: {} -> {}", table, column, from, to)
+            }
+            SchemaChange::ChangeConstraints { table, column, from, to } => {
+                let list = |cs: &[Constraint]| cs.iter().map(describe).collect::<Vec<_>>().join(", ");
+                write!(f, "~ constraints {}.{}: [{}] -> [{}]", table, column, list(from), list(to))
+            }
+        }
+    }
+}
+
+fn describe(constraint: &Constraint) -> String {
+    match constraint {
+        Constraint::NotNull => "NOT NULL".to_string(),
+        Constraint::PrimaryKey => "PRIMARY KEY".to_string(),
+        Constraint::Check(expr) => format!("CHECK ({})", expr),
+        Constraint::Collate(name) => format!("COLLATE {}", name),
+    }
+}
@@ -0,0 +1,167 @@
+// semantics.rs — SQL's three-valued logic, and what arithmetic errors give
+// Author: Sreerag Devadasan
+//
+// A condition is TRUE, FALSE or UNKNOWN, and UNKNOWN is what NULL is as a
+// boolean. The evaluator, the bytecode VM and its constant folding, and
+// the columnar filters all follow the rules below; tests/semantics.rs
+// checks each of them against these functions, case by case.
+//
+// - NOT UNKNOWN is UNKNOWN.
+// - AND is FALSE when either side is FALSE, even if the other is UNKNOWN,
+//   and OR is TRUE when either side is TRUE. Otherwise an UNKNOWN side
+//   makes the result UNKNOWN.
+// - IS NULL and IS NOT NULL test for NULL, and are never UNKNOWN
+//   themselves: NULL IS NULL is TRUE, 1 IS NULL FALSE.
+// - IS DISTINCT FROM is != that treats NULL as a value: NULL is distinct
+//   from every other value but not from NULL, so it too is never UNKNOWN.
+//   IS NOT DISTINCT FROM is its negation.
+// - Every other operator is NULL when an operand is NULL: NULL = NULL and
+//   NULL != NULL are both NULL, as are NULL + 1 and NULL LIKE '%'.
+// - WHERE keeps a row only when its condition is TRUE. FALSE and UNKNOWN
+//   both reject it, so `NOT (x = 1)` doesn't keep the rows where x is NULL
+//   any more than `x = 1` does.
+//
+// An optimization may only rely on these rules. Constant folding, for one,
+// replaces a subexpression without columns by the value the evaluator
+// computes for it, and so can't turn `NULL = NULL` into TRUE or drop the
+// UNKNOWN of `NULL OR FALSE`.
+//
+// A truth value is an `Option<bool>`, `None` standing for UNKNOWN.
+//
+// Three errors can end an evaluation that SQL systems disagree about:
+// an overflow (`9223372036854775807 + 1`), a division by zero (`1 / 0`)
+// and a cast of a value that isn't one of the type (`CAST('abc' AS INT)`).
+// The standard raises all three, and so does this crate unless told
+// otherwise by an `ErrorHandling`, which can instead give NULL or saturate
+// each of them; MySQL, for one, gives NULL for `1 / 0` and 0 for the cast
+// (`Dialect::error_handling`). The evaluator, the VM and the engine all
+// follow the same `ErrorHandling`, and constant folding computes a
+// constant under it too, so `1 / 0` folds to NULL where it is NULL and is
+// left to fail at run time where it fails.
+
+use crate::prelude::*;
+use crate::ast::BinaryOperator;
+use crate::eval::{EvalError, Value};
+
+pub fn not(a: Option<bool>) -> Option<bool> {
+    a.map(|a| !a)
+}
+
+/// FALSE wins over UNKNOWN.
+pub fn and(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None,
+    }
+}
+
+/// TRUE wins over UNKNOWN.
+pub fn or(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        (Some(false), Some(false)) => Some(false),
+        _ => None,
+    }
+}
+
+/// The truth value of an operand of AND, OR or NOT, which must be a
+/// boolean or NULL.
+pub fn truth(value: &Value) -> Result<Option<bool>, EvalError> {
+    match value {
+        Value::Bool(b) => Ok(Some(*b)),
+        Value::Null => Ok(None),
+        other => Err(EvalError::TypeMismatch(format!("expected a boolean, found {:?}", other))),
+    }
+}
+
+/// A truth value as a value: UNKNOWN is NULL.
+pub fn value(truth: Option<bool>) -> Value {
+    truth.map_or(Value::Null, Value::Bool)
+}
+
+/// Whether `operator` is NULL whenever one of its operands is, which is
+/// every operator but AND, OR and IS [NOT] DISTINCT FROM.
+pub fn is_strict(operator: &BinaryOperator) -> bool {
+    !matches!(
+        operator,
+        BinaryOperator::And | BinaryOperator::Or | BinaryOperator::IsDistinctFrom | BinaryOperator::IsNotDistinctFrom
+    )
+}
+
+/// `value IS NULL`.
+pub fn is_null(value: &Value) -> bool {
+    *value == Value::Null
+}
+
+/// `a IS DISTINCT FROM b`, where `equal` compares them with `=` when
+/// neither is NULL.
+pub fn distinct(
+    a: &Value,
+    b: &Value,
+    equal: impl FnOnce(&Value, &Value) -> Result<Value, EvalError>,
+) -> Result<bool, EvalError> {
+    match (is_null(a), is_null(b)) {
+        (true, true) => Ok(false),
+        (true, false) | (false, true) => Ok(true),
+        (false, false) => Ok(!accepts(&equal(a, b)?)),
+    }
+}
+
+/// Whether WHERE keeps a row its condition gives `value` for.
+pub fn accepts(value: &Value) -> bool {
+    *value == Value::Bool(true)
+}
+
+// === Arithmetic errors ===
+
+/// What one kind of error gives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnError {
+    /// Fails the evaluation with the error.
+    #[default]
+    Raise,
+    /// Gives NULL.
+    Null,
+    /// Gives the value of the type nearest to the exact result; see the
+    /// fields of `ErrorHandling`.
+    Saturate,
+}
+
+/// What each kind of arithmetic error gives. The default raises them all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ErrorHandling {
+    /// A result too large for its type. Saturating gives the largest or the
+    /// smallest value the type has, by the sign of the exact result.
+    pub overflow: OnError,
+    /// Dividing by zero. Saturating gives the largest or the smallest
+    /// value by the sign of the dividend, and NULL for `0 / 0`.
+    pub division_by_zero: OnError,
+    /// A CAST of a value that isn't one of the type. Saturating reads a
+    /// string cast to a number as the number its first characters make, 0
+    /// if they make none, and gives NULL for a cast to any other type.
+    pub invalid_cast: OnError,
+}
+
+impl ErrorHandling {
+    /// Every error raised, as the standard has it.
+    pub const STRICT: ErrorHandling =
+        ErrorHandling { overflow: OnError::Raise, division_by_zero: OnError::Raise, invalid_cast: OnError::Raise };
+
+    /// What an evaluation that failed with `error` gives instead, with
+    /// `saturated` the value to give when saturating; errors of other kinds
+    /// are raised.
+    pub fn recover(&self, error: EvalError, saturated: impl FnOnce() -> Value) -> Result<Value, EvalError> {
+        let on_error = match error {
+            EvalError::Overflow => self.overflow,
+            EvalError::DivisionByZero => self.division_by_zero,
+            EvalError::InvalidCast(_) => self.invalid_cast,
+            _ => OnError::Raise,
+        };
+        match on_error {
+            OnError::Raise => Err(error),
+            OnError::Null => Ok(Value::Null),
+            OnError::Saturate => Ok(saturated()),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+// session.rs — reusable, shareable parser configuration
+// Author: Sreerag Devadasan
+//
+// A `ParserSession` bundles the options every parse should use, so callers
+// configure the dialect and limits once instead of at every call site. It
+// is plain data: cloning is cheap and it can be shared between threads.
+
+use crate::prelude::*;
+use crate::ast::Statement;
+use crate::batch::statement_spans_with_dialect;
+use crate::dialect::Dialect;
+use crate::extension::Extensions;
+use crate::parser::SQLParser;
+use crate::profile::{nonstandard_tokens, Profile, Warning};
+use crate::span::Span;
+use crate::tokenizer::{collect_tokens, Limit, ParseError, Token, Tokenizer};
+
+/// Default `max_expression_depth`: far deeper than any hand-written query,
+/// and shallow enough to stay well within a worker thread's stack.
+pub const DEFAULT_MAX_EXPRESSION_DEPTH: usize = 256;
+
+/// Settings for tokenizing and parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParserOptions {
+    /// Quoting rules used by the tokenizer, and the dialect-specific syntax
+    /// the parser accepts.
+    pub dialect: Dialect,
+    /// Whether to accept only standard SQL, what `dialect` does, or what
+    /// any dialect does; see profile.rs.
+    pub profile: Profile,
+    /// Read `"..."` as a string literal (`Some(true)`) or an identifier
+    /// (`Some(false)`) whatever the dialect says; `None` follows the dialect.
+    pub double_quoted_strings: Option<bool>,
+    /// Deepest allowed nesting of expressions (parentheses, unary and
+    /// binary operators), counted together with the queries, FROM items and
+    /// EXPLAIN or PREPARE statements they are inside; `None` for no limit.
+    pub max_expression_depth: Option<usize>,
+    /// Most tokens allowed in one statement; `None` for no limit.
+    pub max_tokens: Option<usize>,
+    /// Longest allowed statement text, in bytes; `None` for no limit.
+    pub max_statement_length: Option<usize>,
+    /// Accept CREATE TABLE and DROP TABLE.
+    pub allow_ddl: bool,
+    /// Accept row limiting clauses (LIMIT, OFFSET, TOP, FETCH).
+    pub allow_row_limits: bool,
+    /// Return statements that fail to parse as `Statement::Unparsed`
+    /// instead of an error. Exceeding a limit is still an error.
+    pub loose: bool,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            dialect: Dialect::Generic,
+            profile: Profile::Dialect,
+            double_quoted_strings: None,
+            max_expression_depth: Some(DEFAULT_MAX_EXPRESSION_DEPTH),
+            max_tokens: None,
+            max_statement_length: None,
+            allow_ddl: true,
+            allow_row_limits: true,
+            loose: false,
+        }
+    }
+}
+
+impl ParserOptions {
+    /// ANSI SQL only: no dialect's extensions, and warnings about the
+    /// nonstandard constructs every dialect accepts.
+    pub fn strict() -> Self {
+        Self { dialect: Dialect::Ansi, profile: Profile::Strict, ..Self::default() }
+    }
+
+    /// Everything any supported dialect accepts.
+    pub fn lenient() -> Self {
+        Self { profile: Profile::Lenient, ..Self::default() }
+    }
+
+    /// Whether ILIKE, case-insensitive LIKE, is an operator.
+    pub fn supports_ilike(&self) -> bool {
+        self.profile.allows(self.dialect.supports_ilike())
+    }
+
+    /// Whether a SELECT may have QUALIFY, and `*` EXCLUDE and REPLACE.
+    pub fn supports_warehouse_extensions(&self) -> bool {
+        self.profile.allows(self.dialect.supports_warehouse_extensions())
+    }
+
+    /// Whether a FROM item may be followed by PIVOT and UNPIVOT.
+    pub fn supports_pivot(&self) -> bool {
+        self.profile.allows(self.dialect.supports_pivot())
+    }
+
+    /// Whether `[...]` delimits an identifier.
+    pub fn bracketed_identifiers(&self) -> bool {
+        self.profile.allows(self.dialect.bracketed_identifiers())
+    }
+}
+
+/// Parses statements with a fixed set of options, and the statements of
+/// any extensions given.
+#[derive(Debug, Clone, Default)]
+pub struct ParserSession {
+    options: ParserOptions,
+    extensions: Extensions,
+}
+
+// Sessions are meant to be shared by worker threads
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ParserSession>();
+};
+
+impl ParserSession {
+    pub fn new(options: ParserOptions) -> Self {
+        Self { options, extensions: Extensions::default() }
+    }
+
+    /// The session, also parsing the statements `extensions` handles.
+    pub fn with_extensions(mut self, extensions: Extensions) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    pub fn options(&self) -> &ParserOptions {
+        &self.options
+    }
+
+    /// Tokenizes `sql`, enforcing the length and token limits; input that
+    /// doesn't tokenize fails with its lexical errors.
+    pub fn tokenize(&self, sql: &str) -> Result<Vec<Token>, ParseError> {
+        if let Some(max) = self.options.max_statement_length {
+            if sql.len() > max {
+                return Err(ParseError::LimitExceeded { limit: Limit::StatementLength, max });
+            }
+        }
+        let mut tokenizer = self.tokenizer(sql);
+        let tokens = collect_tokens(&mut tokenizer, self.options.max_tokens)?;
+        match tokenizer.errors() {
+            [] => Ok(tokens),
+            errors => Err(ParseError::Lex(errors.to_vec())),
+        }
+    }
+
+    fn tokenizer(&self, sql: &str) -> Tokenizer {
+        let tokenizer = Tokenizer::with_dialect(sql, self.options.dialect)
+            .with_bracketed_identifiers(self.options.bracketed_identifiers());
+        match self.options.double_quoted_strings {
+            Some(strings) => tokenizer.with_double_quoted_strings(strings),
+            None => tokenizer,
+        }
+    }
+
+    /// Parses a single statement.
+    pub fn parse(&self, sql: &str) -> Result<Statement, ParseError> {
+        self.parse_at(sql, 0)
+    }
+
+    /// The constructs of `sql` outside standard SQL when the profile is
+    /// strict, with spans into `sql`; none otherwise.
+    pub fn warnings(&self, sql: &str) -> Vec<Warning> {
+        match self.options.profile {
+            Profile::Strict => nonstandard_tokens(sql, self.tokenizer(sql)),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Splits a script on top-level semicolons and parses each statement.
+    /// In loose mode the spans of unparsed statements are script offsets.
+    pub fn parse_script(&self, script: &str) -> Vec<Result<Statement, ParseError>> {
+        let spans = statement_spans_with_dialect(script, self.options.dialect);
+        spans.into_iter().map(|span| self.parse_at(span.slice(script), span.start)).collect()
+    }
+
+    /// Parses `sql`, which starts at byte `offset` of the text being parsed.
+    pub(crate) fn parse_at(&self, sql: &str, offset: usize) -> Result<Statement, ParseError> {
+        self.parse_counted(sql, offset).0
+    }
+
+    /// Like `parse_at`, also returning the number of tokens in `sql` (not
+    /// counting Eof), or 0 when it couldn't be tokenized.
+    pub(crate) fn parse_counted(&self, sql: &str, offset: usize) -> (Result<Statement, ParseError>, usize) {
+        let unparsed = || Statement::Unparsed { raw_sql: sql.to_string(), span: Span::new(offset, offset + sql.len()) };
+        let tokens = match self.tokenize(sql) {
+            Ok(tokens) => tokens,
+            Err(ParseError::Lex(_)) if self.options.loose => return (Ok(unparsed()), 0),
+            Err(error) => return (Err(error), 0),
+        };
+        let mut parser = SQLParser::with_options(&tokens, self.options).with_extensions(&self.extensions);
+        let result = match parser.parse_to_end() {
+            Err(error) if self.options.loose && !matches!(error, ParseError::LimitExceeded { .. }) => Ok(unparsed()),
+            result => result,
+        };
+        (result, tokens.len() - 1)
+    }
+}
@@ -0,0 +1,262 @@
+// sexp.rs — the AST of a statement as a compact s-expression
+// Author: Sreerag Devadasan
+//
+// `Statement::to_sexp` prints a statement on one line, lisp-style, for the
+// REPL and for eyeballing trees in logs and bug reports where `{:#?}` is
+// far too tall:
+//
+//   (select (columns id name) (from users) (where (> age 18)))
+//
+// Like `graph`, it reads the code `codegen` generates, so every node is
+// covered: a struct or enum variant becomes a list headed by its name in
+// kebab case, each non-empty field a `(field value ...)` list, and empty
+// fields (None, false, empty lists, defaults) are left out. Expressions
+// are written the way lisp writes them: operators and functions head their
+// operands, `(and (= a 1) (not b))`, grouping is implied by the nesting,
+// names are bare (`|Name|` when quoted in SQL, `t.id` when qualified) and
+// strings are in double quotes. The output depends on the tree alone, so
+// it is the same from one run and one build to the next.
+
+use crate::prelude::*;
+use crate::ast::{BinaryOperator, Statement};
+use crate::codegen::{statement_code, Code};
+use crate::dialect::Dialect;
+
+/// Every binary operator as `codegen` names it, with the symbol it heads
+/// its operands with.
+const OPERATORS: [BinaryOperator; 15] = [
+    BinaryOperator::Equals,
+    BinaryOperator::NotEquals,
+    BinaryOperator::GreaterThan,
+    BinaryOperator::GreaterThanOrEqual,
+    BinaryOperator::LessThan,
+    BinaryOperator::LessThanOrEqual,
+    BinaryOperator::And,
+    BinaryOperator::Or,
+    BinaryOperator::Add,
+    BinaryOperator::Subtract,
+    BinaryOperator::Multiply,
+    BinaryOperator::Divide,
+    BinaryOperator::Like,
+    BinaryOperator::ILike,
+    BinaryOperator::Concat,
+];
+
+impl Statement {
+    /// The AST as an s-expression on one line.
+    pub fn to_sexp(&self) -> String {
+        let mut out = String::new();
+        match self {
+            // Its code is only the value's `Debug`, so it's shown as SQL
+            Statement::Custom(custom) => {
+                out.push_str(&format!("(custom {:?} {:?})", custom.kind(), custom.to_sql(Dialect::Generic)));
+            }
+            statement => write(&statement_code(statement), &mut out),
+        }
+        out
+    }
+}
+
+/// Appends the s-expression for `code` to `out`.
+fn write(code: &Code, out: &mut String) {
+    match code {
+        Code::Seq { head: "Some" | "Box::new", items, .. } if items.len() == 1 => write(&items[0], out),
+        Code::Seq { head: "Ident::new", items, .. } if items.len() == 1 => out.push_str(&literal(&items[0])),
+        Code::Seq { head: "Ident::quoted", items, .. } if !items.is_empty() => {
+            out.push_str(&format!("|{}|", literal(&items[0])));
+        }
+        Code::Seq { head: "vec!", items, .. } => list("", items, out),
+        Code::Seq { head: "Expression::Grouped", items, .. } if items.len() == 1 => write(&items[0], out),
+        Code::Seq { head: "Expression::CompoundIdentifier", items, .. } if items.len() == 1 => match &items[0] {
+            Code::Seq { items: parts, .. } => {
+                let parts: Vec<String> = parts.iter().map(sexp).collect();
+                out.push_str(&parts.join("."));
+            }
+            part => write(part, out),
+        },
+        Code::Seq { head: "Expression::Placeholder", items, .. } if items.len() == 1 => {
+            out.push_str(&literal(&items[0]));
+        }
+        // The other expressions of one value are that value: a name, a
+        // number, a string or a boolean
+        Code::Seq { head, items, .. } if head.starts_with("Expression::") && items.len() == 1 => write(&items[0], out),
+        Code::Seq { head, items, .. } => list(&kebab(short_name(head)), items, out),
+        Code::Struct { path: "Expression::BinaryOperation", fields } => {
+            let operator = field(fields, "operator").map(operator).unwrap_or_default();
+            let operands = [field(fields, "left_operand"), field(fields, "right_operand")];
+            call(&operator, operands.into_iter().flatten(), out);
+        }
+        Code::Struct { path: "Expression::UnaryOperation", fields } => {
+            let operator = match field(fields, "operator") {
+                Some(Code::Atom(text)) if text == "UnaryOperator::Negate" => "-".to_string(),
+                Some(operator) => sexp(operator),
+                None => String::new(),
+            };
+            call(&operator, field(fields, "operand"), out);
+        }
+        Code::Struct { path: "Expression::Function", fields } => {
+            let name = field(fields, "name").map(literal).unwrap_or_default();
+            let args = match field(fields, "args") {
+                Some(Code::Seq { items, .. }) => items.iter().collect(),
+                _ => Vec::new(),
+            };
+            out.push('(');
+            out.push_str(&name);
+            for arg in args {
+                out.push(' ');
+                write(arg, out);
+            }
+            // An empty window is still a window: `(over)`
+            if let Some(over) = field(fields, "over").filter(|over| !is_empty(over)) {
+                out.push_str(" (over");
+                fields_of(over, out);
+                out.push(')');
+            }
+            out.push(')');
+        }
+        // A select item reads as its expression, `(as expr alias)` if named
+        Code::Struct { path: "SelectItem", fields } => match (field(fields, "expr"), field(fields, "alias")) {
+            (Some(expr), Some(alias)) if !is_empty(alias) => call("as", [expr, alias], out),
+            (Some(expr), _) => write(expr, out),
+            _ => fields_of(code, out),
+        },
+        // A plain table reads as its name
+        Code::Struct { path: "TableFactor::Table", fields } if fields[1..].iter().all(|(_, value)| is_empty(value)) => {
+            write(&fields[0].1, out);
+        }
+        Code::Struct { path, .. } => {
+            out.push('(');
+            out.push_str(&kebab(short_name(path)));
+            fields_of(code, out);
+            out.push(')');
+        }
+        Code::Atom(text) => out.push_str(&atom(text)),
+    }
+}
+
+/// Appends ` (field value)` for each non-empty field of the struct `code`,
+/// splicing lists, and the fields of a struct, into the field's list.
+fn fields_of(code: &Code, out: &mut String) {
+    let fields = match code {
+        Code::Seq { head: "Some" | "Box::new", items, .. } if items.len() == 1 => return fields_of(&items[0], out),
+        Code::Struct { fields, .. } => fields,
+        _ => return,
+    };
+    for (name, value) in fields {
+        if is_empty(value) {
+            continue;
+        }
+        out.push(' ');
+        let name = match *name {
+            "selection" => "where".to_string(),
+            name => name.replace('_', "-"),
+        };
+        match unwrap(value) {
+            Code::Seq { head: "vec!", items, .. } => list(&name, items, out),
+            // A struct's own name adds nothing to the field's
+            value @ Code::Struct { path, .. } if !path.contains("::") => {
+                out.push('(');
+                out.push_str(&name);
+                fields_of(value, out);
+                out.push(')');
+            }
+            value => call(&name, Some(value), out),
+        }
+    }
+}
+
+/// `(head item ...)`, or `(item ...)` for an empty head.
+fn list(head: &str, items: &[Code], out: &mut String) {
+    call(head, items.iter(), out);
+}
+
+fn call<'c>(head: &str, items: impl IntoIterator<Item = &'c Code>, out: &mut String) {
+    out.push('(');
+    out.push_str(head);
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 || !head.is_empty() {
+            out.push(' ');
+        }
+        write(item, out);
+    }
+    out.push(')');
+}
+
+fn sexp(code: &Code) -> String {
+    let mut out = String::new();
+    write(code, &mut out);
+    out
+}
+
+fn field<'c>(fields: &'c [(&'static str, Code)], name: &str) -> Option<&'c Code> {
+    fields.iter().find(|(field, _)| *field == name).map(|(_, value)| value)
+}
+
+/// `code` without the `Some` and `Box::new` around it.
+fn unwrap(code: &Code) -> &Code {
+    match code {
+        Code::Seq { head: "Some" | "Box::new", items, .. } if items.len() == 1 => unwrap(&items[0]),
+        code => code,
+    }
+}
+
+fn is_empty(code: &Code) -> bool {
+    let Code::Atom(text) = code else { return false };
+    text == "None" || text == "false" || text == "Vec::new()" || text.ends_with("::default()")
+}
+
+/// The symbol of the operator `code` names, or its name in kebab case for
+/// one without a symbol of its own here.
+fn operator(code: &Code) -> String {
+    let Code::Atom(text) = code else { return sexp(code) };
+    OPERATORS
+        .iter()
+        .find(|operator| format!("BinaryOperator::{:?}", operator) == *text)
+        .map(|operator| operator.to_string().to_lowercase())
+        .unwrap_or_else(|| atom(text))
+}
+
+/// The text of a generated string literal, `"a".to_string()` or `"a"`,
+/// without its quotes.
+fn literal(code: &Code) -> String {
+    match code {
+        Code::Atom(text) => {
+            let text = text.strip_suffix(".to_string()").unwrap_or(text);
+            text.strip_prefix('"').and_then(|text| text.strip_suffix('"')).unwrap_or(text).to_string()
+        }
+        code => sexp(code),
+    }
+}
+
+/// An atom of generated code in an s-expression: strings keep their
+/// quotes, and paths such as `JoinKind::Inner` become `inner`.
+fn atom(text: &str) -> String {
+    if let Some(string) = text.strip_suffix(".to_string()") {
+        string.to_string()
+    } else if text.starts_with('"') || !text.contains("::") {
+        text.to_string()
+    } else {
+        kebab(short_name(text))
+    }
+}
+
+/// `Statement::Select` to `Select`.
+fn short_name(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+/// `CreateTable` to `create-table`.
+fn kebab(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                out.push('-');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
@@ -0,0 +1,173 @@
+// span.rs — byte ranges of AST nodes in the original query text
+// Author: Sreerag Devadasan
+//
+// Spans are kept next to the AST rather than inside it, so comparing,
+// hashing and rewriting statements never has to think about positions.
+// `parse_with_source_map` returns a `SourceMap` holding the range of every
+// name, statement, FROM item, join and expression node of the statement;
+// tools such as a column rename use it to edit exactly those bytes of the
+// original text.
+//
+// The parser records the first and last token of each node as it builds
+// it, so a span is exactly what the node was parsed from. The nodes of
+// each kind are listed in the order `visit::walk` visits them, which is
+// how the `*_spans` methods find the node each span belongs to. Nodes the
+// parser makes up, such as the comparisons `a BETWEEN b AND c` stands for,
+// span the text they were read from.
+
+use core::ops::Range;
+
+use crate::prelude::*;
+use crate::ast::{Expression, Join, Statement, TableFactor};
+use crate::dialect::Dialect;
+use crate::parser::SQLParser;
+use crate::session::ParserOptions;
+use crate::tokenizer::{tokenize_with_errors, ParseError};
+use crate::visit::{walk_nodes, Nodes};
+
+/// A byte range `start..end` in the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash, PartialOrd, Ord)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// The smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    /// The text this span covers in `source`.
+    pub fn slice(self, source: &str) -> &str {
+        &source[self.start..self.end]
+    }
+}
+
+/// Source ranges of the parts of one parsed statement.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    /// The whole statement, from its first to its last consumed token.
+    pub statement: Span,
+    /// The table name (FROM table, or the table of CREATE/DROP TABLE).
+    pub table: Option<Span>,
+    /// SELECT list items, or the column names of a CREATE TABLE.
+    pub columns: Vec<Span>,
+    /// ORDER BY items.
+    pub order_by: Vec<Span>,
+    /// The statement and every statement in it, outer ones first.
+    pub statements: Vec<Span>,
+    /// Every FROM and JOIN item, outer ones first.
+    pub factors: Vec<Span>,
+    /// Every join, from its keywords to the end of its ON condition (a
+    /// comma join is only its table).
+    pub joins: Vec<Span>,
+    /// Every expression node, parents first.
+    pub expressions: Vec<Span>,
+}
+
+impl SourceMap {
+    /// Pairs the statement and every statement in it with its span.
+    pub fn statement_spans<'a>(&self, statement: &'a Statement) -> Vec<(&'a Statement, Span)> {
+        let nodes = Collected::of(statement);
+        nodes.statements.into_iter().zip(self.statements.iter().copied()).collect()
+    }
+
+    /// Pairs every FROM and JOIN item of `statement` with its span.
+    pub fn factor_spans<'a>(&self, statement: &'a Statement) -> Vec<(&'a TableFactor, Span)> {
+        let nodes = Collected::of(statement);
+        nodes.factors.into_iter().zip(self.factors.iter().copied()).collect()
+    }
+
+    /// Pairs every join of `statement` with its span.
+    pub fn join_spans<'a>(&self, statement: &'a Statement) -> Vec<(&'a Join, Span)> {
+        let nodes = Collected::of(statement);
+        nodes.joins.into_iter().zip(self.joins.iter().copied()).collect()
+    }
+
+    /// Pairs every expression node of `statement` with its span.
+    pub fn expression_spans<'a>(&self, statement: &'a Statement) -> Vec<(&'a Expression, Span)> {
+        let nodes = Collected::of(statement);
+        nodes.expressions.into_iter().zip(self.expressions.iter().copied()).collect()
+    }
+}
+
+/// The nodes of a statement, in the order `visit::walk` visits them.
+#[derive(Default)]
+struct Collected<'a> {
+    statements: Vec<&'a Statement>,
+    factors: Vec<&'a TableFactor>,
+    joins: Vec<&'a Join>,
+    expressions: Vec<&'a Expression>,
+}
+
+impl<'a> Collected<'a> {
+    fn of(statement: &'a Statement) -> Self {
+        let mut nodes = Collected::default();
+        walk_nodes(statement, &mut nodes);
+        nodes
+    }
+}
+
+impl<'a> Nodes<'a> for Collected<'a> {
+    fn statement(&mut self, statement: &'a Statement) {
+        self.statements.push(statement);
+    }
+
+    fn table_factor(&mut self, factor: &'a TableFactor) {
+        self.factors.push(factor);
+    }
+
+    fn join(&mut self, join: &'a Join) {
+        self.joins.push(join);
+    }
+
+    fn expression(&mut self, expr: &'a Expression) {
+        self.expressions.push(expr);
+    }
+}
+
+/// Parses one statement and records where each of its parts came from.
+pub fn parse_with_source_map(input: &str, dialect: Dialect) -> Result<(Statement, SourceMap), ParseError> {
+    let (tokens, token_spans, errors) = tokenize_with_errors(input, dialect);
+    if !errors.is_empty() {
+        return Err(ParseError::Lex(errors));
+    }
+    let mut parser = SQLParser::with_options(&tokens, ParserOptions { dialect, ..ParserOptions::default() });
+    let statement = parser.parse_statement()?;
+    let marks = parser.marks();
+
+    // Eof never counts as part of the statement
+    let consumed = parser.position().clamp(1, tokens.len().saturating_sub(1).max(1));
+    let spans = |ranges: &[Range<usize>]| -> Vec<Span> {
+        ranges.iter().map(|range| token_spans[range.start].to(token_spans[range.end - 1])).collect()
+    };
+    let map = SourceMap {
+        statement: token_spans[0].to(token_spans[consumed - 1]),
+        table: marks.table.map(|i| token_spans[i]),
+        columns: marks.columns.iter().map(|&i| token_spans[i]).collect(),
+        order_by: spans(&marks.order_by),
+        statements: spans(&marks.statements),
+        factors: spans(&marks.factors),
+        joins: spans(&marks.joins),
+        expressions: spans(&marks.expressions),
+    };
+    Ok((statement, map))
+}
+
+/// The span of the token a parse of `input` fails at, or of its first
+/// lexical error, for pointing at the error; None when the input parses.
+pub fn error_span(input: &str, dialect: Dialect) -> Option<Span> {
+    let (tokens, token_spans, errors) = tokenize_with_errors(input, dialect);
+    if let Some(error) = errors.first() {
+        return Some(error.span);
+    }
+    let mut parser = SQLParser::with_options(&tokens, ParserOptions { dialect, ..ParserOptions::default() });
+    parser.parse_to_end().err()?;
+    let at = parser.position().max(parser.furthest());
+    token_spans.get(at).or(token_spans.last()).copied()
+}
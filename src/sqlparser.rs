@@ -0,0 +1,1712 @@
+// sqlparser.rs — statements converted to and from the AST of sqlparser-rs
+// Author: Sreerag Devadasan
+//
+// Tools built on sqlparser-rs can hand their statements to this crate and
+// take this crate's back without either depending on the other: a
+// statement goes across as the JSON that sqlparser's `serde` feature
+// writes for a `sqlparser::ast::Statement`, much as arrow.rs speaks the
+// Arrow IPC format rather than linking the arrow crate. On the other side
+// `serde_json::from_str::<Statement>` reads what `to_json` writes, and
+// `from_json` reads what `serde_json::to_string` makes of a statement.
+//
+// The JSON follows the AST of sqlparser 0.52. Enums are externally
+// tagged, as serde writes them: `{"Variant": data}`, or a unit variant as
+// the string `"Variant"`. Every field is written, `None` as null, since
+// serde only fills in missing fields that are `Option`s; fields a later
+// version added are ignored by older ones. Reading looks the fields up by
+// name and skips those it doesn't know, so it also takes the JSON of
+// neighbouring versions, down to the `{"Identifier": ident}` parts of
+// names in 0.55.
+//
+// Queries, INSERT (of VALUES or of a query), UPDATE, DELETE and DROP
+// TABLE convert both ways, with the expressions, FROM items and types
+// this crate parses. A node the other AST has no room for, such as MERGE
+// or TABLESAMPLE here or `Select.having` from sqlparser, fails with
+// `Unsupported` naming it rather than being dropped. sqlparser keeps the
+// ORDER BY, LIMIT, OFFSET and FOR clauses of a set operation on the query
+// around it, where this crate keeps them on its last SELECT; BETWEEN and
+// EXTRACT are read as the parser reads them (see pratt.rs).
+
+use core::fmt;
+
+use crate::prelude::*;
+use crate::ast::{
+    BinaryOperator, Cte, DBType, Expression, GroupingElement, Join, JoinKind, LockStrength, LockWait, LockingClause,
+    OrderByExpr, SelectItem, SetOperator, Statement, StringPrefix, TableAlias, TableFactor, UnaryOperator,
+    WildcardOptions, WindowSpec,
+};
+use crate::decimal::MAX_PRECISION;
+use crate::ident::{Ident, QuoteStyle};
+use crate::json;
+use crate::pratt::{between, decimal_type};
+
+// === Errors ===
+
+/// Errors raised while converting a statement to or from sqlparser's AST.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlparserError {
+    /// The text isn't JSON, or not JSON of sqlparser's AST.
+    Malformed(String),
+    /// A node that only one of the two ASTs has.
+    Unsupported(String),
+}
+
+impl fmt::Display for SqlparserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SqlparserError::Malformed(e) => write!(f, "Malformed sqlparser JSON: {}", e),
+            SqlparserError::Unsupported(node) => write!(f, "{} can't be converted between the two ASTs", node),
+        }
+    }
+}
+
+impl core::error::Error for SqlparserError {}
+
+fn unsupported<T>(node: impl Into<String>) -> Result<T, SqlparserError> {
+    Err(SqlparserError::Unsupported(node.into()))
+}
+
+fn malformed(expected: &str, found: &Json) -> SqlparserError {
+    SqlparserError::Malformed(format!("expected {}, found {}", expected, found.describe()))
+}
+
+/// Writes `statement` as the JSON of the sqlparser `Statement` for it.
+pub fn to_json(statement: &Statement) -> Result<String, SqlparserError> {
+    Ok(write_statement(statement)?.to_string())
+}
+
+/// Reads a statement from the JSON of a sqlparser `Statement`.
+pub fn from_json(text: &str) -> Result<Statement, SqlparserError> {
+    read_statement(&Reader::parse(text)?)
+}
+
+// === JSON ===
+
+/// Objects keep their fields in order, and numbers are kept as written.
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+const NULL: &Json = &Json::Null;
+
+/// How deeply arrays and objects may nest, so that reading hostile input
+/// can't overflow the stack.
+const MAX_DEPTH: usize = 512;
+
+impl Json {
+    /// The field `key` of an object, or null when there is none.
+    fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map_or(NULL, |(_, value)| value),
+            _ => NULL,
+        }
+    }
+
+    fn is_null(&self) -> bool {
+        *self == Json::Null
+    }
+
+    fn is_true(&self) -> bool {
+        *self == Json::Bool(true)
+    }
+
+    /// Whether the value is null, false or empty: what serde writes for a
+    /// clause that isn't there.
+    fn is_empty(&self) -> bool {
+        match self {
+            Json::Null | Json::Bool(false) => true,
+            Json::Array(items) => items.is_empty(),
+            Json::Object(fields) => fields.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// The items of an array, none for null.
+    fn list(&self) -> Result<&[Json], SqlparserError> {
+        match self {
+            Json::Null => Ok(&[]),
+            Json::Array(items) => Ok(items),
+            other => Err(malformed("an array", other)),
+        }
+    }
+
+    fn as_str(&self) -> Result<&str, SqlparserError> {
+        match self {
+            Json::String(s) => Ok(s),
+            other => Err(malformed("a string", other)),
+        }
+    }
+
+    fn as_u64(&self) -> Result<u64, SqlparserError> {
+        match self {
+            Json::Number(n) => n.parse().map_err(|_| malformed("an unsigned integer", self)),
+            other => Err(malformed("an unsigned integer", other)),
+        }
+    }
+
+    /// The name and data of an enum variant, null data for a unit variant.
+    fn variant(&self) -> Result<(&str, &Json), SqlparserError> {
+        match self {
+            Json::String(name) => Ok((name, NULL)),
+            Json::Object(fields) if fields.len() == 1 => Ok((&fields[0].0, &fields[0].1)),
+            other => Err(malformed("an enum variant", other)),
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Json::Null => "null",
+            Json::Bool(_) => "a boolean",
+            Json::Number(_) => "a number",
+            Json::String(_) => "a string",
+            Json::Array(_) => "an array",
+            Json::Object(_) => "an object",
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => f.write_str(n),
+            Json::String(s) => f.write_str(&json::string(s)),
+            Json::Array(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str("]")
+            }
+            Json::Object(fields) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}:{}", json::string(key), value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// A reader of JSON text, one value at a time.
+struct Reader<'a> {
+    text: &'a str,
+    at: usize,
+    depth: usize,
+}
+
+impl Reader<'_> {
+    /// The single value `text` holds.
+    fn parse(text: &str) -> Result<Json, SqlparserError> {
+        let mut reader = Reader { text, at: 0, depth: 0 };
+        let value = reader.value()?;
+        reader.whitespace();
+        if reader.at < text.len() {
+            return Err(SqlparserError::Malformed(format!("text after the JSON value at byte {}", reader.at)));
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, SqlparserError> {
+        self.whitespace();
+        let rest = &self.text[self.at..];
+        match rest.bytes().next() {
+            Some(b'{') => self.nested(b'}', |reader| {
+                let key = reader.string()?;
+                reader.punct(b':')?;
+                Ok((key, reader.value()?))
+            })
+            .map(Json::Object),
+            Some(b'[') => self.nested(b']', Reader::value).map(Json::Array),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'-' | b'0'..=b'9') => {
+                let number = |c: char| matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9');
+                let len = rest.find(|c: char| !number(c)).unwrap_or(rest.len());
+                self.at += len;
+                Ok(Json::Number(rest[..len].to_string()))
+            }
+            _ => {
+                for (word, value) in [("null", Json::Null), ("true", Json::Bool(true)), ("false", Json::Bool(false))] {
+                    if rest.starts_with(word) {
+                        self.at += word.len();
+                        return Ok(value);
+                    }
+                }
+                Err(self.error("a value"))
+            }
+        }
+    }
+
+    /// The items of an object or array, from its opening bracket to
+    /// `close`.
+    fn nested<T>(
+        &mut self,
+        close: u8,
+        mut item: impl FnMut(&mut Self) -> Result<T, SqlparserError>,
+    ) -> Result<Vec<T>, SqlparserError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(SqlparserError::Malformed(format!("JSON nested more than {} deep", MAX_DEPTH)));
+        }
+        self.at += 1;
+        let mut items = Vec::new();
+        self.whitespace();
+        if !self.eat(close) {
+            loop {
+                items.push(item(self)?);
+                self.whitespace();
+                if self.eat(close) {
+                    break;
+                }
+                self.punct(b',')?;
+            }
+        }
+        self.depth -= 1;
+        Ok(items)
+    }
+
+    fn string(&mut self) -> Result<String, SqlparserError> {
+        self.punct(b'"')?;
+        let mut out = String::new();
+        let mut chars = self.text[self.at..].char_indices();
+        while let Some((i, ch)) = chars.next() {
+            match ch {
+                '"' => {
+                    self.at += i + 1;
+                    return Ok(out);
+                }
+                '\\' => out.push(match chars.next().map(|(_, escape)| escape) {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('/') => '/',
+                    Some('b') => '\u{8}',
+                    Some('f') => '\u{c}',
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('t') => '\t',
+                    Some('u') => {
+                        let mut code = hex4(&mut chars)?;
+                        // A character past the first plane is a surrogate pair
+                        if (0xD800..0xDC00).contains(&code) {
+                            let low = match (chars.next(), chars.next()) {
+                                (Some((_, '\\')), Some((_, 'u'))) => hex4(&mut chars)?,
+                                _ => 0,
+                            };
+                            if !(0xDC00..0xE000).contains(&low) {
+                                return Err(SqlparserError::Malformed("an unpaired surrogate in a string".into()));
+                            }
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                        }
+                        char::from_u32(code)
+                            .ok_or_else(|| SqlparserError::Malformed("an unpaired surrogate in a string".into()))?
+                    }
+                    _ => return Err(SqlparserError::Malformed("an unknown escape in a string".into())),
+                }),
+                ch => out.push(ch),
+            }
+        }
+        Err(SqlparserError::Malformed("a string without its closing quote".into()))
+    }
+
+    fn whitespace(&mut self) {
+        let rest = &self.text[self.at..];
+        self.at += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let found = self.text.as_bytes().get(self.at) == Some(&byte);
+        if found {
+            self.at += 1;
+        }
+        found
+    }
+
+    fn punct(&mut self, byte: u8) -> Result<(), SqlparserError> {
+        self.whitespace();
+        match self.eat(byte) {
+            true => Ok(()),
+            false => Err(self.error(&format!("'{}'", byte as char))),
+        }
+    }
+
+    fn error(&self, expected: &str) -> SqlparserError {
+        match self.text[self.at..].chars().next() {
+            Some(found) => {
+                SqlparserError::Malformed(format!("expected {}, found '{}' at byte {}", expected, found, self.at))
+            }
+            None => SqlparserError::Malformed(format!("expected {}, found the end of the text", expected)),
+        }
+    }
+}
+
+fn hex4(chars: &mut core::str::CharIndices) -> Result<u32, SqlparserError> {
+    let mut code = 0;
+    for _ in 0..4 {
+        let digit = chars.next().and_then(|(_, c)| c.to_digit(16));
+        code = code * 16 + digit.ok_or_else(|| SqlparserError::Malformed("a bad \\u escape in a string".into()))?;
+    }
+    Ok(code)
+}
+
+// === From this crate's AST ===
+
+/// `{"name": data}`, serde's form of a variant with data.
+fn variant(name: &str, data: Json) -> Json {
+    Json::Object(vec![(name.to_string(), data)])
+}
+
+/// `"name"`, a unit variant.
+fn unit(name: &str) -> Json {
+    Json::String(name.to_string())
+}
+
+fn object(fields: Vec<(&str, Json)>) -> Json {
+    Json::Object(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+}
+
+fn number(n: u64) -> Json {
+    Json::Number(n.to_string())
+}
+
+fn empty() -> Json {
+    Json::Array(Vec::new())
+}
+
+/// sqlparser's names for the binary operators other than LIKE and ILIKE,
+/// which are expressions of their own there.
+const BINARY_OPERATORS: [(BinaryOperator, &str); 13] = [
+    (BinaryOperator::Equals, "Eq"),
+    (BinaryOperator::NotEquals, "NotEq"),
+    (BinaryOperator::GreaterThan, "Gt"),
+    (BinaryOperator::GreaterThanOrEqual, "GtEq"),
+    (BinaryOperator::LessThan, "Lt"),
+    (BinaryOperator::LessThanOrEqual, "LtEq"),
+    (BinaryOperator::And, "And"),
+    (BinaryOperator::Or, "Or"),
+    (BinaryOperator::Add, "Plus"),
+    (BinaryOperator::Subtract, "Minus"),
+    (BinaryOperator::Multiply, "Multiply"),
+    (BinaryOperator::Divide, "Divide"),
+    (BinaryOperator::Concat, "StringConcat"),
+];
+
+fn write_statement(statement: &Statement) -> Result<Json, SqlparserError> {
+    match statement {
+        Statement::Select { .. }
+        | Statement::SetOperation { .. }
+        | Statement::With { .. }
+        | Statement::Values { .. } => Ok(variant("Query", write_query(statement)?)),
+        Statement::Insert { table_name, columns, rows } => {
+            let values = write_query(&Statement::Values { rows: rows.clone() })?;
+            Ok(write_insert(table_name, columns, values))
+        }
+        Statement::InsertSelect { table_name, columns, query } => {
+            Ok(write_insert(table_name, columns, write_query(query)?))
+        }
+        Statement::Update { table_name, alias, assignments, selection } => {
+            let assignments = assignments
+                .iter()
+                .map(|(column, value)| {
+                    let target = variant("ColumnName", write_name(column));
+                    Ok(object(vec![("target", target), ("value", write_expr(value)?)]))
+                })
+                .collect::<Result<_, SqlparserError>>()?;
+            Ok(variant(
+                "Update",
+                object(vec![
+                    ("table", write_target(table_name, alias)),
+                    ("assignments", Json::Array(assignments)),
+                    ("from", Json::Null),
+                    ("selection", write_opt_expr(selection)?),
+                    ("returning", Json::Null),
+                    ("or", Json::Null),
+                ]),
+            ))
+        }
+        Statement::Delete { table_name, alias, selection } => Ok(variant(
+            "Delete",
+            object(vec![
+                ("tables", empty()),
+                ("from", variant("WithFromKeyword", Json::Array(vec![write_target(table_name, alias)]))),
+                ("using", Json::Null),
+                ("selection", write_opt_expr(selection)?),
+                ("returning", Json::Null),
+                ("order_by", empty()),
+                ("limit", Json::Null),
+            ]),
+        )),
+        Statement::DropTable { table_name, if_exists } => Ok(variant(
+            "Drop",
+            object(vec![
+                ("object_type", unit("Table")),
+                ("if_exists", Json::Bool(*if_exists)),
+                ("names", Json::Array(vec![write_name(table_name)])),
+                ("cascade", Json::Bool(false)),
+                ("restrict", Json::Bool(false)),
+                ("purge", Json::Bool(false)),
+                ("temporary", Json::Bool(false)),
+            ]),
+        )),
+        other => unsupported(other.kind()),
+    }
+}
+
+fn write_insert(table_name: &Ident, columns: &Option<Vec<Ident>>, source: Json) -> Json {
+    variant(
+        "Insert",
+        object(vec![
+            ("or", Json::Null),
+            ("ignore", Json::Bool(false)),
+            ("into", Json::Bool(true)),
+            ("table_name", write_name(table_name)),
+            ("table_alias", Json::Null),
+            ("columns", Json::Array(columns.iter().flatten().map(write_ident).collect())),
+            ("overwrite", Json::Bool(false)),
+            ("source", source),
+            ("partitioned", Json::Null),
+            ("after_columns", empty()),
+            ("table", Json::Bool(false)),
+            ("on", Json::Null),
+            ("returning", Json::Null),
+            ("replace_into", Json::Bool(false)),
+            ("priority", Json::Null),
+            ("insert_alias", Json::Null),
+        ]),
+    )
+}
+
+/// The table an UPDATE or DELETE writes, as a `TableWithJoins`.
+fn write_target(table_name: &Ident, alias: &Option<Ident>) -> Json {
+    let alias = alias.clone().map(|name| TableAlias { name, columns: Vec::new() });
+    write_from(write_table(table_name, &alias, Json::Null), Vec::new())
+}
+
+/// A `Query`: the WITH clause, then the body with the clauses sqlparser
+/// keeps on the query rather than on the body's last SELECT.
+fn write_query(statement: &Statement) -> Result<Json, SqlparserError> {
+    let (with, body) = match statement {
+        Statement::With { recursive, ctes, body } => {
+            let ctes = ctes.iter().map(write_cte).collect::<Result<_, SqlparserError>>()?;
+            (object(vec![("recursive", Json::Bool(*recursive)), ("cte_tables", Json::Array(ctes))]), &**body)
+        }
+        body => (Json::Null, body),
+    };
+    let (mut order_by, mut limit, mut offset, mut locks) = (Json::Null, Json::Null, Json::Null, empty());
+    if let Some(Statement::Select { order_by: items, limit: count, offset: skip, locking, .. }) = last_select(body) {
+        if !items.is_empty() {
+            let exprs = items.iter().map(|item| write_order_by(&item.expr, item.asc));
+            let exprs = exprs.collect::<Result<_, SqlparserError>>()?;
+            order_by = object(vec![("exprs", Json::Array(exprs)), ("interpolate", Json::Null)]);
+        }
+        if let Some(count) = count {
+            limit = write_number(&count.to_string());
+        }
+        if let Some(skip) = skip {
+            offset = object(vec![("value", write_number(&skip.to_string())), ("rows", unit("None"))]);
+        }
+        locks = Json::Array(locking.iter().map(write_lock).collect::<Result<_, SqlparserError>>()?);
+    }
+    Ok(object(vec![
+        ("with", with),
+        ("body", write_body(body, true)?),
+        ("order_by", order_by),
+        ("limit", limit),
+        ("limit_by", empty()),
+        ("offset", offset),
+        ("fetch", Json::Null),
+        ("locks", locks),
+        ("for_clause", Json::Null),
+        ("settings", Json::Null),
+        ("format_clause", Json::Null),
+    ]))
+}
+
+/// The SELECT whose ORDER BY, LIMIT, OFFSET and FOR clauses end `statement`.
+fn last_select(statement: &Statement) -> Option<&Statement> {
+    match statement {
+        Statement::Select { .. } => Some(statement),
+        Statement::SetOperation { right, .. } => last_select(right),
+        _ => None,
+    }
+}
+
+/// A `SetExpr`. The last SELECT of the query leaves its trailing clauses
+/// to the query; any other SELECT that has them is a query of its own.
+fn write_body(statement: &Statement, last: bool) -> Result<Json, SqlparserError> {
+    match statement {
+        Statement::Select { order_by, limit: None, offset: None, locking, .. }
+            if order_by.is_empty() && locking.is_empty() =>
+        {
+            Ok(variant("Select", write_select(statement)?))
+        }
+        Statement::Select { .. } if last => Ok(variant("Select", write_select(statement)?)),
+        Statement::Select { .. } | Statement::With { .. } => Ok(variant("Query", write_query(statement)?)),
+        Statement::SetOperation { operator, all, left, right } => {
+            let op = match operator {
+                SetOperator::Union => "Union",
+                SetOperator::Intersect => "Intersect",
+                SetOperator::Except => "Except",
+            };
+            Ok(variant(
+                "SetOperation",
+                object(vec![
+                    ("op", unit(op)),
+                    ("set_quantifier", unit(if *all { "All" } else { "None" })),
+                    ("left", write_body(left, false)?),
+                    ("right", write_body(right, last)?),
+                ]),
+            ))
+        }
+        Statement::Values { rows } => {
+            let rows = rows.iter().map(|row| write_exprs(row)).collect::<Result<_, SqlparserError>>()?;
+            Ok(variant("Values", object(vec![("explicit_row", Json::Bool(false)), ("rows", Json::Array(rows))])))
+        }
+        other => unsupported(other.kind()),
+    }
+}
+
+fn write_cte(cte: &Cte) -> Result<Json, SqlparserError> {
+    let alias = TableAlias { name: cte.name.clone(), columns: cte.columns.clone() };
+    Ok(object(vec![
+        ("alias", write_alias(&Some(alias))),
+        ("query", write_query(&cte.query)?),
+        ("from", Json::Null),
+        ("materialized", Json::Null),
+    ]))
+}
+
+fn write_select(statement: &Statement) -> Result<Json, SqlparserError> {
+    let Statement::Select { hints, columns, wildcard, from, joins, selection, group_by, qualify, .. } = statement else {
+        return unsupported(statement.kind());
+    };
+    if !hints.is_empty() {
+        return unsupported("Optimizer hints");
+    }
+    // The options belong to the first `*`, as the parser reads them
+    let none = WildcardOptions::default();
+    let mut options = Some(wildcard);
+    let mut projection = Vec::new();
+    for item in columns {
+        projection.push(match &item.alias {
+            _ if item.is_wildcard() => variant("Wildcard", write_wildcard(options.take().unwrap_or(&none))?),
+            Some(alias) => {
+                let fields = vec![("expr", write_expr(&item.expr)?), ("alias", write_ident(alias))];
+                variant("ExprWithAlias", object(fields))
+            }
+            None => variant("UnnamedExpr", write_expr(&item.expr)?),
+        });
+    }
+    let joins = joins.iter().map(write_join).collect::<Result<_, SqlparserError>>()?;
+    let group_by = group_by.iter().map(write_grouping).collect::<Result<_, SqlparserError>>()?;
+    Ok(object(vec![
+        ("distinct", Json::Null),
+        ("top", Json::Null),
+        ("top_before_distinct", Json::Bool(false)),
+        ("projection", Json::Array(projection)),
+        ("into", Json::Null),
+        ("from", Json::Array(vec![write_from(write_factor(from)?, joins)])),
+        ("lateral_views", empty()),
+        ("prewhere", Json::Null),
+        ("selection", write_opt_expr(selection)?),
+        ("group_by", variant("Expressions", Json::Array(vec![Json::Array(group_by), empty()]))),
+        ("cluster_by", empty()),
+        ("distribute_by", empty()),
+        ("sort_by", empty()),
+        ("having", Json::Null),
+        ("named_window", empty()),
+        ("qualify", write_opt_expr(qualify)?),
+        ("window_before_qualify", Json::Bool(false)),
+        ("value_table_mode", Json::Null),
+        ("connect_by", Json::Null),
+    ]))
+}
+
+fn write_wildcard(options: &WildcardOptions) -> Result<Json, SqlparserError> {
+    let exclude = match options.exclude.as_slice() {
+        [] => Json::Null,
+        [column] => variant("Single", write_ident(column)),
+        columns => variant("Multiple", Json::Array(columns.iter().map(write_ident).collect())),
+    };
+    let replace = match options.replace.as_slice() {
+        [] => Json::Null,
+        replace => {
+            let items = replace.iter().map(|(expr, column)| {
+                Ok(object(vec![
+                    ("expr", write_expr(expr)?),
+                    ("column_name", write_ident(column)),
+                    ("as_keyword", Json::Bool(true)),
+                ]))
+            });
+            object(vec![("items", Json::Array(items.collect::<Result<_, SqlparserError>>()?))])
+        }
+    };
+    Ok(object(vec![
+        ("opt_ilike", Json::Null),
+        ("opt_exclude", exclude),
+        ("opt_except", Json::Null),
+        ("opt_replace", replace),
+        ("opt_rename", Json::Null),
+    ]))
+}
+
+fn write_grouping(element: &GroupingElement) -> Result<Json, SqlparserError> {
+    // sqlparser's ROLLUP and CUBE take lists of expressions, each one a list of one here
+    let singles = |exprs: &[Expression]| {
+        exprs.iter().map(|expr| Ok(Json::Array(vec![write_expr(expr)?]))).collect::<Result<_, SqlparserError>>()
+    };
+    Ok(match element {
+        GroupingElement::Expression(expr) => write_expr(expr)?,
+        GroupingElement::Rollup(exprs) => variant("Rollup", Json::Array(singles(exprs)?)),
+        GroupingElement::Cube(exprs) => variant("Cube", Json::Array(singles(exprs)?)),
+        GroupingElement::GroupingSets(sets) => {
+            let sets = sets.iter().map(|set| write_exprs(set)).collect::<Result<_, SqlparserError>>()?;
+            variant("GroupingSets", Json::Array(sets))
+        }
+    })
+}
+
+/// A `TableWithJoins`.
+fn write_from(relation: Json, joins: Vec<Json>) -> Json {
+    object(vec![("relation", relation), ("joins", Json::Array(joins))])
+}
+
+fn write_join(join: &Join) -> Result<Json, SqlparserError> {
+    let constraint = match &join.on {
+        Some(on) => variant("On", write_expr(on)?),
+        None => unit("None"),
+    };
+    let operator = match join.kind {
+        JoinKind::Inner => variant("Inner", constraint),
+        JoinKind::Left => variant("LeftOuter", constraint),
+        JoinKind::Right => variant("RightOuter", constraint),
+        JoinKind::Full => variant("FullOuter", constraint),
+        JoinKind::Cross => unit("CrossJoin"),
+    };
+    Ok(object(vec![
+        ("relation", write_factor(&join.relation)?),
+        ("global", Json::Bool(false)),
+        ("join_operator", operator),
+    ]))
+}
+
+fn write_factor(factor: &TableFactor) -> Result<Json, SqlparserError> {
+    Ok(match factor {
+        TableFactor::Table { name, alias, sample: None } => write_table(name, alias, Json::Null),
+        TableFactor::Table { sample: Some(_), .. } => return unsupported("TABLESAMPLE"),
+        TableFactor::AsOf { .. } => return unsupported("AS OF VERSION"),
+        TableFactor::Derived { lateral, subquery, alias } => variant(
+            "Derived",
+            object(vec![
+                ("lateral", Json::Bool(*lateral)),
+                ("subquery", write_query(subquery)?),
+                ("alias", write_alias(alias)),
+            ]),
+        ),
+        // sqlparser reads a function in FROM as a table with arguments, unless it is LATERAL
+        TableFactor::Function { lateral: false, name, args, alias } => {
+            let args = object(vec![("args", write_function_args(args)?), ("settings", Json::Null)]);
+            write_table(&Ident::new(name.as_str()), alias, args)
+        }
+        TableFactor::Function { lateral: true, name, args, alias } => variant(
+            "Function",
+            object(vec![
+                ("lateral", Json::Bool(true)),
+                ("name", write_name(&Ident::new(name.as_str()))),
+                ("args", write_function_args(args)?),
+                ("alias", write_alias(alias)),
+            ]),
+        ),
+        TableFactor::Pivot { table, aggregate, column, values, alias } => {
+            let values = values.iter().map(write_expr_with_alias).collect::<Result<_, SqlparserError>>()?;
+            variant(
+                "Pivot",
+                object(vec![
+                    ("table", write_factor(table)?),
+                    ("aggregate_functions", Json::Array(vec![write_expr_with_alias(aggregate)?])),
+                    ("value_column", Json::Array(vec![write_ident(column)])),
+                    ("value_source", variant("List", Json::Array(values))),
+                    ("default_on_null", Json::Null),
+                    ("alias", write_alias(alias)),
+                ]),
+            )
+        }
+        TableFactor::Unpivot { table, value, name, columns, alias } => variant(
+            "Unpivot",
+            object(vec![
+                ("table", write_factor(table)?),
+                ("value", write_ident(value)),
+                ("name", write_ident(name)),
+                ("columns", Json::Array(columns.iter().map(write_ident).collect())),
+                ("alias", write_alias(alias)),
+            ]),
+        ),
+    })
+}
+
+fn write_table(name: &Ident, alias: &Option<TableAlias>, args: Json) -> Json {
+    variant(
+        "Table",
+        object(vec![
+            ("name", write_name(name)),
+            ("alias", write_alias(alias)),
+            ("args", args),
+            ("with_hints", empty()),
+            ("version", Json::Null),
+            ("with_ordinality", Json::Bool(false)),
+            ("partitions", empty()),
+            ("json_path", Json::Null),
+        ]),
+    )
+}
+
+fn write_alias(alias: &Option<TableAlias>) -> Json {
+    match alias {
+        Some(alias) => object(vec![
+            ("name", write_ident(&alias.name)),
+            ("columns", Json::Array(alias.columns.iter().map(write_ident).collect())),
+        ]),
+        None => Json::Null,
+    }
+}
+
+fn write_lock(lock: &LockingClause) -> Result<Json, SqlparserError> {
+    let lock_type = match lock.strength {
+        LockStrength::Update => "Update",
+        LockStrength::Share => "Share",
+        LockStrength::NoKeyUpdate => return unsupported("FOR NO KEY UPDATE"),
+        LockStrength::KeyShare => return unsupported("FOR KEY SHARE"),
+    };
+    let of = match lock.of.as_slice() {
+        [] => Json::Null,
+        [table] => write_name(table),
+        _ => return unsupported("FOR ... OF more than one table"),
+    };
+    let nonblock = match lock.wait {
+        None => Json::Null,
+        Some(LockWait::NoWait) => unit("Nowait"),
+        Some(LockWait::SkipLocked) => unit("SkipLocked"),
+    };
+    Ok(object(vec![("lock_type", unit(lock_type)), ("of", of), ("nonblock", nonblock)]))
+}
+
+fn write_ident(ident: &Ident) -> Json {
+    let quote = ident.quote.map_or(Json::Null, |quote| Json::String(quote.delimiters().0.to_string()));
+    object(vec![("value", Json::String(ident.value.clone())), ("quote_style", quote)])
+}
+
+/// An `ObjectName` of one part.
+fn write_name(name: &Ident) -> Json {
+    Json::Array(vec![write_ident(name)])
+}
+
+fn write_exprs(exprs: &[Expression]) -> Result<Json, SqlparserError> {
+    Ok(Json::Array(exprs.iter().map(write_expr).collect::<Result<_, SqlparserError>>()?))
+}
+
+fn write_opt_expr(expr: &Option<Expression>) -> Result<Json, SqlparserError> {
+    expr.as_ref().map_or(Ok(Json::Null), write_expr)
+}
+
+fn write_expr_with_alias(expr: &Expression) -> Result<Json, SqlparserError> {
+    Ok(object(vec![("expr", write_expr(expr)?), ("alias", Json::Null)]))
+}
+
+fn write_order_by(expr: &Expression, asc: Option<bool>) -> Result<Json, SqlparserError> {
+    Ok(object(vec![
+        ("expr", write_expr(expr)?),
+        ("asc", asc.map_or(Json::Null, Json::Bool)),
+        ("nulls_first", Json::Null),
+        ("with_fill", Json::Null),
+    ]))
+}
+
+fn write_number(text: &str) -> Json {
+    variant("Value", variant("Number", Json::Array(vec![Json::String(text.to_string()), Json::Bool(false)])))
+}
+
+fn write_expr(expr: &Expression) -> Result<Json, SqlparserError> {
+    Ok(match expr {
+        Expression::Identifier(name) => variant("Identifier", write_ident(name)),
+        Expression::CompoundIdentifier(parts) => {
+            variant("CompoundIdentifier", Json::Array(parts.iter().map(write_ident).collect()))
+        }
+        Expression::Number(n) => write_number(&n.to_string()),
+        Expression::Decimal(text) => write_number(text),
+        Expression::String(s) => variant("Value", variant("SingleQuotedString", Json::String(s.clone()))),
+        Expression::PrefixedString { prefix, value } => {
+            let name = match prefix {
+                StringPrefix::National => "NationalStringLiteral",
+                StringPrefix::Escape => "EscapedStringLiteral",
+                StringPrefix::Hex => "HexStringLiteral",
+                StringPrefix::Bit => return unsupported("Bit string literals"),
+            };
+            variant("Value", variant(name, Json::String(value.clone())))
+        }
+        Expression::Boolean(b) => variant("Value", variant("Boolean", Json::Bool(*b))),
+        Expression::Null => variant("Value", unit("Null")),
+        Expression::Placeholder(p) => variant("Value", variant("Placeholder", Json::String(p.clone()))),
+        // sqlparser has an expression of its own for each IS test
+        Expression::UnaryOperation {
+            operator: operator @ (UnaryOperator::IsNull | UnaryOperator::IsNotNull),
+            operand,
+        } => variant(if *operator == UnaryOperator::IsNull { "IsNull" } else { "IsNotNull" }, write_expr(operand)?),
+        Expression::BinaryOperation {
+            left_operand,
+            operator: operator @ (BinaryOperator::IsDistinctFrom | BinaryOperator::IsNotDistinctFrom),
+            right_operand,
+        } => variant(
+            if *operator == BinaryOperator::IsDistinctFrom { "IsDistinctFrom" } else { "IsNotDistinctFrom" },
+            Json::Array(vec![write_expr(left_operand)?, write_expr(right_operand)?]),
+        ),
+        Expression::UnaryOperation { operator, operand } => {
+            let op = match operator {
+                UnaryOperator::Not => "Not",
+                UnaryOperator::Negate => "Minus",
+                UnaryOperator::IsNull | UnaryOperator::IsNotNull => unreachable!("written above"),
+            };
+            variant("UnaryOp", object(vec![("op", unit(op)), ("expr", write_expr(operand)?)]))
+        }
+        Expression::BinaryOperation {
+            left_operand,
+            operator: operator @ (BinaryOperator::Like | BinaryOperator::ILike),
+            right_operand,
+        } => variant(
+            if *operator == BinaryOperator::Like { "Like" } else { "ILike" },
+            object(vec![
+                ("negated", Json::Bool(false)),
+                ("any", Json::Bool(false)),
+                ("expr", write_expr(left_operand)?),
+                ("pattern", write_expr(right_operand)?),
+                ("escape_char", Json::Null),
+            ]),
+        ),
+        Expression::BinaryOperation { left_operand, operator, right_operand } => {
+            let Some((_, op)) = BINARY_OPERATORS.iter().find(|(known, _)| known == operator) else {
+                return unsupported(format!("The operator {:?}", operator));
+            };
+            variant(
+                "BinaryOp",
+                object(vec![
+                    ("left", write_expr(left_operand)?),
+                    ("op", unit(op)),
+                    ("right", write_expr(right_operand)?),
+                ]),
+            )
+        }
+        Expression::Grouped(inner) => variant("Nested", write_expr(inner)?),
+        Expression::Function { name, args, over } => variant("Function", write_function(name, args, over)?),
+        Expression::TypedString { data_type, value } => variant(
+            "TypedString",
+            object(vec![("data_type", write_type(data_type)), ("value", Json::String(value.clone()))]),
+        ),
+        Expression::Collate { expr, collation } => {
+            variant("Collate", object(vec![("expr", write_expr(expr)?), ("collation", write_name(collation))]))
+        }
+        Expression::Cast { expr, data_type } => variant(
+            "Cast",
+            object(vec![
+                ("kind", unit("Cast")),
+                ("expr", write_expr(expr)?),
+                ("data_type", write_type(data_type)),
+                ("format", Json::Null),
+            ]),
+        ),
+    })
+}
+
+fn write_function(name: &str, args: &[Expression], over: &Option<Box<WindowSpec>>) -> Result<Json, SqlparserError> {
+    let over = match over {
+        Some(window) => {
+            let order_by = window.order_by.iter().map(|item| write_order_by(&item.expr, item.asc));
+            let order_by = order_by.collect::<Result<_, SqlparserError>>()?;
+            variant(
+                "WindowSpec",
+                object(vec![
+                    ("window_name", Json::Null),
+                    ("partition_by", write_exprs(&window.partition_by)?),
+                    ("order_by", Json::Array(order_by)),
+                    ("window_frame", Json::Null),
+                ]),
+            )
+        }
+        None => Json::Null,
+    };
+    let args = object(vec![
+        ("duplicate_treatment", Json::Null),
+        ("args", write_function_args(args)?),
+        ("clauses", empty()),
+    ]);
+    Ok(object(vec![
+        ("name", write_name(&Ident::new(name))),
+        ("parameters", unit("None")),
+        ("args", variant("List", args)),
+        ("filter", Json::Null),
+        ("null_treatment", Json::Null),
+        ("over", over),
+        ("within_group", empty()),
+        ("uses_odbc_syntax", Json::Bool(false)),
+    ]))
+}
+
+fn write_function_args(args: &[Expression]) -> Result<Json, SqlparserError> {
+    // The `*` of `COUNT(*)` is the identifier `*` here, a wildcard there
+    let args = args.iter().map(|arg| match arg {
+        Expression::Identifier(name) if name == "*" => Ok(variant("Unnamed", unit("Wildcard"))),
+        arg => Ok(variant("Unnamed", variant("Expr", write_expr(arg)?))),
+    });
+    Ok(Json::Array(args.collect::<Result<_, SqlparserError>>()?))
+}
+
+fn write_type(data_type: &DBType) -> Json {
+    let time = || Json::Array(vec![Json::Null, unit("None")]);
+    match data_type {
+        DBType::Int => variant("Int", Json::Null),
+        DBType::Varchar(length) => variant(
+            "Varchar",
+            variant("IntegerLength", object(vec![("length", number(*length as u64)), ("unit", Json::Null)])),
+        ),
+        DBType::Bool => unit("Boolean"),
+        DBType::Date => unit("Date"),
+        DBType::Time => variant("Time", time()),
+        DBType::Timestamp => variant("Timestamp", time()),
+        DBType::Decimal(precision, scale) => variant(
+            "Decimal",
+            variant("PrecisionAndScale", Json::Array(vec![number(*precision as u64), number(*scale as u64)])),
+        ),
+    }
+}
+
+// === To this crate's AST ===
+
+/// Fails on the first of `fields` that holds a clause: something
+/// `node` has that this crate's AST doesn't.
+fn none_of(json: &Json, node: &str, fields: &[&str]) -> Result<(), SqlparserError> {
+    match fields.iter().find(|field| !json.get(field).is_empty()) {
+        Some(field) => unsupported(format!("{}.{}", node, field)),
+        None => Ok(()),
+    }
+}
+
+fn read_statement(json: &Json) -> Result<Statement, SqlparserError> {
+    let (name, data) = json.variant()?;
+    match name {
+        "Query" => read_query(data),
+        "Insert" => read_insert(data),
+        "Update" => read_update(data),
+        "Delete" => read_delete(data),
+        "Drop" => read_drop(data),
+        other => unsupported(format!("Statement::{}", other)),
+    }
+}
+
+fn read_insert(insert: &Json) -> Result<Statement, SqlparserError> {
+    none_of(
+        insert,
+        "Insert",
+        &[
+            "or", "ignore", "table_alias", "overwrite", "partitioned", "after_columns", "on", "returning",
+            "replace_into", "priority", "insert_alias",
+        ],
+    )?;
+    // The table is `table_name` up to 0.52, a `TableObject` in `table` after
+    let table_name = match (insert.get("table_name"), insert.get("table")) {
+        (Json::Null, table) => match table.variant()? {
+            ("TableName", name) => read_name(name)?,
+            (other, _) => return unsupported(format!("TableObject::{}", other)),
+        },
+        (name, table) if !table.is_true() => read_name(name)?,
+        _ => return unsupported("Insert.table"),
+    };
+    let columns = read_idents(insert.get("columns"))?;
+    let columns = if columns.is_empty() { None } else { Some(columns) };
+    let source = insert.get("source");
+    if source.is_null() {
+        return unsupported("An INSERT without a query");
+    }
+    match read_query(source)? {
+        Statement::Values { rows } => Ok(Statement::Insert { table_name, columns, rows }),
+        query => Ok(Statement::InsertSelect { table_name, columns, query: Box::new(query) }),
+    }
+}
+
+fn read_update(update: &Json) -> Result<Statement, SqlparserError> {
+    none_of(update, "Update", &["from", "returning", "or"])?;
+    let (table_name, alias) = read_target(update.get("table"))?;
+    let assignments = update.get("assignments").list()?.iter().map(|assignment| {
+        let column = match assignment.get("target").variant()? {
+            ("ColumnName", name) => read_name(name)?,
+            (other, _) => return unsupported(format!("AssignmentTarget::{}", other)),
+        };
+        Ok((column, read_expr(assignment.get("value"))?))
+    });
+    Ok(Statement::Update {
+        table_name,
+        alias,
+        assignments: assignments.collect::<Result<_, SqlparserError>>()?,
+        selection: read_opt_expr(update.get("selection"))?,
+    })
+}
+
+fn read_delete(delete: &Json) -> Result<Statement, SqlparserError> {
+    none_of(delete, "Delete", &["tables", "using", "returning", "order_by", "limit"])?;
+    // WithFromKeyword or WithoutKeyword, the same tables either way
+    let (_, from) = delete.get("from").variant()?;
+    let [table] = from.list()? else { return unsupported("A DELETE from other than one table") };
+    let (table_name, alias) = read_target(table)?;
+    Ok(Statement::Delete { table_name, alias, selection: read_opt_expr(delete.get("selection"))? })
+}
+
+/// The table and alias of the `TableWithJoins` an UPDATE or DELETE writes.
+fn read_target(table: &Json) -> Result<(Ident, Option<Ident>), SqlparserError> {
+    if !table.get("joins").is_empty() {
+        return unsupported("A join in an UPDATE or DELETE");
+    }
+    match read_factor(table.get("relation"))? {
+        TableFactor::Table { name, alias, sample: None } if alias.as_ref().is_none_or(|a| a.columns.is_empty()) => {
+            Ok((name, alias.map(|alias| alias.name)))
+        }
+        _ => unsupported("An UPDATE or DELETE of other than a table"),
+    }
+}
+
+fn read_drop(drop: &Json) -> Result<Statement, SqlparserError> {
+    none_of(drop, "Drop", &["cascade", "restrict", "purge", "temporary"])?;
+    match drop.get("object_type").variant()? {
+        ("Table", _) => {}
+        (other, _) => return unsupported(format!("ObjectType::{}", other)),
+    }
+    let [name] = drop.get("names").list()? else { return unsupported("A DROP TABLE of other than one table") };
+    Ok(Statement::DropTable { table_name: read_name(name)?, if_exists: drop.get("if_exists").is_true() })
+}
+
+fn read_query(query: &Json) -> Result<Statement, SqlparserError> {
+    none_of(query, "Query", &["limit_by", "for_clause", "settings", "format_clause"])?;
+    let mut body = read_body(query.get("body"))?;
+
+    let order_by = read_order_by(query.get("order_by"))?;
+    let mut limit = match query.get("limit") {
+        Json::Null => None,
+        limit => Some(read_count(limit)?),
+    };
+    if let fetch @ Json::Object(_) = query.get("fetch") {
+        none_of(fetch, "Fetch", &["with_ties", "percent"])?;
+        if limit.is_some() {
+            return unsupported("LIMIT and FETCH together");
+        }
+        limit = Some(read_count(fetch.get("quantity"))?);
+    }
+    let offset = match query.get("offset") {
+        Json::Null => None,
+        offset => Some(read_count(offset.get("value"))?),
+    };
+    let locks = query.get("locks").list()?.iter().map(read_lock).collect::<Result<Vec<_>, SqlparserError>>()?;
+    // The clauses belong to the body's last SELECT here
+    if !order_by.is_empty() || limit.is_some() || offset.is_some() || !locks.is_empty() {
+        match last_select_mut(&mut body) {
+            Some(Statement::Select { order_by: o, limit: l @ None, offset: f @ None, locking, .. })
+                if o.is_empty() && locking.is_empty() =>
+            {
+                (*o, *l, *f, *locking) = (order_by, limit, offset, locks);
+            }
+            _ => return unsupported("ORDER BY, LIMIT, OFFSET or FOR after other than a SELECT"),
+        }
+    }
+
+    match query.get("with") {
+        Json::Null => Ok(body),
+        with => {
+            let ctes = with.get("cte_tables").list()?.iter().map(read_cte);
+            Ok(Statement::With {
+                recursive: with.get("recursive").is_true(),
+                ctes: ctes.collect::<Result<_, SqlparserError>>()?,
+                body: Box::new(body),
+            })
+        }
+    }
+}
+
+fn last_select_mut(statement: &mut Statement) -> Option<&mut Statement> {
+    if matches!(statement, Statement::Select { .. }) {
+        return Some(statement);
+    }
+    match statement {
+        Statement::SetOperation { right, .. } => last_select_mut(right),
+        _ => None,
+    }
+}
+
+fn read_cte(cte: &Json) -> Result<Cte, SqlparserError> {
+    none_of(cte, "Cte", &["from", "materialized"])?;
+    let Some(TableAlias { name, columns }) = read_alias(cte.get("alias"))? else {
+        return Err(malformed("a CTE's alias", NULL));
+    };
+    Ok(Cte { name, columns, query: Box::new(read_query(cte.get("query"))?) })
+}
+
+fn read_body(json: &Json) -> Result<Statement, SqlparserError> {
+    let (name, data) = json.variant()?;
+    match name {
+        "Select" => read_select(data),
+        "Query" => read_query(data),
+        "SetOperation" => {
+            let operator = match data.get("op").variant()?.0 {
+                "Union" => SetOperator::Union,
+                "Intersect" => SetOperator::Intersect,
+                "Except" => SetOperator::Except,
+                other => return unsupported(format!("SetOperator::{}", other)),
+            };
+            let all = match data.get("set_quantifier").variant()?.0 {
+                "All" => true,
+                "None" | "Distinct" => false,
+                other => return unsupported(format!("SetQuantifier::{}", other)),
+            };
+            Ok(Statement::SetOperation {
+                operator,
+                all,
+                left: Box::new(read_body(data.get("left"))?),
+                right: Box::new(read_body(data.get("right"))?),
+            })
+        }
+        "Values" => {
+            let rows = data.get("rows").list()?.iter().map(read_exprs);
+            Ok(Statement::Values { rows: rows.collect::<Result<_, SqlparserError>>()? })
+        }
+        other => unsupported(format!("SetExpr::{}", other)),
+    }
+}
+
+fn read_select(select: &Json) -> Result<Statement, SqlparserError> {
+    none_of(
+        select,
+        "Select",
+        &[
+            "distinct", "top", "into", "lateral_views", "prewhere", "cluster_by", "distribute_by", "sort_by", "having",
+            "named_window", "value_table_mode", "connect_by",
+        ],
+    )?;
+    let mut wildcard = WildcardOptions::default();
+    let mut columns = Vec::new();
+    for item in select.get("projection").list()? {
+        columns.push(match item.variant()? {
+            ("UnnamedExpr", expr) => SelectItem { expr: read_expr(expr)?, alias: None },
+            ("ExprWithAlias", data) => {
+                SelectItem { expr: read_expr(data.get("expr"))?, alias: Some(read_ident(data.get("alias"))?) }
+            }
+            ("Wildcard", options) => {
+                read_wildcard(options, &mut wildcard)?;
+                SelectItem::wildcard()
+            }
+            (other, _) => return unsupported(format!("SelectItem::{}", other)),
+        });
+    }
+
+    // A comma in the FROM list is a CROSS join
+    let mut tables = select.get("from").list()?.iter();
+    let Some(first) = tables.next() else { return unsupported("A SELECT without FROM") };
+    let from = read_factor(first.get("relation"))?;
+    let mut joins = read_joins(first.get("joins"))?;
+    for table in tables {
+        joins.push(Join { kind: JoinKind::Cross, relation: read_factor(table.get("relation"))?, on: None });
+        joins.extend(read_joins(table.get("joins"))?);
+    }
+
+    Ok(Statement::Select {
+        hints: Vec::new(),
+        columns,
+        wildcard,
+        from: Box::new(from),
+        joins,
+        selection: read_opt_expr(select.get("selection"))?,
+        group_by: read_group_by(select.get("group_by"))?,
+        qualify: read_opt_expr(select.get("qualify"))?,
+        order_by: Vec::new(),
+        limit: None,
+        offset: None,
+        locking: Vec::new(),
+    })
+}
+
+fn read_wildcard(options: &Json, wildcard: &mut WildcardOptions) -> Result<(), SqlparserError> {
+    none_of(options, "WildcardAdditionalOptions", &["opt_ilike", "opt_except", "opt_rename"])?;
+    match options.get("opt_exclude") {
+        Json::Null => {}
+        exclude => match exclude.variant()? {
+            ("Single", column) => wildcard.exclude.push(read_ident(column)?),
+            ("Multiple", columns) => wildcard.exclude.extend(read_idents(columns)?),
+            (other, _) => return unsupported(format!("ExcludeSelectItem::{}", other)),
+        },
+    }
+    for item in options.get("opt_replace").get("items").list()? {
+        wildcard.replace.push((read_expr(item.get("expr"))?, read_ident(item.get("column_name"))?));
+    }
+    Ok(())
+}
+
+fn read_group_by(json: &Json) -> Result<Vec<GroupingElement>, SqlparserError> {
+    if json.is_null() {
+        return Ok(Vec::new());
+    }
+    let exprs = match json.variant()? {
+        // `[exprs, modifiers]`, or the expressions alone before modifiers were added
+        ("Expressions", data) => match data.list()? {
+            [Json::Array(exprs), modifiers] if modifiers.list()?.is_empty() => exprs.as_slice(),
+            [Json::Array(_), _] => return unsupported("GroupByExpr modifiers"),
+            exprs => exprs,
+        },
+        (other, _) => return unsupported(format!("GroupByExpr::{}", other)),
+    };
+    exprs.iter().map(read_grouping).collect()
+}
+
+fn read_grouping(json: &Json) -> Result<GroupingElement, SqlparserError> {
+    let lists = |data: &Json| data.list()?.iter().map(read_exprs).collect::<Result<Vec<_>, SqlparserError>>();
+    let singles = |node: &str, data: &Json| {
+        let lists = lists(data)?;
+        if lists.iter().any(|list| list.len() != 1) {
+            return unsupported(format!("{} of lists of expressions", node));
+        }
+        Ok(lists.into_iter().flatten().collect())
+    };
+    Ok(match json.variant()? {
+        ("Rollup", data) => GroupingElement::Rollup(singles("ROLLUP", data)?),
+        ("Cube", data) => GroupingElement::Cube(singles("CUBE", data)?),
+        ("GroupingSets", data) => GroupingElement::GroupingSets(lists(data)?),
+        _ => GroupingElement::Expression(read_expr(json)?),
+    })
+}
+
+/// The items of a query's ORDER BY.
+fn read_order_by(json: &Json) -> Result<Vec<OrderByExpr>, SqlparserError> {
+    // A list of items up to 0.49, then `{exprs, interpolate}`, then the items in `kind`
+    let items = match json {
+        Json::Null => return Ok(Vec::new()),
+        Json::Array(items) => items.as_slice(),
+        order_by => {
+            none_of(order_by, "OrderBy", &["interpolate"])?;
+            match order_by.get("kind") {
+                Json::Null => order_by.get("exprs").list()?,
+                kind => match kind.variant()? {
+                    ("Expressions", items) => items.list()?,
+                    (other, _) => return unsupported(format!("OrderByKind::{}", other)),
+                },
+            }
+        }
+    };
+    items.iter().map(read_order_by_expr).collect()
+}
+
+/// An ORDER BY item of a query or a window.
+fn read_order_by_expr(item: &Json) -> Result<OrderByExpr, SqlparserError> {
+    none_of(item, "OrderByExpr", &["nulls_first", "with_fill"])?;
+    // Later versions keep the direction in `options`
+    let options = match item.get("options") {
+        Json::Null => item,
+        options => {
+            none_of(options, "OrderByOptions", &["nulls_first"])?;
+            options
+        }
+    };
+    let asc = match options.get("asc") {
+        Json::Null => None,
+        Json::Bool(asc) => Some(*asc),
+        other => return Err(malformed("a boolean", other)),
+    };
+    Ok(OrderByExpr { expr: read_expr(item.get("expr"))?, asc })
+}
+
+fn read_count(json: &Json) -> Result<u64, SqlparserError> {
+    match read_expr(json)? {
+        Expression::Number(n) => Ok(n),
+        _ => unsupported("A LIMIT or OFFSET other than a number"),
+    }
+}
+
+fn read_lock(lock: &Json) -> Result<LockingClause, SqlparserError> {
+    let strength = match lock.get("lock_type").variant()?.0 {
+        "Update" => LockStrength::Update,
+        "Share" => LockStrength::Share,
+        other => return unsupported(format!("LockType::{}", other)),
+    };
+    let of = match lock.get("of") {
+        Json::Null => Vec::new(),
+        of => vec![read_name(of)?],
+    };
+    let wait = match lock.get("nonblock") {
+        Json::Null => None,
+        nonblock => match nonblock.variant()?.0 {
+            "Nowait" => Some(LockWait::NoWait),
+            "SkipLocked" => Some(LockWait::SkipLocked),
+            other => return unsupported(format!("NonBlock::{}", other)),
+        },
+    };
+    Ok(LockingClause { strength, of, wait })
+}
+
+fn read_joins(json: &Json) -> Result<Vec<Join>, SqlparserError> {
+    json.list()?.iter().map(read_join).collect()
+}
+
+fn read_join(join: &Json) -> Result<Join, SqlparserError> {
+    none_of(join, "Join", &["global"])?;
+    let relation = read_factor(join.get("relation"))?;
+    let (name, constraint) = join.get("join_operator").variant()?;
+    let kind = match name {
+        "Inner" | "Join" => JoinKind::Inner,
+        "LeftOuter" | "Left" => JoinKind::Left,
+        "RightOuter" | "Right" => JoinKind::Right,
+        "FullOuter" => JoinKind::Full,
+        "CrossJoin" => return Ok(Join { kind: JoinKind::Cross, relation, on: None }),
+        other => return unsupported(format!("JoinOperator::{}", other)),
+    };
+    match constraint.variant()? {
+        ("On", on) => Ok(Join { kind, relation, on: Some(read_expr(on)?) }),
+        (other, _) => unsupported(format!("JoinConstraint::{}", other)),
+    }
+}
+
+fn read_factor(json: &Json) -> Result<TableFactor, SqlparserError> {
+    let (name, data) = json.variant()?;
+    let alias = read_alias(data.get("alias"))?;
+    match name {
+        "Table" => {
+            none_of(
+                data,
+                "TableFactor::Table",
+                &["with_hints", "version", "with_ordinality", "partitions", "json_path", "sample", "index_hints"],
+            )?;
+            let table = read_name(data.get("name"))?;
+            // A function in FROM: its arguments alone, or in `args` with the SETTINGS since 0.48
+            let args = match data.get("args") {
+                Json::Null => return Ok(TableFactor::Table { name: table, alias, sample: None }),
+                args @ Json::Object(_) => {
+                    none_of(args, "TableFunctionArgs", &["settings"])?;
+                    args.get("args")
+                }
+                args => args,
+            };
+            Ok(TableFactor::Function { lateral: false, name: table.value, args: read_function_args(args)?, alias })
+        }
+        "Derived" => Ok(TableFactor::Derived {
+            lateral: data.get("lateral").is_true(),
+            subquery: Box::new(read_query(data.get("subquery"))?),
+            alias,
+        }),
+        "Function" => Ok(TableFactor::Function {
+            lateral: data.get("lateral").is_true(),
+            name: read_name(data.get("name"))?.value,
+            args: read_function_args(data.get("args"))?,
+            alias,
+        }),
+        "Pivot" => {
+            none_of(data, "TableFactor::Pivot", &["default_on_null"])?;
+            let aggregates = data.get("aggregate_functions").list()?;
+            let (aggregate, column) = match (aggregates, data.get("value_column").list()?) {
+                ([aggregate], [column]) if aggregate.get("alias").is_null() => {
+                    (read_expr(aggregate.get("expr"))?, read_ident(column)?)
+                }
+                _ => return unsupported("A PIVOT of other than one aggregate and one column"),
+            };
+            let values = match data.get("value_source").variant()? {
+                ("List", values) => values.list()?.iter().map(|value| {
+                    none_of(value, "ExprWithAlias", &["alias"])?;
+                    read_expr(value.get("expr"))
+                }),
+                (other, _) => return unsupported(format!("PivotValueSource::{}", other)),
+            };
+            Ok(TableFactor::Pivot {
+                table: Box::new(read_factor(data.get("table"))?),
+                aggregate,
+                column,
+                values: values.collect::<Result<_, SqlparserError>>()?,
+                alias,
+            })
+        }
+        "Unpivot" => Ok(TableFactor::Unpivot {
+            table: Box::new(read_factor(data.get("table"))?),
+            value: read_ident(data.get("value"))?,
+            name: read_ident(data.get("name"))?,
+            columns: read_idents(data.get("columns"))?,
+            alias,
+        }),
+        other => unsupported(format!("TableFactor::{}", other)),
+    }
+}
+
+fn read_alias(json: &Json) -> Result<Option<TableAlias>, SqlparserError> {
+    if json.is_null() {
+        return Ok(None);
+    }
+    // The columns are idents, or since 0.53 `{name, data_type}`
+    let columns = json.get("columns").list()?.iter().map(|column| match column.get("data_type") {
+        Json::Null => read_ident(match column.get("name") {
+            Json::Null => column,
+            name => name,
+        }),
+        _ => unsupported("A typed column of an alias"),
+    });
+    let columns = columns.collect::<Result<_, SqlparserError>>()?;
+    Ok(Some(TableAlias { name: read_ident(json.get("name"))?, columns }))
+}
+
+fn read_ident(json: &Json) -> Result<Ident, SqlparserError> {
+    let value = json.get("value").as_str()?.to_string();
+    let quote = match json.get("quote_style") {
+        Json::Null => None,
+        quote => Some(match quote.as_str()? {
+            "\"" => QuoteStyle::Double,
+            "`" => QuoteStyle::Backtick,
+            "[" => QuoteStyle::Bracket,
+            other => return unsupported(format!("Identifiers quoted with {}", other)),
+        }),
+    };
+    Ok(Ident { value, quote })
+}
+
+fn read_idents(json: &Json) -> Result<Vec<Ident>, SqlparserError> {
+    json.list()?.iter().map(read_ident).collect()
+}
+
+/// An `ObjectName`, which names are in one part here.
+fn read_name(json: &Json) -> Result<Ident, SqlparserError> {
+    match json.list()? {
+        // An ident, or since 0.55 `{"Identifier": ident}`
+        [part] => read_ident(match part.get("Identifier") {
+            Json::Null => part,
+            ident => ident,
+        }),
+        [] => Err(malformed("a name", json)),
+        _ => unsupported("A qualified name"),
+    }
+}
+
+fn read_exprs(json: &Json) -> Result<Vec<Expression>, SqlparserError> {
+    json.list()?.iter().map(read_expr).collect()
+}
+
+fn read_opt_expr(json: &Json) -> Result<Option<Expression>, SqlparserError> {
+    match json {
+        Json::Null => Ok(None),
+        expr => read_expr(expr).map(Some),
+    }
+}
+
+fn read_expr(json: &Json) -> Result<Expression, SqlparserError> {
+    let boxed = |json: &Json| read_expr(json).map(Box::new);
+    let (name, data) = json.variant()?;
+    Ok(match name {
+        "Identifier" => Expression::Identifier(read_ident(data)?),
+        "CompoundIdentifier" => Expression::CompoundIdentifier(read_idents(data)?),
+        "Value" => read_value(data)?,
+        "BinaryOp" => {
+            let op = data.get("op").variant()?.0;
+            let Some((operator, _)) = BINARY_OPERATORS.iter().find(|(_, known)| *known == op) else {
+                return unsupported(format!("BinaryOperator::{}", op));
+            };
+            Expression::BinaryOperation {
+                left_operand: boxed(data.get("left"))?,
+                operator: operator.clone(),
+                right_operand: boxed(data.get("right"))?,
+            }
+        }
+        "Like" | "ILike" => {
+            none_of(data, name, &["negated", "any", "escape_char"])?;
+            Expression::BinaryOperation {
+                left_operand: boxed(data.get("expr"))?,
+                operator: if name == "Like" { BinaryOperator::Like } else { BinaryOperator::ILike },
+                right_operand: boxed(data.get("pattern"))?,
+            }
+        }
+        "IsNull" | "IsNotNull" => Expression::UnaryOperation {
+            operator: if name == "IsNull" { UnaryOperator::IsNull } else { UnaryOperator::IsNotNull },
+            operand: boxed(data)?,
+        },
+        "IsDistinctFrom" | "IsNotDistinctFrom" => {
+            let [left, right] = data.list()? else { return Err(malformed("a pair of expressions", data)) };
+            Expression::BinaryOperation {
+                left_operand: boxed(left)?,
+                operator: if name == "IsDistinctFrom" {
+                    BinaryOperator::IsDistinctFrom
+                } else {
+                    BinaryOperator::IsNotDistinctFrom
+                },
+                right_operand: boxed(right)?,
+            }
+        }
+        "UnaryOp" => {
+            let operator = match data.get("op").variant()?.0 {
+                "Not" => UnaryOperator::Not,
+                "Minus" => UnaryOperator::Negate,
+                other => return unsupported(format!("UnaryOperator::{}", other)),
+            };
+            Expression::UnaryOperation { operator, operand: boxed(data.get("expr"))? }
+        }
+        "Nested" => Expression::Grouped(boxed(data)?),
+        "Function" => read_function(data)?,
+        "Cast" => {
+            none_of(data, "Cast", &["format"])?;
+            match data.get("kind") {
+                Json::Null => {}
+                kind => match kind.variant()?.0 {
+                    "Cast" | "DoubleColon" => {}
+                    other => return unsupported(format!("CastKind::{}", other)),
+                },
+            }
+            Expression::Cast { expr: boxed(data.get("expr"))?, data_type: read_type(data.get("data_type"))? }
+        }
+        "TypedString" => Expression::TypedString {
+            data_type: read_type(data.get("data_type"))?,
+            value: data.get("value").as_str()?.to_string(),
+        },
+        "Collate" => {
+            Expression::Collate { expr: boxed(data.get("expr"))?, collation: read_name(data.get("collation"))? }
+        }
+        "Between" => between(
+            read_expr(data.get("expr"))?,
+            data.get("negated").is_true(),
+            read_expr(data.get("low"))?,
+            read_expr(data.get("high"))?,
+        ),
+        "Extract" => {
+            let Json::String(field) = data.get("field") else { return unsupported("EXTRACT of a custom field") };
+            let args = vec![Expression::String(field.to_lowercase()), read_expr(data.get("expr"))?];
+            Expression::Function { name: "date_part".to_string(), args, over: None }
+        }
+        other => return unsupported(format!("Expr::{}", other)),
+    })
+}
+
+fn read_value(json: &Json) -> Result<Expression, SqlparserError> {
+    // Since 0.55 the value is in a `ValueWithSpan`
+    let json = match json.get("span") {
+        Json::Null => json,
+        _ => json.get("value"),
+    };
+    let (name, value) = json.variant()?;
+    Ok(match name {
+        "Number" => {
+            let text = match value {
+                Json::Array(parts) => parts.first().unwrap_or(NULL).as_str()?,
+                text => text.as_str()?,
+            };
+            let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+            let decimal = text.split_once('.').is_some_and(|(whole, fraction)| digits(whole) && digits(fraction));
+            match text.parse() {
+                Ok(n) => Expression::Number(n),
+                Err(_) if decimal => Expression::Decimal(text.to_string()),
+                Err(_) => return unsupported(format!("The number {}", text)),
+            }
+        }
+        "SingleQuotedString" => Expression::String(value.as_str()?.to_string()),
+        "NationalStringLiteral" | "EscapedStringLiteral" | "HexStringLiteral" => {
+            let prefix = match name {
+                "NationalStringLiteral" => StringPrefix::National,
+                "EscapedStringLiteral" => StringPrefix::Escape,
+                _ => StringPrefix::Hex,
+            };
+            Expression::PrefixedString { prefix, value: value.as_str()?.to_string() }
+        }
+        "Boolean" => match value {
+            Json::Bool(b) => Expression::Boolean(*b),
+            other => return Err(malformed("a boolean", other)),
+        },
+        "Null" => Expression::Null,
+        "Placeholder" => Expression::Placeholder(value.as_str()?.to_string()),
+        other => return unsupported(format!("Value::{}", other)),
+    })
+}
+
+fn read_function(function: &Json) -> Result<Expression, SqlparserError> {
+    none_of(function, "Function", &["filter", "null_treatment", "within_group", "uses_odbc_syntax"])?;
+    match function.get("parameters") {
+        Json::Null => {}
+        parameters if parameters.variant()?.0 == "None" => {}
+        _ => return unsupported("Function.parameters"),
+    }
+    // A list of arguments up to 0.47, then `FunctionArguments`
+    let args = match function.get("args") {
+        args @ Json::Array(_) => read_function_args(args)?,
+        args => match args.variant()? {
+            ("List", list) => {
+                none_of(list, "FunctionArgumentList", &["duplicate_treatment", "clauses"])?;
+                read_function_args(list.get("args"))?
+            }
+            (other, _) => return unsupported(format!("FunctionArguments::{}", other)),
+        },
+    };
+    let over = match function.get("over") {
+        Json::Null => None,
+        over => match over.variant()? {
+            ("WindowSpec", window) => {
+                none_of(window, "WindowSpec", &["window_name", "window_frame"])?;
+                let order_by = window.get("order_by").list()?.iter().map(read_order_by_expr);
+                Some(Box::new(WindowSpec {
+                    partition_by: read_exprs(window.get("partition_by"))?,
+                    order_by: order_by.collect::<Result<_, SqlparserError>>()?,
+                }))
+            }
+            (other, _) => return unsupported(format!("WindowType::{}", other)),
+        },
+    };
+    Ok(Expression::Function { name: read_name(function.get("name"))?.value, args, over })
+}
+
+fn read_function_args(json: &Json) -> Result<Vec<Expression>, SqlparserError> {
+    let args = json.list()?.iter().map(|arg| match arg.variant()? {
+        ("Unnamed", arg) => match arg.variant()? {
+            ("Expr", expr) => read_expr(expr),
+            ("Wildcard", _) => Ok(Expression::Identifier(Ident::new("*"))),
+            (other, _) => unsupported(format!("FunctionArgExpr::{}", other)),
+        },
+        (other, _) => unsupported(format!("FunctionArg::{}", other)),
+    });
+    args.collect()
+}
+
+fn read_type(json: &Json) -> Result<DBType, SqlparserError> {
+    let (name, data) = json.variant()?;
+    Ok(match name {
+        "Int" | "Integer" if data.is_null() => DBType::Int,
+        "Boolean" | "Bool" => DBType::Bool,
+        "Varchar" | "CharacterVarying" => match data.variant() {
+            Ok(("IntegerLength", length)) if length.get("unit").is_null() => {
+                DBType::Varchar(length.get("length").as_u64()? as usize)
+            }
+            _ => return unsupported("VARCHAR without a length in characters"),
+        },
+        "Date" => DBType::Date,
+        // `[precision, time zone]`
+        "Time" | "Timestamp" => match data.list()? {
+            [precision, zone] if precision.is_null() && zone.variant()?.0 == "None" => {
+                if name == "Time" { DBType::Time } else { DBType::Timestamp }
+            }
+            _ => return unsupported(format!("{} with a precision or time zone", name.to_uppercase())),
+        },
+        "Decimal" | "Numeric" => {
+            let (precision, scale) = match data.variant()? {
+                ("None", _) => (MAX_PRECISION as u64, 0),
+                ("Precision", precision) => (precision.as_u64()?, 0),
+                ("PrecisionAndScale", both) => match both.list()? {
+                    [precision, scale] => (precision.as_u64()?, scale.as_u64()?),
+                    _ => return Err(malformed("a precision and a scale", both)),
+                },
+                (other, _) => return unsupported(format!("ExactNumberInfo::{}", other)),
+            };
+            decimal_type(precision, scale).map_err(SqlparserError::Unsupported)?
+        }
+        other => return unsupported(format!("DataType::{}", other)),
+    })
+}
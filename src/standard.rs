@@ -0,0 +1,466 @@
+// standard.rs — the SQL standard features a statement needs
+// Author: Sreerag Devadasan
+//
+// For auditing how portable a query is, `conformance` labels each
+// construct of a statement with the feature of ISO/IEC 9075 it needs, by
+// the standard's own ids (E051 for a basic query specification, T121 for
+// WITH, F302 for INTERSECT, ...), or as a vendor extension the standard
+// doesn't have at all. Features are either part of Core SQL, which every
+// conforming implementation supports, or optional, so a statement needs
+// one of three levels: Core SQL, Core SQL plus some optional features, or
+// a vendor's SQL.
+//
+// Most of it comes from the AST; what the AST doesn't keep, such as
+// whether rows were limited with FETCH FIRST or LIMIT, comes from the
+// tokens, along with the nonstandard lexical forms profile.rs warns about.
+// Constructs every query has, such as WHERE or comparisons, aren't listed.
+
+use core::fmt;
+
+use crate::prelude::*;
+use crate::ast::{
+    BinaryOperator, Constraint, DBType, Expression, GroupingElement, JoinKind, MergeAction, RoutineType, SetOperator,
+    Statement, StringPrefix, TableFactor, TransactionCommand, TriggerEvent, TriggerTiming, UnaryOperator,
+};
+use crate::dialect::Dialect;
+use crate::parser::parse_sql_with_dialect;
+use crate::profile::nonstandard_tokens;
+use crate::tokenizer::{Keyword, ParseError, Token, Tokenizer};
+use crate::visit::{walk, Visitor};
+
+/// A feature of the SQL standard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Feature {
+    /// The standard's id, such as `T121` or `F041-03`.
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Whether the feature is part of Core SQL.
+    pub core: bool,
+}
+
+const fn core(id: &'static str, name: &'static str) -> Feature {
+    Feature { id, name, core: true }
+}
+
+const fn optional(id: &'static str, name: &'static str) -> Feature {
+    Feature { id, name, core: false }
+}
+
+const NUMERIC_TYPES: Feature = core("E011", "Numeric data types");
+const CHARACTER_TYPES: Feature = core("E021", "Character string types");
+const QUERY: Feature = core("E051", "Basic query specification");
+const LIKE: Feature = core("E061-04", "LIKE predicate");
+const NULL_PREDICATE: Feature = core("E061-06", "NULL predicate");
+const UNION: Feature = core("E071-01", "UNION DISTINCT table operator");
+const UNION_ALL: Feature = core("E071-02", "UNION ALL table operator");
+const EXCEPT: Feature = core("E071-03", "EXCEPT DISTINCT table operator");
+const PRIVILEGES: Feature = core("E081", "Basic privileges");
+const SET_FUNCTIONS: Feature = core("E091", "Set functions");
+const INSERT: Feature = core("E101-01", "INSERT statement");
+const UPDATE: Feature = core("E101-03", "Searched UPDATE statement");
+const DELETE: Feature = core("E101-04", "Searched DELETE statement");
+const NOT_NULL: Feature = core("E141-01", "NOT NULL constraints");
+const PRIMARY_KEY: Feature = core("E141-03", "PRIMARY KEY constraints");
+const CHECK: Feature = core("E141-06", "CHECK constraints");
+const TRANSACTIONS: Feature = core("E151", "Transaction support");
+const CREATE_TABLE: Feature = core("F031-01", "CREATE TABLE statement to create persistent base tables");
+const CREATE_VIEW: Feature = core("F031-02", "CREATE VIEW statement");
+const DROP_TABLE: Feature = core("F031-13", "DROP TABLE statement");
+const INNER_JOIN: Feature = core("F041-01", "Inner join");
+const LEFT_JOIN: Feature = core("F041-03", "LEFT OUTER JOIN");
+const RIGHT_JOIN: Feature = core("F041-04", "RIGHT OUTER JOIN");
+const DATE: Feature = core("F051-01", "DATE data type");
+const TIME: Feature = core("F051-02", "TIME data type");
+const TIMESTAMP: Feature = core("F051-03", "TIMESTAMP data type");
+const CAST: Feature = core("F201", "CAST function");
+const ROUTINES: Feature = core("T321", "Basic SQL-invoked routines");
+const INTERSECT: Feature = optional("F302-01", "INTERSECT DISTINCT table operator");
+const INTERSECT_ALL: Feature = optional("F302-02", "INTERSECT ALL table operator");
+const EXCEPT_ALL: Feature = optional("F304", "EXCEPT ALL table operator");
+const MERGE: Feature = optional("F312", "MERGE statement");
+const MERGE_CONDITIONS: Feature = optional("F313", "Enhanced MERGE statement");
+const MERGE_DELETE: Feature = optional("F314", "MERGE statement with DELETE branch");
+const FULL_JOIN: Feature = optional("F401-02", "FULL OUTER JOIN");
+const CROSS_JOIN: Feature = optional("F401-04", "CROSS JOIN");
+const TIME_ZONE: Feature = optional("F411", "Time zone specification");
+const NATIONAL: Feature = optional("F421", "National character");
+const DERIVED_TABLES: Feature = optional("F591", "Derived tables");
+const VALUES: Feature = optional("F641", "Row and table constructors");
+const COLLATE: Feature = optional("F690", "Collation support");
+const FETCH_FIRST: Feature = optional("F857", "Top-level <fetch first clause> in query expression");
+const OFFSET: Feature = optional("F861", "Top-level <result offset clause> in query expression");
+const BINARY: Feature = optional("T021", "BINARY and VARBINARY data types");
+const BOOLEAN: Feature = optional("T031", "BOOLEAN data type");
+const WITH: Feature = optional("T121", "WITH (excluding RECURSIVE) in query expression");
+const RECURSIVE: Feature = optional("T131", "Recursive query");
+const DISTINCT_PREDICATE: Feature = optional("T151", "DISTINCT predicate");
+const NOT_DISTINCT: Feature = optional("T152", "DISTINCT predicate with negation");
+const TRIGGERS: Feature = optional("T211", "Basic trigger capability");
+const INSTEAD_OF: Feature = optional("T213", "INSTEAD OF triggers");
+const START_TRANSACTION: Feature = optional("T241", "START TRANSACTION statement");
+const TABLE_FUNCTIONS: Feature = optional("T326", "Table functions");
+const ROLES: Feature = optional("T331", "Basic roles");
+const GROUPING: Feature = optional("T431", "Extended grouping capabilities");
+const LATERAL: Feature = optional("T491", "LATERAL derived table");
+const WINDOWS: Feature = optional("T611", "Elementary OLAP operations");
+const SAMPLING: Feature = optional("T613", "Sampling");
+
+/// What a construct needs: a feature of the standard, or a vendor's SQL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requirement {
+    Feature(Feature),
+    Extension,
+}
+
+/// A construct used in a statement, such as `INTERSECT` or `LIMIT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Usage {
+    pub construct: String,
+    pub requirement: Requirement,
+}
+
+/// The least a database has to conform to for a statement to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Core,
+    /// Core SQL plus optional features.
+    Optional,
+    /// Beyond the standard, in some vendor's SQL.
+    Vendor,
+}
+
+impl Level {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Core => "Core SQL",
+            Level::Optional => "Core SQL with optional features",
+            Level::Vendor => "vendor extensions",
+        }
+    }
+}
+
+/// Every construct of a statement the standard has something to say
+/// about, each listed once, in the order first met.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Conformance {
+    pub usages: Vec<Usage>,
+}
+
+impl Conformance {
+    pub fn level(&self) -> Level {
+        let level = |usage: &Usage| match usage.requirement {
+            Requirement::Feature(feature) if feature.core => Level::Core,
+            Requirement::Feature(_) => Level::Optional,
+            Requirement::Extension => Level::Vendor,
+        };
+        self.usages.iter().map(level).max().unwrap_or(Level::Core)
+    }
+
+    /// The features needed, sorted by id.
+    pub fn features(&self) -> Vec<Feature> {
+        let mut features: Vec<Feature> = Vec::new();
+        for usage in &self.usages {
+            if let Requirement::Feature(feature) = usage.requirement {
+                if !features.contains(&feature) {
+                    features.push(feature);
+                }
+            }
+        }
+        features.sort_by_key(|feature| feature.id);
+        features
+    }
+
+    /// The constructs that are vendor extensions.
+    pub fn extensions(&self) -> Vec<&str> {
+        let extensions = self.usages.iter().filter(|usage| usage.requirement == Requirement::Extension);
+        extensions.map(|usage| usage.construct.as_str()).collect()
+    }
+
+    fn push(&mut self, construct: impl Into<String>, requirement: Requirement) {
+        let usage = Usage { construct: construct.into(), requirement };
+        if !self.usages.contains(&usage) {
+            self.usages.push(usage);
+        }
+    }
+
+    fn feature(&mut self, construct: impl Into<String>, feature: Feature) {
+        self.push(construct, Requirement::Feature(feature));
+    }
+
+    fn extension(&mut self, construct: impl Into<String>) {
+        self.push(construct, Requirement::Extension);
+    }
+}
+
+impl fmt::Display for Conformance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for usage in &self.usages {
+            match usage.requirement {
+                Requirement::Feature(feature) => {
+                    let kind = if feature.core { "core" } else { "optional" };
+                    writeln!(f, "{}: {} {} ({})", usage.construct, feature.id, feature.name, kind)?;
+                }
+                Requirement::Extension => writeln!(f, "{}: vendor extension", usage.construct)?,
+            }
+        }
+        write!(f, "needs {}", self.level().name())?;
+        match self.level() {
+            Level::Core => Ok(()),
+            Level::Optional => {
+                let optional: Vec<&str> = self.features().iter().filter(|f| !f.core).map(|f| f.id).collect();
+                write!(f, ": {}", optional.join(", "))
+            }
+            Level::Vendor => write!(f, ": {}", self.extensions().join(", ")),
+        }
+    }
+}
+
+/// Parses `sql` in `dialect` and labels its constructs.
+pub fn conformance(sql: &str, dialect: Dialect) -> Result<Conformance, ParseError> {
+    let statement = parse_sql_with_dialect(sql, dialect)?;
+    let mut conformance = statement_conformance(&statement);
+    let mut tokenizer = Tokenizer::with_dialect(sql, dialect);
+    loop {
+        match tokenizer.next_token() {
+            Token::Eof => break,
+            Token::Keyword(Keyword::Fetch) => conformance.feature("FETCH FIRST", FETCH_FIRST),
+            Token::Keyword(Keyword::Offset) => conformance.feature("OFFSET", OFFSET),
+            _ => {}
+        }
+    }
+    for warning in nonstandard_tokens(sql, Tokenizer::with_dialect(sql, dialect)) {
+        conformance.extension(warning.construct);
+    }
+    Ok(conformance)
+}
+
+/// Labels the constructs of an already parsed statement. The AST doesn't
+/// say how rows were limited or how literals were written, so those are
+/// left out; `conformance` reads them from the SQL.
+pub fn statement_conformance(statement: &Statement) -> Conformance {
+    let mut labeler = Labeler::default();
+    walk(statement, &mut labeler);
+    labeler.0
+}
+
+#[derive(Default)]
+struct Labeler(Conformance);
+
+impl Labeler {
+    fn data_type(&mut self, data_type: &DBType) {
+        let feature = match data_type {
+            DBType::Int | DBType::Decimal(..) => NUMERIC_TYPES,
+            DBType::Varchar(_) => CHARACTER_TYPES,
+            DBType::Bool => BOOLEAN,
+            DBType::Date => DATE,
+            DBType::Time => TIME,
+            DBType::Timestamp => TIMESTAMP,
+        };
+        self.0.feature(data_type.to_string(), feature);
+    }
+}
+
+impl Visitor for Labeler {
+    fn statement(&mut self, statement: &Statement) {
+        let labels = &mut self.0;
+        match statement {
+            Statement::Select { wildcard, joins, group_by, qualify, locking, .. } => {
+                labels.feature("SELECT", QUERY);
+                for join in joins {
+                    match join.kind {
+                        JoinKind::Inner => labels.feature("INNER JOIN", INNER_JOIN),
+                        JoinKind::Left => labels.feature("LEFT JOIN", LEFT_JOIN),
+                        JoinKind::Right => labels.feature("RIGHT JOIN", RIGHT_JOIN),
+                        JoinKind::Full => labels.feature("FULL JOIN", FULL_JOIN),
+                        JoinKind::Cross => labels.feature("CROSS JOIN", CROSS_JOIN),
+                    }
+                }
+                for element in group_by {
+                    match element {
+                        GroupingElement::Rollup(_) => labels.feature("ROLLUP", GROUPING),
+                        GroupingElement::Cube(_) => labels.feature("CUBE", GROUPING),
+                        GroupingElement::GroupingSets(_) => labels.feature("GROUPING SETS", GROUPING),
+                        _ => {}
+                    }
+                }
+                if !wildcard.exclude.is_empty() {
+                    labels.extension("* EXCLUDE");
+                }
+                if !wildcard.replace.is_empty() {
+                    labels.extension("* REPLACE");
+                }
+                if qualify.is_some() {
+                    labels.extension("QUALIFY");
+                }
+                if !locking.is_empty() {
+                    labels.extension("locking clause");
+                }
+            }
+            Statement::CreateTable { column_list, .. } => {
+                labels.feature("CREATE TABLE", CREATE_TABLE);
+                for column in column_list {
+                    self.data_type(&column.column_type);
+                    for constraint in &column.constraints {
+                        match constraint {
+                            Constraint::NotNull => self.0.feature("NOT NULL", NOT_NULL),
+                            Constraint::PrimaryKey => self.0.feature("PRIMARY KEY", PRIMARY_KEY),
+                            Constraint::Check(_) => self.0.feature("CHECK", CHECK),
+                            Constraint::Collate(_) => self.0.feature("COLLATE", COLLATE),
+                        }
+                    }
+                }
+            }
+            Statement::DropTable { if_exists, .. } => {
+                labels.feature("DROP TABLE", DROP_TABLE);
+                if *if_exists {
+                    labels.extension("IF EXISTS");
+                }
+            }
+            Statement::CreateFunction { or_replace, procedure, parameters, returns, .. } => {
+                labels.feature(if *procedure { "CREATE PROCEDURE" } else { "CREATE FUNCTION" }, ROUTINES);
+                if *or_replace {
+                    labels.extension("OR REPLACE");
+                }
+                for data_type in parameters.iter().map(|parameter| &parameter.data_type).chain(returns) {
+                    match data_type {
+                        RoutineType::Known(known) => self.data_type(known),
+                        RoutineType::Named(name) => self.0.extension(name.clone()),
+                    }
+                }
+            }
+            Statement::CreateView { or_replace, .. } => {
+                labels.feature("CREATE VIEW", CREATE_VIEW);
+                if *or_replace {
+                    labels.extension("OR REPLACE");
+                }
+            }
+            Statement::Insert { .. } | Statement::InsertSelect { .. } => labels.feature("INSERT", INSERT),
+            Statement::Update { .. } => labels.feature("UPDATE", UPDATE),
+            Statement::Delete { .. } => labels.feature("DELETE", DELETE),
+            Statement::Values { .. } => labels.feature("VALUES", VALUES),
+            Statement::Grant { .. } => labels.feature("GRANT", PRIVILEGES),
+            Statement::Revoke { .. } => labels.feature("REVOKE", PRIVILEGES),
+            Statement::GrantRole { .. } => labels.feature("GRANT", ROLES),
+            Statement::RevokeRole { .. } => labels.feature("REVOKE", ROLES),
+            Statement::CreateRole { options, .. } => {
+                labels.feature("CREATE ROLE", ROLES);
+                if !options.is_empty() {
+                    labels.extension("role options");
+                }
+            }
+            Statement::CreateTrigger { timing, events, .. } => {
+                labels.feature("CREATE TRIGGER", TRIGGERS);
+                if *timing == TriggerTiming::InsteadOf {
+                    labels.feature("INSTEAD OF", INSTEAD_OF);
+                }
+                if events.contains(&TriggerEvent::Truncate) {
+                    labels.extension("ON TRUNCATE");
+                }
+            }
+            Statement::Merge { clauses, .. } => {
+                labels.feature("MERGE", MERGE);
+                for clause in clauses {
+                    if clause.condition.is_some() {
+                        labels.feature("WHEN ... AND", MERGE_CONDITIONS);
+                    }
+                    if clause.action == MergeAction::Delete {
+                        labels.feature("THEN DELETE", MERGE_DELETE);
+                    }
+                }
+            }
+            Statement::SetVariable { name, .. } if name.eq_ignore_ascii_case("TIME ZONE") => {
+                labels.feature("SET TIME ZONE", TIME_ZONE);
+            }
+            Statement::Prepare { parameter_types, .. } => {
+                labels.extension("PREPARE");
+                for data_type in parameter_types {
+                    self.data_type(data_type);
+                }
+            }
+            Statement::Transaction { command: TransactionCommand::Begin } => {
+                labels.feature("START TRANSACTION", START_TRANSACTION);
+            }
+            Statement::Transaction { .. } => labels.feature(statement.kind(), TRANSACTIONS),
+            Statement::SetOperation { operator, all, .. } => {
+                let feature = match (operator, all) {
+                    (SetOperator::Union, false) => UNION,
+                    (SetOperator::Union, true) => UNION_ALL,
+                    (SetOperator::Except, false) => EXCEPT,
+                    (SetOperator::Except, true) => EXCEPT_ALL,
+                    (SetOperator::Intersect, false) => INTERSECT,
+                    (SetOperator::Intersect, true) => INTERSECT_ALL,
+                };
+                let construct = if *all { format!("{} ALL", statement.kind()) } else { statement.kind().to_string() };
+                labels.feature(construct, feature);
+            }
+            Statement::With { recursive, .. } => {
+                labels.feature("WITH", WITH);
+                if *recursive {
+                    labels.feature("WITH RECURSIVE", RECURSIVE);
+                }
+            }
+            Statement::Unparsed { .. } => {}
+            // COPY, LOAD DATA, SET, EXECUTE, DEALLOCATE, EXPLAIN, ALTER ROLE
+            // and the statements of extensions
+            statement => labels.extension(statement.kind()),
+        }
+    }
+
+    fn table_factor(&mut self, factor: &TableFactor) {
+        match factor {
+            TableFactor::Table { sample: Some(_), .. } => self.0.feature("TABLESAMPLE", SAMPLING),
+            TableFactor::Derived { lateral, .. } => {
+                self.0.feature("subquery in FROM", DERIVED_TABLES);
+                if *lateral {
+                    self.0.feature("LATERAL", LATERAL);
+                }
+            }
+            TableFactor::Function { .. } => self.0.feature("table function", TABLE_FUNCTIONS),
+            TableFactor::AsOf { .. } => self.0.extension("AS OF VERSION"),
+            TableFactor::Pivot { .. } => self.0.extension("PIVOT"),
+            TableFactor::Unpivot { .. } => self.0.extension("UNPIVOT"),
+            _ => {}
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::BinaryOperation { operator: BinaryOperator::Like, .. } => self.0.feature("LIKE", LIKE),
+            Expression::BinaryOperation { operator: BinaryOperator::ILike, .. } => self.0.extension("ILIKE"),
+            Expression::BinaryOperation { operator: BinaryOperator::IsDistinctFrom, .. } => {
+                self.0.feature("IS DISTINCT FROM", DISTINCT_PREDICATE)
+            }
+            Expression::BinaryOperation { operator: BinaryOperator::IsNotDistinctFrom, .. } => {
+                self.0.feature("IS DISTINCT FROM", DISTINCT_PREDICATE);
+                self.0.feature("IS NOT DISTINCT FROM", NOT_DISTINCT);
+            }
+            Expression::UnaryOperation { operator: UnaryOperator::IsNull | UnaryOperator::IsNotNull, .. } => {
+                self.0.feature("IS NULL", NULL_PREDICATE)
+            }
+            Expression::Function { name, over, .. } => {
+                let name = name.to_ascii_uppercase();
+                if ["COUNT", "SUM", "AVG", "MIN", "MAX"].contains(&name.as_str()) {
+                    self.0.feature(name, SET_FUNCTIONS);
+                }
+                if over.is_some() {
+                    self.0.feature("OVER", WINDOWS);
+                }
+            }
+            Expression::Boolean(_) => self.0.feature("TRUE/FALSE", BOOLEAN),
+            Expression::TypedString { data_type, .. } => self.data_type(data_type),
+            Expression::Cast { data_type, .. } => {
+                self.0.feature("CAST", CAST);
+                self.data_type(data_type);
+            }
+            Expression::Collate { .. } => self.0.feature("COLLATE", COLLATE),
+            Expression::PrefixedString { prefix, .. } => match prefix {
+                StringPrefix::National => self.0.feature("N'...' string", NATIONAL),
+                StringPrefix::Hex => self.0.feature("X'...' string", BINARY),
+                StringPrefix::Bit => self.0.extension("B'...' string"),
+                // An E string is one of the tokens profile.rs warns about
+                StringPrefix::Escape => {}
+            },
+            _ => {}
+        }
+    }
+}
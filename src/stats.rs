@@ -0,0 +1,217 @@
+// stats.rs — table statistics for estimating how many rows a query reads
+// Author: Sreerag Devadasan
+//
+// The planner estimates the rows each step of a query produces from the
+// `Statistics` it's given: per table, a row count and, per column, the
+// number of distinct values and optionally a histogram of an integer
+// column. Tables and columns without statistics get the planner's
+// defaults, so statistics only need to cover the tables that matter.
+//
+// Statistics are set in code, collected from an engine table, or parsed
+// from a text file with one line per table and one per histogram:
+//
+//   # table rows=N [column=distinct ...]
+//   users rows=10000 id=10000 city=40
+//   orders rows=50000 user_id=8000
+//   # table.column histogram=bound,bound,...
+//   users.age histogram=18,25,31,40,52,90
+//
+// A histogram's bounds split the column's values into buckets holding the
+// same number of rows each: the one above has five buckets, the first of
+// them the ages from 18 to 25.
+
+use alloc::collections::BTreeMap;
+#[cfg(feature = "engine")]
+use alloc::collections::BTreeSet;
+use core::fmt;
+
+use crate::prelude::*;
+#[cfg(feature = "engine")]
+use crate::engine::Table;
+#[cfg(feature = "engine")]
+use crate::eval::Value;
+
+/// Equal-depth buckets of an integer column: `bounds` ascend, and each
+/// pair of neighbours holds the same share of the rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub bounds: Vec<i64>,
+}
+
+impl Histogram {
+    /// `buckets` buckets over `values`, or None for no values.
+    pub fn from_values(values: &mut [i64], buckets: usize) -> Option<Self> {
+        if values.is_empty() || buckets == 0 {
+            return None;
+        }
+        values.sort_unstable();
+        let last = values.len() - 1;
+        Some(Histogram { bounds: (0..=buckets).map(|i| values[i * last / buckets]).collect() })
+    }
+
+    /// The share of the rows whose value is below `value`, assuming values
+    /// spread evenly inside each bucket.
+    pub fn fraction_below(&self, value: i64) -> f64 {
+        let (Some(&first), Some(&last)) = (self.bounds.first(), self.bounds.last()) else { return 0.5 };
+        if value <= first {
+            return 0.0;
+        }
+        if value > last {
+            return 1.0;
+        }
+        let buckets = (self.bounds.len() - 1).max(1) as f64;
+        let bucket = self.bounds.windows(2).position(|pair| value <= pair[1]).unwrap_or(0);
+        let (low, high) = (self.bounds[bucket], self.bounds[bucket + 1]);
+        let within = if high > low { (value - low) as f64 / (high - low) as f64 } else { 1.0 };
+        (bucket as f64 + within) / buckets
+    }
+}
+
+/// What's known about one column.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ColumnStats {
+    pub distinct: u64,
+    pub histogram: Option<Histogram>,
+}
+
+/// What's known about one table; column names are matched without regard
+/// to case.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TableStats {
+    pub rows: u64,
+    pub columns: BTreeMap<String, ColumnStats>,
+}
+
+impl TableStats {
+    pub fn new(rows: u64) -> Self {
+        TableStats { rows, columns: BTreeMap::new() }
+    }
+
+    /// Sets the distinct count of `column`.
+    pub fn distinct(mut self, column: &str, distinct: u64) -> Self {
+        self.columns.entry(column.to_lowercase()).or_default().distinct = distinct;
+        self
+    }
+
+    pub fn histogram(mut self, column: &str, histogram: Histogram) -> Self {
+        self.columns.entry(column.to_lowercase()).or_default().histogram = Some(histogram);
+        self
+    }
+
+    pub fn column(&self, name: &str) -> Option<&ColumnStats> {
+        self.columns.get(&name.to_lowercase())
+    }
+
+    /// The statistics of all of `table`'s rows, with a histogram of
+    /// `buckets` buckets for each integer column.
+    #[cfg(feature = "engine")]
+    pub fn collect(table: &Table, buckets: usize) -> Self {
+        let rows = table.rows();
+        let mut stats = TableStats::new(rows.len() as u64);
+        for (i, column) in table.columns.iter().enumerate() {
+            // Values by a key that orders them, NULLs left out
+            let keys: BTreeSet<(u8, i128, &str)> = rows
+                .iter()
+                .filter_map(|row| match &row[i] {
+                    Value::Null => None,
+                    Value::Int(n) => Some((0, i128::from(*n), "")),
+                    Value::Bool(b) => Some((1, i128::from(*b), "")),
+                    Value::String(s) => Some((2, 0, s.as_str())),
+                    Value::Date(date) => Some((3, i128::from(date.days()), "")),
+                    Value::Time(time) => Some((4, i128::from(time.micros()), "")),
+                    Value::Timestamp(timestamp) => Some((5, i128::from(timestamp.micros()), "")),
+                    // A column's decimals all have its scale
+                    Value::Decimal(decimal) => Some((6, decimal.mantissa(), "")),
+                })
+                .collect();
+            stats = stats.distinct(&column.column_name, keys.len() as u64);
+            let mut integers: Vec<i64> = rows
+                .iter()
+                .filter_map(|row| match row[i] {
+                    Value::Int(n) => Some(n),
+                    _ => None,
+                })
+                .collect();
+            if let Some(histogram) = Histogram::from_values(&mut integers, buckets) {
+                stats = stats.histogram(&column.column_name, histogram);
+            }
+        }
+        stats
+    }
+}
+
+/// Statistics of tables by name, matched without regard to case.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Statistics {
+    tables: BTreeMap<String, TableStats>,
+}
+
+/// A line of a statistics file that couldn't be read; `line` counts from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl core::error::Error for StatsError {}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the statistics of `table`, replacing any it had.
+    pub fn set(&mut self, table: &str, stats: TableStats) -> &mut Self {
+        self.tables.insert(table.to_lowercase(), stats);
+        self
+    }
+
+    pub fn table(&self, name: &str) -> Option<&TableStats> {
+        self.tables.get(&name.to_lowercase())
+    }
+
+    /// Reads statistics in the text format described at the top of this
+    /// file.
+    pub fn parse(text: &str) -> Result<Self, StatsError> {
+        let mut statistics = Statistics::new();
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: String| StatsError { line: i + 1, message };
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut words = line.split_whitespace();
+            let Some(name) = words.next() else { continue };
+            let settings: Vec<(&str, &str)> = words
+                .map(|word| word.split_once('=').ok_or_else(|| error(format!("expected key=value, found {}", word))))
+                .collect::<Result<_, _>>()?;
+            if let Some((table, column)) = name.split_once('.') {
+                for (key, value) in settings {
+                    if key != "histogram" {
+                        return Err(error(format!("unknown column setting {}", key)));
+                    }
+                    let bounds: Result<Vec<i64>, _> = value.split(',').map(|bound| bound.trim().parse()).collect();
+                    let bounds = bounds.map_err(|_| error(format!("not a list of integers: {}", value)))?;
+                    if bounds.len() < 2 || bounds.windows(2).any(|pair| pair[0] > pair[1]) {
+                        return Err(error("a histogram needs two or more ascending bounds".to_string()));
+                    }
+                    let stats = statistics.tables.entry(table.to_lowercase()).or_default();
+                    stats.columns.entry(column.to_lowercase()).or_default().histogram = Some(Histogram { bounds });
+                }
+                continue;
+            }
+            let stats = statistics.tables.entry(name.to_lowercase()).or_default();
+            for (key, value) in settings {
+                let count = value.parse().map_err(|_| error(format!("not a count: {}", value)))?;
+                match key {
+                    "rows" => stats.rows = count,
+                    column => stats.columns.entry(column.to_lowercase()).or_default().distinct = count,
+                }
+            }
+        }
+        Ok(statistics)
+    }
+}
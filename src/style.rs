@@ -0,0 +1,201 @@
+// style.rs — ANSI colors for CLI output, in a light or a dark theme
+// Author: Sreerag Devadasan
+//
+// A `Styler` colors each piece of output by its `Role`, using the palette
+// of its theme: SQL is highlighted token by token (keywords, strings,
+// numbers, parameters, comments), errors are underlined under the part of
+// the query they point at, and result tables get a highlighted header and
+// dimmed separators. A plain styler returns everything unchanged, apart
+// from the underline, which is carets on a line of its own either way;
+// deciding when to color (a `--color` option, whether stdout is a
+// terminal) is left to the caller.
+
+use crate::prelude::*;
+use crate::dialect::Dialect;
+#[cfg(feature = "engine")]
+use crate::engine::QueryResult;
+#[cfg(feature = "engine")]
+use crate::eval::Value;
+use crate::span::Span;
+use crate::tokenizer::{tokenize_with_spans, Token};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    /// Bright colors, for terminals with a dark background.
+    #[default]
+    Dark,
+    /// Deeper colors, for terminals with a light background.
+    Light,
+}
+
+impl Theme {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "dark" => Some(Theme::Dark),
+            "light" => Some(Theme::Light),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+        }
+    }
+
+    /// The SGR parameters of a role in this theme.
+    fn code(self, role: Role) -> &'static str {
+        match (self, role) {
+            (_, Role::Header) => "1",
+            (_, Role::Border | Role::Comment) => "2",
+            (_, Role::Null) => "2;3",
+            (Theme::Dark, Role::Prompt) => "1;96",
+            (Theme::Dark, Role::Keyword) => "1;94",
+            (Theme::Dark, Role::String) => "92",
+            (Theme::Dark, Role::Number) => "93",
+            (Theme::Dark, Role::Placeholder) => "95",
+            (Theme::Dark, Role::Success) => "92",
+            (Theme::Dark, Role::Warning) => "93",
+            (Theme::Dark, Role::Error) => "1;91",
+            (Theme::Light, Role::Prompt) => "1;36",
+            (Theme::Light, Role::Keyword) => "1;34",
+            (Theme::Light, Role::String) => "32",
+            (Theme::Light, Role::Number) => "35",
+            (Theme::Light, Role::Placeholder) => "36",
+            (Theme::Light, Role::Success) => "32",
+            (Theme::Light, Role::Warning) => "33",
+            (Theme::Light, Role::Error) => "1;31",
+        }
+    }
+}
+
+/// What a piece of output is, which decides its color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Prompt,
+    Keyword,
+    String,
+    Number,
+    Placeholder,
+    Comment,
+    Success,
+    Warning,
+    Error,
+    Header,
+    Border,
+    Null,
+}
+
+/// Colors output in a theme's palette, or leaves it plain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Styler {
+    theme: Option<Theme>,
+}
+
+impl Styler {
+    /// A styler that adds no color.
+    pub fn plain() -> Self {
+        Self { theme: None }
+    }
+
+    pub fn new(theme: Theme) -> Self {
+        Self { theme: Some(theme) }
+    }
+
+    pub fn is_colored(&self) -> bool {
+        self.theme.is_some()
+    }
+
+    pub fn paint(&self, role: Role, text: &str) -> String {
+        match self.theme {
+            Some(theme) if !text.is_empty() => format!("\x1b[{}m{}\x1b[0m", theme.code(role), text),
+            _ => text.to_string(),
+        }
+    }
+
+    /// `sql` with its keywords, literals, parameters and comments colored,
+    /// tokenized as `dialect` does. The text itself is left as written.
+    pub fn highlight(&self, sql: &str, dialect: Dialect) -> String {
+        if !self.is_colored() {
+            return sql.to_string();
+        }
+        highlight_with(sql, dialect, |role, text| role.map_or_else(|| text.to_string(), |role| self.paint(role, text)))
+    }
+
+    /// The line of `sql` that `span` starts on, and under it carets
+    /// marking the span (up to the end of that line), for pointing at an
+    /// error. An empty span, such as the end of the input, gets one caret.
+    pub fn underline(&self, sql: &str, span: Span) -> String {
+        let start = span.start.min(sql.len());
+        let line_start = sql[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = sql[start..].find('\n').map_or(sql.len(), |i| start + i);
+        let line = &sql[line_start..line_end];
+        let indent = sql[line_start..start].chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect::<String>();
+        let width = sql[start..span.end.clamp(start, line_end)].chars().count().max(1);
+        format!("{}\n{}{}", line, indent, self.paint(Role::Error, &"^".repeat(width)))
+    }
+
+    /// A query result's column names and rows, one line each, or "OK" for
+    /// a statement that returned no rows.
+    #[cfg(feature = "engine")]
+    pub fn table(&self, result: &QueryResult) -> String {
+        if result.columns.is_empty() {
+            return "OK".to_string();
+        }
+        let separator = self.paint(Role::Border, " | ");
+        let header: Vec<String> = result.columns.iter().map(|name| self.paint(Role::Header, name)).collect();
+        let mut lines = vec![header.join(&separator)];
+        for row in &result.rows {
+            let values: Vec<String> = row
+                .iter()
+                .map(|value| match value {
+                    Value::Null => self.paint(Role::Null, "NULL"),
+                    Value::Int(n) => n.to_string(),
+                    Value::Bool(b) => b.to_string(),
+                    Value::String(s) => s.clone(),
+                    Value::Date(date) => date.to_string(),
+                    Value::Time(time) => time.to_string(),
+                    Value::Timestamp(timestamp) => timestamp.to_string(),
+                    Value::Decimal(decimal) => decimal.to_string(),
+                })
+                .collect();
+            lines.push(values.join(&separator));
+        }
+        lines.join("\n")
+    }
+}
+
+/// `sql` split into the pieces `Styler::highlight` colors, each passed to
+/// `paint` with its role (None for the rest of the text) and the results
+/// joined, so other output formats can mark up SQL the same way.
+pub fn highlight_with(sql: &str, dialect: Dialect, mut paint: impl FnMut(Option<Role>, &str) -> String) -> String {
+    let (tokens, spans) = tokenize_with_spans(sql, dialect);
+    let mut out = String::new();
+    let mut end = 0;
+    for (token, span) in tokens.iter().zip(&spans) {
+        // Between tokens there is only whitespace and comments
+        let gap = &sql[end..span.start];
+        if !gap.is_empty() {
+            out.push_str(&paint(if gap.trim().is_empty() { None } else { Some(Role::Comment) }, gap));
+        }
+        let role = match token {
+            Token::Keyword(_) => Some(Role::Keyword),
+            Token::String(_) | Token::PrefixedString(..) => Some(Role::String),
+            Token::Number(_) | Token::Decimal(_) => Some(Role::Number),
+            Token::Placeholder(_) => Some(Role::Placeholder),
+            Token::Hint(_) => Some(Role::Comment),
+            Token::Invalid(_) => Some(Role::Error),
+            _ => None,
+        };
+        let text = span.slice(sql);
+        if !text.is_empty() {
+            out.push_str(&paint(role, text));
+        }
+        end = span.end;
+    }
+    if end < sql.len() {
+        out.push_str(&paint(None, &sql[end..]));
+    }
+    out
+}
@@ -0,0 +1,236 @@
+// template.rs — optional Jinja-lite pre-processing before tokenizing
+// Author: Sreerag Devadasan
+//
+// dbt-style SQL files mix template syntax into the query text:
+//
+//   {{ ref('model') }}          expands to `model`
+//   {{ source('src', 'tbl') }}  expands to `tbl`
+//   {{ var('name') }} / {{ name }}  expands to the variable's value
+//   {{ config(...) }}           removed, as it only configures the model
+//   {% if name %} ... {% elif other %} ... {% else %} ... {% endif %}
+//   {# comment #}               removed
+//
+// Expressions that can't be resolved are replaced by opaque identifiers
+// (`template_1`, `template_2`, ...) so the surrounding statement still
+// tokenizes and parses. Loops and assignments are opaque the same way: a
+// `{% for %}` body is kept once, with its loop variable unresolved, and
+// `{% set %}` is removed, its block form with what it captures. An `{% if %}`
+// tests a variable or its `not`; other conditions, such as comparisons,
+// are errors rather than silently false. Braces inside a SQL string are
+// the string's own unless they start a region closed within it.
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Errors raised while expanding a template.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateError {
+    Unterminated(String),
+    UnsupportedTag(String),
+    UnsupportedCondition(String),
+    UnbalancedBlock(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Unterminated(open) => write!(f, "Unterminated template region starting with {}", open),
+            TemplateError::UnsupportedTag(tag) => write!(f, "Unsupported template tag: {}", tag),
+            TemplateError::UnsupportedCondition(condition) => {
+                write!(f, "Unsupported template condition: {} (only `name` and `not name` are)", condition)
+            }
+            TemplateError::UnbalancedBlock(tag) => write!(f, "Unbalanced template block at: {}", tag),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// Variables available to `{{ name }}`, `{{ var('name') }}` and `{% if %}`.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateContext {
+    pub variables: HashMap<String, String>,
+}
+
+impl TemplateContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_variable(mut self, name: &str, value: &str) -> Self {
+        self.variables.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// A variable is true unless it's missing, empty, `false` or `0`.
+    fn is_truthy(&self, name: &str) -> bool {
+        match self.variables.get(name) {
+            Some(value) => !matches!(value.trim().to_lowercase().as_str(), "" | "false" | "0"),
+            None => false,
+        }
+    }
+}
+
+/// One piece of the input after splitting out template regions.
+enum Segment<'a> {
+    Text(&'a str),
+    Expression(&'a str),
+    Tag(&'a str),
+}
+
+/// Where the text between template regions is in the SQL it is part of.
+#[derive(Clone, Copy, PartialEq)]
+enum Sql {
+    Code,
+    String,
+    LineComment,
+    BlockComment,
+}
+
+/// State of one open `{% if %}`, `{% for %}` or `{% set %}` block.
+struct Block {
+    // The tag that closes it
+    end: &'static str,
+    // Whether the text around the block is being emitted
+    parent_active: bool,
+    // Whether an earlier branch of this block was already taken; a loop's
+    // body is, so its `{% else %}` isn't
+    branch_taken: bool,
+    // Whether the current branch is being emitted
+    active: bool,
+}
+
+/// Expands template syntax into plain SQL.
+pub fn render(input: &str, context: &TemplateContext) -> Result<String, TemplateError> {
+    let mut output = String::with_capacity(input.len());
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut opaque_count = 0;
+
+    for segment in split(input)? {
+        let active = blocks.last().is_none_or(|b| b.active);
+        match segment {
+            Segment::Text(text) if active => output.push_str(text),
+            Segment::Text(_) => {}
+            Segment::Expression(expr) if active => {
+                output.push_str(&expand_expression(expr, context).unwrap_or_else(|| {
+                    opaque_count += 1;
+                    format!("template_{}", opaque_count)
+                }))
+            }
+            Segment::Expression(_) => {}
+            Segment::Tag(tag) => {
+                let (name, argument) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+                let unbalanced = || TemplateError::UnbalancedBlock(tag.to_string());
+                match name {
+                    "if" => {
+                        let taken = evaluate_condition(argument, context)? && active;
+                        blocks.push(Block { end: "endif", parent_active: active, branch_taken: taken, active: taken });
+                    }
+                    "elif" | "else" => {
+                        // A loop has an else too
+                        let branches = |b: &&mut Block| b.end == "endif" || (name, b.end) == ("else", "endfor");
+                        let block = blocks.last_mut().filter(branches).ok_or_else(unbalanced)?;
+                        let condition = name == "else" || evaluate_condition(argument, context)?;
+                        block.active = block.parent_active && !block.branch_taken && condition;
+                        block.branch_taken |= block.active;
+                    }
+                    "for" => blocks.push(Block { end: "endfor", parent_active: active, branch_taken: true, active }),
+                    // `{% set name = value %}`, or a block whose text is the value
+                    "set" if argument.contains('=') => {}
+                    "set" => {
+                        blocks.push(Block { end: "endset", parent_active: active, branch_taken: true, active: false })
+                    }
+                    "endif" | "endfor" | "endset" => {
+                        blocks.pop().filter(|block| block.end == name).ok_or_else(unbalanced)?;
+                    }
+                    _ => return Err(TemplateError::UnsupportedTag(tag.to_string())),
+                }
+            }
+        }
+    }
+
+    if let Some(block) = blocks.last() {
+        return Err(TemplateError::UnbalancedBlock(format!("missing {{% {} %}}", block.end)));
+    }
+    Ok(output)
+}
+
+/// Splits the input into text, `{{ }}` expressions and `{% %}` tags.
+/// Comments are dropped and `-` whitespace-control markers are ignored.
+/// In a SQL string, an opening `{{`, `{%` or `{#` right before the closing
+/// quote, or never closed, is text: `'{{'` is a string of two braces.
+fn split(input: &str) -> Result<Vec<Segment<'_>>, TemplateError> {
+    let mut segments = Vec::new();
+    let (mut text_start, mut at, mut sql) = (0, 0, Sql::Code);
+
+    while at < input.len() {
+        let rest = &input[at..];
+        let close = match rest.as_bytes() {
+            [b'{', b'{', ..] => Some("}}"),
+            [b'{', b'%', ..] => Some("%}"),
+            [b'{', b'#', ..] => Some("#}"),
+            _ => None,
+        };
+        if let Some(close) = close {
+            let quoted = sql == Sql::String;
+            match rest[2..].find(close) {
+                Some(end) if !(quoted && rest[2..].starts_with('\'')) => {
+                    segments.push(Segment::Text(&input[text_start..at]));
+                    let body = rest[2..2 + end].trim_matches('-').trim();
+                    match close {
+                        "}}" => segments.push(Segment::Expression(body)),
+                        "%}" => segments.push(Segment::Tag(body)),
+                        _ => {}
+                    }
+                    at += end + 4;
+                    text_start = at;
+                    continue;
+                }
+                None if !quoted => return Err(TemplateError::Unterminated(rest[..2].to_string())),
+                _ => {}
+            }
+        }
+        // Only where the SQL's strings start and end matters; a quote in a comment is just text
+        let (next, length) = match (sql, rest.as_bytes()) {
+            (Sql::Code, [b'\'', ..]) => (Sql::String, 1),
+            (Sql::Code, [b'-', b'-', ..]) => (Sql::LineComment, 2),
+            (Sql::Code, [b'/', b'*', ..]) => (Sql::BlockComment, 2),
+            (Sql::String, [b'\'', ..]) => (Sql::Code, 1),
+            (Sql::LineComment, [b'\n', ..]) => (Sql::Code, 1),
+            (Sql::BlockComment, [b'*', b'/', ..]) => (Sql::Code, 2),
+            (sql, _) => (sql, rest.chars().next().map_or(1, char::len_utf8)),
+        };
+        sql = next;
+        at += length;
+    }
+    segments.push(Segment::Text(&input[text_start..]));
+    Ok(segments)
+}
+
+/// Resolves `ref(...)`, `source(...)`, `var(...)` and bare variable names.
+fn expand_expression(expr: &str, context: &TemplateContext) -> Option<String> {
+    if let Some((function, arguments)) = expr.strip_suffix(')').and_then(|e| e.split_once('(')) {
+        let arguments: Vec<&str> = arguments.split(',').map(|a| a.trim().trim_matches(['\'', '"'])).collect();
+        return match (function.trim(), arguments.as_slice()) {
+            ("ref", [.., model]) => Some(model.to_string()),
+            ("config", _) => Some(String::new()),
+            ("source", [_, table]) => Some(table.to_string()),
+            ("var", [name, ..]) => context.variables.get(*name).cloned(),
+            _ => None,
+        };
+    }
+    context.variables.get(expr).cloned()
+}
+
+/// Evaluates an `{% if %}` condition: `name` or `not name`.
+fn evaluate_condition(condition: &str, context: &TemplateContext) -> Result<bool, TemplateError> {
+    let condition = condition.trim();
+    let (negated, name) = match condition.strip_prefix("not ") {
+        Some(name) => (true, name.trim()),
+        None => (false, condition),
+    };
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(TemplateError::UnsupportedCondition(condition.to_string()));
+    }
+    Ok(context.is_truthy(name) != negated)
+}
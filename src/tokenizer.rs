@@ -1,6 +1,12 @@
 // tokenizer.rs (combined with error.rs)
 
-use std::fmt;
+use core::fmt;
+
+use crate::prelude::*;
+use crate::ast::StringPrefix;
+use crate::dialect::Dialect;
+use crate::ident::{Ident, QuoteStyle};
+use crate::span::Span;
 
 // === ParseError ===
 
@@ -11,7 +17,35 @@ pub enum ParseError {
     ExpectedIdentifier,
     InvalidExpression(String),
     UnknownStartOfStatement(String),
+    ExpectedNumber,
     General(String),
+    /// The token at the failure point, and every kind of token the parser
+    /// would have accepted there.
+    Unexpected { found: Token, expected: Vec<TokenKind> },
+    /// The input went over one of the configured size limits.
+    LimitExceeded { limit: Limit, max: usize },
+    /// The input couldn't be read into tokens; every such error, in order.
+    Lex(Vec<LexError>),
+    /// The batch the statement was in was cancelled before it was parsed.
+    Cancelled,
+}
+
+/// The size limits a parse can be configured with; see `ParserOptions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    ExpressionDepth,
+    Tokens,
+    StatementLength,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::ExpressionDepth => write!(f, "Expression nesting depth"),
+            Limit::Tokens => write!(f, "Token count"),
+            Limit::StatementLength => write!(f, "Statement length in bytes"),
+        }
+    }
 }
 
 impl fmt::Display for ParseError {
@@ -22,17 +56,90 @@ impl fmt::Display for ParseError {
             ParseError::ExpectedIdentifier => write!(f, "Expected an identifier"),
             ParseError::InvalidExpression(e) => write!(f, "Invalid expression: {}", e),
             ParseError::UnknownStartOfStatement(t) => write!(f, "Unknown start of statement: {}", t),
+            ParseError::ExpectedNumber => write!(f, "Expected a number"),
             ParseError::General(e) => write!(f, "Error: {}", e),
+            ParseError::LimitExceeded { limit, max } => write!(f, "{} exceeds the limit of {}", limit, max),
+            ParseError::Lex(errors) => {
+                let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", messages.join("; "))
+            }
+            ParseError::Cancelled => write!(f, "Cancelled before it was parsed"),
+            ParseError::Unexpected { found, expected } => {
+                let names: Vec<String> = expected.iter().map(|k| k.to_string()).collect();
+                match names.len() {
+                    0 => write!(f, "Unexpected {}", found.describe()),
+                    1 => write!(f, "Expected {}, found {}", names[0], found.describe()),
+                    _ => write!(f, "Expected one of {}, found {}", names.join(", "), found.describe()),
+                }
+            }
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+impl ParseError {
+    /// A stable name for the kind of error, e.g. to count errors by.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedEnd => "unexpected_end",
+            ParseError::ExpectedKeyword(_) => "expected_keyword",
+            ParseError::ExpectedIdentifier => "expected_identifier",
+            ParseError::InvalidExpression(_) => "invalid_expression",
+            ParseError::UnknownStartOfStatement(_) => "unknown_start_of_statement",
+            ParseError::ExpectedNumber => "expected_number",
+            ParseError::General(_) => "general",
+            ParseError::Unexpected { .. } => "unexpected",
+            ParseError::LimitExceeded { .. } => "limit_exceeded",
+            ParseError::Lex(_) => "lexical",
+            ParseError::Cancelled => "cancelled",
         }
     }
 }
 
-impl std::error::Error for ParseError {}
+/// A part of the input that isn't a token, and where it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexErrorKind {
+    /// A character that starts no token.
+    InvalidCharacter(char),
+    /// Quoted text with no closing quote, given here.
+    UnterminatedString(char),
+    /// An integer too large for 64 bits, as written.
+    NumberOverflow(String),
+    /// A `\u` or `\U` escape of an `E` string that names no character.
+    InvalidEscape,
+    /// A character of a `B` or `X` string that isn't a binary or hex digit.
+    InvalidDigit(char),
+}
+
+impl fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexErrorKind::InvalidCharacter(ch) => write!(f, "Invalid character '{}'", ch),
+            LexErrorKind::UnterminatedString(quote) => write!(f, "Unterminated string, missing its closing {}", quote),
+            LexErrorKind::NumberOverflow(digits) => write!(f, "Number {} is too large", digits),
+            LexErrorKind::InvalidEscape => write!(f, "Invalid escape sequence"),
+            LexErrorKind::InvalidDigit(ch) => write!(f, "Invalid digit '{}' in a bit or hex string", ch),
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.kind, self.span.start)
+    }
+}
 
 
 // === Tokenizer and supporting enums ===
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Keyword {
     Select,
     From,
@@ -53,14 +160,143 @@ pub enum Keyword {
     Key,
     Check,
     Null,
+    Limit,
+    Offset,
+    Top,
+    Fetch,
+    Drop,
+    If,
+    Exists,
+    Insert,
+    Into,
+    Values,
+    Grant,
+    Revoke,
+    Alter,
+    On,
+    To,
+    With,
+    Merge,
+    Using,
+    When,
+    Then,
+    Set,
+    As,
+}
+
+impl Keyword {
+    /// Every reserved keyword, in declaration order.
+    pub const ALL: [Keyword; 41] = [
+        Keyword::Select,
+        Keyword::From,
+        Keyword::Where,
+        Keyword::Create,
+        Keyword::Table,
+        Keyword::Order,
+        Keyword::By,
+        Keyword::And,
+        Keyword::Or,
+        Keyword::Not,
+        Keyword::True,
+        Keyword::False,
+        Keyword::Int,
+        Keyword::Bool,
+        Keyword::Varchar,
+        Keyword::Primary,
+        Keyword::Key,
+        Keyword::Check,
+        Keyword::Null,
+        Keyword::Limit,
+        Keyword::Offset,
+        Keyword::Top,
+        Keyword::Fetch,
+        Keyword::Drop,
+        Keyword::If,
+        Keyword::Exists,
+        Keyword::Insert,
+        Keyword::Into,
+        Keyword::Values,
+        Keyword::Grant,
+        Keyword::Revoke,
+        Keyword::Alter,
+        Keyword::On,
+        Keyword::To,
+        Keyword::With,
+        Keyword::Merge,
+        Keyword::Using,
+        Keyword::When,
+        Keyword::Then,
+        Keyword::Set,
+        Keyword::As,
+    ];
+
+    /// The keyword as written in SQL, in upper case.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Keyword::Select => "SELECT",
+            Keyword::From => "FROM",
+            Keyword::Where => "WHERE",
+            Keyword::Create => "CREATE",
+            Keyword::Table => "TABLE",
+            Keyword::Order => "ORDER",
+            Keyword::By => "BY",
+            Keyword::And => "AND",
+            Keyword::Or => "OR",
+            Keyword::Not => "NOT",
+            Keyword::True => "TRUE",
+            Keyword::False => "FALSE",
+            Keyword::Int => "INT",
+            Keyword::Bool => "BOOL",
+            Keyword::Varchar => "VARCHAR",
+            Keyword::Primary => "PRIMARY",
+            Keyword::Key => "KEY",
+            Keyword::Check => "CHECK",
+            Keyword::Null => "NULL",
+            Keyword::Limit => "LIMIT",
+            Keyword::Offset => "OFFSET",
+            Keyword::Top => "TOP",
+            Keyword::Fetch => "FETCH",
+            Keyword::Drop => "DROP",
+            Keyword::If => "IF",
+            Keyword::Exists => "EXISTS",
+            Keyword::Insert => "INSERT",
+            Keyword::Into => "INTO",
+            Keyword::Values => "VALUES",
+            Keyword::Grant => "GRANT",
+            Keyword::Revoke => "REVOKE",
+            Keyword::Alter => "ALTER",
+            Keyword::On => "ON",
+            Keyword::To => "TO",
+            Keyword::With => "WITH",
+            Keyword::Merge => "MERGE",
+            Keyword::Using => "USING",
+            Keyword::When => "WHEN",
+            Keyword::Then => "THEN",
+            Keyword::Set => "SET",
+            Keyword::As => "AS",
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     Keyword(Keyword),
-    Identifier(String),
+    Identifier(Ident),
     String(String),
+    /// A string with a prefix letter, `N'...'`, `E'...'`, `B'...'` or
+    /// `X'...'`; it is of kind `TokenKind::String` too.
+    PrefixedString(StringPrefix, String),
     Number(u64),
+    /// A number with a point and digits after it, as written; it is of
+    /// kind `TokenKind::Number` too.
+    Decimal(String),
+    /// A bind parameter as written: `$1`, `?` or `:name`.
+    Placeholder(String),
+    /// The text of an optimizer hint comment, `/*+ text */`.
+    Hint(String),
+    /// A `BEGIN ... END` compound statement right after the word ROW (the
+    /// body of a MySQL trigger), as written.
+    Block(String),
     Invalid(char),
     LeftParentheses,
     RightParentheses,
@@ -74,24 +310,250 @@ pub enum Token {
     Divide,
     Minus,
     Plus,
+    Concat,
+    DoubleColon,
+    Comma,
+    Dot,
+    Semicolon,
+    Eof,
+}
+
+/// A token without its payload: what the parser can expect next.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum TokenKind {
+    Keyword(Keyword),
+    /// A non-reserved word such as ROWS, matched case-insensitively against
+    /// identifier tokens.
+    Word(&'static str),
+    Identifier,
+    String,
+    Number,
+    Placeholder,
+    Hint,
+    Block,
+    Invalid,
+    LeftParentheses,
+    RightParentheses,
+    GreaterThan,
+    GreaterThanOrEqual,
+    LessThan,
+    LessThanOrEqual,
+    Equal,
+    NotEqual,
+    Multiply,
+    Divide,
+    Minus,
+    Plus,
+    /// `||`, concatenation in most dialects and OR in MySQL.
+    Concat,
+    /// `::`, the Postgres-style cast `expr::type`.
+    DoubleColon,
     Comma,
+    Dot,
     Semicolon,
     Eof,
 }
 
+impl Token {
+    /// The token as it reads in messages, e.g. `identifier users` or `'('`.
+    pub fn describe(&self) -> String {
+        match self {
+            Token::Identifier(name) => format!("identifier {}", name),
+            Token::String(s) => format!("string '{}'", s),
+            Token::PrefixedString(prefix, s) => format!("string {}'{}'", prefix.letter(), s),
+            Token::Number(n) => format!("number {}", n),
+            Token::Decimal(text) => format!("number {}", text),
+            Token::Placeholder(p) => format!("placeholder {}", p),
+            Token::Invalid(ch) => format!("invalid character '{}'", ch),
+            other => other.kind().to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Token::Keyword(k) => TokenKind::Keyword(*k),
+            Token::Identifier(_) => TokenKind::Identifier,
+            Token::String(_) | Token::PrefixedString(..) => TokenKind::String,
+            Token::Number(_) | Token::Decimal(_) => TokenKind::Number,
+            Token::Placeholder(_) => TokenKind::Placeholder,
+            Token::Hint(_) => TokenKind::Hint,
+            Token::Block(_) => TokenKind::Block,
+            Token::Invalid(_) => TokenKind::Invalid,
+            Token::LeftParentheses => TokenKind::LeftParentheses,
+            Token::RightParentheses => TokenKind::RightParentheses,
+            Token::GreaterThan => TokenKind::GreaterThan,
+            Token::GreaterThanOrEqual => TokenKind::GreaterThanOrEqual,
+            Token::LessThan => TokenKind::LessThan,
+            Token::LessThanOrEqual => TokenKind::LessThanOrEqual,
+            Token::Equal => TokenKind::Equal,
+            Token::NotEqual => TokenKind::NotEqual,
+            Token::Multiply => TokenKind::Multiply,
+            Token::Divide => TokenKind::Divide,
+            Token::Minus => TokenKind::Minus,
+            Token::Plus => TokenKind::Plus,
+            Token::Concat => TokenKind::Concat,
+            Token::DoubleColon => TokenKind::DoubleColon,
+            Token::Comma => TokenKind::Comma,
+            Token::Dot => TokenKind::Dot,
+            Token::Semicolon => TokenKind::Semicolon,
+            Token::Eof => TokenKind::Eof,
+        }
+    }
+}
+
+impl TokenKind {
+    /// Returns true if `token` is of this kind.
+    pub fn matches(&self, token: &Token) -> bool {
+        match (self, token) {
+            (TokenKind::Word(word), Token::Identifier(name)) => name.eq_ignore_ascii_case(word),
+            (TokenKind::Word(_), _) => false,
+            _ => token.kind() == *self,
+        }
+    }
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Keyword(k) => write!(f, "{}", k.as_str()),
+            TokenKind::Word(w) => write!(f, "{}", w),
+            TokenKind::Identifier => write!(f, "identifier"),
+            TokenKind::String => write!(f, "string"),
+            TokenKind::Number => write!(f, "number"),
+            TokenKind::Placeholder => write!(f, "placeholder"),
+            TokenKind::Hint => write!(f, "hint"),
+            TokenKind::Block => write!(f, "BEGIN ... END block"),
+            TokenKind::Invalid => write!(f, "invalid character"),
+            TokenKind::LeftParentheses => write!(f, "'('"),
+            TokenKind::RightParentheses => write!(f, "')'"),
+            TokenKind::GreaterThan => write!(f, "'>'"),
+            TokenKind::GreaterThanOrEqual => write!(f, "'>='"),
+            TokenKind::LessThan => write!(f, "'<'"),
+            TokenKind::LessThanOrEqual => write!(f, "'<='"),
+            TokenKind::Equal => write!(f, "'='"),
+            TokenKind::NotEqual => write!(f, "'!='"),
+            TokenKind::Multiply => write!(f, "'*'"),
+            TokenKind::Divide => write!(f, "'/'"),
+            TokenKind::Minus => write!(f, "'-'"),
+            TokenKind::Plus => write!(f, "'+'"),
+            TokenKind::Concat => write!(f, "'||'"),
+            TokenKind::DoubleColon => write!(f, "'::'"),
+            TokenKind::Comma => write!(f, "','"),
+            TokenKind::Dot => write!(f, "'.'"),
+            TokenKind::Semicolon => write!(f, "';'"),
+            TokenKind::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+/// How far through its input a tokenizer, or a script being parsed, is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes read so far.
+    pub offset: usize,
+    /// Bytes in the whole input.
+    pub total: usize,
+    /// Tokens read so far, not counting Eof.
+    pub tokens: usize,
+}
+
+impl Progress {
+    /// The share of the input read, from 0 to 1; 1 for an empty input.
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.offset as f64 / self.total as f64
+        }
+    }
+}
+
 pub struct Tokenizer {
     input: Vec<char>,
+    // Byte offset of every char in `input`, plus the total length at the end
+    offsets: Vec<usize>,
     position: usize,
+    // Tokens returned so far, not counting Eof
+    tokens: usize,
+    double_quoted_identifiers: bool,
+    bracketed_identifiers: bool,
+    // The last token was SELECT or a hint, so a `/*+` comment is a hint
+    hint_allowed: bool,
+    // The last token was the word ROW, so BEGIN opens a compound block
+    block_allowed: bool,
+    // Where the token being read starts, as an index into `input`
+    start: usize,
+    errors: Vec<LexError>,
 }
 
 impl Tokenizer {
     pub fn new(input: &str) -> Self {
+        Self::with_dialect(input, Dialect::Generic)
+    }
+
+    /// Creates a tokenizer that follows the quoting rules of `dialect`.
+    pub fn with_dialect(input: &str, dialect: Dialect) -> Self {
+        let mut offsets: Vec<usize> = input.char_indices().map(|(i, _)| i).collect();
+        offsets.push(input.len());
         Self {
             input: input.chars().collect(),
+            offsets,
             position: 0,
+            tokens: 0,
+            double_quoted_identifiers: dialect.double_quoted_identifiers(),
+            bracketed_identifiers: dialect.bracketed_identifiers(),
+            hint_allowed: false,
+            block_allowed: false,
+            start: 0,
+            errors: Vec::new(),
         }
     }
 
+    /// Overrides the dialect's reading of `"..."`: a string literal if
+    /// `strings` is true, an identifier otherwise.
+    pub fn with_double_quoted_strings(mut self, strings: bool) -> Self {
+        self.double_quoted_identifiers = !strings;
+        self
+    }
+
+    /// Overrides whether the dialect reads `[...]` as an identifier.
+    pub fn with_bracketed_identifiers(mut self, brackets: bool) -> Self {
+        self.bracketed_identifiers = brackets;
+        self
+    }
+
+    /// Returns true if `word` is a reserved keyword (case-insensitive).
+    /// The byte offset of the next character to read.
+    pub fn offset(&self) -> usize {
+        self.offsets[self.position.min(self.input.len())]
+    }
+
+    /// The number of tokens read so far, not counting Eof.
+    pub fn token_count(&self) -> usize {
+        self.tokens
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress { offset: self.offset(), total: self.offsets[self.input.len()], tokens: self.tokens }
+    }
+
+    /// The errors met so far, each read as a `Token::Invalid`.
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    /// Records `kind` for the token being read, up to the current position,
+    /// and returns the `Token::Invalid(ch)` that stands for it.
+    fn invalid(&mut self, kind: LexErrorKind, ch: char) -> Token {
+        let span = Span::new(self.offsets[self.start.min(self.input.len())], self.offset());
+        self.errors.push(LexError { kind, span });
+        Token::Invalid(ch)
+    }
+
+    pub fn is_keyword(word: &str) -> bool {
+        Self::lookup_keyword(word).is_some()
+    }
+
     fn peek(&self) -> Option<char> {
         self.input.get(self.position).copied()
     }
@@ -102,14 +564,39 @@ impl Tokenizer {
         ch
     }
 
+    /// Skips whitespace, `-- line` comments and `/* block */` comments,
+    /// stopping at a hint comment right after SELECT. An unterminated block
+    /// comment runs to the end of the input.
     fn skip_whitespace(&mut self) {
-        while let Some(ch) = self.peek() {
-            if ch.is_whitespace() {
+        loop {
+            match (self.peek(), self.input.get(self.position + 1)) {
+                (Some(ch), _) if ch.is_whitespace() => self.position += 1,
+                (Some('-'), Some('-')) => {
+                    while self.peek().is_some_and(|ch| ch != '\n') {
+                        self.position += 1;
+                    }
+                }
+                (Some('/'), Some('*')) if !(self.hint_allowed && self.input.get(self.position + 2) == Some(&'+')) => {
+                    self.position += 2;
+                    self.read_comment_body();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Reads up to and including the `*/` that closes a block comment,
+    /// returning the text before it.
+    fn read_comment_body(&mut self) -> String {
+        let mut text = String::new();
+        while let Some(ch) = self.advance() {
+            if ch == '*' && self.peek() == Some('/') {
                 self.advance();
-            } else {
                 break;
             }
+            text.push(ch);
         }
+        text
     }
 
     fn read_identifier(&mut self) -> String {
@@ -126,40 +613,198 @@ impl Tokenizer {
     }
 
     fn lookup_keyword(word: &str) -> Option<Keyword> {
-        match word.to_uppercase().as_str() {
-            "SELECT" => Some(Keyword::Select),
-            "FROM" => Some(Keyword::From),
-            "WHERE" => Some(Keyword::Where),
-            "CREATE" => Some(Keyword::Create),
-            "TABLE" => Some(Keyword::Table),
-            "ORDER" => Some(Keyword::Order),
-            "BY" => Some(Keyword::By),
-            "AND" => Some(Keyword::And),
-            "OR" => Some(Keyword::Or),
-            "NOT" => Some(Keyword::Not),
-            "TRUE" => Some(Keyword::True),
-            "FALSE" => Some(Keyword::False),
-            "INT" => Some(Keyword::Int),
-            "BOOL" => Some(Keyword::Bool),
-            "VARCHAR" => Some(Keyword::Varchar),
-            "PRIMARY" => Some(Keyword::Primary),
-            "KEY" => Some(Keyword::Key),
-            "CHECK" => Some(Keyword::Check),
-            "NULL" => Some(Keyword::Null),
-            _ => None,
+        Keyword::ALL.into_iter().find(|k| k.as_str().eq_ignore_ascii_case(word))
+    }
+
+    /// Reads up to the closing `quote`; a doubled quote stands for the quote
+    /// character itself. Unterminated input yields `Token::Invalid(quote)`.
+    fn read_quoted(&mut self, quote: char, make: fn(String) -> Token) -> Token {
+        let mut result = String::new();
+        while let Some(ch) = self.advance() {
+            if ch != quote {
+                result.push(ch);
+            } else if self.peek() == Some(quote) {
+                self.advance();
+                result.push(quote);
+            } else {
+                return make(result);
+            }
+        }
+        self.invalid(LexErrorKind::UnterminatedString(quote), quote)
+    }
+
+    /// Reads the string after a prefix letter and its opening quote. An `E`
+    /// string resolves its backslash escapes; a `B` or `X` string holding
+    /// anything but binary or hex digits yields `Token::Invalid` of the
+    /// first character that isn't one.
+    fn read_prefixed(&mut self, prefix: StringPrefix) -> Token {
+        self.advance();
+        let token = match prefix {
+            StringPrefix::Escape => self.read_escaped(),
+            _ => self.read_quoted('\'', Token::String),
+        };
+        let Token::String(value) = token else { return token };
+        let digit = |ch: &char| match prefix {
+            StringPrefix::Bit => matches!(ch, '0' | '1'),
+            StringPrefix::Hex => ch.is_ascii_hexdigit(),
+            _ => true,
+        };
+        match value.chars().find(|ch| !digit(ch)) {
+            Some(ch) => self.invalid(LexErrorKind::InvalidDigit(ch), ch),
+            None => Token::PrefixedString(prefix, value),
+        }
+    }
+
+    /// Reads up to the closing quote of an `E'...'` string, resolving `\n`,
+    /// `\t`, `\r`, `\b`, `\f`, octal `\ooo`, `\xhh`, `\uXXXX` and
+    /// `\UXXXXXXXX`. A backslash before any other character, a quote among
+    /// them, stands for that character, and so does a doubled quote. A
+    /// `\u` or `\U` without as many digits, or not naming a character,
+    /// yields `Token::Invalid('\\')` once the string is read to its end.
+    fn read_escaped(&mut self) -> Token {
+        let mut result = String::new();
+        let mut escapes_valid = true;
+        while let Some(ch) = self.advance() {
+            let escaped = match ch {
+                '\'' if self.peek() == Some('\'') => {
+                    self.advance();
+                    '\''
+                }
+                '\'' if escapes_valid => return Token::String(result),
+                '\'' => return self.invalid(LexErrorKind::InvalidEscape, '\\'),
+                '\\' => {
+                    let Some(escaped) = self.advance() else { break };
+                    let code = match escaped {
+                        'n' => Some('\n'),
+                        't' => Some('\t'),
+                        'r' => Some('\r'),
+                        'b' => Some('\u{8}'),
+                        'f' => Some('\u{c}'),
+                        '0'..='7' => {
+                            self.position -= 1;
+                            self.read_code(8, 3, 1)
+                        }
+                        'x' => match self.peek().filter(char::is_ascii_hexdigit) {
+                            Some(_) => self.read_code(16, 2, 1),
+                            None => Some('x'),
+                        },
+                        'u' => self.read_code(16, 4, 4),
+                        'U' => self.read_code(16, 8, 8),
+                        other => Some(other),
+                    };
+                    escapes_valid &= code.is_some();
+                    code.unwrap_or('\\')
+                }
+                other => other,
+            };
+            result.push(escaped);
+        }
+        self.invalid(LexErrorKind::UnterminatedString('\''), '\'')
+    }
+
+    /// The character numbered by the next `min` to `max` digits in `radix`.
+    fn read_code(&mut self, radix: u32, max: usize, min: usize) -> Option<char> {
+        let mut code = 0;
+        let mut digits = 0;
+        while let Some(digit) = self.peek().and_then(|ch| ch.to_digit(radix)).filter(|_| digits < max) {
+            code = code * radix + digit;
+            digits += 1;
+            self.advance();
         }
+        (digits >= min).then(|| char::from_u32(code)).flatten()
+    }
+
+    /// After a `$`: whether it opens a dollar-quoted string, `$$` or `$tag$`
+    /// (a `$1` placeholder otherwise).
+    fn at_dollar_quote(&self) -> bool {
+        let rest = &self.input[self.position.min(self.input.len())..];
+        let tag = rest.iter().take_while(|ch| ch.is_alphanumeric() || **ch == '_').count();
+        rest.get(tag) == Some(&'$') && !rest.first().is_some_and(char::is_ascii_digit)
+    }
+
+    /// Reads a dollar-quoted string after its opening `$`: the body runs
+    /// verbatim, quotes and semicolons included, up to the same `$tag$`.
+    /// Unterminated input yields `Token::Invalid('$')`.
+    fn read_dollar_quoted(&mut self) -> Token {
+        let tag = self.read_identifier();
+        self.advance();
+        let delimiter: Vec<char> = format!("${}$", tag).chars().collect();
+        let start = self.position;
+        while self.position < self.input.len() {
+            if self.input[self.position..].starts_with(&delimiter) {
+                let body = self.input[start..self.position].iter().collect();
+                self.position += delimiter.len();
+                return Token::String(body);
+            }
+            self.position += 1;
+        }
+        self.invalid(LexErrorKind::UnterminatedString('$'), '$')
+    }
+
+    /// Reads the `BEGIN ... END` block starting at char `start`, up to its
+    /// matching END; `None` (leaving BEGIN a plain word) if it has none.
+    fn read_block(&mut self, start: usize) -> Option<Token> {
+        let rest: String = self.input[start..].iter().collect();
+        let text = &rest[..compound_block_len(&rest)?];
+        self.position = start + text.chars().count();
+        Some(Token::Block(text.to_string()))
+    }
+
+    /// Like `next_token`, but also returns the byte range of the token.
+    pub fn next_spanned(&mut self) -> (Token, Span) {
+        self.skip_whitespace();
+        let start = self.offset();
+        let token = self.next_token();
+        let end = self.offset();
+        (token, Span::new(start, end))
     }
 
     pub fn next_token(&mut self) -> Token {
         self.skip_whitespace();
+        self.start = self.position;
+        let token = self.read_token();
+        if token != Token::Eof {
+            self.tokens += 1;
+        }
+        self.hint_allowed = matches!(token, Token::Keyword(Keyword::Select) | Token::Hint(_));
+        self.block_allowed = matches!(&token, Token::Identifier(word) if word.eq_ignore_ascii_case("ROW"));
+        token
+    }
+
+    fn read_token(&mut self) -> Token {
         match self.advance() {
             Some(',') => Token::Comma,
             Some(';') => Token::Semicolon,
+            Some('.') => Token::Dot,
+            Some('?') => Token::Placeholder("?".to_string()),
+            Some('$') if self.at_dollar_quote() => self.read_dollar_quoted(),
+            Some(':') if self.peek() == Some(':') => {
+                self.advance();
+                Token::DoubleColon
+            }
+            Some(sigil @ ('$' | ':')) => {
+                let name = self.read_identifier();
+                let valid = match sigil {
+                    '$' => !name.is_empty() && name.chars().all(|c| c.is_ascii_digit()),
+                    _ => name.starts_with(|c: char| c.is_alphabetic()),
+                };
+                if valid {
+                    Token::Placeholder(format!("{}{}", sigil, name))
+                } else {
+                    self.position -= name.chars().count();
+                    self.invalid(LexErrorKind::InvalidCharacter(sigil), sigil)
+                }
+            }
             Some('(') => Token::LeftParentheses,
             Some(')') => Token::RightParentheses,
             Some('+') => Token::Plus,
             Some('-') => Token::Minus,
             Some('*') => Token::Multiply,
+            // skip_whitespace only stops at `/*` for a hint
+            Some('/') if self.peek() == Some('*') => {
+                self.position += 2;
+                Token::Hint(self.read_comment_body().trim().to_string())
+            }
             Some('/') => Token::Divide,
             Some('=') => Token::Equal,
             Some('>') => {
@@ -178,52 +823,246 @@ impl Tokenizer {
                     Token::LessThan
                 }
             }
+            Some('|') if self.peek() == Some('|') => {
+                self.advance();
+                Token::Concat
+            }
             Some('!') => {
                 if self.peek() == Some('=') {
                     self.advance();
                     Token::NotEqual
                 } else {
-                    Token::Invalid('!')
+                    self.invalid(LexErrorKind::InvalidCharacter('!'), '!')
                 }
             }
             Some(ch) if ch.is_alphabetic() => {
                 let mut ident = String::new();
                 ident.push(ch);
                 ident.push_str(&self.read_identifier());
+                if let Some(prefix) = StringPrefix::from_letter(ch).filter(|_| ident.len() == 1) {
+                    if self.peek() == Some('\'') {
+                        return self.read_prefixed(prefix);
+                    }
+                }
+                if self.block_allowed && ident.eq_ignore_ascii_case("BEGIN") {
+                    if let Some(block) = self.read_block(self.position - ident.chars().count()) {
+                        return block;
+                    }
+                }
                 if let Some(keyword) = Self::lookup_keyword(&ident) {
                     Token::Keyword(keyword)
                 } else {
-                    Token::Identifier(ident)
+                    Token::Identifier(Ident::new(ident))
                 }
             }
-            Some(ch) if ch.is_digit(10) => {
+            Some(ch) if ch.is_ascii_digit() => {
                 let mut num_str = String::new();
                 num_str.push(ch);
                 while let Some(next) = self.peek() {
-                    if next.is_digit(10) {
+                    if next.is_ascii_digit() {
                         num_str.push(next);
                         self.advance();
                     } else {
                         break;
                     }
                 }
-                Token::Number(num_str.parse::<u64>().unwrap())
-            }
-            Some('"') | Some('\'') => {
-                let quote = self.input[self.position - 1];
-                let mut result = String::new();
-                while let Some(ch) = self.peek() {
+                // A point followed by a digit makes it a decimal
+                if self.peek() == Some('.') && self.input.get(self.position + 1).is_some_and(char::is_ascii_digit) {
+                    num_str.push('.');
                     self.advance();
-                    if ch == quote {
-                        return Token::String(result);
-                    } else {
-                        result.push(ch);
+                    while let Some(next) = self.peek().filter(char::is_ascii_digit) {
+                        num_str.push(next);
+                        self.advance();
                     }
+                    return Token::Decimal(num_str);
+                }
+                match num_str.parse::<u64>() {
+                    Ok(n) => Token::Number(n),
+                    Err(_) => self.invalid(LexErrorKind::NumberOverflow(num_str), ch),
                 }
-                Token::Invalid(quote)
             }
+            Some('"') if self.double_quoted_identifiers => {
+                self.read_quoted('"', |name| Token::Identifier(Ident::quoted(name, QuoteStyle::Double)))
+            }
+            Some('[') if self.bracketed_identifiers => {
+                self.read_quoted(']', |name| Token::Identifier(Ident::quoted(name, QuoteStyle::Bracket)))
+            }
+            Some('`') => self.read_quoted('`', |name| Token::Identifier(Ident::quoted(name, QuoteStyle::Backtick))),
+            Some(quote @ ('"' | '\'')) => self.read_quoted(quote, Token::String),
             None => Token::Eof,
-            Some(ch) => Token::Invalid(ch),
+            Some(ch) => self.invalid(LexErrorKind::InvalidCharacter(ch), ch),
+        }
+    }
+}
+
+/// The length of the dollar-quoted string `$tag$ ... $tag$` at the start of
+/// `text` (to the end of `text` if unterminated), or 0 if there is none.
+pub(crate) fn dollar_quoted_len(text: &str) -> usize {
+    let tag = text[1..].find(|ch: char| !(ch.is_alphanumeric() || ch == '_')).map_or(text.len() - 1, |n| n);
+    if !text[1 + tag..].starts_with('$') || text[1..].starts_with(|ch: char| ch.is_ascii_digit()) {
+        return 0;
+    }
+    let delimiter = &text[..tag + 2];
+    text[delimiter.len()..].find(delimiter).map_or(text.len(), |end| 2 * delimiter.len() + end)
+}
+
+/// The length of the quoted text at the start of `text`, through the quote
+/// that closes it (to the end of `text` if unterminated). A doubled quote
+/// stands for the quote, as `read_quoted` reads it; with
+/// `backslash_escapes`, as in an `E'...'` string, so does a backslash and
+/// the character after it.
+pub(crate) fn quoted_len(text: &str, backslash_escapes: bool) -> usize {
+    let quote = text.chars().next().unwrap_or_default();
+    let mut chars = text.char_indices().skip(1);
+    while let Some((i, ch)) = chars.next() {
+        if ch == '\\' && backslash_escapes {
+            chars.next();
+        } else if ch == quote && !text[i + 1..].starts_with(quote) {
+            return i + 1;
+        } else if ch == quote {
+            chars.next();
+        }
+    }
+    text.len()
+}
+
+/// Tokenizes the whole input, always ending the list with `Token::Eof`.
+/// The length in bytes of the `BEGIN ... END` block at the start of `text`,
+/// through its matching END; `None` if there is none. BEGIN and
+/// CASE open nested blocks, END IF, END LOOP, END WHILE and END REPEAT
+/// close statements that didn't, and quoted text, dollar-quoted text and
+/// comments are skipped.
+pub(crate) fn compound_block_len(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut closing_case = false;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '\'' | '"' | '`' => while chars.next().is_some_and(|(_, c)| c != ch) {},
+            '$' => {
+                let end = i + dollar_quoted_len(&text[i..]);
+                while chars.next_if(|&(j, _)| j < end).is_some() {}
+            }
+            '-' if chars.peek().is_some_and(|&(_, c)| c == '-') => while chars.next_if(|&(_, c)| c != '\n').is_some() {},
+            '/' if chars.peek().is_some_and(|&(_, c)| c == '*') => {
+                chars.next();
+                while let Some((_, c)) = chars.next() {
+                    if c == '*' && chars.next_if(|&(_, c)| c == '/').is_some() {
+                        break;
+                    }
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = i + c.len_utf8();
+                while let Some((j, c)) = chars.next_if(|&(_, c)| c.is_alphanumeric() || c == '_') {
+                    end = j + c.len_utf8();
+                }
+                let word = text[i..end].to_ascii_uppercase();
+                match word.as_str() {
+                    "BEGIN" => depth += 1,
+                    "CASE" if !closing_case => depth += 1,
+                    "END" => {
+                        let next = text[end..].trim_start();
+                        let next = &next[..next.find(|c: char| !c.is_alphanumeric()).unwrap_or(next.len())];
+                        if ["IF", "LOOP", "WHILE", "REPEAT"].iter().any(|w| next.eq_ignore_ascii_case(w)) {
+                            continue;
+                        }
+                        closing_case = next.eq_ignore_ascii_case("CASE");
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(end);
+                        }
+                        continue;
+                    }
+                    _ => {}
+                }
+                closing_case = false;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+pub fn tokenize(input: &str) -> Vec<Token> {
+    tokenize_with_dialect(input, Dialect::Generic)
+}
+
+/// Like `tokenize`, but follows the quoting rules of `dialect`.
+pub fn tokenize_with_dialect(input: &str, dialect: Dialect) -> Vec<Token> {
+    tokenize_with_spans(input, dialect).0
+}
+
+/// Like `tokenize_with_dialect`, but stops with `LimitExceeded` as soon as
+/// more than `max_tokens` tokens (not counting Eof) have been read, so huge
+/// inputs are rejected without being tokenized in full.
+pub fn tokenize_limited(input: &str, dialect: Dialect, max_tokens: usize) -> Result<Vec<Token>, ParseError> {
+    collect_tokens(&mut Tokenizer::with_dialect(input, dialect), Some(max_tokens))
+}
+
+/// Like `tokenize_with_dialect`, but calls `report` every `every` tokens
+/// and once more at the end, so a caller can show how far a large input
+/// has got.
+pub fn tokenize_with_progress(
+    input: &str,
+    dialect: Dialect,
+    every: usize,
+    mut report: impl FnMut(Progress),
+) -> Vec<Token> {
+    let mut tokenizer = Tokenizer::with_dialect(input, dialect);
+    let mut tokens = Vec::new();
+    loop {
+        let token = tokenizer.next_token();
+        let done = token == Token::Eof;
+        tokens.push(token);
+        if done || tokenizer.token_count().is_multiple_of(every.max(1)) {
+            report(tokenizer.progress());
+        }
+        if done {
+            return tokens;
+        }
+    }
+}
+
+/// Reads every token of an already configured tokenizer, enforcing
+/// `max_tokens` like `tokenize_limited`. The tokenizer keeps the errors.
+pub(crate) fn collect_tokens(tokenizer: &mut Tokenizer, max_tokens: Option<usize>) -> Result<Vec<Token>, ParseError> {
+    trace_span!("tokenize", tokenizer.offsets[tokenizer.input.len()]);
+    let mut tokens = Vec::new();
+    loop {
+        let token = tokenizer.next_token();
+        let done = token == Token::Eof;
+        if let Some(max_tokens) = max_tokens.filter(|&max| !done && tokens.len() == max) {
+            return Err(ParseError::LimitExceeded { limit: Limit::Tokens, max: max_tokens });
+        }
+        tokens.push(token);
+        if done {
+            return Ok(tokens);
+        }
+    }
+}
+
+/// Tokenizes the whole input, returning each token's byte range alongside it.
+pub fn tokenize_with_spans(input: &str, dialect: Dialect) -> (Vec<Token>, Vec<Span>) {
+    let (tokens, spans, _) = tokenize_with_errors(input, dialect);
+    (tokens, spans)
+}
+
+/// Like `tokenize_with_spans`, also returning the errors of the input;
+/// each stands in the tokens as a `Token::Invalid`.
+pub fn tokenize_with_errors(input: &str, dialect: Dialect) -> (Vec<Token>, Vec<Span>, Vec<LexError>) {
+    trace_span!("tokenize", input.len());
+    let mut tokenizer = Tokenizer::with_dialect(input, dialect);
+    let mut tokens = Vec::new();
+    let mut spans = Vec::new();
+    loop {
+        let (token, span) = tokenizer.next_spanned();
+        let done = token == Token::Eof;
+        tokens.push(token);
+        spans.push(span);
+        if done {
+            break;
         }
     }
+    (tokens, spans, tokenizer.errors)
 }
@@ -0,0 +1,155 @@
+// trace.rs — optional instrumentation of tokenizing and parsing
+// Author: Sreerag Devadasan
+//
+// Built only with the "trace" feature. Tokenization, every statement and
+// each grammar rule report an Enter and an Exit event (with the elapsed
+// time) to the installed subscriber, which can print them or forward them
+// to a logging framework. The parsers also report each token they consume,
+// and the Pratt loop each precedence comparison that decides whether an
+// operator joins the expression being built or is left to an enclosing
+// one. `collect` gathers the events of one parse and `render_tree` lays
+// them out as an indented tree, for seeing how a statement was parsed.
+// Without the feature the `trace_span!` and `trace_event!` calls compile
+// to nothing.
+
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::tokenizer::Token;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceKind {
+    Enter,
+    Exit(Duration),
+    /// The token at the event's position was consumed.
+    Token,
+    /// The binary operator at the event's position, which binds with
+    /// `precedence`, was compared with the lowest precedence the current
+    /// subexpression accepts, `min`; it's taken when not below `min`.
+    Precedence { precedence: u8, min: u8 },
+}
+
+/// One instrumentation event. `position` is the token index (or, for
+/// "tokenize", the input length in bytes) where the span started, or of
+/// the token a `Token` or `Precedence` event is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEvent {
+    pub name: &'static str,
+    pub position: usize,
+    pub kind: TraceKind,
+}
+
+type Subscriber = Box<dyn Fn(&TraceEvent) + Send + Sync>;
+
+static SUBSCRIBER: RwLock<Option<Subscriber>> = RwLock::new(None);
+
+/// Routes all future events to `subscriber`, replacing any previous one.
+/// The subscriber must not call back into this module.
+pub fn set_subscriber(subscriber: impl Fn(&TraceEvent) + Send + Sync + 'static) {
+    *SUBSCRIBER.write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(subscriber));
+}
+
+/// Stops delivering events.
+pub fn clear_subscriber() {
+    *SUBSCRIBER.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// A subscriber that prints an indented call tree to stderr.
+pub fn stderr_subscriber() -> impl Fn(&TraceEvent) + Send + Sync + 'static {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    let depth = AtomicUsize::new(0);
+    move |event| match event.kind {
+        TraceKind::Enter => {
+            let indent = depth.fetch_add(1, Ordering::Relaxed);
+            eprintln!("[TRACE] {:indent$}-> {} at {}", "", event.name, event.position, indent = indent * 2);
+        }
+        TraceKind::Exit(elapsed) => {
+            let indent = depth.fetch_sub(1, Ordering::Relaxed).saturating_sub(1);
+            eprintln!("[TRACE] {:indent$}<- {} ({:?})", "", event.name, elapsed, indent = indent * 2);
+        }
+        TraceKind::Token => {
+            let indent = depth.load(Ordering::Relaxed);
+            eprintln!("[TRACE] {:indent$}token {}", "", event.position, indent = indent * 2);
+        }
+        TraceKind::Precedence { precedence, min } => {
+            let indent = depth.load(Ordering::Relaxed);
+            let (at, name) = (event.position, event.name);
+            eprintln!("[TRACE] {:indent$}{} at {}: {} vs {}", "", name, at, precedence, min, indent = indent * 2);
+        }
+    }
+}
+
+pub(crate) fn emit(event: TraceEvent) {
+    if let Some(subscriber) = SUBSCRIBER.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        subscriber(&event);
+    }
+}
+
+/// Emits Enter when created and Exit when dropped.
+pub struct TraceSpan {
+    name: &'static str,
+    position: usize,
+    start: Instant,
+}
+
+impl TraceSpan {
+    pub fn enter(name: &'static str, position: usize) -> Self {
+        emit(TraceEvent { name, position, kind: TraceKind::Enter });
+        Self { name, position, start: Instant::now() }
+    }
+}
+
+impl Drop for TraceSpan {
+    fn drop(&mut self) {
+        emit(TraceEvent { name: self.name, position: self.position, kind: TraceKind::Exit(self.start.elapsed()) });
+    }
+}
+
+/// Runs `f`, returning what it returned and the events it reported on this
+/// thread. Replaces any installed subscriber, and clears it when done.
+pub fn collect<T>(f: impl FnOnce() -> T) -> (T, Vec<TraceEvent>) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    let thread = thread::current().id();
+    set_subscriber(move |event| {
+        if thread::current().id() == thread {
+            sink.lock().unwrap_or_else(PoisonError::into_inner).push(*event);
+        }
+    });
+    let result = f();
+    clear_subscriber();
+    let events = events.lock().unwrap_or_else(PoisonError::into_inner).clone();
+    (result, events)
+}
+
+/// The events of a parse of `tokens` as a tree: each grammar rule on a
+/// line of its own above what happened inside it, indented a level
+/// further; each consumed token; and each precedence comparison with
+/// whether the operator was taken. "tokenize" spans are left out.
+pub fn render_tree(events: &[TraceEvent], tokens: &[Token]) -> String {
+    let describe = |position: usize| tokens.get(position).map_or("end of input".to_string(), Token::describe);
+    let mut tree = String::new();
+    let mut depth = 0;
+    for event in events.iter().filter(|event| event.name != "tokenize") {
+        let indent = depth * 2;
+        match event.kind {
+            TraceKind::Enter => {
+                let _ = writeln!(tree, "{:indent$}{}", "", event.name);
+                depth += 1;
+            }
+            TraceKind::Exit(_) => depth = depth.saturating_sub(1),
+            TraceKind::Token => {
+                let _ = writeln!(tree, "{:indent$}consume {}", "", describe(event.position));
+            }
+            TraceKind::Precedence { precedence, min } => {
+                let (comparison, verdict) = if precedence >= min { (">=", "take it") } else { ("<", "leave it") };
+                let operator = describe(event.position);
+                let decision = format!("binds at {} {} {}: {}", precedence, comparison, min, verdict);
+                let _ = writeln!(tree, "{:indent$}{} {}", "", operator, decision);
+            }
+        }
+    }
+    tree
+}
@@ -0,0 +1,478 @@
+// tutorial.rs — staged SQL exercises with structural checking and hints
+// Author: Sreerag Devadasan
+//
+// A `Tutorial` walks through `EXERCISES` in order, from selecting every
+// column of a table to creating one. Each exercise explains a concept and
+// sets a task; an answer is parsed and its AST checked for what the task
+// asks for, so any way of writing a correct answer passes (`30 < age` as
+// well as `age > 30`, extra columns, other aliases) and the feedback on a
+// wrong one says which part is missing. An answer that doesn't parse gets
+// a hint derived from the parse error: a misspelled keyword, a clause cut
+// short, ORDER without BY, and so on.
+//
+// The exercises use two tables:
+//
+//   users (id INT, name VARCHAR, age INT, city VARCHAR)
+//   orders (id INT, user_id INT, total INT)
+
+use crate::prelude::*;
+use crate::ast::{
+    BinaryOperator, Constraint, DBType, Expression, GroupingElement, JoinKind, SelectItem, Statement, TableFactor,
+};
+use crate::dialect::Dialect;
+use crate::ident::closest;
+use crate::parser::SQLParser;
+use crate::session::ParserOptions;
+use crate::span::Span;
+use crate::tokenizer::{tokenize_with_spans, Keyword, ParseError, Token, TokenKind};
+
+/// One stage of the tutorial.
+#[derive(Debug, Clone, Copy)]
+pub struct Exercise {
+    /// The concept taught, as a short title.
+    pub concept: &'static str,
+    /// A few sentences explaining the concept.
+    pub lesson: &'static str,
+    pub task: &'static str,
+    /// One answer that passes.
+    pub solution: &'static str,
+    /// Whether a parsed answer does what the task asks, or what it lacks.
+    pub check: fn(&Statement) -> Result<(), &'static str>,
+}
+
+pub const EXERCISES: &[Exercise] = &[
+    Exercise {
+        concept: "Selecting every column",
+        lesson: "A SELECT reads rows from a table. `SELECT * FROM t` returns every column of every row of t.",
+        task: "Select every column of the users table.",
+        solution: "SELECT * FROM users",
+        check: |statement| {
+            let (columns, from, _) = select_parts(statement)?;
+            reads(from, "users")?;
+            require(columns.iter().any(SelectItem::is_wildcard), "Use * to select every column.")
+        },
+    },
+    Exercise {
+        concept: "Choosing columns",
+        lesson: "Instead of *, a SELECT can list the columns it wants, separated by commas, in the order they \
+                 should come out.",
+        task: "Select the name and age of every user.",
+        solution: "SELECT name, age FROM users",
+        check: |statement| {
+            let (columns, from, _) = select_parts(statement)?;
+            reads(from, "users")?;
+            require(!columns.iter().any(SelectItem::is_wildcard), "List the columns by name rather than using *.")?;
+            require(has_column(columns, "name"), "Include the name column.")?;
+            require(has_column(columns, "age"), "Include the age column.")
+        },
+    },
+    Exercise {
+        concept: "Filtering rows with WHERE",
+        lesson: "A WHERE clause after the FROM keeps only the rows its condition is true for. Conditions \
+                 compare columns with values using =, !=, <, <=, > and >=.",
+        task: "Write a SELECT with a WHERE clause filtering age > 30 from users.",
+        solution: "SELECT name FROM users WHERE age > 30",
+        check: |statement| {
+            let (_, from, selection) = select_parts(statement)?;
+            reads(from, "users")?;
+            let selection = selection.ok_or("Add a WHERE clause after FROM users.")?;
+            require(
+                any_condition(selection, &|e| compares(e, "age", BinaryOperator::GreaterThan, 30)),
+                "The condition should keep the users whose age is greater than 30: age > 30.",
+            )
+        },
+    },
+    Exercise {
+        concept: "Combining conditions",
+        lesson: "AND keeps the rows both conditions are true for, OR the rows either one is. Strings are written \
+                 in single quotes, like 'Paris'.",
+        task: "Select the users older than 30 who live in Paris.",
+        solution: "SELECT name FROM users WHERE age > 30 AND city = 'Paris'",
+        check: |statement| {
+            let (_, from, selection) = select_parts(statement)?;
+            reads(from, "users")?;
+            let selection = selection.ok_or("Add a WHERE clause with two conditions.")?;
+            let conditions = conjuncts(selection);
+            require(conditions.len() > 1, "Join the two conditions with AND.")?;
+            require(
+                conditions.iter().any(|e| compares(e, "age", BinaryOperator::GreaterThan, 30)),
+                "One of the conditions should be age > 30.",
+            )?;
+            require(
+                conditions.iter().any(|e| equals_string(e, "city", "Paris")),
+                "One of the conditions should be city = 'Paris'.",
+            )
+        },
+    },
+    Exercise {
+        concept: "Sorting and limiting",
+        lesson: "ORDER BY sorts the result by a column, ascending unless DESC follows it. LIMIT n keeps only the \
+                 first n rows.",
+        task: "Select the names of the three oldest users.",
+        solution: "SELECT name FROM users ORDER BY age DESC LIMIT 3",
+        check: |statement| {
+            let Statement::Select { from, order_by, limit, .. } = statement else {
+                return Err("Start with SELECT.");
+            };
+            reads(from, "users")?;
+            let first = order_by.first().ok_or("Sort the users by age with ORDER BY.")?;
+            require(is_column(&first.expr, "age"), "Sort by the age column.")?;
+            require(first.asc == Some(false), "The oldest come first in descending order: ORDER BY age DESC.")?;
+            require(*limit == Some(3), "Keep only three rows with LIMIT 3.")
+        },
+    },
+    Exercise {
+        concept: "Joining tables",
+        lesson: "A JOIN pairs the rows of two tables; its ON condition says which rows belong together, usually \
+                 a key of one table equal to a column of the other. Qualify the names as table.column.",
+        task: "Select the orders together with the users who placed them, matching orders.user_id to users.id.",
+        solution: "SELECT * FROM users JOIN orders ON users.id = orders.user_id",
+        check: |statement| {
+            let Statement::Select { from, joins, .. } = statement else {
+                return Err("Start with SELECT.");
+            };
+            let [join] = joins.as_slice() else {
+                return Err("Add one JOIN after the FROM table.");
+            };
+            let (users, orders) = match (table_name(from), table_name(&join.relation)) {
+                (Some(a), Some(b)) if a.eq_ignore_ascii_case("users") && b.eq_ignore_ascii_case("orders") => {
+                    (from.as_ref(), &join.relation)
+                }
+                (Some(a), Some(b)) if a.eq_ignore_ascii_case("orders") && b.eq_ignore_ascii_case("users") => {
+                    (&join.relation, from.as_ref())
+                }
+                _ => return Err("Join the users and orders tables."),
+            };
+            require(join.kind != JoinKind::Cross, "Use a JOIN with an ON condition, not a CROSS JOIN.")?;
+            let on = join.on.as_ref().ok_or("Say which rows match with ON.")?;
+            let (users, orders) = (qualifier(users), qualifier(orders));
+            require(
+                any_condition(on, &|e| joins_keys(e, users, orders)),
+                "The ON condition should match the keys: users.id = orders.user_id.",
+            )
+        },
+    },
+    Exercise {
+        concept: "Grouping rows",
+        lesson: "GROUP BY collapses the rows that share a value into one row per value, which is how a query \
+                 lists each distinct value once.",
+        task: "List each city the users live in once, grouping the users by city.",
+        solution: "SELECT city FROM users GROUP BY city",
+        check: |statement| {
+            let Statement::Select { columns, from, group_by, .. } = statement else {
+                return Err("Start with SELECT.");
+            };
+            reads(from, "users")?;
+            require(has_column(columns, "city"), "Select the city column.")?;
+            let grouped = group_by.iter().any(|element| {
+                matches!(element, GroupingElement::Expression(e) if is_column(e, "city"))
+            });
+            require(grouped, "Group the rows with GROUP BY city.")
+        },
+    },
+    Exercise {
+        concept: "Adding rows",
+        lesson: "INSERT INTO t (columns) VALUES (...) adds a row, giving a value for each listed column in order.",
+        task: "Add a user named 'Ada', aged 36, to the users table.",
+        solution: "INSERT INTO users (name, age) VALUES ('Ada', 36)",
+        check: |statement| {
+            let Statement::Insert { table_name, columns, rows } = statement else {
+                return Err("Start with INSERT INTO.");
+            };
+            require(table_name.eq_ignore_ascii_case("users"), "Insert into the users table.")?;
+            let columns = columns.as_deref().ok_or("List the columns the values are for: (name, age).")?;
+            let [row] = rows.as_slice() else {
+                return Err("Add exactly one row of VALUES.");
+            };
+            require(columns.len() == row.len(), "Give one value for each listed column.")?;
+            let value = |name: &str| {
+                columns.iter().position(|column| column.eq_ignore_ascii_case(name)).and_then(|i| row.get(i))
+            };
+            require(matches!(value("name"), Some(Expression::String(s)) if s == "Ada"), "The name should be 'Ada'.")?;
+            require(matches!(value("age"), Some(Expression::Number(36))), "The age should be 36.")
+        },
+    },
+    Exercise {
+        concept: "Creating tables",
+        lesson: "CREATE TABLE t (column type, ...) defines a new table. Types include INT, BOOL and VARCHAR(n); \
+                 PRIMARY KEY after a column's type makes it the key.",
+        task: "Create a table named products with an INT id that is the primary key and a VARCHAR(100) title.",
+        solution: "CREATE TABLE products (id INT PRIMARY KEY, title VARCHAR(100))",
+        check: |statement| {
+            let Statement::CreateTable { table_name, column_list } = statement else {
+                return Err("Start with CREATE TABLE.");
+            };
+            require(table_name.eq_ignore_ascii_case("products"), "Name the table products.")?;
+            let column = |name: &str| column_list.iter().find(|column| column.column_name.eq_ignore_ascii_case(name));
+            let id = column("id").ok_or("Add an id column.")?;
+            require(id.column_type == DBType::Int, "The id column should be an INT.")?;
+            require(
+                id.constraints.contains(&Constraint::PrimaryKey),
+                "Make id the primary key with PRIMARY KEY.",
+            )?;
+            let title = column("title").ok_or("Add a title column.")?;
+            require(title.column_type == DBType::Varchar(100), "The title column should be a VARCHAR(100).")
+        },
+    },
+];
+
+/// What an answer earned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Feedback {
+    Correct,
+    /// The answer parsed but doesn't do what the task asks; the text says
+    /// what is missing.
+    Incorrect(String),
+    /// The answer didn't parse: the parser's message, a hint, and the span
+    /// of the token the parse stopped at.
+    ParseError { message: String, hint: String, span: Option<Span> },
+}
+
+/// Progress through `EXERCISES`.
+#[derive(Debug, Clone)]
+pub struct Tutorial {
+    dialect: Dialect,
+    stage: usize,
+    attempts: usize,
+}
+
+impl Tutorial {
+    pub fn new(dialect: Dialect) -> Self {
+        Self { dialect, stage: 0, attempts: 0 }
+    }
+
+    /// The index of the current exercise; `EXERCISES.len()` once finished.
+    pub fn stage(&self) -> usize {
+        self.stage
+    }
+
+    /// The exercise being worked on, or None once every one is done.
+    pub fn current(&self) -> Option<&'static Exercise> {
+        EXERCISES.get(self.stage)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current().is_none()
+    }
+
+    /// Wrong answers given to the current exercise so far.
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    /// Checks an answer to the current exercise, moving on to the next one
+    /// if it's correct. Once finished, every answer is correct.
+    pub fn submit(&mut self, input: &str) -> Feedback {
+        let Some(exercise) = self.current() else {
+            return Feedback::Correct;
+        };
+        let feedback = check(exercise, input, self.dialect);
+        if feedback == Feedback::Correct {
+            self.skip();
+        } else {
+            self.attempts += 1;
+        }
+        feedback
+    }
+
+    /// Moves on to the next exercise without answering this one.
+    pub fn skip(&mut self) {
+        self.stage = (self.stage + 1).min(EXERCISES.len());
+        self.attempts = 0;
+    }
+}
+
+/// Parses `input` in `dialect` and checks it against `exercise`.
+pub fn check(exercise: &Exercise, input: &str, dialect: Dialect) -> Feedback {
+    let (tokens, spans) = tokenize_with_spans(input, dialect);
+    let mut parser = SQLParser::with_options(&tokens, ParserOptions { dialect, ..ParserOptions::default() });
+    match parser.parse_to_end() {
+        Ok(statement) => match (exercise.check)(&statement) {
+            Ok(()) => Feedback::Correct,
+            Err(missing) => Feedback::Incorrect(missing.to_string()),
+        },
+        Err(error) => {
+            let at = parser.position().max(parser.furthest()).min(tokens.len() - 1);
+            Feedback::ParseError {
+                message: error.to_string(),
+                hint: hint(&error, &tokens, at),
+                span: spans.get(at).or(spans.last()).copied(),
+            }
+        }
+    }
+}
+
+// === Hints ===
+
+/// Advice for fixing a parse that failed at `tokens[at]`.
+fn hint(error: &ParseError, tokens: &[Token], at: usize) -> String {
+    // A misspelled keyword reads as an identifier, and the parse fails at
+    // it or, when it could be taken for an alias, just after it
+    for token in tokens[at.saturating_sub(1)..=at].iter().rev() {
+        if let Token::Identifier(word) = token {
+            if let Some(keyword) = misspelled_keyword(word) {
+                return format!("\"{}\" isn't a keyword; did you mean {}?", word, keyword.as_str());
+            }
+        }
+    }
+
+    let previous = at.checked_sub(1).map(|i| &tokens[i]);
+    let expected = match error {
+        ParseError::Unexpected { expected, .. } => expected.as_slice(),
+        _ => &[],
+    };
+    let expects = |kind: TokenKind| expected.contains(&kind);
+    match (previous, &tokens[at]) {
+        (Some(Token::Keyword(Keyword::Order)), _) => "ORDER is always followed by BY: ORDER BY column.".to_string(),
+        (Some(Token::Equal), Token::Equal) => "SQL compares with a single =, not ==.".to_string(),
+        (_, Token::Invalid('"')) => "Strings are written in single quotes, like 'Paris'.".to_string(),
+        (_, Token::Invalid(ch)) => format!("'{}' has no meaning in SQL here; remove it.", ch),
+        (_, Token::Eof) if expects(TokenKind::Keyword(Keyword::From)) => {
+            "Name the table to read with FROM after the columns, e.g. SELECT name FROM users.".to_string()
+        }
+        (_, Token::Eof) if expects(TokenKind::Number) => {
+            "The statement ends in the middle of an expression; finish it with a column or a value, e.g. \
+             age > 30."
+                .to_string()
+        }
+        (_, Token::Eof) if expects(TokenKind::RightParentheses) => "Close the '(' with a ')'.".to_string(),
+        (_, Token::Eof) => "The statement ends too early; something is missing at the end.".to_string(),
+        (Some(Token::Comma), Token::Keyword(keyword)) => {
+            format!("A comma must be followed by another item; remove the comma before {}.", keyword.as_str())
+        }
+        (_, Token::Keyword(keyword)) if expects(TokenKind::Identifier) => format!(
+            "{} is a reserved word, so it can't be used as a name here; pick another name or quote it.",
+            keyword.as_str()
+        ),
+        (_, found) if !expected.is_empty() => {
+            let expected: Vec<String> = expected.iter().map(TokenKind::to_string).collect();
+            format!("The parser stopped at {}; it can continue with {}.", found.describe(), expected.join(", "))
+        }
+        _ => "Compare your statement with the example in the lesson.".to_string(),
+    }
+}
+
+/// The keyword `word` is a likely misspelling of.
+fn misspelled_keyword(word: &str) -> Option<Keyword> {
+    let keyword = closest(word, Keyword::ALL.iter().map(Keyword::as_str))?;
+    Keyword::ALL.iter().find(|candidate| candidate.as_str() == keyword).copied()
+}
+
+// === Checks ===
+
+fn require(condition: bool, missing: &'static str) -> Result<(), &'static str> {
+    if condition {
+        Ok(())
+    } else {
+        Err(missing)
+    }
+}
+
+/// The columns, FROM item and WHERE condition of a SELECT.
+fn select_parts(statement: &Statement) -> Result<(&[SelectItem], &TableFactor, Option<&Expression>), &'static str> {
+    match statement {
+        Statement::Select { columns, from, selection, .. } => Ok((columns, from, selection.as_ref())),
+        _ => Err("Start with SELECT."),
+    }
+}
+
+fn table_name(factor: &TableFactor) -> Option<&str> {
+    match factor {
+        TableFactor::Table { name, .. } => Some(name),
+        _ => None,
+    }
+}
+
+fn reads(from: &TableFactor, table: &'static str) -> Result<(), &'static str> {
+    let found = table_name(from).is_some_and(|name| name.eq_ignore_ascii_case(table));
+    require(found, if table == "users" { "Read from the users table: FROM users." } else { "Read from another table." })
+}
+
+fn has_column(columns: &[SelectItem], name: &str) -> bool {
+    columns.iter().any(|item| is_column(&item.expr, name))
+}
+
+/// Whether `expr` names the column, qualified or not.
+fn is_column(expr: &Expression, name: &str) -> bool {
+    match expr {
+        Expression::Identifier(column) => column.eq_ignore_ascii_case(name),
+        Expression::CompoundIdentifier(parts) => parts.last().is_some_and(|column| column.eq_ignore_ascii_case(name)),
+        Expression::Grouped(inner) => is_column(inner, name),
+        _ => false,
+    }
+}
+
+/// The name a FROM item's columns are qualified with: its alias, or the
+/// table's own name.
+fn qualifier(factor: &TableFactor) -> &str {
+    match factor {
+        TableFactor::Table { alias: Some(alias), .. } => &alias.name,
+        TableFactor::Table { name, .. } => name,
+        _ => "",
+    }
+}
+
+fn is_qualified_column(expr: &Expression, table: &str, name: &str) -> bool {
+    match expr {
+        Expression::CompoundIdentifier(parts) => {
+            parts.len() == 2 && parts[0].eq_ignore_ascii_case(table) && parts[1].eq_ignore_ascii_case(name)
+        }
+        Expression::Grouped(inner) => is_qualified_column(inner, table, name),
+        _ => false,
+    }
+}
+
+/// The conditions joined by AND at the top of `expr`.
+fn conjuncts(expr: &Expression) -> Vec<&Expression> {
+    match expr {
+        Expression::BinaryOperation { left_operand, operator: BinaryOperator::And, right_operand } => {
+            let mut conditions = conjuncts(left_operand);
+            conditions.extend(conjuncts(right_operand));
+            conditions
+        }
+        Expression::Grouped(inner) => conjuncts(inner),
+        _ => vec![expr],
+    }
+}
+
+/// Whether `expr` or any condition joined into it by AND meets `test`.
+fn any_condition(expr: &Expression, test: &dyn Fn(&Expression) -> bool) -> bool {
+    conjuncts(expr).into_iter().any(test)
+}
+
+/// Whether `expr` is `column <operator> value`, either way round.
+fn compares(expr: &Expression, column: &str, operator: BinaryOperator, value: u64) -> bool {
+    let Expression::BinaryOperation { left_operand, operator: found, right_operand } = expr else {
+        return false;
+    };
+    let flipped = match operator {
+        BinaryOperator::GreaterThan => BinaryOperator::LessThan,
+        BinaryOperator::LessThan => BinaryOperator::GreaterThan,
+        BinaryOperator::GreaterThanOrEqual => BinaryOperator::LessThanOrEqual,
+        BinaryOperator::LessThanOrEqual => BinaryOperator::GreaterThanOrEqual,
+        ref other => other.clone(),
+    };
+    let number = |e: &Expression| matches!(e, Expression::Number(n) if *n == value);
+    (*found == operator && is_column(left_operand, column) && number(right_operand))
+        || (*found == flipped && number(left_operand) && is_column(right_operand, column))
+}
+
+fn equals_string(expr: &Expression, column: &str, value: &str) -> bool {
+    let Expression::BinaryOperation { left_operand, operator: BinaryOperator::Equals, right_operand } = expr else {
+        return false;
+    };
+    let string = |e: &Expression| matches!(e, Expression::String(s) if s == value);
+    (is_column(left_operand, column) && string(right_operand))
+        || (string(left_operand) && is_column(right_operand, column))
+}
+
+/// Whether `expr` is `users.id = orders.user_id`, either way round, with
+/// the tables qualified as `users` and `orders`.
+fn joins_keys(expr: &Expression, users: &str, orders: &str) -> bool {
+    let Expression::BinaryOperation { left_operand, operator: BinaryOperator::Equals, right_operand } = expr else {
+        return false;
+    };
+    let key = |e: &Expression| is_qualified_column(e, users, "id");
+    let reference = |e: &Expression| is_qualified_column(e, orders, "user_id");
+    (key(left_operand) && reference(right_operand)) || (reference(left_operand) && key(right_operand))
+}
@@ -0,0 +1,243 @@
+// views.rs — inline the views a statement reads as subqueries
+// Author: Sreerag Devadasan
+//
+// `expand_views` replaces every reference to a view of a `Schema` (a
+// `CatalogSnapshot` derefs to one) with the view's query, so that the
+// statement reads only tables and can be analyzed or executed without the
+// catalog. `FROM v AS x` becomes `FROM (query) AS x`, and a bare `FROM v`
+// keeps `v` as the alias, so qualified names outside still resolve; the
+// view's column list, with any the alias gives on top, renames the
+// subquery's columns. Views that read views are expanded all the way down.
+//
+// The query of a view only sees the catalog, but once inlined it sits
+// inside the WITH clauses of the statement, where a CTE with the name of a
+// table it reads would capture that name. Such a CTE is renamed first,
+// with its references aliased back to the old name. Names bound by WITH
+// are CTEs, not views, and are left alone, as are the targets of writes.
+
+use core::fmt;
+use core::mem;
+
+use crate::prelude::*;
+use crate::ast::{MergeSource, Statement, TableAlias, TableFactor, TriggerBody};
+use crate::ident::Ident;
+use crate::schema_diff::Schema;
+
+/// Why a statement's views couldn't be inlined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViewError {
+    /// A view that reads itself, with the views it went through: `a, b, a`.
+    Recursive(Vec<String>),
+    /// A view under TABLESAMPLE, which a subquery can't take.
+    Sampled(String),
+}
+
+impl fmt::Display for ViewError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViewError::Recursive(chain) => write!(f, "view {} reads itself: {}", chain[0], chain.join(" -> ")),
+            ViewError::Sampled(view) => write!(f, "view {} can't be inlined under TABLESAMPLE", view),
+        }
+    }
+}
+
+impl core::error::Error for ViewError {}
+
+/// `statement` with the views of `schema` it reads replaced by their
+/// queries.
+pub fn expand_views(statement: &Statement, schema: &Schema) -> Result<Statement, ViewError> {
+    let mut expanded = statement.clone();
+    let mut expander = Expander { schema, ctes: Vec::new(), expanding: Vec::new(), captured: Vec::new() };
+    expander.statement(&mut expanded)?;
+    Ok(expanded)
+}
+
+struct Expander<'a> {
+    schema: &'a Schema,
+    /// The names bound by the WITH clauses around the current statement.
+    ctes: Vec<String>,
+    /// The views being inlined, outermost first.
+    expanding: Vec<String>,
+    /// The CTE names an inlined view's tables would have been bound to.
+    captured: Vec<String>,
+}
+
+impl Expander<'_> {
+    fn statement(&mut self, statement: &mut Statement) -> Result<(), ViewError> {
+        if let Statement::With { .. } = statement {
+            self.unshadow(statement)?;
+        }
+        match statement {
+            Statement::Select { from, joins, .. } => {
+                self.factor(from)?;
+                for join in joins {
+                    self.factor(&mut join.relation)?;
+                }
+            }
+            Statement::SetOperation { left, right, .. } => {
+                self.statement(left)?;
+                self.statement(right)?;
+            }
+            Statement::With { .. } => self.with(statement)?,
+            Statement::Merge { source, source_alias, .. } => match source {
+                MergeSource::Query(query) => self.statement(query)?,
+                MergeSource::Table(name) => {
+                    if let Some(query) = self.inline(name)? {
+                        source_alias.get_or_insert_with(|| name.clone());
+                        *source = MergeSource::Query(Box::new(query));
+                    }
+                }
+            },
+            Statement::InsertSelect { query, .. } | Statement::CreateView { query, .. } => self.statement(query)?,
+            Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => self.statement(statement)?,
+            Statement::CreateTrigger { body: TriggerBody::Statement(statement), .. } => self.statement(statement)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The CTEs and body of a WITH, each CTE in scope of the ones after it
+    /// (and of itself under RECURSIVE) and of the body.
+    fn with(&mut self, statement: &mut Statement) -> Result<(), ViewError> {
+        let Statement::With { recursive, ctes, body } = statement else { return Ok(()) };
+        let outer = self.ctes.len();
+        for cte in ctes {
+            if *recursive {
+                self.ctes.push(cte.name.to_string());
+            }
+            self.statement(&mut cte.query)?;
+            if !*recursive {
+                self.ctes.push(cte.name.to_string());
+            }
+        }
+        self.statement(body)?;
+        self.ctes.truncate(outer);
+        Ok(())
+    }
+
+    /// Renames the CTEs of a WITH that would capture a table of a view
+    /// inlined under it, found by expanding a copy.
+    fn unshadow(&mut self, statement: &mut Statement) -> Result<(), ViewError> {
+        let mut probe = Expander {
+            schema: self.schema,
+            ctes: self.ctes.clone(),
+            expanding: self.expanding.clone(),
+            captured: Vec::new(),
+        };
+        let mut copy = statement.clone();
+        probe.with(&mut copy)?;
+        let Statement::With { recursive, ctes, body } = statement else { return Ok(()) };
+        let taken = |name: &str| {
+            self.schema.tables.keys().chain(self.schema.views.keys()).chain(&self.ctes).any(|t| same(t, name))
+                || ctes.iter().any(|cte| same(&cte.name, name))
+                || copy.tables().iter().any(|t| same(t, name))
+        };
+        let mut renames = Vec::new();
+        for (i, cte) in ctes.iter().enumerate() {
+            if probe.captured.iter().any(|name| same(name, &cte.name)) {
+                let fresh = (1..).map(|n| format!("{}_{}", cte.name, n)).find(|name| !taken(name)).unwrap();
+                renames.push((i, fresh));
+            }
+        }
+        for (i, fresh) in renames {
+            let old = mem::replace(&mut ctes[i].name, Ident::new(fresh.as_str()));
+            // Without RECURSIVE a CTE isn't in scope of itself or those before it
+            let start = if *recursive { i } else { i + 1 };
+            for cte in &mut ctes[start..] {
+                rebind(&mut cte.query, &old, &fresh);
+            }
+            rebind(body, &old, &fresh);
+        }
+        Ok(())
+    }
+
+    fn factor(&mut self, factor: &mut TableFactor) -> Result<(), ViewError> {
+        match factor {
+            TableFactor::Table { name, alias, sample } => {
+                if self.ctes.iter().any(|cte| same(cte, name)) {
+                    return Ok(());
+                }
+                let Some(view) = self.schema.view(name) else { return Ok(()) };
+                if sample.is_some() {
+                    return Err(ViewError::Sampled(name.to_string()));
+                }
+                let Some(query) = self.inline(name)? else { return Ok(()) };
+                let mut alias = alias.take().unwrap_or_else(|| TableAlias { name: name.clone(), columns: Vec::new() });
+                if alias.columns.len() < view.columns.len() {
+                    alias.columns.extend(view.columns[alias.columns.len()..].iter().cloned());
+                }
+                *factor = TableFactor::Derived { lateral: false, subquery: Box::new(query), alias: Some(alias) };
+            }
+            TableFactor::Derived { subquery, .. } => self.statement(subquery)?,
+            TableFactor::Pivot { table, .. } | TableFactor::Unpivot { table, .. } => self.factor(table)?,
+            // Only tables have versions, so the name isn't a view's
+            TableFactor::AsOf { .. } | TableFactor::Function { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// The expanded query of view `name`, if there is one by that name.
+    fn inline(&mut self, name: &Ident) -> Result<Option<Statement>, ViewError> {
+        let Some(view) = self.schema.view(name) else { return Ok(None) };
+        if self.expanding.iter().any(|outer| same(outer, name)) {
+            let mut chain = self.expanding.clone();
+            chain.push(name.to_string());
+            let start = chain.iter().position(|outer| same(outer, name)).unwrap_or(0);
+            return Err(ViewError::Recursive(chain.split_off(start)));
+        }
+        let mut query = view.query.clone();
+        // A view's query is written against the catalog, outside any WITH
+        let outer = mem::take(&mut self.ctes);
+        self.expanding.push(name.to_string());
+        let expanded = self.statement(&mut query);
+        self.expanding.pop();
+        self.ctes = outer;
+        expanded?;
+        for table in query.tables() {
+            if self.ctes.iter().any(|cte| same(cte, table)) {
+                self.captured.push(table.to_string());
+            }
+        }
+        Ok(Some(query))
+    }
+}
+
+/// Points the references to CTE `old` in `statement` at `new`, aliased as
+/// `old` so that qualified names keep resolving. A nested WITH that binds
+/// `old` again shadows it.
+fn rebind(statement: &mut Statement, old: &Ident, new: &str) {
+    fn factor(item: &mut TableFactor, old: &Ident, new: &str) {
+        match item {
+            TableFactor::Table { name, alias, .. } if same(name, old) => {
+                alias.get_or_insert_with(|| TableAlias { name: old.clone(), columns: Vec::new() });
+                *name = Ident::new(new);
+            }
+            TableFactor::Derived { subquery, .. } => rebind(subquery, old, new),
+            TableFactor::Pivot { table, .. } | TableFactor::Unpivot { table, .. } => factor(table, old, new),
+            _ => {}
+        }
+    }
+    match statement {
+        Statement::Select { from, joins, .. } => {
+            factor(from, old, new);
+            for join in joins {
+                factor(&mut join.relation, old, new);
+            }
+        }
+        Statement::SetOperation { left, right, .. } => {
+            rebind(left, old, new);
+            rebind(right, old, new);
+        }
+        Statement::With { ctes, body, .. } if !ctes.iter().any(|cte| same(&cte.name, old)) => {
+            for cte in ctes {
+                rebind(&mut cte.query, old, new);
+            }
+            rebind(body, old, new);
+        }
+        _ => {}
+    }
+}
+
+fn same(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
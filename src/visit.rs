@@ -0,0 +1,146 @@
+// visit.rs — walking an AST without matching on every variant
+// Author: Sreerag Devadasan
+//
+// The AST enums are `#[non_exhaustive]`: new statements, clauses and
+// expressions arrive in minor versions, so code outside the crate can't
+// match them exhaustively and handles the ones it doesn't know with a
+// wildcard arm. Code that only cares about part of the tree has it easier
+// with the accessors (`Statement::kind`, `tables`, `expressions`, ...) or
+// a `Visitor`, which `walk` calls for every statement, FROM item and
+// expression of a statement, nested queries included. Both keep working,
+// unchanged, when variants are added.
+
+use crate::ast::{Expression, Join, MergeSource, Statement, TableFactor, TriggerBody};
+
+/// Callbacks for the parts of a statement; each does nothing by default.
+pub trait Visitor {
+    /// The statement and every statement nested in it (the sides of a set
+    /// operation, CTEs, subqueries, ...), outer ones first.
+    fn statement(&mut self, _statement: &Statement) {}
+
+    /// Every FROM and JOIN item, outer ones first.
+    fn table_factor(&mut self, _factor: &TableFactor) {}
+
+    /// Every expression and sub-expression, parents first.
+    fn expression(&mut self, _expr: &Expression) {}
+}
+
+/// A `Visitor` that may keep what it is given, and sees the joins too;
+/// the source map pairs nodes with their spans through it.
+pub(crate) trait Nodes<'a> {
+    fn statement(&mut self, statement: &'a Statement);
+    fn table_factor(&mut self, factor: &'a TableFactor);
+    fn join(&mut self, _join: &'a Join) {}
+    fn expression(&mut self, expr: &'a Expression);
+}
+
+impl<'a, V: Visitor> Nodes<'a> for V {
+    fn statement(&mut self, statement: &'a Statement) {
+        Visitor::statement(self, statement)
+    }
+
+    fn table_factor(&mut self, factor: &'a TableFactor) {
+        Visitor::table_factor(self, factor)
+    }
+
+    fn expression(&mut self, expr: &'a Expression) {
+        Visitor::expression(self, expr)
+    }
+}
+
+/// Calls `visitor` for `statement` and everything in it, in source order.
+pub fn walk(statement: &Statement, visitor: &mut impl Visitor) {
+    walk_nodes(statement, visitor);
+}
+
+/// `walk`, for a `Nodes` visitor.
+pub(crate) fn walk_nodes<'a>(statement: &'a Statement, visitor: &mut impl Nodes<'a>) {
+    visitor.statement(statement);
+    match statement {
+        Statement::Select { columns, wildcard, from, joins, selection, group_by, qualify, order_by, .. } => {
+            let mut replaced = false;
+            for item in columns {
+                walk_expression(&item.expr, visitor);
+                // The REPLACE items are written after the first `*`
+                if item.is_wildcard() && !replaced {
+                    wildcard.replace.iter().for_each(|(expr, _)| walk_expression(expr, visitor));
+                    replaced = true;
+                }
+            }
+            walk_factor(from, visitor);
+            for join in joins {
+                visitor.join(join);
+                walk_factor(&join.relation, visitor);
+                if let Some(on) = &join.on {
+                    walk_expression(on, visitor);
+                }
+            }
+            let clauses = group_by.iter().flat_map(|element| element.expressions());
+            let ordered = order_by.iter().map(|item| &item.expr);
+            for expr in selection.iter().chain(clauses).chain(qualify).chain(ordered) {
+                walk_expression(expr, visitor);
+            }
+        }
+        Statement::SetOperation { left, right, .. } => {
+            walk_nodes(left, visitor);
+            walk_nodes(right, visitor);
+        }
+        Statement::With { ctes, body, .. } => {
+            for cte in ctes {
+                walk_nodes(&cte.query, visitor);
+            }
+            walk_nodes(body, visitor);
+        }
+        Statement::InsertSelect { query, .. } | Statement::CreateView { query, .. } => walk_nodes(query, visitor),
+        Statement::Prepare { statement, .. } | Statement::Explain { statement, .. } => walk_nodes(statement, visitor),
+        Statement::Merge { source, on, clauses, .. } => {
+            if let MergeSource::Query(query) = source {
+                walk_nodes(query, visitor);
+            }
+            walk_expression(on, visitor);
+            for expr in clauses.iter().flat_map(|clause| clause.expressions()) {
+                walk_expression(expr, visitor);
+            }
+        }
+        Statement::CreateTrigger { condition, body, .. } => {
+            if let Some(condition) = condition {
+                walk_expression(condition, visitor);
+            }
+            match body {
+                TriggerBody::Execute { args, .. } => args.iter().for_each(|arg| walk_expression(arg, visitor)),
+                TriggerBody::Statement(statement) => walk_nodes(statement, visitor),
+                TriggerBody::Block(_) => {}
+            }
+        }
+        // The rest hold no statements, so their expressions are all there is
+        statement => {
+            for expr in statement.expressions() {
+                walk_expression(expr, visitor);
+            }
+        }
+    }
+}
+
+fn walk_factor<'a>(factor: &'a TableFactor, visitor: &mut impl Nodes<'a>) {
+    visitor.table_factor(factor);
+    match factor {
+        TableFactor::Table { sample, .. } => {
+            for expr in sample.iter().flat_map(|sample| sample.expressions()) {
+                walk_expression(expr, visitor);
+            }
+        }
+        TableFactor::AsOf { .. } => {}
+        TableFactor::Derived { subquery, .. } => walk_nodes(subquery, visitor),
+        TableFactor::Function { args, .. } => args.iter().for_each(|arg| walk_expression(arg, visitor)),
+        TableFactor::Pivot { table, aggregate, values, .. } => {
+            walk_factor(table, visitor);
+            walk_expression(aggregate, visitor);
+            values.iter().for_each(|value| walk_expression(value, visitor));
+        }
+        TableFactor::Unpivot { table, .. } => walk_factor(table, visitor),
+    }
+}
+
+fn walk_expression<'a>(expr: &'a Expression, visitor: &mut impl Nodes<'a>) {
+    expr.walk(&mut |expr| visitor.expression(expr));
+}
@@ -0,0 +1,212 @@
+// wal.rs — a file-backed engine database made durable by a write-ahead log
+// Author: Sreerag Devadasan
+//
+// Every statement that changes the database (CREATE TABLE, DROP TABLE,
+// INSERT, UPDATE, DELETE) is first run against a copy of the tables, which validates it;
+// it is then appended to the log and the log is synced to disk, and only
+// then does the copy replace the live tables. A transaction's statements
+// are kept in memory until COMMIT, which appends them all as one group
+// between BEGIN and COMMIT records. Opening the log replays it into an
+// empty database, dropping a group that never got its COMMIT.
+//
+// Each record is a header line `<length> <checksum>` followed by the
+// statement's SQL and a newline; the checksum is the FNV-1a hash of the
+// SQL in hex. A crash mid-append leaves a torn record at the end of the
+// log, which fails its check; it is cut off when the log is next opened,
+// along with the rest of a group it cut short.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use crate::ast::{Statement, TransactionCommand};
+use crate::dialect::Dialect;
+use crate::engine::{Database, EngineError, QueryResult};
+use crate::hash::fnv1a;
+use crate::parser::parse_sql;
+
+/// Errors raised while opening or writing a log.
+#[derive(Debug)]
+pub enum WalError {
+    Io(io::Error),
+    /// The statement failed; neither the database nor the log changed.
+    Engine(EngineError),
+    /// An intact record of the log doesn't replay.
+    Replay { record: usize, error: EngineError },
+}
+
+impl fmt::Display for WalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalError::Io(e) => write!(f, "I/O error: {}", e),
+            WalError::Engine(e) => write!(f, "{}", e),
+            WalError::Replay { record, error } => write!(f, "Log record {} doesn't replay: {}", record, error),
+        }
+    }
+}
+
+impl std::error::Error for WalError {}
+
+impl From<io::Error> for WalError {
+    fn from(e: io::Error) -> Self {
+        WalError::Io(e)
+    }
+}
+
+impl From<EngineError> for WalError {
+    fn from(e: EngineError) -> Self {
+        WalError::Engine(e)
+    }
+}
+
+/// A database whose changes survive a crash of the process.
+#[derive(Debug)]
+pub struct DurableDatabase {
+    database: Database,
+    log: File,
+    /// The records of the open transaction, written at COMMIT.
+    pending: Option<Vec<String>>,
+    discarded: u64,
+}
+
+impl DurableDatabase {
+    /// Opens the log at `path`, creating it if it doesn't exist, and
+    /// replays it.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, WalError> {
+        let mut log = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+        let mut bytes = Vec::new();
+        log.read_to_end(&mut bytes)?;
+
+        let mut database = Database::new();
+        // The end of the last record that isn't part of an unfinished group
+        let mut intact = 0;
+        for (index, (sql, end)) in read_records(&bytes).into_iter().enumerate() {
+            parse_sql(&sql)
+                .map_err(EngineError::from)
+                .and_then(|statement| database.execute(&statement))
+                .map_err(|error| WalError::Replay { record: index + 1, error })?;
+            if !database.in_transaction() {
+                intact = end;
+            }
+        }
+        if database.in_transaction() {
+            database.execute(&Statement::Transaction { command: TransactionCommand::Rollback })?;
+        }
+
+        let discarded = (bytes.len() - intact) as u64;
+        if discarded > 0 {
+            log.set_len(intact as u64)?;
+            log.sync_data()?;
+        }
+        Ok(Self { database, log, pending: None, discarded })
+    }
+
+    pub fn database(&self) -> &Database {
+        &self.database
+    }
+
+    /// Keeps the tables of the last `versions` versions from now on; see
+    /// `Database::set_history_limit`. Those replayed from the log aren't
+    /// kept, but the numbering counts them.
+    pub fn set_history_limit(&mut self, versions: usize) {
+        self.database.set_history_limit(versions);
+    }
+
+    /// Bytes cut off the end of the log when it was opened: a torn record
+    /// and the group it belonged to.
+    pub fn discarded(&self) -> u64 {
+        self.discarded
+    }
+
+    /// Parses and executes one statement.
+    pub fn execute_sql(&mut self, sql: &str) -> Result<QueryResult, WalError> {
+        let statement = parse_sql(sql).map_err(EngineError::from)?;
+        self.execute(&statement)
+    }
+
+    /// Executes a statement, returning once any change it makes (outside a
+    /// transaction) or commits is on disk.
+    pub fn execute(&mut self, statement: &Statement) -> Result<QueryResult, WalError> {
+        match statement {
+            Statement::CreateTable { .. }
+            | Statement::DropTable { .. }
+            | Statement::Insert { .. }
+            | Statement::Update { .. }
+            | Statement::Delete { .. }
+            | Statement::Transaction { .. } => {}
+            other => return Ok(self.database.execute(other)?),
+        }
+        let mut next = self.database.clone();
+        let result = next.execute(statement)?;
+
+        let pending = match (statement, self.pending.take()) {
+            (Statement::Transaction { command: TransactionCommand::Begin }, _) => Some(Vec::new()),
+            (Statement::Transaction { command: TransactionCommand::Commit }, Some(records)) => {
+                if !records.is_empty() {
+                    let mut group = vec!["BEGIN".to_string()];
+                    group.extend(records.iter().cloned());
+                    group.push("COMMIT".to_string());
+                    if let Err(e) = self.append(&group) {
+                        self.pending = Some(records);
+                        return Err(e.into());
+                    }
+                }
+                None
+            }
+            (Statement::Transaction { .. }, _) => None,
+            (_, Some(mut records)) => {
+                records.push(statement.to_sql(Dialect::Generic));
+                Some(records)
+            }
+            (_, None) => {
+                self.append(&[statement.to_sql(Dialect::Generic)])?;
+                None
+            }
+        };
+        self.pending = pending;
+        self.database = next;
+        Ok(result)
+    }
+
+    /// Appends records to the log in one write, then syncs it.
+    fn append(&mut self, records: &[String]) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        for sql in records {
+            bytes.extend(format!("{} {:016x}\n", sql.len(), fnv1a(sql.as_bytes())).as_bytes());
+            bytes.extend(sql.as_bytes());
+            bytes.push(b'\n');
+        }
+        self.log.write_all(&bytes)?;
+        self.log.sync_data()
+    }
+}
+
+/// The SQL of each record up to the first torn one, with the offset where
+/// the record ends.
+fn read_records(bytes: &[u8]) -> Vec<(String, usize)> {
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while let Some((sql, next)) = read_record(bytes, offset) {
+        records.push((sql, next));
+        offset = next;
+    }
+    records
+}
+
+fn read_record(bytes: &[u8], offset: usize) -> Option<(String, usize)> {
+    let rest = &bytes[offset..];
+    let header_end = rest.iter().position(|&b| b == b'\n')?;
+    let header = std::str::from_utf8(&rest[..header_end]).ok()?;
+    let (length, checksum) = header.split_once(' ')?;
+    let length: usize = length.parse().ok()?;
+    let checksum = u64::from_str_radix(checksum, 16).ok()?;
+
+    let start = header_end + 1;
+    let sql = rest.get(start..start.checked_add(length)?)?;
+    if rest.get(start + length) != Some(&b'\n') || fnv1a(sql) != checksum {
+        return None;
+    }
+    let sql = String::from_utf8(sql.to_vec()).ok()?;
+    Some((sql, offset + start + length + 1))
+}
@@ -0,0 +1,134 @@
+// analyzer.rs — checks function and operator signatures against the calls made
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::analyzer::{analyze, Problem, Registry, SqlType};
+use sreerag_devadasan::ast::BinaryOperator;
+use sreerag_devadasan::lint::Severity;
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::schema_diff::Schema;
+
+fn problems(sql: &str, registry: &Registry) -> Vec<Problem> {
+    analyze(&parse_sql(sql).unwrap(), registry, None)
+}
+
+#[test]
+fn registered_functions_are_checked_instead_of_unknown() {
+    let sql = "SELECT id FROM orders WHERE tax_rate(region, 2) > 10";
+    assert_eq!(problems(sql, &Registry::new()), [Problem::UnknownFunction("tax_rate".to_string())]);
+
+    let mut registry = Registry::new();
+    registry.scalar("TAX_RATE", &[SqlType::Text, SqlType::Integer], SqlType::Integer);
+    assert_eq!(problems(sql, &registry), []);
+    assert_eq!(
+        problems("SELECT id FROM orders WHERE tax_rate(region) > 10", &registry),
+        [Problem::WrongArity { function: "tax_rate".to_string(), expected: "2".to_string(), found: 1 }]
+    );
+    let problem = &problems("SELECT id FROM orders WHERE tax_rate(region, 'high') > 10", &registry)[0];
+    assert_eq!(problem.to_string(), "Argument 2 of tax_rate should be integer, but is text");
+    // The return type carries on into the comparison
+    let mismatch = problems("SELECT id FROM orders WHERE tax_rate(region, 2) = 'high'", &registry);
+    assert_eq!(mismatch[0].to_string(), "= can't be applied to integer and text");
+
+    // Overloads and repeated parameters
+    registry.scalar("tax_rate", &[SqlType::Text], SqlType::Integer).variadic("tags", &[SqlType::Text], SqlType::Text);
+    let overloaded = "SELECT id FROM orders WHERE tax_rate(region) = 1 AND tags('a', 'b', 'c') = 'a'";
+    assert_eq!(problems(overloaded, &registry), []);
+    let problem = &problems("SELECT id FROM orders WHERE tags() = 'a'", &registry)[0];
+    assert_eq!(problem.to_string(), "tags takes at least 1 argument(s), but is given 0");
+}
+
+#[test]
+fn create_function_registers_its_signature() {
+    let mut registry = Registry::new();
+    assert!(registry.apply(&parse_sql("CREATE FUNCTION discount(INT) RETURNS INT AS 'SELECT 1'").unwrap()));
+    assert!(!registry.apply(&parse_sql("CREATE PROCEDURE tidy() AS 'SELECT 1'").unwrap()));
+    assert_eq!(problems("SELECT id FROM orders WHERE discount(3) > 1", &registry), []);
+    assert!(matches!(problems("SELECT id FROM orders WHERE tidy() > 1", &registry)[0], Problem::UnknownFunction(_)));
+
+    // Column types come from the schema
+    let schema = Schema::from_sql("CREATE TABLE orders (id INT, region VARCHAR(10))").unwrap();
+    let statement = parse_sql("SELECT id FROM orders WHERE discount(region) > 1").unwrap();
+    assert_eq!(analyze(&statement, &registry, None), []);
+    let problem = &analyze(&statement, &registry, Some(&schema))[0];
+    assert_eq!(problem.to_string(), "Argument 1 of discount should be integer, but is text");
+
+    // A type the analyzer doesn't know takes anything
+    let create = parse_sql("CREATE FUNCTION label(x TEXT) RETURNS SETOF TEXT AS 'SELECT x'").unwrap();
+    assert_eq!(create.to_string(), "CREATE FUNCTION label(x TEXT) RETURNS SETOF TEXT AS $$SELECT x$$");
+    assert!(registry.apply(&create));
+    let statement = parse_sql("SELECT id FROM orders WHERE label(id) = label(region)").unwrap();
+    assert_eq!(analyze(&statement, &registry, Some(&schema)), []);
+}
+
+#[test]
+fn aggregates_windows_and_operators() {
+    let registry = Registry::new();
+    let nested = problems("SELECT id FROM t WHERE sum(count(id)) > 1", &registry);
+    assert_eq!(nested, [Problem::NestedAggregate { function: "count".to_string(), inside: "sum".to_string() }]);
+    assert_eq!(problems("SELECT id FROM t WHERE sum(count(id) OVER (PARTITION BY id)) > 1", &registry), []);
+    let missing = problems("SELECT id FROM t WHERE row_number() = 1", &registry);
+    assert_eq!(missing, [Problem::MissingOver("row_number".to_string())]);
+
+    // An operator only takes the operands it was registered for
+    let sql = "SELECT id FROM t WHERE 'a' + 'b' = 'ab' AND NOT 1";
+    assert_eq!(problems(sql, &registry).len(), 2);
+    let mut concatenating = Registry::new();
+    concatenating.operator(BinaryOperator::Add, SqlType::Text, SqlType::Text, SqlType::Text);
+    assert_eq!(problems(sql, &concatenating)[0].to_string(), "NOT can't be applied to integer");
+    // Unknown types fit anything, but an empty registry has no operators
+    assert_eq!(problems("SELECT id FROM t WHERE amount + NULL > :limit", &registry), []);
+    assert_eq!(problems("SELECT id FROM t WHERE amount + NULL > :limit", &Registry::empty()).len(), 2);
+}
+
+#[test]
+fn unknown_names_come_with_the_closest_known_one() {
+    let schema = Schema::from_sql("CREATE TABLE users (user_id INT, name VARCHAR(20)); CREATE TABLE orders (id INT)")
+        .unwrap();
+    let registry = Registry::new();
+    let check = |sql: &str| -> Vec<String> {
+        analyze(&parse_sql(sql).unwrap(), &registry, Some(&schema)).iter().map(Problem::to_string).collect()
+    };
+    assert_eq!(
+        check("SELECT usre_id, name FROM users WHERE users.nmae = 'a'"),
+        ["Unknown column: usre_id; did you mean user_id?", "Unknown column: nmae; did you mean name?"]
+    );
+    assert_eq!(check("SELECT id FROM usres"), ["Unknown table: usres; did you mean users?"]);
+    // Only the columns of the tables read are in scope, and nothing close is no suggestion
+    assert_eq!(check("SELECT id FROM users"), ["Unknown column: id"]);
+    assert_eq!(check("SELECT user_id FROM users JOIN orders ON orders.id = users.user_id"), Vec::<String>::new());
+    // ORDER BY items are checked too, but a bare one may be a select-list alias
+    assert_eq!(check("SELECT name AS n FROM users ORDER BY n, user_id + 1"), Vec::<String>::new());
+    assert_eq!(check("SELECT name FROM users ORDER BY nmae DESC"), ["Unknown column: nmae; did you mean name?"]);
+
+    // A derived table or a CTE has columns of its own, and is left alone
+    assert_eq!(check("SELECT total FROM (SELECT user_id FROM users) AS t"), Vec::<String>::new());
+    assert_eq!(check("WITH t AS (SELECT name FROM users) SELECT size FROM t"), Vec::<String>::new());
+    assert_eq!(check("CREATE TABLE items (sku INT CHECK (sku > 0))"), Vec::<String>::new());
+}
+
+#[test]
+fn names_that_resolve_ambiguously_are_warnings() {
+    let schema = "CREATE TABLE users (id INT, name VARCHAR(20)); CREATE TABLE orders (id INT, total INT)";
+    let schema = Schema::from_sql(schema).unwrap();
+    let registry = Registry::new();
+    let check = |sql: &str| analyze(&parse_sql(sql).unwrap(), &registry, Some(&schema));
+
+    let problems = check("SELECT name FROM users JOIN orders ON orders.id = users.id WHERE id > 1");
+    let tables = vec!["users".to_string(), "orders".to_string()];
+    assert_eq!(problems, [Problem::AmbiguousColumn { name: "id".to_string(), tables }]);
+    assert_eq!(problems[0].severity(), Severity::Warning);
+    assert_eq!(problems[0].to_string(), "The column id is ambiguous: it's in users and orders");
+
+    let problems = check("SELECT total FROM users AS o JOIN orders AS O ON o.id = O.id");
+    assert_eq!(problems, [Problem::DuplicateAlias("O".to_string())]);
+    // Each SELECT has FROM items of its own
+    assert_eq!(check("SELECT id FROM users WHERE id > 1 UNION SELECT id FROM users"), []);
+
+    let problems = check("WITH orders AS (SELECT id FROM users) SELECT id FROM orders");
+    let messages: Vec<String> = problems.iter().map(Problem::to_string).collect();
+    assert_eq!(messages, ["The CTE orders hides the table of the same name"]);
+    // Errors keep their severity alongside the warnings
+    let problems = check("SELECT id FROM users JOIN users ON users.id = users.id WHERE nmae = 'a'");
+    let severities: Vec<Severity> = problems.iter().map(Problem::severity).collect();
+    assert_eq!(severities, [Severity::Warning, Severity::Error]);
+}
@@ -0,0 +1,20 @@
+// anonymize.rs — checks that anonymized queries keep no literal values
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::anonymize::{anonymize_sql, AnonymizeMode};
+
+#[test]
+fn typed_literals_are_replaced_and_keep_their_type() {
+    let sql = "SELECT a FROM t WHERE d = DATE '2024-01-31' AND s < TIMESTAMP '2024-01-31 12:00:00'";
+    assert_eq!(
+        anonymize_sql(sql, AnonymizeMode::Placeholders).unwrap(),
+        "SELECT a FROM t WHERE d = CAST(:string AS DATE) AND s < CAST(:string AS TIMESTAMP)"
+    );
+
+    let hashed = anonymize_sql(sql, AnonymizeMode::Hashed).unwrap();
+    assert!(hashed.contains("DATE 'h_") && hashed.contains("TIMESTAMP 'h_"), "{}", hashed);
+    assert!(!hashed.contains("2024"), "{}", hashed);
+    // Equal values hash the same
+    let again = anonymize_sql("SELECT a FROM t WHERE d = DATE '2024-01-31'", AnonymizeMode::Hashed).unwrap();
+    assert!(hashed.contains(&again["SELECT a FROM t WHERE d = ".len()..]), "{} / {}", hashed, again);
+}
@@ -0,0 +1,135 @@
+// arithmetic.rs — checks what overflows, divisions by zero and invalid casts give
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::ast::{DBType, Expression};
+use sreerag_devadasan::bytecode::{Instruction, Program};
+use sreerag_devadasan::decimal::Decimal;
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::engine::{Database, EngineError, Storage};
+use sreerag_devadasan::eval::{builtin, evaluate, evaluate_handling, EvalError, Value};
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::pratt::PrattParser;
+use sreerag_devadasan::semantics::{ErrorHandling, OnError};
+use sreerag_devadasan::span::parse_with_source_map;
+use sreerag_devadasan::tokenizer::tokenize;
+
+const OVERFLOW: &str = "9223372036854775807 + 1";
+
+fn parse(sql: &str) -> Expression {
+    PrattParser::new(&tokenize(sql)).parse_expression(1).unwrap()
+}
+
+fn eval_handling(sql: &str, errors: &ErrorHandling) -> Result<Value, EvalError> {
+    evaluate_handling(&parse(sql), &[], &[], &builtin, errors)
+}
+
+fn saturating() -> ErrorHandling {
+    ErrorHandling { overflow: OnError::Saturate, division_by_zero: OnError::Saturate, invalid_cast: OnError::Saturate }
+}
+
+#[test]
+fn casts_convert_between_types() {
+    let eval = |sql: &str| evaluate(&parse(sql), &[], &[]);
+    assert_eq!(eval("CAST(' 42 ' AS INT)"), Ok(Value::Int(42)));
+    assert_eq!(eval("CAST(TRUE AS INT) + CAST(2.5 AS INT)"), Ok(Value::Int(4)));
+    assert_eq!(eval("CAST('yes' AS BOOL)"), Ok(Value::Bool(true)));
+    assert_eq!(eval("CAST(12345 AS VARCHAR(3))"), Ok(Value::String("123".to_string())));
+    assert_eq!(eval("CAST('2.675' AS DECIMAL(5,2))"), Ok(Value::Decimal(Decimal::parse("2.68").unwrap())));
+    assert_eq!(eval("CAST('2024-01-31' AS DATE) = DATE '2024-01-31'"), Ok(Value::Bool(true)));
+    assert_eq!(eval("CAST(TIMESTAMP '2024-01-31 10:30:00' AS TIME) = TIME '10:30'"), Ok(Value::Bool(true)));
+    assert_eq!(eval("CAST(NULL AS INT)"), Ok(Value::Null));
+    assert!(matches!(eval("CAST(DATE '2024-01-31' AS INT)"), Err(EvalError::TypeMismatch(_))));
+
+    for sql in ["SELECT id FROM t WHERE CAST(code AS INT) > 1", "VALUES (CAST('1.5' AS DECIMAL(3,1)))"] {
+        assert_eq!(parse_sql(sql).unwrap().to_string(), sql);
+    }
+    assert!(parse_sql("VALUES (CAST(1 AS DECIMAL(40)))").is_err());
+    assert!(parse_sql("VALUES (CAST(1 INT))").is_err());
+    let sql = "SELECT id FROM t WHERE CAST(a + 1 AS INT) = 2";
+    let (statement, map) = parse_with_source_map(sql, Dialect::Generic).unwrap();
+    let spans = map.expression_spans(&statement);
+    let cast = spans.iter().find(|(expr, _)| matches!(expr, Expression::Cast { data_type: DBType::Int, .. }));
+    assert_eq!(cast.map(|(_, span)| span.slice(sql)), Some("CAST(a + 1 AS INT)"));
+}
+
+#[test]
+fn errors_are_raised_by_default() {
+    let strict = ErrorHandling::default();
+    assert_eq!(strict, ErrorHandling::STRICT);
+    assert_eq!(eval_handling(OVERFLOW, &strict), Err(EvalError::Overflow));
+    assert_eq!(eval_handling("1 / 0", &strict), Err(EvalError::DivisionByZero));
+    let error = eval_handling("CAST('abc' AS INT)", &strict).unwrap_err();
+    assert_eq!(error.to_string(), "Invalid cast: String(\"abc\") to INT");
+    assert_eq!(eval_handling("CAST('99999999999999999999' AS INT)", &strict), Err(EvalError::Overflow));
+
+    // None of them is folded away
+    for sql in [OVERFLOW, "1 / 0", "CAST('abc' AS INT)"] {
+        let program = Program::compile(&parse(sql), &[]).unwrap();
+        assert!(program.instructions().len() > 1, "{}", sql);
+        assert!(program.evaluate(&[]).is_err(), "{}", sql);
+    }
+
+    let mut db = Database::new();
+    let error = db.execute_sql(&format!("VALUES ({})", OVERFLOW)).unwrap_err();
+    assert!(matches!(error, EngineError::Eval(EvalError::Overflow)));
+}
+
+#[test]
+fn errors_give_null_or_saturate_when_configured() {
+    let nulls = ErrorHandling { overflow: OnError::Null, division_by_zero: OnError::Null, invalid_cast: OnError::Null };
+    for sql in [OVERFLOW, "1 / 0", "CAST('abc' AS INT)"] {
+        assert_eq!(eval_handling(sql, &nulls), Ok(Value::Null), "{}", sql);
+        let program = Program::compile_handling(&parse(sql), &[], &nulls).unwrap();
+        assert_eq!(program.instructions(), [Instruction::Push(Value::Null)], "{}", sql);
+    }
+
+    let saturating = saturating();
+    let eval = |sql: &str| eval_handling(sql, &saturating).unwrap();
+    assert_eq!(eval(OVERFLOW), Value::Int(i64::MAX));
+    assert_eq!(eval("-9223372036854775807 - 2"), Value::Int(i64::MIN));
+    assert_eq!(eval("-9223372036854775807 * 2"), Value::Int(i64::MIN));
+    assert_eq!(eval("-7 / 0"), Value::Int(i64::MIN));
+    assert_eq!(eval("0 / 0"), Value::Null);
+    assert_eq!(eval("99999999999999999999999999999999999.999 + 1"), Value::Decimal(Decimal::MAX));
+    assert_eq!(eval("CAST('12abc' AS INT)"), Value::Int(12));
+    assert_eq!(eval("CAST(' -1.5x' AS DECIMAL(3,1))"), Value::Decimal(Decimal::parse("-1.5").unwrap()));
+    assert_eq!(eval("CAST('1234' AS DECIMAL(3,1))"), Value::Decimal(Decimal::parse("99.9").unwrap()));
+    assert_eq!(eval("CAST('soon' AS DATE)"), Value::Null);
+    // Errors of other kinds are raised whatever the configuration
+    assert!(matches!(eval_handling("1 + 'a'", &saturating), Err(EvalError::TypeMismatch(_))));
+
+    // A column's value goes through the VM the same way
+    let columns = ["x".to_string()];
+    let program = Program::compile_handling(&parse("x * 2"), &columns, &saturating).unwrap();
+    assert_eq!(program.evaluate(&[Value::Int(i64::MAX)]), Ok(Value::Int(i64::MAX)));
+}
+
+#[test]
+fn databases_follow_the_error_handling_of_a_dialect() {
+    let mysql = Dialect::MySql.error_handling();
+    assert_eq!((mysql.overflow, mysql.division_by_zero), (OnError::Raise, OnError::Null));
+    assert_eq!(Dialect::Postgres.error_handling(), ErrorHandling::STRICT);
+
+    for storage in [Storage::Rows, Storage::Columnar] {
+        let mut db = Database::with_storage(storage);
+        db.execute_sql("CREATE TABLE t (id INT, n INT, code VARCHAR(10))").unwrap();
+        db.execute_sql("INSERT INTO t VALUES (1, 0, '7'), (2, 2, 'x1'), (3, 4, '12')").unwrap();
+        let sql = "SELECT id FROM t WHERE 4 / n = 2 OR CAST(code AS INT) = 7";
+        assert!(db.execute_sql(sql).is_err(), "{:?}", storage);
+
+        db.set_error_handling(mysql);
+        assert_eq!(db.error_handling(), mysql);
+        // Run twice, so the second comes from the plan cache
+        for _ in 0..2 {
+            assert_eq!(db.execute_sql(sql).unwrap().rows, [vec![Value::Int(1)], vec![Value::Int(2)]], "{:?}", storage);
+        }
+        let rows = db.execute_sql("VALUES (1 / 0, CAST('abc' AS INT), CAST('3 apples' AS INT))").unwrap().rows;
+        assert_eq!(rows, [vec![Value::Null, Value::Int(0), Value::Int(3)]]);
+        db.execute_sql("INSERT INTO t VALUES (4, 1 / 0, 'y')").unwrap();
+        assert_eq!(db.execute_sql("SELECT id FROM t WHERE n = NULL OR id = 4").unwrap().rows, [vec![Value::Int(4)]]);
+        assert!(db.execute_sql(&format!("VALUES ({})", OVERFLOW)).is_err());
+
+        db.set_error_handling(ErrorHandling::STRICT);
+        assert!(db.execute_sql(sql).is_err(), "{:?}", storage);
+    }
+}
@@ -0,0 +1,129 @@
+// arrow.rs — checks record batch conversion and Arrow IPC files
+// Author: Sreerag Devadasan
+
+#![cfg(feature = "arrow")]
+
+use sreerag_devadasan::arrow::{ArrowError, DataType, Field, IpcFile, RecordBatch};
+use sreerag_devadasan::ast::DBType;
+use sreerag_devadasan::columnar::ColumnarTable;
+use sreerag_devadasan::engine::{Database, EngineError, QueryResult};
+use sreerag_devadasan::eval::Value;
+
+fn people() -> QueryResult {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE people (id INT, name VARCHAR(20), active BOOL)").unwrap();
+    let rows: Vec<String> = (0..130)
+        .map(|i| match i % 3 {
+            0 => format!("({}, 'user{}', TRUE)", i, i),
+            1 => format!("({}, NULL, FALSE)", i),
+            _ => format!("({}, 'ünïcode', NULL)", i),
+        })
+        .collect();
+    db.execute_sql(&format!("INSERT INTO people VALUES {}", rows.join(", "))).unwrap();
+    db.execute_sql("SELECT * FROM people").unwrap()
+}
+
+#[test]
+fn results_convert_to_record_batches() {
+    let result = people();
+    let batch = RecordBatch::from_result(&result).unwrap();
+    let types: Vec<DataType> = batch.schema().iter().map(|field| field.data_type).collect();
+    assert_eq!(types, [DataType::Int64, DataType::Utf8, DataType::Boolean]);
+    assert_eq!(batch.num_rows(), 130);
+    assert_eq!(batch.to_result(), result);
+
+    let mixed = QueryResult {
+        columns: vec!["a".to_string()],
+        rows: vec![vec![Value::Int(1)], vec![Value::Null], vec![Value::Bool(true)]],
+    };
+    assert!(matches!(RecordBatch::from_result(&mixed), Err(ArrowError::MixedTypes(c)) if c == "a"));
+}
+
+#[test]
+fn ipc_files_round_trip() {
+    let result = people();
+    let file = IpcFile::from_result(&result).unwrap();
+    let bytes = file.to_bytes().unwrap();
+    assert_eq!(&bytes[..8], b"ARROW1\0\0");
+    assert_eq!(&bytes[bytes.len() - 6..], b"ARROW1");
+    // The stream ends with an end-of-stream marker before the footer
+    let footer_length = i32::from_le_bytes(bytes[bytes.len() - 10..bytes.len() - 6].try_into().unwrap()) as usize;
+    let stream_end = bytes.len() - 10 - footer_length;
+    assert_eq!(&bytes[stream_end - 8..stream_end], [0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0]);
+
+    let read = IpcFile::from_bytes(&bytes).unwrap();
+    assert_eq!(read, file);
+    assert_eq!(read.batches()[0].to_result(), result);
+
+    // Several batches and an empty one
+    let schema = file.schema().to_vec();
+    let batch = file.batches()[0].clone();
+    let empty = ColumnarTable::new(&[DBType::Int, DBType::Varchar(20), DBType::Bool]);
+    let empty = RecordBatch::new(schema.clone(), empty).unwrap();
+    let file = IpcFile::new(schema, vec![batch.clone(), empty, batch]).unwrap();
+    assert_eq!(IpcFile::from_bytes(&file.to_bytes().unwrap()).unwrap(), file);
+}
+
+#[test]
+fn ipc_files_register_as_read_only_tables() {
+    let path = std::env::temp_dir().join(format!("sreerag-arrow-{}.arrow", std::process::id()));
+    IpcFile::from_result(&people()).unwrap().write(&path).unwrap();
+
+    let mut db = Database::new();
+    IpcFile::read(&path).unwrap().register(&mut db, "people").unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let result = db.execute_sql("SELECT name FROM people WHERE active AND id > 120").unwrap();
+    let names: Vec<Value> = ["user123", "user126", "user129"].iter().map(|n| Value::String(n.to_string())).collect();
+    assert_eq!(result.rows.concat(), names);
+    assert!(db.table("people").unwrap().is_read_only());
+    for sql in ["INSERT INTO people VALUES (1, 'x', TRUE)", "UPDATE people SET id = 1", "DELETE FROM people"] {
+        assert!(matches!(db.execute_sql(sql), Err(EngineError::ReadOnly(_))), "{}", sql);
+    }
+    db.execute_sql("DROP TABLE people").unwrap();
+}
+
+#[test]
+fn malformed_files_are_rejected() {
+    let bytes = IpcFile::from_result(&people()).unwrap().to_bytes().unwrap();
+    assert!(matches!(IpcFile::from_bytes(&bytes[..bytes.len() - 1]), Err(ArrowError::Malformed(_))));
+    assert!(matches!(IpcFile::from_bytes(b"PAR1"), Err(ArrowError::Malformed(_))));
+    // A footer pointing past the end of the file
+    let mut truncated = bytes[..200].to_vec();
+    truncated.extend(&bytes[bytes.len() - 10..]);
+    assert!(IpcFile::from_bytes(&truncated).is_err());
+
+    let schema = vec![Field { name: "id".to_string(), data_type: DataType::Utf8, nullable: true }];
+    let batch = RecordBatch::from_result(&people()).unwrap();
+    assert!(matches!(RecordBatch::new(schema, batch.columns().clone()), Err(ArrowError::SchemaMismatch)));
+}
+
+#[test]
+fn dates_and_times_round_trip() {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE events (day DATE, at TIME, created TIMESTAMP)").unwrap();
+    let insert = "INSERT INTO events VALUES ('2024-02-29', '23:59:59.5', '1969-12-31 12:00:00'), (NULL, NULL, NULL)";
+    db.execute_sql(insert).unwrap();
+    let result = db.execute_sql("SELECT * FROM events").unwrap();
+    let file = IpcFile::from_result(&result).unwrap();
+    let types: Vec<DataType> = file.schema().iter().map(|field| field.data_type).collect();
+    assert_eq!(types, [DataType::Date32, DataType::Time64, DataType::Timestamp]);
+    let read = IpcFile::from_bytes(&file.to_bytes().unwrap()).unwrap();
+    assert_eq!(read.batches()[0].to_result(), result);
+}
+
+#[test]
+fn decimals_round_trip_at_their_scale() {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE prices (price DECIMAL(10,2))").unwrap();
+    db.execute_sql("INSERT INTO prices VALUES (19.99), (NULL), (-0.5)").unwrap();
+    let result = db.execute_sql("SELECT price FROM prices").unwrap();
+    let file = IpcFile::from_result(&result).unwrap();
+    assert_eq!(file.schema()[0].data_type, DataType::Decimal128(38, 2));
+    let read = IpcFile::from_bytes(&file.to_bytes().unwrap()).unwrap();
+    assert_eq!(read.batches()[0].to_result(), result);
+    assert_eq!(read.batches()[0].to_result().rows[2][0], db.execute_sql("VALUES (-0.50)").unwrap().rows[0][0]);
+
+    // Decimals of different scales don't make one column
+    let mixed = db.execute_sql("VALUES (1.5), (2.25)").unwrap();
+    assert!(matches!(IpcFile::from_result(&mixed), Err(ArrowError::MixedTypes(_))));
+}
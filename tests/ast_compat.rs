@@ -0,0 +1,493 @@
+// ast_compat.rs — fails to compile when a published AST variant or field changes
+// Author: Sreerag Devadasan
+//
+// Every variant of every AST enum is matched here with all of its fields
+// named, and every AST struct is built with all of its fields, as
+// downstream code may do. Adding a variant is fine (the enums are
+// non-exhaustive, so each match ends in a wildcard); removing or renaming
+// a variant or a field, or adding a field, breaks this file, and is a
+// breaking change that needs a major version. Update the file in the same
+// change, knowingly.
+
+use sreerag_devadasan::ast::*;
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::parser::parse_sql_with_dialect;
+use sreerag_devadasan::visit::{walk, Visitor};
+
+#[test]
+fn the_parser_produces_every_published_statement() {
+    #[derive(Default)]
+    struct Shapes {
+        statements: Vec<&'static str>,
+        factors: Vec<&'static str>,
+        expressions: Vec<&'static str>,
+    }
+    impl Visitor for Shapes {
+        fn statement(&mut self, value: &Statement) {
+            self.statements.push(statement(value));
+        }
+        fn table_factor(&mut self, value: &TableFactor) {
+            self.factors.push(table_factor(value));
+        }
+        fn expression(&mut self, value: &Expression) {
+            self.expressions.push(expression(value));
+        }
+    }
+
+    let corpus = [
+        "SELECT a FROM t",
+        "CREATE TABLE t (id INT)",
+        "DROP TABLE t",
+        "CREATE VIEW v AS SELECT a FROM t",
+        "CREATE FUNCTION f(INT) RETURNS INT AS 'SELECT 1'",
+        "INSERT INTO t VALUES (1)",
+        "INSERT INTO t SELECT a FROM u",
+        "UPDATE t SET a = 1",
+        "DELETE FROM t",
+        "GRANT SELECT ON t TO u",
+        "REVOKE SELECT ON t FROM u",
+        "GRANT r TO u",
+        "REVOKE r FROM u",
+        "CREATE ROLE r LOGIN",
+        "CREATE TRIGGER trg BEFORE INSERT ON t FOR EACH ROW EXECUTE FUNCTION f()",
+        "ALTER ROLE r NOLOGIN",
+        "MERGE INTO t USING s ON t.id = s.id WHEN MATCHED THEN DELETE",
+        "COPY t FROM STDIN",
+        "LOAD DATA INFILE 'rows.csv' INTO TABLE t",
+        "SET x = 1",
+        "PREPARE p AS SELECT a FROM t",
+        "EXECUTE p(1)",
+        "DEALLOCATE p",
+        "BEGIN",
+        "VALUES (1)",
+        "SELECT a FROM t UNION SELECT a FROM u",
+        "WITH c AS (SELECT a FROM t) SELECT a FROM c",
+    ];
+    let parse = |sql: &str| parse_sql_with_dialect(sql, Dialect::Postgres).unwrap_or_else(|e| panic!("{}: {}", sql, e));
+    let kinds: Vec<&str> = corpus.iter().map(|sql| statement(&parse(sql))).collect();
+    let expected = [
+        "Select", "CreateTable", "DropTable", "CreateView", "CreateFunction", "Insert", "InsertSelect", "Update",
+        "Delete", "Grant", "Revoke", "GrantRole", "RevokeRole", "CreateRole", "CreateTrigger", "AlterRole", "Merge", "Copy",
+        "LoadData", "SetVariable", "Prepare", "Execute", "Deallocate", "Transaction", "Values", "SetOperation", "With",
+    ];
+    assert_eq!(kinds, expected);
+
+    // Nested parts are visited too
+    let sql = "SELECT a FROM (SELECT a FROM t TABLESAMPLE BERNOULLI (5)) AS d WHERE f(-a, 'x') = :p OR (b.c = TRUE)";
+    let mut shapes = Shapes::default();
+    walk(&parse_sql_with_dialect(sql, Dialect::Postgres).unwrap(), &mut shapes);
+    assert_eq!(shapes.statements, ["Select", "Select"]);
+    assert_eq!(shapes.factors, ["Derived", "Table"]);
+    let expected = [
+        "Identifier", "Identifier", "Number", "BinaryOperation", "BinaryOperation", "Function", "UnaryOperation",
+        "Identifier", "String", "Placeholder", "Grouped", "BinaryOperation", "CompoundIdentifier", "Boolean",
+    ];
+    assert_eq!(shapes.expressions, expected);
+}
+
+#[test]
+fn published_enums_keep_their_variants() {
+    assert_eq!(binary_operator(&BinaryOperator::ILike), "ILike");
+    assert_eq!(unary_operator(&UnaryOperator::Negate), "Negate");
+    assert_eq!(unary_operator(&UnaryOperator::IsNotNull), "IsNotNull");
+    assert_eq!(binary_operator(&BinaryOperator::IsNotDistinctFrom), "IsNotDistinctFrom");
+    assert_eq!(db_type(&DBType::Varchar(10)), "Varchar");
+    assert_eq!(routine_type(&RoutineType::Named("TEXT".to_string())), "Named");
+    assert_eq!(constraint(&Constraint::Check(Expression::Null)), "Check");
+    assert_eq!(trigger_timing(&TriggerTiming::InsteadOf), "InsteadOf");
+    assert_eq!(trigger_event(&TriggerEvent::Truncate), "Truncate");
+    assert_eq!(trigger_body(&TriggerBody::Block(String::new())), "Block");
+    assert_eq!(privilege(&Privilege::All), "All");
+    assert_eq!(grant_object(&GrantObject::Schema(Ident::new("s"))), "Schema");
+    assert_eq!(role_option(&RoleOption::Login(true)), "Login");
+    assert_eq!(merge_source(&MergeSource::Table(Ident::new("t"))), "Table");
+    assert_eq!(lock_strength(&LockStrength::KeyShare), "KeyShare");
+    assert_eq!(lock_wait(&LockWait::SkipLocked), "SkipLocked");
+    assert_eq!(set_operator(&SetOperator::Except), "Except");
+    assert_eq!(join_kind(&JoinKind::Full), "Full");
+    assert_eq!(grouping_element(&GroupingElement::Cube(Vec::new())), "Cube");
+    assert_eq!(merge_action(&MergeAction::Delete), "Delete");
+    assert_eq!(copy_target(&CopyTarget::Stdin), "Stdin");
+    assert_eq!(duplicate_handling(&DuplicateHandling::Ignore), "Ignore");
+    assert_eq!(set_scope(&SetScope::Local), "Local");
+    assert_eq!(transaction_command(&TransactionCommand::Rollback), "Rollback");
+    assert_eq!(quote_style(&QuoteStyle::Bracket), "Bracket");
+}
+
+#[test]
+fn published_structs_keep_their_fields() {
+    let expr = Expression::Number(1);
+    let window = WindowSpec {
+        partition_by: vec![expr.clone()],
+        order_by: vec![OrderByExpr { expr: expr.clone(), asc: None }],
+    };
+    let parameter = FunctionParameter { name: None, data_type: RoutineType::Known(DBType::Int) };
+    let column = TableColumn { column_name: Ident::new("id"), column_type: DBType::Int, constraints: Vec::new() };
+    let hint = Hint { name: "INDEX".to_string(), args: Vec::new() };
+    let alias = TableAlias { name: Ident::new("a"), columns: Vec::new() };
+    let sample = TableSample { method: "SYSTEM".to_string(), percent: expr.clone(), repeatable: None };
+    let locking = LockingClause { strength: LockStrength::Update, of: Vec::new(), wait: None };
+    let query = Statement::Values { rows: vec![vec![expr.clone()]] };
+    let cte = Cte { name: Ident::new("c"), columns: Vec::new(), query: Box::new(query) };
+    let relation = TableFactor::Table { name: Ident::new("t"), alias: None, sample: None };
+    let join = Join { kind: JoinKind::Cross, relation, on: None };
+    let wildcard = WildcardOptions { exclude: Vec::new(), replace: Vec::new() };
+    let item = SelectItem { expr: expr.clone(), alias: None };
+    let clause = MergeClause { matched: true, condition: None, action: MergeAction::Delete };
+    let option = CopyOption { name: "FORMAT".to_string(), value: None };
+    let format = LoadDataFormat {
+        fields_terminated_by: None,
+        fields_enclosed_by: None,
+        optionally_enclosed: false,
+        fields_escaped_by: None,
+        lines_starting_by: None,
+        lines_terminated_by: None,
+    };
+    let ident = Ident { value: "id".to_string(), quote: None };
+    let granted = GrantedPrivilege { privilege: Privilege::Update, columns: vec![ident.clone()] };
+    // A query's ORDER BY items are a window's
+    let select = Statement::new_select(vec![item.clone()], Ident::new("t"), None, window.order_by.clone(), None, None);
+    let Statement::Select { order_by, .. } = &select else { unreachable!() };
+    let order_by: &Vec<OrderByExpr> = order_by;
+    assert_eq!(order_by, &window.order_by);
+    assert_eq!(window.partition_by.len() + window.order_by.len(), 2);
+    assert_eq!((parameter.name, column.constraints.len(), hint.args.len(), alias.columns.len()), (None, 0, 0, 0));
+    assert_eq!((sample.repeatable, locking.wait, cte.columns.len(), join.on), (None, None, 0, None));
+    assert_eq!((wildcard.exclude.len(), clause.condition, option.value, format), (0, None, None, Default::default()));
+    assert_eq!((ident, item.alias, granted.columns.len()), (Ident::new("id"), None, 1));
+}
+
+fn quote_style(value: &QuoteStyle) -> &'static str {
+    match value {
+        QuoteStyle::Double => "Double",
+        QuoteStyle::Backtick => "Backtick",
+        QuoteStyle::Bracket => "Bracket",
+        _ => "new",
+    }
+}
+
+fn expression(value: &Expression) -> &'static str {
+    match value {
+        Expression::Identifier(_) => "Identifier",
+        Expression::CompoundIdentifier(_) => "CompoundIdentifier",
+        Expression::Number(_) => "Number",
+        Expression::String(_) => "String",
+        Expression::UnaryOperation { operator: _, operand: _ } => "UnaryOperation",
+        Expression::BinaryOperation { left_operand: _, operator: _, right_operand: _ } => "BinaryOperation",
+        Expression::Boolean(_) => "Boolean",
+        Expression::Null => "Null",
+        Expression::Grouped(_) => "Grouped",
+        Expression::Placeholder(_) => "Placeholder",
+        Expression::Function { name: _, args: _, over: _ } => "Function",
+        _ => "new",
+    }
+}
+
+fn binary_operator(value: &BinaryOperator) -> &'static str {
+    match value {
+        BinaryOperator::Equals => "Equals",
+        BinaryOperator::NotEquals => "NotEquals",
+        BinaryOperator::GreaterThan => "GreaterThan",
+        BinaryOperator::GreaterThanOrEqual => "GreaterThanOrEqual",
+        BinaryOperator::LessThan => "LessThan",
+        BinaryOperator::LessThanOrEqual => "LessThanOrEqual",
+        BinaryOperator::And => "And",
+        BinaryOperator::Or => "Or",
+        BinaryOperator::Add => "Add",
+        BinaryOperator::Subtract => "Subtract",
+        BinaryOperator::Multiply => "Multiply",
+        BinaryOperator::Divide => "Divide",
+        BinaryOperator::Like => "Like",
+        BinaryOperator::ILike => "ILike",
+        BinaryOperator::Concat => "Concat",
+        BinaryOperator::IsDistinctFrom => "IsDistinctFrom",
+        BinaryOperator::IsNotDistinctFrom => "IsNotDistinctFrom",
+        _ => "new",
+    }
+}
+
+fn unary_operator(value: &UnaryOperator) -> &'static str {
+    match value {
+        UnaryOperator::Not => "Not",
+        UnaryOperator::Negate => "Negate",
+        UnaryOperator::IsNull => "IsNull",
+        UnaryOperator::IsNotNull => "IsNotNull",
+        _ => "new",
+    }
+}
+
+fn db_type(value: &DBType) -> &'static str {
+    match value {
+        DBType::Int => "Int",
+        DBType::Varchar(_) => "Varchar",
+        DBType::Bool => "Bool",
+        _ => "new",
+    }
+}
+
+fn routine_type(value: &RoutineType) -> &'static str {
+    match value {
+        RoutineType::Known(_) => "Known",
+        RoutineType::Named(_) => "Named",
+        _ => "new",
+    }
+}
+
+fn constraint(value: &Constraint) -> &'static str {
+    match value {
+        Constraint::NotNull => "NotNull",
+        Constraint::PrimaryKey => "PrimaryKey",
+        Constraint::Check(_) => "Check",
+        _ => "new",
+    }
+}
+
+fn trigger_timing(value: &TriggerTiming) -> &'static str {
+    match value {
+        TriggerTiming::Before => "Before",
+        TriggerTiming::After => "After",
+        TriggerTiming::InsteadOf => "InsteadOf",
+        _ => "new",
+    }
+}
+
+fn trigger_event(value: &TriggerEvent) -> &'static str {
+    match value {
+        TriggerEvent::Insert => "Insert",
+        TriggerEvent::Update => "Update",
+        TriggerEvent::Delete => "Delete",
+        TriggerEvent::Truncate => "Truncate",
+        _ => "new",
+    }
+}
+
+fn trigger_body(value: &TriggerBody) -> &'static str {
+    match value {
+        TriggerBody::Execute { procedure: _, function: _, args: _ } => "Execute",
+        TriggerBody::Block(_) => "Block",
+        TriggerBody::Statement(_) => "Statement",
+        _ => "new",
+    }
+}
+
+fn privilege(value: &Privilege) -> &'static str {
+    match value {
+        Privilege::All => "All",
+        Privilege::Select => "Select",
+        Privilege::Insert => "Insert",
+        Privilege::Update => "Update",
+        Privilege::Delete => "Delete",
+        Privilege::Truncate => "Truncate",
+        Privilege::References => "References",
+        Privilege::Trigger => "Trigger",
+        Privilege::Usage => "Usage",
+        Privilege::Execute => "Execute",
+        _ => "new",
+    }
+}
+
+fn grant_object(value: &GrantObject) -> &'static str {
+    match value {
+        GrantObject::Table(_) => "Table",
+        GrantObject::Sequence(_) => "Sequence",
+        GrantObject::Schema(_) => "Schema",
+        GrantObject::Function(_) => "Function",
+        _ => "new",
+    }
+}
+
+fn role_option(value: &RoleOption) -> &'static str {
+    match value {
+        RoleOption::Login(_) => "Login",
+        RoleOption::Superuser(_) => "Superuser",
+        RoleOption::CreateDb(_) => "CreateDb",
+        RoleOption::CreateRole(_) => "CreateRole",
+        RoleOption::Password(_) => "Password",
+        _ => "new",
+    }
+}
+
+fn merge_source(value: &MergeSource) -> &'static str {
+    match value {
+        MergeSource::Table(_) => "Table",
+        MergeSource::Query(_) => "Query",
+        _ => "new",
+    }
+}
+
+fn table_factor(value: &TableFactor) -> &'static str {
+    match value {
+        TableFactor::Table { name: _, alias: _, sample: _ } => "Table",
+        TableFactor::AsOf { name: _, version: _, alias: _ } => "AsOf",
+        TableFactor::Derived { lateral: _, subquery: _, alias: _ } => "Derived",
+        TableFactor::Function { lateral: _, name: _, args: _, alias: _ } => "Function",
+        TableFactor::Pivot { table: _, aggregate: _, column: _, values: _, alias: _ } => "Pivot",
+        TableFactor::Unpivot { table: _, value: _, name: _, columns: _, alias: _ } => "Unpivot",
+        _ => "new",
+    }
+}
+
+fn lock_strength(value: &LockStrength) -> &'static str {
+    match value {
+        LockStrength::Update => "Update",
+        LockStrength::NoKeyUpdate => "NoKeyUpdate",
+        LockStrength::Share => "Share",
+        LockStrength::KeyShare => "KeyShare",
+        _ => "new",
+    }
+}
+
+fn lock_wait(value: &LockWait) -> &'static str {
+    match value {
+        LockWait::NoWait => "NoWait",
+        LockWait::SkipLocked => "SkipLocked",
+        _ => "new",
+    }
+}
+
+fn set_operator(value: &SetOperator) -> &'static str {
+    match value {
+        SetOperator::Union => "Union",
+        SetOperator::Intersect => "Intersect",
+        SetOperator::Except => "Except",
+        _ => "new",
+    }
+}
+
+fn join_kind(value: &JoinKind) -> &'static str {
+    match value {
+        JoinKind::Inner => "Inner",
+        JoinKind::Left => "Left",
+        JoinKind::Right => "Right",
+        JoinKind::Full => "Full",
+        JoinKind::Cross => "Cross",
+        _ => "new",
+    }
+}
+
+fn grouping_element(value: &GroupingElement) -> &'static str {
+    match value {
+        GroupingElement::Expression(_) => "Expression",
+        GroupingElement::Rollup(_) => "Rollup",
+        GroupingElement::Cube(_) => "Cube",
+        GroupingElement::GroupingSets(_) => "GroupingSets",
+        _ => "new",
+    }
+}
+
+fn merge_action(value: &MergeAction) -> &'static str {
+    match value {
+        MergeAction::Update(_) => "Update",
+        MergeAction::Delete => "Delete",
+        MergeAction::Insert { columns: _, values: _ } => "Insert",
+        _ => "new",
+    }
+}
+
+fn copy_target(value: &CopyTarget) -> &'static str {
+    match value {
+        CopyTarget::File(_) => "File",
+        CopyTarget::Stdin => "Stdin",
+        CopyTarget::Stdout => "Stdout",
+        _ => "new",
+    }
+}
+
+fn duplicate_handling(value: &DuplicateHandling) -> &'static str {
+    match value {
+        DuplicateHandling::Replace => "Replace",
+        DuplicateHandling::Ignore => "Ignore",
+        _ => "new",
+    }
+}
+
+fn set_scope(value: &SetScope) -> &'static str {
+    match value {
+        SetScope::Session => "Session",
+        SetScope::Local => "Local",
+        SetScope::Global => "Global",
+        _ => "new",
+    }
+}
+
+fn transaction_command(value: &TransactionCommand) -> &'static str {
+    match value {
+        TransactionCommand::Begin => "Begin",
+        TransactionCommand::Commit => "Commit",
+        TransactionCommand::Rollback => "Rollback",
+        _ => "new",
+    }
+}
+
+fn statement(value: &Statement) -> &'static str {
+    match value {
+        Statement::Select {
+            hints: _,
+            columns: _,
+            wildcard: _,
+            from: _,
+            joins: _,
+            selection: _,
+            group_by: _,
+            qualify: _,
+            order_by: _,
+            limit: _,
+            offset: _,
+            locking: _,
+        } => "Select",
+        Statement::CreateTable { table_name: _, column_list: _ } => "CreateTable",
+        Statement::DropTable { table_name: _, if_exists: _ } => "DropTable",
+        Statement::CreateFunction {
+            or_replace: _,
+            procedure: _,
+            name: _,
+            parameters: _,
+            returns: _,
+            language: _,
+            body: _,
+        } => "CreateFunction",
+        Statement::Insert { table_name: _, columns: _, rows: _ } => "Insert",
+        Statement::CreateView { or_replace: _, name: _, columns: _, query: _ } => "CreateView",
+        Statement::InsertSelect { table_name: _, columns: _, query: _ } => "InsertSelect",
+        Statement::Update { table_name: _, alias: _, assignments: _, selection: _ } => "Update",
+        Statement::Delete { table_name: _, alias: _, selection: _ } => "Delete",
+        Statement::Grant { privileges: _, object: _, grantees: _, with_grant_option: _ } => "Grant",
+        Statement::Revoke { privileges: _, object: _, grantees: _ } => "Revoke",
+        Statement::GrantRole { roles: _, grantees: _, with_admin_option: _ } => "GrantRole",
+        Statement::RevokeRole { roles: _, grantees: _ } => "RevokeRole",
+        Statement::CreateRole { role_name: _, options: _ } => "CreateRole",
+        Statement::CreateTrigger {
+            name: _,
+            timing: _,
+            events: _,
+            update_columns: _,
+            table_name: _,
+            for_each_row: _,
+            condition: _,
+            body: _,
+        } => "CreateTrigger",
+        Statement::AlterRole { role_name: _, options: _ } => "AlterRole",
+        Statement::Merge { target: _, target_alias: _, source: _, source_alias: _, on: _, clauses: _ } => "Merge",
+        Statement::Copy { table_name: _, columns: _, from: _, target: _, options: _ } => "Copy",
+        Statement::LoadData {
+            local: _,
+            file: _,
+            duplicates: _,
+            table_name: _,
+            format: _,
+            ignore_lines: _,
+            columns: _,
+        } => "LoadData",
+        Statement::SetVariable { scope: _, name: _, value: _ } => "SetVariable",
+        Statement::Prepare { name: _, parameter_types: _, statement: _ } => "Prepare",
+        Statement::Execute { name: _, parameters: _ } => "Execute",
+        Statement::Deallocate { name: _ } => "Deallocate",
+        Statement::Transaction { command: _ } => "Transaction",
+        Statement::Values { rows: _ } => "Values",
+        Statement::SetOperation { operator: _, all: _, left: _, right: _ } => "SetOperation",
+        Statement::With { recursive: _, ctes: _, body: _ } => "With",
+        Statement::Unparsed { raw_sql: _, span: _ } => "Unparsed",
+        Statement::Custom(_) => "Custom",
+        _ => "new",
+    }
+}
@@ -0,0 +1,77 @@
+// batch.rs — checks parsing batches of statements across worker threads, in order and cancellably
+// Author: Sreerag Devadasan
+
+#![cfg(feature = "std")]
+
+use std::time::Duration;
+
+use sreerag_devadasan::batch::{parse_many, parse_many_cancellable, parse_script, Cancellation};
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::tokenizer::ParseError;
+
+#[test]
+fn a_cancelled_batch_stops_its_workers_and_reports_what_it_didnt_parse() {
+    let sql = "SELECT a, b + 1 FROM t WHERE c = 'x' AND d BETWEEN 1 AND 3";
+    let statements = vec![sql; 200_000];
+    let outcome = parse_many_cancellable(&statements, &Cancellation::with_timeout(Duration::from_millis(20)));
+    assert!(outcome.cancelled);
+    let completed = outcome.completed();
+    assert!(0 < completed && completed < statements.len(), "{} of {}", completed, statements.len());
+    // Workers take statements in order, so nothing far past the parsed ones was started
+    let last = outcome.results.iter().rposition(Option::is_some).unwrap();
+    assert!(last < completed + 64, "{} parsed, the last at {}", completed, last);
+
+    let expected = parse_sql(sql).unwrap();
+    let results = outcome.into_results();
+    assert_eq!(results.len(), statements.len());
+    let cancelled = results.iter().filter(|r| matches!(r, Err(ParseError::Cancelled))).count();
+    assert_eq!(cancelled, statements.len() - completed);
+    assert!(results.iter().all(|r| matches!(r, Err(ParseError::Cancelled)) || matches!(r, Ok(s) if *s == expected)));
+    assert_eq!(ParseError::Cancelled.code(), "cancelled");
+}
+
+#[test]
+fn a_batch_cancelled_before_it_starts_parses_nothing() {
+    let cancel = Cancellation::new();
+    let clone = cancel.clone();
+    clone.cancel();
+    assert!(cancel.is_cancelled());
+    let outcome = parse_many_cancellable(&["SELECT 1 FROM t", "SELECT 2 FROM t"], &cancel);
+    assert!(outcome.cancelled);
+    assert_eq!(outcome.completed(), 0);
+    assert!(outcome.into_results().iter().all(|r| matches!(r, Err(ParseError::Cancelled))));
+
+    let outcome = parse_many_cancellable(&["SELECT 1 FROM t", "SELECT"], &Cancellation::new());
+    assert!(!outcome.cancelled);
+    assert_eq!(outcome.completed(), 2);
+}
+
+#[test]
+fn parse_many_keeps_the_input_order_and_each_statements_own_error() {
+    let statements: Vec<String> = (0..500)
+        .map(|i| if i % 7 == 3 { format!("SELECT {} FROM", i) } else { format!("SELECT {} FROM t{}", i, i) })
+        .collect();
+    let statements: Vec<&str> = statements.iter().map(String::as_str).collect();
+    let results = parse_many(&statements);
+    assert_eq!(results.len(), statements.len());
+    for (i, (sql, result)) in statements.iter().zip(&results).enumerate() {
+        match result {
+            Ok(statement) => {
+                assert_ne!(i % 7, 3, "{}", sql);
+                assert_eq!(*statement, parse_sql(sql).unwrap());
+            }
+            Err(error) => {
+                assert_eq!(i % 7, 3, "{}: {}", sql, error);
+                assert_eq!(error.to_string(), parse_sql(sql).unwrap_err().to_string());
+            }
+        }
+    }
+
+    let script = "SELECT a FROM t; SELECT FROM; DELETE FROM u WHERE b = 1;\nSELECT 'unterminated";
+    let results = parse_script(script);
+    assert_eq!(results.len(), 4);
+    assert!(results[0].is_ok() && results[2].is_ok());
+    assert!(matches!(results[1], Err(ParseError::Unexpected { .. })));
+    assert!(matches!(results[3], Err(ParseError::Lex(_))));
+    assert!(parse_many(&[]).is_empty());
+}
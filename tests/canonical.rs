@@ -0,0 +1,42 @@
+// canonical.rs — checks which statements are structurally equivalent
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::canonical::{canonicalize, equivalent_sql};
+use sreerag_devadasan::parser::parse_sql;
+
+#[test]
+fn aliases_and_decimal_zeros_dont_change_a_statement() {
+    for (a, b) in [
+        ("SELECT x.a FROM t x", "SELECT y.a FROM t AS y"),
+        ("SELECT a AS s FROM t ORDER BY s", "SELECT a AS total FROM t ORDER BY total"),
+        (
+            "SELECT o.id FROM orders o JOIN users u ON u.id = o.uid",
+            "SELECT a.id FROM orders a JOIN users b ON b.id = a.uid",
+        ),
+        ("SELECT s.n FROM (SELECT a AS n FROM t) AS s", "SELECT q.m FROM (SELECT a AS m FROM t) AS q"),
+        ("UPDATE t AS x SET a = x.a + 1", "UPDATE t AS y SET a = y.a + 1"),
+        ("SELECT a FROM t WHERE a = 1.0", "SELECT a FROM t WHERE a = 1.00"),
+        ("SELECT 01.50 FROM t", "SELECT 1.5 FROM t"),
+    ] {
+        assert!(equivalent_sql(a, b).unwrap(), "{} / {}", a, b);
+    }
+}
+
+#[test]
+fn what_names_other_things_or_changes_a_value_does() {
+    for (a, b) in [
+        ("SELECT x.a FROM t x", "SELECT x.a FROM u x"),
+        ("SELECT a FROM t WHERE a = 1.5", "SELECT a FROM t WHERE a = 1.05"),
+        // A qualifier that is a table's name, not an alias, stays a name
+        ("SELECT t.a FROM t", "SELECT x.a FROM t x"),
+        ("SELECT x.a FROM t x JOIN u y ON TRUE", "SELECT y.a FROM t x JOIN u y ON TRUE"),
+    ] {
+        assert!(!equivalent_sql(a, b).unwrap(), "{} / {}", a, b);
+    }
+}
+
+#[test]
+fn the_canonical_form_keeps_aliases_and_decimals_as_written() {
+    let sql = "SELECT a AS total, 1.50 FROM t AS x";
+    assert_eq!(canonicalize(&parse_sql(sql).unwrap()), parse_sql(sql).unwrap());
+}
@@ -0,0 +1,36 @@
+// capabilities.rs — checks that capabilities() matches the grammar the build parses
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::capabilities;
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::session::{ParserOptions, ParserSession};
+
+#[test]
+fn capabilities_match_the_grammar() {
+    let capabilities = capabilities();
+    let warehouse = ParserSession::new(ParserOptions { dialect: Dialect::Warehouse, ..Default::default() });
+    for (enabled, feature, sql) in [
+        (capabilities.ddl, "ddl", "CREATE TABLE t (id INT)"),
+        (capabilities.ddl, "ddl", "DROP TABLE t"),
+        (capabilities.dml, "dml", "INSERT INTO t VALUES (1)"),
+        (capabilities.cte, "cte", "WITH a AS (SELECT x FROM t) SELECT x FROM a"),
+        (capabilities.window, "window", "SELECT x FROM t WHERE rank() OVER (ORDER BY x) = 1"),
+    ] {
+        match warehouse.parse(sql) {
+            Ok(_) => assert!(enabled, "{} parsed without the {} feature", sql, feature),
+            Err(e) => {
+                assert!(!enabled, "{} failed with the {} feature: {}", sql, feature, e);
+                assert!(e.to_string().contains(&format!("\"{}\" feature", feature)), "{}", e);
+            }
+        }
+    }
+    // Without the window feature QUALIFY isn't a clause
+    assert_eq!(warehouse.parse("SELECT x FROM t QUALIFY x = 1").is_ok(), capabilities.window);
+
+    assert_eq!(capabilities.std, cfg!(feature = "std"));
+    assert!(parse_sql("SELECT x FROM t").is_ok());
+    let enabled = capabilities.enabled();
+    assert_eq!(enabled.len(), capabilities.features().iter().filter(|(_, on)| *on).count());
+    assert_eq!(enabled.contains(&"engine"), cfg!(feature = "engine"));
+}
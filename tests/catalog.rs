@@ -0,0 +1,77 @@
+// catalog.rs — checks snapshots and versioning of the shared catalog
+// Author: Sreerag Devadasan
+
+use std::thread;
+
+use sreerag_devadasan::catalog::SharedCatalog;
+use sreerag_devadasan::complete::{complete, CompletionKind};
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::schema_diff::Schema;
+
+#[test]
+fn snapshots_are_unaffected_by_later_ddl() {
+    let catalog = SharedCatalog::new(Schema::from_sql("CREATE TABLE users (id INT)").unwrap());
+    let before = catalog.snapshot();
+    assert_eq!(before.version, 0);
+
+    assert_eq!(catalog.apply_sql("CREATE TABLE orders (id INT); DROP TABLE users").unwrap(), 1);
+    let after = catalog.snapshot();
+    assert_eq!(after.version, 1);
+    assert_eq!(before.tables.keys().collect::<Vec<_>>(), ["users"]);
+    assert_eq!(after.tables.keys().collect::<Vec<_>>(), ["orders"]);
+
+    let names = |sql: &str, schema: &Schema| -> Vec<String> {
+        let tables = complete(sql, sql.len(), schema).into_iter().filter(|c| c.kind == CompletionKind::Table);
+        tables.map(|c| c.text).collect()
+    };
+    assert_eq!(names("SELECT id FROM ", &before), ["users"]);
+    assert_eq!(names("SELECT id FROM ", &after), ["orders"]);
+}
+
+#[test]
+fn only_changes_bump_the_version() {
+    let catalog = SharedCatalog::default();
+    assert_eq!(catalog.apply(&parse_sql("SELECT id FROM users").unwrap()), 0);
+    assert_eq!(catalog.apply(&parse_sql("DROP TABLE users").unwrap()), 0);
+    assert_eq!(catalog.apply(&parse_sql("CREATE TABLE users (id INT)").unwrap()), 1);
+    assert_eq!(catalog.version(), 1);
+    assert_eq!(catalog.snapshot(), catalog.snapshot());
+}
+
+#[test]
+fn scripts_that_fail_to_parse_change_nothing() {
+    let catalog = SharedCatalog::default();
+    assert!(catalog.apply_sql("CREATE TABLE a (id INT); CREATE TABLE (").is_err());
+    assert_eq!(catalog.version(), 0);
+    assert!(catalog.snapshot().tables.is_empty());
+}
+
+#[test]
+fn is_shared_between_threads() {
+    let catalog = SharedCatalog::default();
+    let writers: Vec<_> = (0..4)
+        .map(|i| {
+            let catalog = catalog.clone();
+            thread::spawn(move || catalog.apply_sql(&format!("CREATE TABLE t{} (id INT)", i)).unwrap())
+        })
+        .collect();
+    let reader = {
+        let catalog = catalog.clone();
+        thread::spawn(move || {
+            // Versions only move forward, and each snapshot is consistent
+            let mut last = 0;
+            for _ in 0..100 {
+                let snapshot = catalog.snapshot();
+                assert!(snapshot.version >= last);
+                assert_eq!(snapshot.tables.len() as u64, snapshot.version);
+                last = snapshot.version;
+            }
+        })
+    };
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    reader.join().unwrap();
+    assert_eq!(catalog.version(), 4);
+    assert_eq!(catalog.snapshot().tables.len(), 4);
+}
@@ -0,0 +1,68 @@
+// cli.rs — checks the command line parser, the help text, completions and the man page
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::cli::{self, CliError, Shell, COMMANDS};
+
+fn parse(line: &str) -> Result<cli::Matches, CliError> {
+    let args: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+    cli::parse(&args)
+}
+
+#[test]
+fn parses_commands_positionals_and_options() {
+    let matches = parse("--color=never log-stats q.log --top 5 --json --theme light").unwrap();
+    assert_eq!(matches.command, Some("log-stats"));
+    assert_eq!(matches.positional(0), Some("q.log"));
+    assert_eq!(matches.value("top"), Some("5"));
+    assert_eq!(matches.value("color"), Some("never"));
+    assert_eq!(matches.value("theme"), Some("light"));
+    assert!(matches.flag("json"));
+    assert!(!matches.flag("csv"));
+
+    let matches = parse("conformance a.sql b.sql c.sql --failures").unwrap();
+    assert_eq!(matches.positionals, ["a.sql", "b.sql", "c.sql"]);
+    let matches = parse("render model.sql --var a=1 --var=b=2").unwrap();
+    assert_eq!((matches.command, matches.positional(0)), (Some("render"), Some("model.sql")));
+    assert_eq!(matches.values("var"), ["a=1", "b=2"]);
+    assert_eq!(parse("").unwrap().command, None);
+    assert_eq!(parse("minify").unwrap().positional(0), None);
+}
+
+#[test]
+fn rejects_bad_command_lines() {
+    assert_eq!(parse("frob").unwrap_err(), CliError::UnknownCommand("frob".to_string()));
+    assert_eq!(parse("minify --json").unwrap_err(), CliError::UnknownOption("--json".to_string()));
+    assert_eq!(parse("check").unwrap_err(), CliError::MissingArgument { command: "check", argument: "file.sql" });
+    assert_eq!(parse("translate mysql").unwrap_err(), CliError::MissingArgument { command: "translate", argument: "to" });
+    assert_eq!(parse("schema-diff a b c").unwrap_err(), CliError::UnexpectedArgument("c".to_string()));
+    assert_eq!(parse("log-stats q.log --top").unwrap_err(), CliError::MissingValue("top"));
+    assert_eq!(parse("anonymize --hash=yes").unwrap_err(), CliError::UnexpectedValue("hash"));
+    let error = parse("translate mysql oracle").unwrap_err();
+    assert!(matches!(error, CliError::InvalidValue { name: "to", .. }), "{:?}", error);
+    assert!(error.to_string().contains("postgres"));
+    assert!(matches!(parse("log-stats q.log --top x").unwrap_err(), CliError::InvalidValue { name: "n", .. }));
+    assert!(matches!(parse("--color sometimes").unwrap_err(), CliError::InvalidValue { .. }));
+}
+
+#[test]
+fn help_completions_and_man_page_cover_every_command() {
+    let usage = cli::usage();
+    let man = cli::man_page();
+    for command in COMMANDS {
+        assert!(usage.contains(&command.synopsis()), "usage lacks {}", command.name);
+        assert!(man.contains(&format!("\\fB{}\\fR", command.name.replace('-', "\\-"))), "man lacks {}", command.name);
+    }
+    for section in ["NAME", "SYNOPSIS", "DESCRIPTION", "OPTIONS", "COMMANDS", "ENVIRONMENT", "FILES"] {
+        assert!(man.contains(&format!(".SH {}\n", section)), "man lacks {}", section);
+    }
+
+    for name in cli::SHELLS {
+        let script = cli::completions(Shell::from_name(name).unwrap());
+        assert!(script.contains(cli::BINARY));
+        for command in COMMANDS {
+            assert!(script.contains(command.name), "{} completions lack {}", name, command.name);
+        }
+        assert!(script.contains("--top") || script.contains("-l top"), "{} completions lack log-stats options", name);
+    }
+    assert_eq!(Shell::from_name("tcsh"), None);
+}
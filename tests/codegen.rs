@@ -0,0 +1,76 @@
+// codegen.rs — checks the Rust code generated for parsed statements
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::ast::*;
+use sreerag_devadasan::codegen::sql_to_rust;
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::parser::parse_sql;
+
+const INSERT: &str = "INSERT INTO t (a, b) VALUES (1, 'x''y'), (NULL, lower('A'))";
+
+#[test]
+fn generated_code_is_laid_out_like_rustfmt() {
+    let expected = r#"Statement::Insert {
+    table_name: Ident::new("t"),
+    columns: Some(vec![Ident::new("a"), Ident::new("b")]),
+    rows: vec![
+        vec![Expression::Number(1), Expression::String("x'y".to_string())],
+        vec![
+            Expression::Null,
+            Expression::Function {
+                name: "lower".to_string(),
+                args: vec![Expression::String("A".to_string())],
+                over: None,
+            },
+        ],
+    ],
+}"#;
+    assert_eq!(sql_to_rust(INSERT, Dialect::Generic).unwrap(), expected);
+}
+
+#[test]
+fn generated_code_builds_the_parsed_statement() {
+    // The output of sql_to_rust(INSERT), pasted
+    let built = Statement::Insert {
+        table_name: Ident::new("t"),
+        columns: Some(vec![Ident::new("a"), Ident::new("b")]),
+        rows: vec![
+            vec![Expression::Number(1), Expression::String("x'y".to_string())],
+            vec![
+                Expression::Null,
+                Expression::Function {
+                    name: "lower".to_string(),
+                    args: vec![Expression::String("A".to_string())],
+                    over: None,
+                },
+            ],
+        ],
+    };
+    assert_eq!(parse_sql(INSERT).unwrap(), built);
+
+    let built = Statement::Select {
+        hints: Vec::new(),
+        columns: vec![SelectItem { expr: Expression::Identifier(Ident::new("name")), alias: None }],
+        wildcard: WildcardOptions::default(),
+        from: Box::new(TableFactor::Table { name: Ident::new("users"), alias: None, sample: None }),
+        joins: Vec::new(),
+        selection: Some(Expression::BinaryOperation {
+            left_operand: Box::new(Expression::Identifier(Ident::new("id"))),
+            operator: BinaryOperator::Equals,
+            right_operand: Box::new(Expression::Placeholder(":id".to_string())),
+        }),
+        group_by: Vec::new(),
+        qualify: None,
+        order_by: Vec::new(),
+        limit: None,
+        offset: None,
+        locking: Vec::new(),
+    };
+    assert_eq!(parse_sql("SELECT name FROM users WHERE id = :id").unwrap(), built);
+}
+
+#[test]
+fn short_nodes_stay_on_one_line() {
+    let code = sql_to_rust("DROP TABLE IF EXISTS t", Dialect::Generic).unwrap();
+    assert_eq!(code, r#"Statement::DropTable { table_name: Ident::new("t"), if_exists: true }"#);
+}
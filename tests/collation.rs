@@ -0,0 +1,106 @@
+// collation.rs — checks COLLATE on columns and expressions
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::bytecode::{Instruction, Program};
+use sreerag_devadasan::canonical::canonicalize;
+use sreerag_devadasan::collation::Collation;
+use sreerag_devadasan::engine::{Database, EngineError, Storage};
+use sreerag_devadasan::eval::{evaluate, EvalError, Value};
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::pratt::PrattParser;
+use sreerag_devadasan::tokenizer::tokenize;
+
+fn database(storage: Storage) -> Database {
+    let mut db = Database::with_storage(storage);
+    db.execute_sql("CREATE TABLE users (id INT, name VARCHAR(10) COLLATE NOCASE, code VARCHAR(10))").unwrap();
+    let insert = "INSERT INTO users VALUES (1, 'bob', 'b'), (2, 'Ann', 'A'), (3, 'ANN', 'a'), (4, 'carl', NULL)";
+    db.execute_sql(insert).unwrap();
+    db
+}
+
+fn ids(db: &mut Database, sql: &str) -> Vec<Value> {
+    db.execute_sql(sql).unwrap().rows.into_iter().map(|row| row[0].clone()).collect()
+}
+
+#[test]
+fn collations_compare_and_clauses_round_trip() {
+    let nocase = Collation::from_name("NoCase").unwrap();
+    assert!(nocase.compare("Ann", "aNN").is_eq());
+    assert!(nocase.compare("b", "A").is_gt());
+    assert!(Collation::Binary.compare("b", "A").is_gt() && Collation::Binary.compare("a", "B").is_gt());
+    assert_eq!(Collation::from_name("de_DE"), None);
+
+    for sql in [
+        "CREATE TABLE t (name VARCHAR(10) NOT NULL COLLATE nocase)",
+        "SELECT id FROM t WHERE name COLLATE nocase = 'a' AND -x COLLATE binary < 1",
+    ] {
+        assert_eq!(parse_sql(sql).unwrap().to_string(), sql);
+    }
+    // COLLATE binds tighter than any operator, so a sum keeps its parentheses
+    let statement = parse_sql("SELECT id FROM t WHERE (a + b) COLLATE nocase = 1").unwrap();
+    assert_eq!(canonicalize(&statement).to_string(), "SELECT id FROM t WHERE (a + b) COLLATE nocase = 1");
+    assert!(parse_sql("SELECT id FROM t WHERE name COLLATE = 'a'").is_err());
+}
+
+#[test]
+fn expressions_compare_by_the_collation_they_name() {
+    let parse = |sql: &str| PrattParser::new(&tokenize(sql)).parse_expression(1).unwrap();
+    let eval = |sql: &str| evaluate(&parse(sql), &[], &[]);
+    assert_eq!(eval("'Ann' COLLATE nocase = 'aNN'"), Ok(Value::Bool(true)));
+    assert_eq!(eval("'Ann' = ('aNN' COLLATE NOCASE)"), Ok(Value::Bool(true)));
+    assert_eq!(eval("'Ann' COLLATE binary = 'aNN'"), Ok(Value::Bool(false)));
+    assert_eq!(eval("'b' COLLATE nocase > 'A'"), Ok(Value::Bool(true)));
+    assert_eq!(eval("'ABC' COLLATE nocase LIKE 'a%'"), Ok(Value::Bool(true)));
+    assert_eq!(eval("NULL COLLATE nocase = 'a'"), Ok(Value::Null));
+    assert_eq!(eval("'a' COLLATE nocase = 'A' COLLATE nocase"), Ok(Value::Bool(true)));
+    assert!(matches!(eval("'a' COLLATE nocase = 'A' COLLATE binary"), Err(EvalError::TypeMismatch(_))));
+    assert_eq!(eval("'a' COLLATE klingon = 'a'"), Err(EvalError::UnknownCollation("klingon".to_string())));
+
+    // A constant comparison folds to its collated result, but the VM
+    // can't carry a collation from an operand to the comparison
+    let folded = Program::compile(&parse("'Ann' COLLATE nocase = 'aNN'"), &[]).unwrap();
+    assert_eq!(folded.instructions(), [Instruction::Push(Value::Bool(true))]);
+    assert!(Program::compile(&parse("name = ('A' COLLATE nocase)"), &["name".to_string()]).is_err());
+}
+
+#[test]
+fn columns_compare_sort_and_key_by_their_collation() {
+    for storage in [Storage::Rows, Storage::Columnar] {
+        let mut db = database(storage);
+        assert_eq!(ids(&mut db, "SELECT id FROM users WHERE name = 'ann' ORDER BY id"), [Value::Int(2), Value::Int(3)]);
+        assert_eq!(ids(&mut db, "SELECT id FROM users WHERE 'BOB' = name"), [Value::Int(1)]);
+        assert_eq!(ids(&mut db, "SELECT id FROM users WHERE code = 'A'"), [Value::Int(2)]);
+        // A collation written in the query wins over the column's
+        assert_eq!(ids(&mut db, "SELECT id FROM users WHERE name = 'ANN' COLLATE binary"), [Value::Int(3)]);
+        let sql = "SELECT id FROM users WHERE code COLLATE nocase = 'a' ORDER BY id";
+        assert_eq!(ids(&mut db, sql), [Value::Int(2), Value::Int(3)]);
+        // Through an alias, too
+        assert_eq!(ids(&mut db, "SELECT i FROM users AS u (i, n) WHERE n = 'CARL'"), [Value::Int(4)]);
+
+        // Equal names keep their insertion order
+        let by_name = ids(&mut db, "SELECT id FROM users ORDER BY name");
+        assert_eq!(by_name, [Value::Int(2), Value::Int(3), Value::Int(1), Value::Int(4)], "{:?}", storage);
+        let by_code = ids(&mut db, "SELECT id FROM users ORDER BY code");
+        assert_eq!(by_code, [Value::Int(4), Value::Int(2), Value::Int(3), Value::Int(1)], "{:?}", storage);
+        // An ORDER BY item may name a collation of its own, and DESC reverses it
+        let by_binary = ids(&mut db, "SELECT id FROM users ORDER BY name COLLATE binary, id");
+        assert_eq!(by_binary, [Value::Int(3), Value::Int(2), Value::Int(1), Value::Int(4)], "{:?}", storage);
+        let by_nocase = ids(&mut db, "SELECT id FROM users ORDER BY code COLLATE nocase DESC, id");
+        assert_eq!(by_nocase, [Value::Int(1), Value::Int(2), Value::Int(3), Value::Int(4)], "{:?}", storage);
+        let by_name = ids(&mut db, "SELECT id FROM users ORDER BY name DESC, id DESC");
+        assert_eq!(by_name, [Value::Int(4), Value::Int(1), Value::Int(3), Value::Int(2)], "{:?}", storage);
+        let error = db.execute_sql("SELECT id FROM users ORDER BY name COLLATE klingon").unwrap_err();
+        assert_eq!(error.to_string(), "Unknown collation: klingon");
+    }
+
+    // Keys that differ only in case are the same key under NOCASE
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE tags (name VARCHAR(10) PRIMARY KEY COLLATE NOCASE)").unwrap();
+    db.execute_sql("INSERT INTO tags VALUES ('rust')").unwrap();
+    assert!(matches!(db.execute_sql("INSERT INTO tags VALUES ('Rust')"), Err(EngineError::DuplicateKey(_))));
+    assert!(matches!(db.execute_sql("INSERT INTO tags VALUES ('go'), ('GO')"), Err(EngineError::DuplicateKey(_))));
+
+    let error = db.execute_sql("CREATE TABLE t (name VARCHAR(10) COLLATE klingon)").unwrap_err();
+    assert_eq!(error.to_string(), "Unknown collation: klingon");
+    assert!(db.table("t").is_none());
+}
@@ -0,0 +1,123 @@
+// columnar.rs — checks columnar storage against the row layout
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::analyzer::SqlType;
+use sreerag_devadasan::ast::DBType;
+use sreerag_devadasan::columnar::{Aggregate, Bitmap, ColumnarTable};
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::engine::{Database, Storage};
+use sreerag_devadasan::eval::Value;
+use sreerag_devadasan::parser::parse_sql_with_dialect;
+use sreerag_devadasan::pratt::PrattParser;
+use sreerag_devadasan::tokenizer::tokenize;
+
+const SETUP: [&str; 2] = [
+    "CREATE TABLE people (id INT PRIMARY KEY, age INT, name VARCHAR(20), active BOOL)",
+    "INSERT INTO people VALUES (1, 34, 'ann', TRUE), (2, NULL, 'bob', FALSE), (3, 71, NULL, TRUE), \
+     (4, 18, 'dave', NULL), (5, 34, 'Eve', FALSE)",
+];
+
+fn database(storage: Storage) -> Database {
+    let mut db = Database::with_storage(storage);
+    db.register_scalar("name_length", &[SqlType::Text], SqlType::Integer, |args| match &args[0] {
+        Value::String(s) => Ok(Value::Int(s.chars().count() as i64)),
+        _ => Ok(Value::Null),
+    });
+    for sql in SETUP {
+        db.execute_sql(sql).unwrap();
+    }
+    db
+}
+
+#[test]
+fn bitmaps_count_and_combine() {
+    let odd = Bitmap::from_fn(130, |i| i % 2 == 1);
+    let low = Bitmap::from_fn(130, |i| i < 10);
+    assert_eq!((odd.len(), odd.count()), (130, 65));
+    assert_eq!(odd.and(&low).ones().collect::<Vec<_>>(), [1, 3, 5, 7, 9]);
+    assert_eq!(odd.or(&low).count(), 70);
+    // The unused bits of the last word stay clear
+    assert_eq!(odd.not().count(), 65);
+    assert_eq!(Bitmap::new(130, true).count(), 130);
+
+    let mut pushed = Bitmap::default();
+    for i in 0..130 {
+        pushed.push(i % 2 == 1);
+    }
+    assert_eq!(pushed, odd);
+    assert!(pushed.get(129) && !pushed.get(128) && !pushed.get(500));
+}
+
+#[test]
+fn both_layouts_select_the_same_rows() {
+    let mut rows = database(Storage::Rows);
+    let mut columns = database(Storage::Columnar);
+    assert_eq!(columns.table("people").unwrap().storage(), Storage::Columnar);
+    assert_eq!(rows.table("people").unwrap().rows(), columns.table("people").unwrap().rows());
+
+    for selection in [
+        "age > 30",
+        "age >= 34 AND active",
+        "NOT active OR age < 20",
+        "NOT (age = 34)",
+        "age * 2 - id = 67",
+        "-age < -50",
+        "name LIKE '%e'",
+        "name ILIKE 'e%'",
+        "name != 'bob' AND name < 'c'",
+        "active = FALSE",
+        "age = NULL OR id = 3",
+        "id = age",
+        "TRUE",
+        "1 = 2",
+        // Not vectorized: evaluated row by row
+        "age",
+        "active AND (name = 'ann' OR name_length(name) = 3)",
+    ] {
+        // ILIKE is Postgres syntax
+        let dialect = if selection.contains("ILIKE") { Dialect::Postgres } else { Dialect::Generic };
+        let sql = format!("SELECT id FROM people WHERE {}", selection);
+        let statement = parse_sql_with_dialect(&sql, dialect).unwrap();
+        let expected = rows.execute(&statement);
+        assert!(expected.is_ok(), "{}: {:?}", sql, expected);
+        assert_eq!(columns.execute(&statement).unwrap(), expected.unwrap(), "{}", sql);
+    }
+    let aliased = "SELECT years FROM people AS p (pk, years) WHERE years > 30 ORDER BY years";
+    assert_eq!(columns.execute_sql(aliased).unwrap(), rows.execute_sql(aliased).unwrap());
+}
+
+#[test]
+fn both_layouts_fail_on_the_same_rows() {
+    let mut rows = database(Storage::Rows);
+    let mut columns = database(Storage::Columnar);
+    for selection in ["name > 3", "age / (id - 1) > 1", "age + 9223372036854775800 > 0", "NOT name", "missing = 1"] {
+        let sql = format!("SELECT id FROM people WHERE {}", selection);
+        let expected = rows.execute_sql(&sql).unwrap_err().to_string();
+        assert_eq!(columns.execute_sql(&sql).unwrap_err().to_string(), expected, "{}", sql);
+    }
+    // Constraints hold as for rows
+    assert!(columns.execute_sql("INSERT INTO people VALUES (1, 1, 'x', TRUE)").is_err());
+    assert_eq!(columns.table("people").unwrap().len(), 5);
+}
+
+#[test]
+fn aggregates_skip_nulls() {
+    let db = database(Storage::Columnar);
+    let table = db.table("people").unwrap().columnar().unwrap();
+    let names: Vec<String> = ["id", "age", "name", "active"].iter().map(|c| c.to_string()).collect();
+    let tokens = tokenize("active");
+    let active = table.filter(&PrattParser::new(&tokens).parse_expression(1).unwrap(), &names).unwrap();
+
+    assert_eq!(table.aggregate(Aggregate::Count, None, None), Ok(Value::Int(5)));
+    assert_eq!(table.aggregate(Aggregate::Count, Some(1), None), Ok(Value::Int(4)));
+    assert_eq!(table.aggregate(Aggregate::Sum, Some(1), None), Ok(Value::Int(157)));
+    assert_eq!(table.aggregate(Aggregate::Sum, Some(1), Some(&active)), Ok(Value::Int(105)));
+    assert_eq!(table.aggregate(Aggregate::Min, Some(2), None), Ok(Value::String("Eve".to_string())));
+    assert_eq!(table.aggregate(Aggregate::Max, Some(1), None), Ok(Value::Int(71)));
+    assert_eq!(table.aggregate(Aggregate::Count, None, Some(&active)), Ok(Value::Int(2)));
+    assert!(table.aggregate(Aggregate::Sum, Some(2), None).is_err());
+
+    let empty = ColumnarTable::new(&[DBType::Int]);
+    assert_eq!(empty.aggregate(Aggregate::Max, Some(0), None), Ok(Value::Null));
+    assert_eq!(Aggregate::from_name("sum"), Some(Aggregate::Sum));
+}
@@ -0,0 +1,100 @@
+// complete.rs — checks completion candidates, which the parser's expected tokens decide
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::complete::{complete, complete_with_dialect, tab, CompletionKind};
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::schema_diff::Schema;
+
+const SCHEMA: &str = "CREATE TABLE users (id INT, name VARCHAR(20)); CREATE TABLE orders (id INT, user_id INT)";
+
+fn schema() -> Schema {
+    Schema::from_sql(SCHEMA).unwrap()
+}
+
+fn texts(sql: &str, dialect: Dialect) -> Vec<String> {
+    complete_with_dialect(sql, sql.len(), &schema(), dialect).into_iter().map(|c| c.text).collect()
+}
+
+fn has(sql: &str, dialect: Dialect, word: &str) -> bool {
+    texts(sql, dialect).iter().any(|text| text == word)
+}
+
+#[test]
+fn the_parser_decides_what_comes_next() {
+    let generic = Dialect::Generic;
+    assert_eq!(texts("SEL", generic), ["SELECT"]);
+    assert_eq!(texts("SELECT id FROM u", generic), ["users"]);
+    assert_eq!(texts("SELECT id F", generic), ["FROM"]);
+    // Columns of the tables the query names, even after the cursor
+    assert_eq!(texts("SELECT ", generic)[..2], ["id", "name"]);
+    let sql = "SELECT  FROM orders";
+    let found = complete(sql, 7, &schema());
+    let columns = found.iter().filter(|c| c.kind == CompletionKind::Column);
+    let columns: Vec<&str> = columns.map(|c| c.text.as_str()).collect();
+    assert_eq!(columns, ["id", "user_id"]);
+
+    // Joins and their conditions
+    assert!(has("SELECT id FROM users ", generic, "JOIN"));
+    assert!(has("SELECT id FROM users JOIN ", generic, "orders"));
+    let sql = "SELECT u.id FROM users u JOIN orders o ON ";
+    assert_eq!(texts(sql, generic)[..3], ["id", "name", "user_id"]);
+
+    // GROUP BY is where the grammar has it, not found by its words
+    assert_eq!(texts("SELECT id FROM users GR", generic), ["GROUP"]);
+    assert!(has("SELECT id FROM users GROUP BY ", generic, "ROLLUP"));
+    assert!(has("SELECT id FROM users GROUP BY id ", generic, "ORDER"));
+    assert!(!has("SELECT id FROM users ORDER BY id ", generic, "GROUP"));
+
+    // PIVOT and QUALIFY only in the dialects that have them
+    assert!(has("SELECT id FROM users ", Dialect::Warehouse, "PIVOT"));
+    assert!(has("SELECT id FROM users ", Dialect::Warehouse, "QUALIFY"));
+    assert!(!has("SELECT id FROM users ", generic, "QUALIFY"));
+    assert_eq!(texts("SELECT n FROM users PIVOT (SUM(id) FOR ", Dialect::Warehouse), ["id", "name"]);
+    assert!(has("SELECT id FROM users QUALIFY ", Dialect::Warehouse, "name"));
+
+    // New names, such as aliases and tables being created, get nothing, and
+    // neither does a prefix that fails to parse
+    assert!(texts("CREATE TABLE ", generic).is_empty());
+    assert_eq!(texts("SELECT id AS ", generic), Vec::<String>::new());
+    assert!(texts("SELECT FROM WHERE ", generic).is_empty());
+    assert_eq!(texts("INSERT INTO users (", generic), ["id", "name"]);
+    assert_eq!(texts("UPDATE orders SET ", generic), ["id", "user_id"]);
+}
+
+#[test]
+fn a_cursor_inside_a_character_is_moved_back_onto_it() {
+    let sql = "SELECT é";
+    for cursor in 0..=sql.len() + 1 {
+        complete(sql, cursor, &schema());
+    }
+    assert_eq!(complete(sql, 8, &schema()), complete(sql, 7, &schema()));
+
+    // A prefix ending inside a candidate's character doesn't match it
+    let schema = Schema::from_sql("CREATE TABLE aé (id INT); CREATE TABLE abc (id INT)").unwrap();
+    let found = complete_with_dialect("SELECT id FROM ab", 17, &schema, Dialect::Generic);
+    assert_eq!(found.iter().map(|c| c.text.as_str()).collect::<Vec<_>>(), ["abc"]);
+    let found = complete_with_dialect("SELECT id FROM a", 16, &schema, Dialect::Generic);
+    assert_eq!(found.len(), 2);
+}
+
+#[test]
+fn tab_completes_as_far_as_the_candidates_agree() {
+    let schema = schema();
+    let done = tab("sel", 3, &schema, Dialect::Generic);
+    assert_eq!((done.line.as_str(), done.cursor, done.choices.len()), ("SELECT ", 7, 0));
+    let done = tab("SELECT id FROM us WHERE id = 1", 17, &schema, Dialect::Generic);
+    assert_eq!((done.line.as_str(), done.cursor), ("SELECT id FROM users WHERE id = 1", 20));
+    let done = tab("SELECT id FROM users WHERE i", 28, &schema, Dialect::Generic);
+    assert_eq!(done.line, "SELECT id FROM users WHERE id ");
+    let done = tab("SELECT id FROM users ", 21, &schema, Dialect::Generic);
+    assert_eq!(done.line, "SELECT id FROM users ");
+    assert!(done.choices.len() > 5);
+    let done = tab("SELECT id FROM users CR", 23, &schema, Dialect::Generic);
+    assert_eq!(done.line, "SELECT id FROM users CROSS ");
+    let done = tab("SELECT ", 7, &schema, Dialect::Generic);
+    assert_eq!(done.line, "SELECT ");
+
+    let schema = Schema::from_sql("CREATE TABLE users (id INT); CREATE TABLE user_roles (id INT)").unwrap();
+    let done = tab("select id from US", 17, &schema, Dialect::Generic);
+    assert_eq!((done.line.as_str(), done.choices.len()), ("select id from user", 2));
+}
@@ -0,0 +1,32 @@
+// conformance.rs — checks the corpus adapters on the bundled samples
+// Author: Sreerag Devadasan
+
+use std::path::Path;
+
+use sreerag_devadasan::conformance::{load, run};
+
+fn corpus(name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("conformance").join(name)
+}
+
+#[test]
+fn sqllogictest_records_are_categorized() {
+    let entries = load(corpus("basic.slt")).unwrap();
+    assert_eq!(entries.len(), 6);
+    assert!(entries[2].expect_error);
+
+    let report = run(&entries);
+    assert_eq!(report.categories["CREATE TABLE"], (1, 1));
+    assert_eq!(report.categories["SELECT"], (3, 3));
+    assert_eq!(report.categories["INSERT"], (1, 1));
+    assert_eq!((report.passed(), report.total()), (6, 6));
+}
+
+#[test]
+fn corpus_categories_come_from_headers() {
+    let report = run(&load(corpus("basic.sql")).unwrap());
+    assert_eq!(report.categories["filters"], (2, 2));
+    assert_eq!(report.categories["joins"], (1, 2));
+    assert_eq!(report.failures.len(), 1);
+    assert!(report.failures[0].0.ends_with("basic.sql:5"));
+}
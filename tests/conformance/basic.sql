@@ -0,0 +1,6 @@
+-- category: filters
+SELECT id FROM users WHERE age > 18;
+SELECT id FROM users WHERE name = 'x' AND NOT (age < 3);
+-- category: joins
+SELECT id FROM users JOIN orders ON users.id = orders.user_id;
+SELECT id FROM users NATURAL JOIN orders;
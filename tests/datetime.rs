@@ -0,0 +1,111 @@
+// datetime.rs — checks DATE, TIME and TIMESTAMP values in the evaluator and the engine
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::datetime::{Date, Time, Timestamp};
+use sreerag_devadasan::engine::{Database, Storage};
+use sreerag_devadasan::eval::Value;
+use sreerag_devadasan::parser::parse_sql;
+
+fn eval(sql: &str) -> Result<Value, String> {
+    let result = Database::new().execute_sql(&format!("VALUES ({})", sql)).map_err(|e| e.to_string())?;
+    Ok(result.rows[0][0].clone())
+}
+
+fn date(text: &str) -> Value {
+    Value::Date(Date::parse(text).unwrap())
+}
+
+#[test]
+fn values_parse_print_and_follow_the_calendar() {
+    assert_eq!(Date::parse("1970-01-01").unwrap().days(), 0);
+    assert_eq!(Date::parse("1969-12-31").unwrap().days(), -1);
+    assert_eq!(Date::parse("2000-03-01").unwrap().days() - Date::parse("2000-02-28").unwrap().days(), 2);
+    assert_eq!(Date::parse("2024-2-9").unwrap().to_string(), "2024-02-09");
+    for invalid in ["2023-02-29", "1900-02-29", "2024-13-01", "24-01-01", "2024-01-01x", ""] {
+        assert_eq!(Date::parse(invalid), None, "{}", invalid);
+    }
+    assert_eq!(Date::from_ymd(2024, 12, 31).unwrap().ordinal(), 366);
+    // 2021-01-03 was a Sunday in the last ISO week of 2020
+    let sunday = Date::parse("2021-01-03").unwrap();
+    assert_eq!((sunday.weekday(), sunday.iso_week()), (0, 53));
+    assert_eq!(Date::parse("2024-12-30").unwrap().iso_week(), 1);
+
+    assert_eq!(Time::parse("09:05").unwrap().to_string(), "09:05:00");
+    assert_eq!(Time::parse("23:59:59.250").unwrap().to_string(), "23:59:59.25");
+    for invalid in ["24:00:00", "9:05", "12:60", "12:00:00.1234567", "12:30.5"] {
+        assert_eq!(Time::parse(invalid), None, "{}", invalid);
+    }
+    let timestamp = Timestamp::parse("1969-12-31T23:00:00").unwrap();
+    assert_eq!(timestamp.micros(), -3_600_000_000);
+    assert_eq!(timestamp.to_string(), "1969-12-31 23:00:00");
+    assert_eq!(Timestamp::parse("2024-01-31").unwrap().to_string(), "2024-01-31 00:00:00");
+}
+
+#[test]
+fn literals_compare_extract_and_truncate() {
+    assert_eq!(eval("DATE '2024-01-31' + 1"), Ok(date("2024-02-01")));
+    assert_eq!(eval("DATE '2024-03-01' - DATE '2024-02-01'"), Ok(Value::Int(29)));
+    assert_eq!(eval("DATE '2024-01-31' < '2024-02-01'"), Ok(Value::Bool(true)));
+    assert_eq!(eval("'2024-02-01' < DATE '2024-01-31'"), Ok(Value::Bool(false)));
+    // A date is its midnight next to a timestamp
+    assert_eq!(eval("DATE '2024-01-31' = TIMESTAMP '2024-01-31 00:00'"), Ok(Value::Bool(true)));
+    assert_eq!(eval("TIME '12:00' > '09:30:15'"), Ok(Value::Bool(true)));
+    assert!(eval("DATE '2024-01-31' = 'soon'").unwrap_err().contains("is not a date"));
+    assert!(eval("DATE '2024-01-31' = TIME '12:00'").is_err());
+    assert!(eval("DATE '2024-02-30'").unwrap_err().contains("not a valid DATE"));
+
+    let ts = "TIMESTAMP '2024-08-14 13:45:30.5'";
+    let part = |field: &str| eval(&format!("EXTRACT({} FROM {})", field, ts));
+    assert_eq!(part("YEAR"), Ok(Value::Int(2024)));
+    assert_eq!(part("quarter"), Ok(Value::Int(3)));
+    assert_eq!(part("month"), Ok(Value::Int(8)));
+    assert_eq!(part("week"), Ok(Value::Int(33)));
+    assert_eq!(part("dow"), Ok(Value::Int(3)));
+    assert_eq!(part("doy"), Ok(Value::Int(227)));
+    assert_eq!(part("second"), Ok(Value::Int(30)));
+    assert_eq!(part("epoch"), Ok(Value::Int(1_723_643_130)));
+    assert_eq!(eval("date_part('hour', TIME '07:15')"), Ok(Value::Int(7)));
+    assert!(eval("date_part('year', TIME '07:15')").is_err());
+    assert!(eval("date_part('fortnight', DATE '2024-01-01')").is_err());
+
+    let trunc = |field: &str| eval(&format!("date_trunc('{}', {})", field, ts)).map(|value| format!("{:?}", value));
+    let timestamp = |text: &str| Ok(format!("{:?}", Value::Timestamp(Timestamp::parse(text).unwrap())));
+    assert_eq!(trunc("year"), timestamp("2024-01-01"));
+    assert_eq!(trunc("quarter"), timestamp("2024-07-01"));
+    assert_eq!(trunc("week"), timestamp("2024-08-12"));
+    assert_eq!(trunc("minute"), timestamp("2024-08-14 13:45"));
+    assert_eq!(eval("date_trunc('month', DATE '2024-08-14')"), Ok(date("2024-08-01")));
+    assert_eq!(eval("date_part('year', NULL)"), Ok(Value::Null));
+
+    // The literals and EXTRACT print back as SQL that parses to the same tree
+    let statement = parse_sql("SELECT id FROM t WHERE EXTRACT(YEAR FROM created) = 2024 AND day > DATE '2024-01-31'");
+    let printed = statement.unwrap().to_string();
+    assert_eq!(printed, "SELECT id FROM t WHERE date_part('year', created) = 2024 AND day > DATE '2024-01-31'");
+    assert_eq!(parse_sql(&printed).unwrap().to_string(), printed);
+}
+
+#[test]
+fn the_engine_stores_filters_and_sorts_dates() {
+    for storage in [Storage::Rows, Storage::Columnar] {
+        let mut db = Database::with_storage(storage);
+        db.execute_sql("CREATE TABLE orders (id INT, placed DATE, shipped TIMESTAMP)").unwrap();
+        let rows = "(1, '2024-03-05', '2024-03-06 10:00'), (2, '2023-12-31', NULL), (3, DATE '2024-01-15', '2024-01-15')";
+        db.execute_sql(&format!("INSERT INTO orders VALUES {}", rows)).unwrap();
+        assert!(db.execute_sql("INSERT INTO orders VALUES (4, 'yesterday', NULL)").is_err());
+        assert!(db.execute_sql("INSERT INTO orders VALUES (4, 20240101, NULL)").is_err());
+
+        let ids = |db: &mut Database, sql: &str| -> Vec<Value> {
+            db.execute_sql(sql).unwrap().rows.into_iter().map(|row| row[0].clone()).collect()
+        };
+        let sql = "SELECT id FROM orders WHERE placed >= '2024-01-01' ORDER BY placed";
+        assert_eq!(ids(&mut db, sql), [Value::Int(3), Value::Int(1)]);
+        let sql = "SELECT id FROM orders WHERE EXTRACT(month FROM shipped) = 3 OR placed < DATE '2024-01-01'";
+        assert_eq!(ids(&mut db, sql), [Value::Int(1), Value::Int(2)]);
+        let sql = "SELECT placed FROM orders WHERE shipped = placed";
+        assert_eq!(ids(&mut db, sql), [date("2024-01-15")]);
+        let result = db.execute_sql("SELECT shipped FROM orders ORDER BY shipped").unwrap();
+        let shown: Vec<String> = result.rows.iter().map(|row| format!("{:?}", row[0])).collect();
+        assert_eq!(shown[0], "Null");
+        assert_eq!(result.rows[2][0], Value::Timestamp(Timestamp::parse("2024-03-06 10:00").unwrap()));
+    }
+}
@@ -0,0 +1,82 @@
+// dead_code.rs — checks finding the tables and columns a workload never references
+// Author: Sreerag Devadasan
+
+use std::env;
+use std::fs;
+
+use sreerag_devadasan::dead_code::{analyze_workload, unused};
+use sreerag_devadasan::dependencies::{dependencies, ColumnReference};
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::schema_diff::Schema;
+
+const SCHEMA: &str = "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(20), nickname VARCHAR(20),
+                                          email VARCHAR(40));
+                      CREATE TABLE orders (id INT, user_id INT, total INT, legacy_code INT);
+                      CREATE TABLE audit_log (id INT, message VARCHAR(100))";
+
+fn reference(column: &str, tables: &[&str]) -> ColumnReference {
+    ColumnReference { column: column.to_string(), tables: tables.iter().map(|table| table.to_string()).collect() }
+}
+
+#[test]
+fn dependencies_resolve_columns_to_their_tables() {
+    let sql = "SELECT name FROM users u JOIN orders o ON o.user_id = u.id WHERE total > 10 ORDER BY name";
+    let found = dependencies(&parse_sql(sql).unwrap());
+    assert_eq!(found.tables, ["users", "orders"]);
+    let both = ["users", "orders"];
+    let expected = [reference("name", &both), reference("user_id", &["orders"]), reference("id", &["users"])];
+    assert_eq!(found.columns[..3], expected);
+    assert_eq!(found.columns[3..], [reference("total", &both)]);
+    assert!(found.uses_column("USERS", "Name") && !found.uses_column("users", "email"));
+
+    // Names bound by WITH and subquery aliases aren't tables
+    let found = dependencies(&parse_sql("WITH r AS (SELECT id FROM orders) SELECT id FROM r").unwrap());
+    assert_eq!((found.tables, found.columns), (vec!["orders".to_string()], vec![reference("id", &["orders"])]));
+    let found = dependencies(&parse_sql("SELECT total FROM (SELECT * FROM orders) AS s").unwrap());
+    assert_eq!(found.all_columns, ["orders"]);
+    assert!(found.columns.is_empty());
+
+    let found = dependencies(&parse_sql("UPDATE users AS u SET email = 'x' WHERE u.id = 1").unwrap());
+    assert_eq!(found.columns, [reference("email", &["users"]), reference("id", &["users"])]);
+    assert_eq!(dependencies(&parse_sql("INSERT INTO users VALUES (1, 'a', 'b', 'c')").unwrap()).all_columns, ["users"]);
+    let found = dependencies(&parse_sql("EXPLAIN DELETE FROM orders WHERE legacy_code = 3").unwrap());
+    assert_eq!(found.columns, [reference("legacy_code", &["orders"])]);
+    assert_eq!(dependencies(&parse_sql("DROP TABLE users").unwrap()), Default::default());
+}
+
+#[test]
+fn unreferenced_tables_and_columns_are_reported() {
+    let schema = Schema::from_sql(SCHEMA).unwrap();
+    let workload: Vec<_> = [
+        "SELECT name FROM users u JOIN orders o ON o.user_id = u.id WHERE o.total > 10",
+        "UPDATE users SET email = 'x' WHERE id = 1",
+        "WITH big AS (SELECT id, total FROM orders) SELECT id FROM big",
+    ]
+    .iter()
+    .map(|sql| parse_sql(sql).unwrap())
+    .collect();
+    let report = unused(&schema, &workload);
+    assert_eq!(report.tables, ["audit_log"]);
+    let columns: Vec<String> = report.columns.iter().map(|(table, column)| format!("{}.{}", table, column)).collect();
+    // users.id is read by both queries on users, orders.id by the CTE
+    assert_eq!(columns, ["orders.legacy_code", "users.nickname"]);
+    assert_eq!(report.statements, 3);
+
+    // A workload directory, with a statement that doesn't parse
+    let dir = env::temp_dir().join(format!("sreerag-workload-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.sql"), "SELECT * FROM audit_log;\nSELECT nickname FROM users;").unwrap();
+    fs::write(dir.join("b.sql"), "\nSELECT id FROM orders WHERE").unwrap();
+    fs::write(dir.join("notes.txt"), "SELECT legacy_code FROM orders").unwrap();
+    let report = analyze_workload(&dir, &schema).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    // orders is only named by the statement that failed
+    assert_eq!(report.tables, ["orders"]);
+    assert_eq!(report.columns, [("users".to_string(), "id".to_string()), ("users".into(), "name".into()),
+                                ("users".into(), "email".into())]);
+    assert_eq!(report.statements, 3);
+    assert!(report.failures[0].0.ends_with("b.sql:2"), "{:?}", report.failures);
+    assert!(report.to_string().ends_with("3 statements, 1 failed to parse: 1 unused tables, 3 unused columns \
+                                         (what the failed statements use is unknown, and may be listed)"));
+    assert!(report.to_json().starts_with(r#"{"statements":3,"failures":[{"location":"#));
+}
@@ -0,0 +1,106 @@
+// decimal.rs — checks exact DECIMAL values in the evaluator and the engine
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::analyzer::{analyze, Registry};
+use sreerag_devadasan::decimal::Decimal;
+use sreerag_devadasan::engine::{Database, EngineError, Storage};
+use sreerag_devadasan::eval::{EvalError, Value};
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::schema_diff::Schema;
+
+fn eval(sql: &str) -> Result<Value, EngineError> {
+    Ok(Database::new().execute_sql(&format!("VALUES ({})", sql))?.rows[0][0].clone())
+}
+
+fn decimal(text: &str) -> Value {
+    Value::Decimal(Decimal::parse(text).unwrap())
+}
+
+#[test]
+fn decimals_parse_print_and_compare_as_numbers() {
+    for text in ["0", "19.99", "-0.05", "100.000", "12345678901234567890.123456789012345678"] {
+        assert_eq!(Decimal::parse(text).unwrap().to_string(), text);
+    }
+    assert_eq!(Decimal::parse(".5").unwrap().to_string(), "0.5");
+    for invalid in ["", ".", "1.2.3", "1e5", "abc", "123456789012345678901234567890123456789"] {
+        assert_eq!(Decimal::parse(invalid), None, "{}", invalid);
+    }
+    let price = Decimal::parse("19.99").unwrap();
+    assert_eq!((price.mantissa(), price.scale(), price.precision()), (1999, 2, 4));
+    assert_eq!(Decimal::parse("0.05").unwrap().precision(), 2);
+
+    // Equal numbers are equal decimals whatever their scales
+    assert_eq!(Decimal::parse("1.5"), Decimal::parse("1.50"));
+    assert!(Decimal::parse("-2").unwrap() < Decimal::parse("-1.99").unwrap());
+    assert_eq!(Decimal::parse("2.675").unwrap().rescale(2).unwrap().to_string(), "2.68");
+    assert_eq!(Decimal::parse("-2.675").unwrap().rescale(2).unwrap().to_string(), "-2.68");
+    assert_eq!(Decimal::parse("123.45").unwrap().fit(3, 1), None);
+    assert_eq!(Decimal::parse("123.45").unwrap().fit(5, 1).unwrap().to_string(), "123.5");
+
+    for sql in ["SELECT price FROM t WHERE price > 9.99", "CREATE TABLE t (price DECIMAL(10,2))"] {
+        assert_eq!(parse_sql(sql).unwrap().to_string(), sql);
+    }
+    assert_eq!(parse_sql("CREATE TABLE t (n NUMERIC(5))").unwrap().to_string(), "CREATE TABLE t (n DECIMAL(5,0))");
+    assert!(parse_sql("CREATE TABLE t (n DECIMAL(39, 2))").is_err());
+    assert!(parse_sql("CREATE TABLE t (n DECIMAL(2, 3))").is_err());
+    assert!(parse_sql("SELECT 1. FROM t").is_err());
+}
+
+#[test]
+fn arithmetic_is_exact_and_scales_follow_the_operands() {
+    assert_eq!(eval("0.1 + 0.2").unwrap(), decimal("0.3"));
+    let Value::Decimal(sum) = eval("1.5 + 0.25").unwrap() else { panic!("not a decimal") };
+    assert_eq!((sum.to_string(), sum.scale()), ("1.75".to_string(), 2));
+    assert_eq!(eval("1.5 * 0.25").unwrap(), decimal("0.375"));
+    assert_eq!(eval("19.99 * 3").unwrap(), decimal("59.97"));
+    assert_eq!(eval("10 - 0.01").unwrap(), decimal("9.99"));
+    assert_eq!(eval("1.00 / 3").unwrap(), decimal("0.333333"));
+    assert_eq!(eval("2 / 3.0").unwrap(), decimal("0.666667"));
+    assert_eq!(eval("-1.5").unwrap(), decimal("-1.5"));
+    assert_eq!(eval("0.1 + NULL").unwrap(), Value::Null);
+    assert_eq!(eval("1.50 = 1.5").unwrap(), Value::Bool(true));
+    assert_eq!(eval("2 > 1.99").unwrap(), Value::Bool(true));
+    assert_eq!(eval("'3.10' = 3.1").unwrap(), Value::Bool(true));
+    assert!(matches!(eval("1.5 / 0"), Err(EngineError::Eval(EvalError::DivisionByZero))));
+    let big = "99999999999999999999999999999999999999";
+    assert!(matches!(eval(&format!("{}.0 + 1", big)), Err(EngineError::Eval(EvalError::Overflow))));
+    assert!(matches!(eval("1.5 + 'x'"), Err(EngineError::Eval(EvalError::TypeMismatch(_)))));
+}
+
+#[test]
+fn decimal_columns_round_to_their_scale_and_sort_as_numbers() {
+    for storage in [Storage::Rows, Storage::Columnar] {
+        let mut db = Database::with_storage(storage);
+        db.execute_sql("CREATE TABLE items (id INT, price DECIMAL(5,2))").unwrap();
+        db.execute_sql("INSERT INTO items VALUES (1, 19.99), (2, 5), (3, '0.125'), (4, NULL), (5, 100.5)").unwrap();
+        let ids = db.execute_sql("SELECT id FROM items ORDER BY price").unwrap().rows;
+        assert_eq!(ids.concat(), [4, 3, 2, 1, 5].map(Value::Int), "{:?}", storage);
+        let rows = db.execute_sql("SELECT id, price FROM items WHERE price > 9.99 ORDER BY price").unwrap().rows;
+        let expected = [vec![Value::Int(1), decimal("19.99")], vec![Value::Int(5), decimal("100.50")]];
+        assert_eq!(rows, expected, "{:?}", storage);
+        // 0.125 rounds half away from zero, to 0.13
+        let rows = db.execute_sql("SELECT id FROM items WHERE price = 0.13").unwrap().rows;
+        assert_eq!(rows, [vec![Value::Int(3)]], "{:?}", storage);
+        let rows = db.execute_sql("SELECT id FROM items WHERE price * 2 = 10 OR price - 0.01 = 19.98").unwrap().rows;
+        assert_eq!(rows, [vec![Value::Int(1)], vec![Value::Int(2)]], "{:?}", storage);
+
+        let error = db.execute_sql("INSERT INTO items VALUES (6, 1000)").unwrap_err();
+        assert_eq!(error.to_string(), "Value for column price is out of range for DECIMAL(5,2)");
+        for value in ["'cheap'", "TRUE"] {
+            let sql = format!("INSERT INTO items VALUES (6, {})", value);
+            assert!(matches!(db.execute_sql(&sql), Err(EngineError::TypeMismatch { .. })), "{}", value);
+        }
+    }
+}
+
+#[test]
+fn the_analyzer_types_decimal_expressions() {
+    let schema = Schema::from_sql("CREATE TABLE items (id INT, price DECIMAL(5,2), name VARCHAR(10))").unwrap();
+    let problems = |sql: &str| -> Vec<String> {
+        let problems = analyze(&parse_sql(sql).unwrap(), &Registry::new(), Some(&schema));
+        problems.iter().map(ToString::to_string).collect()
+    };
+    assert!(problems("SELECT id FROM items WHERE price * 2 - 0.5 > 9.99 AND -price < id").is_empty());
+    assert!(problems("SELECT id FROM items WHERE price = '19.99'").is_empty());
+    assert_eq!(problems("SELECT id FROM items WHERE price + name > 1"), ["+ can't be applied to decimal and text"]);
+}
@@ -0,0 +1,45 @@
+// dsl.rs — checks the typed query builder against the printer
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::dsl::{from, insert_into, Table};
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::sql_table;
+
+sql_table! {
+    pub struct Users = "users" { id: u64, name: String, active: bool, manager_id: u64 }
+}
+
+#[test]
+fn tables_know_their_columns() {
+    assert_eq!(Users::NAME, "users");
+    assert_eq!(Users::COLUMNS, ["id", "name", "active", "manager_id"]);
+    assert_eq!(Users::manager_id().name(), "manager_id");
+}
+
+#[test]
+fn select_lowers_to_the_parsed_statement() {
+    let query = from::<Users>()
+        .select(Users::id())
+        .select(Users::name())
+        .filter(Users::active().eq(true))
+        .filter(Users::name().like("A%").or(Users::id().lt(Users::manager_id())))
+        .order_by(Users::name())
+        .limit(10);
+    let sql = "SELECT id, name FROM users WHERE active = TRUE AND (name LIKE 'A%' OR id < manager_id) \
+               ORDER BY name LIMIT 10";
+    assert_eq!(query.to_sql(Dialect::Generic), sql);
+    assert_eq!(query.to_statement(), parse_sql(sql).unwrap());
+}
+
+#[test]
+fn queries_print_in_any_dialect() {
+    let query = from::<Users>().filter(!Users::id().ne(1)).limit(5);
+    assert_eq!(query.to_sql(Dialect::SqlServer), "SELECT TOP 5 * FROM users WHERE NOT (id != 1)");
+}
+
+#[test]
+fn insert_takes_typed_values() {
+    let insert = insert_into::<Users>().value(Users::id(), 7u64).value(Users::name(), "Ann").value(Users::active(), false);
+    assert_eq!(insert.to_sql(Dialect::Generic), "INSERT INTO users (id, name, active) VALUES (7, 'Ann', FALSE)");
+}
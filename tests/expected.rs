@@ -0,0 +1,122 @@
+// expected.rs — checks the sets of tokens the parser expects next, after prefixes and in errors
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::parser::{expected_tokens, parse_sql};
+use sreerag_devadasan::tokenizer::{Keyword, ParseError, TokenKind};
+
+fn expected(prefix: &str) -> Vec<TokenKind> {
+    expected_tokens(prefix, Dialect::Generic)
+}
+
+const OPERAND: [TokenKind; 10] = [
+    TokenKind::Identifier,
+    TokenKind::Number,
+    TokenKind::String,
+    TokenKind::Placeholder,
+    TokenKind::Keyword(Keyword::True),
+    TokenKind::Keyword(Keyword::False),
+    TokenKind::Keyword(Keyword::Null),
+    TokenKind::Keyword(Keyword::Not),
+    TokenKind::Minus,
+    TokenKind::LeftParentheses,
+];
+
+const TYPES: [TokenKind; 8] = [
+    TokenKind::Keyword(Keyword::Int),
+    TokenKind::Keyword(Keyword::Bool),
+    TokenKind::Keyword(Keyword::Varchar),
+    TokenKind::Word("DATE"),
+    TokenKind::Word("TIME"),
+    TokenKind::Word("TIMESTAMP"),
+    TokenKind::Word("DECIMAL"),
+    TokenKind::Word("NUMERIC"),
+];
+
+#[test]
+fn a_statement_starts_with_one_of_its_keywords() {
+    let start = expected("");
+    for kind in [
+        TokenKind::Keyword(Keyword::Select),
+        TokenKind::Keyword(Keyword::With),
+        TokenKind::Keyword(Keyword::Insert),
+        TokenKind::Word("UPDATE"),
+        TokenKind::Word("DELETE"),
+        TokenKind::Keyword(Keyword::Create),
+        TokenKind::Word("EXPLAIN"),
+        TokenKind::LeftParentheses,
+    ] {
+        assert!(start.contains(&kind), "{:?} in {:?}", kind, start);
+    }
+    assert!(!start.contains(&TokenKind::Eof) && !start.contains(&TokenKind::Identifier));
+    assert_eq!(expected("DROP"), [TokenKind::Keyword(Keyword::Table)]);
+    assert_eq!(expected("SELECT a FROM t ORDER"), [TokenKind::Keyword(Keyword::By)]);
+    assert_eq!(expected("SELECT a FROM t LIMIT"), [TokenKind::Number]);
+}
+
+#[test]
+fn an_operand_may_be_followed_by_any_operator_or_what_ends_the_clause() {
+    assert_eq!(expected("SELECT a FROM t WHERE"), OPERAND);
+    assert_eq!(expected("SELECT a FROM t WHERE a ="), OPERAND);
+    let after = expected("SELECT a FROM t WHERE a");
+    for kind in [
+        TokenKind::Keyword(Keyword::And),
+        TokenKind::Keyword(Keyword::Or),
+        TokenKind::Equal,
+        TokenKind::LessThanOrEqual,
+        TokenKind::Concat,
+        TokenKind::DoubleColon,
+        TokenKind::Word("LIKE"),
+        TokenKind::Word("BETWEEN"),
+        TokenKind::Keyword(Keyword::Not),
+        TokenKind::Word("COLLATE"),
+        TokenKind::Keyword(Keyword::Order),
+        TokenKind::Keyword(Keyword::Limit),
+        TokenKind::Semicolon,
+        TokenKind::Eof,
+    ] {
+        assert!(after.contains(&kind), "{:?} in {:?}", kind, after);
+    }
+    // ILIKE only where the dialect has it
+    assert!(!after.contains(&TokenKind::Word("ILIKE")));
+    assert!(expected_tokens("SELECT a FROM t WHERE a", Dialect::Postgres).contains(&TokenKind::Word("ILIKE")));
+
+    assert_eq!(expected("SELECT a FROM t WHERE a NOT"), [TokenKind::Word("BETWEEN")]);
+    assert!(expected("SELECT a FROM t WHERE a BETWEEN 1").contains(&TokenKind::Keyword(Keyword::And)));
+    assert_eq!(expected("SELECT a FROM t WHERE a COLLATE"), [TokenKind::Identifier]);
+    // Inside parentheses only the closing one ends it
+    let grouped = expected("SELECT a FROM t WHERE (a");
+    assert!(grouped.contains(&TokenKind::RightParentheses) && !grouped.contains(&TokenKind::Eof));
+}
+
+#[test]
+fn types_are_expected_where_one_is_written() {
+    assert_eq!(expected("SELECT a::"), TYPES);
+    assert_eq!(expected("SELECT CAST(a AS"), TYPES);
+    assert_eq!(expected("CREATE TABLE t (a"), TYPES);
+    assert_eq!(expected("SELECT a::VARCHAR"), [TokenKind::LeftParentheses]);
+    let column = expected("CREATE TABLE t (a INT");
+    for kind in [TokenKind::Keyword(Keyword::Not), TokenKind::Keyword(Keyword::Primary), TokenKind::Comma] {
+        assert!(column.contains(&kind), "{:?} in {:?}", kind, column);
+    }
+}
+
+#[test]
+fn invalid_prefixes_expect_nothing_and_errors_carry_the_same_set() {
+    assert_eq!(expected("SELECT a FROM FROM t"), []);
+    assert_eq!(expected("SELECT a FROM t WHERE a NOT x"), []);
+
+    for (sql, prefix) in [
+        ("CREATE TABLE t (a FLOAT)", "CREATE TABLE t (a"),
+        ("SELECT a FROM t ORDER a", "SELECT a FROM t ORDER"),
+        ("SELECT a FROM t WHERE a NOT 1", "SELECT a FROM t WHERE a NOT"),
+        ("SELECT a FROM t WHERE = 1", "SELECT a FROM t WHERE"),
+    ] {
+        let Err(ParseError::Unexpected { expected: in_error, .. }) = parse_sql(sql) else {
+            panic!("{} should fail on an unexpected token", sql);
+        };
+        assert_eq!(in_error, expected(prefix), "{}", sql);
+    }
+    let error = parse_sql("SELECT a FROM t WHERE a NOT 1").unwrap_err();
+    assert_eq!(error.to_string(), "Expected BETWEEN, found number 1");
+}
@@ -0,0 +1,83 @@
+// extension.rs — checks custom statements parsed by registered handlers
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::ast::{Expression, Ident, Statement};
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::extension::{CustomStatement, Extension, Extensions};
+use sreerag_devadasan::hash::hash_statement;
+use sreerag_devadasan::parser::SQLParser;
+use sreerag_devadasan::session::ParserSession;
+use sreerag_devadasan::tokenizer::{Keyword, ParseError, TokenKind};
+
+/// `ANALYZE MYEXT table [WHERE condition]`
+#[derive(Debug, Clone, PartialEq)]
+struct Analyze {
+    table: Ident,
+    condition: Option<Expression>,
+}
+
+impl CustomStatement for Analyze {
+    fn kind(&self) -> &'static str {
+        "ANALYZE MYEXT"
+    }
+
+    fn to_sql(&self, dialect: Dialect) -> String {
+        let mut sql = format!("ANALYZE MYEXT {}", self.table.to_sql(dialect));
+        if let Some(condition) = &self.condition {
+            sql.push_str(&format!(" WHERE {}", condition.to_sql(dialect)));
+        }
+        sql
+    }
+}
+
+fn analyze(parser: &mut SQLParser) -> Result<Option<Extension>, ParseError> {
+    parser.expect_word(&["ANALYZE"])?;
+    // Plain ANALYZE isn't ours
+    if !parser.eat(TokenKind::Word("MYEXT")) {
+        return Ok(None);
+    }
+    let table = parser.expect_identifier()?;
+    let mut condition = None;
+    if parser.eat(TokenKind::Keyword(Keyword::Where)) {
+        condition = Some(parser.parse_expression()?);
+    }
+    Ok(Some(Extension::new(Analyze { table, condition })))
+}
+
+fn session() -> ParserSession {
+    let mut extensions = Extensions::new();
+    extensions.register("analyze", analyze);
+    // Registered for a builtin keyword, always deferring to the grammar
+    extensions.register("SELECT", |_| Ok(None));
+    ParserSession::default().with_extensions(extensions)
+}
+
+#[test]
+fn handlers_parse_their_statements() {
+    let session = session();
+    let statement = session.parse("analyze myext orders WHERE total > 10;").unwrap();
+    assert_eq!(statement.kind(), "ANALYZE MYEXT");
+    assert_eq!(statement.to_sql(Dialect::Generic), "ANALYZE MYEXT orders WHERE total > 10");
+
+    let Statement::Custom(custom) = &statement else { panic!("expected a custom statement, got {:?}", statement) };
+    let analyze = custom.downcast_ref::<Analyze>().unwrap();
+    assert_eq!(analyze.table, "orders");
+    assert_eq!(statement.clone(), statement);
+    assert_ne!(statement, session.parse("ANALYZE MYEXT orders").unwrap());
+    let reparsed = session.parse(&statement.to_sql(Dialect::Generic)).unwrap();
+    assert_eq!(hash_statement(&statement), hash_statement(&reparsed));
+    assert!(format!("{:?}", statement).starts_with("Custom(Analyze { table: \"orders\""));
+
+    // Errors inside a handler are the statement's errors
+    assert!(session.parse("ANALYZE MYEXT 42").is_err());
+}
+
+#[test]
+fn handlers_can_defer_to_the_grammar() {
+    let session = session();
+    assert_eq!(session.parse("SELECT a FROM t").unwrap().kind(), "SELECT");
+    let error = session.parse("ANALYZE orders").unwrap_err();
+    assert!(matches!(error, ParseError::UnknownStartOfStatement(_)), "{:?}", error);
+    // Without the extension ANALYZE isn't a statement at all
+    assert!(ParserSession::default().parse("ANALYZE MYEXT orders").is_err());
+}
@@ -0,0 +1,49 @@
+// from_row.rs — checks mapping engine result rows onto structs
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::engine::{Database, EngineError};
+use sreerag_devadasan::impl_from_row;
+
+#[derive(Debug, PartialEq)]
+struct User {
+    id: i64,
+    name: String,
+    email: Option<String>,
+}
+
+impl_from_row!(User { id, name, email });
+
+/// Reads `name` as an integer, which it isn't.
+struct BadName {
+    name: i64,
+}
+
+impl_from_row!(BadName { name });
+
+fn database() -> Database {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(20) NOT NULL, email VARCHAR(40))").unwrap();
+    db.execute_sql("INSERT INTO users VALUES (1, 'Ann', 'ann@example.com'), (2, 'Bo', NULL)").unwrap();
+    db
+}
+
+#[test]
+fn query_maps_rows_by_column_name() {
+    let users: Vec<User> = database().query("SELECT email, name, id FROM users ORDER BY id").unwrap();
+    assert_eq!(
+        users,
+        [
+            User { id: 1, name: "Ann".to_string(), email: Some("ann@example.com".to_string()) },
+            User { id: 2, name: "Bo".to_string(), email: None },
+        ]
+    );
+}
+
+#[test]
+fn missing_columns_and_wrong_types_are_errors() {
+    let mut db = database();
+    assert!(matches!(db.query::<User>("SELECT id, name FROM users"), Err(EngineError::UnknownColumn(c)) if c == "email"));
+
+    let error = db.query::<BadName>("SELECT name FROM users").map(|rows| rows[0].name).unwrap_err();
+    assert_eq!(error.to_string(), "Column name holds String(\"Ann\"), which isn't an integer");
+}
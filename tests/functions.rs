@@ -0,0 +1,65 @@
+// functions.rs — checks functions registered with the engine from Rust
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::analyzer::{analyze, SqlType};
+use sreerag_devadasan::engine::{Database, EngineError, Storage};
+use sreerag_devadasan::eval::{EvalError, Value};
+use sreerag_devadasan::parser::parse_sql;
+
+fn database(storage: Storage) -> Database {
+    let mut db = Database::with_storage(storage);
+    db.register_scalar("reverse", &[SqlType::Text], SqlType::Text, |args| match &args[0] {
+        Value::String(s) => Ok(Value::String(s.chars().rev().collect())),
+        other => Ok(other.clone()),
+    });
+    db.execute_sql("CREATE TABLE users (id INT, name VARCHAR(10))").unwrap();
+    let insert = "INSERT INTO users VALUES (1, 'ann'), (2, reverse('bob')), (3, reverse('evan')), (4, NULL)";
+    db.execute_sql(insert).unwrap();
+    db
+}
+
+#[test]
+fn scalar_functions_are_callable_from_sql() {
+    for storage in [Storage::Rows, Storage::Columnar] {
+        let mut db = database(storage);
+        let sql = "SELECT id FROM users WHERE reverse(name) = 'evan' OR REVERSE(name) = 'nna'";
+        let result = db.execute_sql(sql).unwrap();
+        assert_eq!(result.rows, [[Value::Int(1)], [Value::Int(3)]]);
+        // The arguments are checked against the declaration before the closure runs
+        let error = db.execute_sql("SELECT id FROM users WHERE reverse(id) = 'a'").unwrap_err();
+        assert_eq!(error.to_string(), "Type mismatch: argument 1 of reverse should be text, but is integer");
+        assert!(db.execute_sql("SELECT id FROM users WHERE reverse(name, name) = 'a'").is_err());
+        assert!(matches!(
+            db.execute_sql("SELECT id FROM users WHERE shout(name) = 'A'"),
+            Err(EngineError::Eval(EvalError::UnknownFunction(_)))
+        ));
+    }
+
+    // Transactions see the functions of the database they began on
+    let db = database(Storage::Rows);
+    let mut transaction = db.begin();
+    assert_eq!(transaction.execute_sql("VALUES (reverse('ab'))").unwrap().rows, [[Value::String("ba".to_string())]]);
+}
+
+#[test]
+fn aggregates_fold_a_column_and_signatures_reach_the_analyzer() {
+    let mut db = database(Storage::Rows);
+    db.register_aggregate("longest", &[SqlType::Text], SqlType::Integer, Value::Int(0), |longest, args| {
+        match (longest, &args[0]) {
+            (Value::Int(n), Value::String(s)) => Ok(Value::Int(n.max(s.len() as i64))),
+            (longest, _) => Ok(longest),
+        }
+    });
+    assert_eq!(db.aggregate("longest", "users", "name").unwrap(), Value::Int(4));
+    assert!(db.aggregate("longest", "users", "id").is_err());
+    assert!(db.aggregate("reverse", "users", "name").is_err());
+    assert!(db.execute_sql("SELECT id FROM users WHERE longest(name) > 1").is_err());
+
+    let registry = db.registry();
+    let check = |sql: &str| analyze(&parse_sql(sql).unwrap(), &registry, None);
+    assert_eq!(check("SELECT id FROM users WHERE reverse(name) = 'a' AND lower(name) = 'a'"), []);
+    let problems = check("SELECT id FROM users WHERE reverse(1) = 'a' AND longest(longest(name)) > 1");
+    let messages: Vec<String> = problems.iter().map(ToString::to_string).collect();
+    assert_eq!(messages.len(), 3, "{:?}", messages);
+    assert_eq!(messages[0], "Argument 1 of reverse should be text, but is integer");
+}
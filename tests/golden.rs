@@ -0,0 +1,84 @@
+// golden.rs — snapshot tests of parser output
+// Author: Sreerag Devadasan
+//
+// Every `tests/golden/*.sql` file is split into statements and parsed; the
+// AST (or error) of each statement is compared with the matching `.out`
+// file. Run with UPDATE_GOLDENS=1 to (re)write the `.out` files after an
+// intended change, then review the diff.
+//
+// A first line of the form `-- dialect: mysql` selects the dialect.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use sreerag_devadasan::batch::split_statements;
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::parser::parse_sql_with_dialect;
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+/// Splits off the `-- dialect:` header, if there is one.
+fn dialect_of(script: &str) -> (Dialect, &str) {
+    let (first, rest) = script.split_once('\n').unwrap_or((script, ""));
+    match first.trim().strip_prefix("-- dialect:") {
+        Some(name) => {
+            let dialect = Dialect::from_name(name.trim()).unwrap_or_else(|| panic!("unknown dialect {}", name));
+            (dialect, rest)
+        }
+        None => (Dialect::Generic, script),
+    }
+}
+
+/// The text stored in a `.out` file for one `.sql` file.
+fn render(script: &str) -> String {
+    let (dialect, body) = dialect_of(script);
+    let mut out = String::new();
+    for (_, statement) in split_statements(body) {
+        writeln!(out, "-- {}", statement.split_whitespace().collect::<Vec<_>>().join(" ")).unwrap();
+        match parse_sql_with_dialect(statement, dialect) {
+            Ok(ast) => writeln!(out, "{:#?}", ast).unwrap(),
+            Err(e) => writeln!(out, "error: {}", e).unwrap(),
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[test]
+fn golden_files() {
+    let update = env::var_os("UPDATE_GOLDENS").is_some();
+    let mut inputs: Vec<PathBuf> = fs::read_dir(corpus_dir())
+        .expect("tests/golden exists")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "sql"))
+        .collect();
+    inputs.sort();
+    assert!(!inputs.is_empty(), "no .sql files in tests/golden");
+
+    let mut failures = Vec::new();
+    for input in &inputs {
+        let actual = render(&fs::read_to_string(input).unwrap());
+        let golden = input.with_extension("out");
+        if update {
+            fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&golden) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{} differs from {}\n--- expected\n{}\n--- actual\n{}",
+                input.display(),
+                golden.display(),
+                expected,
+                actual
+            )),
+            Err(_) => failures.push(format!("{} has no {} (run with UPDATE_GOLDENS=1)", input.display(), golden.display())),
+        }
+    }
+
+    assert!(failures.is_empty(), "{} golden file(s) failed:\n\n{}", failures.len(), failures.join("\n\n"));
+}
@@ -0,0 +1,5 @@
+COPY orders (id, total) FROM '/data/orders.csv' WITH (FORMAT csv, HEADER, DELIMITER ',', NULL '');
+COPY orders TO STDOUT;
+LOAD DATA LOCAL INFILE '/tmp/orders.csv' REPLACE INTO TABLE orders FIELDS TERMINATED BY ',' OPTIONALLY ENCLOSED BY '"' LINES TERMINATED BY '\n' IGNORE 1 LINES (id, total);
+LOAD DATA INFILE 'x.tsv' INTO TABLE orders;
+COPY orders FROM 'x' WITH CSV;
@@ -0,0 +1,11 @@
+SELECT id FROM a UNION SELECT id FROM b;
+SELECT id FROM a UNION ALL SELECT id FROM b EXCEPT SELECT id FROM c;
+SELECT id FROM a UNION SELECT id FROM b INTERSECT SELECT id FROM c;
+(SELECT id FROM a ORDER BY id LIMIT 5) UNION DISTINCT (SELECT id FROM b);
+WITH recent AS (SELECT id FROM orders WHERE id > 100) SELECT id FROM recent;
+WITH a AS (SELECT x FROM t), b(y) AS (SELECT x FROM a) SELECT y FROM b;
+WITH RECURSIVE tree(id, parent) AS (SELECT id, parent FROM nodes WHERE parent = 0 UNION ALL SELECT id, parent FROM nodes JOIN tree ON nodes.parent = tree.id) SELECT id FROM tree;
+WITH RECURSIVE reach(id) AS (SELECT dst FROM edges WHERE src = 1 UNION SELECT dst FROM edges JOIN reach ON edges.src = reach.id WHERE dst != 1) SELECT id FROM reach;
+SELECT v FROM (VALUES (1) UNION ALL VALUES (2)) AS t(v);
+WITH x AS SELECT a FROM t SELECT a FROM x;
+SELECT id FROM a UNION;
@@ -0,0 +1,12 @@
+GRANT SELECT, INSERT ON orders TO analyst, reporting WITH GRANT OPTION;
+GRANT ALL PRIVILEGES ON TABLE orders TO admin;
+REVOKE UPDATE, DELETE ON orders FROM analyst;
+CREATE ROLE app WITH LOGIN NOSUPERUSER PASSWORD 'secret';
+ALTER ROLE app NOLOGIN PASSWORD NULL;
+ALTER ROLE app;
+GRANT UPDATE (price, note), REFERENCES (id) ON orders TO clerk;
+GRANT TRUNCATE, TRIGGER ON orders TO admin;
+GRANT USAGE ON SCHEMA sales TO analyst;
+REVOKE EXECUTE ON FUNCTION refresh FROM analyst;
+GRANT analyst, reporting TO bob WITH ADMIN OPTION;
+REVOKE reporting FROM bob;
@@ -0,0 +1,4 @@
+CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(64) NOT NULL, active BOOL);
+CREATE TABLE orders (id INT, total INT CHECK (total >= 0));
+DROP TABLE users;
+DROP TABLE IF EXISTS orders;
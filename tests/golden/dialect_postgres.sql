@@ -0,0 +1,2 @@
+-- dialect: postgres
+SELECT "Order" FROM "My Table" WHERE `a` = 1;
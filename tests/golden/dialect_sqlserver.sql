@@ -0,0 +1,2 @@
+-- dialect: sqlserver
+SELECT TOP 5 [first name] FROM [users] WHERE "x" = 'y';
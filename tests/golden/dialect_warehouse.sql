@@ -0,0 +1,12 @@
+-- dialect: warehouse
+SELECT id, ts FROM events QUALIFY row_number() OVER (PARTITION BY id ORDER BY ts DESC) = 1;
+SELECT id FROM events WHERE kind = 'click' QUALIFY rank() OVER (ORDER BY ts, id ASC) <= 3 ORDER BY id;
+SELECT region FROM sales GROUP BY region QUALIFY count() OVER () > 1;
+SELECT id FROM users WHERE name ILIKE 'a%' OR "Name" LIKE '_b%';
+SELECT * EXCLUDE (password, salt) FROM users;
+SELECT * EXCLUDE password FROM users;
+SELECT * REPLACE (price * 2 AS price, upper(name) AS name) FROM items;
+SELECT * EXCLUDE (id) REPLACE (lower(email) AS email) FROM users u;
+SELECT id FROM events QUALIFY;
+SELECT * REPLACE (price) FROM items;
+SELECT id FROM events QUALIFY row_number() OVER (PARTITION id) = 1;
@@ -0,0 +1,7 @@
+-- dialect: postgres
+VALUES ($$it's$$, $tag$a; b$tag$, $$$$);
+SELECT id FROM t WHERE note = $x$has $$ inside$x$ AND code = $1;
+INSERT INTO t VALUES ($q$line one
+line two$q$, $_$ -- not a comment $_$);
+CREATE TRIGGER stamp BEFORE INSERT ON t FOR EACH ROW BEGIN SET NEW.note = $$END;$$; END;
+SELECT id FROM t WHERE note = $$unterminated;
@@ -0,0 +1,6 @@
+SELECT id FROM;
+SELECT id FROM users WHERE;
+CREATE TABLE t (a FLOAT);
+CREATE TABLE t (a VARCHAR);
+UPDATE users;
+DROP TABLE IF users;
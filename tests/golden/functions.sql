@@ -0,0 +1,17 @@
+-- dialect: postgres
+CREATE FUNCTION add(a INT, b INT) RETURNS INT AS $$ SELECT a + b; $$ LANGUAGE sql;
+CREATE OR REPLACE FUNCTION touch() RETURNS BOOL LANGUAGE plpgsql AS $body$
+BEGIN
+  UPDATE t SET at = now(); -- ';' and $$ inside the body
+  RETURN true;
+END;
+$body$;
+CREATE FUNCTION label(VARCHAR(10)) RETURNS VARCHAR(20) AS 'SELECT ''<'' || $1' LANGUAGE 'sql';
+CREATE PROCEDURE cleanup() LANGUAGE sql AS $$ DELETE FROM logs; $$;
+CREATE FUNCTION audit() RETURNS trigger AS $$ BEGIN RETURN NEW; END; $$ LANGUAGE plpgsql;
+CREATE FUNCTION evens(n INT) RETURNS SETOF INT AS $$ SELECT n $$ LANGUAGE sql;
+CREATE FUNCTION stamp(x TEXT, public.money, NUMERIC(10)) RETURNS TIMESTAMP WITH TIME ZONE AS $$ SELECT now() $$;
+CREATE FUNCTION pad(s character varying(5), "my type") RETURNS text LANGUAGE sql AS $$ SELECT s $$;
+CREATE FUNCTION f() RETURNS INT LANGUAGE sql;
+CREATE OR REPLACE TABLE t (id INT);
+CREATE FUNCTION f() RETURNS INT AS $$ SELECT 1 LANGUAGE sql
@@ -0,0 +1,10 @@
+SELECT region FROM sales GROUP BY region;
+SELECT region, product FROM sales WHERE year = 2024 GROUP BY region, product ORDER BY region;
+SELECT region, product FROM sales GROUP BY ROLLUP(region, product);
+SELECT region, product FROM sales GROUP BY CUBE(region, product) LIMIT 10;
+SELECT region, product FROM sales GROUP BY GROUPING SETS ((region), (product), ());
+SELECT region, product FROM sales GROUP BY year, GROUPING SETS (region, (region, product));
+SELECT region FROM sales s GROUP BY s.region;
+SELECT region FROM sales GROUP region;
+SELECT region FROM sales GROUP BY ROLLUP region;
+SELECT region FROM sales GROUP BY GROUPING SETS (region;
@@ -0,0 +1,7 @@
+SELECT /*+ INDEX(orders orders_user_idx) NO_MERGE */ id FROM orders;
+SELECT /*+ LEADING(a, b) USE_NL(b) */ /*+ PARALLEL(4) */ id FROM a JOIN b ON a.id = b.id;
+SELECT STRAIGHT_JOIN id FROM a JOIN b ON a.id = b.id;
+SELECT id /*+ not a hint */ FROM orders -- trailing comment
+WHERE id > 1;
+SELECT /* plain comment */ id FROM orders;
+SELECT id FROM orders /* unterminated
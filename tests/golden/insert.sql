@@ -0,0 +1,3 @@
+INSERT INTO users VALUES (1, 'ada', TRUE);
+INSERT INTO users (id, name) VALUES (2, 'grace'), (3, NULL);
+INSERT INTO users VALUES (-1);
@@ -0,0 +1,3 @@
+INSERT INTO archive (id, total) SELECT id, total FROM orders WHERE total > 100;
+INSERT INTO archive WITH old AS (SELECT id FROM orders) SELECT id FROM old UNION SELECT id FROM returns;
+INSERT INTO archive (id) DELETE FROM orders;
@@ -0,0 +1,11 @@
+SELECT a FROM t1, t2;
+SELECT a FROM t1 JOIN t2 ON t1.id = t2.id;
+SELECT a FROM t1 AS x LEFT OUTER JOIN t2 y ON x.id = y.id RIGHT JOIN t3 ON TRUE;
+SELECT a FROM t1 INNER JOIN t2 ON a = b FULL JOIN t3 ON b = c;
+SELECT a FROM users u CROSS JOIN LATERAL (SELECT total FROM orders WHERE user_id = u.id) o;
+SELECT n FROM generate_series(1, 10) AS g(n);
+SELECT x FROM t, LATERAL unnest(t.tags) AS tag(x);
+SELECT x FROM now();
+SELECT a FROM t1 JOIN t2;
+SELECT a FROM t1 CROSS t2;
+SELECT a FROM t1 CROSS JOIN LATERAL t2;
@@ -0,0 +1,6 @@
+SELECT name FROM users WHERE LEFT(name, 3) = 'Ada' AND RIGHT(name, 1) != 'x';
+SELECT name FROM users WHERE REPLACE(email, '@', ' at ') LIKE '%example%';
+SELECT * FROM users u LEFT JOIN orders o ON LEFT(u.code, 2) = o.prefix RIGHT JOIN items i ON i.id = o.item_id;
+INSERT INTO codes VALUES (LEFT('abcdef', 3), RIGHT('abcdef', 3));
+SELECT * FROM users left;
+SELECT LEFT(name, 3) FROM users;
@@ -0,0 +1,5 @@
+SELECT id FROM users LIMIT 10;
+SELECT id FROM users LIMIT 5, 10;
+SELECT id FROM users ORDER BY id LIMIT 10 OFFSET 20;
+SELECT id FROM users OFFSET 20 ROWS FETCH NEXT 10 ROWS ONLY;
+SELECT TOP (3) id FROM users;
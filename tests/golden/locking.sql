@@ -0,0 +1,10 @@
+SELECT id FROM accounts WHERE id = 1 FOR UPDATE;
+SELECT id FROM accounts ORDER BY id LIMIT 10 FOR UPDATE SKIP LOCKED;
+SELECT id FROM accounts a JOIN owners o ON a.owner = o.id FOR UPDATE OF a, o NOWAIT;
+SELECT id FROM accounts FOR SHARE FOR NO KEY UPDATE OF accounts;
+SELECT id FROM accounts FOR KEY SHARE;
+SELECT id FROM events TABLESAMPLE SYSTEM (10);
+SELECT id FROM events AS e TABLESAMPLE bernoulli (2) REPEATABLE (42) WHERE id > 5;
+SELECT id FROM accounts FOR;
+SELECT id FROM accounts FOR UPDATE SKIP;
+SELECT id FROM events TABLESAMPLE SYSTEM 10;
@@ -0,0 +1,6 @@
+MERGE INTO dim_customer AS t USING staging s ON t.id = s.id
+WHEN MATCHED AND s.deleted = TRUE THEN DELETE
+WHEN MATCHED THEN UPDATE SET name = s.name, version = t.version + 1
+WHEN NOT MATCHED THEN INSERT (id, name, version) VALUES (s.id, s.name, 1);
+MERGE INTO t USING (SELECT id, v FROM src WHERE v > 10) AS s ON t.id = s.id WHEN NOT MATCHED THEN INSERT VALUES (s.id, s.v);
+MERGE INTO t USING s ON t.id = s.id;
@@ -0,0 +1,9 @@
+-- dialect: warehouse
+SELECT * FROM sales PIVOT (SUM(amount) FOR month IN ('Jan', 'Feb'));
+SELECT * FROM sales s PIVOT (sum(amount) FOR month IN ('Jan', 'Feb', 'Mar')) AS p WHERE p.region = 'EU';
+SELECT * FROM monthly UNPIVOT (amount FOR month IN (jan, feb, mar));
+SELECT * FROM monthly UNPIVOT (amount FOR month IN (jan, feb)) u PIVOT (max(amount) FOR month IN ('jan')) AS p;
+SELECT * FROM a PIVOT (count(id) FOR kind IN (1, 2)) p JOIN b ON p.x = b.x;
+SELECT * FROM sales PIVOT (SUM(amount) month IN ('Jan'));
+SELECT * FROM sales PIVOT (SUM(amount) FOR month IN ());
+SELECT * FROM monthly UNPIVOT (amount FOR month IN ('jan'));
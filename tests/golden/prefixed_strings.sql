@@ -0,0 +1,8 @@
+INSERT INTO t VALUES (N'Grüße', n'it''s', E'line\nnext\ttab \'q\' \\ \x41\101é', B'1010', X'DEADbeef', x'');
+SELECT id FROM t WHERE code = X'4142' AND flags = b'0110' AND name = N'Ann';
+SELECT n FROM t WHERE n = 'N' AND e = x;
+VALUES (B'102');
+VALUES (X'GG');
+VALUES (E'\u12');
+-- An unterminated E string runs to the end of the script
+VALUES (E'unterminated \');
@@ -0,0 +1,7 @@
+PREPARE recent (INT, VARCHAR(16)) AS SELECT id FROM orders WHERE total > $1 AND status = $2;
+PREPARE add_order AS INSERT INTO orders (id, total) VALUES (?, :total);
+EXECUTE recent (100, 'open');
+EXECUTE add_order;
+DEALLOCATE PREPARE recent;
+DEALLOCATE ALL;
+PREPARE bad AS SELECT id FROM orders WHERE total > $;
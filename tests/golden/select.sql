@@ -0,0 +1,4 @@
+SELECT id FROM users;
+select id, name from users;
+SELECT id , name FROM users ORDER BY name, id;
+SELECT id FROM users ORDER BY name DESC, id + 1 ASC, name COLLATE nocase;
@@ -0,0 +1,10 @@
+SET search_path = public, "$user";
+SET NAMES utf8;
+SET autocommit = 0;
+SET SESSION statement_timeout TO '5s';
+SET LOCAL enable_seqscan = ON;
+SET GLOBAL max_connections = DEFAULT;
+SET TIME ZONE 'UTC';
+SET myapp.tenant_id = -42;
+SET local = 1;
+SET x;
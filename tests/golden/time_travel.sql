@@ -0,0 +1,4 @@
+SELECT id, total FROM orders AS OF VERSION 3;
+SELECT id FROM orders AS OF VERSION 12 AS o JOIN users u ON u.id = o.user_id;
+SELECT id FROM orders AS OF VERSION 2 o WHERE total > 10;
+SELECT id FROM orders AS OF 3;
@@ -0,0 +1,9 @@
+BEGIN;
+START TRANSACTION;
+BEGIN WORK;
+COMMIT;
+COMMIT TRANSACTION;
+END;
+ROLLBACK;
+ROLLBACK WORK;
+START;
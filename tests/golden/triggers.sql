@@ -0,0 +1,16 @@
+CREATE TRIGGER audit BEFORE INSERT ON orders FOR EACH ROW EXECUTE FUNCTION log_order();
+CREATE TRIGGER sync AFTER INSERT OR UPDATE OR DELETE ON users FOR EACH STATEMENT EXECUTE PROCEDURE notify('users', 1);
+CREATE TRIGGER guard INSTEAD OF DELETE ON v FOR ROW WHEN (id = 1) EXECUTE FUNCTION keep();
+CREATE TRIGGER wipe AFTER TRUNCATE ON logs EXECUTE FUNCTION reset();
+CREATE TRIGGER repriced AFTER UPDATE OF price, qty OR INSERT ON items FOR EACH ROW EXECUTE FUNCTION log_price();
+CREATE TRIGGER stamp BEFORE UPDATE ON items FOR EACH ROW BEGIN
+  SET NEW.updated = now();
+  IF NEW.price < 0 THEN
+    SET NEW.price = CASE WHEN OLD.price > 0 THEN OLD.price ELSE 0 END;
+  END IF;
+  -- END; in a comment, and in a 'string END;'
+END;
+CREATE TRIGGER bump AFTER INSERT ON items FOR EACH ROW SET total = 1;
+CREATE TRIGGER t BEFORE SELECT ON items FOR EACH ROW EXECUTE FUNCTION f();
+CREATE TRIGGER t BEFORE INSERT ON items FOR EACH ROW EXECUTE f();
+CREATE TRIGGER t BEFORE INSERT ON items FOR EACH ROW BEGIN SET x = 1;
@@ -0,0 +1,6 @@
+UPDATE users SET name = 'Ada', visits = visits + 1 WHERE id = 1;
+UPDATE users AS u SET active = FALSE;
+DELETE FROM sessions s WHERE s.expires < :now;
+DELETE FROM sessions;
+UPDATE users WHERE id = 1;
+DELETE sessions WHERE id = 1;
@@ -0,0 +1,7 @@
+VALUES (1, 'a'), (2, 'b');
+SELECT * FROM (VALUES (1, 'a'), (2, 'b')) v(id, name);
+SELECT id FROM (VALUES (1)) AS v(id) WHERE id = 1;
+SELECT a FROM (SELECT a FROM t) AS s;
+SELECT a FROM t AS x;
+SELECT * FROM (VALUES (1));
+SELECT a FROM (VALUES (1)) x(;
@@ -0,0 +1,3 @@
+CREATE VIEW big_orders AS SELECT id, total FROM orders WHERE total > 100;
+CREATE OR REPLACE VIEW totals (id, amount) AS SELECT id, total FROM orders UNION SELECT id, total FROM refunds;
+CREATE VIEW broken (id) SELECT id FROM orders;
@@ -0,0 +1,9 @@
+SELECT id FROM users WHERE age > 18;
+SELECT id FROM users WHERE age >= 18 AND name = 'it''s';
+SELECT id FROM users WHERE a = 1 OR b = 2 AND NOT (c != 3);
+SELECT id FROM users WHERE price * 2 + 1 <= -total / 4 ORDER BY id;
+SELECT id FROM users WHERE active = TRUE;
+SELECT id FROM users WHERE name LIKE 'A%' AND lower(email) = 'a@b.c';
+SELECT id FROM users WHERE coalesce(nickname, name) != '';
+SELECT id FROM users WHERE nickname IS NULL OR NOT email IS NOT DISTINCT FROM name;
+SELECT id FROM users WHERE name ILIKE 'a%';
@@ -0,0 +1,125 @@
+// grant.rs — checks GRANT and REVOKE of privileges, on columns and other objects, and of role membership
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::ast::{GrantObject, GrantedPrivilege, Ident, Privilege, Statement};
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::hash::hash_statement;
+use sreerag_devadasan::parser::{parse_sql, parse_sql_with_dialect};
+use sreerag_devadasan::rename::{apply_edits, rename_column, rename_column_edits, rename_table_edits};
+
+fn granted(privilege: Privilege, columns: &[&str]) -> GrantedPrivilege {
+    GrantedPrivilege { privilege, columns: columns.iter().map(|c| Ident::new(*c)).collect() }
+}
+
+#[test]
+fn privileges_may_name_columns_and_objects_other_than_tables() {
+    let statement = parse_sql("GRANT UPDATE (a, b), SELECT, REFERENCES (c) ON TABLE t TO u WITH GRANT OPTION").unwrap();
+    let expected = Statement::Grant {
+        privileges: vec![
+            granted(Privilege::Update, &["a", "b"]),
+            granted(Privilege::Select, &[]),
+            granted(Privilege::References, &["c"]),
+        ],
+        object: GrantObject::Table(Ident::new("t")),
+        grantees: vec!["u".to_string()],
+        with_grant_option: true,
+    };
+    assert_eq!(statement, expected);
+    assert_eq!(statement.table_name(), Some("t"));
+
+    let privileges = [
+        ("ALL PRIVILEGES", Privilege::All),
+        ("INSERT", Privilege::Insert),
+        ("DELETE", Privilege::Delete),
+        ("TRUNCATE", Privilege::Truncate),
+        ("TRIGGER", Privilege::Trigger),
+        ("USAGE", Privilege::Usage),
+        ("EXECUTE", Privilege::Execute),
+    ];
+    for (sql, privilege) in privileges {
+        let statement = parse_sql(&format!("REVOKE {} ON t FROM u", sql)).unwrap();
+        assert!(matches!(statement, Statement::Revoke { ref privileges, .. } if privileges[0].privilege == privilege));
+    }
+    for (sql, object) in [
+        ("GRANT USAGE ON SCHEMA s TO u", GrantObject::Schema(Ident::new("s"))),
+        ("GRANT USAGE ON SEQUENCE ids TO u", GrantObject::Sequence(Ident::new("ids"))),
+        ("REVOKE EXECUTE ON FUNCTION f FROM u", GrantObject::Function(Ident::new("f"))),
+    ] {
+        let statement = parse_sql(sql).unwrap();
+        let (Statement::Grant { object: found, .. } | Statement::Revoke { object: found, .. }) = &statement else {
+            panic!("{}: {:?}", sql, statement)
+        };
+        assert_eq!(*found, object);
+        // Only a table is the statement's table
+        assert_eq!(statement.table_name(), None, "{}", sql);
+        assert_eq!(statement.to_string(), sql);
+    }
+
+    for sql in [
+        "GRANT UPDATE (a, b), SELECT ON t TO u, v WITH GRANT OPTION",
+        "REVOKE ALL PRIVILEGES (a) ON t FROM u",
+        "GRANT TRUNCATE, REFERENCES, TRIGGER ON t TO u",
+    ] {
+        assert_eq!(parse_sql(sql).unwrap().to_string(), sql);
+    }
+    let quoted = parse_sql_with_dialect("GRANT SELECT (\"Name\") ON t TO u", Dialect::Postgres).unwrap();
+    assert_eq!(quoted.to_sql(Dialect::Postgres), "GRANT SELECT (\"Name\") ON t TO u");
+    // The columns are part of what is granted
+    let whole = parse_sql("GRANT UPDATE ON t TO u").unwrap();
+    assert_ne!(hash_statement(&whole), hash_statement(&parse_sql("GRANT UPDATE (a) ON t TO u").unwrap()));
+
+    for sql in [
+        "GRANT UPDATE () ON t TO u",
+        "GRANT UPDATE (a ON t TO u",
+        "GRANT SELECT, ON t TO u",
+        "GRANT SELECT t TO u",
+        "GRANT USAGE ON SCHEMA TO u",
+        "GRANT SELECT, analyst ON t TO u",
+    ] {
+        assert!(parse_sql(sql).is_err(), "{}", sql);
+    }
+}
+
+#[test]
+fn roles_are_granted_and_revoked_without_an_object() {
+    let statement = parse_sql("GRANT analyst, reporting TO bob, carol WITH ADMIN OPTION").unwrap();
+    let expected = Statement::GrantRole {
+        roles: vec!["analyst".to_string(), "reporting".to_string()],
+        grantees: vec!["bob".to_string(), "carol".to_string()],
+        with_admin_option: true,
+    };
+    assert_eq!(statement, expected);
+    assert_eq!((statement.kind(), statement.table_name(), statement.is_dcl()), ("GRANT ROLE", None, true));
+
+    let statement = parse_sql("REVOKE analyst FROM bob").unwrap();
+    let expected = Statement::RevokeRole { roles: vec!["analyst".to_string()], grantees: vec!["bob".to_string()] };
+    assert_eq!(statement, expected);
+    assert_eq!(statement.kind(), "REVOKE ROLE");
+
+    let sqls = ["GRANT analyst TO bob", "GRANT analyst, reporting TO bob WITH ADMIN OPTION", "REVOKE analyst FROM bob"];
+    for sql in sqls {
+        assert_eq!(parse_sql(sql).unwrap().to_string(), sql);
+    }
+    for sql in [
+        "GRANT analyst ON t TO bob",
+        "GRANT analyst TO bob WITH GRANT OPTION",
+        "REVOKE analyst TO bob",
+        "GRANT analyst, SELECT TO bob",
+    ] {
+        assert!(parse_sql(sql).is_err(), "{}", sql);
+    }
+}
+
+#[test]
+fn renaming_reaches_the_granted_table_and_its_columns() {
+    let sql = "GRANT SELECT (id, name), UPDATE (name) ON users TO clerk";
+    let edits = rename_column_edits(sql, Dialect::Postgres, "users", "name", "label").unwrap();
+    assert_eq!(apply_edits(sql, &edits), "GRANT SELECT (id, label), UPDATE (label) ON users TO clerk");
+    let tables = rename_table_edits(sql, Dialect::Postgres, "users", "people").unwrap();
+    assert_eq!(apply_edits(sql, &tables), "GRANT SELECT (id, name), UPDATE (name) ON people TO clerk");
+    // Another table's columns, and a schema of the same name, are left alone
+    let other = parse_sql("REVOKE UPDATE (name) ON orders FROM clerk").unwrap();
+    assert_eq!(rename_column(&other, "users", "name", "label"), other);
+    let schema = "GRANT USAGE ON SCHEMA users TO clerk";
+    assert!(rename_table_edits(schema, Dialect::Postgres, "users", "people").unwrap().is_empty());
+}
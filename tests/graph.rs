@@ -0,0 +1,59 @@
+// graph.rs — checks the DOT and Mermaid diagrams of parsed statements
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::parser::parse_sql;
+
+#[test]
+fn dot_draws_nodes_with_scalars_inside_and_fields_as_edges() {
+    let statement = parse_sql("SELECT a, b FROM t JOIN u ON t.id = u.id WHERE NOT a LIMIT 3").unwrap();
+    let expected = r#"digraph ast {
+    node [shape=box, fontname="monospace"];
+    n0 [label="Select\nlimit: 3"];
+    n1 [label="SelectItem"];
+    n2 [label="Identifier\n\"a\""];
+    n3 [label="SelectItem"];
+    n4 [label="Identifier\n\"b\""];
+    n5 [label="Table\nname: \"t\""];
+    n6 [label="Join\nkind: Inner"];
+    n7 [label="Table\nname: \"u\""];
+    n8 [label="BinaryOperation\noperator: Equals"];
+    n9 [label="CompoundIdentifier\n[\"t\", \"id\"]"];
+    n10 [label="CompoundIdentifier\n[\"u\", \"id\"]"];
+    n11 [label="UnaryOperation\noperator: Not"];
+    n12 [label="Identifier\n\"a\""];
+    n0 -> n1 [label="columns[0]"];
+    n1 -> n2 [label="expr"];
+    n0 -> n3 [label="columns[1]"];
+    n3 -> n4 [label="expr"];
+    n0 -> n5 [label="from"];
+    n0 -> n6 [label="joins[0]"];
+    n6 -> n7 [label="relation"];
+    n6 -> n8 [label="on"];
+    n8 -> n9 [label="left_operand"];
+    n8 -> n10 [label="right_operand"];
+    n0 -> n11 [label="selection"];
+    n11 -> n12 [label="operand"];
+}
+"#;
+    assert_eq!(statement.to_dot(), expected);
+}
+
+#[test]
+fn mermaid_escapes_labels_and_indexes_nested_lists() {
+    let statement = parse_sql("INSERT INTO t (a) VALUES (1, '<#\">')").unwrap();
+    let expected = r#"flowchart TD
+    n0["Insert<br/>table_name: #quot;t#quot;<br/>columns: [#quot;a#quot;]"]
+    n1["Number<br/>1"]
+    n2["String<br/>#quot;#lt;#35;\#quot;#gt;#quot;"]
+    n0 -->|"rows[0][0]"| n1
+    n0 -->|"rows[0][1]"| n2
+"#;
+    assert_eq!(statement.to_mermaid(), expected);
+
+    // Two columns, and the VARCHAR with its length gets a box of its own
+    let statement = parse_sql("CREATE TABLE t (id INT PRIMARY KEY, name VARCHAR(20) NOT NULL)").unwrap();
+    let mermaid = statement.to_mermaid();
+    assert_eq!(mermaid.lines().filter(|line| line.contains("-->")).count(), 3, "{}", mermaid);
+    assert!(mermaid.contains("n2 -->|\"column_type\"| n3"), "{}", mermaid);
+    assert!(mermaid.contains("constraints: [PrimaryKey]"), "{}", mermaid);
+}
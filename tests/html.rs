@@ -0,0 +1,25 @@
+// html.rs — checks the HTML report of a script: highlighting, errors, lint findings and the dependency graph
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::html::html_report;
+use sreerag_devadasan::session::ParserSession;
+
+#[test]
+fn report_covers_every_statement() {
+    let script = "CREATE TABLE users (id INT);\nSELECT * FROM users WHERE id = NULL;\nSELECT FROM WHERE;\n";
+    let html = html_report("a <b> script", script, &ParserSession::default());
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>a &lt;b&gt; script</title>"));
+    assert!(html.contains("<p>3 statements, 1 failed to parse, 1 lint errors, 1 lint warnings</p>"));
+    assert!(html.contains("<span class=\"kw\">CREATE</span> <span class=\"kw\">TABLE</span> users"));
+    assert!(html.contains("<pre>SELECT <mark>FROM</mark> WHERE</pre>"));
+    assert!(html.contains("<summary>selection: BinaryOperation</summary>"));
+    assert!(html.contains("error [null-comparison]: `id = NULL`"));
+
+    // Statement 1 writes users, statement 2 reads it
+    assert!(html.contains("<svg"));
+    assert!(html.contains("<text x=\"356\" y=\"28\">users</text>"));
+    assert!(html.contains("<line class=\"write\" x1=\"170\" y1=\"24\" x2=\"350\" y2=\"24\"/>"));
+    assert!(html.contains("<line class=\"read\" x1=\"170\" y1=\"52\" x2=\"350\" y2=\"24\"/>"));
+    assert!(!html.contains("href=\"#s3\""), "failed statements aren't in the graph");
+}
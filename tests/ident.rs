@@ -0,0 +1,63 @@
+// ident.rs — checks identifier quoting, case folding and dialect-aware comparison
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::ast::{Expression, Ident, QuoteStyle, Statement, TableFactor};
+use sreerag_devadasan::dialect::{translate, Dialect};
+use sreerag_devadasan::parser::parse_sql_with_dialect;
+
+fn table(sql: &str, dialect: Dialect) -> Ident {
+    match parse_sql_with_dialect(sql, dialect).unwrap() {
+        Statement::Select { from, .. } => match *from {
+            TableFactor::Table { name, .. } => name,
+            other => panic!("expected a table, got {:?}", other),
+        },
+        other => panic!("expected a SELECT, got {:?}", other),
+    }
+}
+
+#[test]
+fn names_compare_the_way_the_dialect_resolves_them() {
+    let postgres = Dialect::Postgres;
+    let bare = table("SELECT * FROM users", postgres);
+    let upper = table("SELECT * FROM USERS", postgres);
+    let quoted = table("SELECT * FROM \"users\"", postgres);
+    let mixed = table("SELECT * FROM \"Users\"", postgres);
+    assert_eq!(quoted, Ident::quoted("users", QuoteStyle::Double));
+    assert!(bare.eq_in(&upper, postgres) && bare.eq_in(&quoted, postgres));
+    assert!(!bare.eq_in(&mixed, postgres) && !quoted.eq_in(&mixed, postgres));
+    // Exact equality still tells the spellings apart
+    assert_ne!(bare, quoted);
+
+    // The standard folds to upper case instead
+    let ansi = Dialect::Ansi;
+    assert!(Ident::new("users").eq_in(&Ident::quoted("USERS", QuoteStyle::Double), ansi));
+    assert!(!Ident::new("users").eq_in(&Ident::quoted("users", QuoteStyle::Double), ansi));
+
+    assert_eq!(Ident::new("Users").normalized(postgres), "users");
+    assert_eq!(Ident::new("Users").normalized(ansi), "USERS");
+    assert_eq!(mixed.normalized(postgres), "Users");
+}
+
+#[test]
+fn quoted_names_keep_their_quotes() {
+    assert_eq!(Ident::quoted("a\"b", QuoteStyle::Double).to_string(), "\"a\"\"b\"");
+    assert_eq!(Ident::quoted("first name", QuoteStyle::Bracket).to_string(), "[first name]");
+    assert_eq!(Ident::new("users").to_string(), "users");
+
+    // A quoted name is printed quoted in the target's style, a bare one bare
+    let sql = "SELECT \"Id\", name FROM \"Users\" WHERE \"Users\".\"Id\" = 1";
+    let printed = translate(sql, Dialect::Postgres, Dialect::MySql).unwrap();
+    assert_eq!(printed, "SELECT `Id`, name FROM `Users` WHERE `Users`.`Id` = 1");
+    assert_eq!(translate(&printed, Dialect::MySql, Dialect::Postgres).unwrap(), sql);
+
+    let Statement::Select { selection: Some(Expression::BinaryOperation { left_operand, .. }), .. } =
+        parse_sql_with_dialect(sql, Dialect::Postgres).unwrap()
+    else {
+        panic!("expected a SELECT with a comparison");
+    };
+    let Expression::CompoundIdentifier(parts) = *left_operand else {
+        panic!("expected a qualified column");
+    };
+    assert!(parts.iter().all(Ident::is_quoted));
+    assert_eq!(parts[1], "Id");
+}
@@ -0,0 +1,120 @@
+// insert.rs — checks that INSERTs of many rows take the batch path unchanged
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::datetime::Date;
+use sreerag_devadasan::decimal::Decimal;
+use sreerag_devadasan::engine::{Database, EngineError, Storage};
+use sreerag_devadasan::eval::Value;
+
+const SCHEMA: &str = "CREATE TABLE items (id INT PRIMARY KEY, name VARCHAR(8) NOT NULL COLLATE NOCASE, \
+                      price DECIMAL(5,2) CHECK (price >= 0), added DATE)";
+
+/// An INSERT of `n` rows, the last of which is `last` when given.
+fn insert(n: usize, last: Option<&str>) -> String {
+    let mut rows: Vec<String> = (0..n).map(|i| format!("({}, 'item{}', {} + 0.5, '2024-01-01')", i, i, i)).collect();
+    if let Some(last) = last {
+        *rows.last_mut().unwrap() = last.to_string();
+    }
+    format!("INSERT INTO items VALUES {}", rows.join(", "))
+}
+
+/// What `sql` gives with the batch path on and off, for each storage.
+fn both_ways(setup: &[&str], sql: &str) -> Vec<Result<Vec<Vec<Value>>, EngineError>> {
+    let mut results = Vec::new();
+    for storage in [Storage::Rows, Storage::Columnar] {
+        for batch in [true, false] {
+            let mut db = Database::with_storage(storage);
+            db.set_batch_inserts(batch);
+            for statement in setup {
+                db.execute_sql(statement).unwrap();
+            }
+            let result = db.execute_sql(sql).and_then(|_| db.execute_sql("SELECT * FROM items ORDER BY id"));
+            results.push(result.map(|result| result.rows));
+        }
+    }
+    results
+}
+
+#[test]
+fn batched_inserts_store_what_row_by_row_ones_do() {
+    let results = both_ways(&[SCHEMA, "INSERT INTO items VALUES (-1, 'first', 1, NULL)"], &insert(100, None));
+    let rows = results[0].as_ref().unwrap();
+    assert_eq!(rows.len(), 101);
+    let day = Value::Date(Date::parse("2024-01-01").unwrap());
+    let price = Value::Decimal(Decimal::parse("0.50").unwrap());
+    assert_eq!(rows[1], [Value::Int(0), Value::String("item0".to_string()), price, day]);
+    assert!(results.iter().all(|result| result.as_ref().ok() == Some(rows)));
+
+    // Values other than literals are evaluated, and columns left out are NULL
+    let sql: Vec<String> = (0..40).map(|i| format!("({} * 2, CAST({} * 10 AS VARCHAR(8)))", i, i)).collect();
+    let sql = format!("INSERT INTO items (id, name) VALUES {}", sql.join(", "));
+    let results = both_ways(&[SCHEMA], &sql);
+    let rows = results[0].as_ref().unwrap();
+    assert_eq!(rows[39], [Value::Int(78), Value::String("390".to_string()), Value::Null, Value::Null]);
+    assert!(results.iter().all(|result| result.as_ref().ok() == Some(rows)));
+}
+
+#[test]
+fn batched_inserts_refuse_what_row_by_row_ones_do() {
+    let existing = "INSERT INTO items VALUES (500, 'old', 1, NULL)";
+    // Each INSERT ends with a bad row, refused with an error whose debug form starts so
+    let cases = [
+        ("(3, 'again', 1, NULL)", "DuplicateKey(\"id\")"),
+        ("(500, 'again', 1, NULL)", "DuplicateKey(\"id\")"),
+        ("(999, NULL, 1, NULL)", "NotNull(\"name\")"),
+        ("(999, 'toolongname', 1, NULL)", "ValueTooLong"),
+        ("(999, 'x', 'cheap', NULL)", "TypeMismatch"),
+        ("(999, 'x', -1, NULL)", "CheckFailed(\"price\")"),
+        ("(999, 'x')", "ColumnCount { expected: 4, found: 2 }"),
+    ];
+    for (last, expected) in cases {
+        let sql = insert(50, Some(last));
+        for result in both_ways(&[SCHEMA, existing], &sql) {
+            let error = result.err().map(|error| format!("{:?}", error));
+            assert!(error.as_ref().is_some_and(|error| error.starts_with(expected)), "{}: {:?}", last, error);
+        }
+    }
+
+    // A failed INSERT stores none of its rows
+    let mut db = Database::new();
+    db.execute_sql(SCHEMA).unwrap();
+    assert!(db.execute_sql(&insert(50, Some("(0, 'again', 1, NULL)"))).is_err());
+    assert!(db.execute_sql("SELECT id FROM items").unwrap().rows.is_empty());
+
+    // Names that differ only in case are the same key under NOCASE
+    db.execute_sql("CREATE TABLE tags (name VARCHAR(10) PRIMARY KEY COLLATE NOCASE)").unwrap();
+    let mut tags: Vec<String> = (0..40).map(|i| format!("('tag{}')", i)).collect();
+    db.execute_sql(&format!("INSERT INTO tags VALUES {}", tags.join(", "))).unwrap();
+    tags = (40..80).map(|i| format!("('tag{}')", i)).collect();
+    tags.push("('TAG7')".to_string());
+    let error = db.execute_sql(&format!("INSERT INTO tags VALUES {}", tags.join(", "))).unwrap_err();
+    assert!(matches!(error, EngineError::DuplicateKey(_)));
+    assert_eq!(db.execute_sql("SELECT name FROM tags").unwrap().rows.len(), 40);
+    // and so are single rows, which look their key up among the stored ones
+    let error = db.execute_sql("INSERT INTO tags VALUES ('Tag39')").unwrap_err();
+    assert!(matches!(error, EngineError::DuplicateKey(_)));
+    db.execute_sql("INSERT INTO tags VALUES ('tag40'), ('tag41')").unwrap();
+    assert!(db.execute_sql("INSERT INTO tags VALUES ('tag42'), ('TAG42')").is_err());
+}
+
+#[test]
+fn of_several_bad_rows_the_first_is_reported() {
+    // Bad rows by position; the error is that of the first, whatever its columns
+    let cases: [(&[(usize, &str)], &str); 5] = [
+        (&[(3, "(3, NULL, 1, NULL)"), (10, "('x', 'ten', 1, NULL)")], "NotNull(\"name\")"),
+        (&[(3, "(3, 'three', 'cheap', NULL)"), (10, "(10, NULL, 1, NULL)")], "TypeMismatch { column: \"price\""),
+        (&[(12, "(12, 'toolongname', 1, NULL)"), (20, "('x', 'twenty', -1, NULL)")], "ValueTooLong"),
+        (&[(20, "(20, 'twenty', -1, NULL)"), (25, "(25, 'short')"), (30, "('x', NULL, 1, NULL)")], "CheckFailed"),
+        (&[(5, "(5, 'five')"), (30, "(30, NULL, -1, NULL)")], "ColumnCount"),
+    ];
+    for (bad, expected) in cases {
+        let mut values: Vec<String> = (0..40).map(|i| format!("({}, 'item{}', {}, NULL)", i, i, i)).collect();
+        for &(row, value) in bad {
+            values[row] = value.to_string();
+        }
+        let sql = format!("INSERT INTO items VALUES {}", values.join(", "));
+        let errors: Vec<String> = both_ways(&[SCHEMA], &sql).iter().map(|result| format!("{:?}", result)).collect();
+        assert!(errors[0].starts_with(&format!("Err({}", expected)), "{:?}: {}", bad, errors[0]);
+        assert!(errors.iter().all(|error| *error == errors[0]), "{:?}: {:?}", bad, errors);
+    }
+}
@@ -0,0 +1,68 @@
+// lex_errors.rs — checks that input that doesn't tokenize fails with where and why
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::ast::Statement;
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::session::{ParserOptions, ParserSession};
+use sreerag_devadasan::span::{error_span, parse_with_source_map, Span};
+use sreerag_devadasan::tokenizer::{tokenize, tokenize_with_errors, LexError, LexErrorKind, ParseError, Token};
+
+fn lex_errors(sql: &str) -> Vec<LexError> {
+    match parse_sql(sql) {
+        Err(ParseError::Lex(errors)) => errors,
+        other => panic!("{}: {:?}", sql, other),
+    }
+}
+
+#[test]
+fn errors_are_recorded_with_their_spans() {
+    let sql = "SELECT a # b FROM t WHERE c = 'open";
+    let (tokens, spans, errors) = tokenize_with_errors(sql, Dialect::Generic);
+    assert_eq!(
+        errors,
+        [
+            LexError { kind: LexErrorKind::InvalidCharacter('#'), span: Span::new(9, 10) },
+            LexError { kind: LexErrorKind::UnterminatedString('\''), span: Span::new(30, 35) },
+        ]
+    );
+    // The tokens still stand for them, so every token keeps its span
+    assert_eq!(tokens[2], Token::Invalid('#'));
+    assert_eq!(spans[2], errors[0].span);
+    assert!(tokenize_with_errors("SELECT 'a' FROM t", Dialect::Generic).2.is_empty());
+
+    let kinds = |sql: &str| lex_errors(sql).into_iter().map(|error| error.kind).collect::<Vec<_>>();
+    let invalid = LexErrorKind::InvalidCharacter;
+    assert_eq!(kinds("SELECT 1 ! 2, $x, :1"), [invalid('!'), invalid('$'), invalid(':')]);
+    let strings = [LexErrorKind::InvalidDigit('2'), LexErrorKind::InvalidDigit('g'), LexErrorKind::InvalidEscape];
+    assert_eq!(kinds("VALUES (B'12', X'0g', E'\\u12x')"), strings);
+    assert_eq!(kinds("SELECT $$ body"), [LexErrorKind::UnterminatedString('$')]);
+
+    // A number too large for 64 bits is an error, not a panic
+    let digits = "18446744073709551616";
+    assert_eq!(tokenize(digits)[0], Token::Invalid('1'));
+    assert_eq!(kinds(&format!("VALUES ({})", digits)), [LexErrorKind::NumberOverflow(digits.to_string())]);
+    assert!(parse_sql("VALUES (18446744073709551615)").is_ok());
+}
+
+#[test]
+fn parsers_report_lexical_errors_first() {
+    let sql = "SELECT a FROM t WHERE b = 'x";
+    let error = parse_sql(sql).unwrap_err();
+    assert_eq!(error.code(), "lexical");
+    assert_eq!(error.to_string(), "Unterminated string, missing its closing ' at byte 26");
+    let error = parse_sql("SELECT # FROM ~").unwrap_err();
+    assert_eq!(error.to_string(), "Invalid character '#' at byte 7; Invalid character '~' at byte 14");
+
+    assert!(matches!(parse_with_source_map(sql, Dialect::Generic), Err(ParseError::Lex(_))));
+    assert_eq!(error_span(sql, Dialect::Generic), Some(Span::new(26, 28)));
+
+    let session = ParserSession::default();
+    assert!(matches!(session.tokenize("SELECT 1 # 2"), Err(ParseError::Lex(errors)) if errors.len() == 1));
+    assert!(matches!(session.parse("SELECT 1 # 2"), Err(ParseError::Lex(_))));
+    // Loose mode keeps what it can't read as it is
+    let loose = ParserSession::new(ParserOptions { loose: true, ..Default::default() });
+    let statements = loose.parse_script("SELECT 1;\nSELECT 'a");
+    assert!(matches!(&statements[1], Ok(Statement::Unparsed { raw_sql, span }) if raw_sql == "SELECT 'a"
+        && *span == Span::new(10, 19)));
+}
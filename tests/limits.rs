@@ -0,0 +1,55 @@
+// limits.rs — checks that nesting past the parser's depth limit is an error rather than a stack overflow
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::ast::Statement;
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::session::{ParserOptions, ParserSession, DEFAULT_MAX_EXPRESSION_DEPTH};
+use sreerag_devadasan::tokenizer::{Limit, ParseError};
+
+/// `n` levels of each way statements nest inside each other.
+fn shapes(n: usize) -> Vec<(&'static str, String)> {
+    vec![
+        ("derived tables", format!("{}SELECT a FROM t{}", "SELECT a FROM (".repeat(n), ") x".repeat(n))),
+        ("parentheses in FROM", format!("SELECT a FROM {}SELECT a FROM t{}", "(".repeat(n), ")".repeat(n))),
+        ("WITH", format!("{}SELECT 1 FROM t{}", "WITH x AS (".repeat(n), ") SELECT 1 FROM x".repeat(n))),
+        ("EXPLAIN", format!("{}SELECT 1 FROM t", "EXPLAIN ".repeat(n))),
+        ("PREPARE", format!("{}SELECT 1 FROM t", "PREPARE p AS ".repeat(n))),
+    ]
+}
+
+fn exceeded(result: Result<Statement, ParseError>, at: usize) -> bool {
+    matches!(result, Err(ParseError::LimitExceeded { limit: Limit::ExpressionDepth, max }) if max == at)
+}
+
+#[test]
+fn statements_nested_past_the_limit_fail_with_limit_exceeded() {
+    // A debug build's frames for a whole query are large, so give the parser a main thread's stack
+    let thread = std::thread::Builder::new().stack_size(8 << 20);
+    thread.spawn(nested_statements).unwrap().join().unwrap();
+}
+
+fn nested_statements() {
+    for (shape, sql) in shapes(5_000) {
+        assert!(exceeded(parse_sql(&sql), DEFAULT_MAX_EXPRESSION_DEPTH), "{}", shape);
+    }
+    // Parentheses that aren't a query still nest
+    let sql = format!("SELECT a FROM {}t{}", "(".repeat(5_000), ")".repeat(5_000));
+    assert!(exceeded(parse_sql(&sql), DEFAULT_MAX_EXPRESSION_DEPTH));
+
+    for (shape, sql) in shapes(DEFAULT_MAX_EXPRESSION_DEPTH / 4) {
+        assert!(parse_sql(&sql).is_ok(), "{}", shape);
+    }
+}
+
+#[test]
+fn queries_and_their_expressions_share_one_depth() {
+    let session = ParserSession::new(ParserOptions { max_expression_depth: Some(10), ..ParserOptions::default() });
+    let query = |queries: usize, parentheses: usize| {
+        let expr = format!("{}1{}", "(".repeat(parentheses), ")".repeat(parentheses));
+        format!("{}SELECT {} FROM t{}", "SELECT a FROM (".repeat(queries), expr, ") x".repeat(queries))
+    };
+    assert!(session.parse(&query(2, 4)).is_ok());
+    assert!(session.parse(&query(0, 8)).is_ok());
+    assert!(exceeded(session.parse(&query(4, 4)), 10));
+    assert!(exceeded(session.parse(&query(10, 0)), 10));
+}
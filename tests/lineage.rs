@@ -0,0 +1,105 @@
+// lineage.rs — checks tracing output columns back to the table columns they derive from
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::lineage::{lineage, Lineage, RelationKind};
+use sreerag_devadasan::parser::{parse_sql, parse_sql_with_dialect};
+use sreerag_devadasan::schema_diff::Schema;
+
+const SCHEMA: &str = "CREATE TABLE orders (id INT, user_id INT, total INT);
+                      CREATE TABLE users (id INT, name VARCHAR(20));
+                      CREATE TABLE archive (order_id INT, amount INT)";
+
+fn traced(sql: &str, schema: Option<&Schema>) -> Lineage {
+    lineage(&parse_sql_with_dialect(sql, Dialect::Warehouse).unwrap(), schema).unwrap()
+}
+
+fn sources(lineage: &Lineage, column: &str) -> Vec<String> {
+    lineage.sources_of(column).iter().map(|node| node.to_string()).collect()
+}
+
+#[test]
+fn columns_are_traced_through_ctes_subqueries_and_aliases() {
+    let sql = "WITH big AS (SELECT id, total FROM orders WHERE total > 10) \
+               SELECT name, total FROM users u JOIN big b ON b.id = u.id";
+    let found = traced(sql, None);
+    assert_eq!(found.to_string(), "name <- users.name\ntotal <- orders.total");
+    // The CTE's columns are nodes of their own, between the table and the result
+    let cte = found.nodes.iter().filter(|node| node.kind == RelationKind::Cte);
+    let cte: Vec<String> = cte.map(|node| node.to_string()).collect();
+    assert_eq!(cte, ["big.id", "big.total"]);
+    assert_eq!(found.outputs().len(), 2);
+
+    // Column lists rename, REPLACE brings in the columns of its expression
+    let sql = "SELECT * REPLACE (total + user_id AS total) FROM (SELECT id, total, user_id FROM orders) AS s(a, total)";
+    let found = traced(sql, None);
+    assert_eq!(sources(&found, "a"), ["orders.id"]);
+    assert_eq!(sources(&found, "total"), ["orders.total", "orders.user_id"]);
+
+    // The arms of a set operation line up by position
+    let found = traced("SELECT id FROM orders UNION SELECT user_id FROM orders", None);
+    assert_eq!(found.to_string(), "id <- orders.id, orders.user_id");
+    let sql = "WITH RECURSIVE r (n) AS (SELECT id FROM orders UNION ALL SELECT n FROM r) SELECT n FROM r";
+    let found = traced(sql, None);
+    assert_eq!(sources(&found, "n"), ["orders.id"]);
+
+    // Without a schema, `*` and names that may be of either table stay open
+    let found = traced("SELECT * EXCLUDE (id) FROM orders JOIN users ON users.id = orders.user_id", None);
+    assert_eq!(found.to_string(), "* <- orders.*\n* <- users.*");
+    let found = traced("SELECT name FROM orders JOIN users ON users.id = orders.user_id", None);
+    assert_eq!(sources(&found, "name"), ["orders.name", "users.name"]);
+    let schema = Schema::from_sql(SCHEMA).unwrap();
+    let found = traced("SELECT name FROM orders JOIN users ON users.id = orders.user_id", Some(&schema));
+    assert_eq!(sources(&found, "name"), ["users.name"]);
+    let found = traced("SELECT * EXCLUDE (id) FROM orders", Some(&schema));
+    assert_eq!(found.to_string(), "user_id <- orders.user_id\ntotal <- orders.total");
+    let found = traced("SELECT * FROM (SELECT * FROM orders) AS s", None);
+    assert_eq!(sources(&found, "*"), ["orders.*"]);
+}
+
+#[test]
+fn inserts_map_columns_to_their_targets_by_position() {
+    let schema = Schema::from_sql(SCHEMA).unwrap();
+    let statement = parse_sql("INSERT INTO archive SELECT id, total FROM orders").unwrap();
+    let found = lineage(&statement, Some(&schema)).unwrap();
+    assert_eq!(found.to_string(), "archive.order_id <- orders.id\narchive.amount <- orders.total");
+    let found = lineage(&statement, None).unwrap();
+    assert_eq!(found.to_string(), "archive.id <- orders.id\narchive.total <- orders.total");
+    let statement = parse_sql("INSERT INTO archive (amount) SELECT * FROM orders").unwrap();
+    assert_eq!(lineage(&statement, None).unwrap().to_string(), "archive.amount <- orders.*");
+
+    let found = lineage(&parse_sql("SELECT id FROM orders o").unwrap(), None).unwrap();
+    let dot = found.to_dot();
+    assert!(dot.starts_with("digraph lineage {\n    rankdir=LR;\n"), "{}", dot);
+    assert!(dot.contains("        label=\"result\";\n        style=bold;\n        n1 [label=\"id\"];\n"), "{}", dot);
+    assert!(dot.ends_with("    n0 -> n1;\n}\n"), "{}", dot);
+    assert_eq!(
+        found.to_json(),
+        r#"{"columns":[{"column":"id","sources":[{"kind":"table","relation":"orders","column":"id"}]}],"#.to_string()
+            + r#""nodes":[{"kind":"table","relation":"orders","column":"id"},"#
+            + r#"{"kind":"output","relation":"","column":"id"}],"edges":[[0,1]]}"#
+    );
+
+    assert!(lineage(&parse_sql("EXPLAIN SELECT id FROM orders").unwrap(), None).is_some());
+    assert!(lineage(&parse_sql("INSERT INTO archive VALUES (1, 2)").unwrap(), None).is_none());
+    assert!(lineage(&parse_sql("DROP TABLE orders").unwrap(), None).is_none());
+}
+
+#[test]
+fn computed_items_are_traced_to_the_columns_they_read() {
+    let found = traced("SELECT a + b AS c FROM t", None);
+    assert_eq!(found.to_string(), "c <- t.a, t.b");
+    // Unnamed items are named by their SQL
+    let schema = Schema::from_sql(SCHEMA).unwrap();
+    let sql = "SELECT total * 2, LEFT(name, 1) AS initial FROM orders JOIN users ON users.id = orders.user_id";
+    let found = traced(sql, Some(&schema));
+    assert_eq!(found.to_string(), "total * 2 <- orders.total\ninitial <- users.name");
+
+    let statement = parse_sql("INSERT INTO archive (amount) SELECT f(x) FROM t").unwrap();
+    assert_eq!(lineage(&statement, None).unwrap().to_string(), "archive.amount <- t.x");
+    let statement = parse_sql("INSERT INTO archive SELECT id, abs(total - user_id) FROM orders").unwrap();
+    let expected = "archive.order_id <- orders.id\narchive.amount <- orders.total, orders.user_id";
+    assert_eq!(lineage(&statement, Some(&schema)).unwrap().to_string(), expected);
+    // A literal derives from nothing
+    assert_eq!(traced("SELECT 1 AS one FROM orders", None).to_string(), "one <- no columns");
+}
@@ -0,0 +1,102 @@
+// lint.rs — checks the lint rules against statements that break them and ones that don't
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::lint::{lint, lint_with_config, LintConfig, Severity, RULES};
+use sreerag_devadasan::parser::parse_sql;
+
+fn rules(sql: &str) -> Vec<&'static str> {
+    lint(&parse_sql(sql).unwrap()).into_iter().map(|finding| finding.rule).collect()
+}
+
+#[test]
+fn rules_flag_likely_mistakes() {
+    assert_eq!(rules("SELECT id FROM users WHERE name = NULL"), ["null-comparison"]);
+    assert_eq!(rules("SELECT * FROM users"), ["select-star"]);
+    assert_eq!(rules("SELECT id FROM users LIMIT 5"), ["limit-without-order"]);
+    assert_eq!(rules("SELECT id FROM users CROSS JOIN orders"), ["cross-join"]);
+    assert_eq!(rules("DROP TABLE users"), ["drop-without-if-exists"]);
+    // Queries nested in CTEs and FROM subqueries are linted too
+    let nested = "WITH recent AS (SELECT * FROM orders) SELECT id FROM (SELECT id FROM recent LIMIT 1) AS r";
+    assert_eq!(rules(nested), ["select-star", "limit-without-order"]);
+
+    let finding = &lint(&parse_sql("SELECT id FROM users WHERE NULL != id").unwrap())[0];
+    assert_eq!(finding.severity, Severity::Error);
+    assert!(finding.message.ends_with("use IS NOT NULL"), "{}", finding.message);
+    assert_eq!(rules("SELECT id FROM users WHERE name IS NULL OR id IS DISTINCT FROM NULL"), Vec::<&str>::new());
+}
+
+#[test]
+fn clean_statements_have_no_findings() {
+    for sql in [
+        "SELECT id FROM users WHERE name = 'NULL'",
+        "SELECT id FROM users ORDER BY id LIMIT 5",
+        "SELECT id FROM users u JOIN orders o ON u.id = o.user_id",
+        "DROP TABLE IF EXISTS users",
+        "INSERT INTO users VALUES (1, 'Ada')",
+    ] {
+        assert_eq!(rules(sql), Vec::<&str>::new(), "{}", sql);
+    }
+    let mut names: Vec<_> = RULES.iter().map(|rule| rule.name).collect();
+    names.dedup();
+    assert_eq!(names.len(), RULES.len());
+}
+
+#[test]
+fn unused_ctes_columns_and_aliases_are_flagged() {
+    let sql = "WITH old AS (SELECT id FROM orders), recent AS (SELECT id, total FROM orders) SELECT id FROM recent";
+    assert_eq!(rules(sql), ["unused-cte", "unused-column"]);
+    let messages: Vec<String> = lint(&parse_sql(sql).unwrap()).into_iter().map(|finding| finding.message).collect();
+    assert_eq!(messages, ["the CTE old is never read; remove it", "the column total of the CTE recent is never used"]);
+
+    // Columns count as used wherever the outer query names them, or all of them with *
+    assert_eq!(rules("WITH r AS (SELECT id, total FROM orders) SELECT id FROM r WHERE total > 10"), Vec::<&str>::new());
+    assert_eq!(rules("WITH r (a, b) AS (SELECT id, total FROM orders) SELECT a FROM r"), ["unused-column"]);
+    assert_eq!(rules("SELECT id FROM (SELECT id, total FROM orders) AS r"), ["unused-column"]);
+    assert_eq!(rules("WITH r AS (SELECT id, total FROM orders) SELECT * FROM r"), ["select-star"]);
+    // A CTE read only by another one is used
+    let chained = "WITH a AS (SELECT id FROM orders), b AS (SELECT id FROM a) SELECT id FROM b";
+    assert_eq!(rules(chained), Vec::<&str>::new());
+
+    assert_eq!(rules("SELECT id FROM users u WHERE id > 1"), ["unused-alias"]);
+    assert_eq!(rules("SELECT id FROM users u JOIN orders o ON o.user_id = users.id"), ["unused-alias"]);
+    assert_eq!(rules("SELECT id FROM users u WHERE u.id > 1"), Vec::<&str>::new());
+}
+
+#[test]
+fn guardrails_flag_costly_statements() {
+    assert_eq!(rules("UPDATE users SET name = 'x'"), ["update-without-where"]);
+    assert_eq!(rules("DELETE FROM sessions"), ["delete-without-where"]);
+    assert_eq!(rules("EXPLAIN DELETE FROM sessions"), ["delete-without-where"]);
+    assert_eq!(rules("SELECT id FROM users WHERE name LIKE '%son'"), ["leading-wildcard-like"]);
+    assert_eq!(rules("DELETE FROM users WHERE name LIKE '_x'"), ["leading-wildcard-like"]);
+    for sql in ["UPDATE users SET name = 'x' WHERE id = 1", "SELECT id FROM users WHERE name LIKE 'jo%'"] {
+        assert_eq!(rules(sql), Vec::<&str>::new(), "{}", sql);
+    }
+    // What's large or indexed is the config's to say
+    assert_eq!(rules("SELECT id FROM events WHERE LOWER(email) = 'a'"), Vec::<&str>::new());
+    let config = LintConfig {
+        large_tables: vec!["events".to_string()],
+        indexed_columns: vec!["events.email".to_string(), "users.id".to_string()],
+        ..LintConfig::default()
+    };
+    let configured = |sql: &str| -> Vec<&'static str> {
+        lint_with_config(&parse_sql(sql).unwrap(), &config).into_iter().map(|finding| finding.rule).collect()
+    };
+    let wrapped = "SELECT id FROM events WHERE LOWER(email) = 'a'";
+    assert_eq!(configured(wrapped), ["unbounded-select", "function-on-indexed-column"]);
+    assert_eq!(configured("SELECT id FROM events e WHERE e.email = 'a' ORDER BY id LIMIT 10"), Vec::<&str>::new());
+    let cast = "SELECT id FROM users u JOIN orders o ON CAST(u.id AS VARCHAR(10)) = o.user_id";
+    assert_eq!(configured(cast), ["function-on-indexed-column"]);
+    assert_eq!(configured("SELECT id FROM orders WHERE LOWER(email) = 'a'"), Vec::<&str>::new());
+
+    // Each rule can be made an error, a warning, or turned off
+    let mut config = LintConfig::default();
+    config.set_level("update-without-where=warn").unwrap();
+    config.set_level("delete-without-where = off").unwrap();
+    let findings = lint_with_config(&parse_sql("UPDATE users SET name = 'x'").unwrap(), &config);
+    assert_eq!(findings[0].severity, Severity::Warning);
+    assert!(lint_with_config(&parse_sql("DELETE FROM users").unwrap(), &config).is_empty());
+    assert!(config.set_level("no-such-rule=error").is_err());
+    assert!(config.set_level("select-star=loud").is_err());
+    assert_eq!(Severity::from_name("WARN"), Some(Severity::Warning));
+}
@@ -0,0 +1,40 @@
+// logictest.rs — runs the bundled sqllogictest files against the engine
+// Author: Sreerag Devadasan
+
+use std::path::Path;
+
+use sreerag_devadasan::logictest::{run, run_file, LogicTestReport};
+
+fn bundled(name: &str) -> LogicTestReport {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("logictest").join(name);
+    run_file(&path).unwrap()
+}
+
+#[test]
+fn bundled_files_pass() {
+    let report = bundled("engine.slt");
+    assert!(report.is_ok(), "{}", report);
+    assert_eq!(report.total(), 21);
+}
+
+#[test]
+fn values_and_derived_tables() {
+    let report = bundled("values.slt");
+    assert!(report.is_ok(), "{}", report);
+    assert_eq!(report.total(), 9);
+}
+
+#[test]
+fn transactions_roll_back() {
+    let report = bundled("transactions.slt");
+    assert!(report.is_ok(), "{}", report);
+    assert_eq!(report.total(), 21);
+}
+
+#[test]
+fn mismatches_are_reported_with_their_location() {
+    let text = "statement ok\nCREATE TABLE t (a INT)\n\nstatement ok\nINSERT INTO t VALUES (1)\n\nquery I\nSELECT a FROM t\n----\n2\n";
+    let report = run("inline", text);
+    assert_eq!(report.passed, 2);
+    assert_eq!(report.failures, vec![("inline:7".to_string(), "expected [2], found [1]".to_string())]);
+}
@@ -0,0 +1,108 @@
+// metrics.rs — checks the metrics registry, its HTTP endpoint and the facade
+// Author: Sreerag Devadasan
+
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::thread;
+
+use sreerag_devadasan::engine::Database;
+use sreerag_devadasan::metrics::{self, handle_request, Metrics};
+use sreerag_devadasan::proxy::{Proxy, ProxyPolicy};
+use sreerag_devadasan::session::ParserSession;
+
+#[test]
+fn registry_renders_prometheus_text() {
+    let registry = Metrics::new();
+    registry.increment("errors_total", "Errors", &[("code", "a \"b\"")], 2);
+    registry.increment("errors_total", "Errors", &[("code", "c")], 1);
+    registry.observe("latency_seconds", "Latency", &[], 0.002);
+    registry.observe("latency_seconds", "Latency", &[], 7.0);
+    // A name keeps its kind
+    registry.increment("latency_seconds", "Latency", &[("x", "y")], 1);
+
+    assert_eq!(registry.count("errors_total", &[("code", "c")]), 1);
+    assert_eq!(registry.count("latency_seconds", &[]), 2);
+    assert_eq!(registry.count("latency_seconds", &[("x", "y")]), 0);
+    let buckets: String = [
+        ("0.00001", 0),
+        ("0.00005", 0),
+        ("0.0001", 0),
+        ("0.0005", 0),
+        ("0.001", 0),
+        ("0.005", 1),
+        ("0.01", 1),
+        ("0.05", 1),
+        ("0.1", 1),
+        ("0.5", 1),
+        ("1", 1),
+        ("5", 1),
+        ("+Inf", 2),
+    ]
+    .iter()
+    .map(|(bound, n)| format!("latency_seconds_bucket{{le=\"{}\"}} {}\n", bound, n))
+    .collect();
+    let expected = format!(
+        "# HELP errors_total Errors\n\
+         # TYPE errors_total counter\n\
+         errors_total{{code=\"a \\\"b\\\"\"}} 2\n\
+         errors_total{{code=\"c\"}} 1\n\
+         # HELP latency_seconds Latency\n\
+         # TYPE latency_seconds histogram\n\
+         {}latency_seconds_sum 7.002\n\
+         latency_seconds_count 2\n",
+        buckets
+    );
+    assert_eq!(registry.render(), expected);
+}
+
+/// Sends one HTTP request to a fresh endpoint and returns the response.
+fn request(registry: &Metrics, request: &str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let registry = registry.clone();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        handle_request(stream, &registry).unwrap();
+    });
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    stream.shutdown(Shutdown::Write).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn endpoint_serves_metrics() {
+    let registry = Metrics::new();
+    registry.increment("up", "Whether the server is up", &[], 1);
+
+    let response = request(&registry, "GET /metrics?x=1 HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"), "{}", head);
+    assert!(head.contains("Content-Type: text/plain; version=0.0.4"));
+    assert!(head.contains(&format!("Content-Length: {}", body.len())));
+    assert_eq!(body, "# HELP up Whether the server is up\n# TYPE up counter\nup 1\n");
+
+    assert!(request(&registry, "HEAD /metrics HTTP/1.1\r\n\r\n").ends_with("\r\n\r\n"));
+    assert!(request(&registry, "GET / HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 404"));
+    assert!(request(&registry, "POST /metrics HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 405"));
+    assert!(request(&registry, "\r\n\r\n").starts_with("HTTP/1.1 400"));
+}
+
+#[test]
+fn installed_registry_counts_parses_and_queries() {
+    let registry = Metrics::new();
+    metrics::install(registry.clone()).unwrap();
+    assert!(metrics::install(Metrics::new()).is_err());
+
+    let mut proxy = Proxy::new(ParserSession::default(), ProxyPolicy::default(), Database::new());
+    for sql in ["CREATE TABLE t (id INT)", "INSERT INTO t VALUES (1)", "SELECT id FROM missing", "SELECT id FROM"] {
+        let _ = proxy.execute(sql);
+    }
+    assert_eq!(registry.count(metrics::STATEMENTS_PARSED, &[]), 3);
+    assert_eq!(registry.count(metrics::PARSE_ERRORS, &[("code", "unexpected")]), 1);
+    assert_eq!(registry.count(metrics::PARSE_DURATION, &[]), 4);
+    assert_eq!(registry.count(metrics::QUERY_DURATION, &[]), 3);
+    assert_eq!(registry.count(metrics::QUERY_ERRORS, &[]), 1);
+}
@@ -0,0 +1,101 @@
+// migrations.rs — checks the migrations command's report, JSON and exit status
+// Author: Sreerag Devadasan
+
+#![cfg(feature = "std")]
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// A fresh directory holding `files`.
+fn migrations(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = env::temp_dir().join(format!("sreerag-migrations-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (file, sql) in files {
+        fs::write(dir.join(file), sql).unwrap();
+    }
+    dir
+}
+
+fn run(dir: &Path, flags: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_sreerag_devadasan")).arg("migrations").arg(dir).args(flags).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn clean_migrations_exit_zero() {
+    let dir = migrations(
+        "clean",
+        &[
+            ("0002_orders.sql", "CREATE TABLE orders (id INT);\nDROP TABLE IF EXISTS legacy;"),
+            ("0001_users.sql", "CREATE TABLE users (id INT, name VARCHAR(20))"),
+            ("README.md", "not a migration"),
+        ],
+    );
+    let output = run(&dir, &[]);
+    assert_eq!(output.status.code(), Some(0));
+    let text = stdout(&output);
+    assert!(text.contains("0001_users.sql (1 statements)"), "{}", text);
+    assert!(text.find("0001_users").unwrap() < text.find("0002_orders").unwrap(), "{}", text);
+    assert!(text.trim_end().ends_with("2 files checked, 0 with problems"), "{}", text);
+
+    let output = run(&dir, &["--json", "--ddl-only"]);
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let json = stdout(&output);
+    assert!(json.starts_with(r#"{"ok":true,"files":[{"path":"#), "{}", json);
+    assert!(json.contains(r#""number":2,"statements":2,"ok":true,"diagnostics":[]"#), "{}", json);
+}
+
+#[test]
+fn problems_exit_one_and_are_in_the_json() {
+    let dir = migrations(
+        "problems",
+        &[
+            ("0001_init.sql", "CREATE TABLE users (id INT);\nDROP TABLE orders;\nSELECT FROM;"),
+            ("1_again.sql", "SELECT id FROM users"),
+            ("seed.sql", "INSERT INTO users VALUES (1)"),
+        ],
+    );
+    let output = run(&dir, &[]);
+    assert_eq!(output.status.code(), Some(1));
+    let text = stdout(&output);
+    assert!(text.contains("    statement 2: DROP TABLE orders without IF EXISTS"), "{}", text);
+    assert!(text.contains("    statement 3: parse error: "), "{}", text);
+    assert!(text.contains("    file name does not start with a migration number"), "{}", text);
+    assert!(text.trim_end().ends_with("3 files checked, 3 with problems"), "{}", text);
+
+    let output = run(&dir, &["--json", "--ddl-only"]);
+    assert_eq!(output.status.code(), Some(1));
+    let json = stdout(&output);
+    assert!(json.starts_with(r#"{"ok":false,"#), "{}", json);
+    assert!(json.contains(r#"{"statement":2,"message":"DROP TABLE orders without IF EXISTS"}"#), "{}", json);
+    assert!(json.contains(r#"{"statement":1,"message":"SELECT is not allowed in a DDL-only migration"}"#), "{}", json);
+    assert!(json.contains(r#""message":"migration number 1 is also used by "#), "{}", json);
+    assert!(json.contains(r#""number":null,"statements":1,"ok":false"#), "{}", json);
+
+    // Only the DDL-only check fails a SELECT
+    fs::write(dir.join("0001_init.sql"), "CREATE TABLE users (id INT)").unwrap();
+    fs::remove_file(dir.join("seed.sql")).unwrap();
+    fs::rename(dir.join("1_again.sql"), dir.join("0002_again.sql")).unwrap();
+    assert_eq!(run(&dir, &[]).status.code(), Some(0));
+    assert_eq!(run(&dir, &["--ddl-only"]).status.code(), Some(1));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_directory_that_cant_be_read_or_a_missing_one_is_an_error() {
+    let missing = env::temp_dir().join(format!("sreerag-migrations-{}-missing", std::process::id()));
+    let output = run(&missing, &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("Couldn't read"));
+    assert!(output.stdout.is_empty());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_sreerag_devadasan")).arg("migrations").output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,35 @@
+// minify.rs — checks the one-line canonical form of queries
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::canonical::minify_sql;
+use sreerag_devadasan::dialect::Dialect;
+
+#[test]
+fn equivalent_queries_minify_to_the_same_text() {
+    let messy = "select  a,\n  b -- the columns\nfrom t /* all of them */ where (b > 1) and 5 < a";
+    let tidy = "SELECT a, b FROM t WHERE a > 5 AND b > 1";
+    assert_eq!(minify_sql(messy, Dialect::Generic).unwrap(), tidy);
+    assert_eq!(minify_sql(tidy, Dialect::Generic).unwrap(), tidy);
+}
+
+#[test]
+fn statements_other_than_queries_minify_their_expressions() {
+    for (sql, tidy) in [
+        ("delete from t where b = 2 and a = 1", "DELETE FROM t WHERE a = 1 AND b = 2"),
+        ("update t set c = (1 + x) where 5 < a", "UPDATE t SET c = x + 1 WHERE a > 5"),
+        ("insert into t values (b * a, 1)", "INSERT INTO t VALUES (a * b, 1)"),
+        ("insert into t select a from u where b = 2 and a = 1", "INSERT INTO t SELECT a FROM u WHERE a = 1 AND b = 2"),
+        ("explain select a from t where 2 < b", "EXPLAIN SELECT a FROM t WHERE b > 2"),
+    ] {
+        assert_eq!(minify_sql(sql, Dialect::Generic).unwrap(), tidy, "{}", sql);
+    }
+    // The assignments of an UPDATE stay in the order they were written
+    let sql = "UPDATE t SET b = 1, a = b";
+    assert_eq!(minify_sql(sql, Dialect::Generic).unwrap(), sql);
+}
+
+#[test]
+fn minified_text_follows_the_dialect() {
+    let sql = "select name from users limit 5";
+    assert_eq!(minify_sql(sql, Dialect::SqlServer).unwrap(), "SELECT TOP 5 name FROM users");
+}
@@ -0,0 +1,29 @@
+// mmap.rs — checks parsing scripts from memory-mapped files
+// Author: Sreerag Devadasan
+
+#![cfg(all(feature = "mmap", unix))]
+
+use std::fs;
+use std::io;
+
+use sreerag_devadasan::batch::parse_file_parallel;
+use sreerag_devadasan::mmap::{parse_file_mapped, MappedFile};
+
+#[test]
+fn mapped_files_parse_like_read_ones() {
+    let dir = std::env::temp_dir();
+    let path = dir.join(format!("sreerag-mmap-{}.sql", std::process::id()));
+    fs::write(&path, "SELECT a FROM t;\nSELEC b;\nINSERT INTO t VALUES (1);\n").unwrap();
+    let mapped = parse_file_mapped(&path).unwrap();
+    assert_eq!(mapped.len(), 3);
+    assert!(mapped[0].is_ok() && mapped[1].is_err() && mapped[2].is_ok());
+    assert_eq!(format!("{:?}", mapped), format!("{:?}", parse_file_parallel(&path).unwrap()));
+
+    fs::write(&path, "").unwrap();
+    assert_eq!(MappedFile::open(&path).unwrap().as_str().unwrap(), "");
+    fs::write(&path, [0xff, 0xfe]).unwrap();
+    let error = parse_file_mapped(&path).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    fs::remove_file(&path).unwrap();
+    assert!(MappedFile::open(&path).is_err());
+}
@@ -0,0 +1,83 @@
+// mysqlwire.rs — checks the fake MySQL server over a real socket
+// Author: Sreerag Devadasan
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::thread;
+
+use sreerag_devadasan::engine::Database;
+use sreerag_devadasan::mysqlwire::handle_connection;
+
+/// Serves one connection on an ephemeral port and returns a client socket
+/// past the handshake.
+fn connect() -> TcpStream {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        handle_connection(stream, &Mutex::new(Database::new()), 7).unwrap();
+    });
+    let mut stream = TcpStream::connect(address).unwrap();
+
+    let (sequence, greeting) = receive(&mut stream);
+    assert_eq!((sequence, greeting[0]), (0, 10));
+    assert!(greeting.ends_with(b"mysql_native_password\0"));
+    // HandshakeResponse41: capabilities, max packet, charset, filler, user
+    let mut response = 0x000f_a205u32.to_le_bytes().to_vec();
+    response.extend([0, 0, 0, 1, 33]);
+    response.extend([0; 23]);
+    response.extend(b"root\0\0");
+    send(&mut stream, 1, &response);
+    assert_eq!(receive(&mut stream), (2, vec![0, 0, 0, 2, 0, 0, 0]));
+    stream
+}
+
+fn send(stream: &mut TcpStream, sequence: u8, payload: &[u8]) {
+    let length = (payload.len() as u32).to_le_bytes();
+    stream.write_all(&[length[0], length[1], length[2], sequence]).unwrap();
+    stream.write_all(payload).unwrap();
+}
+
+fn receive(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+    let mut header = [0; 4];
+    stream.read_exact(&mut header).unwrap();
+    let mut payload = vec![0; u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize];
+    stream.read_exact(&mut payload).unwrap();
+    (header[3], payload)
+}
+
+fn query(stream: &mut TcpStream, sql: &str) -> Vec<u8> {
+    let mut payload = vec![0x03];
+    payload.extend(sql.as_bytes());
+    send(stream, 0, &payload);
+    receive(stream).1
+}
+
+#[test]
+fn text_queries_return_result_sets() {
+    let mut stream = connect();
+    assert_eq!(query(&mut stream, "CREATE TABLE t (id INT, name VARCHAR(10))")[0], 0x00);
+    // OK with two affected rows
+    assert_eq!(query(&mut stream, "INSERT INTO t VALUES (1, 'a'), (2, NULL)")[..2], [0x00, 2]);
+
+    assert_eq!(query(&mut stream, "SELECT id, name FROM t ORDER BY id"), [2]);
+    let (_, id) = receive(&mut stream);
+    assert!(id.starts_with(b"\x03def\0\0\0\x02id\x02id"));
+    receive(&mut stream);
+    assert_eq!(receive(&mut stream).1[0], 0xfe);
+    assert_eq!(receive(&mut stream), (5, b"\x011\x01a".to_vec()));
+    assert_eq!(receive(&mut stream), (6, b"\x012\xfb".to_vec()));
+    assert_eq!(receive(&mut stream).1[0], 0xfe);
+}
+
+#[test]
+fn errors_carry_mysql_codes() {
+    let mut stream = connect();
+    let error = query(&mut stream, "SELECT * FROM missing");
+    assert_eq!(error[..9], *b"\xff\x7a\x04#42S02");
+
+    // The session survives the error
+    send(&mut stream, 0, &[0x0e]);
+    assert_eq!(receive(&mut stream).1[0], 0x00);
+}
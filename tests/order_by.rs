@@ -0,0 +1,96 @@
+// order_by.rs — checks that ORDER BY items are parsed as expressions with a direction, and sorted by the engine
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::ast::{BinaryOperator, Expression, Ident, OrderByExpr, Statement};
+use sreerag_devadasan::engine::{Database, EngineError};
+use sreerag_devadasan::eval::{EvalError, Value};
+use sreerag_devadasan::parser::parse_sql;
+
+fn order_by(sql: &str) -> Vec<OrderByExpr> {
+    match parse_sql(sql).unwrap_or_else(|e| panic!("{}: {}", sql, e)) {
+        Statement::Select { order_by, .. } => order_by,
+        other => panic!("{}: not a SELECT: {:?}", sql, other),
+    }
+}
+
+fn identifier(name: &str) -> Expression {
+    Expression::Identifier(Ident::new(name))
+}
+
+fn database() -> Database {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE t (id INT, name VARCHAR(10), score INT)").unwrap();
+    db.execute_sql("INSERT INTO t VALUES (1, 'b', 20), (2, 'a', 10), (3, 'b', 10), (4, NULL, 30)").unwrap();
+    db
+}
+
+fn ids(db: &mut Database, sql: &str) -> Vec<i64> {
+    let rows = db.execute_sql(sql).unwrap_or_else(|e| panic!("{}: {}", sql, e)).rows;
+    rows.iter()
+        .map(|row| match row[0] {
+            Value::Int(id) => id,
+            ref other => panic!("{}: {:?}", sql, other),
+        })
+        .collect()
+}
+
+#[test]
+fn items_are_expressions_each_with_its_own_direction() {
+    let items = order_by("SELECT a FROM t ORDER BY a DESC, b, c + 1 ASC");
+    let sum = Expression::BinaryOperation {
+        left_operand: Box::new(identifier("c")),
+        operator: BinaryOperator::Add,
+        right_operand: Box::new(Expression::Number(1)),
+    };
+    assert_eq!(
+        items,
+        [
+            OrderByExpr { expr: identifier("a"), asc: Some(false) },
+            OrderByExpr { expr: identifier("b"), asc: None },
+            OrderByExpr { expr: sum, asc: Some(true) },
+        ]
+    );
+    assert!(order_by("SELECT a FROM t").is_empty());
+
+    for sql in [
+        "SELECT a FROM t ORDER BY a DESC, b",
+        "SELECT a FROM t ORDER BY a + 1 DESC LIMIT 3",
+        "SELECT a FROM t ORDER BY name COLLATE nocase ASC, lower(b), t.c",
+        "SELECT a FROM (SELECT a FROM t ORDER BY a DESC) AS x",
+    ] {
+        assert_eq!(parse_sql(sql).unwrap().to_string(), sql);
+    }
+    // Items are separated by commas, and only row limiting may follow them
+    for sql in [
+        "SELECT a FROM t ORDER BY",
+        "SELECT a FROM t ORDER BY a b",
+        "SELECT a FROM t ORDER BY a,",
+        "SELECT a FROM t ORDER BY a DESC DESC",
+        "SELECT a FROM t ORDER BY a WHERE a = 1",
+    ] {
+        assert!(parse_sql(sql).is_err(), "{}", sql);
+    }
+}
+
+#[test]
+fn the_engine_sorts_by_each_key_in_its_direction() {
+    let mut db = database();
+    assert_eq!(ids(&mut db, "SELECT id FROM t ORDER BY name DESC, id"), [1, 3, 2, 4]);
+    assert_eq!(ids(&mut db, "SELECT id FROM t ORDER BY score, name DESC"), [3, 2, 1, 4]);
+    // NULL sorts first ascending, so last descending
+    assert_eq!(ids(&mut db, "SELECT id FROM t ORDER BY name, id DESC"), [4, 2, 3, 1]);
+    assert_eq!(ids(&mut db, "SELECT id FROM t ORDER BY id DESC LIMIT 2 OFFSET 1"), [3, 2]);
+
+    // Expressions are computed for each row, whether or not they are selected
+    assert_eq!(ids(&mut db, "SELECT id FROM t ORDER BY 10 - id"), [4, 3, 2, 1]);
+    assert_eq!(ids(&mut db, "SELECT id, score * 2 FROM t ORDER BY score * 2 DESC, id + 1"), [4, 1, 2, 3]);
+    assert_eq!(ids(&mut db, "SELECT id FROM t ORDER BY name || 'x', id"), [4, 2, 1, 3]);
+    let result = db.execute_sql("SELECT name FROM t WHERE score = 10 ORDER BY id * -1").unwrap();
+    assert_eq!(result.columns, ["name"]);
+    assert_eq!(result.rows, [[Value::String("b".into())], [Value::String("a".into())]]);
+
+    let error = db.execute_sql("SELECT id FROM t ORDER BY missing").unwrap_err();
+    assert!(matches!(error, EngineError::UnknownColumn(ref name) if name == "missing"), "{:?}", error);
+    let error = db.execute_sql("SELECT id FROM t ORDER BY missing + 1").unwrap_err();
+    assert!(matches!(error, EngineError::Eval(EvalError::UnknownColumn(_))), "{:?}", error);
+}
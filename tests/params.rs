@@ -0,0 +1,92 @@
+// params.rs — checks binding values to the placeholders of a statement
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::datetime::Date;
+use sreerag_devadasan::decimal::Decimal;
+use sreerag_devadasan::engine::{Database, EngineError, Storage};
+use sreerag_devadasan::eval::{evaluate, Value};
+use sreerag_devadasan::params::{literal, Parameters};
+use sreerag_devadasan::parser::parse_sql;
+
+fn database(storage: Storage) -> Database {
+    let mut db = Database::with_storage(storage);
+    db.execute_sql("CREATE TABLE users (id INT, name VARCHAR(40), joined DATE)").unwrap();
+    db
+}
+
+fn text(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+#[test]
+fn placeholders_of_each_style_are_parameters() {
+    let names = |sql: &str| Parameters::of(&parse_sql(sql).unwrap()).map(|p| p.names().to_vec());
+    assert_eq!(names("SELECT id FROM t WHERE a = ? AND b > ?").unwrap(), ["?1", "?2"]);
+    assert_eq!(names("SELECT id FROM t WHERE a = $2 OR b = $1 OR c = $2").unwrap(), ["$1", "$2"]);
+    assert_eq!(names("INSERT INTO t VALUES (:id, :name), (:id, :other)").unwrap(), [":id", ":name", ":other"]);
+    assert_eq!(names("SELECT id FROM t").unwrap(), Vec::<String>::new());
+    assert!(matches!(names("SELECT id FROM t WHERE a = ? AND b = $1"), Err(EngineError::MixedParameters)));
+    assert!(matches!(names("SELECT id FROM t WHERE a = $0"), Err(EngineError::UnknownParameter(_))));
+
+    // Every value has a literal that evaluates back to it
+    let date = Date::parse("2024-01-31").unwrap();
+    let values = [
+        Value::Null,
+        Value::Int(-5),
+        Value::Int(i64::MIN),
+        Value::Bool(true),
+        text("it's"),
+        Value::Decimal(Decimal::parse("-0.05").unwrap()),
+        Value::Date(date),
+    ];
+    for value in values {
+        assert_eq!(evaluate(&literal(&value), &[], &[]).unwrap(), value);
+    }
+}
+
+#[test]
+fn statements_run_with_their_values_bound() {
+    for storage in [Storage::Rows, Storage::Columnar] {
+        let mut db = database(storage);
+        let insert = parse_sql("INSERT INTO users VALUES (?, ?, ?)").unwrap();
+        for (id, name) in [(1, "ann"), (2, "bob'; DROP TABLE users; --"), (3, "carl")] {
+            let values = [Value::Int(id), text(name), text("2024-01-31")];
+            db.execute_with_params(&insert, &values).unwrap();
+        }
+        let select = parse_sql("SELECT id FROM users WHERE name = $1 OR id > $2 ORDER BY id").unwrap();
+        let rows = db.execute_with_params(&select, &[text("bob'; DROP TABLE users; --"), Value::Int(2)]).unwrap().rows;
+        assert_eq!(rows, [vec![Value::Int(2)], vec![Value::Int(3)]], "{:?}", storage);
+
+        // The bindings of one statement share its compiled plan
+        let select = parse_sql("SELECT name FROM users WHERE id = :id").unwrap();
+        let before = db.plan_cache_stats();
+        for id in 1..=3 {
+            let rows = db.execute_with_named_params(&select, &[("id", Value::Int(id))]).unwrap().rows;
+            assert_eq!(rows.len(), 1);
+        }
+        assert_eq!(db.plan_cache_stats().hits - before.hits, 2, "{:?}", storage);
+
+        let values = db.execute_with_params(&parse_sql("VALUES (? + 1, ?)").unwrap(), &[Value::Int(-3), Value::Null]);
+        assert_eq!(values.unwrap().rows, [vec![Value::Int(-2), Value::Null]]);
+    }
+}
+
+#[test]
+fn values_are_checked_against_count_names_and_types() {
+    let mut db = database(Storage::Rows);
+    let insert = parse_sql("INSERT INTO users (name, id) VALUES (?, ?)").unwrap();
+    let error = db.execute_with_params(&insert, &[text("ann")]).unwrap_err();
+    assert_eq!(error.to_string(), "Expected 2 parameter values, found 1");
+    let error = db.execute_with_params(&insert, &[text("ann"), text("one")]).unwrap_err();
+    assert_eq!(error.to_string(), "Parameter ?2 is for column id, which can't hold String(\"one\")");
+    assert!(db.table("users").unwrap().rows().is_empty());
+
+    let select = parse_sql("SELECT id FROM users WHERE joined < :day OR :day = joined").unwrap();
+    let error = db.execute_with_named_params(&select, &[("day", text("soon"))]).unwrap_err();
+    assert!(matches!(error, EngineError::ParameterType { ref column, .. } if column == "joined"));
+    let error = db.execute_with_named_params(&select, &[]).unwrap_err();
+    assert_eq!(error.to_string(), "No value for parameter :day");
+    let error = db.execute_with_named_params(&select, &[("day", Value::Null), ("month", Value::Null)]).unwrap_err();
+    assert_eq!(error.to_string(), "No such parameter: month");
+    assert!(db.execute_with_named_params(&select, &[(":day", text("2024-02-01"))]).unwrap().rows.is_empty());
+}
@@ -0,0 +1,120 @@
+// pgwire.rs — checks the fake Postgres server over a real socket
+// Author: Sreerag Devadasan
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::thread;
+
+use sreerag_devadasan::engine::Database;
+use sreerag_devadasan::pgwire::handle_connection;
+
+/// Serves one connection on an ephemeral port and returns a client socket.
+fn connect() -> TcpStream {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        handle_connection(stream, &Mutex::new(Database::new())).unwrap();
+    });
+    TcpStream::connect(address).unwrap()
+}
+
+/// Reads one backend message: its tag and body.
+fn receive(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+    let mut header = [0; 5];
+    stream.read_exact(&mut header).unwrap();
+    let length = i32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    let mut body = vec![0; length - 4];
+    stream.read_exact(&mut body).unwrap();
+    (header[0], body)
+}
+
+/// Reads messages up to and including ReadyForQuery, returning their tags.
+fn receive_until_ready(stream: &mut TcpStream) -> Vec<(u8, Vec<u8>)> {
+    let mut messages = Vec::new();
+    loop {
+        let message = receive(stream);
+        let ready = message.0 == b'Z';
+        messages.push(message);
+        if ready {
+            return messages;
+        }
+    }
+}
+
+fn query(stream: &mut TcpStream, sql: &str) -> Vec<(u8, Vec<u8>)> {
+    let mut message = vec![b'Q'];
+    message.extend(((sql.len() + 5) as i32).to_be_bytes());
+    message.extend(sql.as_bytes());
+    message.push(0);
+    stream.write_all(&message).unwrap();
+    receive_until_ready(stream)
+}
+
+fn tags(messages: &[(u8, Vec<u8>)]) -> String {
+    messages.iter().map(|(tag, _)| *tag as char).collect()
+}
+
+fn start(stream: &mut TcpStream) {
+    // SSLRequest is refused with a single 'N'
+    stream.write_all(&[0, 0, 0, 8, 4, 210, 22, 47]).unwrap();
+    let mut answer = [0; 1];
+    stream.read_exact(&mut answer).unwrap();
+    assert_eq!(&answer, b"N");
+
+    let mut body = 196_608i32.to_be_bytes().to_vec();
+    body.extend(b"user\0test\0\0");
+    let mut startup = ((body.len() + 4) as i32).to_be_bytes().to_vec();
+    startup.extend(body);
+    stream.write_all(&startup).unwrap();
+    let messages = receive_until_ready(stream);
+    assert_eq!(messages[0], (b'R', vec![0, 0, 0, 0]));
+}
+
+#[test]
+fn simple_queries_return_text_rows() {
+    let mut stream = connect();
+    start(&mut stream);
+
+    let setup = "CREATE TABLE t (id INT, name VARCHAR(10)); INSERT INTO t VALUES (1, 'a'), (2, NULL)";
+    let messages = query(&mut stream, setup);
+    assert_eq!(tags(&messages), "CCZ");
+    assert_eq!(messages[1].1, b"INSERT 0 2\0");
+
+    let messages = query(&mut stream, "SELECT id, name FROM t ORDER BY id");
+    assert_eq!(tags(&messages), "TDDCZ");
+    assert_eq!(messages[1].1, [&[0, 2][..], &[0, 0, 0, 1], b"1", &[0, 0, 0, 1], b"a"].concat());
+    assert_eq!(messages[2].1, [&[0, 2][..], &[0, 0, 0, 1], b"2", &[255, 255, 255, 255]].concat());
+    assert_eq!(messages[3].1, b"SELECT 2\0");
+}
+
+#[test]
+fn errors_stop_the_query_and_keep_the_session() {
+    let mut stream = connect();
+    start(&mut stream);
+
+    let messages = query(&mut stream, "SELECT * FROM missing; CREATE TABLE t (id INT)");
+    assert_eq!(tags(&messages), "EZ");
+    let fields = String::from_utf8_lossy(&messages[0].1).into_owned();
+    assert!(fields.contains("C42P01\0"), "{:?}", fields);
+
+    assert_eq!(tags(&query(&mut stream, "")), "IZ");
+    // The CREATE after the error didn't run
+    assert_eq!(tags(&query(&mut stream, "CREATE TABLE t (id INT)")), "CZ");
+}
+
+#[test]
+fn ready_for_query_reports_the_transaction_status() {
+    let mut stream = connect();
+    start(&mut stream);
+
+    let messages = query(&mut stream, "CREATE TABLE t (id INT); BEGIN; INSERT INTO t VALUES (1)");
+    assert_eq!(tags(&messages), "CCCZ");
+    assert_eq!(messages[1].1, b"BEGIN\0");
+    assert_eq!(messages[3].1, b"T");
+
+    let messages = query(&mut stream, "ROLLBACK");
+    assert_eq!((messages[0].1.as_slice(), messages[1].1.as_slice()), (&b"ROLLBACK\0"[..], &b"I"[..]));
+    assert_eq!(tags(&query(&mut stream, "SELECT id FROM t")), "TCZ");
+}
@@ -0,0 +1,83 @@
+// plan_cache.rs — checks that queries differing only in literals share a compiled plan
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::ast::{BinaryOperator, Expression, Ident};
+use sreerag_devadasan::bytecode::Program;
+use sreerag_devadasan::engine::{Database, Storage};
+use sreerag_devadasan::eval::Value;
+use sreerag_devadasan::plan_cache::CacheStats;
+
+fn database(storage: Storage) -> Database {
+    let mut db = Database::with_storage(storage);
+    db.execute_sql("CREATE TABLE t (id INT, name VARCHAR(5))").unwrap();
+    db.execute_sql("INSERT INTO t VALUES (1, 'a'), (2, 'b'), (3, 'c'), (4, 'd')").unwrap();
+    db
+}
+
+fn ids(db: &mut Database, sql: &str) -> Vec<Value> {
+    db.execute_sql(sql).unwrap().rows.into_iter().map(|row| row[0].clone()).collect()
+}
+
+#[test]
+fn queries_differing_in_literals_reuse_one_plan() {
+    for storage in [Storage::Rows, Storage::Columnar] {
+        let mut db = database(storage);
+        let sql = "SELECT id FROM t WHERE id > 1 AND name != 'c' ORDER BY id";
+        assert_eq!(ids(&mut db, sql), [Value::Int(2), Value::Int(4)]);
+        assert_eq!(ids(&mut db, "select id from t where id > 3 and name != 'z' order by id"), [Value::Int(4)]);
+        assert_eq!(ids(&mut db, "SELECT id FROM t WHERE id > 0 AND name != 'a' ORDER BY id").len(), 3);
+        assert_eq!(db.plan_cache_stats(), CacheStats { hits: 2, misses: 1, evictions: 0, entries: 1 });
+    }
+
+    // A table of the same name with other columns needs a plan of its own
+    let mut db = database(Storage::Rows);
+    ids(&mut db, "SELECT name FROM t WHERE id = 1");
+    db.execute_sql("DROP TABLE t").unwrap();
+    db.execute_sql("CREATE TABLE t (name VARCHAR(5), id INT)").unwrap();
+    db.execute_sql("INSERT INTO t VALUES ('x', 1)").unwrap();
+    assert_eq!(ids(&mut db, "SELECT name FROM t WHERE id = 1"), [Value::String("x".to_string())]);
+    assert_eq!((db.plan_cache_stats().hits, db.plan_cache_stats().misses), (0, 2));
+
+    // Typed literals are parameters too
+    db.execute_sql("CREATE TABLE d (id INT, day DATE)").unwrap();
+    db.execute_sql("INSERT INTO d VALUES (1, DATE '2024-01-01'), (2, DATE '2024-02-01')").unwrap();
+    assert_eq!(ids(&mut db, "SELECT id FROM d WHERE day > DATE '2024-01-15'"), [Value::Int(2)]);
+    assert_eq!(ids(&mut db, "SELECT id FROM d WHERE day > DATE '2023-12-31'"), [Value::Int(1), Value::Int(2)]);
+    assert_eq!((db.plan_cache_stats().hits, db.plan_cache_stats().misses), (1, 3));
+
+    // A WHERE clause the bytecode can't run is still evaluated, and still fails the same way
+    assert!(db.execute_sql("SELECT id FROM t WHERE upper(name) = 'X'").is_err());
+    assert!(db.execute_sql("SELECT id FROM t WHERE id = :id").is_err());
+}
+
+#[test]
+fn the_least_recently_used_plan_is_evicted() {
+    let mut db = database(Storage::Rows);
+    db.set_plan_cache_capacity(2);
+    ids(&mut db, "SELECT id FROM t WHERE id = 1");
+    ids(&mut db, "SELECT id FROM t WHERE name = 'a'");
+    ids(&mut db, "SELECT id FROM t WHERE id = 2");
+    // The plan by name was used less recently than the one by id, so it makes room
+    ids(&mut db, "SELECT name FROM t");
+    ids(&mut db, "SELECT id FROM t WHERE id = 3");
+    ids(&mut db, "SELECT id FROM t WHERE name = 'b'");
+    assert_eq!(db.plan_cache_stats(), CacheStats { hits: 2, misses: 4, evictions: 2, entries: 2 });
+
+    db.set_plan_cache_capacity(0);
+    assert_eq!(ids(&mut db, "SELECT id FROM t WHERE id = 4"), [Value::Int(4)]);
+    assert_eq!(db.plan_cache_stats().entries, 0);
+
+    // A template's parameters take their values when bound
+    let template = Expression::BinaryOperation {
+        left_operand: Box::new(Expression::Identifier(Ident::new("id"))),
+        operator: BinaryOperator::GreaterThan,
+        right_operand: Box::new(Expression::Placeholder(":number".to_string())),
+    };
+    let columns = ["id".to_string()];
+    assert!(Program::compile(&template, &columns).is_err());
+    let program = Program::compile_template(&template, &columns).unwrap();
+    assert_eq!(program.parameters(), 1);
+    let bound = program.bind(&[Value::Int(2)]);
+    assert_eq!(bound.evaluate(&[Value::Int(3)]).unwrap(), Value::Bool(true));
+    assert_eq!(bound.evaluate(&[Value::Int(1)]).unwrap(), Value::Bool(false));
+}
@@ -0,0 +1,127 @@
+// planner.rs — checks table statistics and the row counts the planner estimates from them
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::engine::Database;
+use sreerag_devadasan::eval::Value;
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::planner::{plan, plan_as_written, Plan, DEFAULT_ROWS};
+use sreerag_devadasan::stats::{Histogram, Statistics, TableStats};
+
+const STATS: &str = "
+# table rows=N [column=distinct ...]
+users rows=10000 id=10000 city=40
+orders rows=50000 user_id=8000
+countries rows=20 id=20
+users.age histogram=20,30,40,50,60
+";
+
+fn explain(sql: &str, statistics: &Statistics) -> Plan {
+    plan(&parse_sql(sql).unwrap(), statistics).unwrap()
+}
+
+#[test]
+fn statistics_are_read_per_table() {
+    let statistics = Statistics::parse(STATS).unwrap();
+    let users = statistics.table("USERS").unwrap();
+    assert_eq!((users.rows, users.column("City").unwrap().distinct), (10000, 40));
+    let histogram = users.column("age").unwrap().histogram.as_ref().unwrap();
+    assert_eq!(histogram.fraction_below(35), 0.375);
+    assert_eq!((histogram.fraction_below(10), histogram.fraction_below(70)), (0.0, 1.0));
+
+    let error = Statistics::parse("users rows=many").unwrap_err();
+    assert_eq!(error.to_string(), "line 1: not a count: many");
+    assert!(Statistics::parse("users.age histogram=5,1").is_err());
+
+    let mut values = vec![5, 1, 4, 2, 3];
+    assert_eq!(Histogram::from_values(&mut values, 2), Some(Histogram { bounds: vec![1, 3, 5] }));
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE t (id INT, name VARCHAR(5))").unwrap();
+    db.execute_sql("INSERT INTO t VALUES (1, 'a'), (2, 'a'), (3, NULL)").unwrap();
+    let collected = TableStats::collect(db.table("t").unwrap(), 2);
+    assert_eq!(collected.rows, 3);
+    assert_eq!((collected.column("id").unwrap().distinct, collected.column("name").unwrap().distinct), (3, 1));
+    assert!(collected.column("name").unwrap().histogram.is_none());
+}
+
+#[test]
+fn filters_and_joins_are_estimated_from_statistics() {
+    let statistics = Statistics::parse(STATS).unwrap();
+    let sql = "SELECT id FROM users u JOIN orders o ON u.id = o.user_id WHERE age > 30 AND city = 'Paris' LIMIT 10";
+    let expected = "\
+Limit 10  (rows=10 cost=110916)
+  -> Filter age > 30 AND city = 'Paris'  (rows=906 cost=110906)
+       -> Join INNER ON u.id = o.user_id  (rows=50000 cost=110000)
+            -> Scan users AS u  (rows=10000 cost=10000)
+            -> Scan orders AS o  (rows=50000 cost=50000)
+";
+    assert_eq!(explain(sql, &statistics).to_string(), expected);
+
+    // Without a histogram a range keeps a third, and unknown tables have the default rows
+    let rows = |sql: &str| explain(sql, &statistics).rows;
+    assert_eq!(rows("SELECT id FROM orders WHERE user_id < 10"), 16667);
+    assert_eq!(rows("SELECT id FROM users WHERE 40 <= age OR city = 'Oslo'"), 5125);
+    assert_eq!(rows("SELECT id FROM events"), DEFAULT_ROWS);
+    assert_eq!(rows("SELECT id FROM users CROSS JOIN events"), 10_000_000);
+    assert_eq!(rows("SELECT id FROM users LEFT JOIN events ON users.id = events.id"), 10000);
+    assert_eq!(rows("SELECT city FROM users GROUP BY city"), 40);
+    assert_eq!(rows("SELECT id FROM users UNION ALL SELECT id FROM orders"), 60000);
+    assert_eq!(rows("WITH big AS (SELECT id FROM orders) SELECT id FROM big WHERE id = 1"), 5000);
+    assert!(plan(&parse_sql("DROP TABLE users").unwrap(), &statistics).is_none());
+}
+
+#[test]
+fn inner_joins_are_put_in_the_cheapest_order() {
+    let statistics = Statistics::parse(STATS).unwrap();
+    // Written, users and orders are crossed before the small countries table narrows them
+    let sql = "SELECT id FROM users u CROSS JOIN orders o JOIN countries c ON c.id = u.country AND c.id = o.country";
+    let chosen = explain(sql, &statistics);
+    let written = plan_as_written(&parse_sql(sql).unwrap(), &statistics).unwrap();
+    let expected = "\
+Join INNER ON c.id = o.country  (rows=25000000 cost=25070020)
+  -> Join INNER ON c.id = u.country  (rows=10000 cost=20020)
+       -> Scan users AS u  (rows=10000 cost=10000)
+       -> Scan countries AS c  (rows=20 cost=20)
+  -> Scan orders AS o  (rows=50000 cost=50000)
+";
+    assert_eq!(chosen.to_string(), expected);
+    assert_eq!((written.rows, written.cost), (chosen.rows, 525_060_020));
+
+    // Ties keep the order written, and outer joins are never moved
+    let sql = "SELECT id FROM users u JOIN orders o ON u.id = o.user_id";
+    assert_eq!(explain(sql, &statistics), plan_as_written(&parse_sql(sql).unwrap(), &statistics).unwrap());
+    let sql = "SELECT id FROM users u CROSS JOIN orders o LEFT JOIN countries c ON c.id = u.country";
+    assert_eq!(explain(sql, &statistics).cost, 1_000_060_020);
+}
+
+#[test]
+fn explain_analyze_puts_actual_rows_next_to_the_estimates() {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE t (id INT, name VARCHAR(5))").unwrap();
+    db.execute_sql("INSERT INTO t VALUES (1, 'a'), (2, 'b'), (3, 'c'), (4, 'd'), (5, 'e'), (6, 'f')").unwrap();
+    let sql = "SELECT name FROM (SELECT id, name FROM t WHERE id > 1) AS s WHERE id < 5 LIMIT 2";
+    let statement = parse_sql(sql).unwrap();
+    let plan = db.explain(&statement, true).unwrap();
+    let steps = |plan: &Plan| {
+        let mut steps = Vec::new();
+        let mut next = Some(plan);
+        while let Some(plan) = next {
+            steps.push((plan.rows, plan.actual.map(|actual| actual.rows)));
+            next = plan.inputs.first();
+        }
+        steps
+    };
+    // Limit, Filter, Subquery, Filter, Scan, estimated from the table's own statistics
+    assert_eq!(steps(&plan), [(2, Some(2)), (2, Some(3)), (5, Some(5)), (5, Some(5)), (6, Some(6))]);
+    assert_eq!(steps(&db.explain(&statement, false).unwrap())[0], (2, None));
+
+    // As a statement, one line of the plan per row
+    let result = db.execute_sql(&format!("EXPLAIN ANALYZE {}", sql)).unwrap();
+    assert_eq!(result.columns, ["QUERY PLAN"]);
+    assert_eq!(result.rows.len(), 5);
+    let Value::String(first) = &result.rows[0][0] else { panic!("{:?}", result.rows[0]) };
+    assert!(first.starts_with("Limit 2  (rows=2 cost=") && first.contains(") (actual rows=2 time="), "{}", first);
+    let printed = parse_sql("explain analyze SELECT id FROM t").unwrap().to_sql(Dialect::Generic);
+    assert_eq!(printed, "EXPLAIN ANALYZE SELECT id FROM t");
+    assert!(db.execute_sql("EXPLAIN DROP TABLE t").is_err());
+}
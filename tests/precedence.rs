@@ -0,0 +1,202 @@
+// precedence.rs — pins how tightly each dialect binds the binary operators
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::ast::{BinaryOperator, Expression, Statement, UnaryOperator};
+use sreerag_devadasan::canonical::{canonicalize, minify_sql};
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::engine::Database;
+use sreerag_devadasan::eval::Value;
+use sreerag_devadasan::parser::{parse_sql, parse_sql_with_dialect};
+use sreerag_devadasan::pratt::PrecedenceTable;
+
+/// The WHERE clause `condition` parsed in `dialect`, with every binary
+/// operation parenthesized once.
+fn grouping(condition: &str, dialect: Dialect) -> String {
+    fn show(expr: &Expression) -> String {
+        match expr {
+            Expression::BinaryOperation { left_operand, operator, right_operand } => {
+                format!("({} {} {})", show(left_operand), operator, show(right_operand))
+            }
+            Expression::UnaryOperation { operator, operand } if operator.is_postfix() => {
+                format!("({}{})", show(operand), operator)
+            }
+            Expression::UnaryOperation { operator, operand } => format!("({}{})", operator, show(operand)),
+            Expression::Grouped(inner) => show(inner),
+            Expression::Cast { expr, data_type } => format!("({}::{})", show(expr), data_type),
+            Expression::Identifier(name) => name.value.clone(),
+            other => panic!("unexpected operand {:?}", other),
+        }
+    }
+    let sql = format!("SELECT * FROM t WHERE {}", condition);
+    match parse_sql_with_dialect(&sql, dialect).unwrap() {
+        Statement::Select { selection: Some(selection), .. } => show(&selection),
+        other => panic!("expected a filtered SELECT, got {:?}", other),
+    }
+}
+
+#[test]
+fn dialects_rank_comparisons_differently() {
+    for (dialect, equals_then_less, like_then_equals) in [
+        (Dialect::Generic, "(a = (b < c))", "((a = b) LIKE c)"),
+        (Dialect::Ansi, "((a = b) < c)", "((a = b) LIKE c)"),
+        (Dialect::MySql, "((a = b) < c)", "((a = b) LIKE c)"),
+        (Dialect::SqlServer, "((a = b) < c)", "((a = b) LIKE c)"),
+        (Dialect::Warehouse, "((a = b) < c)", "((a = b) LIKE c)"),
+        (Dialect::Postgres, "((a = b) < c)", "(a = (b LIKE c))"),
+    ] {
+        assert_eq!(grouping("a = b < c", dialect), equals_then_less, "{}", dialect.name());
+        assert_eq!(grouping("a = b LIKE c", dialect), like_then_equals, "{}", dialect.name());
+        // What every dialect agrees on
+        assert_eq!(grouping("a OR b AND c", dialect), "(a OR (b AND c))", "{}", dialect.name());
+        assert_eq!(grouping("a + b * c < d - e", dialect), "((a + (b * c)) < (d - e))", "{}", dialect.name());
+        assert_eq!(grouping("a - b - c", dialect), "((a - b) - c)", "{}", dialect.name());
+    }
+}
+
+#[test]
+fn tables_cover_every_operator() {
+    for table in PrecedenceTable::ALL {
+        assert!(table.operators.iter().all(|(_, precedence)| *precedence > 0));
+        assert_eq!(table.of(&BinaryOperator::Or), 1);
+        assert!(table.of(&BinaryOperator::Multiply) > table.of(&BinaryOperator::Add));
+    }
+    assert_eq!(Dialect::Postgres.precedence(), &PrecedenceTable::POSTGRES);
+    assert_eq!(Dialect::Generic.precedence().of(&BinaryOperator::LessThan), 4);
+}
+
+#[test]
+fn minified_sql_keeps_its_meaning_in_every_dialect() {
+    // The generic parse groups `b < c` first, and minifying sorts it to
+    // the left, where no dialect needs parentheses
+    let minified = minify_sql("SELECT * FROM t WHERE a = b < c", Dialect::Generic).unwrap();
+    assert_eq!(minified, "SELECT * FROM t WHERE b < c = a");
+    let condition = minified.strip_prefix("SELECT * FROM t WHERE ").unwrap();
+    for dialect in Dialect::ALL {
+        assert_eq!(grouping(condition, dialect), "((b < c) = a)", "{}", dialect.name());
+    }
+    // The generic dialect needs the left parentheses, the others the right
+    let minified = minify_sql("SELECT * FROM t WHERE (a = b) < (c = d)", Dialect::Generic).unwrap();
+    assert_eq!(minified, "SELECT * FROM t WHERE (a = b) < (c = d)");
+    let condition = minified.strip_prefix("SELECT * FROM t WHERE ").unwrap();
+    for dialect in Dialect::ALL {
+        assert_eq!(grouping(condition, dialect), "((a = b) < (c = d))", "{}", dialect.name());
+    }
+}
+
+#[test]
+fn not_binds_looser_than_comparisons_and_minus_tighter_than_products() {
+    for dialect in Dialect::ALL {
+        assert_eq!(grouping("NOT a = b", dialect), "(NOT (a = b))", "{}", dialect.name());
+        assert_eq!(grouping("NOT a = b AND c", dialect), "((NOT (a = b)) AND c)", "{}", dialect.name());
+        assert_eq!(grouping("a OR NOT b", dialect), "(a OR (NOT b))", "{}", dialect.name());
+        assert_eq!(grouping("NOT NOT a < b", dialect), "(NOT (NOT (a < b)))", "{}", dialect.name());
+        assert_eq!(grouping("-a * b", dialect), "((-a) * b)", "{}", dialect.name());
+        assert_eq!(grouping("a * -b + c", dialect), "((a * (-b)) + c)", "{}", dialect.name());
+
+        let table = dialect.precedence();
+        assert_eq!(table.unary(&UnaryOperator::Not), table.of(&BinaryOperator::Equals));
+        assert!(table.unary(&UnaryOperator::Not) > table.of(&BinaryOperator::And));
+        assert!(table.unary(&UnaryOperator::Negate) > table.of(&BinaryOperator::Multiply));
+    }
+
+    // Minifying keeps the parentheses a prefix operator needs, and only those
+    for (sql, minified) in [
+        ("SELECT * FROM t WHERE NOT (a = b)", "SELECT * FROM t WHERE NOT a = b"),
+        ("SELECT * FROM t WHERE NOT (a AND b)", "SELECT * FROM t WHERE NOT (a AND b)"),
+        ("SELECT * FROM t WHERE (NOT a) = b", "SELECT * FROM t WHERE b = (NOT a)"),
+        ("SELECT * FROM t WHERE -(a * b) < c", "SELECT * FROM t WHERE c > -(a * b)"),
+        ("SELECT * FROM t WHERE (-a) * b < c", "SELECT * FROM t WHERE b * -a < c"),
+    ] {
+        assert_eq!(minify_sql(sql, Dialect::Generic).unwrap(), minified);
+    }
+}
+
+#[test]
+fn is_tests_bind_as_not_does() {
+    for dialect in Dialect::ALL {
+        for (condition, grouped) in [
+            ("a = b IS NULL", "((a = b) IS NULL)"),
+            ("NOT a IS NULL", "(NOT (a IS NULL))"),
+            ("a IS NULL AND b", "((a IS NULL) AND b)"),
+            ("a OR b IS NOT NULL", "(a OR (b IS NOT NULL))"),
+            ("a + b IS NOT NULL", "((a + b) IS NOT NULL)"),
+            ("-a IS NULL", "((-a) IS NULL)"),
+            ("a IS DISTINCT FROM b + c", "(a IS DISTINCT FROM (b + c))"),
+            ("a IS NOT DISTINCT FROM b AND c", "((a IS NOT DISTINCT FROM b) AND c)"),
+            ("a IS DISTINCT FROM b IS NULL", "((a IS DISTINCT FROM b) IS NULL)"),
+        ] {
+            assert_eq!(grouping(condition, dialect), grouped, "{} in {}", condition, dialect.name());
+        }
+    }
+    // Without NULL, NOT or DISTINCT after it, IS is a name
+    assert_eq!(parse_sql("SELECT a is FROM t").unwrap().to_string(), "SELECT a AS is FROM t");
+    for condition in ["a IS", "a IS NOT 1", "a IS DISTINCT b"] {
+        let sql = format!("SELECT * FROM t WHERE {}", condition);
+        assert!(parse_sql(&sql).is_err(), "{}", sql);
+    }
+
+    // Minifying keeps the parentheses a test needs next to the other operators
+    for (sql, minified) in [
+        ("SELECT * FROM t WHERE NOT (a IS NULL)", "SELECT * FROM t WHERE NOT a IS NULL"),
+        ("SELECT * FROM t WHERE (NOT a) IS NULL", "SELECT * FROM t WHERE (NOT a) IS NULL"),
+        ("SELECT * FROM t WHERE (a AND b) IS NOT NULL", "SELECT * FROM t WHERE (a AND b) IS NOT NULL"),
+        ("SELECT * FROM t WHERE -(a IS NULL) < 1", "SELECT * FROM t WHERE 1 > -(a IS NULL)"),
+        ("SELECT * FROM t WHERE (a = b) IS NULL", "SELECT * FROM t WHERE a = b IS NULL"),
+        (
+            "SELECT * FROM t WHERE a IS DISTINCT FROM (b IS NULL)",
+            "SELECT * FROM t WHERE a IS DISTINCT FROM (b IS NULL)",
+        ),
+    ] {
+        assert_eq!(minify_sql(sql, Dialect::Generic).unwrap(), minified);
+        assert_eq!(canonicalize(&parse_sql(minified).unwrap()), canonicalize(&parse_sql(sql).unwrap()), "{}", sql);
+    }
+}
+
+#[test]
+fn dialects_differ_on_pipes_between_and_casts() {
+    let standard_between = "(((a = b) >= c) AND ((a = b) <= d))";
+    let tighter_between = "(a = ((b >= c) AND (b <= d)))";
+    for (dialect, pipes, between, casts) in [
+        (Dialect::Generic, "((a || b) + c)", tighter_between, true),
+        (Dialect::Ansi, "((a || b) + c)", standard_between, false),
+        (Dialect::MySql, "(a OR (b + c))", standard_between, false),
+        (Dialect::SqlServer, "((a || b) + c)", standard_between, false),
+        (Dialect::Warehouse, "((a || b) + c)", standard_between, true),
+        (Dialect::Postgres, "(a || (b + c))", tighter_between, true),
+    ] {
+        assert_eq!(grouping("a || b + c", dialect), pipes, "{}", dialect.name());
+        assert_eq!(grouping("a = b BETWEEN c AND d", dialect), between, "{}", dialect.name());
+        let sql = "SELECT * FROM t WHERE -a::INT < b";
+        assert_eq!(parse_sql_with_dialect(sql, dialect).is_ok(), casts, "{}", dialect.name());
+        if casts {
+            assert_eq!(grouping("-a::INT < b", dialect), "((-(a::INT)) < b)", "{}", dialect.name());
+        }
+        // What every dialect agrees on
+        assert_eq!(grouping("a NOT BETWEEN b AND c", dialect), "(NOT ((a >= b) AND (a <= c)))", "{}", dialect.name());
+        let range = "(((a >= (b + c)) AND (a <= d)) OR e)";
+        assert_eq!(grouping("a BETWEEN b + c AND d OR e", dialect), range, "{}", dialect.name());
+        assert_eq!(grouping("NOT a BETWEEN b AND c", dialect), "(NOT ((a >= b) AND (a <= c)))", "{}", dialect.name());
+
+        let table = dialect.precedence();
+        assert!(table.cast == 0 || table.cast > table.negate, "{}", dialect.name());
+        assert!(table.between > table.of(&BinaryOperator::And));
+    }
+    assert_eq!(Dialect::MySql.precedence().pipes, BinaryOperator::Or);
+
+    // MySQL has `||` concatenation spelled as a call
+    let statement = parse_sql("SELECT * FROM t WHERE a || b = 'ab'").unwrap();
+    assert_eq!(statement.to_sql(Dialect::MySql), "SELECT * FROM t WHERE CONCAT(a, b) = 'ab'");
+    assert_eq!(statement.to_sql(Dialect::Postgres), "SELECT * FROM t WHERE a || b = 'ab'");
+
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE t (id INT, name VARCHAR(5))").unwrap();
+    db.execute_sql("INSERT INTO t VALUES (1, 'ann'), (2, 'bob'), (3, 'cy')").unwrap();
+    let ids = |db: &mut Database, condition: &str| -> Vec<Value> {
+        let sql = format!("SELECT id FROM t WHERE {} ORDER BY id", condition);
+        db.execute_sql(&sql).unwrap().rows.into_iter().map(|row| row[0].clone()).collect()
+    };
+    assert_eq!(ids(&mut db, "id BETWEEN 2 AND 3"), [Value::Int(2), Value::Int(3)]);
+    assert_eq!(ids(&mut db, "id NOT BETWEEN 2 AND 3"), [Value::Int(1)]);
+    assert_eq!(ids(&mut db, "name || '!' = 'bob!'"), [Value::Int(2)]);
+    assert_eq!(ids(&mut db, "'2'::INT = id"), [Value::Int(2)]);
+}
@@ -0,0 +1,69 @@
+// profile.rs — checks the strict and lenient parsing profiles
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::cli;
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::profile::{nonstandard, Profile};
+use sreerag_devadasan::report::report_script;
+use sreerag_devadasan::session::{ParserOptions, ParserSession};
+use sreerag_devadasan::span::Span;
+
+/// Statements only some dialects accept, each with one that does.
+const EXTENSIONS: [(&str, Dialect); 5] = [
+    ("SELECT a FROM t WHERE name ILIKE 'e%'", Dialect::Postgres),
+    ("SELECT * EXCLUDE (password) FROM users", Dialect::Warehouse),
+    ("SELECT x FROM t QUALIFY x = 1", Dialect::Warehouse),
+    ("SELECT * FROM sales PIVOT (SUM(amount) FOR month IN ('Jan', 'Feb'))", Dialect::SqlServer),
+    ("SELECT [order] FROM t", Dialect::SqlServer),
+];
+
+fn session(dialect: Dialect, profile: Profile) -> ParserSession {
+    ParserSession::new(ParserOptions { dialect, profile, ..ParserOptions::default() })
+}
+
+#[test]
+fn profiles_narrow_or_widen_what_a_dialect_accepts() {
+    for (sql, dialect) in EXTENSIONS {
+        assert!(session(dialect, Profile::Dialect).parse(sql).is_ok(), "{}", sql);
+        assert!(session(Dialect::Generic, Profile::Dialect).parse(sql).is_err(), "{}", sql);
+        assert!(session(dialect, Profile::Strict).parse(sql).is_err(), "{}", sql);
+        assert!(ParserSession::new(ParserOptions::strict()).parse(sql).is_err(), "{}", sql);
+        for other in Dialect::ALL.into_iter().filter(|other| *other != Dialect::SqlServer) {
+            assert!(session(other, Profile::Lenient).parse(sql).is_ok(), "{:?}: {}", other, sql);
+        }
+    }
+    assert!(ParserSession::new(ParserOptions::lenient()).parse(EXTENSIONS[0].0).is_ok());
+    // The standard SQL of every dialect parses in every profile
+    for profile in Profile::ALL {
+        assert!(session(Dialect::Ansi, profile).parse("SELECT a FROM t FETCH FIRST 5 ROWS ONLY").is_ok());
+        assert_eq!(Profile::from_name(&profile.name().to_uppercase()), Some(profile));
+    }
+    assert_eq!(ParserOptions::default().profile, Profile::Dialect);
+    assert_eq!(ParserOptions::strict().dialect, Dialect::Ansi);
+}
+
+#[test]
+fn strict_sessions_warn_about_nonstandard_sql() {
+    let sql = "SELECT /*+ FULL(t) */ `id` FROM t WHERE a = E'x' OR b = $$y$$ LIMIT 5";
+    let warnings = nonstandard(sql, Dialect::Generic);
+    let found: Vec<&str> = warnings.iter().map(|warning| warning.span.slice(sql)).collect();
+    assert_eq!(found, ["/*+ FULL(t) */", "`id`", "E'x'", "$$y$$", "LIMIT"]);
+    assert!(warnings[4].message.starts_with("LIMIT isn't standard SQL"));
+    assert_eq!(nonstandard("SELECT TOP 3 \"a\" FROM t", Dialect::Generic).len(), 2);
+    assert_eq!(nonstandard("SELECT TOP 3 \"a\" FROM t", Dialect::Ansi).len(), 1);
+    assert!(nonstandard("SELECT 'a', \"b\" FROM t FETCH FIRST 1 ROWS ONLY", Dialect::Ansi).is_empty());
+
+    // Only a strict session warns, and the warnings don't fail a statement
+    assert!(session(Dialect::Generic, Profile::Dialect).warnings(sql).is_empty());
+    let strict = session(Dialect::Generic, Profile::Strict);
+    assert_eq!(strict.warnings(sql), warnings);
+    let report = report_script(&strict, "SELECT a FROM t;\nSELECT a FROM t LIMIT 1;");
+    assert!(report.is_ok());
+    assert!(report.statements[0].warnings.is_empty());
+    assert_eq!(report.statements[1].warnings[0].span, Span::new(33, 38));
+    assert!(report.to_string().contains("⚠️ line 2 (bytes 33..38): LIMIT isn't standard SQL"), "{}", report);
+    assert!(report.to_json().contains(r#""warnings":[{"start":33,"end":38,"message":"LIMIT"#));
+
+    let args: Vec<String> = ["check", "a.sql", "--strict"].map(str::to_string).to_vec();
+    assert!(cli::parse(&args).unwrap().flag("strict"));
+}
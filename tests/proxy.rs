@@ -0,0 +1,100 @@
+// proxy.rs — checks policy enforcement and rewriting in the query proxy
+// Author: Sreerag Devadasan
+
+use std::sync::{Arc, Mutex};
+
+use sreerag_devadasan::ast::Expression;
+use sreerag_devadasan::engine::Database;
+use sreerag_devadasan::eval::Value;
+use sreerag_devadasan::proxy::{Proxy, ProxyError, ProxyPolicy, QueryLog, TenantFilter};
+use sreerag_devadasan::session::{ParserOptions, ParserSession};
+
+fn database() -> Database {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE orders (id INT, tenant INT, total INT)").unwrap();
+    db.execute_sql("CREATE TABLE secrets (id INT)").unwrap();
+    db.execute_sql("INSERT INTO orders VALUES (1, 1, 10), (2, 2, 20), (3, 1, 30)").unwrap();
+    db
+}
+
+fn tenant_policy() -> ProxyPolicy {
+    ProxyPolicy {
+        tenant_filter: Some(TenantFilter {
+            column: "tenant".to_string(),
+            value: Expression::Number(1),
+            tables: vec!["orders".to_string()],
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn tenant_filter_is_added_to_queries() {
+    let logs = Arc::new(Mutex::new(Vec::<QueryLog>::new()));
+    let sink = Arc::clone(&logs);
+    let mut proxy = Proxy::new(ParserSession::default(), tenant_policy(), database())
+        .with_logger(move |log| sink.lock().unwrap().push(log.clone()));
+
+    let result = proxy.execute("SELECT id FROM orders WHERE total > 5 OR id = 2 ORDER BY id").unwrap();
+    assert_eq!(result.rows, [[Value::Int(1)], [Value::Int(3)]]);
+
+    let logs = logs.lock().unwrap();
+    assert_eq!(
+        logs[0].forwarded.as_deref(),
+        Some("SELECT id FROM orders WHERE (total > 5 OR id = 2) AND tenant = 1 ORDER BY id")
+    );
+    assert_eq!(logs[0].kind, Some("SELECT"));
+    assert_eq!(logs[0].tables, ["orders"]);
+    assert_eq!(logs[0].rows, Some(2));
+}
+
+#[test]
+fn joined_and_nested_tenant_tables_are_filtered() {
+    let logs = Arc::new(Mutex::new(Vec::<QueryLog>::new()));
+    let sink = Arc::clone(&logs);
+    let mut proxy = Proxy::new(ParserSession::default(), tenant_policy(), database())
+        .with_logger(move |log| sink.lock().unwrap().push(log.clone()));
+
+    // The engine runs neither joins nor CTEs; only the rewrite matters here
+    let _ = proxy.execute("SELECT id FROM orders o LEFT JOIN orders p ON p.id = o.id");
+    let _ = proxy.execute("WITH big AS (SELECT id FROM orders WHERE total > 15) SELECT id FROM big");
+    let logs = logs.lock().unwrap();
+    assert_eq!(
+        logs[0].forwarded.as_deref(),
+        Some("SELECT id FROM orders AS o LEFT JOIN orders AS p ON p.id = o.id AND p.tenant = 1 WHERE o.tenant = 1")
+    );
+    assert_eq!(
+        logs[1].forwarded.as_deref(),
+        Some("WITH big AS (SELECT id FROM orders WHERE total > 15 AND tenant = 1) SELECT id FROM big")
+    );
+    assert_eq!(logs[1].tables, ["orders"]);
+}
+
+#[test]
+fn statements_the_filter_cant_cover_are_rejected() {
+    let mut proxy = Proxy::new(ParserSession::default(), tenant_policy(), database());
+    let error = proxy.execute("INSERT INTO orders VALUES (4, 2, 40)").unwrap_err();
+    assert_eq!(error.to_string(), "Rejected: INSERT on orders can't be restricted to the tenant");
+    let outer_join = proxy.execute("SELECT * FROM orders o RIGHT JOIN orders p ON o.id = p.id");
+    assert!(matches!(outer_join, Err(ProxyError::Rejected(_))));
+    assert_eq!(proxy.upstream().table("orders").unwrap().len(), 3);
+
+    let loose = ParserSession::new(ParserOptions { loose: true, ..Default::default() });
+    let mut proxy = Proxy::new(loose, tenant_policy(), database());
+    assert!(matches!(proxy.execute("SELEC * FROM orders"), Err(ProxyError::Rejected(_))));
+}
+
+#[test]
+fn policy_limits_kinds_and_tables() {
+    let policy = ProxyPolicy {
+        allowed_kinds: vec!["SELECT"],
+        denied_tables: vec!["SECRETS".to_string()],
+        ..Default::default()
+    };
+    let mut proxy = Proxy::new(ParserSession::default(), policy, database());
+    assert!(proxy.execute("SELECT total FROM orders").is_ok());
+    let rejection = |proxy: &mut Proxy<Database>, sql| proxy.execute(sql).unwrap_err().to_string();
+    assert_eq!(rejection(&mut proxy, "DROP TABLE orders"), "Rejected: DROP TABLE statements are not allowed");
+    assert_eq!(rejection(&mut proxy, "SELECT id FROM secrets"), "Rejected: table secrets is not accessible");
+    assert!(matches!(proxy.execute("SELECT FROM"), Err(ProxyError::Parse(_))));
+}
@@ -0,0 +1,42 @@
+// query_diff.rs — checks the clause-level query diff
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::query_diff::{diff, QueryChange};
+
+#[test]
+fn clauses_are_compared_item_by_item() {
+    let changes = diff(
+        "select a, b from t where x = 1 and y = 2 order by a limit 10",
+        "SELECT a, c FROM t LEFT JOIN u ON t.id = u.id WHERE y = 2 AND x = 1 AND z > 3 ORDER BY a LIMIT 20",
+        Dialect::Generic,
+    )
+    .unwrap();
+    let lines: Vec<String> = changes.iter().map(|change| change.to_string()).collect();
+    assert_eq!(
+        lines,
+        [
+            "- SELECT: b",
+            "+ SELECT: c",
+            "+ JOIN: LEFT JOIN u ON t.id = u.id",
+            "+ WHERE: z > 3",
+            "~ LIMIT: 10 → 20",
+        ]
+    );
+}
+
+#[test]
+fn reordering_matters_only_where_it_changes_results() {
+    let same = diff("SELECT a FROM t WHERE x = 1 AND y = 2", "SELECT a FROM t WHERE y = 2 AND x = 1", Dialect::Generic);
+    assert_eq!(same.unwrap(), []);
+
+    let changes = diff("SELECT a, b FROM t", "SELECT b, a FROM t", Dialect::Generic).unwrap();
+    assert_eq!(changes, [QueryChange::Changed { clause: "SELECT", from: "a, b".into(), to: "b, a".into() }]);
+}
+
+#[test]
+fn other_statements_are_compared_whole() {
+    let changes = diff("DROP TABLE t", "SELECT a FROM t", Dialect::Generic).unwrap();
+    assert!(matches!(&changes[..], [QueryChange::Changed { clause: "statement", .. }]));
+    assert_eq!(diff("DROP TABLE t", "drop table t", Dialect::Generic).unwrap(), []);
+}
@@ -0,0 +1,127 @@
+// querylog.rs — checks log reading and aggregation by query shape
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::querylog::{analyze, read_entries, LogEntry, LogFormat};
+
+const POSTGRES_LOG: &str = "\
+2024-05-01 10:00:00.123 UTC [123] LOG:  duration: 12.500 ms  statement: SELECT * FROM users WHERE id = 1
+2024-05-01 10:00:01.123 UTC [123] LOG:  duration: 7.500 ms  statement: SELECT * FROM users WHERE id = 2
+2024-05-01 10:00:02.123 UTC [124] LOG:  statement: INSERT INTO orders (id, total)
+\tVALUES (9, 5)
+2024-05-01 10:00:03.123 UTC [124] ERROR:  relation \"x\" does not exist
+2024-05-01 10:00:03.123 UTC [124] STATEMENT:  SELECT * FROM x
+2024-05-01 10:00:04.123 UTC [125] LOG:  duration: 1.000 ms  execute <unnamed>: SELECT name FROM users WHERE id = 3
+2024-05-01 10:00:05.123 UTC [125] LOG:  statement: SELEC oops
+";
+
+const MYSQL_SLOW_LOG: &str = "\
+/usr/sbin/mysqld, Version: 8.0.36 (MySQL Community Server - GPL). started with:
+Tcp port: 3306  Unix socket: /var/run/mysqld/mysqld.sock
+Time                 Id Command    Argument
+# Time: 2024-05-01T10:00:00.000000Z
+# User@Host: app[app] @ localhost []  Id:     8
+# Query_time: 0.250000  Lock_time: 0.000010 Rows_sent: 1  Rows_examined: 1000
+use shop;
+SET timestamp=1714557600;
+SELECT * FROM orders
+WHERE customer_id = 42;
+# Time: 2024-05-01T10:00:01.000000Z
+# Query_time: 0.750000  Lock_time: 0.000010 Rows_sent: 1  Rows_examined: 1000
+SET timestamp=1714557601;
+SELECT * FROM orders WHERE customer_id = 7;
+";
+
+#[test]
+fn detects_formats() {
+    assert_eq!(LogFormat::detect(POSTGRES_LOG), LogFormat::Postgres);
+    assert_eq!(LogFormat::detect(MYSQL_SLOW_LOG), LogFormat::MySqlSlow);
+    assert_eq!(LogFormat::detect("SELECT 1\nSELECT 2\n"), LogFormat::Plain);
+}
+
+#[test]
+fn reads_postgres_statements_and_durations() {
+    let entries = read_entries(POSTGRES_LOG, LogFormat::Postgres);
+    let statements: Vec<(usize, &str, Option<f64>)> =
+        entries.iter().map(|e| (e.line, e.sql.as_str(), e.duration_ms)).collect();
+    assert_eq!(
+        statements,
+        [
+            (1, "SELECT * FROM users WHERE id = 1", Some(12.5)),
+            (2, "SELECT * FROM users WHERE id = 2", Some(7.5)),
+            (3, "INSERT INTO orders (id, total)\nVALUES (9, 5)", None),
+            (7, "SELECT name FROM users WHERE id = 3", Some(1.0)),
+            (8, "SELEC oops", None),
+        ]
+    );
+}
+
+#[test]
+fn reads_mysql_slow_log_entries() {
+    let entries = read_entries(MYSQL_SLOW_LOG, LogFormat::MySqlSlow);
+    assert_eq!(
+        entries,
+        [
+            LogEntry {
+                line: 9,
+                sql: "SELECT * FROM orders\nWHERE customer_id = 42".to_string(),
+                duration_ms: Some(250.0),
+            },
+            LogEntry { line: 14, sql: "SELECT * FROM orders WHERE customer_id = 7".to_string(), duration_ms: Some(750.0) },
+        ]
+    );
+}
+
+#[test]
+fn plain_logs_skip_blank_and_comment_lines() {
+    let entries = read_entries("SELECT 1\n\n-- note\n  SELECT 2  \n", LogFormat::Plain);
+    let lines: Vec<(usize, &str)> = entries.iter().map(|e| (e.line, e.sql.as_str())).collect();
+    assert_eq!(lines, [(1, "SELECT 1"), (4, "SELECT 2")]);
+}
+
+#[test]
+fn groups_statements_by_shape() {
+    let stats = analyze(&read_entries(POSTGRES_LOG, LogFormat::Postgres), Dialect::Postgres);
+    assert_eq!(stats.statements, 5);
+    assert_eq!(stats.failed, [8]);
+
+    let shapes: Vec<(&str, usize)> = stats.shapes.iter().map(|s| (s.example.as_str(), s.count)).collect();
+    assert_eq!(
+        shapes,
+        [
+            ("SELECT * FROM users WHERE id = :number", 2),
+            ("SELECT name FROM users WHERE id = :number", 1),
+            ("INSERT INTO orders (id, total) VALUES (:number, :number)", 1),
+        ]
+    );
+    let users = &stats.shapes[0];
+    assert_eq!((users.timed, users.total_ms, users.max_ms, users.mean_ms()), (2, 20.0, 12.5, Some(10.0)));
+    assert_eq!(stats.shapes[2].mean_ms(), None);
+
+    assert_eq!(stats.tables, [("users".to_string(), 3), ("orders".to_string(), 1)]);
+    assert_eq!(stats.kinds, [("SELECT", 3), ("INSERT", 1)]);
+}
+
+#[test]
+fn exports_json_and_csv() {
+    let stats = analyze(&read_entries(MYSQL_SLOW_LOG, LogFormat::MySqlSlow), Dialect::MySql);
+    let hash = stats.shapes[0].fingerprint;
+    assert_eq!(
+        stats.to_json(10),
+        format!(
+            "{{\"statements\":2,\"failed\":[],\"shapes\":[{{\"fingerprint\":\"{}\",\"kind\":\"SELECT\",\
+             \"query\":\"SELECT * FROM orders WHERE customer_id = :number\",\"count\":2,\"total_ms\":1000.000,\
+             \"mean_ms\":500.000,\"max_ms\":750.000}}],\"tables\":{{\"orders\":2}},\"kinds\":{{\"SELECT\":2}}}}",
+            hash
+        )
+    );
+
+    let log = "SELECT id FROM t WHERE name = 'a\"b'\nSELECT id FROM t WHERE id = 1\nSELECT id FROM t WHERE id = 2\n";
+    let stats = analyze(&read_entries(log, LogFormat::Plain), Dialect::Generic);
+    let csv = stats.to_csv(usize::MAX);
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(rows[0], "fingerprint,kind,count,total_ms,mean_ms,max_ms,query");
+    assert_eq!(rows[1], format!("{},SELECT,2,,,,\"SELECT id FROM t WHERE id = :number\"", stats.shapes[0].fingerprint));
+    assert_eq!(rows[2], format!("{},SELECT,1,,,,\"SELECT id FROM t WHERE name = :string\"", stats.shapes[1].fingerprint));
+    assert_eq!(stats.to_csv(1).lines().count(), 2);
+}
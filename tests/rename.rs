@@ -0,0 +1,91 @@
+// rename.rs — checks renaming tables and columns through qualifiers, aliases, joins and subqueries
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::rename::{apply_edits, rename_column, rename_column_edits, rename_table, rename_table_edits};
+
+fn table_renamed(sql: &str, old: &str, new: &str) -> String {
+    apply_edits(sql, &rename_table_edits(sql, Dialect::Postgres, old, new).unwrap())
+}
+
+fn column_renamed(sql: &str, table: &str, old: &str, new: &str) -> String {
+    apply_edits(sql, &rename_column_edits(sql, Dialect::Postgres, table, old, new).unwrap())
+}
+
+#[test]
+fn tables_are_renamed_in_joins_subqueries_and_the_qualifiers_that_name_them() {
+    let sql = "SELECT o.id FROM orders o JOIN users ON users.id = o.user_id";
+    assert_eq!(table_renamed(sql, "users", "people"), "SELECT o.id FROM orders o JOIN people ON people.id = o.user_id");
+    // An alias stays what the columns are qualified by
+    assert_eq!(table_renamed(sql, "orders", "sales"), "SELECT o.id FROM sales o JOIN users ON users.id = o.user_id");
+
+    assert_eq!(table_renamed("SELECT \"users\".id FROM \"users\"", "users", "people"), "SELECT people.id FROM people");
+    assert_eq!(
+        table_renamed("SELECT x.id FROM (SELECT users.id FROM users WHERE users.id > 1) AS x", "users", "people"),
+        "SELECT x.id FROM (SELECT people.id FROM people WHERE people.id > 1) AS x"
+    );
+    assert_eq!(
+        table_renamed("SELECT a FROM users UNION SELECT users.b FROM orders, users", "users", "people"),
+        "SELECT a FROM people UNION SELECT people.b FROM orders, people"
+    );
+    assert_eq!(
+        table_renamed("UPDATE users SET name = 'x' WHERE users.id = 1", "users", "people"),
+        "UPDATE people SET name = 'x' WHERE people.id = 1"
+    );
+    // A name that isn't the table's is left alone, qualifier or not
+    let aliased = "SELECT users.id FROM orders AS users";
+    assert_eq!(table_renamed(aliased, "users", "people"), aliased);
+
+    // The AST rewrite changes the same names
+    let renamed = rename_table(&parse_sql(sql).unwrap(), "users", "people");
+    assert_eq!(renamed, parse_sql("SELECT o.id FROM orders o JOIN people ON people.id = o.user_id").unwrap());
+}
+
+#[test]
+fn columns_are_renamed_by_their_qualifier_or_when_their_table_is_all_in_scope() {
+    assert_eq!(
+        column_renamed("SELECT id, users.id FROM users WHERE users.id > 1 ORDER BY id", "users", "id", "uid"),
+        "SELECT uid, users.uid FROM users WHERE users.uid > 1 ORDER BY uid"
+    );
+    // With a join, a bare name may be any item's, and only the qualified ones are known to be the table's
+    let sql = "SELECT u.id, o.id, id FROM users u JOIN orders o ON o.user_id = u.id WHERE u.id > 1";
+    assert_eq!(
+        column_renamed(sql, "users", "id", "uid"),
+        "SELECT u.uid, o.id, id FROM users u JOIN orders o ON o.user_id = u.uid WHERE u.uid > 1"
+    );
+    assert_eq!(
+        column_renamed(sql, "orders", "user_id", "buyer"),
+        "SELECT u.id, o.id, id FROM users u JOIN orders o ON o.buyer = u.id WHERE u.id > 1"
+    );
+    // A joined table's columns are renamed too, and a subquery's by its own scope
+    assert_eq!(
+        column_renamed("SELECT x.n FROM orders JOIN (SELECT name AS n FROM users) x ON TRUE", "users", "name", "label"),
+        "SELECT x.n FROM orders JOIN (SELECT label AS n FROM users) x ON TRUE"
+    );
+    assert_eq!(
+        column_renamed("UPDATE users AS u SET id = u.id + 1 WHERE id > 1", "users", "id", "uid"),
+        "UPDATE users AS u SET uid = u.uid + 1 WHERE uid > 1"
+    );
+    assert_eq!(column_renamed("SELECT id FROM orders", "users", "id", "uid"), "SELECT id FROM orders");
+    // ORDER BY items are expressions, renamed as the others are
+    assert_eq!(
+        column_renamed("SELECT name FROM users ORDER BY id DESC, users.id + 1, name", "users", "id", "uid"),
+        "SELECT name FROM users ORDER BY uid DESC, users.uid + 1, name"
+    );
+    assert_eq!(
+        table_renamed("SELECT name FROM users ORDER BY users.id DESC", "users", "people"),
+        "SELECT name FROM people ORDER BY people.id DESC"
+    );
+
+    let trigger = "CREATE TRIGGER t AFTER UPDATE OF name, id ON users FOR EACH ROW EXECUTE FUNCTION f()";
+    assert_eq!(
+        column_renamed(trigger, "users", "id", "uid"),
+        "CREATE TRIGGER t AFTER UPDATE OF name, uid ON users FOR EACH ROW EXECUTE FUNCTION f()"
+    );
+    assert_eq!(parse_sql(trigger).unwrap().to_string(), trigger);
+
+    let statement = parse_sql(sql).unwrap();
+    let expected = "SELECT u.uid, o.id, id FROM users u JOIN orders o ON o.user_id = u.uid WHERE u.uid > 1";
+    assert_eq!(rename_column(&statement, "users", "id", "uid"), parse_sql(expected).unwrap());
+}
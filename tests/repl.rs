@@ -0,0 +1,117 @@
+// repl.rs — checks the REPL's settings file, environment overrides and history
+// Author: Sreerag Devadasan
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::repl::{ColorMode, ConfigError, History, OutputMode, ReplConfig};
+use sreerag_devadasan::style::Theme;
+
+fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+}
+
+#[test]
+fn settings_files_override_the_defaults() {
+    let text = r#"
+        # Settings shared across machines
+        [repl]
+        dialect = "postgres"
+        prompt = 'pg# '           # literal string
+        color = false
+        theme = "light"
+        output = "minified"
+        history_size = 1_500
+        schema = [
+            "schema.sql",   # the tables
+            "/abs/migrations",
+        ]
+    "#;
+    let mut config = ReplConfig::default();
+    config.apply_toml(text, Path::new("/home/ann")).unwrap();
+    assert_eq!(
+        config,
+        ReplConfig {
+            dialect: Dialect::Postgres,
+            prompt: "pg# ".to_string(),
+            color: ColorMode::Never,
+            theme: Theme::Light,
+            output: OutputMode::Minified,
+            history_size: 1500,
+            schema: vec![PathBuf::from("/home/ann/schema.sql"), PathBuf::from("/abs/migrations")],
+        }
+    );
+
+    let config = ReplConfig::from_toml("prompt = \"a \\\"#\\\" b> \"\n").unwrap();
+    assert_eq!(config.prompt, "a \"#\" b> ");
+    assert_eq!(ReplConfig::from_toml("").unwrap(), ReplConfig::default());
+}
+
+#[test]
+fn bad_settings_are_errors() {
+    let error = |text: &str| ReplConfig::from_toml(text).unwrap_err();
+    assert!(matches!(error("\n\ndialect"), ConfigError::Syntax { line: 3, .. }));
+    assert!(matches!(error("prompt = \"open"), ConfigError::Syntax { line: 1, .. }));
+    assert!(matches!(error("schema = [\"a\",\n\"b\""), ConfigError::Syntax { line: 1, .. }));
+    assert!(matches!(error("[server]"), ConfigError::Syntax { .. }));
+    assert!(matches!(error("colour = \"never\""), ConfigError::Invalid { setting, .. } if setting == "colour"));
+    assert!(matches!(error("history_size = -1"), ConfigError::Invalid { setting, .. } if setting == "history_size"));
+    assert!(matches!(error("output = 3"), ConfigError::Invalid { .. }));
+    assert_eq!(
+        error("dialect = \"oracle\"").to_string(),
+        "dialect: unknown dialect oracle (expected one of generic, ansi, mysql, postgres, sqlserver, warehouse)"
+    );
+}
+
+#[test]
+fn environment_variables_override_the_file() {
+    let mut config = ReplConfig::from_toml("dialect = \"mysql\"\nprompt = \"my> \"\ncolor = \"always\"").unwrap();
+    let schema = env::join_paths(["a.sql", "b"]).unwrap().into_string().unwrap();
+    config
+        .apply_env(vars(&[
+            ("MINISQL_DIALECT", "postgres"),
+            ("MINISQL_OUTPUT", "sql"),
+            ("MINISQL_THEME", "light"),
+            ("MINISQL_HISTORY_SIZE", "10"),
+            ("MINISQL_SCHEMA", &schema),
+            ("PATH", "/bin"),
+        ]))
+        .unwrap();
+    assert_eq!((config.dialect, config.prompt.as_str()), (Dialect::Postgres, "my> "));
+    assert_eq!((config.output, config.history_size), (OutputMode::Sql, 10));
+    assert_eq!(config.schema, [PathBuf::from("a.sql"), PathBuf::from("b")]);
+    assert_eq!((config.color, config.theme), (ColorMode::Always, Theme::Light));
+
+    // NO_COLOR applies unless MINISQL_COLOR is set too
+    config.apply_env(vars(&[("NO_COLOR", "1")])).unwrap();
+    assert_eq!(config.color, ColorMode::Never);
+    config.apply_env(vars(&[("NO_COLOR", "1"), ("MINISQL_COLOR", "auto")])).unwrap();
+    assert_eq!(config.color, ColorMode::Auto);
+
+    let error = config.apply_env(vars(&[("MINISQL_COLOR", "sometimes")])).unwrap_err();
+    assert!(matches!(error, ConfigError::Invalid { setting, .. } if setting == "MINISQL_COLOR"));
+}
+
+#[test]
+fn history_keeps_the_newest_lines() {
+    let path = env::temp_dir().join(format!("sreerag-history-{}", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let mut history = History::load(Some(path.clone()), 3);
+    for line in ["SELECT 1", "SELECT 2", "SELECT 2", "", "SELECT 3", "SELECT 4"] {
+        history.push(line);
+    }
+    assert_eq!(history.entries().collect::<Vec<_>>(), ["SELECT 2", "SELECT 3", "SELECT 4"]);
+    history.save().unwrap();
+
+    // A smaller size keeps the end of the file
+    let history = History::load(Some(path.clone()), 2);
+    assert_eq!(history.entries().collect::<Vec<_>>(), ["SELECT 3", "SELECT 4"]);
+    fs::remove_file(&path).unwrap();
+
+    // No history is kept at size 0
+    let mut history = History::load(None, 0);
+    history.push("SELECT 1");
+    assert_eq!(history.entries().count(), 0);
+}
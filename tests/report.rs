@@ -0,0 +1,68 @@
+// report.rs — checks script reports and loose-mode script parsing
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::ast::Statement;
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::report::{report_script, report_script_with_progress};
+use sreerag_devadasan::session::{ParserOptions, ParserSession};
+use sreerag_devadasan::span::Span;
+use sreerag_devadasan::tokenizer::{tokenize_with_progress, Progress, Tokenizer};
+
+const SCRIPT: &str = "SELECT a FROM t;\n\nSELECT FROM;\nCREATE TABLE x (id INT);\n  DROP x;\n";
+
+#[test]
+fn failures_point_at_their_statements() {
+    let report = report_script(&ParserSession::default(), SCRIPT);
+    assert_eq!(report.statements.len(), 4);
+    assert!(!report.is_ok());
+
+    let failed: Vec<(&str, usize)> = report.failures().map(|s| (s.span.slice(SCRIPT), s.line)).collect();
+    assert_eq!(failed, [("SELECT FROM", 3), ("DROP x", 5)]);
+    assert_eq!(report.statements[2].result.as_ref().unwrap().kind(), "CREATE TABLE");
+}
+
+#[test]
+fn json_lists_every_statement() {
+    let json = report_script(&ParserSession::default(), "SELECT a FROM t; SELECT").to_json();
+    assert!(json.starts_with(r#"{"statements":[{"start":0,"end":15,"line":1,"ok":true,"#));
+    assert!(json.contains(r#""start":17,"end":23,"line":1,"ok":false,"#));
+    assert!(json.ends_with(r#""failed":1}"#));
+}
+
+#[test]
+fn loose_sessions_keep_unparsed_statements() {
+    let session = ParserSession::new(ParserOptions { loose: true, ..Default::default() });
+    let results = session.parse_script(SCRIPT);
+    assert_eq!(results.len(), 4);
+    assert_eq!(
+        results[1].as_ref().unwrap(),
+        &Statement::Unparsed { raw_sql: "SELECT FROM".to_string(), span: Span::new(18, 29) }
+    );
+    assert_eq!(results[2].as_ref().unwrap().kind(), "CREATE TABLE");
+    assert!(report_script(&session, SCRIPT).is_ok());
+
+    let strict = ParserSession::new(ParserOptions { loose: true, max_tokens: Some(2), ..Default::default() });
+    assert!(strict.parse("SELECT a FROM t").is_err());
+}
+
+#[test]
+fn progress_counts_bytes_and_tokens() {
+    let mut tokenizer = Tokenizer::new("SELECT a FROM t");
+    tokenizer.next_token();
+    assert_eq!((tokenizer.offset(), tokenizer.token_count()), (6, 1));
+    assert_eq!(tokenizer.progress(), Progress { offset: 6, total: 15, tokens: 1 });
+
+    let mut seen = Vec::new();
+    let tokens = tokenize_with_progress("SELECT a FROM t", Dialect::Generic, 2, |progress| seen.push(progress));
+    assert_eq!(tokens.len(), 5);
+    let offsets: Vec<(usize, usize)> = seen.iter().map(|p| (p.offset, p.tokens)).collect();
+    assert_eq!(offsets, [(8, 2), (15, 4), (15, 4)]);
+
+    // Scripts report each statement, then the end of the file
+    let mut seen = Vec::new();
+    report_script_with_progress(&ParserSession::default(), SCRIPT, |progress| seen.push(progress));
+    let offsets: Vec<usize> = seen.iter().map(|p| p.offset).collect();
+    assert_eq!(offsets, [15, 29, 54, 64, SCRIPT.len()]);
+    assert_eq!(seen.last().unwrap().tokens, 4 + 2 + 7 + 2);
+    assert!(seen.iter().all(|p| p.total == SCRIPT.len()) && seen.last().unwrap().fraction() == 1.0);
+}
@@ -0,0 +1,161 @@
+// result_cache.rs — checks that repeated SELECTs reuse their results until a table they read is written
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::analyzer::SqlType;
+use sreerag_devadasan::engine::{Database, Storage};
+use sreerag_devadasan::eval::Value;
+use sreerag_devadasan::result_cache::ResultCacheStats;
+
+fn database(storage: Storage) -> Database {
+    let mut db = Database::with_storage(storage);
+    db.set_result_cache_capacity(8);
+    db.execute_sql("CREATE TABLE t (id INT, name VARCHAR(5))").unwrap();
+    db.execute_sql("CREATE TABLE u (id INT)").unwrap();
+    db.execute_sql("INSERT INTO t VALUES (1, 'a'), (2, 'b')").unwrap();
+    db
+}
+
+fn ids(db: &mut Database, sql: &str) -> Vec<Value> {
+    db.execute_sql(sql).unwrap().rows.into_iter().map(|row| row[0].clone()).collect()
+}
+
+#[test]
+fn results_are_reused_until_their_tables_are_written() {
+    for storage in [Storage::Rows, Storage::Columnar] {
+        let mut db = database(storage);
+        let sql = "SELECT id FROM t WHERE id > 0 ORDER BY id";
+        assert_eq!(ids(&mut db, sql), [Value::Int(1), Value::Int(2)]);
+        assert_eq!(ids(&mut db, sql), [Value::Int(1), Value::Int(2)]);
+        // Other literals are another query, with a result of its own
+        assert_eq!(ids(&mut db, "SELECT id FROM t WHERE id > 1 ORDER BY id"), [Value::Int(2)]);
+        ids(&mut db, "SELECT id FROM u");
+        let expected = ResultCacheStats { hits: 1, misses: 3, evictions: 0, invalidations: 0, entries: 3 };
+        assert_eq!(db.result_cache_stats(), expected);
+
+        // Writing u leaves the results over t alone
+        db.execute_sql("INSERT INTO u VALUES (7)").unwrap();
+        assert_eq!(ids(&mut db, "SELECT id FROM u"), [Value::Int(7)]);
+        db.execute_sql("INSERT INTO t VALUES (3, 'c')").unwrap();
+        assert_eq!(ids(&mut db, sql), [Value::Int(1), Value::Int(2), Value::Int(3)]);
+        let stats = db.result_cache_stats();
+        assert_eq!((stats.hits, stats.invalidations, stats.entries), (1, 3, 2), "{:?}", storage);
+        // A failed INSERT changes nothing, so the result stands
+        assert!(db.execute_sql("INSERT INTO t VALUES (4, 'toolong')").is_err());
+        ids(&mut db, sql);
+        assert_eq!(db.result_cache_stats().hits, 2);
+    }
+
+    // A table dropped and created again is another table
+    let mut db = database(Storage::Rows);
+    ids(&mut db, "SELECT id FROM (SELECT id FROM t) AS s");
+    db.execute_sql("DROP TABLE t").unwrap();
+    db.execute_sql("CREATE TABLE t (id INT)").unwrap();
+    assert!(ids(&mut db, "SELECT id FROM (SELECT id FROM t) AS s").is_empty());
+
+    // Queries calling a registered function run every time
+    db.register_scalar("twice", &[SqlType::Integer], SqlType::Integer, |args| match &args[0] {
+        Value::Int(n) => Ok(Value::Int(n * 2)),
+        other => Ok(other.clone()),
+    });
+    db.execute_sql("INSERT INTO t VALUES (2)").unwrap();
+    let before = db.result_cache_stats();
+    ids(&mut db, "SELECT id FROM t WHERE twice(id) = 4");
+    ids(&mut db, "SELECT id FROM t WHERE twice(id) = 4");
+    assert_eq!(db.result_cache_stats(), before);
+}
+
+#[test]
+fn transactions_and_capacity_keep_the_cache_honest() {
+    let mut db = database(Storage::Rows);
+    let sql = "SELECT name FROM t";
+    db.execute_sql("BEGIN").unwrap();
+    db.execute_sql("INSERT INTO t VALUES (3, 'c')").unwrap();
+    assert_eq!(ids(&mut db, sql).len(), 3);
+    db.execute_sql("ROLLBACK").unwrap();
+    assert_eq!(ids(&mut db, sql).len(), 2);
+    assert_eq!(ids(&mut db, sql).len(), 2);
+    assert_eq!(db.result_cache_stats().hits, 1);
+
+    // Another session's commit is a write like any other
+    let mut session = db.begin();
+    session.execute_sql("INSERT INTO t VALUES (4, 'd')").unwrap();
+    assert_eq!(ids(&mut db, sql).len(), 2);
+    db.commit(session).unwrap();
+    assert_eq!(ids(&mut db, sql).len(), 3);
+
+    db.set_result_cache_capacity(1);
+    assert_eq!(db.result_cache_stats().entries, 1);
+    ids(&mut db, "SELECT id FROM u");
+    assert_eq!(db.result_cache_stats().evictions, 1);
+    db.set_result_cache_capacity(0);
+    ids(&mut db, "SELECT id FROM u");
+    assert_eq!(db.result_cache_stats().entries, 0);
+    // Off by default
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE t (id INT)").unwrap();
+    ids(&mut db, "SELECT id FROM t");
+    assert_eq!(db.result_cache_stats(), ResultCacheStats::default());
+}
+
+#[test]
+fn each_kind_of_write_drops_the_results_over_its_table() {
+    let sql = "SELECT id FROM t ORDER BY id";
+    for storage in [Storage::Rows, Storage::Columnar] {
+        let mut db = database(storage);
+        ids(&mut db, "SELECT id FROM u");
+        for (write, expected) in [
+            ("INSERT INTO t VALUES (3, 'c')", vec![1, 2, 3]),
+            ("UPDATE t SET id = id * 10 WHERE id > 1", vec![1, 20, 30]),
+            ("UPDATE t SET name = 'z'", vec![1, 20, 30]),
+            ("DELETE FROM t WHERE name = 'z' AND id = 20", vec![1, 30]),
+            ("DELETE FROM t", vec![]),
+        ] {
+            ids(&mut db, sql);
+            let before = db.result_cache_stats();
+            db.execute_sql(write).unwrap();
+            let after = db.result_cache_stats();
+            // The result over t goes, the one over u stays
+            assert_eq!((after.invalidations, after.entries), (before.invalidations + 1, 1), "{}", write);
+            let expected: Vec<Value> = expected.into_iter().map(Value::Int).collect();
+            assert_eq!(ids(&mut db, sql), expected, "{}", write);
+        }
+
+        // A write that matches no row, or fails, changes nothing
+        db.execute_sql("INSERT INTO t VALUES (1, 'a')").unwrap();
+        ids(&mut db, sql);
+        let before = db.result_cache_stats();
+        let writes = ["UPDATE t SET id = 2 WHERE id > 1", "DELETE FROM t WHERE name = 'b'", "UPDATE t SET missing = 1"];
+        for write in writes {
+            let _ = db.execute_sql(write);
+        }
+        assert_eq!(db.result_cache_stats().invalidations, before.invalidations, "{:?}", storage);
+        assert_eq!(ids(&mut db, sql), [Value::Int(1)]);
+        assert_eq!(db.result_cache_stats().hits, before.hits + 1);
+    }
+
+    // Creating and dropping a table, ROLLBACK and a commit drop results too;
+    // ROLLBACK drops those over every table the transaction wrote
+    let mut db = database(Storage::Rows);
+    for (write, expected) in [
+        ("DROP TABLE u", 1),
+        ("CREATE TABLE u (id INT)", 0),
+        ("BEGIN", 0),
+        ("INSERT INTO u VALUES (1)", 1),
+        ("DELETE FROM t", 1),
+        ("ROLLBACK", 2),
+    ] {
+        ids(&mut db, "SELECT id FROM t");
+        let _ = db.execute_sql("SELECT id FROM u");
+        let before = db.result_cache_stats();
+        db.execute_sql(write).unwrap();
+        let dropped = db.result_cache_stats().invalidations - before.invalidations;
+        assert_eq!(dropped, expected, "{}", write);
+    }
+    let mut session = db.begin();
+    session.execute_sql("UPDATE t SET name = 'x'").unwrap();
+    ids(&mut db, "SELECT name FROM t");
+    let before = db.result_cache_stats();
+    db.commit(session).unwrap();
+    assert_eq!(db.result_cache_stats().invalidations, before.invalidations + 1);
+    assert_eq!(ids(&mut db, "SELECT name FROM t"), [Value::String("x".into()), Value::String("x".into())]);
+}
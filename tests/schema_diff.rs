@@ -0,0 +1,97 @@
+// schema_diff.rs — checks the changes between two schemas and the ALTER TABLE statements that apply them
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::schema_diff::{diff, Schema, SchemaChange};
+
+fn changes(old: &str, new: &str) -> Vec<SchemaChange> {
+    diff(&Schema::from_sql(old).unwrap(), &Schema::from_sql(new).unwrap())
+}
+
+/// Each change as `Display` prints it, then the statements that apply it.
+fn described(old: &str, new: &str, dialect: Dialect) -> Vec<(String, Vec<String>)> {
+    changes(old, new).iter().map(|change| (change.to_string(), change.to_alter_sql(dialect))).collect()
+}
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+const USERS: &str = "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(20), age INT)";
+
+#[test]
+fn added_columns_are_added_after_the_others() {
+    let new = "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(20), age INT, \
+               email VARCHAR(50) NOT NULL, nick VARCHAR(20) COLLATE nocase)";
+    assert_eq!(
+        described(USERS, new, Dialect::Postgres),
+        [
+            ("+ column users.email VARCHAR(50)".to_string(), strings(&[
+                "ALTER TABLE users ADD COLUMN email VARCHAR(50) NOT NULL"
+            ])),
+            ("+ column users.nick VARCHAR(20)".to_string(), strings(&[
+                "ALTER TABLE users ADD COLUMN nick VARCHAR(20) COLLATE nocase"
+            ])),
+        ]
+    );
+    // Column names are quoted as the target needs them
+    let keyword = "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(20), age INT, `order` INT)";
+    assert_eq!(described(USERS, keyword, Dialect::SqlServer)[0].1, ["ALTER TABLE users ADD COLUMN [order] INT"]);
+}
+
+#[test]
+fn dropped_columns_and_tables_are_dropped() {
+    let new = "CREATE TABLE users (id INT PRIMARY KEY)";
+    assert_eq!(
+        described(&format!("{}; CREATE TABLE audit (at DATE)", USERS), new, Dialect::Postgres),
+        [
+            ("- table audit".to_string(), strings(&["DROP TABLE audit"])),
+            ("- column users.name".to_string(), strings(&["ALTER TABLE users DROP COLUMN name"])),
+            ("- column users.age".to_string(), strings(&["ALTER TABLE users DROP COLUMN age"])),
+        ]
+    );
+    // A column both dropped and added under another name is a drop and an add, not a rename
+    let renamed = "CREATE TABLE users (id INT PRIMARY KEY, full_name VARCHAR(20), age INT)";
+    assert_eq!(
+        changes(USERS, renamed).iter().map(ToString::to_string).collect::<Vec<_>>(),
+        ["- column users.name", "+ column users.full_name VARCHAR(20)"]
+    );
+}
+
+#[test]
+fn altered_columns_are_retyped_or_have_their_constraints_changed() {
+    let new = "CREATE TABLE users (id INT, name VARCHAR(40) NOT NULL, age DECIMAL(5, 1) CHECK (age > 0))";
+    assert_eq!(
+        described(USERS, new, Dialect::Postgres),
+        [
+            ("~ constraints users.id: [PRIMARY KEY] -> []".to_string(), strings(&[
+                "-- drop PRIMARY KEY on users.id by constraint name"
+            ])),
+            ("~ column users.name: VARCHAR(20) -> VARCHAR(40)".to_string(), strings(&[
+                "ALTER TABLE users ALTER COLUMN name SET DATA TYPE VARCHAR(40)"
+            ])),
+            ("~ constraints users.name: [] -> [NOT NULL]".to_string(), strings(&[
+                "ALTER TABLE users ALTER COLUMN name SET NOT NULL"
+            ])),
+            ("~ column users.age: INT -> DECIMAL(5,1)".to_string(), strings(&[
+                "ALTER TABLE users ALTER COLUMN age SET DATA TYPE DECIMAL(5,1)"
+            ])),
+            ("~ constraints users.age: [] -> [CHECK (age > 0)]".to_string(), strings(&[
+                "ALTER TABLE users ADD CHECK (age > 0)"
+            ])),
+        ]
+    );
+    let back = described(new, USERS, Dialect::Postgres);
+    assert_eq!(back[0].1, ["ALTER TABLE users ADD PRIMARY KEY (id)"]);
+    assert_eq!(back[2].1, ["ALTER TABLE users ALTER COLUMN name DROP NOT NULL"]);
+}
+
+#[test]
+fn names_that_only_change_case_are_the_same_table_and_column() {
+    let new = "CREATE TABLE Users (ID INT PRIMARY KEY, Name VARCHAR(20), AGE INT)";
+    assert_eq!(changes(USERS, new), []);
+    let new = "CREATE TABLE USERS (id INT PRIMARY KEY, name VARCHAR(20), age BOOL)";
+    let retype = described(USERS, new, Dialect::Postgres);
+    assert_eq!(retype[0].1, ["ALTER TABLE USERS ALTER COLUMN age SET DATA TYPE BOOL"]);
+    assert_eq!(changes(USERS, USERS), []);
+}
@@ -0,0 +1,84 @@
+// select_list.rs — checks that select lists are parsed as expressions with aliases, and run by the engine
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::analyzer::SqlType;
+use sreerag_devadasan::ast::{BinaryOperator, Expression, Ident, SelectItem, Statement};
+use sreerag_devadasan::engine::{Database, EngineError};
+use sreerag_devadasan::eval::Value;
+use sreerag_devadasan::parser::parse_sql;
+
+fn items(sql: &str) -> Vec<SelectItem> {
+    match parse_sql(sql).unwrap_or_else(|e| panic!("{}: {}", sql, e)) {
+        Statement::Select { columns, .. } => columns,
+        other => panic!("{}: not a SELECT: {:?}", sql, other),
+    }
+}
+
+fn identifier(name: &str) -> Expression {
+    Expression::Identifier(Ident::new(name))
+}
+
+#[test]
+fn keyword_functions_are_calls_in_the_select_list() {
+    let sql = "SELECT LEFT(name, 3) FROM t";
+    let args = vec![identifier("name"), Expression::Number(3)];
+    let left = Expression::Function { name: "LEFT".to_string(), args, over: None };
+    assert_eq!(items(sql), [SelectItem { expr: left, alias: None }]);
+    assert_eq!(parse_sql(sql).unwrap().to_sql(Default::default()), sql);
+
+    for sql in ["SELECT RIGHT(name, 2) FROM t", "SELECT REPLACE(name, 'a', 'b') FROM t"] {
+        assert!(matches!(&items(sql)[0].expr, Expression::Function { .. }), "{}", sql);
+        assert_eq!(parse_sql(sql).unwrap().to_sql(Default::default()), sql);
+    }
+}
+
+#[test]
+fn items_are_expressions_with_aliases() {
+    let columns = items("SELECT a AS b, a + 1, COUNT(*), * FROM t");
+    assert_eq!(columns[0], SelectItem { expr: identifier("a"), alias: Some(Ident::new("b")) });
+    let sum = Expression::BinaryOperation {
+        left_operand: Box::new(identifier("a")),
+        operator: BinaryOperator::Add,
+        right_operand: Box::new(Expression::Number(1)),
+    };
+    assert_eq!(columns[1], SelectItem { expr: sum, alias: None });
+    let count = Expression::Function { name: "COUNT".to_string(), args: vec![identifier("*")], over: None };
+    assert_eq!(columns[2], SelectItem { expr: count, alias: None });
+    assert!(columns[3].is_wildcard());
+    let names: Vec<String> = columns.iter().map(SelectItem::output_name).collect();
+    assert_eq!(names, ["b", "a + 1", "COUNT(*)", "*"]);
+
+    // An alias without AS is printed with it
+    let printed = |sql: &str| parse_sql(sql).unwrap().to_sql(Default::default());
+    assert_eq!(printed("SELECT a b, a + 1, COUNT(*) FROM t"), "SELECT a AS b, a + 1, COUNT(*) FROM t");
+    assert!(parse_sql("SELECT a AS FROM t").is_err());
+    assert!(parse_sql("SELECT a, FROM t").is_err());
+}
+
+#[test]
+fn the_engine_computes_each_item() {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE t (id INT, name VARCHAR(10))").unwrap();
+    db.execute_sql("INSERT INTO t VALUES (2, 'b'), (1, 'a')").unwrap();
+    let result = db.execute_sql("SELECT id AS n, id * 10 + 1, name FROM t ORDER BY id").unwrap();
+    assert_eq!(result.columns, ["n", "id * 10 + 1", "name"]);
+    let row = |id, computed, name: &str| vec![Value::Int(id), Value::Int(computed), Value::String(name.into())];
+    assert_eq!(result.rows, [row(1, 11, "a"), row(2, 21, "b")]);
+
+    // Registered functions are called for each row, and statements differing
+    // in their literals share a plan but not their names
+    db.register_scalar("twice", &[SqlType::Integer], SqlType::Integer, |args| match &args[0] {
+        Value::Int(n) => Ok(Value::Int(n * 2)),
+        other => Ok(other.clone()),
+    });
+    let result = db.execute_sql("SELECT twice(id) AS t, id + 1 FROM t WHERE id = 2").unwrap();
+    assert_eq!(result.columns, ["t", "id + 1"]);
+    assert_eq!(result.rows, [[Value::Int(4), Value::Int(3)]]);
+    let result = db.execute_sql("SELECT twice(id) AS u, id + 2 FROM t WHERE id = 2").unwrap();
+    assert_eq!(result.columns, ["u", "id + 2"]);
+    assert_eq!(result.rows, [[Value::Int(4), Value::Int(4)]]);
+
+    for sql in ["SELECT COUNT(*) FROM t", "SELECT id, ROW_NUMBER() OVER (ORDER BY id) FROM t"] {
+        assert!(matches!(db.execute_sql(sql), Err(EngineError::UnsupportedFeature(_))), "{}", sql);
+    }
+}
@@ -0,0 +1,190 @@
+// semantics.rs — checks SQL's three-valued logic everywhere conditions are evaluated
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::ast::Expression;
+use sreerag_devadasan::bytecode::{Instruction, Program};
+use sreerag_devadasan::engine::{Database, Storage};
+use sreerag_devadasan::eval::{evaluate, EvalError, Value};
+use sreerag_devadasan::pratt::PrattParser;
+use sreerag_devadasan::semantics::{and, distinct, is_null, not, or};
+use sreerag_devadasan::tokenizer::tokenize;
+
+const TRUTHS: [Option<bool>; 3] = [Some(true), Some(false), None];
+
+fn parse(sql: &str) -> Expression {
+    PrattParser::new(&tokenize(sql)).parse_expression(1).unwrap()
+}
+
+fn literal(truth: Option<bool>) -> &'static str {
+    match truth {
+        Some(true) => "TRUE",
+        Some(false) => "FALSE",
+        None => "NULL",
+    }
+}
+
+fn value(truth: Option<bool>) -> Value {
+    truth.map_or(Value::Null, Value::Bool)
+}
+
+/// Two truth values, then `a AND b` and `a OR b`.
+type Case = (Option<bool>, Option<bool>, Option<bool>, Option<bool>);
+
+/// Every pair of truth values.
+fn binary_cases() -> Vec<Case> {
+    let (t, f, u) = (Some(true), Some(false), None);
+    vec![
+        (t, t, t, t),
+        (t, f, f, t),
+        (t, u, u, t),
+        (f, t, f, t),
+        (f, f, f, f),
+        (f, u, f, u),
+        (u, t, u, t),
+        (u, f, f, u),
+        (u, u, u, u),
+    ]
+}
+
+#[test]
+fn truth_tables_are_those_of_the_standard() {
+    assert_eq!(TRUTHS.map(not), [Some(false), Some(true), None]);
+    for (a, b, a_and_b, a_or_b) in binary_cases() {
+        assert_eq!(and(a, b), a_and_b, "{:?} AND {:?}", a, b);
+        assert_eq!(or(a, b), a_or_b, "{:?} OR {:?}", a, b);
+    }
+}
+
+#[test]
+fn the_evaluator_and_the_bytecode_agree_with_the_tables() {
+    let columns = ["a".to_string(), "b".to_string()];
+    for (a, b, a_and_b, a_or_b) in binary_cases() {
+        let row = [value(a), value(b)];
+        for (operator, expected) in [("AND", a_and_b), ("OR", a_or_b)] {
+            let constant = parse(&format!("{} {} {}", literal(a), operator, literal(b)));
+            let over_columns = parse(&format!("a {} b", operator));
+            for expr in [&constant, &over_columns] {
+                assert_eq!(evaluate(expr, &columns, &row).unwrap(), value(expected), "{:?}", expr);
+                let program = Program::compile(expr, &columns).unwrap();
+                assert_eq!(program.evaluate(&row).unwrap(), value(expected), "{:?}", expr);
+            }
+            // Folding a constant condition leaves the value the evaluator computes for it
+            let program = Program::compile(&constant, &columns).unwrap();
+            assert_eq!(program.instructions(), [Instruction::Push(value(expected))]);
+        }
+    }
+    for a in TRUTHS {
+        let expr = parse(&format!("NOT {}", literal(a)));
+        assert_eq!(evaluate(&expr, &[], &[]).unwrap(), value(not(a)));
+        assert_eq!(Program::compile(&expr, &[]).unwrap().evaluate(&[]).unwrap(), value(not(a)));
+    }
+}
+
+#[test]
+fn every_other_operator_is_null_on_a_null_operand() {
+    for sql in ["NULL = NULL", "NULL != NULL", "1 < NULL", "NULL + 1", "NULL LIKE '%'", "NOT (NULL = NULL)"] {
+        let expr = parse(sql);
+        assert_eq!(evaluate(&expr, &[], &[]).unwrap(), Value::Null, "{}", sql);
+        let program = Program::compile(&expr, &[]).unwrap();
+        assert_eq!(program.instructions(), [Instruction::Push(Value::Null)], "{}", sql);
+    }
+
+    // A constant that fails is left for run time, where it fails the same way
+    let program = Program::compile(&parse("1 / 0 = 1"), &[]).unwrap();
+    assert!(program.instructions().len() > 1);
+    assert!(matches!(program.evaluate(&[]), Err(EvalError::DivisionByZero)));
+    assert!(matches!(evaluate(&parse("1 / 0 = 1"), &[], &[]), Err(EvalError::DivisionByZero)));
+}
+
+#[test]
+fn is_null_and_is_distinct_from_are_never_unknown() {
+    let columns = ["a".to_string(), "b".to_string()];
+    let (one, two, null) = (Value::Int(1), Value::Int(2), Value::Null);
+    // Two values, then `a IS NULL` and `a IS DISTINCT FROM b`
+    let cases = [
+        (&one, &one, false, false),
+        (&one, &two, false, true),
+        (&one, &null, false, true),
+        (&null, &one, true, true),
+        (&null, &null, true, false),
+    ];
+    for (a, b, a_is_null, a_distinct_from_b) in cases {
+        assert_eq!(is_null(a), a_is_null, "{:?}", a);
+        let equal = |a: &Value, b: &Value| Ok(Value::Bool(a == b));
+        assert_eq!(distinct(a, b, equal), Ok(a_distinct_from_b), "{:?} {:?}", a, b);
+
+        let row = [a.clone(), b.clone()];
+        for (sql, expected) in [
+            ("a IS NULL", a_is_null),
+            ("a IS NOT NULL", !a_is_null),
+            ("a IS DISTINCT FROM b", a_distinct_from_b),
+            ("a IS NOT DISTINCT FROM b", !a_distinct_from_b),
+            ("NOT a IS NULL", !a_is_null),
+        ] {
+            let expr = parse(sql);
+            assert_eq!(evaluate(&expr, &columns, &row), Ok(Value::Bool(expected)), "{} on {:?}", sql, row);
+            let program = Program::compile(&expr, &columns).unwrap();
+            assert_eq!(program.evaluate(&row), Ok(Value::Bool(expected)), "{} on {:?}", sql, row);
+        }
+    }
+
+    // Constants fold to the value
+    for (sql, expected) in [
+        ("NULL IS NULL", true),
+        ("NULL IS NOT NULL", false),
+        ("(NULL = NULL) IS NULL", true),
+        ("NULL IS DISTINCT FROM NULL", false),
+        ("NULL IS NOT DISTINCT FROM NULL", true),
+        ("1 IS DISTINCT FROM NULL", true),
+        ("'a' IS NOT DISTINCT FROM 'a'", true),
+    ] {
+        let expr = parse(sql);
+        assert_eq!(evaluate(&expr, &[], &[]), Ok(Value::Bool(expected)), "{}", sql);
+        let program = Program::compile(&expr, &[]).unwrap();
+        assert_eq!(program.instructions(), [Instruction::Push(Value::Bool(expected))], "{}", sql);
+    }
+    // Values `=` can't compare still can't be compared
+    let error = evaluate(&parse("1 IS DISTINCT FROM 'a'"), &[], &[]).unwrap_err();
+    assert_eq!(evaluate(&parse("1 = 'a'"), &[], &[]).unwrap_err(), error);
+}
+
+#[test]
+fn where_keeps_only_the_rows_a_condition_is_true_for() {
+    for storage in [Storage::Rows, Storage::Columnar] {
+        let mut db = Database::with_storage(storage);
+        db.execute_sql("CREATE TABLE t (id INT, a BOOL, b BOOL, x INT)").unwrap();
+        for (id, (a, b, _, _)) in binary_cases().into_iter().enumerate() {
+            let x = if a.is_none() { "NULL" } else { "1" };
+            let sql = format!("INSERT INTO t VALUES ({}, {}, {}, {})", id, literal(a), literal(b), x);
+            db.execute_sql(&sql).unwrap();
+        }
+        let mut ids = |condition: &str| -> Vec<usize> {
+            let sql = format!("SELECT id FROM t WHERE {} ORDER BY id", condition);
+            let rows = db.execute_sql(&sql).unwrap().rows;
+            rows.iter().map(|row| if let Value::Int(id) = row[0] { id as usize } else { unreachable!() }).collect()
+        };
+        let expected = |keep: &dyn Fn(Option<bool>, Option<bool>) -> Option<bool>| -> Vec<usize> {
+            let cases = binary_cases().into_iter().enumerate();
+            cases.filter(|(_, (a, b, _, _))| keep(*a, *b) == Some(true)).map(|(id, _)| id).collect()
+        };
+
+        assert_eq!(ids("a AND b"), expected(&|a, b| and(a, b)), "{:?}", storage);
+        assert_eq!(ids("a OR b"), expected(&|a, b| or(a, b)), "{:?}", storage);
+        assert_eq!(ids("NOT (a AND b)"), expected(&|a, b| not(and(a, b))), "{:?}", storage);
+        assert_eq!(ids("NOT (a OR b)"), expected(&|a, b| not(or(a, b))), "{:?}", storage);
+        // Neither a condition nor its negation keeps the rows where x is NULL
+        assert_eq!(ids("x = 1").len() + ids("NOT (x = 1)").len(), 6);
+        assert_eq!(ids("NOT (x = 1)"), []);
+        assert_eq!(ids("x = NULL OR x != NULL"), []);
+        // A constant condition is folded, but keeps what it would have kept
+        assert_eq!(ids("NULL OR FALSE"), []);
+        assert_eq!(ids("NULL OR TRUE").len(), 9);
+        // IS NULL and IS DISTINCT FROM keep the rows = and != can't decide
+        assert_eq!(ids("x IS NULL"), [6, 7, 8]);
+        assert_eq!(ids("x IS NOT NULL"), [0, 1, 2, 3, 4, 5]);
+        assert_eq!(ids("a IS NULL OR b IS NULL"), [2, 5, 6, 7, 8]);
+        assert_eq!(ids("a IS NOT DISTINCT FROM b"), [0, 4, 8]);
+        assert_eq!(ids("a IS DISTINCT FROM b"), [1, 2, 3, 5, 6, 7]);
+        assert_eq!(ids("x IS DISTINCT FROM 1"), [6, 7, 8]);
+    }
+}
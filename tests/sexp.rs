@@ -0,0 +1,50 @@
+// sexp.rs — checks the s-expressions of parsed statements
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::parser::{parse_sql, parse_sql_with_dialect};
+use sreerag_devadasan::repl::{OutputMode, ReplConfig};
+
+fn sexp(sql: &str) -> String {
+    parse_sql(sql).unwrap().to_sexp()
+}
+
+#[test]
+fn expressions_are_written_operator_first() {
+    let select = sexp("SELECT id, name FROM users WHERE age > 18");
+    assert_eq!(select, "(select (columns id name) (from users) (where (> age 18)))");
+    assert_eq!(
+        sexp("SELECT a FROM t WHERE NOT (a = 1 OR b LIKE 'x%') AND c = -d"),
+        r#"(select (columns a) (from t) (where (and (not (or (= a 1) (like b "x%"))) (= c (- d)))))"#
+    );
+    assert_eq!(
+        sexp("SELECT a FROM t WHERE f(t.a, :p) = NULL AND count(a) OVER () > 1"),
+        "(select (columns a) (from t) (where (and (= (f t.a :p) null) (> (count a (over)) 1))))"
+    );
+    assert_eq!(sexp("SELECT a AS b, a + 1 FROM t"), "(select (columns (as a b) (+ a 1)) (from t))");
+    let quoted = parse_sql_with_dialect("SELECT \"Name\" FROM t", Dialect::Postgres).unwrap();
+    assert_eq!(quoted.to_sexp(), "(select (columns |Name|) (from t))");
+}
+
+#[test]
+fn other_nodes_are_lists_of_their_fields() {
+    assert_eq!(
+        sexp("SELECT a FROM t AS x JOIN u ON x.id = u.id LIMIT 3"),
+        "(select (columns a) (from (table (name t) (alias (name x)))) \
+         (joins (join (kind inner) (relation u) (on (= x.id u.id)))) (limit 3))"
+    );
+    assert_eq!(
+        sexp("CREATE TABLE t (id INT PRIMARY KEY, name VARCHAR(10))"),
+        "(create-table (table-name t) (column-list (table-column (column-name id) (column-type int) \
+         (constraints primary-key)) (table-column (column-name name) (column-type (varchar 10)))))"
+    );
+    assert_eq!(sexp("INSERT INTO t (a) VALUES (1, 'x')"), r#"(insert (table-name t) (columns a) (rows (1 "x")))"#);
+    assert_eq!(sexp("DROP TABLE t"), "(drop-table (table-name t))");
+}
+
+#[test]
+fn the_repl_shows_s_expressions_unless_asked_for_debug() {
+    assert_eq!(ReplConfig::default().output, OutputMode::Sexp);
+    assert_eq!(OutputMode::from_name("AST"), Some(OutputMode::Ast));
+    assert_eq!(OutputMode::from_name("sexp"), Some(OutputMode::Sexp));
+}
@@ -0,0 +1,133 @@
+// span.rs — checks that the source map gives every statement, FROM item, join and expression its own text
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::ast::{Expression, Statement, TableFactor};
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::parser::parse_sql_with_dialect;
+use sreerag_devadasan::rename::{apply_edits, rename_column_edits};
+use sreerag_devadasan::span::{parse_with_source_map, SourceMap};
+use sreerag_devadasan::visit::{walk, Visitor};
+
+const QUERIES: &[&str] = &[
+    "SELECT a + b * 2 AS c, f(x, -y) FROM t WHERE NOT (a = 1 OR b != 2)",
+    "SELECT a FROM (SELECT b + 1 AS a FROM (SELECT b FROM u) AS v) AS s WHERE a > (2)",
+    "SELECT CAST(a + 1 AS DECIMAL(10, 2)), a::INT, DATE '2024-01-31' FROM t",
+    "SELECT COUNT(*), rank() OVER (PARTITION BY a, b ORDER BY c DESC, d) FROM t GROUP BY ROLLUP (a, b)",
+    "SELECT t.a, * FROM t AS x LEFT OUTER JOIN u ON t.a = u.a, v CROSS JOIN w JOIN (SELECT 1 FROM y) AS z ON TRUE",
+    "(SELECT a FROM t) UNION SELECT b FROM u INTERSECT SELECT c FROM v EXCEPT VALUES (1), (2 + 3)",
+    "WITH s AS (SELECT a FROM t WHERE a LIKE 'x%'), r AS (SELECT * FROM s) SELECT a FROM r ORDER BY a",
+    "INSERT INTO t (a, b) SELECT a, b FROM u TABLESAMPLE SYSTEM (10) REPEATABLE (4 + 2)",
+    "UPDATE t SET a = a + 1, b = lower(b) WHERE a = 1",
+    "DELETE FROM t WHERE a = 1 AND b = 'x' COLLATE nocase",
+    "EXPLAIN SELECT a FROM t, LATERAL f(t.a, 2) AS g",
+    "CREATE VIEW v AS SELECT a FROM t UNION ALL SELECT a FROM u",
+];
+
+const WAREHOUSE_QUERIES: &[&str] = &[
+    "SELECT * FROM t PIVOT (SUM(amount) FOR month IN ('jan', 'feb')) AS p UNPIVOT (v FOR k IN (jan, feb)) AS q",
+    "SELECT * REPLACE (a + 1 AS a), b FROM t QUALIFY row_number() OVER (ORDER BY b) = 1",
+];
+
+#[derive(Default)]
+struct Count {
+    statements: usize,
+    factors: usize,
+    expressions: usize,
+}
+
+impl Visitor for Count {
+    fn statement(&mut self, _statement: &Statement) {
+        self.statements += 1;
+    }
+
+    fn table_factor(&mut self, _factor: &TableFactor) {
+        self.factors += 1;
+    }
+
+    fn expression(&mut self, _expr: &Expression) {
+        self.expressions += 1;
+    }
+}
+
+fn map(sql: &str, dialect: Dialect) -> (Statement, SourceMap) {
+    parse_with_source_map(sql, dialect).unwrap_or_else(|e| panic!("{}: {}", sql, e))
+}
+
+/// Checks that every node of `sql` has a span, and that parsing the text
+/// of the span again gives the node back.
+fn check(sql: &str, dialect: Dialect) {
+    let (statement, map) = map(sql, dialect);
+    let reparse = |text: String| parse_sql_with_dialect(&text, dialect).unwrap_or_else(|e| panic!("{}: {}", text, e));
+
+    let mut count = Count::default();
+    walk(&statement, &mut count);
+    assert_eq!(map.statements.len(), count.statements, "{}", sql);
+    assert_eq!(map.factors.len(), count.factors, "{}", sql);
+    assert_eq!(map.expressions.len(), count.expressions, "{}", sql);
+    assert_eq!(map.statements[0], map.statement, "{}", sql);
+
+    for (node, span) in map.statement_spans(&statement) {
+        assert_eq!(&reparse(span.slice(sql).to_string()), node, "{}", span.slice(sql));
+    }
+    for (node, span) in map.factor_spans(&statement) {
+        let Statement::Select { from, .. } = reparse(format!("SELECT * FROM {}", span.slice(sql))) else { panic!() };
+        assert_eq!(&*from, node, "{}", span.slice(sql));
+    }
+    for (node, span) in map.expression_spans(&statement) {
+        let Statement::Select { columns, .. } = reparse(format!("SELECT {} FROM t", span.slice(sql))) else { panic!() };
+        assert_eq!(&columns[0].expr, node, "{}", span.slice(sql));
+    }
+}
+
+#[test]
+fn every_node_spans_the_text_it_was_parsed_from() {
+    for sql in QUERIES {
+        check(sql, Dialect::Generic);
+    }
+    for sql in WAREHOUSE_QUERIES {
+        check(sql, Dialect::Warehouse);
+    }
+
+    // Spans are where the nodes are, whatever the spacing and comments
+    let sql = "SELECT  ( a )+1 ,x  FROM t /* c */  JOIN\n u  ON  a=b";
+    let (statement, map) = map(sql, Dialect::Generic);
+    let texts: Vec<&str> = map.expression_spans(&statement).iter().map(|(_, span)| span.slice(sql)).collect();
+    assert_eq!(texts, ["( a )+1", "( a )", "a", "1", "x", "a=b", "a", "b"]);
+    let joins: Vec<&str> = map.join_spans(&statement).iter().map(|(_, span)| span.slice(sql)).collect();
+    assert_eq!(joins, ["JOIN\n u  ON  a=b"]);
+}
+
+#[test]
+fn joins_span_their_keywords_table_and_condition() {
+    let sql = QUERIES[4];
+    let (statement, map) = map(sql, Dialect::Generic);
+    let joins: Vec<&str> = map.join_spans(&statement).iter().map(|(_, span)| span.slice(sql)).collect();
+    assert_eq!(joins, ["LEFT OUTER JOIN u ON t.a = u.a", "v", "CROSS JOIN w", "JOIN (SELECT 1 FROM y) AS z ON TRUE"]);
+    let factors: Vec<&str> = map.factor_spans(&statement).iter().map(|(_, span)| span.slice(sql)).collect();
+    assert_eq!(factors, ["t AS x", "u", "v", "w", "(SELECT 1 FROM y) AS z", "y"]);
+}
+
+#[test]
+fn nodes_the_parser_makes_up_span_what_they_stand_for() {
+    let sql = "SELECT x FROM t WHERE x NOT BETWEEN 1 AND 2 + 1 OR EXTRACT(year FROM d) = 2024";
+    let (statement, map) = map(sql, Dialect::Generic);
+    let spans = map.expression_spans(&statement);
+    let text = |expr: &Expression| spans.iter().find(|(node, _)| *node == expr).map(|(_, span)| span.slice(sql));
+    let Statement::Select { selection: Some(Expression::BinaryOperation { left_operand, right_operand, .. }), .. } =
+        &statement
+    else {
+        panic!("{:?}", statement)
+    };
+    assert_eq!(text(left_operand), Some("x NOT BETWEEN 1 AND 2 + 1"));
+    assert_eq!(text(right_operand), Some("EXTRACT(year FROM d) = 2024"));
+    let texts: Vec<&str> = spans.iter().map(|(_, span)| span.slice(sql)).collect();
+    let range = "x NOT BETWEEN 1 AND 2 + 1";
+    assert_eq!(texts[2..9], [range, range, range, range, "x NOT BETWEEN 1", "x", "1"]);
+    assert_eq!(texts[9..14], [range, "x", "2 + 1", "2", "1"]);
+    assert_eq!(texts[14..], ["EXTRACT(year FROM d) = 2024", "EXTRACT(year FROM d)", "year", "d", "2024"]);
+
+    // Renaming edits the operand once, where it was written
+    let edits = rename_column_edits(sql, Dialect::Generic, "t", "x", "y").unwrap();
+    assert_eq!(edits.len(), 2);
+    assert!(apply_edits(sql, &edits).starts_with("SELECT y FROM t WHERE y NOT BETWEEN"));
+}
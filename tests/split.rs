@@ -0,0 +1,101 @@
+// split.rs — checks splitting scripts and dump files into statements without parsing them
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::batch::{parse_script, split_statements, split_statements_with_dialect, statement_spans};
+use sreerag_devadasan::dialect::Dialect;
+
+fn texts(script: &str) -> Vec<&str> {
+    split_statements(script).into_iter().map(|(_, sql)| sql).collect()
+}
+
+#[test]
+fn semicolons_in_strings_comments_and_bodies_end_nothing() {
+    let script = "INSERT INTO t VALUES ('a;b', 'it''s; fine', \"x;y\", `c;d`);\n\
+                  SELECT 1 -- it's; a comment\n  FROM t /* ; ' */ WHERE a = $1;\n\
+                  CREATE FUNCTION f() RETURNS INT AS $body$ SELECT 1; $body$ LANGUAGE sql;SELECT $$;$$";
+    assert_eq!(
+        texts(script),
+        [
+            "INSERT INTO t VALUES ('a;b', 'it''s; fine', \"x;y\", `c;d`)",
+            "SELECT 1 -- it's; a comment\n  FROM t /* ; ' */ WHERE a = $1",
+            "CREATE FUNCTION f() RETURNS INT AS $body$ SELECT 1; $body$ LANGUAGE sql",
+            "SELECT $$;$$",
+        ]
+    );
+    // Each statement comes with where it is in the script
+    for (span, sql) in split_statements(script) {
+        assert_eq!(span.slice(script), sql);
+    }
+    assert_eq!(statement_spans(script), split_statements(script).into_iter().map(|(span, _)| span).collect::<Vec<_>>());
+
+    // Unterminated text runs to the end of the script
+    assert_eq!(texts("SELECT 'a; b; SELECT 2"), ["SELECT 'a; b; SELECT 2"]);
+    assert_eq!(texts("SELECT 1; /* a; b"), ["SELECT 1"]);
+    assert!(texts(" ;; \n-- nothing; here\n").is_empty());
+}
+
+#[test]
+fn backslashes_escape_quotes_in_e_strings_and_mysql() {
+    assert_eq!(texts("SELECT E'a\\';b' FROM t; SELECT e'\\\\'"), ["SELECT E'a\\';b' FROM t", "SELECT e'\\\\'"]);
+    // Elsewhere a backslash is just a character
+    assert_eq!(texts("SELECT 'c:\\'; SELECT `d\\`"), ["SELECT 'c:\\'", "SELECT `d\\`"]);
+
+    let mysql = |script| -> Vec<&str> {
+        split_statements_with_dialect(script, Dialect::MySql).into_iter().map(|(_, sql)| sql).collect()
+    };
+    assert_eq!(
+        mysql("INSERT INTO t VALUES ('it\\'s; x', \"a\\\";b\", 'd''e;'); SELECT a FROM t;"),
+        ["INSERT INTO t VALUES ('it\\'s; x', \"a\\\";b\", 'd''e;')", "SELECT a FROM t"]
+    );
+    assert_eq!(mysql("SELECT `d\\`; SELECT 1"), ["SELECT `d\\`", "SELECT 1"]);
+}
+
+#[test]
+fn dumps_split_into_transactions_savepoints_and_blocks() {
+    let dump = "-- Dump of shop; restore with care\n\
+                /* header; */\n\
+                BEGIN;\n\
+                CREATE TABLE t (id INT);\n\
+                SAVEPOINT rows;\n\
+                INSERT INTO t VALUES (1), (2); -- first batch\n\
+                ROLLBACK TO SAVEPOINT rows;\n\
+                RELEASE SAVEPOINT rows;\n\
+                CREATE TRIGGER stamp BEFORE INSERT ON t FOR EACH ROW BEGIN SET NEW.id = 1; END;\n\
+                COMMIT;\n\
+                -- Dump completed\n";
+    let statements = texts(dump);
+    assert_eq!(
+        statements,
+        [
+            "BEGIN",
+            "CREATE TABLE t (id INT)",
+            "SAVEPOINT rows",
+            "INSERT INTO t VALUES (1), (2)",
+            "ROLLBACK TO SAVEPOINT rows",
+            "RELEASE SAVEPOINT rows",
+            "CREATE TRIGGER stamp BEFORE INSERT ON t FOR EACH ROW BEGIN SET NEW.id = 1; END",
+            "COMMIT",
+        ]
+    );
+    // The pieces can go to the parser's workers as they are
+    let parsed = parse_script(dump);
+    assert_eq!(parsed.len(), statements.len());
+    for index in [0, 1, 3, 6, 7] {
+        assert!(parsed[index].is_ok(), "{}: {:?}", statements[index], parsed[index]);
+    }
+
+    // As a mysql dump writes a trigger
+    let dump = "DROP TRIGGER IF EXISTS stamp;\n\
+                DELIMITER ;;\n\
+                CREATE TRIGGER stamp BEFORE INSERT ON t FOR EACH ROW SET NEW.id = 1; ;;\n\
+                DELIMITER ;\n\
+                SELECT 1;";
+    assert_eq!(
+        texts(dump),
+        [
+            "DROP TRIGGER IF EXISTS stamp",
+            "CREATE TRIGGER stamp BEFORE INSERT ON t FOR EACH ROW SET NEW.id = 1;",
+            "SELECT 1",
+        ]
+    );
+}
@@ -0,0 +1,155 @@
+// sqlparser.rs — checks converting statements to and from the JSON of sqlparser-rs's AST
+// Author: Sreerag Devadasan
+
+#![cfg(feature = "sqlparser")]
+
+use sreerag_devadasan::ast::Statement;
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::parser::{parse_sql, parse_sql_with_dialect};
+use sreerag_devadasan::sqlparser::{from_json, to_json, SqlparserError};
+
+const QUERIES: &[&str] = &[
+    "SELECT a, b + 1 AS c, -d, NOT e FROM t WHERE a = 1 AND (b != 2 OR c LIKE 'x%') OR d LIKE :p",
+    "SELECT t.a, \"B\", `c` FROM t AS x (a, b) LEFT JOIN u ON t.a = u.a RIGHT JOIN v ON TRUE FULL JOIN w ON x.a = w.a",
+    "SELECT * FROM t, u CROSS JOIN v JOIN (SELECT 1 FROM y) AS z ON z.a = 1, LATERAL f(t.a, 2) AS g",
+    "SELECT x FROM generate_series(1, 10) AS s WHERE x NOT BETWEEN 2 AND 3 ORDER BY x LIMIT 5 OFFSET 1",
+    "SELECT a FROM t ORDER BY a DESC, b + 1 ASC, c COLLATE nocase, lower(d)",
+    "SELECT CAST(a AS DECIMAL(10, 2)), a::INT, CAST(b AS VARCHAR(5)), DATE '2024-01-31', TIMESTAMP '2024-01-31' FROM t",
+    "SELECT 9.99, 'it''s', N'n', E'e\\n', X'FF', NULL, FALSE, a || b, a COLLATE nocase FROM t",
+    "SELECT COUNT(*), rank() OVER (PARTITION BY a ORDER BY b DESC, c) FROM t GROUP BY ROLLUP (a, b), CUBE (c), d",
+    "SELECT a FROM t GROUP BY GROUPING SETS ((a, b), (a), ())",
+    "(SELECT a FROM t ORDER BY a LIMIT 1) UNION ALL SELECT b FROM u EXCEPT SELECT c FROM v ORDER BY c LIMIT 2",
+    "SELECT a FROM t INTERSECT VALUES (1), (2 * 3)",
+    "WITH RECURSIVE r (n) AS (SELECT 1 FROM t UNION ALL SELECT n + 1 FROM r WHERE n < 5) SELECT n FROM r",
+    "SELECT a FROM t WHERE a = 1 FOR UPDATE OF t NOWAIT",
+    "SELECT a IS NULL, b IS NOT NULL FROM t WHERE a IS DISTINCT FROM b AND NOT b IS NOT DISTINCT FROM 1",
+    "SELECT a FROM t FOR SHARE SKIP LOCKED",
+    "INSERT INTO t (a, b) VALUES (1, 'x'), (2, NULL)",
+    "INSERT INTO t SELECT a FROM u WHERE a > 1",
+    "UPDATE t AS x SET a = a + 1, b = lower(b) WHERE a = 1",
+    "DELETE FROM t WHERE a = 1",
+    "DROP TABLE IF EXISTS t",
+];
+
+const DIALECT_QUERIES: &[(Dialect, &str)] = &[
+    (Dialect::Postgres, "SELECT a FROM t WHERE a ILIKE 'x%'"),
+    (Dialect::SqlServer, "SELECT [a] FROM [t]"),
+    (Dialect::Warehouse, "SELECT * EXCLUDE (a, b), c FROM t PIVOT (SUM(x) FOR m IN ('jan', 'feb')) AS p"),
+    (Dialect::Warehouse, "SELECT * REPLACE (a + 1 AS a) FROM t UNPIVOT (v FOR k IN (jan, feb)) AS q"),
+    (Dialect::Warehouse, "SELECT a FROM t QUALIFY row_number() OVER (ORDER BY a) = 1"),
+];
+
+fn parse(sql: &str, dialect: Dialect) -> Statement {
+    parse_sql_with_dialect(sql, dialect).unwrap_or_else(|e| panic!("{}: {}", sql, e))
+}
+
+fn round_trip(statement: &Statement) -> Statement {
+    let json = to_json(statement).unwrap_or_else(|e| panic!("{:?}: {}", statement, e));
+    from_json(&json).unwrap_or_else(|e| panic!("{}: {}", json, e))
+}
+
+#[test]
+fn statements_come_back_from_sqlparser_json_unchanged() {
+    for sql in QUERIES {
+        let statement = parse(sql, Dialect::Generic);
+        assert_eq!(round_trip(&statement), statement, "{}", sql);
+    }
+    for &(dialect, sql) in DIALECT_QUERIES {
+        let statement = parse(sql, dialect);
+        assert_eq!(round_trip(&statement), statement, "{}", sql);
+    }
+}
+
+#[test]
+fn json_has_the_shape_serde_gives_sqlparser_statements() {
+    let json = to_json(&parse_sql("DROP TABLE IF EXISTS t").unwrap()).unwrap();
+    assert_eq!(
+        json,
+        r#"{"Drop":{"object_type":"Table","if_exists":true,"names":[[{"value":"t","quote_style":null}]],"#.to_string()
+            + r#""cascade":false,"restrict":false,"purge":false,"temporary":false}}"#
+    );
+
+    // A set operation's trailing clauses are on the query around it
+    let json = to_json(&parse_sql("SELECT a FROM t UNION SELECT b FROM u LIMIT 3").unwrap()).unwrap();
+    assert!(json.starts_with(r#"{"Query":{"with":null,"body":{"SetOperation":{"op":"Union","set_quantifier":"None""#));
+    assert!(json.contains(r#""limit":{"Value":{"Number":["3",false]}},"limit_by":[]"#), "{}", json);
+    assert!(json.contains(r#"{"UnnamedExpr":{"Identifier":{"value":"b","quote_style":null}}}"#), "{}", json);
+    assert!(json.contains(r#""group_by":{"Expressions":[[],[]]}"#), "{}", json);
+
+    // Each IS test is an expression of its own
+    let json = to_json(&parse_sql("SELECT a IS NULL, a IS DISTINCT FROM b FROM t").unwrap()).unwrap();
+    assert!(json.contains(r#"{"IsNull":{"Identifier":{"value":"a","quote_style":null}}}"#), "{}", json);
+    let identifier = |name: &str| format!(r#"{{"Identifier":{{"value":"{}","quote_style":null}}}}"#, name);
+    let distinct = format!(r#"{{"IsDistinctFrom":[{},{}]}}"#, identifier("a"), identifier("b"));
+    assert!(json.contains(&distinct), "{}", json);
+}
+
+#[test]
+fn json_written_by_sqlparser_reads_as_the_parser_reads_the_sql() {
+    let ident = |name: &str| format!(r#"{{"value":"{}","quote_style":null}}"#, name);
+    let number = |n: u64| format!(r#"{{"Value":{{"Number":["{}",false]}}}}"#, n);
+    let identifier = |name: &str| format!(r#"{{"Identifier":{}}}"#, ident(name));
+    let table = |name: String| format!(r#"{{"relation":{{"Table":{{"name":[{}]}}}}}}"#, name);
+    let extract = format!(r#"{{"UnnamedExpr":{{"Extract":{{"field":"Year","expr":{}}}}}}}"#, identifier("d"));
+    let between = format!(
+        r#"{{"Between":{{"expr":{},"negated":true,"low":{},"high":{}}}}}"#,
+        identifier("x"),
+        number(1),
+        number(2)
+    );
+    // Fields serde would leave out are missing, and `t` is named in 0.55's parts
+    let select = format!(
+        r#"{{"projection":[{}],"from":[{},{}],"selection":{}}}"#,
+        extract,
+        table(identifier("t")),
+        table(ident("u")),
+        between
+    );
+    let json = format!(
+        r#"{{"Query":{{"body":{{"Select":{}}},"limit":{},"offset":{{"value":{},"rows":"None"}}}}}}"#,
+        select,
+        number(5),
+        number(6)
+    );
+    let sql = "SELECT EXTRACT(year FROM d) FROM t, u WHERE x NOT BETWEEN 1 AND 2 LIMIT 5 OFFSET 6";
+    let expected = parse_sql(sql).unwrap();
+    assert_eq!(from_json(&json).unwrap(), expected);
+    let json = " {\"Query\"\n: {\"body\": {\"Values\": {\"rows\": [[{\"Value\": \"Null\"}]]}}}} ";
+    assert_eq!(from_json(json).unwrap(), parse_sql("VALUES (NULL)").unwrap());
+
+    // Later versions keep an ORDER BY item's direction in `options`
+    let item = format!(r#"{{"expr":{},"options":{{"asc":false,"nulls_first":null}}}}"#, identifier("a"));
+    let select = format!(r#"{{"projection":[{{"UnnamedExpr":{}}}],"from":[{}]}}"#, identifier("a"), table(ident("t")));
+    let order_by = format!(r#"{{"kind":{{"Expressions":[{}]}}}}"#, item);
+    let json = format!(r#"{{"Query":{{"body":{{"Select":{}}},"order_by":{}}}}}"#, select, order_by);
+    assert_eq!(from_json(&json).unwrap(), parse_sql("SELECT a FROM t ORDER BY a DESC").unwrap());
+    let first = json.replace(r#""nulls_first":null"#, r#""nulls_first":true"#);
+    assert_eq!(from_json(&first), Err(SqlparserError::Unsupported("OrderByOptions.nulls_first".into())));
+}
+
+#[test]
+fn what_only_one_ast_has_is_unsupported() {
+    for (sql, node) in [
+        ("CREATE TABLE t (a INT)", "CREATE TABLE"),
+        ("SELECT a FROM t TABLESAMPLE SYSTEM (10)", "TABLESAMPLE"),
+        ("SELECT a FROM t AS OF VERSION 2", "AS OF VERSION"),
+        ("SELECT B'101' FROM t", "Bit string literals"),
+        ("SELECT a FROM t FOR NO KEY UPDATE", "FOR NO KEY UPDATE"),
+    ] {
+        assert_eq!(to_json(&parse_sql(sql).unwrap()), Err(SqlparserError::Unsupported(node.into())), "{}", sql);
+    }
+
+    let query = |select: &str| format!(r#"{{"Query":{{"body":{{"Select":{{{}}}}}}}}}"#, select);
+    let table = r#""from":[{"relation":{"Table":{"name":[{"value":"t","quote_style":null}]}}}]"#;
+    let having = query(&format!(r#"{},"having":{{"Value":{{"Boolean":true}}}}"#, table));
+    assert_eq!(from_json(&having), Err(SqlparserError::Unsupported("Select.having".into())));
+    let error = from_json(&query("")).unwrap_err();
+    assert_eq!(error.to_string(), "A SELECT without FROM can't be converted between the two ASTs");
+    assert_eq!(from_json(r#"{"Truncate":{}}"#), Err(SqlparserError::Unsupported("Statement::Truncate".into())));
+
+    for json in ["", "{\"Query\":", "[1, 2", "\"\\q\"", "nul", "{\"Query\": {}} x", &"[".repeat(10_000)] {
+        assert!(matches!(from_json(json), Err(SqlparserError::Malformed(_))), "{}", json);
+    }
+    let error = from_json("[]").unwrap_err();
+    assert_eq!(error.to_string(), "Malformed sqlparser JSON: expected an enum variant, found an array");
+}
@@ -0,0 +1,66 @@
+// standard.rs — checks labeling statements with the SQL standard features they need
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::standard::{conformance, statement_conformance, Level, Requirement};
+
+fn ids(sql: &str) -> Vec<&'static str> {
+    conformance(sql, Dialect::Generic).unwrap().features().iter().map(|feature| feature.id).collect()
+}
+
+#[test]
+fn constructs_are_labeled_with_their_features() {
+    let report = conformance("SELECT a FROM t LEFT JOIN u ON t.id = u.id WHERE b LIKE 'x%'", Dialect::Generic).unwrap();
+    let constructs: Vec<&str> = report.usages.iter().map(|usage| usage.construct.as_str()).collect();
+    assert_eq!(constructs, ["SELECT", "LEFT JOIN", "LIKE"]);
+    assert_eq!(report.level(), Level::Core);
+    assert_eq!(ids("SELECT a FROM t WHERE CAST(b AS DATE) = DATE '2024-01-01'"), ["E051", "F051-01", "F201"]);
+    assert_eq!(
+        ids("CREATE TABLE t (id INT PRIMARY KEY, name VARCHAR(10) NOT NULL CHECK (name = 'a'))"),
+        ["E011", "E021", "E141-01", "E141-03", "E141-06", "F031-01"]
+    );
+
+    // Optional features raise the level, and are listed once
+    let sql = "WITH RECURSIVE r AS (SELECT a FROM t) SELECT a FROM r INTERSECT SELECT a FROM (SELECT a FROM u) s";
+    assert_eq!(ids(sql), ["E051", "F302-01", "F591", "T121", "T131"]);
+    let report = conformance(sql, Dialect::Generic).unwrap();
+    assert_eq!(report.level(), Level::Optional);
+    assert!(report.to_string().ends_with("needs Core SQL with optional features: F302-01, F591, T121, T131"));
+    assert_eq!(ids("SELECT a FROM t ORDER BY a OFFSET 5 ROWS FETCH FIRST 10 ROWS ONLY"), ["E051", "F857", "F861"]);
+    assert_eq!(ids("SELECT a FROM t UNION ALL SELECT a FROM u"), ["E051", "E071-02"]);
+    assert_eq!(ids("SELECT a FROM t WHERE b = N'x' AND c = X'00'"), ["E051", "F421", "T021"]);
+    assert_eq!(ids("SELECT a FROM t WHERE b IS NULL OR c IS NOT NULL"), ["E051", "E061-06"]);
+    assert_eq!(ids("SELECT a FROM t WHERE b IS NOT DISTINCT FROM c"), ["E051", "T151", "T152"]);
+    assert!(report.to_string().starts_with("WITH: T121 WITH (excluding RECURSIVE) in query expression (optional)\n"));
+}
+
+#[test]
+fn vendor_extensions_need_a_vendors_sql() {
+    let report = conformance("SELECT `a` FROM t WHERE b ILIKE 'x' LIMIT 5", Dialect::Postgres).unwrap();
+    assert_eq!(report.level(), Level::Vendor);
+    assert_eq!(report.extensions(), ["ILIKE", "backquoted name", "LIMIT"]);
+    let report = conformance("SELECT `a` FROM t LIMIT 5", Dialect::MySql).unwrap();
+    assert_eq!(report.extensions(), ["backquoted name", "LIMIT"]);
+    assert_eq!(report.to_string().lines().last(), Some("needs vendor extensions: backquoted name, LIMIT"));
+
+    for sql in [
+        "DROP TABLE IF EXISTS t",
+        "SELECT * EXCLUDE (a) FROM t",
+        "SELECT x FROM t QUALIFY x = 1",
+        "COPY t FROM STDIN",
+        "EXPLAIN SELECT a FROM t",
+        "SELECT a FROM t WHERE b = $$x$$",
+    ] {
+        let report = conformance(sql, Dialect::Warehouse).unwrap();
+        assert_eq!(report.level(), Level::Vendor, "{}", sql);
+    }
+
+    // A parsed statement is labeled from its AST alone
+    let statement = parse_sql("SELECT a FROM t LIMIT 5").unwrap();
+    let report = statement_conformance(&statement);
+    assert_eq!(report.level(), Level::Core);
+    let core = matches!(report.usages[0].requirement, Requirement::Feature(feature) if feature.id == "E051" && feature.core);
+    assert!(core);
+    assert!(conformance("SELECT FROM", Dialect::Generic).is_err());
+}
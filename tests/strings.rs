@@ -0,0 +1,69 @@
+// strings.rs — checks N'...', E'...', B'...' and X'...' string literals
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::anonymize::{anonymize_sql, AnonymizeMode};
+use sreerag_devadasan::ast::{Expression, Statement, StringPrefix};
+use sreerag_devadasan::engine::{Database, EngineError};
+use sreerag_devadasan::eval::{EvalError, Value};
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::tokenizer::{tokenize, Token};
+
+fn eval(sql: &str) -> Result<Value, EngineError> {
+    Ok(Database::new().execute_sql(&format!("VALUES ({})", sql))?.rows[0][0].clone())
+}
+
+#[test]
+fn prefixed_strings_lex_with_their_prefix_and_print_back() {
+    let string = |prefix, value: &str| Token::PrefixedString(prefix, value.to_string());
+    assert_eq!(tokenize("n'a''b'")[0], string(StringPrefix::National, "a'b"));
+    assert_eq!(tokenize(r"E'a\'b\\c\n\x41\101é\q'")[0], string(StringPrefix::Escape, "a'b\\c\nAAéq"));
+    let digits = [string(StringPrefix::Bit, "0110"), string(StringPrefix::Hex, "00ff")];
+    assert_eq!(tokenize("B'0110' X'00ff'")[..2], digits);
+    // Only a quote right after the letter makes it a prefix
+    assert_eq!(tokenize("x 'a'").len(), 3);
+    assert!(matches!(&tokenize("nx'a'")[0], Token::Identifier(name) if name.value == "nx"));
+    assert_eq!(tokenize("B'012'")[0], Token::Invalid('2'));
+    assert_eq!(tokenize(r"E'\u00'")[0], Token::Invalid('\\'));
+    assert_eq!(tokenize(r"E'open \'")[0], Token::Invalid('\''));
+
+    for sql in [
+        "INSERT INTO t VALUES (N'Grüße', B'1010', X'DEADbeef', E'it\\'s\\n\\\\')",
+        "SELECT id FROM t WHERE code = X'4142'",
+    ] {
+        assert_eq!(parse_sql(sql).unwrap().to_string(), sql);
+    }
+    // An E string prints with escapes that read back as its text
+    let Ok(statement) = parse_sql("VALUES (E'tab\\there\\x01')") else { panic!("didn't parse") };
+    assert_eq!(statement.to_string(), "VALUES (E'tab\\there\\u0001')");
+    assert_eq!(parse_sql(&statement.to_string()).unwrap(), statement);
+    // Text strings stand where a plain one does
+    let function = parse_sql(r"CREATE FUNCTION f() RETURNS INT AS E'SELECT \'a\'' LANGUAGE sql").unwrap();
+    assert_eq!(function.to_string(), "CREATE FUNCTION f() RETURNS INT AS $$SELECT 'a'$$ LANGUAGE sql");
+    assert!(parse_sql("CREATE FUNCTION f() RETURNS INT AS X'00' LANGUAGE sql").is_err());
+
+    let hidden = anonymize_sql("SELECT a FROM t WHERE b = N'secret'", AnonymizeMode::Placeholders).unwrap();
+    assert_eq!(hidden, "SELECT a FROM t WHERE b = :string");
+    let hashed = anonymize_sql("SELECT a FROM t WHERE b = X'CAFE'", AnonymizeMode::Hashed).unwrap();
+    assert!(hashed.starts_with("SELECT a FROM t WHERE b = X'") && !hashed.contains("CAFE"), "{}", hashed);
+}
+
+#[test]
+fn prefixed_strings_evaluate_to_text_and_integers() {
+    assert_eq!(eval("N'Grüße'").unwrap(), Value::String("Grüße".to_string()));
+    assert_eq!(eval("E'a\\tb'").unwrap(), Value::String("a\tb".to_string()));
+    assert_eq!(eval("B'1010' + 1").unwrap(), Value::Int(11));
+    assert_eq!(eval("B''").unwrap(), Value::Int(0));
+    assert_eq!(eval("X'4142'").unwrap(), Value::String("AB".to_string()));
+    assert!(matches!(eval(&format!("B'{}'", "1".repeat(64))), Err(EngineError::Eval(EvalError::Overflow))));
+    for sql in ["X'FF'", "X'414'"] {
+        assert!(matches!(eval(sql), Err(EngineError::Eval(EvalError::TypeMismatch(_)))), "{}", sql);
+    }
+
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE t (name VARCHAR(10), flags INT)").unwrap();
+    db.execute_sql("INSERT INTO t VALUES (N'Ann', B'101'), (E'B\\x6fb', 2)").unwrap();
+    let rows = db.execute_sql("SELECT flags FROM t WHERE name = N'Bob' OR name = E'Ann'").unwrap().rows;
+    assert_eq!(rows, [vec![Value::Int(5)], vec![Value::Int(2)]]);
+    let Ok(Statement::Values { rows }) = parse_sql("VALUES (X'00')") else { panic!("not a VALUES") };
+    assert_eq!(rows[0][0], Expression::PrefixedString { prefix: StringPrefix::Hex, value: "00".to_string() });
+}
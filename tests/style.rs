@@ -0,0 +1,55 @@
+// style.rs — checks highlighting, error underlines and colored tables
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::engine::QueryResult;
+use sreerag_devadasan::eval::Value;
+use sreerag_devadasan::span::{error_span, Span};
+use sreerag_devadasan::style::{Role, Styler, Theme};
+
+#[test]
+fn sql_is_highlighted_by_token() {
+    let sql = "SELECT id /* note */ FROM t WHERE name = 'x' AND n > 10 AND k = $1";
+    let styler = Styler::new(Theme::Dark);
+    assert_eq!(
+        styler.highlight(sql, Dialect::Postgres),
+        "\x1b[1;94mSELECT\x1b[0m id\x1b[2m /* note */ \x1b[0m\x1b[1;94mFROM\x1b[0m t \x1b[1;94mWHERE\x1b[0m name = \
+         \x1b[92m'x'\x1b[0m \x1b[1;94mAND\x1b[0m n > \x1b[93m10\x1b[0m \x1b[1;94mAND\x1b[0m k = \x1b[95m$1\x1b[0m"
+    );
+    // The light theme picks other colors for the same roles
+    let light = Styler::new(Theme::Light).highlight("SELECT 1", Dialect::Generic);
+    assert_eq!(light, "\x1b[1;34mSELECT\x1b[0m \x1b[35m1\x1b[0m");
+    // Without color the text comes back as it was
+    assert_eq!(Styler::plain().highlight(sql, Dialect::Postgres), sql);
+    assert_eq!(Styler::plain().paint(Role::Error, "x"), "x");
+    assert_eq!(Theme::from_name("LIGHT"), Some(Theme::Light));
+}
+
+#[test]
+fn errors_are_underlined_where_parsing_stopped() {
+    let plain = Styler::plain();
+    let underline = |sql: &str| plain.underline(sql, error_span(sql, Dialect::Generic).unwrap());
+    assert_eq!(underline("CREATE TABLE t (a FOO)"), "CREATE TABLE t (a FOO)\n                  ^^^");
+    // Past an expression the parser backtracked out of
+    assert_eq!(underline("SELECT a FROM t WHERE a = 1 b"), "SELECT a FROM t WHERE a = 1 b\n                            ^");
+    // The end of the input, on the line it's on
+    assert_eq!(underline("SELECT a\nFROM"), "FROM\n    ^");
+    assert_eq!(error_span("SELECT a FROM t", Dialect::Generic), None);
+
+    let colored = Styler::new(Theme::Dark).underline("x  y", Span::new(3, 4));
+    assert_eq!(colored, "x  y\n   \x1b[1;91m^\x1b[0m");
+}
+
+#[test]
+fn tables_color_headers_separators_and_nulls() {
+    let result = QueryResult {
+        columns: vec!["id".to_string(), "name".to_string()],
+        rows: vec![vec![Value::Int(1), Value::Null]],
+    };
+    assert_eq!(Styler::plain().table(&result), "id | name\n1 | NULL");
+    assert_eq!(
+        Styler::new(Theme::Dark).table(&result),
+        "\x1b[1mid\x1b[0m\x1b[2m | \x1b[0m\x1b[1mname\x1b[0m\n1\x1b[2m | \x1b[0m\x1b[2;3mNULL\x1b[0m"
+    );
+    assert_eq!(Styler::plain().table(&QueryResult::default()), "OK");
+}
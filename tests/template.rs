@@ -0,0 +1,79 @@
+// template.rs — checks expanding dbt-style template syntax into SQL
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::template::{render, TemplateContext, TemplateError};
+
+fn rendered(input: &str) -> String {
+    let context = TemplateContext::new().with_variable("schema", "analytics").with_variable("full", "true");
+    render(input, &context).unwrap_or_else(|e| panic!("{}: {}", input, e))
+}
+
+#[test]
+fn expressions_expand_and_unknown_ones_are_opaque() {
+    assert_eq!(rendered("SELECT a FROM {{ ref('orders') }}"), "SELECT a FROM orders");
+    assert_eq!(rendered("SELECT a FROM {{ source('raw', 'users') }}"), "SELECT a FROM users");
+    let sql = "SELECT a FROM {{ schema }}.t, {{ var(\"schema\") }}.u";
+    assert_eq!(rendered(sql), "SELECT a FROM analytics.t, analytics.u");
+    assert_eq!(rendered("SELECT {{ a }}, {{- b -}} FROM t {# note #}"), "SELECT template_1, template_2 FROM t ");
+
+    // config() only configures the model, so no column is left behind
+    let model = "{{ config(materialized='table') }}\nSELECT a FROM t";
+    assert_eq!(rendered(model), "\nSELECT a FROM t");
+    assert!(parse_sql(&rendered(model)).is_ok());
+}
+
+#[test]
+fn conditions_test_a_variable_and_others_are_errors() {
+    let sql = "SELECT a FROM t{% if full %} WHERE a > 1{% elif other %} WHERE b{% else %} LIMIT 1{% endif %}";
+    assert_eq!(rendered(sql), "SELECT a FROM t WHERE a > 1");
+    assert_eq!(rendered(&sql.replace("if full", "if not full")), "SELECT a FROM t LIMIT 1");
+
+    let context = TemplateContext::new().with_variable("n", "5").with_variable("target.name", "prod");
+    for condition in ["n == '5'", "target.name == 'prod'", "n > 1", ""] {
+        let input = format!("SELECT a FROM t{{% if {} %}} WHERE a{{% endif %}}", condition);
+        let error = TemplateError::UnsupportedCondition(condition.to_string());
+        assert_eq!(render(&input, &context), Err(error), "{}", input);
+    }
+    let input = "SELECT 1{% if full %}{% elif n != 1 %}{% endif %}";
+    assert!(matches!(render(input, &context), Err(TemplateError::UnsupportedCondition(_))));
+}
+
+#[test]
+fn loops_and_assignments_are_opaque() {
+    let sql = "{% set cols = ['a', 'b'] %}SELECT {% for c in cols %}sum({{ c }}) AS total, {% endfor %}1 FROM t";
+    assert_eq!(rendered(sql), "SELECT sum(template_1) AS total, 1 FROM t");
+    assert!(parse_sql(&rendered(sql)).is_ok());
+    // A loop's else is for no iterations, and a set block's text is its value
+    assert_eq!(rendered("SELECT {% for c in x %}a{% else %}b{% endfor %} FROM t"), "SELECT a FROM t");
+    assert_eq!(rendered("{% set where %}WHERE a{% endset %}SELECT a FROM t"), "SELECT a FROM t");
+
+    for (input, tag) in [
+        ("{% for c in x %}a{% endif %}", "endif"),
+        ("{% if full %}a{% endfor %}", "endfor"),
+        ("{% for c in x %}{% elif full %}{% endfor %}", "elif full"),
+        ("{% endset %}", "endset"),
+    ] {
+        let error = TemplateError::UnbalancedBlock(tag.to_string());
+        assert_eq!(render(input, &TemplateContext::new()), Err(error), "{}", input);
+    }
+    let missing = render("{% for c in x %}a", &TemplateContext::new());
+    assert_eq!(missing, Err(TemplateError::UnbalancedBlock("missing {% endfor %}".to_string())));
+    let macro_def = "{% macro m() %}{% endmacro %}";
+    assert_eq!(render(macro_def, &TemplateContext::new()), Err(TemplateError::UnsupportedTag("macro m()".to_string())));
+}
+
+#[test]
+fn braces_in_sql_strings_are_the_strings_own() {
+    assert_eq!(rendered("SELECT '{{' AS a, '{%' AS b, '{#' FROM t"), "SELECT '{{' AS a, '{%' AS b, '{#' FROM t");
+    assert_eq!(rendered("SELECT '{{ not closed' FROM t"), "SELECT '{{ not closed' FROM t");
+    assert_eq!(rendered("SELECT a FROM t WHERE s = '{{ schema }}'"), "SELECT a FROM t WHERE s = 'analytics'");
+    assert_eq!(rendered("SELECT a FROM t WHERE s = '{{ var(\"schema\") }}'"), "SELECT a FROM t WHERE s = 'analytics'");
+    // A quote in a comment doesn't open a string
+    assert_eq!(rendered("SELECT a -- don't\nFROM {{ ref('t') }}"), "SELECT a -- don't\nFROM t");
+
+    let error = render("SELECT {{ a FROM t", &TemplateContext::new()).unwrap_err();
+    assert_eq!(error, TemplateError::Unterminated("{{".to_string()));
+    let error = render("SELECT a FROM t /* don't */ WHERE {% if full", &TemplateContext::new()).unwrap_err();
+    assert_eq!(error, TemplateError::Unterminated("{%".to_string()));
+}
@@ -0,0 +1,93 @@
+// time_travel.rs — checks reading tables as of earlier versions of the database
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::engine::{Database, EngineError, Storage};
+use sreerag_devadasan::eval::Value;
+use sreerag_devadasan::parser::parse_sql;
+
+fn ids(db: &mut Database, sql: &str) -> Vec<Value> {
+    db.execute_sql(sql).unwrap().rows.into_iter().map(|row| row[0].clone()).collect()
+}
+
+#[test]
+fn each_committed_write_is_a_version_to_read_back() {
+    for storage in [Storage::Rows, Storage::Columnar] {
+        let mut db = Database::with_storage(storage);
+        db.set_history_limit(10);
+        db.execute_sql("CREATE TABLE t (id INT, name VARCHAR(5))").unwrap();
+        db.execute_sql("INSERT INTO t VALUES (1, 'a')").unwrap();
+        db.execute_sql("INSERT INTO t VALUES (2, 'b')").unwrap();
+        assert_eq!(db.version(), 3);
+        assert_eq!(db.kept_versions(), [0, 1, 2, 3]);
+
+        assert!(ids(&mut db, "SELECT id FROM t AS OF VERSION 1").is_empty());
+        assert_eq!(ids(&mut db, "SELECT id FROM t AS OF VERSION 2"), [Value::Int(1)]);
+        let sql = "SELECT name FROM t AS OF VERSION 3 AS old WHERE id = 2";
+        assert_eq!(ids(&mut db, sql), [Value::String("b".into())]);
+        assert!(matches!(db.execute_sql("SELECT id FROM t AS OF VERSION 0"), Err(EngineError::NoSuchTable(_))));
+
+        // A transaction is one version, and only once committed
+        db.execute_sql("BEGIN").unwrap();
+        db.execute_sql("INSERT INTO t VALUES (3, 'c')").unwrap();
+        db.execute_sql("INSERT INTO t VALUES (4, 'd')").unwrap();
+        assert_eq!(db.version(), 3);
+        assert_eq!(ids(&mut db, "SELECT id FROM t AS OF VERSION 3").len(), 2);
+        db.execute_sql("COMMIT").unwrap();
+        assert_eq!(db.version(), 4);
+        assert_eq!(ids(&mut db, "SELECT id FROM t AS OF VERSION 4").len(), 4);
+        db.execute_sql("BEGIN").unwrap();
+        db.execute_sql("ROLLBACK").unwrap();
+        db.execute_sql("DROP TABLE t").unwrap();
+        assert_eq!(db.version(), 5);
+        assert_eq!(ids(&mut db, "SELECT id FROM t AS OF VERSION 3 ORDER BY id"), [Value::Int(1), Value::Int(2)]);
+    }
+}
+
+#[test]
+fn only_the_last_versions_are_kept() {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE t (id INT)").unwrap();
+    db.execute_sql("INSERT INTO t VALUES (1)").unwrap();
+    // Nothing is kept by default, and what is kept starts at the current version
+    assert!(db.kept_versions().is_empty());
+    assert!(matches!(db.execute_sql("SELECT id FROM t AS OF VERSION 2"), Err(EngineError::NoSuchVersion(2))));
+    db.set_history_limit(2);
+    assert_eq!(db.kept_versions(), [2]);
+    db.execute_sql("INSERT INTO t VALUES (2)").unwrap();
+    db.execute_sql("INSERT INTO t VALUES (3)").unwrap();
+    assert_eq!(db.kept_versions(), [3, 4]);
+    let error = db.execute_sql("SELECT id FROM t AS OF VERSION 2").unwrap_err();
+    assert_eq!(error.to_string(), "Version 2 of the database isn't kept");
+
+    // A failed write makes no version
+    assert!(db.execute_sql("INSERT INTO t VALUES ('x')").is_err());
+    assert_eq!(db.version(), 4);
+
+    // A session's commit is a version of the database it commits to
+    let mut session = db.begin();
+    session.execute_sql("INSERT INTO t VALUES (4)").unwrap();
+    assert_eq!(session.execute_sql("SELECT id FROM t AS OF VERSION 4").unwrap().rows.len(), 3);
+    assert_eq!(session.database().version(), 4);
+    db.commit(session).unwrap();
+    assert_eq!(db.kept_versions(), [4, 5]);
+    assert_eq!(ids(&mut db, "SELECT id FROM t AS OF VERSION 5").len(), 4);
+
+    let statement = parse_sql("SELECT id FROM t AS OF VERSION 5 AS v").unwrap();
+    assert_eq!(statement.tables(), ["t"]);
+    assert_eq!(statement.to_sql(Default::default()), "SELECT id FROM t AS OF VERSION 5 AS v");
+}
+
+#[test]
+fn results_as_of_a_version_go_with_the_version() {
+    let mut db = Database::new();
+    db.set_history_limit(5);
+    db.set_result_cache_capacity(10);
+    db.execute_sql("CREATE TABLE t (id INT)").unwrap();
+    db.execute_sql("INSERT INTO t VALUES (1)").unwrap();
+    let sql = "SELECT id FROM t AS OF VERSION 2";
+    assert_eq!(ids(&mut db, sql), [Value::Int(1)]);
+    assert_eq!(ids(&mut db, sql), [Value::Int(1)]);
+    db.set_history_limit(0);
+    assert!(matches!(db.execute_sql(sql), Err(EngineError::NoSuchVersion(2))));
+    assert_eq!(db.result_cache_stats().entries, 0);
+}
@@ -0,0 +1,59 @@
+// trace.rs — checks the trace events of a parse and their tree rendering
+// Author: Sreerag Devadasan
+
+#![cfg(feature = "trace")]
+
+use sreerag_devadasan::parser::SQLParser;
+use sreerag_devadasan::tokenizer::tokenize;
+use sreerag_devadasan::trace::{self, TraceKind};
+
+#[test]
+fn tree_shows_rules_tokens_and_precedence_decisions() {
+    let tokens = tokenize("SELECT a FROM t WHERE a + b * c AND x = 1");
+    let (parsed, events) = trace::collect(|| SQLParser::new(&tokens).parse_to_end());
+    assert!(parsed.is_ok());
+
+    // Every token is consumed once, in order
+    let consumed: Vec<usize> = events.iter().filter(|e| e.kind == TraceKind::Token).map(|e| e.position).collect();
+    assert_eq!(consumed, (0..tokens.len()).collect::<Vec<_>>());
+
+    let tree = trace::render_tree(&events, &tokens);
+    let expected = "\
+statement
+  select
+    consume SELECT
+    expression
+      subexpression
+        consume identifier a
+    consume FROM
+    consume identifier t
+    consume WHERE
+    expression
+      subexpression
+        consume identifier a
+        '+' binds at 5 >= 1: take it
+        consume '+'
+        subexpression
+          consume identifier b
+          '*' binds at 6 >= 6: take it
+          consume '*'
+          subexpression
+            consume identifier c
+            AND binds at 2 < 7: leave it
+          AND binds at 2 < 6: leave it
+        AND binds at 2 >= 1: take it
+        consume AND
+        subexpression
+          consume identifier x
+          '=' binds at 3 >= 3: take it
+          consume '='
+          subexpression
+            consume number 1
+consume end of input
+";
+    assert_eq!(tree, expected);
+
+    // Nothing is collected once the closure returns
+    let (_, events) = trace::collect(|| ());
+    assert!(events.is_empty());
+}
@@ -0,0 +1,93 @@
+// transactions.rs — checks snapshot isolation between engine transactions
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::engine::{Database, EngineError};
+use sreerag_devadasan::eval::Value;
+use sreerag_devadasan::parser::parse_sql;
+
+fn database() -> Database {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE a (id INT)").unwrap();
+    db.execute_sql("CREATE TABLE b (id INT)").unwrap();
+    db.execute_sql("INSERT INTO a VALUES (1)").unwrap();
+    db
+}
+
+fn ids(db: &Database, table: &str) -> Vec<Value> {
+    db.table(table).unwrap().rows().iter().map(|row| row[0].clone()).collect()
+}
+
+#[test]
+fn transactions_see_a_snapshot_until_they_commit() {
+    let mut db = database();
+    let mut first = db.begin();
+    let mut second = db.begin();
+
+    first.execute_sql("INSERT INTO a VALUES (2)").unwrap();
+    assert_eq!(ids(first.database(), "a"), [Value::Int(1), Value::Int(2)]);
+    assert_eq!(ids(second.database(), "a"), [Value::Int(1)]);
+    assert_eq!(ids(&db, "a"), [Value::Int(1)]);
+
+    db.commit(first).unwrap();
+    assert_eq!(ids(&db, "a"), [Value::Int(1), Value::Int(2)]);
+    // Still the snapshot taken at begin
+    assert_eq!(ids(second.database(), "a"), [Value::Int(1)]);
+
+    // Changes to other tables don't conflict
+    second.execute_sql("INSERT INTO b VALUES (3)").unwrap();
+    second.execute_sql("CREATE TABLE c (id INT)").unwrap();
+    db.commit(second).unwrap();
+    assert_eq!(ids(&db, "a"), [Value::Int(1), Value::Int(2)]);
+    assert_eq!(ids(&db, "b"), [Value::Int(3)]);
+    assert!(db.table("c").is_some());
+}
+
+#[test]
+fn the_first_committer_wins() {
+    let mut db = database();
+    let mut first = db.begin();
+    let mut second = db.begin();
+    first.execute_sql("INSERT INTO a VALUES (2)").unwrap();
+    second.execute_sql("DROP TABLE a").unwrap();
+
+    db.commit(first).unwrap();
+    assert!(matches!(db.commit(second), Err(EngineError::WriteConflict(table)) if table == "a"));
+    assert_eq!(ids(&db, "a"), [Value::Int(1), Value::Int(2)]);
+}
+
+#[test]
+fn failed_statements_and_reads_write_nothing() {
+    let mut db = database();
+    let mut first = db.begin();
+    let mut second = db.begin();
+    first.execute_sql("SELECT id FROM a").unwrap();
+    assert!(first.execute_sql("INSERT INTO a VALUES ('x')").is_err());
+    second.execute_sql("INSERT INTO a VALUES (2)").unwrap();
+
+    db.commit(second).unwrap();
+    db.commit(first).unwrap();
+    assert_eq!(ids(&db, "a"), [Value::Int(1), Value::Int(2)]);
+}
+
+#[test]
+fn sessions_run_statements_in_their_transaction() {
+    let mut db = database();
+    let mut session = None;
+    let run = |db: &mut Database, session: &mut _, sql: &str| db.execute_in(session, &parse_sql(sql).unwrap());
+
+    run(&mut db, &mut session, "BEGIN").unwrap();
+    assert!(session.is_some());
+    assert!(matches!(run(&mut db, &mut session, "BEGIN"), Err(EngineError::TransactionInProgress)));
+    run(&mut db, &mut session, "INSERT INTO a VALUES (2)").unwrap();
+    assert_eq!(ids(&db, "a"), [Value::Int(1)]);
+    run(&mut db, &mut session, "COMMIT").unwrap();
+    assert!(session.is_none());
+    assert_eq!(ids(&db, "a"), [Value::Int(1), Value::Int(2)]);
+
+    run(&mut db, &mut session, "BEGIN").unwrap();
+    run(&mut db, &mut session, "DROP TABLE a").unwrap();
+    run(&mut db, &mut session, "ROLLBACK").unwrap();
+    assert!(db.table("a").is_some());
+    assert!(matches!(run(&mut db, &mut session, "ROLLBACK"), Err(EngineError::NoTransaction)));
+    assert!(!db.in_transaction());
+}
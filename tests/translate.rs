@@ -0,0 +1,112 @@
+// translate.rs — checks translating row limits, quoted names and booleans between dialects
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::dialect::{translate, Dialect};
+use sreerag_devadasan::parser::parse_sql_with_dialect;
+
+/// `sql` from Postgres printed as each of `Dialect::ALL`, in that order.
+fn as_each(sql: &str) -> Vec<String> {
+    Dialect::ALL.iter().map(|&to| translate(sql, Dialect::Postgres, to).unwrap()).collect()
+}
+
+/// Whether `sql` translated from `from` to `to` reads in `to` as it did in `from`.
+fn survives(sql: &str, from: Dialect, to: Dialect) -> bool {
+    let translated = translate(sql, from, to).unwrap();
+    let original = parse_sql_with_dialect(sql, from).unwrap();
+    parse_sql_with_dialect(&translated, to).unwrap().to_sql(from) == original.to_sql(from)
+}
+
+#[test]
+fn limits_are_written_as_limit_top_or_fetch() {
+    assert_eq!(
+        as_each("SELECT id FROM users LIMIT 10"),
+        [
+            "SELECT id FROM users LIMIT 10",
+            "SELECT id FROM users FETCH FIRST 10 ROWS ONLY",
+            "SELECT id FROM users LIMIT 10",
+            "SELECT id FROM users LIMIT 10",
+            "SELECT TOP 10 id FROM users",
+            "SELECT id FROM users LIMIT 10",
+        ]
+    );
+    assert_eq!(
+        as_each("SELECT id FROM users ORDER BY id LIMIT 10 OFFSET 20"),
+        [
+            "SELECT id FROM users ORDER BY id LIMIT 10 OFFSET 20",
+            "SELECT id FROM users ORDER BY id OFFSET 20 ROWS FETCH FIRST 10 ROWS ONLY",
+            "SELECT id FROM users ORDER BY id LIMIT 10 OFFSET 20",
+            "SELECT id FROM users ORDER BY id LIMIT 10 OFFSET 20",
+            "SELECT id FROM users ORDER BY id OFFSET 20 ROWS FETCH NEXT 10 ROWS ONLY",
+            "SELECT id FROM users ORDER BY id LIMIT 10 OFFSET 20",
+        ]
+    );
+    // SQL Server needs an ORDER BY before OFFSET, and MySQL a LIMIT
+    let offset = as_each("SELECT id FROM users OFFSET 20");
+    assert_eq!(offset[2], "SELECT id FROM users LIMIT 18446744073709551615 OFFSET 20");
+    assert_eq!(offset[4], "SELECT id FROM users ORDER BY (SELECT NULL) OFFSET 20 ROWS");
+    // Each query's own limit goes with it
+    let nested = "SELECT x FROM (SELECT id AS x FROM users LIMIT 4) AS s LIMIT 2";
+    assert_eq!(
+        translate(nested, Dialect::Postgres, Dialect::SqlServer).unwrap(),
+        "SELECT TOP 2 x FROM (SELECT TOP 4 id AS x FROM users) AS s"
+    );
+
+    // And each is read back as the limit it was written from
+    assert_eq!(
+        translate("SELECT TOP (3) id FROM users WHERE a = 1", Dialect::SqlServer, Dialect::Postgres).unwrap(),
+        "SELECT id FROM users WHERE a = 1 LIMIT 3"
+    );
+    let fetch = "SELECT id FROM users OFFSET 20 ROWS FETCH NEXT 10 ROWS ONLY";
+    assert_eq!(translate(fetch, Dialect::Ansi, Dialect::MySql).unwrap(), "SELECT id FROM users LIMIT 10 OFFSET 20");
+    assert_eq!(
+        translate("SELECT id FROM users LIMIT 5, 10", Dialect::MySql, Dialect::Ansi).unwrap(),
+        "SELECT id FROM users OFFSET 5 ROWS FETCH FIRST 10 ROWS ONLY"
+    );
+    for sql in ["SELECT id FROM users LIMIT 10", "SELECT id FROM users ORDER BY id LIMIT 10 OFFSET 20"] {
+        for to in Dialect::ALL {
+            assert!(survives(sql, Dialect::Postgres, to), "{} as {:?}", sql, to);
+        }
+    }
+}
+
+#[test]
+fn quoted_names_are_requoted_in_the_targets_style() {
+    let sql = "SELECT \"Id\", \"a\"\"b\", \"x`y\", \"q]r\", \"select\", name FROM \"my table\"";
+    assert_eq!(
+        as_each(sql),
+        [
+            "SELECT `Id`, `a\"b`, `x``y`, `q]r`, `select`, name FROM `my table`",
+            sql,
+            "SELECT `Id`, `a\"b`, `x``y`, `q]r`, `select`, name FROM `my table`",
+            sql,
+            "SELECT [Id], [a\"b], [x`y], [q]]r], [select], name FROM [my table]",
+            sql,
+        ]
+    );
+    for to in Dialect::ALL {
+        assert!(survives(sql, Dialect::Postgres, to), "{:?}", to);
+    }
+    let bracketed = "SELECT [a]]b], [Id] FROM [my table]";
+    let printed = translate(bracketed, Dialect::SqlServer, Dialect::Postgres).unwrap();
+    assert_eq!(printed, "SELECT \"a]b\", \"Id\" FROM \"my table\"");
+    assert_eq!(translate(&printed, Dialect::Postgres, Dialect::SqlServer).unwrap(), bracketed);
+}
+
+#[test]
+fn booleans_are_one_and_zero_in_sql_server() {
+    let sql = "SELECT TRUE FROM users WHERE a = TRUE AND NOT b = FALSE";
+    let printed = as_each(sql);
+    assert_eq!(printed[4], "SELECT 1 FROM users WHERE a = 1 AND NOT b = 0");
+    for (dialect, printed) in Dialect::ALL.iter().zip(&printed) {
+        if *dialect != Dialect::SqlServer {
+            assert_eq!(printed, sql, "{:?}", dialect);
+        }
+    }
+    // MySQL has no IS DISTINCT FROM, but `<=>` is its negation
+    let printed = as_each("SELECT a FROM t WHERE a IS DISTINCT FROM b AND c IS NOT DISTINCT FROM d");
+    assert_eq!(printed[2], "SELECT a FROM t WHERE NOT (a <=> b) AND c <=> d");
+    // Strings keep their doubled quotes everywhere
+    for printed in as_each("SELECT 'it''s' FROM t WHERE a = 'TRUE'") {
+        assert_eq!(printed, "SELECT 'it''s' FROM t WHERE a = 'TRUE'");
+    }
+}
@@ -0,0 +1,85 @@
+// tutorial.rs — checks the tutorial's exercises, their structural checks and the parse error hints
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::tutorial::{check, Feedback, Tutorial, EXERCISES};
+
+fn hint(feedback: Feedback) -> String {
+    match feedback {
+        Feedback::ParseError { hint, .. } => hint,
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn every_solution_passes_its_exercise() {
+    for exercise in EXERCISES {
+        assert_eq!(check(exercise, exercise.solution, Dialect::Generic), Feedback::Correct, "{}", exercise.concept);
+    }
+    // The first answer does none of what the later exercises ask
+    for exercise in &EXERCISES[1..] {
+        let feedback = check(exercise, EXERCISES[0].solution, Dialect::Generic);
+        assert!(matches!(feedback, Feedback::Incorrect(_)), "{}", exercise.concept);
+    }
+}
+
+#[test]
+fn answers_are_checked_by_structure() {
+    let filter = &EXERCISES[2];
+    for answer in [
+        "SELECT * FROM users WHERE age > 30",
+        "select id, name from USERS where 30 < age",
+        "SELECT name FROM users WHERE city = 'Paris' AND (age > 30)",
+    ] {
+        assert_eq!(check(filter, answer, Dialect::Generic), Feedback::Correct, "{}", answer);
+    }
+    assert_eq!(
+        check(filter, "SELECT name FROM users", Dialect::Generic),
+        Feedback::Incorrect("Add a WHERE clause after FROM users.".to_string())
+    );
+    let not_over = check(filter, "SELECT name FROM users WHERE age >= 30", Dialect::Generic);
+    assert!(matches!(not_over, Feedback::Incorrect(_)));
+
+    let join = &EXERCISES[5];
+    let aliased = "SELECT * FROM orders o INNER JOIN users u ON o.user_id = u.id";
+    assert_eq!(check(join, aliased, Dialect::Generic), Feedback::Correct);
+    let wrong_keys = "SELECT * FROM users u JOIN orders o ON u.id = o.id";
+    assert!(matches!(check(join, wrong_keys, Dialect::Generic), Feedback::Incorrect(_)));
+}
+
+#[test]
+fn parse_errors_get_targeted_hints() {
+    let exercise = &EXERCISES[2];
+    let hint_for = |answer| hint(check(exercise, answer, Dialect::Generic));
+    assert!(hint_for("SELCT name FROM users").contains("did you mean SELECT?"));
+    assert!(hint_for("SELECT name FROM users WERE age > 30").contains("did you mean WHERE?"));
+    assert!(hint_for("SELECT name FROM users WHERE age >").contains("finish it with a column or a value"));
+    assert!(hint_for("SELECT name FROM users ORDER age").contains("ORDER BY"));
+    assert!(hint_for("SELECT name users").contains("FROM"));
+    assert!(hint_for("SELECT name FROM users WHERE age == 30").contains("single ="));
+
+    let answer = "SELECT name FROM users WERE age > 30";
+    let Feedback::ParseError { span: Some(span), .. } = check(exercise, answer, Dialect::Generic) else {
+        panic!("expected a parse error with a span");
+    };
+    assert_eq!(span.slice(answer), "age");
+}
+
+#[test]
+fn tutorial_moves_on_after_correct_answers() {
+    let mut tutorial = Tutorial::new(Dialect::Postgres);
+    assert_eq!(tutorial.current().unwrap().concept, EXERCISES[0].concept);
+    assert!(matches!(tutorial.submit("SELECT name FROM users"), Feedback::Incorrect(_)));
+    assert!(matches!(tutorial.submit("SELECT FROM"), Feedback::ParseError { .. }));
+    assert_eq!((tutorial.stage(), tutorial.attempts()), (0, 2));
+    assert_eq!(tutorial.submit("SELECT * FROM users;"), Feedback::Correct);
+    assert_eq!((tutorial.stage(), tutorial.attempts()), (1, 0));
+
+    for _ in 1..EXERCISES.len() {
+        tutorial.skip();
+    }
+    assert!(tutorial.is_finished());
+    tutorial.skip();
+    assert_eq!(tutorial.stage(), EXERCISES.len());
+    assert_eq!(tutorial.submit("anything"), Feedback::Correct);
+}
@@ -0,0 +1,92 @@
+// update_delete.rs — checks that UPDATE and DELETE change the rows their WHERE holds for, keeping the constraints
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::engine::{Database, EngineError, Storage};
+use sreerag_devadasan::eval::{EvalError, Value};
+
+fn database(storage: Storage) -> Database {
+    let mut db = Database::with_storage(storage);
+    db.execute_sql(
+        "CREATE TABLE t (id INT PRIMARY KEY, name VARCHAR(5) NOT NULL COLLATE nocase, score INT CHECK (score >= 0))",
+    )
+    .unwrap();
+    db.execute_sql("INSERT INTO t VALUES (1, 'ann', 10), (2, 'bob', 20), (3, 'Cat', NULL)").unwrap();
+    db
+}
+
+fn rows(db: &mut Database) -> Vec<Vec<Value>> {
+    db.execute_sql("SELECT id, name, score FROM t ORDER BY id").unwrap().rows
+}
+
+fn text(value: &str) -> Value {
+    Value::String(value.into())
+}
+
+fn row(id: i64, name: &str, score: Option<i64>) -> Vec<Value> {
+    vec![Value::Int(id), text(name), score.map_or(Value::Null, Value::Int)]
+}
+
+#[test]
+fn update_sets_the_rows_its_where_holds_for_from_their_old_values() {
+    for storage in [Storage::Rows, Storage::Columnar] {
+        let mut db = database(storage);
+        db.execute_sql("UPDATE t SET score = score + 1, name = 'x' WHERE id >= 2").unwrap();
+        assert_eq!(rows(&mut db), [row(1, "ann", Some(10)), row(2, "x", Some(21)), row(3, "x", None)]);
+        // Every value is computed from the row as it was
+        db.execute_sql("UPDATE t SET id = id + 10, score = id WHERE name = 'ANN'").unwrap();
+        assert_eq!(rows(&mut db), [row(2, "x", Some(21)), row(3, "x", None), row(11, "ann", Some(1))]);
+        // Without WHERE, every row
+        db.execute_sql("UPDATE t SET score = 0").unwrap();
+        let scores: Vec<Value> = rows(&mut db).into_iter().map(|row| row[2].clone()).collect();
+        assert_eq!(scores, [Value::Int(0), Value::Int(0), Value::Int(0)]);
+        // A WHERE that is NULL doesn't hold
+        db.execute_sql("UPDATE t SET score = 5 WHERE NULL").unwrap();
+        assert!(rows(&mut db).iter().all(|row| row[2] == Value::Int(0)), "{:?}", storage);
+    }
+}
+
+#[test]
+fn a_failed_update_changes_no_row() {
+    let mut db = database(Storage::Rows);
+    let before = rows(&mut db);
+    for (sql, expected) in [
+        ("UPDATE t SET id = 2 WHERE id = 1", EngineError::DuplicateKey("id".into())),
+        ("UPDATE t SET id = 7", EngineError::DuplicateKey("id".into())),
+        // The later rows would break NOT NULL and CHECK after the first was changed
+        ("UPDATE t SET name = NULL WHERE id > 1", EngineError::NotNull("name".into())),
+        ("UPDATE t SET score = 5 - score", EngineError::CheckFailed("score".into())),
+        ("UPDATE t SET name = 'toolong'", EngineError::ValueTooLong { column: "name".into(), max: 5 }),
+        ("UPDATE t SET score = 'x'", EngineError::TypeMismatch { column: "score".into(), value: text("x") }),
+        ("UPDATE t SET missing = 1", EngineError::UnknownColumn("missing".into())),
+        ("UPDATE t SET score = 1, score = 2", EngineError::DuplicateColumn("score".into())),
+        ("UPDATE u SET score = 1", EngineError::NoSuchTable("u".into())),
+    ] {
+        let error = db.execute_sql(sql).unwrap_err();
+        assert_eq!(error.to_string(), expected.to_string(), "{}", sql);
+        assert_eq!(rows(&mut db), before, "{}", sql);
+    }
+    let error = db.execute_sql("UPDATE t SET score = 1 / 0 WHERE id = 1").unwrap_err();
+    assert!(matches!(error, EngineError::Eval(EvalError::DivisionByZero)), "{:?}", error);
+    // Swapping keys leaves them unique
+    db.execute_sql("UPDATE t SET id = 3 - id WHERE id < 3").unwrap();
+    assert_eq!(rows(&mut db), [row(1, "bob", Some(20)), row(2, "ann", Some(10)), row(3, "Cat", None)]);
+}
+
+#[test]
+fn delete_removes_the_rows_its_where_holds_for() {
+    for storage in [Storage::Rows, Storage::Columnar] {
+        let mut db = database(storage);
+        let error = db.execute_sql("DELETE FROM t WHERE missing = 1").unwrap_err();
+        assert!(matches!(error, EngineError::Eval(EvalError::UnknownColumn(_))), "{:?}", error);
+        assert_eq!(rows(&mut db).len(), 3);
+        db.execute_sql("DELETE FROM t WHERE name = 'cat' OR score > 15").unwrap();
+        assert_eq!(rows(&mut db), [row(1, "ann", Some(10))]);
+        db.execute_sql("DELETE FROM t WHERE score > 100").unwrap();
+        assert_eq!(rows(&mut db).len(), 1);
+        // A deleted key may be inserted again
+        db.execute_sql("INSERT INTO t VALUES (2, 'bob', 1)").unwrap();
+        db.execute_sql("DELETE FROM t").unwrap();
+        assert!(rows(&mut db).is_empty(), "{:?}", storage);
+        assert!(matches!(db.execute_sql("DELETE FROM u"), Err(EngineError::NoSuchTable(_))));
+    }
+}
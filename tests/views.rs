@@ -0,0 +1,77 @@
+// views.rs — checks inlining the views of a catalog into the statements that read them
+// Author: Sreerag Devadasan
+
+use sreerag_devadasan::catalog::SharedCatalog;
+use sreerag_devadasan::dialect::Dialect;
+use sreerag_devadasan::parser::parse_sql;
+use sreerag_devadasan::schema_diff::Schema;
+use sreerag_devadasan::views::{expand_views, ViewError};
+
+const SCHEMA: &str = "CREATE TABLE orders (id INT, user_id INT, total INT);
+                      CREATE TABLE users (id INT, name VARCHAR(20));
+                      CREATE VIEW big_orders AS SELECT id, user_id, total FROM orders WHERE total > 100;
+                      CREATE VIEW big_spenders (user_id, name) AS
+                          SELECT id, name FROM users u JOIN big_orders o ON o.user_id = u.id";
+
+fn expanded(sql: &str, schema: &Schema) -> String {
+    expand_views(&parse_sql(sql).unwrap(), schema).unwrap().to_sql(Dialect::Generic)
+}
+
+#[test]
+fn views_become_subqueries_under_their_names() {
+    let schema = Schema::from_sql(SCHEMA).unwrap();
+    assert_eq!(schema.views.len(), 2);
+    assert_eq!(schema.view("BIG_ORDERS").unwrap().query.kind(), "SELECT");
+
+    assert_eq!(
+        expanded("SELECT total FROM big_orders WHERE big_orders.id = 1", &schema),
+        "SELECT total FROM (SELECT id, user_id, total FROM orders WHERE total > 100) AS big_orders \
+         WHERE big_orders.id = 1"
+    );
+    // Views that read views; the column list renames by position, an alias's on top
+    assert_eq!(
+        expanded("SELECT name FROM big_spenders AS s (uid)", &schema),
+        "SELECT name FROM (SELECT id, name FROM users AS u JOIN \
+         (SELECT id, user_id, total FROM orders WHERE total > 100) AS o ON o.user_id = u.id) AS s(uid, name)"
+    );
+    // A CTE of the same name is what the query reads
+    let sql = "WITH big_orders AS (SELECT id FROM orders) SELECT id FROM big_orders";
+    assert_eq!(expanded(sql, &schema), sql);
+    let sql = "MERGE INTO users USING big_orders ON users.id = big_orders.user_id WHEN MATCHED THEN DELETE";
+    assert!(expanded(sql, &schema).starts_with("MERGE INTO users USING (SELECT id, user_id, total FROM orders"));
+    let sql = "INSERT INTO orders SELECT id, user_id, total FROM big_orders";
+    assert!(expanded(sql, &schema).ends_with("FROM orders WHERE total > 100) AS big_orders"));
+    assert_eq!(expanded("SELECT id FROM orders", &schema), "SELECT id FROM orders");
+
+    // The catalog's snapshots hold the views too
+    let catalog = SharedCatalog::new(Schema::default());
+    catalog.apply_sql(SCHEMA).unwrap();
+    let statement = expand_views(&parse_sql("SELECT id FROM big_orders").unwrap(), &catalog.snapshot()).unwrap();
+    assert_eq!(statement.tables(), ["orders"]);
+}
+
+#[test]
+fn ctes_that_would_capture_a_views_tables_are_renamed() {
+    let schema = Schema::from_sql(SCHEMA).unwrap();
+    // Inlined under this WITH, the view's `orders` would read the CTE
+    let sql = "WITH orders AS (SELECT id FROM users) SELECT total FROM orders JOIN big_orders b ON b.id = orders.id";
+    assert_eq!(
+        expanded(sql, &schema),
+        "WITH orders_1 AS (SELECT id FROM users) SELECT total FROM orders_1 AS orders JOIN \
+         (SELECT id, user_id, total FROM orders WHERE total > 100) AS b ON b.id = orders.id"
+    );
+    let sql = "WITH a AS (SELECT id FROM orders), orders AS (SELECT id FROM a) SELECT id FROM big_orders";
+    assert_eq!(
+        expanded(sql, &schema),
+        "WITH a AS (SELECT id FROM orders), orders_1 AS (SELECT id FROM a) SELECT id FROM \
+         (SELECT id, user_id, total FROM orders WHERE total > 100) AS big_orders"
+    );
+
+    let schema = Schema::from_sql(
+        "CREATE VIEW a AS SELECT x FROM b; CREATE VIEW b AS SELECT x FROM c; CREATE VIEW c AS SELECT x FROM a",
+    )
+    .unwrap();
+    let error = expand_views(&parse_sql("SELECT x FROM b").unwrap(), &schema).unwrap_err();
+    assert_eq!(error, ViewError::Recursive(vec!["b".into(), "c".into(), "a".into(), "b".into()]));
+    assert_eq!(error.to_string(), "view b reads itself: b -> c -> a -> b");
+}
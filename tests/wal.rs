@@ -0,0 +1,126 @@
+// wal.rs — checks logging, replay and crash recovery of durable databases
+// Author: Sreerag Devadasan
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use sreerag_devadasan::eval::Value;
+use sreerag_devadasan::wal::{DurableDatabase, WalError};
+
+/// A fresh log path for one test.
+fn log_path(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("sreerag-wal-{}-{}.log", std::process::id(), name));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn ids(database: &DurableDatabase) -> Vec<Value> {
+    database.database().table("t").map_or(Vec::new(), |t| t.rows().iter().map(|row| row[0].clone()).collect())
+}
+
+#[test]
+fn changes_survive_reopening() {
+    let path = log_path("reopen");
+    let mut database = DurableDatabase::open(&path).unwrap();
+    database.execute_sql("CREATE TABLE t (id INT PRIMARY KEY)").unwrap();
+    database.execute_sql("INSERT INTO t VALUES (1), (2)").unwrap();
+    // Rejected statements and queries aren't logged
+    assert!(matches!(database.execute_sql("INSERT INTO t VALUES (1)"), Err(WalError::Engine(_))));
+    database.execute_sql("SELECT id FROM t").unwrap();
+    drop(database);
+
+    let log = fs::read_to_string(&path).unwrap();
+    assert_eq!(log.lines().filter(|line| !line.starts_with(char::is_numeric)).count(), 2);
+    let database = DurableDatabase::open(&path).unwrap();
+    assert_eq!(ids(&database), [Value::Int(1), Value::Int(2)]);
+    assert_eq!(database.discarded(), 0);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn updates_and_deletes_are_logged_and_replayed() {
+    let path = log_path("update-delete");
+    let mut database = DurableDatabase::open(&path).unwrap();
+    database.execute_sql("CREATE TABLE t (id INT PRIMARY KEY)").unwrap();
+    database.execute_sql("INSERT INTO t VALUES (1), (2), (3)").unwrap();
+    database.execute_sql("UPDATE t SET id = id * 10 WHERE id > 1").unwrap();
+    database.execute_sql("DELETE FROM t WHERE id = 20").unwrap();
+    assert!(matches!(database.execute_sql("UPDATE t SET id = 1"), Err(WalError::Engine(_))));
+    for sql in ["BEGIN", "DELETE FROM t", "ROLLBACK"] {
+        database.execute_sql(sql).unwrap();
+    }
+    assert_eq!(ids(&database), [Value::Int(1), Value::Int(30)]);
+    drop(database);
+
+    let database = DurableDatabase::open(&path).unwrap();
+    assert_eq!(ids(&database), [Value::Int(1), Value::Int(30)]);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn only_committed_transactions_are_replayed() {
+    let path = log_path("transactions");
+    let mut database = DurableDatabase::open(&path).unwrap();
+    database.execute_sql("CREATE TABLE t (id INT)").unwrap();
+    for sql in ["BEGIN", "INSERT INTO t VALUES (1)", "COMMIT", "BEGIN", "INSERT INTO t VALUES (2)", "ROLLBACK"] {
+        database.execute_sql(sql).unwrap();
+    }
+    // Open at the "crash"
+    database.execute_sql("BEGIN").unwrap();
+    database.execute_sql("INSERT INTO t VALUES (3)").unwrap();
+    assert_eq!(ids(&database), [Value::Int(1), Value::Int(3)]);
+    drop(database);
+
+    let database = DurableDatabase::open(&path).unwrap();
+    assert_eq!(ids(&database), [Value::Int(1)]);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn torn_writes_are_cut_off() {
+    let path = log_path("torn");
+    let mut database = DurableDatabase::open(&path).unwrap();
+    database.execute_sql("CREATE TABLE t (id INT)").unwrap();
+    database.execute_sql("INSERT INTO t VALUES (1)").unwrap();
+    database.execute_sql("BEGIN").unwrap();
+    database.execute_sql("INSERT INTO t VALUES (2)").unwrap();
+    database.execute_sql("INSERT INTO t VALUES (3)").unwrap();
+    database.execute_sql("COMMIT").unwrap();
+    drop(database);
+
+    // A crash in the middle of writing the group: its COMMIT is missing and
+    // its second INSERT is cut short
+    let intact = fs::read(&path).unwrap();
+    let text = String::from_utf8(intact.clone()).unwrap();
+    // The group starts at the header line of its BEGIN record
+    let group = text[..text.find("\nBEGIN\n").unwrap()].rfind('\n').unwrap() + 1;
+    let torn = text.rfind("VALUES (3)").unwrap();
+    fs::write(&path, &intact[..torn]).unwrap();
+
+    let mut database = DurableDatabase::open(&path).unwrap();
+    assert_eq!(ids(&database), [Value::Int(1)]);
+    assert_eq!(database.discarded(), (torn - group) as u64);
+    assert_eq!(fs::read(&path).unwrap(), &intact[..group]);
+
+    // New records follow the last intact one
+    database.execute_sql("INSERT INTO t VALUES (4)").unwrap();
+    drop(database);
+    assert_eq!(ids(&DurableDatabase::open(&path).unwrap()), [Value::Int(1), Value::Int(4)]);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn intact_records_that_fail_to_replay_are_errors() {
+    let path = log_path("replay");
+    let mut log = OpenOptions::new().create(true).append(true).open(&path).unwrap();
+    // An intact record for a table that doesn't exist
+    let sql = "INSERT INTO t VALUES (1)";
+    let checksum = sql.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3));
+    write!(log, "{} {:016x}\n{}\n", sql.len(), checksum, sql).unwrap();
+    drop(log);
+
+    assert!(matches!(DurableDatabase::open(&path), Err(WalError::Replay { record: 1, .. })));
+    fs::remove_file(&path).unwrap();
+}